@@ -1,10 +1,13 @@
+mod oidc;
+mod tokens;
+
 use axum::{
     body::Body,
     extract::{Request, State},
-    http::{HeaderMap, StatusCode},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     middleware::{self, Next},
     response::{Json, Response},
-    routing::{any, get},
+    routing::{any, delete, get, post},
     Router,
 };
 use dashmap::DashMap;
@@ -17,7 +20,11 @@ use tower_http::trace::TraceLayer;
 struct AppState {
     core_url: String,
     jwt_secret: String,
+    /// Signs the token each forwarded request carries to the engine; `None` forwards the client's credentials.
+    engine_key: Option<jsonwebtoken::EncodingKey>,
     rate_limiters: DashMap<String, TokenBucket>,
+    tokens: tokens::TokenStore,
+    oidc: Option<oidc::Oidc>,
     start_time: Instant,
 }
 
@@ -51,7 +58,37 @@ struct Err { error: String, #[serde(skip_serializing_if = "Option::is_none")] de
 struct LicenseInfo { license: String, source_code: String, notice: String }
 
 #[derive(Deserialize, Serialize, Clone)]
-struct Claims { sub: String, email: Option<String>, role: Option<String>, exp: usize }
+struct Claims {
+    sub: String,
+    email: Option<String>,
+    role: Option<String>,
+    /// Tenant the caller acts for; none means the engine's default tenant.
+    #[serde(default)]
+    tenant: Option<String>,
+    #[serde(default)]
+    access_role: Option<String>,
+    exp: usize,
+}
+
+/// The caller as the engine's `LEGAL_JWT_SECRET` check reads it.
+#[derive(Serialize)]
+struct EngineClaims<'a> { sub: &'a str, tenant: &'a str, role: Option<&'a str>, admin: bool, exp: u64 }
+
+/// How long a token signed for one forwarded request stays valid.
+const ENGINE_TOKEN_SECS: u64 = 60;
+
+/// Read by the engine to pick tenant and field-level view; only the gateway may set them.
+const TENANT_HEADER: &str = "x-tenant-id";
+const ROLE_HEADER: &str = "x-access-role";
+
+/// Replaces whatever identity headers the client sent with the authenticated caller's.
+fn act_as(req: &mut Request, tenant: Option<&str>, access_role: Option<&str>) {
+    let headers = req.headers_mut();
+    headers.remove(TENANT_HEADER);
+    headers.remove(ROLE_HEADER);
+    if let Some(v) = tenant.and_then(|t| HeaderValue::from_str(t).ok()) { headers.insert(TENANT_HEADER, v); }
+    if let Some(v) = access_role.and_then(|r| HeaderValue::from_str(r).ok()) { headers.insert(ROLE_HEADER, v); }
+}
 
 #[tokio::main]
 async fn main() {
@@ -62,18 +99,55 @@ async fn main() {
         )
         .init();
     let env = |k: &str, d: &str| std::env::var(k).unwrap_or_else(|_| d.into());
+    let oidc = match oidc::OidcConfig::from_env() {
+        Some(config) => {
+            let issuer = config.issuer.clone();
+            let provider = oidc::Oidc::discover(config).await.unwrap_or_else(|e| panic!("OIDC discovery failed: {e}"));
+            tracing::info!(%issuer, "OIDC sign-in enabled");
+            Some(provider)
+        }
+        None => None,
+    };
+    let tokens = match std::env::var("GATEWAY_TOKENS_FILE") {
+        Ok(path) => tokens::TokenStore::open(path.into()).unwrap_or_else(|e| panic!("Loading API tokens failed: {e}")),
+        Err(_) => {
+            tracing::warn!("GATEWAY_TOKENS_FILE is not set; API tokens are lost on restart");
+            tokens::TokenStore::default()
+        }
+    };
     let state = Arc::new(AppState {
         core_url: env("CORE_ENGINE_URL", "http://core-engine:8081"),
         jwt_secret: env("JWT_SECRET", "dev-secret-change-me"),
+        engine_key: std::env::var("ENGINE_JWT_SECRET").ok().map(|k| jsonwebtoken::EncodingKey::from_secret(k.as_bytes())),
         rate_limiters: DashMap::new(),
+        tokens,
+        oidc,
         start_time: Instant::now(),
     });
+    let housekeeping = state.clone();
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(std::time::Duration::from_secs(30));
+        loop {
+            tick.tick().await;
+            housekeeping.tokens.flush();
+            if let Some(provider) = &housekeeping.oidc { provider.prune(tokens::now_secs()); }
+        }
+    });
     let cors = CorsLayer::new().allow_origin(Any).allow_methods(Any).allow_headers(Any);
     let public = Router::new()
         .route("/health", get(health))
-        .route("/license", get(license_handler));
+        .route("/license", get(license_handler))
+        .route("/auth/oidc/login", get(oidc::login))
+        .route("/auth/oidc/callback", get(oidc::callback))
+        .route("/auth/oidc/session", get(oidc::session))
+        .route("/auth/oidc/refresh", post(oidc::refresh))
+        .route("/auth/oidc/logout", post(oidc::logout))
+        .route("/auth/oidc/tokens", get(oidc::list_tokens).post(oidc::create_token))
+        .route("/auth/oidc/tokens/:id", delete(oidc::revoke_token));
     let api = Router::new()
-        .route("/api/v1/{*p}", any(proxy_core))
+        .route("/api/v1/*p", any(proxy_core))
+        .route("/admin/tokens", get(tokens::list_tokens).post(tokens::create_token))
+        .route("/admin/tokens/:id", delete(tokens::revoke_token))
         .layer(middleware::from_fn_with_state(state.clone(), auth_mw))
         .layer(middleware::from_fn_with_state(state.clone(), rate_mw));
     let app = Router::new()
@@ -111,8 +185,26 @@ async fn auth_mw(
 ) -> Result<Response, (StatusCode, Json<Err>)> {
     let auth = req.headers().get("Authorization").and_then(|h| h.to_str().ok()).map(|s| s.to_string());
     let api_key = req.headers().get("X-API-Key").and_then(|h| h.to_str().ok()).map(|s| s.to_string());
+    let scoped = api_key.as_deref().or_else(|| auth.as_deref().and_then(|a| a.strip_prefix("Bearer ")))
+        .filter(|t| t.starts_with(tokens::TOKEN_PREFIX));
+    if let Some(secret) = scoped {
+        let info = s.tokens.authorize(secret, req.method(), req.uri().path(), tokens::now_secs())
+            .map_err(tokens::Denied::into_error)?;
+        let role = if info.scopes.iter().any(|sc| sc == "admin") { "admin" } else { "api" };
+        let exp = info.expires_at.map_or(usize::MAX, |e| usize::try_from(e).unwrap_or(usize::MAX));
+        act_as(&mut req, Some(&info.tenant), info.access_role.as_deref());
+        req.extensions_mut().insert(Claims {
+            sub: format!("token:{}", info.id), email: None, role: Some(role.into()), tenant: Some(info.tenant), access_role: info.access_role, exp,
+        });
+        return Ok(next.run(req).await);
+    }
     if let Some(a) = &auth {
         if let Some(token) = a.strip_prefix("Bearer ") {
+            if let Some(provider) = s.oidc.as_ref().filter(|_| oidc::issued_by_idp(token)) {
+                let identity = provider.bearer(token).await.map_err(oidc::Denied::into_error)?;
+                oidc::admit(&identity, &mut req)?;
+                return Ok(next.run(req).await);
+            }
             let mut val = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS256);
             val.validate_aud = false;
             match jsonwebtoken::decode::<Claims>(
@@ -120,13 +212,21 @@ async fn auth_mw(
                 &jsonwebtoken::DecodingKey::from_secret(s.jwt_secret.as_bytes()),
                 &val,
             ) {
-                Ok(data) => { req.extensions_mut().insert(data.claims); return Ok(next.run(req).await); }
+                Ok(data) => {
+                    act_as(&mut req, data.claims.tenant.as_deref(), data.claims.access_role.as_deref());
+                    req.extensions_mut().insert(data.claims);
+                    return Ok(next.run(req).await);
+                }
                 Err(e) => return Err((StatusCode::UNAUTHORIZED, Json(Err { error: "Invalid token".into(), details: Some(e.to_string()) }))),
             }
         }
     }
     if api_key.is_some() {
-        req.extensions_mut().insert(Claims { sub: "api-key-user".into(), email: None, role: Some("api".into()), exp: usize::MAX });
+        return Err((StatusCode::UNAUTHORIZED, Json(Err { error: "Invalid API key".into(), details: Some("X-API-Key takes a scoped token (lgt_…)".into()) })));
+    }
+    if let (Some(provider), Some(id)) = (&s.oidc, oidc::session_cookie(req.headers())) {
+        let identity = provider.session(&id).await.map_err(oidc::Denied::into_error)?;
+        oidc::admit(&identity, &mut req)?;
         return Ok(next.run(req).await);
     }
     Err((StatusCode::UNAUTHORIZED, Json(Err { error: "Auth required".into(), details: Some("Provide Bearer token or X-API-Key".into()) })))
@@ -166,8 +266,64 @@ async fn forward(url: &str, req: Request) -> Result<Response, (StatusCode, Json<
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(Err { error: "Build fail".into(), details: Some(e.to_string()) })))
 }
 
+/// Vouches for the authenticated caller with a short-lived signed token in
+/// place of the client's own credentials.
+fn sign_for_engine(key: &jsonwebtoken::EncodingKey, req: &mut Request) -> Result<(), (StatusCode, Json<Err>)> {
+    let failed = |details: String| (StatusCode::INTERNAL_SERVER_ERROR, Json(Err { error: "Signing for the engine failed".into(), details: Some(details) }));
+    let claims = req.extensions().get::<Claims>().cloned().ok_or_else(|| failed("request has no caller".into()))?;
+    let engine = EngineClaims {
+        sub: &claims.sub,
+        tenant: claims.tenant.as_deref().unwrap_or("default"),
+        role: claims.access_role.as_deref(),
+        admin: claims.role.as_deref() == Some("admin"),
+        exp: tokens::now_secs() + ENGINE_TOKEN_SECS,
+    };
+    let token = jsonwebtoken::encode(&jsonwebtoken::Header::default(), &engine, key).map_err(|e| failed(e.to_string()))?;
+    let bearer = HeaderValue::from_str(&format!("Bearer {token}")).map_err(|e| failed(e.to_string()))?;
+    let headers = req.headers_mut();
+    headers.remove("x-api-key");
+    headers.remove(header::COOKIE);
+    headers.insert(header::AUTHORIZATION, bearer);
+    Ok(())
+}
+
 async fn proxy_core(
-    State(s): State<Arc<AppState>>, req: Request,
+    State(s): State<Arc<AppState>>, mut req: Request,
 ) -> Result<Response, (StatusCode, Json<Err>)> {
+    if let Some(key) = &s.engine_key { sign_for_engine(key, &mut req)?; }
     forward(&s.core_url, req).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(headers: &[(&str, &str)]) -> Request {
+        let mut builder = Request::builder().uri("/api/v1/legal/analyze");
+        for (k, v) in headers { builder = builder.header(*k, *v); }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn client_identity_headers_are_replaced() {
+        let mut req = request(&[(TENANT_HEADER, "globex"), (ROLE_HEADER, "admin")]);
+        act_as(&mut req, Some("acme"), None);
+        assert_eq!(req.headers()[TENANT_HEADER], "acme");
+        assert!(!req.headers().contains_key(ROLE_HEADER));
+    }
+
+    #[test]
+    fn the_engine_gets_a_signed_token_instead_of_client_credentials() {
+        let mut req = request(&[("x-api-key", "lgt_secret"), ("authorization", "Bearer client"), ("cookie", "alice_session=s1")]);
+        req.extensions_mut().insert(Claims {
+            sub: "token:tok_1".into(), email: None, role: Some("api".into()), tenant: Some("acme".into()), access_role: Some("legal".into()), exp: usize::MAX,
+        });
+        sign_for_engine(&jsonwebtoken::EncodingKey::from_secret(b"shared"), &mut req).ok().unwrap();
+        assert!(!req.headers().contains_key("x-api-key") && !req.headers().contains_key(header::COOKIE));
+        let token = req.headers()[header::AUTHORIZATION].to_str().unwrap().strip_prefix("Bearer ").unwrap();
+        let claims = jsonwebtoken::decode::<serde_json::Value>(
+            token, &jsonwebtoken::DecodingKey::from_secret(b"shared"), &jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS256),
+        ).unwrap().claims;
+        assert_eq!((&claims["sub"], &claims["tenant"], &claims["role"], &claims["admin"]), (&"token:tok_1".into(), &"acme".into(), &"legal".into(), &false.into()));
+    }
+}
@@ -0,0 +1,591 @@
+//! OpenID Connect single sign-on (Okta, Azure AD, Google or any issuer with
+//! discovery). Browsers sign in with the authorization-code flow and get a
+//! session cookie; API clients send a token the IdP issued them as a bearer
+//! token. Either way the IdP groups decide the caller's rights: groups in
+//! `OIDC_ADMIN_GROUPS` make an admin, `OIDC_ROLE_MAP` picks the access role
+//! and a tenant claim or `OIDC_TENANT_MAP` picks the tenant forwarded to the
+//! engine. A session whose ID token expires is renewed with its refresh
+//! token, which also picks up group changes. Signed-in users may issue scoped
+//! API tokens that act as them.
+
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Redirect, Response},
+};
+use dashmap::DashMap;
+use jsonwebtoken::{jwk::{Jwk, JwkSet}, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::{Arc, RwLock};
+
+use crate::tokens::{self, now_secs, CreatedToken, TokenInfo};
+use crate::{act_as, AppState, Claims, Err};
+
+pub const SESSION_COOKIE: &str = "alice_session";
+
+/// Engine routes an IdP user reaches only through an admin group.
+const ADMIN_PATHS: [&str; 2] = ["/api/v1/legal/admin/", "/api/v1/legal/audit"];
+
+/// How long a started login may take before its callback is refused.
+const LOGIN_TTL_SECS: u64 = 600;
+/// Unknown key IDs refetch the key set at most this often.
+const JWKS_REFETCH_SECS: u64 = 60;
+/// A session unused this long ends, even if it could still be refreshed.
+const SESSION_IDLE_SECS: u64 = 8 * 3600;
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+pub struct OidcConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// This gateway's `/auth/oidc/callback` as registered with the IdP.
+    pub redirect_url: String,
+    /// Expected `aud` of bearer tokens; ID tokens are always for `client_id`.
+    pub audience: String,
+    pub scopes: String,
+    pub groups_claim: String,
+    pub admin_groups: Vec<String>,
+    /// (group, access role), first match wins.
+    pub role_map: Vec<(String, String)>,
+    /// Claim naming the user's tenant, e.g. `tenant`; wins over `tenant_map`.
+    pub tenant_claim: Option<String>,
+    /// (group, tenant), first match wins.
+    pub tenant_map: Vec<(String, String)>,
+    /// Tenant of users neither the claim nor the map places; `None` refuses them.
+    pub default_tenant: Option<String>,
+    /// What a signing key verifies when its JWK names no algorithm.
+    pub algorithms: Vec<Algorithm>,
+    /// Longest lifetime of an API token a signed-in user issues.
+    pub token_max_secs: u64,
+}
+
+impl OidcConfig {
+    /// `None` unless `OIDC_ISSUER` is set; a partial configuration stops startup.
+    pub fn from_env() -> Option<Self> {
+        let issuer = std::env::var("OIDC_ISSUER").ok()?.trim_end_matches('/').to_string();
+        let need = |k: &str| std::env::var(k).unwrap_or_else(|_| panic!("OIDC_ISSUER is set but {k} is not"));
+        let env = |k: &str, d: &str| std::env::var(k).unwrap_or_else(|_| d.into());
+        let list = |k: &str| env(k, "").split(',').map(str::trim).filter(|g| !g.is_empty()).map(String::from).collect::<Vec<_>>();
+        let pairs = |k: &str, what: &str| list(k).into_iter().map(|pair| match pair.split_once('=') {
+            Some((g, v)) if !g.trim().is_empty() && !v.trim().is_empty() => (g.trim().to_string(), v.trim().to_string()),
+            _ => panic!("{k} expects group={what} pairs, got `{pair}`"),
+        }).collect::<Vec<_>>();
+        let client_id = need("OIDC_CLIENT_ID");
+        let algorithms = list("OIDC_ALGORITHMS").iter().map(|a| match a.parse::<Algorithm>() {
+            Ok(Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) | Err(_) => panic!("OIDC_ALGORITHMS names `{a}`, which is not an IdP signing algorithm"),
+            Ok(alg) => alg,
+        }).collect::<Vec<_>>();
+        Some(Self {
+            issuer,
+            client_secret: need("OIDC_CLIENT_SECRET"),
+            redirect_url: need("OIDC_REDIRECT_URL"),
+            audience: env("OIDC_AUDIENCE", &client_id),
+            client_id,
+            scopes: env("OIDC_SCOPES", "openid email profile offline_access"),
+            groups_claim: env("OIDC_GROUPS_CLAIM", "groups"),
+            admin_groups: list("OIDC_ADMIN_GROUPS"),
+            role_map: pairs("OIDC_ROLE_MAP", "role"),
+            tenant_claim: std::env::var("OIDC_TENANT_CLAIM").ok().filter(|c| !c.trim().is_empty()),
+            tenant_map: pairs("OIDC_TENANT_MAP", "tenant"),
+            default_tenant: std::env::var("OIDC_DEFAULT_TENANT").ok().filter(|t| !t.trim().is_empty()),
+            algorithms: if algorithms.is_empty() { vec![Algorithm::RS256] } else { algorithms },
+            token_max_secs: env("OIDC_TOKEN_MAX_SECS", "7776000").parse().expect("OIDC_TOKEN_MAX_SECS must be a number of seconds"),
+        })
+    }
+
+    /// Admin and access role for a member of `groups`; `None` when no group is mapped.
+    pub fn rights(&self, groups: &[String]) -> Option<(bool, Option<String>)> {
+        let admin = self.admin_groups.iter().any(|g| groups.contains(g));
+        let role = self.role_map.iter().find(|(g, _)| groups.contains(g)).map(|(_, r)| r.clone());
+        (admin || role.is_some()).then_some((admin, role))
+    }
+
+    /// The tenant a user acts for: the tenant claim, else their first mapped
+    /// group, else the default; `None` when nothing places them.
+    pub fn tenant(&self, claims: &Map<String, Value>, groups: &[String]) -> Option<String> {
+        let claimed = self.tenant_claim.as_deref().and_then(|c| claims.get(c)).and_then(Value::as_str).filter(|t| !t.is_empty());
+        claimed.map(String::from)
+            .or_else(|| self.tenant_map.iter().find(|(g, _)| groups.contains(g)).map(|(_, t)| t.clone()))
+            .or_else(|| self.default_tenant.clone())
+            .filter(|t| HeaderValue::from_str(t).is_ok())
+    }
+
+    /// The algorithms `jwk` may verify: the one it names, else the configured ones.
+    fn pinned(&self, jwk: &Jwk) -> Result<Vec<Algorithm>, String> {
+        match jwk.common.key_algorithm {
+            Some(alg) => match alg.to_string().parse::<Algorithm>() {
+                Ok(Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) | Err(_) => Err(format!("signing key uses {alg}, which is not a signature algorithm the gateway accepts")),
+                Ok(alg) => Ok(vec![alg]),
+            },
+            None => Ok(self.algorithms.clone()),
+        }
+    }
+}
+
+// ── Identity ──────────────────────────────────────────────────────────────────
+
+/// A verified IdP user and what their groups grant.
+#[derive(Clone, Serialize)]
+pub struct Identity {
+    pub sub: String,
+    pub email: Option<String>,
+    pub groups: Vec<String>,
+    pub admin: bool,
+    pub access_role: Option<String>,
+    pub tenant: String,
+    /// Expiry of the token the identity was read from, Unix seconds.
+    pub expires_at: u64,
+}
+
+impl Identity {
+    pub fn claims(&self) -> Claims {
+        Claims {
+            sub: format!("oidc:{}", self.sub),
+            email: self.email.clone(),
+            role: Some(if self.admin { "admin" } else { "api" }.into()),
+            tenant: Some(self.tenant.clone()),
+            access_role: self.access_role.clone(),
+            exp: usize::try_from(self.expires_at).unwrap_or(usize::MAX),
+        }
+    }
+}
+
+pub enum Denied { Invalid(String), Unmapped, NoTenant }
+
+impl Denied {
+    pub fn into_error(self) -> (StatusCode, Json<Err>) {
+        match self {
+            Denied::Invalid(details) => (StatusCode::UNAUTHORIZED, Json(Err { error: "Invalid token".into(), details: Some(details) })),
+            Denied::Unmapped => (StatusCode::FORBIDDEN, Json(Err { error: "No role is mapped to your IdP groups".into(), details: None })),
+            Denied::NoTenant => (StatusCode::FORBIDDEN, Json(Err { error: "No tenant is mapped to your IdP identity".into(), details: None })),
+        }
+    }
+}
+
+/// Lets an IdP user's request through: gateway claims, and their tenant and
+/// access role in place of whatever the client sent.
+pub fn admit(identity: &Identity, req: &mut Request) -> Result<(), (StatusCode, Json<Err>)> {
+    if !identity.admin && ADMIN_PATHS.iter().any(|p| req.uri().path().starts_with(p)) {
+        return Err((StatusCode::FORBIDDEN, Json(Err { error: "Admin group membership required".into(), details: None })));
+    }
+    act_as(req, Some(&identity.tenant), identity.access_role.as_deref());
+    req.extensions_mut().insert(identity.claims());
+    Ok(())
+}
+
+/// Whether a bearer token was signed by an IdP key rather than `JWT_SECRET`.
+pub fn issued_by_idp(token: &str) -> bool {
+    jsonwebtoken::decode_header(token).is_ok_and(|h| !matches!(h.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512))
+}
+
+/// Group claims come as an array, or a single string from some IdPs.
+fn groups(claims: &Map<String, Value>, name: &str) -> Vec<String> {
+    match claims.get(name) {
+        Some(Value::Array(items)) => items.iter().filter_map(|g| g.as_str().map(String::from)).collect(),
+        Some(Value::String(g)) => vec![g.clone()],
+        _ => Vec::new(),
+    }
+}
+
+/// Only same-site paths, so the login cannot bounce the user to another host.
+fn local_redirect(target: Option<String>) -> String {
+    target.filter(|t| t.starts_with('/') && !t.starts_with("//") && !t.contains('\\')).unwrap_or_else(|| "/".into())
+}
+
+pub fn session_cookie(headers: &HeaderMap) -> Option<String> {
+    headers.get_all(header::COOKIE).iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .find_map(|c| c.trim().strip_prefix(SESSION_COOKIE)?.strip_prefix('=').map(String::from))
+        .filter(|id| !id.is_empty())
+}
+
+// ── Provider ──────────────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+struct Discovery { authorization_endpoint: String, token_endpoint: String, jwks_uri: String }
+
+#[derive(Deserialize)]
+struct TokenResponse { id_token: Option<String>, refresh_token: Option<String> }
+
+struct PendingLogin { nonce: String, redirect: String, started_at: u64 }
+
+#[derive(Clone)]
+struct Session { identity: Identity, refresh_token: Option<String>, last_seen: u64 }
+
+pub struct Oidc {
+    pub config: OidcConfig,
+    discovery: Discovery,
+    /// The IdP's signing keys and when they were fetched.
+    jwks: RwLock<(JwkSet, u64)>,
+    pending: DashMap<String, PendingLogin>,
+    sessions: DashMap<String, Session>,
+    http: reqwest::Client,
+}
+
+impl Oidc {
+    /// Reads the issuer's discovery document and signing keys.
+    pub async fn discover(config: OidcConfig) -> Result<Self, String> {
+        let http = reqwest::Client::new();
+        let url = format!("{}/.well-known/openid-configuration", config.issuer);
+        let discovery: Discovery = get_json(&http, &url).await?;
+        let jwks: JwkSet = get_json(&http, &discovery.jwks_uri).await?;
+        Ok(Self { config, discovery, jwks: RwLock::new((jwks, now_secs())), pending: DashMap::new(), sessions: DashMap::new(), http })
+    }
+
+    /// The key named `kid` and the algorithms it may verify.
+    async fn key(&self, kid: &str) -> Result<(DecodingKey, Vec<Algorithm>), String> {
+        let (cached, fetched_at) = {
+            let jwks = self.jwks.read().unwrap_or_else(|e| e.into_inner());
+            (jwks.0.find(kid).cloned(), jwks.1)
+        };
+        // A key rotation at the IdP shows up as an unknown kid.
+        let jwk = match cached {
+            Some(jwk) => jwk,
+            None if now_secs() >= fetched_at + JWKS_REFETCH_SECS => {
+                let fresh: JwkSet = get_json(&self.http, &self.discovery.jwks_uri).await?;
+                let jwk = fresh.find(kid).cloned();
+                *self.jwks.write().unwrap_or_else(|e| e.into_inner()) = (fresh, now_secs());
+                jwk.ok_or_else(|| format!("unknown signing key {kid}"))?
+            }
+            None => return Err(format!("unknown signing key {kid}")),
+        };
+        Ok((DecodingKey::from_jwk(&jwk).map_err(|e| e.to_string())?, self.config.pinned(&jwk)?))
+    }
+
+    /// Checks signature, issuer, audience, expiry and, for a login, the nonce.
+    async fn verify(&self, token: &str, audience: &str, nonce: Option<&str>) -> Result<Identity, Denied> {
+        if !issued_by_idp(token) { return Err(Denied::Invalid("not signed by the IdP".into())); }
+        let header = jsonwebtoken::decode_header(token).map_err(|e| Denied::Invalid(e.to_string()))?;
+        let kid = header.kid.ok_or_else(|| Denied::Invalid("token names no signing key".into()))?;
+        let (key, algorithms) = self.key(&kid).await.map_err(Denied::Invalid)?;
+        // The key, not the token, decides the algorithm.
+        if !algorithms.contains(&header.alg) { return Err(Denied::Invalid(format!("key {kid} does not sign with {:?}", header.alg))); }
+        let mut val = Validation::new(header.alg);
+        val.algorithms = algorithms;
+        val.set_issuer(&[&self.config.issuer]);
+        val.set_audience(&[audience]);
+        let claims = jsonwebtoken::decode::<Map<String, Value>>(token, &key, &val).map_err(|e| Denied::Invalid(e.to_string()))?.claims;
+        if let Some(nonce) = nonce {
+            if claims.get("nonce").and_then(Value::as_str) != Some(nonce) { return Err(Denied::Invalid("nonce mismatch".into())); }
+        }
+        let text = |k: &str| claims.get(k).and_then(Value::as_str).map(String::from);
+        let groups = groups(&claims, &self.config.groups_claim);
+        let (admin, access_role) = self.config.rights(&groups).ok_or(Denied::Unmapped)?;
+        let tenant = self.config.tenant(&claims, &groups).ok_or(Denied::NoTenant)?;
+        Ok(Identity {
+            sub: text("sub").ok_or_else(|| Denied::Invalid("token has no subject".into()))?,
+            email: text("email"),
+            groups,
+            admin,
+            access_role,
+            tenant,
+            expires_at: claims.get("exp").and_then(Value::as_u64).unwrap_or(0),
+        })
+    }
+
+    /// An IdP-issued bearer token sent by an API client.
+    pub async fn bearer(&self, token: &str) -> Result<Identity, Denied> {
+        self.verify(token, &self.config.audience, None).await
+    }
+
+    async fn token_request(&self, form: &[(&str, &str)]) -> Result<TokenResponse, String> {
+        let mut form = form.to_vec();
+        form.extend([("client_id", self.config.client_id.as_str()), ("client_secret", self.config.client_secret.as_str())]);
+        let resp = self.http.post(&self.discovery.token_endpoint).form(&form).send().await.map_err(|e| e.to_string())?;
+        if !resp.status().is_success() { return Err(format!("token endpoint answered {}", resp.status())); }
+        resp.json().await.map_err(|e| e.to_string())
+    }
+
+    /// Renews an expired session; group changes at the IdP apply from here.
+    async fn refresh(&self, id: &str, session: Session) -> Result<Identity, Denied> {
+        let Some(refresh_token) = session.refresh_token else { return Err(Denied::Invalid("session expired".into())) };
+        let renewed = match self.token_request(&[("grant_type", "refresh_token"), ("refresh_token", &refresh_token)]).await {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                // A concurrent request may have rotated the refresh token first.
+                if let Some(s) = self.sessions.get(id).filter(|s| s.identity.expires_at > now_secs()) { return Ok(s.identity.clone()); }
+                self.sessions.remove(id);
+                return Err(Denied::Invalid(format!("refresh failed: {e}")));
+            }
+        };
+        let id_token = renewed.id_token.ok_or_else(|| Denied::Invalid("refresh returned no ID token".into()))?;
+        let identity = match self.verify(&id_token, &self.config.client_id, None).await {
+            Ok(identity) => identity,
+            Err(e) => { self.sessions.remove(id); return Err(e); }
+        };
+        self.sessions.insert(id.to_string(), Session {
+            identity: identity.clone(), refresh_token: renewed.refresh_token.or(Some(refresh_token)), last_seen: now_secs(),
+        });
+        tracing::info!(sub = %identity.sub, "OIDC session refreshed");
+        Ok(identity)
+    }
+
+    /// The session's identity, refreshed first if its ID token has expired.
+    pub async fn session(&self, id: &str) -> Result<Identity, Denied> {
+        let now = now_secs();
+        let session = {
+            let mut s = self.sessions.get_mut(id).filter(|s| now < s.last_seen + SESSION_IDLE_SECS)
+                .ok_or_else(|| Denied::Invalid("unknown session".into()))?;
+            s.last_seen = now;
+            s.clone()
+        };
+        if session.identity.expires_at > now { return Ok(session.identity); }
+        self.refresh(id, session).await
+    }
+
+    /// Drops abandoned logins, idle sessions, and expired sessions that
+    /// cannot be refreshed; run on a timer.
+    pub fn prune(&self, now: u64) {
+        self.pending.retain(|_, p| now < p.started_at + LOGIN_TTL_SECS);
+        self.sessions.retain(|_, s| now < s.last_seen + SESSION_IDLE_SECS && (s.refresh_token.is_some() || s.identity.expires_at > now));
+    }
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(http: &reqwest::Client, url: &str) -> Result<T, String> {
+    let resp = http.get(url).send().await.map_err(|e| format!("{url}: {e}"))?;
+    if !resp.status().is_success() { return Err(format!("{url} answered {}", resp.status())); }
+    resp.json().await.map_err(|e| format!("{url}: {e}"))
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+fn provider(s: &AppState) -> Result<&Oidc, (StatusCode, Json<Err>)> {
+    s.oidc.as_ref().ok_or((StatusCode::NOT_FOUND, Json(Err { error: "SSO is not configured".into(), details: None })))
+}
+
+fn cookie(config: &OidcConfig, value: &str, max_age: Option<u64>) -> String {
+    let secure = if config.redirect_url.starts_with("https://") { "; Secure" } else { "" };
+    let age = max_age.map(|a| format!("; Max-Age={a}")).unwrap_or_default();
+    format!("{SESSION_COOKIE}={value}; Path=/; HttpOnly; SameSite=Lax{secure}{age}")
+}
+
+#[derive(Deserialize)]
+pub struct LoginQuery { redirect: Option<String> }
+
+pub async fn login(State(s): State<Arc<AppState>>, Query(q): Query<LoginQuery>) -> Result<Redirect, (StatusCode, Json<Err>)> {
+    let oidc = provider(&s)?;
+    let state = uuid::Uuid::new_v4().simple().to_string();
+    let nonce = uuid::Uuid::new_v4().simple().to_string();
+    let mut url = reqwest::Url::parse(&oidc.discovery.authorization_endpoint)
+        .map_err(|e| (StatusCode::BAD_GATEWAY, Json(Err { error: "Bad IdP authorization endpoint".into(), details: Some(e.to_string()) })))?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &oidc.config.client_id)
+        .append_pair("redirect_uri", &oidc.config.redirect_url)
+        .append_pair("scope", &oidc.config.scopes)
+        .append_pair("state", &state)
+        .append_pair("nonce", &nonce);
+    oidc.pending.insert(state, PendingLogin { nonce, redirect: local_redirect(q.redirect), started_at: now_secs() });
+    Ok(Redirect::to(url.as_str()))
+}
+
+#[derive(Deserialize)]
+pub struct CallbackQuery { code: Option<String>, state: Option<String>, error: Option<String> }
+
+pub async fn callback(State(s): State<Arc<AppState>>, Query(q): Query<CallbackQuery>) -> Result<Response, (StatusCode, Json<Err>)> {
+    let oidc = provider(&s)?;
+    let refused = |details: String| (StatusCode::UNAUTHORIZED, Json(Err { error: "Sign-in failed".into(), details: Some(details) }));
+    if let Some(error) = q.error { return Err(refused(format!("IdP returned {error}"))); }
+    let (_, pending) = q.state.as_deref().and_then(|st| oidc.pending.remove(st))
+        .filter(|(_, p)| now_secs() < p.started_at + LOGIN_TTL_SECS)
+        .ok_or_else(|| refused("unknown or expired login; start again".into()))?;
+    let code = q.code.ok_or_else(|| refused("no authorization code".into()))?;
+    let tokens = oidc.token_request(&[("grant_type", "authorization_code"), ("code", &code), ("redirect_uri", &oidc.config.redirect_url)])
+        .await.map_err(refused)?;
+    let id_token = tokens.id_token.ok_or_else(|| refused("IdP returned no ID token".into()))?;
+    let identity = oidc.verify(&id_token, &oidc.config.client_id, Some(&pending.nonce)).await.map_err(Denied::into_error)?;
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    tracing::info!(sub = %identity.sub, admin = identity.admin, role = ?identity.access_role, "OIDC sign-in");
+    oidc.sessions.insert(id.clone(), Session { identity, refresh_token: tokens.refresh_token, last_seen: now_secs() });
+    Ok(([(header::SET_COOKIE, cookie(&oidc.config, &id, None))], Redirect::to(&pending.redirect)).into_response())
+}
+
+fn current(headers: &HeaderMap) -> Result<String, (StatusCode, Json<Err>)> {
+    session_cookie(headers).ok_or((StatusCode::UNAUTHORIZED, Json(Err { error: "Not signed in".into(), details: None })))
+}
+
+pub async fn session(State(s): State<Arc<AppState>>, headers: HeaderMap) -> Result<Json<Identity>, (StatusCode, Json<Err>)> {
+    let oidc = provider(&s)?;
+    let id = current(&headers)?;
+    Ok(Json(oidc.session(&id).await.map_err(Denied::into_error)?))
+}
+
+/// Renews the session now rather than when its ID token expires.
+pub async fn refresh(State(s): State<Arc<AppState>>, headers: HeaderMap) -> Result<Json<Identity>, (StatusCode, Json<Err>)> {
+    let oidc = provider(&s)?;
+    let id = current(&headers)?;
+    let session = oidc.sessions.get(&id).map(|s| s.clone())
+        .ok_or((StatusCode::UNAUTHORIZED, Json(Err { error: "Not signed in".into(), details: None })))?;
+    Ok(Json(oidc.refresh(&id, session).await.map_err(Denied::into_error)?))
+}
+
+pub async fn logout(State(s): State<Arc<AppState>>, headers: HeaderMap) -> Result<Response, (StatusCode, Json<Err>)> {
+    let oidc = provider(&s)?;
+    if let Some(id) = session_cookie(&headers) { oidc.sessions.remove(&id); }
+    Ok((StatusCode::NO_CONTENT, [(header::SET_COOKIE, cookie(&oidc.config, "", Some(0)))]).into_response())
+}
+
+// ── API tokens for signed-in users ────────────────────────────────────────────
+
+/// The signed-in user, from an IdP bearer token or the session cookie.
+async fn caller(oidc: &Oidc, headers: &HeaderMap) -> Result<Identity, (StatusCode, Json<Err>)> {
+    let bearer = headers.get(header::AUTHORIZATION).and_then(|h| h.to_str().ok()).and_then(|a| a.strip_prefix("Bearer "));
+    if let Some(token) = bearer.filter(|t| issued_by_idp(t)) { return oidc.bearer(token).await.map_err(Denied::into_error); }
+    oidc.session(&current(headers)?).await.map_err(Denied::into_error)
+}
+
+fn owner(identity: &Identity) -> String { format!("oidc:{}", identity.sub) }
+
+#[derive(Deserialize)]
+pub struct CreateOwnToken { name: String, scopes: Vec<String>, expires_in_secs: Option<u64> }
+
+/// Issues a token acting as the caller: their tenant and access role, and the
+/// `admin` scope only for admins. It always expires.
+pub async fn create_token(
+    State(s): State<Arc<AppState>>, headers: HeaderMap, Json(req): Json<CreateOwnToken>,
+) -> Result<(StatusCode, Json<CreatedToken>), (StatusCode, Json<Err>)> {
+    let oidc = provider(&s)?;
+    let identity = caller(oidc, &headers).await?;
+    if !identity.admin && req.scopes.iter().any(|sc| sc == "admin") {
+        return Err((StatusCode::FORBIDDEN, Json(Err { error: "Admin group membership required".into(), details: None })));
+    }
+    let expires_in_secs = req.expires_in_secs.unwrap_or(oidc.config.token_max_secs);
+    if expires_in_secs > oidc.config.token_max_secs {
+        return Err(tokens::bad_request(format!("expires_in_secs may be at most {}", oidc.config.token_max_secs)));
+    }
+    let req = tokens::CreateToken {
+        name: req.name, tenant: identity.tenant.clone(), access_role: identity.access_role.clone(),
+        scopes: req.scopes, expires_in_secs: Some(expires_in_secs), owner: Some(owner(&identity)),
+    };
+    tokens::validate(&req)?;
+    let created = s.tokens.create(req, now_secs());
+    tracing::info!(token_id = %created.info.id, sub = %identity.sub, tenant = %created.info.tenant, scopes = ?created.info.scopes, "API token issued to SSO user");
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+pub async fn list_tokens(State(s): State<Arc<AppState>>, headers: HeaderMap) -> Result<Json<Vec<TokenInfo>>, (StatusCode, Json<Err>)> {
+    let owner = owner(&caller(provider(&s)?, &headers).await?);
+    Ok(Json(s.tokens.list().into_iter().filter(|t| t.owner.as_deref() == Some(owner.as_str())).collect()))
+}
+
+pub async fn revoke_token(
+    State(s): State<Arc<AppState>>, headers: HeaderMap, Path(id): Path<String>,
+) -> Result<Json<TokenInfo>, (StatusCode, Json<Err>)> {
+    let owner = owner(&caller(provider(&s)?, &headers).await?);
+    let unknown = || (StatusCode::NOT_FOUND, Json(Err { error: "Unknown token".into(), details: None }));
+    if !s.tokens.list().iter().any(|t| t.id == id && t.owner.as_deref() == Some(owner.as_str())) { return Err(unknown()); }
+    let info = s.tokens.revoke(&id, now_secs()).ok_or_else(unknown)?;
+    tracing::info!(token_id = %id, by = %owner, "API token revoked by its owner");
+    Ok(Json(info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::jwk::KeyAlgorithm;
+
+    fn config() -> OidcConfig {
+        let pairs = |p: &[(&str, &str)]| p.iter().map(|(g, v)| (g.to_string(), v.to_string())).collect();
+        OidcConfig {
+            issuer: "https://idp.example.com".into(),
+            client_id: "alice".into(),
+            client_secret: "secret".into(),
+            redirect_url: "https://legal.example.com/auth/oidc/callback".into(),
+            audience: "alice".into(),
+            scopes: "openid".into(),
+            groups_claim: "groups".into(),
+            admin_groups: vec!["ops".into()],
+            role_map: pairs(&[("legal-team", "legal"), ("finance", "finance")]),
+            tenant_claim: Some("tenant".into()),
+            tenant_map: pairs(&[("acme-staff", "acme"), ("globex-staff", "globex")]),
+            default_tenant: None,
+            algorithms: vec![Algorithm::RS256],
+            token_max_secs: 3600,
+        }
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> { items.iter().map(|s| s.to_string()).collect() }
+
+    #[test]
+    fn groups_map_to_admin_and_the_first_matching_role() {
+        let c = config();
+        assert_eq!(c.rights(&strings(&["finance", "legal-team"])), Some((false, Some("legal".into()))));
+        assert_eq!(c.rights(&strings(&["ops"])), Some((true, None)));
+        assert_eq!(c.rights(&strings(&["ops", "finance"])), Some((true, Some("finance".into()))));
+        assert_eq!(c.rights(&strings(&["marketing"])), None);
+    }
+
+    #[test]
+    fn the_tenant_comes_from_the_claim_then_the_groups_then_the_default() {
+        let mut c = config();
+        let claims = |v: Value| v.as_object().cloned().unwrap();
+        assert_eq!(c.tenant(&claims(serde_json::json!({ "tenant": "initech" })), &strings(&["acme-staff"])).as_deref(), Some("initech"));
+        assert_eq!(c.tenant(&claims(serde_json::json!({})), &strings(&["globex-staff", "acme-staff"])).as_deref(), Some("acme"));
+        assert_eq!(c.tenant(&claims(serde_json::json!({ "tenant": "" })), &strings(&["legal-team"])), None);
+        c.default_tenant = Some("default".into());
+        assert_eq!(c.tenant(&claims(serde_json::json!({})), &strings(&["legal-team"])).as_deref(), Some("default"));
+    }
+
+    #[test]
+    fn a_key_that_names_its_algorithm_pins_it() {
+        let c = config();
+        let mut jwk: Jwk = serde_json::from_value(serde_json::json!({ "kty": "RSA", "kid": "k1", "n": "AQAB", "e": "AQAB" })).unwrap();
+        assert_eq!(c.pinned(&jwk).unwrap(), vec![Algorithm::RS256]);
+        jwk.common.key_algorithm = Some(KeyAlgorithm::PS256);
+        assert_eq!(c.pinned(&jwk).unwrap(), vec![Algorithm::PS256]);
+        jwk.common.key_algorithm = Some(KeyAlgorithm::HS256);
+        assert!(c.pinned(&jwk).is_err());
+    }
+
+    #[test]
+    fn redirects_stay_on_the_gateway() {
+        assert_eq!(local_redirect(Some("/dashboard?tab=1".into())), "/dashboard?tab=1");
+        for outside in ["https://evil.example", "//evil.example", "/\\evil.example", "dashboard"] {
+            assert_eq!(local_redirect(Some(outside.into())), "/", "{outside}");
+        }
+        assert_eq!(local_redirect(None), "/");
+    }
+
+    #[test]
+    fn the_session_cookie_is_read_among_others_and_set_http_only() {
+        let mut headers = HeaderMap::new();
+        headers.append(header::COOKIE, HeaderValue::from_static("theme=dark; alice_session_old=x"));
+        assert_eq!(session_cookie(&headers), None);
+        headers.append(header::COOKIE, HeaderValue::from_static("lang=en; alice_session=abc123"));
+        assert_eq!(session_cookie(&headers).as_deref(), Some("abc123"));
+
+        let mut c = config();
+        assert_eq!(cookie(&c, "abc123", None), "alice_session=abc123; Path=/; HttpOnly; SameSite=Lax; Secure");
+        c.redirect_url = "http://localhost:8080/auth/oidc/callback".into();
+        assert_eq!(cookie(&c, "", Some(0)), "alice_session=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0");
+    }
+
+    #[test]
+    fn pruning_drops_idle_and_unrefreshable_sessions() {
+        let identity = |expires_at| Identity {
+            sub: "u1".into(), email: None, groups: Vec::new(), admin: false, access_role: None, tenant: "acme".into(), expires_at,
+        };
+        let oidc = Oidc {
+            config: config(),
+            discovery: Discovery { authorization_endpoint: String::new(), token_endpoint: String::new(), jwks_uri: String::new() },
+            jwks: RwLock::new((JwkSet { keys: Vec::new() }, 0)),
+            pending: DashMap::new(),
+            sessions: DashMap::new(),
+            http: reqwest::Client::new(),
+        };
+        let now = 100_000;
+        oidc.sessions.insert("live".into(), Session { identity: identity(now + 60), refresh_token: None, last_seen: now });
+        oidc.sessions.insert("renewable".into(), Session { identity: identity(now - 60), refresh_token: Some("r".into()), last_seen: now });
+        oidc.sessions.insert("expired".into(), Session { identity: identity(now - 60), refresh_token: None, last_seen: now });
+        oidc.sessions.insert("idle".into(), Session { identity: identity(now + 60), refresh_token: Some("r".into()), last_seen: now - SESSION_IDLE_SECS });
+        oidc.pending.insert("old".into(), PendingLogin { nonce: String::new(), redirect: "/".into(), started_at: now - LOGIN_TTL_SECS });
+        oidc.prune(now);
+        let mut left: Vec<String> = oidc.sessions.iter().map(|s| s.key().clone()).collect();
+        left.sort();
+        assert_eq!(left, ["live", "renewable"]);
+        assert!(oidc.pending.is_empty());
+    }
+}
@@ -0,0 +1,310 @@
+//! Scoped API tokens: each token carries scopes that map to routes, the
+//! tenant and access role it acts as, an optional expiry, and last-used
+//! tracking. Revoked tokens stay listed (the revocation list) so the admin API
+//! can show who was cut off and when. Only the SHA-256 of a secret is kept;
+//! with `GATEWAY_TOKENS_FILE` set the tokens are saved there and survive a
+//! restart, otherwise a restart revokes every token.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderValue, Method, StatusCode},
+    response::Json,
+    Extension,
+};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{AppState, Claims, Err};
+
+/// Issued tokens start with this, so the auth middleware can tell them from legacy API keys.
+pub const TOKEN_PREFIX: &str = "lgt_";
+
+/// (scope, method or `*`, path prefix) — a scope grants every row it appears in.
+const SCOPE_ROUTES: &[(&str, &str, &str)] = &[
+    ("analyze", "POST", "/api/v1/legal/analyze"),
+    ("analyze", "*", "/api/v1/legal/jobs"),
+    ("analyze", "GET", "/api/v1/legal/analyses"),
+    ("templates:read", "GET", "/api/v1/legal/templates"),
+    ("templates:write", "*", "/api/v1/legal/templates"),
+    ("compile", "POST", "/api/v1/legal/compile"),
+    ("admin", "*", "/"),
+];
+
+pub fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn known_scope(scope: &str) -> bool { SCOPE_ROUTES.iter().any(|(s, _, _)| *s == scope) }
+
+/// Prefix match on whole path segments, so `/templates` does not grant `/templatesX`.
+pub fn allows(scopes: &[String], method: &Method, path: &str) -> bool {
+    SCOPE_ROUTES.iter().any(|(scope, m, prefix)| {
+        scopes.iter().any(|s| s == scope)
+            && (*m == "*" || *m == method.as_str())
+            && path.strip_prefix(prefix).is_some_and(|rest| prefix.ends_with('/') || rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+fn hash(secret: &str) -> String { format!("{:x}", Sha256::digest(secret.as_bytes())) }
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TokenInfo {
+    pub id: String,
+    pub name: String,
+    /// Sent to the engine as `x-tenant-id` in place of whatever the client sent.
+    pub tenant: String,
+    /// Sent to the engine as `x-access-role`; none means the engine's default view.
+    pub access_role: Option<String>,
+    /// The SSO user who issued the token for themselves; none for admin-issued ones.
+    #[serde(default)]
+    pub owner: Option<String>,
+    pub scopes: Vec<String>,
+    pub created_at: u64,
+    pub expires_at: Option<u64>,
+    pub revoked_at: Option<u64>,
+    pub last_used_at: Option<u64>,
+    pub use_count: u64,
+}
+
+#[derive(Deserialize)]
+pub struct CreateToken {
+    pub name: String,
+    pub tenant: String,
+    pub access_role: Option<String>,
+    pub scopes: Vec<String>,
+    pub expires_in_secs: Option<u64>,
+    #[serde(skip)]
+    pub owner: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CreatedToken { #[serde(flatten)] pub info: TokenInfo, pub token: String }
+
+pub enum Denied { Unknown, Revoked, Expired, OutOfScope }
+
+impl Denied {
+    pub fn into_error(self) -> (StatusCode, Json<Err>) {
+        let (status, error) = match self {
+            Denied::Unknown => (StatusCode::UNAUTHORIZED, "Invalid token"),
+            Denied::Revoked => (StatusCode::UNAUTHORIZED, "Token revoked"),
+            Denied::Expired => (StatusCode::UNAUTHORIZED, "Token expired"),
+            Denied::OutOfScope => (StatusCode::FORBIDDEN, "Token scope does not cover this route"),
+        };
+        (status, Json(Err { error: error.into(), details: None }))
+    }
+}
+
+/// What `GATEWAY_TOKENS_FILE` holds.
+#[derive(Default, Serialize, Deserialize)]
+struct TokenFile { tokens: Vec<StoredToken> }
+
+#[derive(Serialize, Deserialize)]
+struct StoredToken { secret_sha256: String, #[serde(flatten)] info: TokenInfo }
+
+/// Tokens by the SHA-256 of their secret; `ids` maps the public ID back for the admin API.
+#[derive(Default)]
+pub struct TokenStore {
+    by_hash: DashMap<String, TokenInfo>,
+    ids: DashMap<String, String>,
+    file: Option<PathBuf>,
+    /// Usage changed since the file was last written.
+    dirty: AtomicBool,
+    /// Serializes writes, so an older snapshot never replaces a newer one.
+    saving: Mutex<()>,
+}
+
+impl TokenStore {
+    /// Loads the tokens saved at `path`; a missing file starts an empty store there.
+    pub fn open(path: PathBuf) -> Result<Self, String> {
+        let saved: TokenFile = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| format!("{}: {e}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => TokenFile::default(),
+            Err(e) => return Err(format!("{}: {e}", path.display())),
+        };
+        let store = Self { file: Some(path), ..Self::default() };
+        for t in saved.tokens {
+            store.ids.insert(t.info.id.clone(), t.secret_sha256.clone());
+            store.by_hash.insert(t.secret_sha256, t.info);
+        }
+        Ok(store)
+    }
+
+    /// Writes every token to the file, through a temporary file so a crash never leaves half of one.
+    fn save(&self) {
+        let Some(path) = &self.file else { return };
+        let _guard = self.saving.lock().unwrap_or_else(|e| e.into_inner());
+        self.dirty.store(false, Ordering::Relaxed);
+        let mut tokens: Vec<StoredToken> = self.by_hash.iter()
+            .map(|t| StoredToken { secret_sha256: t.key().clone(), info: t.value().clone() }).collect();
+        tokens.sort_by(|a, b| a.info.created_at.cmp(&b.info.created_at).then_with(|| a.info.id.cmp(&b.info.id)));
+        let tmp = path.with_extension("tmp");
+        let written = serde_json::to_vec_pretty(&TokenFile { tokens }).map_err(|e| e.to_string())
+            .and_then(|bytes| std::fs::write(&tmp, bytes).map_err(|e| e.to_string()))
+            .and_then(|()| std::fs::rename(&tmp, path).map_err(|e| e.to_string()));
+        if let Err(e) = written { tracing::error!(path = %path.display(), error = %e, "saving API tokens failed"); }
+    }
+
+    /// Saves usage recorded since the last write; run on a timer rather than per request.
+    pub fn flush(&self) {
+        if self.dirty.load(Ordering::Relaxed) { self.save(); }
+    }
+
+    pub fn create(&self, req: CreateToken, now: u64) -> CreatedToken {
+        let id = format!("tok_{}", &uuid::Uuid::new_v4().simple().to_string()[..12]);
+        let secret = format!("{TOKEN_PREFIX}{}", uuid::Uuid::new_v4().simple());
+        let info = TokenInfo {
+            id: id.clone(), name: req.name, tenant: req.tenant, access_role: req.access_role, owner: req.owner,
+            scopes: req.scopes, created_at: now,
+            expires_at: req.expires_in_secs.map(|s| now + s), revoked_at: None, last_used_at: None, use_count: 0,
+        };
+        self.ids.insert(id, hash(&secret));
+        self.by_hash.insert(hash(&secret), info.clone());
+        self.save();
+        CreatedToken { info, token: secret }
+    }
+
+    /// Checks revocation, expiry and scope, and records the use on success.
+    pub fn authorize(&self, secret: &str, method: &Method, path: &str, now: u64) -> Result<TokenInfo, Denied> {
+        let mut t = self.by_hash.get_mut(&hash(secret)).ok_or(Denied::Unknown)?;
+        if t.revoked_at.is_some() { return Err(Denied::Revoked); }
+        if t.expires_at.is_some_and(|e| now >= e) { return Err(Denied::Expired); }
+        if !allows(&t.scopes, method, path) { return Err(Denied::OutOfScope); }
+        t.last_used_at = Some(now);
+        t.use_count += 1;
+        self.dirty.store(true, Ordering::Relaxed);
+        Ok(t.clone())
+    }
+
+    pub fn revoke(&self, id: &str, now: u64) -> Option<TokenInfo> {
+        let hash = self.ids.get(id)?.clone();
+        let info = {
+            let mut t = self.by_hash.get_mut(&hash)?;
+            t.revoked_at.get_or_insert(now);
+            t.clone()
+        };
+        self.save();
+        Some(info)
+    }
+
+    pub fn list(&self) -> Vec<TokenInfo> {
+        let mut all: Vec<TokenInfo> = self.by_hash.iter().map(|t| t.clone()).collect();
+        all.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        all
+    }
+}
+
+fn require_admin(claims: &Claims) -> Result<(), (StatusCode, Json<Err>)> {
+    if claims.role.as_deref() == Some("admin") { return Ok(()); }
+    Err((StatusCode::FORBIDDEN, Json(Err { error: "Admin role required".into(), details: None })))
+}
+
+pub fn bad_request(details: String) -> (StatusCode, Json<Err>) {
+    (StatusCode::BAD_REQUEST, Json(Err { error: "Invalid token request".into(), details: Some(details) }))
+}
+
+pub async fn create_token(
+    State(s): State<Arc<AppState>>, Extension(claims): Extension<Claims>, Json(req): Json<CreateToken>,
+) -> Result<(StatusCode, Json<CreatedToken>), (StatusCode, Json<Err>)> {
+    require_admin(&claims)?;
+    validate(&req)?;
+    let created = s.tokens.create(req, now_secs());
+    tracing::info!(token_id = %created.info.id, by = %claims.sub, tenant = %created.info.tenant, scopes = ?created.info.scopes, "API token created");
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+pub fn validate(req: &CreateToken) -> Result<(), (StatusCode, Json<Err>)> {
+    if req.name.trim().is_empty() || req.scopes.is_empty() { return Err(bad_request("name and at least one scope are required".into())); }
+    if req.tenant.trim().is_empty() || HeaderValue::from_str(&req.tenant).is_err() { return Err(bad_request("tenant must be a non-empty header-safe ID".into())); }
+    if req.access_role.as_deref().is_some_and(|r| r.is_empty() || HeaderValue::from_str(r).is_err()) {
+        return Err(bad_request("access_role must be a non-empty header-safe name".into()));
+    }
+    if let Some(bad) = req.scopes.iter().find(|s| !known_scope(s)) { return Err(bad_request(format!("unknown scope {bad}"))); }
+    if req.expires_in_secs == Some(0) { return Err(bad_request("expires_in_secs must be positive".into())); }
+    Ok(())
+}
+
+pub async fn list_tokens(
+    State(s): State<Arc<AppState>>, Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<TokenInfo>>, (StatusCode, Json<Err>)> {
+    require_admin(&claims)?;
+    Ok(Json(s.tokens.list()))
+}
+
+pub async fn revoke_token(
+    State(s): State<Arc<AppState>>, Extension(claims): Extension<Claims>, Path(id): Path<String>,
+) -> Result<Json<TokenInfo>, (StatusCode, Json<Err>)> {
+    require_admin(&claims)?;
+    let info = s.tokens.revoke(&id, now_secs())
+        .ok_or((StatusCode::NOT_FOUND, Json(Err { error: "Unknown token".into(), details: None })))?;
+    tracing::info!(token_id = %id, by = %claims.sub, "API token revoked");
+    Ok(Json(info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(scopes: &[&str], expires_in_secs: Option<u64>) -> CreateToken {
+        CreateToken {
+            name: "intake".into(), tenant: "acme".into(), access_role: None,
+            scopes: scopes.iter().map(|s| s.to_string()).collect(), expires_in_secs, owner: None,
+        }
+    }
+
+    #[test]
+    fn scopes_match_whole_path_segments() {
+        let analyze = vec!["analyze".to_string()];
+        assert!(allows(&analyze, &Method::POST, "/api/v1/legal/analyze"));
+        assert!(allows(&analyze, &Method::POST, "/api/v1/legal/analyze/batch"));
+        assert!(!allows(&analyze, &Method::GET, "/api/v1/legal/analyze"));
+        assert!(!allows(&analyze, &Method::POST, "/api/v1/legal/analyzeX"));
+        assert!(allows(&analyze, &Method::DELETE, "/api/v1/legal/jobs/j1"));
+
+        let read = vec!["templates:read".to_string()];
+        assert!(allows(&read, &Method::GET, "/api/v1/legal/templates/nda"));
+        assert!(!allows(&read, &Method::POST, "/api/v1/legal/templates"));
+        assert!(!allows(&read, &Method::GET, "/api/v1/legal/templatesX"));
+
+        let admin = vec!["admin".to_string()];
+        assert!(allows(&admin, &Method::DELETE, "/admin/tokens/tok_1"));
+        assert!(!allows(&[], &Method::GET, "/api/v1/legal/templates"));
+    }
+
+    #[test]
+    fn authorize_checks_revocation_expiry_and_scope() {
+        let store = TokenStore::default();
+        let created = store.create(request(&["compile"], Some(60)), 1000);
+        let secret = created.token.as_str();
+
+        assert!(matches!(store.authorize("lgt_unknown", &Method::POST, "/api/v1/legal/compile", 1000), Err(Denied::Unknown)));
+        assert!(matches!(store.authorize(secret, &Method::POST, "/api/v1/legal/analyze", 1000), Err(Denied::OutOfScope)));
+        let used = store.authorize(secret, &Method::POST, "/api/v1/legal/compile", 1010).ok().unwrap();
+        assert_eq!((used.tenant.as_str(), used.use_count, used.last_used_at), ("acme", 1, Some(1010)));
+        assert!(matches!(store.authorize(secret, &Method::POST, "/api/v1/legal/compile", 1060), Err(Denied::Expired)));
+
+        store.revoke(&created.info.id, 1020).unwrap();
+        assert!(matches!(store.authorize(secret, &Method::POST, "/api/v1/legal/compile", 1030), Err(Denied::Revoked)));
+        assert_eq!(store.list()[0].revoked_at, Some(1020));
+    }
+
+    #[test]
+    fn the_file_keeps_tokens_but_not_their_secrets() {
+        let path = std::env::temp_dir().join(format!("gateway-tokens-{}.json", uuid::Uuid::new_v4().simple()));
+        let store = TokenStore::open(path.clone()).unwrap();
+        let created = store.create(request(&["analyze"], None), 1000);
+        store.authorize(&created.token, &Method::POST, "/api/v1/legal/analyze", 1005).ok().unwrap();
+        store.flush();
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(!saved.contains(&created.token));
+        let reopened = TokenStore::open(path.clone()).unwrap();
+        let again = reopened.authorize(&created.token, &Method::POST, "/api/v1/legal/analyze", 1010).ok().unwrap();
+        assert_eq!((again.id, again.use_count), (created.info.id, 2));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,9 @@
+//! Generates the gRPC service and messages from `proto/legal.proto`, with
+//! the `protoc` shipped in `protoc-bin-vendored` so none has to be installed.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto/legal.proto");
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::compile_protos("proto/legal.proto")?;
+    Ok(())
+}
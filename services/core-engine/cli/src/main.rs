@@ -0,0 +1,385 @@
+//! `alice-legal`: the engine on the command line, for machines that cannot
+//! reach the HTTP service. Analyzes and scores a contract file, or compiles
+//! a built-in template, in-process and prints the result as text or JSON.
+
+use std::{
+    collections::HashMap,
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use alice_legal_core::{AnalyzeResponse, AppState, CompileResponse, RiskScoreResponse};
+use serde::Serialize;
+
+const USAGE: &str = "\
+usage: alice-legal <command> [options]
+
+commands:
+  analyze <file>                 clauses, issues and the risk score
+  risk <file>                    the risk model's factors and recommendations
+  compile <template> --var k=v   render a built-in template
+
+options:
+  --json                 print the JSON the HTTP API returns
+  --language <code>      document language for analyze (default: en)
+  --tenant <id>          tenant whose lexicons and quotas apply (default: default)
+  --var <key>=<value>    template variable for compile; repeatable
+
+<file> may be `-` to read standard input. The LEGAL_* environment
+configures the engine as it does the server.";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Analyze {
+        file: String,
+        language: String,
+        tenant: String,
+    },
+    Risk {
+        file: String,
+    },
+    Compile {
+        template: String,
+        variables: HashMap<String, String>,
+    },
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    command: Command,
+    json: bool,
+}
+
+fn parse(args: &[String]) -> Result<Args, String> {
+    let mut json = false;
+    let mut language = None;
+    let mut tenant = None;
+    let mut variables = HashMap::new();
+    let mut positional = Vec::new();
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let mut value = |name: &str| {
+            it.next()
+                .cloned()
+                .ok_or_else(|| format!("{name} needs a value"))
+        };
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => {
+                return Ok(Args {
+                    command: Command::Help,
+                    json,
+                })
+            }
+            "--language" => language = Some(value("--language")?),
+            "--tenant" => tenant = Some(value("--tenant")?),
+            "--var" => {
+                let pair = value("--var")?;
+                let (key, val) = pair
+                    .split_once('=')
+                    .filter(|(k, _)| !k.trim().is_empty())
+                    .ok_or_else(|| format!("--var expects key=value, got `{pair}`"))?;
+                variables.insert(key.trim().to_string(), val.to_string());
+            }
+            "-" => positional.push(arg.clone()),
+            flag if flag.starts_with('-') => return Err(format!("unknown option `{flag}`")),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let name = positional.next().ok_or("missing command")?;
+    let operand = positional.next();
+    if let Some(extra) = positional.next() {
+        return Err(format!("unexpected argument `{extra}`"));
+    }
+    let only = |allowed: bool, flag: &str| {
+        if allowed {
+            Ok(())
+        } else {
+            Err(format!("{flag} does not apply to `{name}`"))
+        }
+    };
+    only(language.is_none() || name == "analyze", "--language")?;
+    only(tenant.is_none() || name == "analyze", "--tenant")?;
+    only(variables.is_empty() || name == "compile", "--var")?;
+
+    let command = match name.as_str() {
+        "analyze" => Command::Analyze {
+            file: operand.ok_or("analyze needs a file")?,
+            language: language.unwrap_or_else(|| "en".to_string()),
+            tenant: tenant.unwrap_or_else(|| "default".to_string()),
+        },
+        "risk" => Command::Risk {
+            file: operand.ok_or("risk needs a file")?,
+        },
+        "compile" => Command::Compile {
+            template: operand.ok_or("compile needs a template ID")?,
+            variables,
+        },
+        "help" => Command::Help,
+        other => return Err(format!("unknown command `{other}`")),
+    };
+    Ok(Args { command, json })
+}
+
+fn read_document(file: &str) -> Result<String, String> {
+    if file == "-" {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| format!("cannot read standard input: {e}"))?;
+        return Ok(text);
+    }
+    std::fs::read_to_string(file).map_err(|e| format!("cannot read {file}: {e}"))
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).expect("responses serialize")
+}
+
+fn analysis_text(r: &AnalyzeResponse) -> String {
+    let mut out = format!(
+        "Analysis {} ({}, {} words)\nRisk score: {:.2}\n",
+        r.analysis_id, r.language, r.word_count, r.risk_score
+    );
+    if r.partial {
+        out.push_str(&format!(
+            "Partial: skipped {}\n",
+            r.skipped_stages.join(", ")
+        ));
+    }
+    out.push_str(&format!("\nClauses ({}):\n", r.clauses.len()));
+    for c in &r.clauses {
+        out.push_str(&format!(
+            "  [{}] {} ({:.2})\n",
+            c.risk_level, c.clause_type, c.confidence
+        ));
+    }
+    out.push_str(&format!("\nIssues ({}):\n", r.issues.len()));
+    for i in &r.issues {
+        out.push_str(&format!("  [{}] {}\n", i.severity, i.description));
+        if !i.location.is_empty() {
+            out.push_str(&format!("      at {}\n", i.location));
+        }
+    }
+    if !r.escalations.is_empty() {
+        out.push_str(&format!("\nEscalations: {}\n", r.escalations.join(", ")));
+    }
+    out
+}
+
+fn risk_text(r: &RiskScoreResponse) -> String {
+    let mut out = format!(
+        "Risk score: {:.2} ({})\n\nFactors:\n",
+        r.overall_score, r.risk_level
+    );
+    for f in &r.risk_factors {
+        out.push_str(&format!(
+            "  {:<24} weight {:.2}  score {:.2}  {}\n",
+            f.factor, f.weight, f.score, f.description
+        ));
+    }
+    if !r.recommendations.is_empty() {
+        out.push_str("\nRecommendations:\n");
+        for rec in &r.recommendations {
+            out.push_str(&format!("  - {rec}\n"));
+        }
+    }
+    out
+}
+
+/// The compiled document, with what the reader should still fix on stderr.
+fn compile_text(r: &CompileResponse) -> (String, Vec<String>) {
+    let mut notes = Vec::new();
+    if !r.missing_variables.is_empty() {
+        notes.push(format!(
+            "missing variables: {}",
+            r.missing_variables.join(", ")
+        ));
+    }
+    for w in &r.policy_warnings {
+        notes.push(format!(
+            "policy: {}",
+            serde_json::to_string(w).expect("warnings serialize")
+        ));
+    }
+    for w in &r.entity_warnings {
+        notes.push(format!(
+            "entity: {}",
+            serde_json::to_string(w).expect("warnings serialize")
+        ));
+    }
+    (r.compiled_document.clone(), notes)
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let state = AppState::from_env();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("cannot start the runtime: {e}"))?;
+    runtime.block_on(async { state.start() })?;
+
+    match args.command {
+        Command::Help => unreachable!("help is printed before the engine starts"),
+        Command::Analyze {
+            file,
+            language,
+            tenant,
+        } => {
+            let document = read_document(&file)?;
+            let response = state.analyze_document(&tenant, &document, &language);
+            if args.json {
+                println!("{}", to_json(&response));
+            } else {
+                print!("{}", analysis_text(&response));
+            }
+        }
+        Command::Risk { file } => {
+            let document = read_document(&file)?;
+            let response = state.score_risk(&document);
+            if args.json {
+                println!("{}", to_json(&response));
+            } else {
+                print!("{}", risk_text(&response));
+            }
+        }
+        Command::Compile {
+            template,
+            variables,
+        } => {
+            let response = state
+                .compile_document(&template, &variables)
+                .map_err(|status| match status.as_u16() {
+                    404 => format!("unknown template `{template}`"),
+                    _ => format!("template `{template}` did not compile with these variables"),
+                })?;
+            if args.json {
+                println!("{}", to_json(&response));
+            } else {
+                let (document, notes) = compile_text(&response);
+                println!("{document}");
+                for note in notes {
+                    eprintln!("warning: {note}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let argv: Vec<String> = std::env::args().skip(1).collect();
+    let args = match parse(&argv) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("alice-legal: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    if args.command == Command::Help {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("alice-legal: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Result<Args, String> {
+        parse(&list.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn parses_each_command_with_its_options() {
+        assert_eq!(
+            args(&["analyze", "nda.txt", "--language", "de", "--json"]).unwrap(),
+            Args {
+                command: Command::Analyze {
+                    file: "nda.txt".into(),
+                    language: "de".into(),
+                    tenant: "default".into(),
+                },
+                json: true,
+            }
+        );
+        assert_eq!(
+            args(&["risk", "-"]).unwrap().command,
+            Command::Risk { file: "-".into() }
+        );
+        let Command::Compile {
+            template,
+            variables,
+        } = args(&[
+            "compile",
+            "nda",
+            "--var",
+            "party_a=Acme",
+            "--var",
+            "term=2 = two",
+        ])
+        .unwrap()
+        .command
+        else {
+            panic!("expected compile");
+        };
+        assert_eq!(template, "nda");
+        assert_eq!(variables["party_a"], "Acme");
+        assert_eq!(variables["term"], "2 = two");
+    }
+
+    #[test]
+    fn rejects_bad_usage() {
+        for bad in [
+            &["analyze"][..],
+            &["frobnicate", "x"],
+            &["risk", "a.txt", "b.txt"],
+            &["risk", "a.txt", "--var", "k=v"],
+            &["compile", "nda", "--var", "novalue"],
+            &["compile", "nda", "--language", "en"],
+            &["analyze", "a.txt", "--tenant"],
+            &["analyze", "a.txt", "--verbose"],
+            &[],
+        ] {
+            assert!(args(bad).is_err(), "{bad:?} should be rejected");
+        }
+        assert_eq!(args(&["--help"]).unwrap().command, Command::Help);
+    }
+
+    #[test]
+    fn human_output_covers_what_the_json_does() {
+        let state = AppState::in_memory();
+        let doc = "The Supplier shall indemnify the Customer without limitation. \
+                   Either party may terminate this agreement on 30 days notice.";
+
+        let analysis = state.analyze_document("default", doc, "en");
+        let text = analysis_text(&analysis);
+        assert!(text.starts_with(&format!("Analysis {}", analysis.analysis_id)));
+        assert!(text.contains(&format!("Clauses ({})", analysis.clauses.len())));
+        for c in &analysis.clauses {
+            assert!(text.contains(&c.clause_type));
+        }
+
+        let risk = state.score_risk(doc);
+        let text = risk_text(&risk);
+        assert!(text.contains(&risk.risk_level));
+        for f in &risk.risk_factors {
+            assert!(text.contains(&f.factor));
+        }
+
+        let compiled = state.compile_document("nda", &HashMap::new()).unwrap();
+        let (document, notes) = compile_text(&compiled);
+        assert_eq!(document, compiled.compiled_document);
+        assert_eq!(notes.is_empty(), compiled.missing_variables.is_empty());
+    }
+}
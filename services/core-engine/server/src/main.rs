@@ -0,0 +1,133 @@
+//! The engine's HTTP server: reads the `LEGAL_*` environment, starts the
+//! engine and serves the API, with the admin endpoints on their own
+//! listener when `LEGAL_ADMIN_ADDR` is set and the gRPC API on one when
+//! `LEGAL_GRPC_ADDR` is. SIGTERM or Ctrl-C drains the in-flight work before
+//! the listeners close. Without API keys or a token secret it refuses to
+//! start unless `LEGAL_ALLOW_UNAUTHENTICATED` is set.
+
+use std::{future::IntoFuture, io, net::SocketAddr};
+
+use alice_legal_core::{admin_router, build_router, grpc_router, public_router, AppState};
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+/// Resolves on SIGTERM, as sent by Kubernetes and `docker stop`, or Ctrl-C.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to listen for Ctrl-C");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        () = ctrl_c => {},
+        () = terminate => {},
+    }
+}
+
+/// Serves the gRPC API until shutdown; resolves at once without a listener.
+async fn serve_grpc(state: AppState, listener: Option<tokio::net::TcpListener>) -> io::Result<()> {
+    let Some(listener) = listener else {
+        return Ok(());
+    };
+    axum::serve(
+        listener,
+        grpc_router(state.clone()).into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(state.stopped())
+    .await
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+            EnvFilter::new("alice_legal_core=info,alice_legal_server=info,tower_http=debug")
+        }))
+        .init();
+
+    let state = AppState::from_env();
+    if let Err(e) = state.require_authentication().and_then(|()| state.start()) {
+        panic!("{e}");
+    }
+    let addr_str = std::env::var("LEGAL_ADDR").unwrap_or_else(|_| "0.0.0.0:8081".to_string());
+    let addr: SocketAddr = addr_str.parse().expect("invalid LEGAL_ADDR");
+    let admin_addr: Option<SocketAddr> = std::env::var("LEGAL_ADMIN_ADDR")
+        .ok()
+        .map(|a| a.parse().expect("invalid LEGAL_ADMIN_ADDR"));
+    let grpc_addr: Option<SocketAddr> = std::env::var("LEGAL_GRPC_ADDR")
+        .ok()
+        .map(|a| a.parse().expect("invalid LEGAL_GRPC_ADDR"));
+    let grpc_listener = match grpc_addr {
+        Some(grpc_addr) => {
+            let listener = tokio::net::TcpListener::bind(grpc_addr)
+                .await
+                .expect("failed to bind gRPC listener");
+            info!("gRPC API listening on {}", grpc_addr);
+            Some(listener)
+        }
+        None => None,
+    };
+
+    let draining = state.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        draining.begin_shutdown();
+    });
+
+    let Some(admin_addr) = admin_addr else {
+        info!("ALICE Legal Engine listening on {}", addr);
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .expect("failed to bind");
+        tokio::try_join!(
+            axum::serve(
+                listener,
+                build_router(state.clone()).into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(state.stopped())
+            .into_future(),
+            serve_grpc(state.clone(), grpc_listener),
+        )
+        .expect("server error");
+        info!("ALICE Legal Engine stopped");
+        return;
+    };
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .expect("failed to bind");
+    let admin_listener = tokio::net::TcpListener::bind(admin_addr)
+        .await
+        .expect("failed to bind admin listener");
+    info!("ALICE Legal Engine listening on {}", addr);
+    info!("admin endpoints listening on {}", admin_addr);
+
+    // Either listener failing takes the process down rather than leaving
+    // half a service running.
+    tokio::try_join!(
+        axum::serve(
+            listener,
+            public_router(state.clone()).into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(state.stopped())
+        .into_future(),
+        axum::serve(
+            admin_listener,
+            admin_router(state.clone()).into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(state.stopped())
+        .into_future(),
+        serve_grpc(state.clone(), grpc_listener),
+    )
+    .expect("server error");
+    info!("ALICE Legal Engine stopped");
+}
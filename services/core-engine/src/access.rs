@@ -0,0 +1,321 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tracing::{info, warn};
+
+use crate::{
+    auth, corpus::StoredDocument, diligence, evidence::DEFAULT_CONTEXT_CHARS, export_control,
+    notifier, renewal, AppState, Clause, Issue,
+};
+
+/// Set by the gateway from the caller's credentials; clients cannot pick fields.
+pub const ROLE_HEADER: &str = "x-access-role";
+
+// ── Policy ────────────────────────────────────────────────────────────────────
+
+/// Fields each role may see, as dotted paths into the full analysis record.
+/// `*` grants everything; a path into an array applies to every element.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessPolicy {
+    pub roles: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for AccessPolicy {
+    fn default() -> Self {
+        let paths = |p: &[&str]| p.iter().map(|s| s.to_string()).collect();
+        Self {
+            roles: BTreeMap::from([
+                ("legal".to_string(), paths(&["*"])),
+                (
+                    "sales".to_string(),
+                    paths(&[
+                        "analysis_id",
+                        "language",
+                        "family_id",
+                        "commercial_terms",
+                        "payment.end_date",
+                        "clauses.clause_type",
+                        "clauses.risk_level",
+                    ]),
+                ),
+                (
+                    "finance".to_string(),
+                    paths(&[
+                        "analysis_id",
+                        "family_id",
+                        "payment",
+                        "commercial_terms.termination_notice_days",
+                        "commercial_terms.auto_renewal",
+                        "commercial_terms.liability_cap",
+                        "risk_score",
+                    ]),
+                ),
+            ]),
+        }
+    }
+}
+
+impl AccessPolicy {
+    /// Reads `LEGAL_ACCESS_POLICY_FILE` when set. A missing or malformed
+    /// file stops startup rather than silently widening access.
+    pub fn from_env() -> Self {
+        match std::env::var("LEGAL_ACCESS_POLICY_FILE") {
+            Ok(path) => {
+                let raw = std::fs::read_to_string(&path)
+                    .unwrap_or_else(|e| panic!("cannot read access policy {path}: {e}"));
+                serde_json::from_str(&raw)
+                    .unwrap_or_else(|e| panic!("invalid access policy {path}: {e}"))
+            }
+            Err(_) => Self::default(),
+        }
+    }
+}
+
+fn project(value: &Value, path: &[&str]) -> Option<Value> {
+    let Some((head, rest)) = path.split_first() else {
+        return Some(value.clone());
+    };
+    match value {
+        Value::Object(map) => {
+            let inner = project(map.get(*head)?, rest)?;
+            Some(Value::Object(Map::from_iter([(head.to_string(), inner)])))
+        }
+        // Keep one slot per element so projections of sibling paths line up.
+        Value::Array(items) => Some(Value::Array(
+            items
+                .iter()
+                .map(|v| project(v, path).unwrap_or_else(|| Value::Object(Map::new())))
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
+fn merge(into: &mut Value, from: Value) {
+    match (into, from) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (k, v) in b {
+                match a.get_mut(&k) {
+                    Some(existing) => merge(existing, v),
+                    None => {
+                        a.insert(k, v);
+                    }
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for (x, y) in a.iter_mut().zip(b) {
+                merge(x, y);
+            }
+        }
+        (slot, v) => *slot = v,
+    }
+}
+
+/// Keeps only the fields reachable through `allowed`.
+pub fn filter(record: &Value, allowed: &[String]) -> Value {
+    if allowed.iter().any(|p| p == "*") {
+        return record.clone();
+    }
+    let mut out = Value::Object(Map::new());
+    for path in allowed {
+        let segments: Vec<&str> = path.split('.').collect();
+        if let Some(v) = project(record, &segments) {
+            merge(&mut out, v);
+        }
+    }
+    out
+}
+
+// ── Record ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct CommercialTerms {
+    pub governing_law: Option<String>,
+    pub termination_notice_days: Option<u32>,
+    pub auto_renewal: bool,
+    pub assignment_restricted: bool,
+    pub change_of_control: bool,
+    pub exclusivity: bool,
+    pub non_compete: bool,
+    pub liability_cap: bool,
+    pub indemnification: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentTerms {
+    pub pricing: Option<String>,
+    pub end_date: Option<chrono::NaiveDate>,
+}
+
+/// Everything known about a stored analysis; roles see projections of it.
+#[derive(Debug, Serialize)]
+pub struct AnalysisRecord {
+    pub analysis_id: String,
+    pub language: String,
+    pub stored_at: chrono::DateTime<chrono::Utc>,
+    pub family_id: Option<String>,
+    pub parent_id: Option<String>,
+    pub text: String,
+    pub parties: Vec<String>,
+    pub commercial_terms: CommercialTerms,
+    pub payment: PaymentTerms,
+    pub risk_score: f64,
+    pub clauses: Vec<Clause>,
+    pub issues: Vec<Issue>,
+}
+
+pub fn record(state: &AppState, doc: StoredDocument) -> AnalysisRecord {
+    // Reviewed analyses win over a fresh run so verdicts are reflected.
+    let (clauses, issues, risk_score) = match state.escalations.analysis(&doc.id) {
+        Some(a) => (a.clauses, a.issues, a.risk_score),
+        None => {
+            let issues = export_control::issues(
+                &doc.text,
+                &state.export_policy.get(),
+                1,
+                DEFAULT_CONTEXT_CHARS,
+            );
+            (
+                state.clause_extractor.extract(
+                    state.precompiled.keywords(),
+                    &doc.text,
+                    DEFAULT_CONTEXT_CHARS,
+                ),
+                issues,
+                state
+                    .risk_model
+                    .factors(&doc.text)
+                    .iter()
+                    .map(|f| f.weight * f.score)
+                    .sum(),
+            )
+        }
+    };
+    let terms = diligence::key_terms(&doc.text);
+    AnalysisRecord {
+        parties: renewal::parties(&doc.text).map_or_else(Vec::new, |(a, b)| vec![a, b]),
+        commercial_terms: CommercialTerms {
+            governing_law: terms.governing_law,
+            termination_notice_days: terms.termination_notice_days,
+            auto_renewal: terms.auto_renewal,
+            assignment_restricted: terms.assignment_restricted,
+            change_of_control: terms.change_of_control,
+            exclusivity: terms.exclusivity,
+            non_compete: terms.non_compete,
+            liability_cap: terms.liability_cap,
+            indemnification: terms.indemnification,
+        },
+        payment: PaymentTerms {
+            pricing: renewal::pricing(&doc.text),
+            end_date: renewal::expiry_date(&doc.text),
+        },
+        risk_score,
+        clauses,
+        issues,
+        analysis_id: doc.id,
+        language: doc.language,
+        stored_at: doc.stored_at,
+        family_id: doc.family_id,
+        parent_id: doc.parent_id,
+        text: doc.text,
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn get_analysis(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    let role = headers
+        .get(ROLE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or(StatusCode::FORBIDDEN)?;
+    let allowed = state
+        .access_policy
+        .roles
+        .get(role)
+        .ok_or(StatusCode::FORBIDDEN)?;
+    let full = match auth::owned_document(&state, &headers, &id) {
+        Some(doc) => serde_json::to_value(record(&state, doc))
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        // Not in the corpus (a risk score, or from before a restart): serve
+        // the stored response, but only to its own tenant.
+        None => history_view(&state, &id, &notifier::tenant(&headers))?,
+    };
+    info!(analysis_id = %id, role, "analysis view served");
+    Ok(Json(filter(&full, allowed)))
+}
+
+fn history_view(state: &AppState, id: &str, tenant: &str) -> Result<Value, StatusCode> {
+    let stored = state
+        .history
+        .get(id)
+        .map_err(|e| {
+            warn!(analysis_id = %id, error = %e, "analysis history lookup failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .filter(|r| r.tenant == tenant)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let mut full = stored.result;
+    if let Value::Object(map) = &mut full {
+        map.insert("id".into(), json!(stored.summary.id));
+        map.insert("kind".into(), json!(stored.summary.kind));
+        map.insert("stored_at".into(), json!(stored.summary.created_at));
+        map.insert(
+            "document_sha256".into(),
+            json!(stored.summary.document_sha256),
+        );
+    }
+    Ok(full)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn allow(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn filter_projects_nested_and_array_paths() {
+        let record = json!({
+            "id": "a",
+            "text": "secret",
+            "terms": { "law": "NY", "cap": true },
+            "clauses": [{ "type": "Liability", "text": "x" }, { "type": "Term", "text": "y" }],
+        });
+        let out = filter(&record, &allow(&["id", "terms.law", "clauses.type"]));
+        assert_eq!(
+            out,
+            json!({
+                "id": "a",
+                "terms": { "law": "NY" },
+                "clauses": [{ "type": "Liability" }, { "type": "Term" }],
+            })
+        );
+    }
+
+    #[test]
+    fn wildcard_and_unknown_paths() {
+        let record = json!({ "a": 1, "b": { "c": 2 } });
+        assert_eq!(filter(&record, &allow(&["*"])), record);
+        assert_eq!(filter(&record, &allow(&["b.c.d", "z"])), json!({}));
+        assert_eq!(
+            filter(&record, &allow(&["b.c", "b"])),
+            json!({ "b": { "c": 2 } })
+        );
+    }
+}
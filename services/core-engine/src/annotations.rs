@@ -0,0 +1,242 @@
+//! Risk metadata for template sections, emitted alongside compiled documents
+//! so a fresh draft starts negotiation with its clause map and playbook
+//! positions already attached.
+
+use std::ops::Range;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Negotiability {
+    /// Not to be changed in negotiation.
+    Fixed,
+    /// Changes need sign-off from legal.
+    ApprovalRequired,
+    /// The business may agree any listed fallback.
+    Negotiable,
+}
+
+/// Metadata for one paragraph of a template (paragraphs are separated by a
+/// blank line; 0 is the title).
+struct ClauseTag {
+    paragraph: usize,
+    clause_type: &'static str,
+    risk_level: &'static str,
+    negotiability: Negotiability,
+    standard_position: &'static str,
+    fallback_positions: &'static [&'static str],
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ClauseAnnotation {
+    pub clause_id: String,
+    /// Byte range of the clause in `compiled_document`.
+    pub start: usize,
+    pub end: usize,
+    pub clause_type: String,
+    pub risk_level: String,
+    pub negotiability: Negotiability,
+    pub standard_position: String,
+    pub fallback_positions: Vec<String>,
+}
+
+const fn tag(
+    paragraph: usize,
+    clause_type: &'static str,
+    risk_level: &'static str,
+    negotiability: Negotiability,
+    standard_position: &'static str,
+    fallback_positions: &'static [&'static str],
+) -> ClauseTag {
+    ClauseTag {
+        paragraph,
+        clause_type,
+        risk_level,
+        negotiability,
+        standard_position,
+        fallback_positions,
+    }
+}
+
+fn clause_tags(template_id: &str) -> Vec<ClauseTag> {
+    use Negotiability::{ApprovalRequired, Fixed, Negotiable};
+    match template_id {
+        "nda" => vec![
+            tag(
+                1,
+                "Jurisdiction",
+                "low",
+                ApprovalRequired,
+                "Our home jurisdiction.",
+                &[
+                    "Counterparty jurisdiction with arbitration seated in ours.",
+                    "Neutral jurisdiction such as England and Wales.",
+                ],
+            ),
+            tag(
+                2,
+                "Confidentiality",
+                "medium",
+                Negotiable,
+                "Three-year confidentiality term.",
+                &["Two-year term.", "Five years, limited to trade secrets."],
+            ),
+        ],
+        "sla" => vec![
+            tag(
+                1,
+                "Service Levels",
+                "high",
+                ApprovalRequired,
+                "Uptime as stated, remedied by service credits only.",
+                &[
+                    "Lower uptime commitment with higher service credits.",
+                    "Scheduled maintenance excluded from uptime.",
+                ],
+            ),
+            tag(
+                2,
+                "Incident Response",
+                "medium",
+                Negotiable,
+                "Response within the stated hours for all incidents.",
+                &["Business-hours response for non-critical incidents."],
+            ),
+        ],
+        "dpa" => vec![
+            tag(
+                1,
+                "Data Processing",
+                "high",
+                Fixed,
+                "GDPR Article 28 terms apply in full.",
+                &[],
+            ),
+            tag(
+                2,
+                "Data Retention",
+                "high",
+                ApprovalRequired,
+                "Retention limited to the stated period.",
+                &["Deletion on termination with a 30-day grace period."],
+            ),
+        ],
+        "tos" => vec![
+            tag(
+                1,
+                "Acceptance",
+                "low",
+                Fixed,
+                "Use of the service constitutes acceptance.",
+                &[],
+            ),
+            tag(2, "Jurisdiction", "low", Fixed, "Our governing law.", &[]),
+        ],
+        "privacy" => vec![tag(
+            2,
+            "Data Collection",
+            "medium",
+            Fixed,
+            "Only the listed data is collected.",
+            &[],
+        )],
+        "employment" => vec![tag(
+            1,
+            "Compensation",
+            "medium",
+            Negotiable,
+            "Annual salary as stated.",
+            &["Signing bonus instead of a higher base salary."],
+        )],
+        "license" => vec![tag(
+            1,
+            "License Grant",
+            "high",
+            ApprovalRequired,
+            "Non-exclusive licence against the stated fee.",
+            &["Time-limited exclusivity in a defined field of use."],
+        )],
+        "renewal" => vec![
+            tag(
+                2,
+                "Term",
+                "medium",
+                Negotiable,
+                "Extension to the stated end date.",
+                &[
+                    "Shorter extension.",
+                    "Extension with termination for convenience on 90 days notice.",
+                ],
+            ),
+            tag(
+                3,
+                "Pricing",
+                "high",
+                ApprovalRequired,
+                "Fees as stated for the extended term.",
+                &["Increase capped at CPI."],
+            ),
+            tag(
+                4,
+                "Entire Agreement",
+                "low",
+                Fixed,
+                "All other terms unchanged.",
+                &[],
+            ),
+        ],
+        _ => vec![],
+    }
+}
+
+/// Annotations for a compiled template, given the byte range of each of its
+/// paragraphs in the compiled text.
+pub fn annotate(template_id: &str, paragraphs: &[Range<usize>]) -> Vec<ClauseAnnotation> {
+    clause_tags(template_id)
+        .into_iter()
+        .filter_map(|t| {
+            let range = paragraphs.get(t.paragraph)?;
+            Some((t, range.clone()))
+        })
+        .enumerate()
+        .map(|(i, (t, range))| ClauseAnnotation {
+            clause_id: format!("clause-{:03}", i + 1),
+            start: range.start,
+            end: range.end,
+            clause_type: t.clause_type.to_string(),
+            risk_level: t.risk_level.to_string(),
+            negotiability: t.negotiability,
+            standard_position: t.standard_position.to_string(),
+            fallback_positions: t.fallback_positions.iter().map(|s| s.to_string()).collect(),
+        })
+        .collect()
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{warmup::Precompiled, BUILTIN_TEMPLATES};
+    use std::collections::HashMap;
+
+    #[test]
+    fn every_tag_points_at_a_template_paragraph() {
+        let pre = Precompiled::default();
+        for id in BUILTIN_TEMPLATES {
+            let rendered = pre.template(id).unwrap().render(&HashMap::new()).unwrap();
+            for t in clause_tags(id) {
+                assert!(
+                    t.paragraph < rendered.paragraphs.len(),
+                    "{id} paragraph {}",
+                    t.paragraph
+                );
+                if t.negotiability == Negotiability::Negotiable {
+                    assert!(!t.fallback_positions.is_empty(), "{id} {}", t.clause_type);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::Mutex;
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub id: String,
+    pub at: DateTime<Utc>,
+    pub action: String,
+    /// What the action was about, e.g. a document ID.
+    pub subject: String,
+    pub actor: Option<String>,
+    pub detail: serde_json::Value,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    pub subject: Option<String>,
+    pub action: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditResponse {
+    pub entries: Vec<AuditEntry>,
+    pub count: usize,
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+/// Append-only record of decisions that must be reconstructible later.
+#[derive(Default)]
+pub struct AuditLog {
+    entries: Mutex<Vec<AuditEntry>>,
+}
+
+impl AuditLog {
+    pub fn record(
+        &self,
+        id: String,
+        at: DateTime<Utc>,
+        action: &str,
+        subject: &str,
+        actor: Option<&str>,
+        detail: serde_json::Value,
+    ) -> AuditEntry {
+        let entry = AuditEntry {
+            id,
+            at,
+            action: action.to_string(),
+            subject: subject.to_string(),
+            actor: actor.map(str::to_string),
+            detail,
+        };
+        self.entries
+            .lock()
+            .expect("audit log lock poisoned")
+            .push(entry.clone());
+        entry
+    }
+
+    /// Rewrites entries in place, e.g. to pseudonymize erased people;
+    /// returns how many `f` reported as changed.
+    pub fn rewrite(&self, f: impl FnMut(&mut AuditEntry) -> bool) -> usize {
+        self.entries
+            .lock()
+            .expect("audit log lock poisoned")
+            .iter_mut()
+            .map(f)
+            .filter(|changed| *changed)
+            .count()
+    }
+
+    /// Entries in the order they were recorded.
+    pub fn query(&self, q: &AuditQuery) -> Vec<AuditEntry> {
+        self.entries
+            .lock()
+            .expect("audit log lock poisoned")
+            .iter()
+            .filter(|e| q.subject.as_ref().is_none_or(|s| e.subject == *s))
+            .filter(|e| q.action.as_ref().is_none_or(|a| e.action == *a))
+            .cloned()
+            .collect()
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn list_entries(
+    State(state): State<AppState>,
+    Query(q): Query<AuditQuery>,
+) -> Json<AuditResponse> {
+    let entries = state.audit.query(&q);
+    let count = entries.len();
+    Json(AuditResponse { entries, count })
+}
@@ -0,0 +1,427 @@
+//! Authentication by API key or signed token. With `LEGAL_AUTH_FILE` or
+//! `LEGAL_JWT_SECRET` set every request must carry a key from the file or an
+//! HS256 token signed with the secret, such as the one the gateway signs for
+//! each request it forwards. The credential decides the caller's tenant and
+//! access role: the tenant and role headers the handlers read are overwritten
+//! from it, so a client can neither pick another tenant nor widen its own
+//! view. Admin routes, the audit log and writes to stores every tenant shares
+//! (macros, regulatory rules, policies, the clause library, built-in
+//! template revisions) also need an admin credential. Without either setting
+//! nothing vouches for those headers, so they are dropped and every request
+//! acts for the default tenant, unless the state was built to trust them.
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::{
+    access::ROLE_HEADER,
+    corpus::StoredDocument,
+    notifier::{self, TENANT_HEADER},
+    openapi, AppState,
+};
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Routes that check their own credentials: share links carry a token,
+/// the content webhook a shared secret, and probes and API docs none.
+const OPEN_PATHS: [&str; 7] = [
+    "/health",
+    openapi::SPEC_PATH,
+    openapi::DOCS_PATH,
+    "/health/warm",
+    "/metrics",
+    "/api/v1/legal/admin/content/refresh",
+    "/api/v1/legal/shared/",
+];
+
+/// Stores every tenant reads; changing them needs an admin key.
+const SHARED_STORES: [&str; 5] = [
+    "/api/v1/legal/macros",
+    "/api/v1/legal/clause-library",
+    "/api/v1/legal/regulatory/rules",
+    "/api/v1/legal/export-control/policy",
+    "/api/v1/legal/compile/policy",
+];
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiKey {
+    /// Names the key in logs and audit entries; never the key itself.
+    pub id: String,
+    pub tenant: String,
+    /// Hex SHA-256 of the key, so the file holds no usable secret.
+    pub key_sha256: String,
+    /// Access role for field-level views; callers without one get none.
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub admin: bool,
+    /// Requests per minute for this key instead of the server's limit.
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AuthFile {
+    keys: Vec<ApiKey>,
+}
+
+/// What a signed bearer token carries.
+#[derive(Debug, Deserialize)]
+struct TokenClaims {
+    sub: String,
+    tenant: String,
+    #[serde(default)]
+    role: Option<String>,
+    #[serde(default)]
+    admin: bool,
+}
+
+/// Keys by their hash, and the secret signed tokens are checked with.
+#[derive(Default)]
+pub struct AuthConfig {
+    keys: HashMap<String, ApiKey>,
+    jwt_key: Option<DecodingKey>,
+}
+
+impl AuthConfig {
+    pub fn new(keys: Vec<ApiKey>) -> Self {
+        Self {
+            keys: keys
+                .into_iter()
+                .map(|k| (k.key_sha256.to_ascii_lowercase(), k))
+                .collect(),
+            jwt_key: None,
+        }
+    }
+
+    /// Also accepts HS256 tokens signed with `secret`.
+    pub fn with_jwt_secret(mut self, secret: &str) -> Self {
+        self.jwt_key = Some(DecodingKey::from_secret(secret.as_bytes()));
+        self
+    }
+
+    /// Reads `LEGAL_AUTH_FILE` and `LEGAL_JWT_SECRET`; `None` when neither
+    /// is set. A missing or malformed file or an empty secret stops startup
+    /// rather than silently opening the API.
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var("LEGAL_AUTH_FILE").ok();
+        let secret = std::env::var("LEGAL_JWT_SECRET").ok();
+        if path.is_none() && secret.is_none() {
+            return None;
+        }
+        if secret.as_deref().is_some_and(|s| s.trim().is_empty()) {
+            panic!("LEGAL_JWT_SECRET is set but empty");
+        }
+        let config = path.map_or_else(|| Self::new(Vec::new()), |p| Self::from_file(&p));
+        Some(match secret {
+            Some(secret) => config.with_jwt_secret(&secret),
+            None => config,
+        })
+    }
+
+    fn from_file(path: &str) -> Self {
+        let raw = std::fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("cannot read auth file {path}: {e}"));
+        let file: AuthFile =
+            serde_json::from_str(&raw).unwrap_or_else(|e| panic!("invalid auth file {path}: {e}"));
+        if let Some(k) = file.keys.iter().find(|k| {
+            k.tenant.trim().is_empty()
+                || k.key_sha256.len() != 64
+                || k.rate_limit_per_minute == Some(0)
+        }) {
+            panic!(
+                "invalid auth file {path}: key {} needs a tenant, a 64-digit key_sha256 and a positive rate_limit_per_minute if any",
+                k.id
+            );
+        }
+        Self::new(file.keys)
+    }
+
+    fn find(&self, key: &str) -> Option<&ApiKey> {
+        self.keys.get(&hash(key))
+    }
+
+    /// The caller a key or signed token names; tokens are HS256 only, so
+    /// the token cannot choose how it is checked.
+    fn caller(&self, credential: &str) -> Option<Caller> {
+        if let Some(key) = self.find(credential) {
+            return Some(Caller {
+                id: key.id.clone(),
+                tenant: key.tenant.clone(),
+                role: key.role.clone(),
+                admin: key.admin,
+                rate_limit_per_minute: key.rate_limit_per_minute,
+            });
+        }
+        let jwt_key = self.jwt_key.as_ref()?;
+        let claims = jsonwebtoken::decode::<TokenClaims>(
+            credential,
+            jwt_key,
+            &Validation::new(Algorithm::HS256),
+        )
+        .ok()?
+        .claims;
+        (!claims.tenant.trim().is_empty()).then(|| Caller {
+            id: format!("jwt:{}", claims.sub),
+            tenant: claims.tenant,
+            role: claims.role,
+            admin: claims.admin,
+            rate_limit_per_minute: None,
+        })
+    }
+}
+
+/// An authenticated key or token.
+struct Caller {
+    id: String,
+    tenant: String,
+    role: Option<String>,
+    admin: bool,
+    rate_limit_per_minute: Option<u32>,
+}
+
+pub fn hash(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+// ── Middleware ────────────────────────────────────────────────────────────────
+
+/// Who the request is from; present on every request when keys are on.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub key_id: String,
+    pub admin: bool,
+    pub rate_limit_per_minute: Option<u32>,
+}
+
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    bearer
+        .or_else(|| headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok()))
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+}
+
+/// Routes open to admin keys only, wherever they are mounted.
+fn needs_admin(method: &Method, path: &str) -> bool {
+    let revision = path.starts_with("/api/v1/legal/templates/") && path.ends_with("/revisions");
+    let shared = revision || SHARED_STORES.iter().any(|p| path.starts_with(p));
+    path == "/api/v1/legal/audit" || (shared && method != Method::GET)
+}
+
+fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer")],
+    )
+        .into_response()
+}
+
+async fn check(state: AppState, mut req: Request, next: Next, admin_only: bool) -> Response {
+    let Some(config) = state.auth.as_deref() else {
+        if !state.trust_identity_headers {
+            req.headers_mut().remove(TENANT_HEADER);
+            req.headers_mut().remove(ROLE_HEADER);
+        }
+        return next.run(req).await;
+    };
+    let path = req.uri().path().to_string();
+    let method = req.method().clone();
+    if OPEN_PATHS
+        .iter()
+        .any(|p| path == *p || (p.ends_with('/') && path.starts_with(p)))
+    {
+        return next.run(req).await;
+    }
+    let Some(key) = presented_key(req.headers()).and_then(|k| config.caller(k)) else {
+        info!(path = %path, "request without a valid API key or token");
+        return unauthorized();
+    };
+    let headers = req.headers_mut();
+    let claimed = notifier::tenant(headers);
+    if headers.contains_key(TENANT_HEADER) && claimed != key.tenant {
+        warn!(key_id = %key.id, tenant = %claimed, "credential used for another tenant");
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if (admin_only || needs_admin(&method, &path)) && !key.admin {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let Ok(tenant) = HeaderValue::from_str(&key.tenant) else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    headers.insert(TENANT_HEADER, tenant);
+    headers.remove(ROLE_HEADER);
+    if let Some(role) = key
+        .role
+        .as_deref()
+        .and_then(|r| HeaderValue::from_str(r).ok())
+    {
+        headers.insert(ROLE_HEADER, role);
+    }
+    req.extensions_mut().insert(Principal {
+        key_id: key.id,
+        admin: key.admin,
+        rate_limit_per_minute: key.rate_limit_per_minute,
+    });
+    next.run(req).await
+}
+
+pub async fn authenticate(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    check(state, req, next, false).await
+}
+
+pub async fn authenticate_admin(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    check(state, req, next, true).await
+}
+
+// ── Tenant scoping ────────────────────────────────────────────────────────────
+
+/// Whether the request may act across tenants: an admin key, or any
+/// request while keys are off.
+pub fn cross_tenant(state: &AppState, principal: Option<&Principal>) -> bool {
+    state.auth.is_none() || principal.is_some_and(|p| p.admin)
+}
+
+/// The caller's stored document; another tenant's reads as unknown.
+pub fn owned_document(state: &AppState, headers: &HeaderMap, id: &str) -> Option<StoredDocument> {
+    let tenant = notifier::tenant(headers);
+    state.corpus.get(id).filter(|d| d.tenant == tenant)
+}
+
+/// The caller's stored documents.
+pub fn tenant_documents(headers: &HeaderMap, state: &AppState) -> Vec<StoredDocument> {
+    let tenant = notifier::tenant(headers);
+    state
+        .corpus
+        .all()
+        .into_iter()
+        .filter(|d| d.tenant == tenant)
+        .collect()
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_found_by_hash_only() {
+        let config = AuthConfig::new(vec![ApiKey {
+            id: "acme-ci".to_string(),
+            tenant: "acme".to_string(),
+            key_sha256: hash("s3cret").to_uppercase(),
+            role: None,
+            admin: false,
+            rate_limit_per_minute: None,
+        }]);
+        assert_eq!(config.find("s3cret").unwrap().tenant, "acme");
+        assert!(config.find(&hash("s3cret")).is_none());
+        assert!(config.find("other").is_none());
+    }
+
+    #[test]
+    fn signed_tokens_name_the_caller_and_only_hs256_is_accepted() {
+        use jsonwebtoken::{encode, EncodingKey, Header};
+        let config = AuthConfig::new(Vec::new()).with_jwt_secret("shared");
+        let sign = |alg: Algorithm, secret: &str, claims: serde_json::Value| {
+            encode(
+                &Header::new(alg),
+                &claims,
+                &EncodingKey::from_secret(secret.as_bytes()),
+            )
+            .unwrap()
+        };
+        let exp = chrono::Utc::now().timestamp() + 60;
+        let claims =
+            serde_json::json!({ "sub": "oidc:u1", "tenant": "acme", "role": "legal", "exp": exp });
+
+        let caller = config
+            .caller(&sign(Algorithm::HS256, "shared", claims.clone()))
+            .unwrap();
+        assert_eq!(
+            (
+                caller.id.as_str(),
+                caller.tenant.as_str(),
+                caller.role.as_deref(),
+                caller.admin
+            ),
+            ("jwt:oidc:u1", "acme", Some("legal"), false)
+        );
+        assert!(config
+            .caller(&sign(Algorithm::HS256, "other", claims.clone()))
+            .is_none());
+        assert!(config
+            .caller(&sign(Algorithm::HS512, "shared", claims))
+            .is_none());
+        let expired = serde_json::json!({ "sub": "u1", "tenant": "acme", "exp": exp - 3600 });
+        assert!(config
+            .caller(&sign(Algorithm::HS256, "shared", expired))
+            .is_none());
+        let no_tenant = serde_json::json!({ "sub": "u1", "tenant": " ", "exp": exp });
+        assert!(config
+            .caller(&sign(Algorithm::HS256, "shared", no_tenant))
+            .is_none());
+        assert!(AuthConfig::new(Vec::new())
+            .caller(&sign(
+                Algorithm::HS256,
+                "shared",
+                serde_json::json!({ "sub": "u1", "tenant": "acme", "exp": exp })
+            ))
+            .is_none());
+    }
+
+    #[test]
+    fn bearer_wins_over_the_key_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(API_KEY_HEADER, HeaderValue::from_static("from-header"));
+        assert_eq!(presented_key(&headers), Some("from-header"));
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer from-bearer"),
+        );
+        assert_eq!(presented_key(&headers), Some("from-bearer"));
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Basic abc"));
+        assert_eq!(presented_key(&headers), Some("from-header"));
+    }
+
+    #[test]
+    fn shared_stores_are_read_by_all_but_written_by_admins() {
+        assert!(needs_admin(&Method::GET, "/api/v1/legal/audit"));
+        assert!(needs_admin(&Method::PUT, "/api/v1/legal/macros/closing"));
+        assert!(needs_admin(
+            &Method::POST,
+            "/api/v1/legal/templates/nda/revisions"
+        ));
+        assert!(needs_admin(
+            &Method::POST,
+            "/api/v1/legal/clause-library/cl-1/approve"
+        ));
+        assert!(!needs_admin(&Method::GET, "/api/v1/legal/macros"));
+        assert!(!needs_admin(&Method::GET, "/api/v1/legal/clause-library"));
+        assert!(!needs_admin(&Method::POST, "/api/v1/legal/templates"));
+        assert!(!needs_admin(&Method::PUT, "/api/v1/legal/templates/nda"));
+    }
+}
@@ -0,0 +1,414 @@
+//! Signing authority: each tenant's matrix of who may sign for which of its
+//! entities, up to what contract value and for which contract types, with
+//! the reporting line above each signer. The pre-signature gate and
+//! execution records check signatories against it. Signatories for
+//! entities the matrix does not list, such as the counterparty, are not
+//! checked.
+
+use std::collections::BTreeSet;
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use crate::{
+    access::ROLE_HEADER, notifier, renewal::CURRENCY_MARKERS, signature::Signatory, AppState,
+};
+
+const MAX_SIGNERS: usize = 5_000;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Signer {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The tenant's legal entity this person may sign for.
+    pub entity: String,
+    /// Highest contract value they may sign; unlimited when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_value: Option<f64>,
+    /// Contract types they may sign, matched within the contract type;
+    /// any type when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contract_types: Vec<String>,
+    /// The signer above them, suggested when they lack authority.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reports_to: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuthorityMatrix {
+    pub signers: Vec<Signer>,
+}
+
+/// What is being signed, as far as authority goes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deal {
+    pub value: Option<f64>,
+    pub contract_type: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthorityResponse {
+    pub tenant: String,
+    #[serde(flatten)]
+    pub matrix: AuthorityMatrix,
+}
+
+fn same(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+// ── Deal terms ────────────────────────────────────────────────────────────────
+
+/// The largest currency amount in `text`, taken as the contract value.
+pub fn largest_amount(text: &str) -> Option<f64> {
+    CURRENCY_MARKERS
+        .iter()
+        .flat_map(|marker| text.match_indices(marker).map(|(i, m)| i + m.len()))
+        .filter_map(|start| {
+            let rest = text[start..].trim_start();
+            let end = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == ',' || c == '.'))
+                .unwrap_or(rest.len());
+            rest[..end]
+                .trim_end_matches(['.', ','])
+                .replace(',', "")
+                .parse::<f64>()
+                .ok()
+        })
+        .max_by(f64::total_cmp)
+}
+
+/// The document's title, its first non-empty line, taken as the contract type.
+pub fn title(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+// ── Checking ──────────────────────────────────────────────────────────────────
+
+impl Signer {
+    /// Why this signer may not sign `deal`, if they may not.
+    fn refusal(&self, deal: &Deal) -> Option<String> {
+        if let Some(max) = self.max_value {
+            match deal.value {
+                Some(value) if value > max => {
+                    return Some(format!(
+                        "may sign up to {max} for {}, but the contract is worth {value}",
+                        self.entity
+                    ))
+                }
+                None => {
+                    return Some(format!(
+                        "may sign up to {max} for {}, but the contract value is unknown",
+                        self.entity
+                    ))
+                }
+                _ => {}
+            }
+        }
+        let kind = deal.contract_type.to_lowercase();
+        if !self.contract_types.is_empty()
+            && !self
+                .contract_types
+                .iter()
+                .any(|t| kind.contains(&t.trim().to_lowercase()))
+        {
+            return Some(format!(
+                "may only sign {} contracts for {}, not \"{}\"",
+                self.contract_types.join(", "),
+                self.entity,
+                deal.contract_type
+            ));
+        }
+        None
+    }
+}
+
+impl AuthorityMatrix {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.signers.is_empty() {
+            return Err("at least one signer is needed".to_string());
+        }
+        if self.signers.len() > MAX_SIGNERS {
+            return Err(format!("at most {MAX_SIGNERS} signers"));
+        }
+        let mut seen = BTreeSet::new();
+        for (i, s) in self.signers.iter().enumerate() {
+            if s.name.trim().is_empty() || s.entity.trim().is_empty() {
+                return Err(format!("signer {i} needs a name and an entity"));
+            }
+            if s.max_value.is_some_and(|v| !v.is_finite() || v < 0.0) {
+                return Err(format!("signer {i} has an invalid max_value"));
+            }
+            if !seen.insert((s.name.trim().to_lowercase(), s.entity.trim().to_lowercase())) {
+                return Err(format!("{} is listed twice for {}", s.name, s.entity));
+            }
+            if let Some(boss) = &s.reports_to {
+                if self.signer(boss, &s.entity).is_none() {
+                    return Err(format!(
+                        "{} reports to {boss}, who is not a signer for {}",
+                        s.name, s.entity
+                    ));
+                }
+            }
+            if self.chain(s).count() > self.signers.len() {
+                return Err(format!("the reporting line above {} loops", s.name));
+            }
+        }
+        Ok(())
+    }
+
+    fn signer(&self, name: &str, entity: &str) -> Option<&Signer> {
+        self.signers
+            .iter()
+            .find(|s| same(&s.name, name) && same(&s.entity, entity))
+    }
+
+    /// The signers above `signer`, nearest first; bounded, so a loop
+    /// (which `validate` rejects) still ends.
+    fn chain<'a>(&'a self, signer: &'a Signer) -> impl Iterator<Item = &'a Signer> + 'a {
+        let limit = self.signers.len() + 1;
+        std::iter::successors(Some(signer), |s| {
+            s.reports_to
+                .as_deref()
+                .and_then(|boss| self.signer(boss, &s.entity))
+        })
+        .skip(1)
+        .take(limit)
+    }
+
+    /// One line per signatory who may not sign `deal` for an entity the
+    /// matrix lists.
+    pub fn violations(&self, signatories: &[Signatory], deal: &Deal) -> Vec<String> {
+        signatories
+            .iter()
+            .filter(|s| self.signers.iter().any(|m| same(&m.entity, &s.entity)))
+            .filter_map(|s| {
+                let Some(signer) = self.signer(&s.name, &s.entity) else {
+                    return Some(format!(
+                        "{} has no signing authority for {}",
+                        s.name, s.entity
+                    ));
+                };
+                let refusal = signer.refusal(deal)?;
+                let escalate = self
+                    .chain(signer)
+                    .find(|boss| boss.refusal(deal).is_none())
+                    .map(|boss| match &boss.title {
+                        Some(title) => format!("; escalate to {} ({title})", boss.name),
+                        None => format!("; escalate to {}", boss.name),
+                    })
+                    .unwrap_or_default();
+                Some(format!("{} {refusal}{escalate}", s.name))
+            })
+            .collect()
+    }
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct AuthorityStore {
+    tenants: DashMap<String, AuthorityMatrix>,
+}
+
+impl AuthorityStore {
+    pub fn get(&self, tenant: &str) -> Option<AuthorityMatrix> {
+        self.tenants.get(tenant).map(|m| m.value().clone())
+    }
+
+    pub fn put(&self, tenant: &str, matrix: AuthorityMatrix) -> Result<(), String> {
+        matrix.validate()?;
+        self.tenants.insert(tenant.to_string(), matrix);
+        Ok(())
+    }
+
+    pub fn remove(&self, tenant: &str) -> bool {
+        self.tenants.remove(tenant).is_some()
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// Changing who may sign needs a role granted `*` in the access policy.
+fn authorize(state: &AppState, headers: &HeaderMap) -> Result<String, StatusCode> {
+    let role = headers
+        .get(ROLE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or(StatusCode::FORBIDDEN)?;
+    let full_access = state
+        .access_policy
+        .roles
+        .get(role)
+        .is_some_and(|paths| paths.iter().any(|p| p == "*"));
+    if !full_access {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(role.to_string())
+}
+
+pub async fn get_matrix(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<AuthorityResponse>, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    let matrix = state.authority.get(&tenant).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(AuthorityResponse { tenant, matrix }))
+}
+
+pub async fn put_matrix(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(matrix): Json<AuthorityMatrix>,
+) -> Result<Json<AuthorityResponse>, Response> {
+    let role = authorize(&state, &headers).map_err(IntoResponse::into_response)?;
+    let tenant = notifier::tenant(&headers);
+    if let Err(e) = state.authority.put(&tenant, matrix.clone()) {
+        info!(tenant = %tenant, error = %e, "authority matrix rejected");
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({ "error": e })),
+        )
+            .into_response());
+    }
+    info!(tenant = %tenant, signers = matrix.signers.len(), "authority matrix saved");
+    state.record_audit(
+        "signing_authority.updated",
+        &tenant,
+        Some(&role),
+        json!({ "signers": matrix.signers.len() }),
+    );
+    Ok(Json(AuthorityResponse { tenant, matrix }))
+}
+
+pub async fn delete_matrix(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    let role = authorize(&state, &headers)?;
+    let tenant = notifier::tenant(&headers);
+    if !state.authority.remove(&tenant) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    state.record_audit("signing_authority.removed", &tenant, Some(&role), json!({}));
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer(name: &str, max: Option<f64>, reports_to: Option<&str>) -> Signer {
+        Signer {
+            name: name.to_string(),
+            title: None,
+            entity: "Acme Corp".to_string(),
+            max_value: max,
+            contract_types: Vec::new(),
+            reports_to: reports_to.map(str::to_string),
+        }
+    }
+
+    fn matrix() -> AuthorityMatrix {
+        AuthorityMatrix {
+            signers: vec![
+                Signer {
+                    contract_types: vec!["services".to_string()],
+                    ..signer("Sam Lee", Some(50_000.0), Some("Jane Roe"))
+                },
+                Signer {
+                    title: Some("CFO".to_string()),
+                    ..signer("Jane Roe", Some(1_000_000.0), Some("Ann Poe"))
+                },
+                signer("Ann Poe", None, None),
+            ],
+        }
+    }
+
+    fn signatory(name: &str, entity: &str) -> Signatory {
+        Signatory {
+            name: name.to_string(),
+            entity: entity.to_string(),
+        }
+    }
+
+    #[test]
+    fn deal_terms_come_from_the_text() {
+        let text = "\n  MASTER SERVICES AGREEMENT\nFees: USD 12,500.00 per month, \
+                    capped at $150,000. Deposit EUR 900.";
+        assert_eq!(largest_amount(text), Some(150_000.0));
+        assert_eq!(title(text), "MASTER SERVICES AGREEMENT");
+        assert_eq!(largest_amount("No fees."), None);
+    }
+
+    #[test]
+    fn violations_name_the_limit_and_who_to_escalate_to() {
+        let deal = Deal {
+            value: Some(120_000.0),
+            contract_type: "Master Services Agreement".to_string(),
+        };
+        let signatories = [
+            signatory("sam lee", "ACME Corp"),
+            signatory("Jane Roe", "Acme Corp"),
+            signatory("Max Mustermann", "Acme Corp"),
+            signatory("Erika Muster", "Beta GmbH"),
+        ];
+        assert_eq!(
+            matrix().violations(&signatories, &deal),
+            [
+                "sam lee may sign up to 50000 for Acme Corp, but the contract is worth 120000; \
+                 escalate to Jane Roe (CFO)",
+                "Max Mustermann has no signing authority for Acme Corp",
+            ]
+        );
+
+        let nda = Deal {
+            value: Some(0.0),
+            contract_type: "Mutual NDA".to_string(),
+        };
+        let found = matrix().violations(&[signatory("Sam Lee", "Acme Corp")], &nda);
+        assert!(
+            found[0].contains("may only sign services contracts"),
+            "{found:?}"
+        );
+        let unknown = Deal { value: None, ..nda };
+        let found = matrix().violations(&[signatory("Jane Roe", "Acme Corp")], &unknown);
+        assert!(
+            found[0].ends_with("unknown; escalate to Ann Poe"),
+            "{found:?}"
+        );
+    }
+
+    #[test]
+    fn invalid_matrices_are_rejected() {
+        assert!(matrix().validate().is_ok());
+        assert!(AuthorityMatrix::default().validate().is_err());
+
+        let mut dangling = matrix();
+        dangling.signers[2].reports_to = Some("Nobody".to_string());
+        assert!(dangling.validate().unwrap_err().contains("Nobody"));
+
+        let mut looped = matrix();
+        looped.signers[2].reports_to = Some("Sam Lee".to_string());
+        assert!(looped.validate().unwrap_err().contains("loops"));
+
+        let mut twice = matrix();
+        twice.signers.push(signer("ann poe", None, None));
+        assert!(twice.validate().is_err());
+    }
+}
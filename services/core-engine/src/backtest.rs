@@ -0,0 +1,449 @@
+//! Risk factor backtesting: tenants label past contracts with how they
+//! turned out, and the backtest measures how well each risk factor, and
+//! the overall score, separated the contracts that went wrong.
+
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{notifier, AppState};
+
+const MAX_LABELS: usize = 5_000;
+
+/// Share of a suggested weight that comes from the backtest rather than the
+/// current weight, so a small label set cannot swing the model entirely.
+const SUGGESTION_BLEND: f64 = 0.5;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// Ran its course without trouble.
+    None,
+    Disputed,
+    Renegotiated,
+    #[serde(alias = "written_off")]
+    WroteOff,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OutcomeLabel {
+    /// The tenant's own reference for the contract; labels with the same ID
+    /// replace each other.
+    pub contract_id: String,
+    /// Contract text, or else the ID of an analysis whose document is scored.
+    #[serde(default)]
+    pub document: Option<String>,
+    #[serde(default)]
+    pub analysis_id: Option<String>,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OutcomeUpload {
+    pub labels: Vec<OutcomeLabel>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FactorScore {
+    pub factor: String,
+    pub weight: f64,
+    pub score: f64,
+}
+
+/// A labeled contract. Only its factor scores are kept, not its text.
+#[derive(Debug, Clone, Serialize)]
+pub struct LabeledContract {
+    pub contract_id: String,
+    pub outcome: Outcome,
+    pub labeled_at: DateTime<Utc>,
+    pub factors: Vec<FactorScore>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OutcomeList {
+    pub tenant: String,
+    pub labels: Vec<LabeledContract>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct BacktestRequest {
+    /// Outcomes that count as the contract having gone wrong; every outcome
+    /// other than `none` when omitted.
+    #[serde(default)]
+    pub adverse: Option<Vec<Outcome>>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Separation {
+    /// Chance that an adverse contract scores above a clean one; 0.5 is no
+    /// better than guessing.
+    pub auc: f64,
+    pub mean_adverse: f64,
+    pub mean_other: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FactorBacktest {
+    pub factor: String,
+    #[serde(flatten)]
+    pub separation: Separation,
+    pub current_weight: f64,
+    pub suggested_weight: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BacktestReport {
+    pub tenant: String,
+    pub label_count: usize,
+    pub adverse_count: usize,
+    pub overall: Separation,
+    pub factors: Vec<FactorBacktest>,
+    /// AUC of the overall score under the suggested weights, measured on the
+    /// same labels, so optimistic.
+    pub suggested_auc: f64,
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+/// Labeled contracts per tenant, by contract ID.
+#[derive(Default)]
+pub struct OutcomeStore {
+    tenants: DashMap<String, BTreeMap<String, LabeledContract>>,
+}
+
+impl OutcomeStore {
+    pub fn put(&self, tenant: &str, labels: Vec<LabeledContract>) -> usize {
+        let mut entry = self.tenants.entry(tenant.to_string()).or_default();
+        for label in labels {
+            entry.insert(label.contract_id.clone(), label);
+        }
+        entry.len()
+    }
+
+    pub fn list(&self, tenant: &str) -> Vec<LabeledContract> {
+        self.tenants
+            .get(tenant)
+            .map(|m| m.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Drops the tenant's labels, returning how many there were.
+    pub fn remove(&self, tenant: &str) -> usize {
+        self.tenants.remove(tenant).map_or(0, |(_, m)| m.len())
+    }
+}
+
+// ── Metrics ───────────────────────────────────────────────────────────────────
+
+/// Area under the ROC curve via the Mann-Whitney statistic, with tied
+/// scores sharing their average rank. 0.5 when either class is empty.
+#[allow(clippy::cast_precision_loss)]
+pub fn auc(points: &[(f64, bool)]) -> f64 {
+    let positives = points.iter().filter(|p| p.1).count();
+    let negatives = points.len() - positives;
+    if positives == 0 || negatives == 0 {
+        return 0.5;
+    }
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut rank_sum = 0.0;
+    let mut i = 0;
+    while i < sorted.len() {
+        let j = i + sorted[i..]
+            .iter()
+            .take_while(|p| p.0 == sorted[i].0)
+            .count();
+        // Ranks i+1..=j averaged over the tie group.
+        let rank = (i + 1 + j) as f64 / 2.0;
+        rank_sum += rank * sorted[i..j].iter().filter(|p| p.1).count() as f64;
+        i = j;
+    }
+    let (p, n) = (positives as f64, negatives as f64);
+    (rank_sum - p * (p + 1.0) / 2.0) / (p * n)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn separation(points: &[(f64, bool)]) -> Separation {
+    let mean = |adverse: bool| {
+        let scores: Vec<f64> = points
+            .iter()
+            .filter(|p| p.1 == adverse)
+            .map(|p| p.0)
+            .collect();
+        scores.iter().sum::<f64>() / scores.len().max(1) as f64
+    };
+    Separation {
+        auc: auc(points),
+        mean_adverse: mean(true),
+        mean_other: mean(false),
+    }
+}
+
+fn overall(factors: &[FactorScore], weights: &[f64]) -> f64 {
+    factors.iter().zip(weights).map(|(f, w)| w * f.score).sum()
+}
+
+/// Moves each weight toward the factor's share of the predictive signal
+/// (AUC above 0.5), keeping the total. Factors that predict nothing, or
+/// predict backwards, drift toward zero.
+pub fn suggest_weights(current: &[f64], aucs: &[f64]) -> Vec<f64> {
+    let signal: Vec<f64> = aucs.iter().map(|a| (a - 0.5).max(0.0)).collect();
+    let total_signal: f64 = signal.iter().sum();
+    if total_signal == 0.0 {
+        return current.to_vec();
+    }
+    let total_weight: f64 = current.iter().sum();
+    current
+        .iter()
+        .zip(&signal)
+        .map(|(w, s)| {
+            let target = total_weight * s / total_signal;
+            w + SUGGESTION_BLEND * (target - w)
+        })
+        .collect()
+}
+
+pub fn backtest(
+    tenant: &str,
+    labels: &[LabeledContract],
+    adverse: &[Outcome],
+) -> Result<BacktestReport, String> {
+    let is_adverse: Vec<bool> = labels
+        .iter()
+        .map(|l| adverse.contains(&l.outcome))
+        .collect();
+    let adverse_count = is_adverse.iter().filter(|a| **a).count();
+    if adverse_count == 0 || adverse_count == labels.len() {
+        return Err("labels must include both adverse and other outcomes".into());
+    }
+    // Every label is scored by the same factor list, in the same order.
+    let template = &labels[0].factors;
+    let current: Vec<f64> = template.iter().map(|f| f.weight).collect();
+    let points = |score: &dyn Fn(&LabeledContract) -> f64| -> Vec<(f64, bool)> {
+        labels
+            .iter()
+            .zip(&is_adverse)
+            .map(|(l, a)| (score(l), *a))
+            .collect()
+    };
+
+    let per_factor: Vec<Separation> = (0..template.len())
+        .map(|i| separation(&points(&|l| l.factors[i].score)))
+        .collect();
+    let aucs: Vec<f64> = per_factor.iter().map(|s| s.auc).collect();
+    let suggested = suggest_weights(&current, &aucs);
+
+    Ok(BacktestReport {
+        tenant: tenant.to_string(),
+        label_count: labels.len(),
+        adverse_count,
+        overall: separation(&points(&|l| overall(&l.factors, &current))),
+        suggested_auc: auc(&points(&|l| overall(&l.factors, &suggested))),
+        factors: template
+            .iter()
+            .zip(per_factor)
+            .zip(suggested)
+            .map(|((f, separation), suggested_weight)| FactorBacktest {
+                factor: f.factor.clone(),
+                separation,
+                current_weight: f.weight,
+                suggested_weight,
+            })
+            .collect(),
+    })
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn upload_outcomes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(upload): Json<OutcomeUpload>,
+) -> Result<Json<OutcomeList>, StatusCode> {
+    if upload.labels.is_empty() || upload.labels.len() > MAX_LABELS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let tenant = notifier::tenant(&headers);
+    let now = state.clock.now();
+    let labeled = upload
+        .labels
+        .into_iter()
+        .map(|label| {
+            let text = match (&label.document, &label.analysis_id) {
+                (Some(text), None) if !text.trim().is_empty() => text.clone(),
+                // Only the tenant's own analyses can be labeled.
+                (None, Some(id)) => state
+                    .corpus
+                    .get(id)
+                    .filter(|d| d.tenant == tenant)
+                    .map(|d| d.text)
+                    .ok_or_else(|| format!("unknown analysis {id:?}"))?,
+                _ => return Err("each label needs either document or analysis_id".to_string()),
+            };
+            if label.contract_id.trim().is_empty() {
+                return Err("contract_id is required".to_string());
+            }
+            Ok(LabeledContract {
+                contract_id: label.contract_id,
+                outcome: label.outcome,
+                labeled_at: now,
+                factors: state
+                    .risk_model
+                    .factors(&text)
+                    .into_iter()
+                    .map(|f| FactorScore {
+                        factor: f.factor,
+                        weight: f.weight,
+                        score: f.score,
+                    })
+                    .collect(),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(|e| {
+            info!(tenant = %tenant, error = %e, "outcome labels rejected");
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?;
+
+    let uploaded = labeled.len();
+    let total = state.outcomes.put(&tenant, labeled);
+    info!(tenant = %tenant, uploaded, total, "outcome labels saved");
+    state.record_audit(
+        "outcomes.uploaded",
+        &tenant,
+        None,
+        serde_json::json!({ "uploaded": uploaded, "total": total }),
+    );
+    Ok(Json(OutcomeList {
+        labels: state.outcomes.list(&tenant),
+        tenant,
+    }))
+}
+
+pub async fn list_outcomes(State(state): State<AppState>, headers: HeaderMap) -> Json<OutcomeList> {
+    let tenant = notifier::tenant(&headers);
+    Json(OutcomeList {
+        labels: state.outcomes.list(&tenant),
+        tenant,
+    })
+}
+
+pub async fn delete_outcomes(State(state): State<AppState>, headers: HeaderMap) -> StatusCode {
+    if state.outcomes.remove(&notifier::tenant(&headers)) > 0 {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+pub async fn run_backtest(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<BacktestRequest>,
+) -> Result<Json<BacktestReport>, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    let labels = state.outcomes.list(&tenant);
+    if labels.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let adverse = req
+        .adverse
+        .unwrap_or_else(|| vec![Outcome::Disputed, Outcome::Renegotiated, Outcome::WroteOff]);
+    let report = backtest(&tenant, &labels, &adverse).map_err(|e| {
+        info!(tenant = %tenant, error = %e, "backtest rejected");
+        StatusCode::UNPROCESSABLE_ENTITY
+    })?;
+    info!(
+        tenant = %tenant,
+        labels = report.label_count,
+        adverse = report.adverse_count,
+        auc = report.overall.auc,
+        "risk factors backtested"
+    );
+    Ok(Json(report))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn auc_counts_ties_as_half() {
+        assert_eq!(auc(&[(0.9, true), (0.1, false)]), 1.0);
+        assert_eq!(auc(&[(0.1, true), (0.9, false)]), 0.0);
+        assert_eq!(auc(&[(0.5, true), (0.5, false)]), 0.5);
+        // Positives at 0.8 and 0.3 against negatives at 0.3 and 0.1:
+        // 0.8 beats both, 0.3 ties one and beats one.
+        let points = [(0.8, true), (0.3, true), (0.3, false), (0.1, false)];
+        assert_eq!(auc(&points), 0.875);
+        assert_eq!(auc(&[(0.4, true)]), 0.5);
+    }
+
+    #[test]
+    fn weights_shift_toward_predictive_factors_keeping_the_total() {
+        let suggested = suggest_weights(&[0.5, 0.5], &[1.0, 0.4]);
+        assert_eq!(suggested, [0.75, 0.25]);
+        assert_eq!(suggest_weights(&[0.3, 0.7], &[0.5, 0.2]), [0.3, 0.7]);
+    }
+
+    fn contract(id: &str, outcome: Outcome, text: &str) -> LabeledContract {
+        LabeledContract {
+            contract_id: id.to_string(),
+            outcome,
+            labeled_at: Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap(),
+            factors: crate::risk_model::RiskModel::default()
+                .factors(text)
+                .into_iter()
+                .map(|f| FactorScore {
+                    factor: f.factor,
+                    weight: f.weight,
+                    score: f.score,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn backtest_finds_the_factor_that_separates_outcomes() {
+        let labels = [
+            contract(
+                "c1",
+                Outcome::Disputed,
+                "Each party shall indemnify the other.",
+            ),
+            contract("c2", Outcome::WroteOff, "Supplier will indemnify Customer."),
+            contract("c3", Outcome::None, "Fees are due monthly."),
+            contract("c4", Outcome::None, "Either party may terminate."),
+        ];
+        let adverse = [Outcome::Disputed, Outcome::WroteOff];
+        let report = backtest("acme", &labels, &adverse).unwrap();
+        assert_eq!((report.label_count, report.adverse_count), (4, 2));
+        let by_name: BTreeMap<&str, &FactorBacktest> = report
+            .factors
+            .iter()
+            .map(|f| (f.factor.as_str(), f))
+            .collect();
+        let indemnity = by_name["Indemnification"];
+        assert_eq!(indemnity.separation.auc, 1.0);
+        assert!(indemnity.suggested_weight > indemnity.current_weight);
+        assert!(by_name["Liability Clauses"].suggested_weight < 0.30);
+        let total: f64 = report.factors.iter().map(|f| f.suggested_weight).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!(report.suggested_auc >= report.overall.auc);
+
+        assert!(backtest("acme", &labels[2..], &adverse).is_err());
+    }
+}
@@ -0,0 +1,542 @@
+//! Portfolio analysis: up to `LEGAL_BATCH_MAX_DOCUMENTS` contracts in one
+//! request, sent as JSON or as a ZIP of text, PDF and DOCX files. Documents
+//! are analyzed concurrently on the blocking pool, at most
+//! `LEGAL_BATCH_CONCURRENCY` at a time across all batches on the instance,
+//! and the response adds portfolio aggregates to the per-document results.
+//! One unreadable or failing document does not fail the batch.
+
+use std::{
+    collections::BTreeMap,
+    io::{Cursor, Read},
+    sync::Arc,
+};
+
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    response::Json,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+use crate::{
+    deadline::{AnalysisMode, Deadline},
+    evidence::EvidenceOptions,
+    inflight::Origin,
+    ingest::{docx_text, pdf_text},
+    isolation::{self, Site},
+    notifier, run_analysis,
+    warmup::KeywordMatcher,
+    webhooks, AnalysisOptions, AnalyzeResponse, AppState,
+};
+
+pub const ZIP_CONTENT_TYPE: &str = "application/zip";
+/// Documents listed under `worst_documents`.
+const WORST_DOCUMENTS: usize = 5;
+/// Issues listed under `common_issues`.
+const COMMON_ISSUES: usize = 10;
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    pub max_documents: usize,
+    /// Analyses running at once, shared by every batch on the instance.
+    pub concurrency: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            max_documents: 300,
+            concurrency: 8,
+        }
+    }
+}
+
+impl BatchConfig {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        let var = |key: &str, fallback: usize| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(fallback)
+        };
+        Self {
+            max_documents: var("LEGAL_BATCH_MAX_DOCUMENTS", d.max_documents),
+            concurrency: var("LEGAL_BATCH_CONCURRENCY", d.concurrency),
+        }
+    }
+}
+
+/// The batch settings and the slots batch analyses run in.
+#[derive(Debug)]
+pub struct BatchPool {
+    pub config: BatchConfig,
+    slots: Arc<Semaphore>,
+}
+
+impl BatchPool {
+    pub fn new(config: BatchConfig) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(config.concurrency)),
+            config,
+        }
+    }
+
+    pub fn free_slots(&self) -> usize {
+        self.slots.available_permits()
+    }
+}
+
+impl Default for BatchPool {
+    fn default() -> Self {
+        Self::new(BatchConfig::default())
+    }
+}
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct BatchDocument {
+    /// Echoed in the results; defaults to the document's position.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub document: String,
+    /// Overrides the batch language for this document.
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchRequest {
+    pub documents: Vec<BatchDocument>,
+    #[serde(default = "default_language")]
+    pub language: String,
+    #[serde(default)]
+    pub mode: AnalysisMode,
+    #[serde(default)]
+    pub analysis_options: EvidenceOptions,
+}
+
+/// Settings for a ZIP upload, which has no JSON body to carry them.
+#[derive(Debug, Deserialize)]
+pub struct BatchQuery {
+    #[serde(default = "default_language")]
+    language: String,
+    #[serde(default)]
+    mode: AnalysisMode,
+    #[serde(default)]
+    evidence_context_chars: Option<usize>,
+    #[serde(default)]
+    fallback_clauses: bool,
+}
+
+fn default_language() -> String {
+    "en".into()
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchResult {
+    pub index: usize,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<AnalyzeResponse>,
+    /// Why the document has no analysis.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RankedDocument {
+    pub index: usize,
+    pub name: String,
+    pub analysis_id: String,
+    pub risk_score: f64,
+    pub high_severity_issues: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommonIssue {
+    pub description: String,
+    pub severity: String,
+    /// Documents the issue was found in.
+    pub documents: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Portfolio {
+    pub document_count: usize,
+    pub analyzed: usize,
+    pub failed: usize,
+    /// Mean risk score of the analyzed documents; 0 when none were.
+    pub average_risk: f64,
+    /// Highest risk first.
+    pub worst_documents: Vec<RankedDocument>,
+    /// Found in the most documents first.
+    pub common_issues: Vec<CommonIssue>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchResponse {
+    pub portfolio: Portfolio,
+    pub documents: Vec<BatchResult>,
+}
+
+/// A batch entry before analysis: its text, or why there is none.
+struct Entry {
+    name: String,
+    language: String,
+    text: Result<String, String>,
+}
+
+/// What every document of one batch is analyzed with.
+#[derive(Clone)]
+struct Settings {
+    deadline: Deadline,
+    mode: AnalysisMode,
+    context_chars: usize,
+    fallback_clauses: bool,
+    keywords: Option<Arc<KeywordMatcher>>,
+    tenant: String,
+}
+
+// ── ZIP ───────────────────────────────────────────────────────────────────────
+
+/// Folders, hidden files and the metadata macOS adds to archives.
+fn skipped(name: &str) -> bool {
+    let file = name.rsplit('/').next().unwrap_or(name);
+    name.ends_with('/') || name.starts_with("__MACOSX/") || file.starts_with('.')
+}
+
+/// Text of one archive member, by its extension.
+fn member_text(name: &str, bytes: &[u8]) -> Result<String, String> {
+    let lower = name.to_ascii_lowercase();
+    match lower.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("txt" | "md") => String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string()),
+        Some("pdf") => pdf_text(bytes),
+        Some("docx") => docx_text(bytes),
+        _ => Err("unsupported file type; expected .txt, .md, .pdf or .docx".to_string()),
+    }
+}
+
+/// The archive's documents in archive order. Members are read up to
+/// `max_bytes` each, so a compressed bomb cannot exhaust memory.
+fn zip_entries(
+    body: &[u8],
+    language: &str,
+    max_documents: usize,
+    max_bytes: usize,
+) -> Result<Vec<Entry>, StatusCode> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(body)).map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let mut member = archive
+            .by_index(i)
+            .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+        let name = member.name().to_string();
+        if skipped(&name) {
+            continue;
+        }
+        if entries.len() == max_documents {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        let mut bytes = Vec::new();
+        let read = member
+            .by_ref()
+            .take(max_bytes as u64 + 1)
+            .read_to_end(&mut bytes);
+        let text = match read {
+            Err(e) => Err(e.to_string()),
+            Ok(_) if bytes.len() > max_bytes => Err(format!("larger than {max_bytes} bytes")),
+            Ok(_) => member_text(&name, &bytes),
+        };
+        entries.push(Entry {
+            name,
+            language: language.to_string(),
+            text,
+        });
+    }
+    Ok(entries)
+}
+
+// ── Aggregates ────────────────────────────────────────────────────────────────
+
+#[allow(clippy::cast_precision_loss)]
+pub fn portfolio(results: &[BatchResult]) -> Portfolio {
+    let analyzed: Vec<(&BatchResult, &AnalyzeResponse)> = results
+        .iter()
+        .filter_map(|r| r.analysis.as_ref().map(|a| (r, a)))
+        .collect();
+    let average_risk = if analyzed.is_empty() {
+        0.0
+    } else {
+        analyzed.iter().map(|(_, a)| a.risk_score).sum::<f64>() / analyzed.len() as f64
+    };
+
+    let mut worst: Vec<RankedDocument> = analyzed
+        .iter()
+        .map(|(r, a)| RankedDocument {
+            index: r.index,
+            name: r.name.clone(),
+            analysis_id: a.analysis_id.clone(),
+            risk_score: a.risk_score,
+            high_severity_issues: a.issues.iter().filter(|i| i.severity == "high").count(),
+        })
+        .collect();
+    worst.sort_by(|a, b| {
+        b.risk_score
+            .total_cmp(&a.risk_score)
+            .then(b.high_severity_issues.cmp(&a.high_severity_issues))
+            .then(a.index.cmp(&b.index))
+    });
+    worst.truncate(WORST_DOCUMENTS);
+
+    // Counted once per document, however often a document repeats it.
+    let mut issues: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for (_, a) in &analyzed {
+        let mut seen: Vec<(&str, &str)> = a
+            .issues
+            .iter()
+            .map(|i| (i.description.as_str(), i.severity.as_str()))
+            .collect();
+        seen.sort_unstable();
+        seen.dedup();
+        for key in seen {
+            *issues.entry(key).or_default() += 1;
+        }
+    }
+    let mut common: Vec<CommonIssue> = issues
+        .into_iter()
+        .map(|((description, severity), documents)| CommonIssue {
+            description: description.to_string(),
+            severity: severity.to_string(),
+            documents,
+        })
+        .collect();
+    common.sort_by_key(|c| std::cmp::Reverse(c.documents));
+    common.truncate(COMMON_ISSUES);
+
+    Portfolio {
+        document_count: results.len(),
+        analyzed: analyzed.len(),
+        failed: results.len() - analyzed.len(),
+        average_risk,
+        worst_documents: worst,
+        common_issues: common,
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// Body is a JSON `BatchRequest`, or with `Content-Type: application/zip`
+/// an archive whose settings come from the query string.
+pub async fn analyze_batch(
+    State(state): State<AppState>,
+    Extension(deadline): Extension<Deadline>,
+    Query(query): Query<BatchQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<BatchResponse>, StatusCode> {
+    let max_documents = state.batches.config.max_documents;
+    let is_zip = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with(ZIP_CONTENT_TYPE));
+    let (entries, mode, options) = if is_zip {
+        let entries = zip_entries(
+            &body,
+            &query.language,
+            max_documents,
+            state.ingest.max_bytes,
+        )?;
+        let options = EvidenceOptions {
+            evidence_context_chars: query.evidence_context_chars,
+            fallback_clauses: query.fallback_clauses,
+        };
+        (entries, query.mode, options)
+    } else {
+        let req: BatchRequest =
+            serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+        if req.documents.len() > max_documents {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        let entries = req
+            .documents
+            .into_iter()
+            .map(|d| Entry {
+                name: d.name.unwrap_or_default(),
+                language: d.language.unwrap_or_else(|| req.language.clone()),
+                text: Ok(d.document),
+            })
+            .collect();
+        (entries, req.mode, req.analysis_options)
+    };
+    if entries.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let tenant = notifier::tenant(&headers);
+    let settings = Settings {
+        deadline,
+        mode,
+        context_chars: options.context_chars()?,
+        fallback_clauses: options.fallback_clauses,
+        keywords: state.lexicons.matcher(&tenant),
+        tenant,
+    };
+    let runs = entries.into_iter().enumerate().map(|(index, entry)| {
+        let state = state.clone();
+        let settings = settings.clone();
+        async move {
+            let name = if entry.name.is_empty() {
+                format!("document-{}", index + 1)
+            } else {
+                entry.name
+            };
+            let analyzed = match entry.text {
+                Ok(text) if text.trim().is_empty() => Err("empty document".to_string()),
+                Ok(text) => analyze_one(state, settings, text, entry.language).await,
+                Err(e) => Err(format!("unreadable: {e}")),
+            };
+            let (analysis, error) = match analyzed {
+                Ok(analysis) => (Some(analysis), None),
+                Err(e) => (None, Some(e)),
+            };
+            BatchResult {
+                index,
+                name,
+                analysis,
+                error,
+            }
+        }
+    });
+    let documents = futures_util::future::join_all(runs).await;
+    let portfolio = portfolio(&documents);
+    info!(
+        tenant = %settings.tenant,
+        documents = portfolio.document_count,
+        failed = portfolio.failed,
+        average_risk = portfolio.average_risk,
+        "batch analyzed"
+    );
+    for analysis in documents.iter().filter_map(|d| d.analysis.as_ref()) {
+        webhooks::risk_scored(&state, &settings.tenant, analysis);
+    }
+    webhooks::batch_completed(&state, &settings.tenant, &portfolio);
+    Ok(Json(BatchResponse {
+        portfolio,
+        documents,
+    }))
+}
+
+/// One document on the blocking pool, once a batch slot is free. Batch
+/// documents are analyzed with the heuristics only.
+async fn analyze_one(
+    state: AppState,
+    settings: Settings,
+    text: String,
+    language: String,
+) -> Result<AnalyzeResponse, String> {
+    let slots = Arc::clone(&state.batches.slots);
+    let _permit = slots
+        .acquire_owned()
+        .await
+        .map_err(|_| "batch pool closed".to_string())?;
+    let analyzed = tokio::task::spawn_blocking(move || {
+        let opts = AnalysisOptions {
+            mode: settings.mode,
+            deadline: settings.deadline,
+            context_chars: settings.context_chars,
+            fallback_clauses: settings.fallback_clauses,
+            cancel: None,
+            latency: None,
+            keywords: settings.keywords.as_deref(),
+            tenant: &settings.tenant,
+            ml: None,
+            model_call: None,
+            progress: None,
+            origin: Origin::Batch,
+            our_party: None,
+        };
+        isolation::contain(&state, Site::Analysis, || {
+            run_analysis(&state, &text, &language, opts)
+        })
+    })
+    .await;
+    match analyzed {
+        Ok(Ok(analysis)) => Ok(analysis),
+        Ok(Err(panicked)) => Err(format!(
+            "{} (error id {})",
+            panicked.error, panicked.error_id
+        )),
+        Err(e) => {
+            warn!(error = %e, "batch analysis worker failed");
+            Err("analysis worker failed".to_string())
+        }
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, bytes) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(bytes).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn zip_members_are_read_by_extension() {
+        let body = zip(&[
+            (
+                "contracts/msa.txt",
+                b"This Agreement is governed by Ohio law.",
+            ),
+            ("__MACOSX/contracts/._msa.txt", b"junk"),
+            ("contracts/.DS_Store", b"junk"),
+            ("contracts/logo.png", b"\x89PNG"),
+        ]);
+        let entries = zip_entries(&body, "en", 10, 1024).unwrap();
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["contracts/msa.txt", "contracts/logo.png"]);
+        assert_eq!(
+            entries[0].text.as_deref(),
+            Ok("This Agreement is governed by Ohio law.")
+        );
+        assert!(entries[1]
+            .text
+            .as_ref()
+            .unwrap_err()
+            .contains("unsupported"));
+    }
+
+    #[test]
+    fn zip_limits_members_and_their_size() {
+        let body = zip(&[("a.txt", b"first"), ("b.txt", b"second document")]);
+        assert_eq!(
+            zip_entries(&body, "en", 1, 1024).err(),
+            Some(StatusCode::PAYLOAD_TOO_LARGE)
+        );
+        let entries = zip_entries(&body, "en", 10, 8).unwrap();
+        assert!(entries[0].text.is_ok());
+        assert_eq!(entries[1].text.as_ref().unwrap_err(), "larger than 8 bytes");
+        assert_eq!(
+            zip_entries(b"not a zip", "en", 10, 8).err(),
+            Some(StatusCode::UNPROCESSABLE_ENTITY)
+        );
+    }
+}
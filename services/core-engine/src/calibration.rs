@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::AppState;
+
+const MAX_SAMPLES: usize = 5_000;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct LabeledSample {
+    pub document: String,
+    /// Clause types a reviewer confirmed are present in `document`.
+    pub clause_types: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CalibrationRequest {
+    pub samples: Vec<LabeledSample>,
+    #[serde(default = "default_bins")]
+    pub bins: usize,
+    /// When set, suggest the threshold with the best recall at or above this
+    /// precision; otherwise maximize F1.
+    pub min_precision: Option<f64>,
+}
+
+fn default_bins() -> usize {
+    10
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CurveBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub mean_confidence: f64,
+    pub observed_rate: f64,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct OperatingPoint {
+    pub threshold: f64,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClauseTypeCalibration {
+    pub clause_type: String,
+    pub positives: usize,
+    pub brier_score: f64,
+    pub curve: Vec<CurveBin>,
+    pub operating_points: Vec<OperatingPoint>,
+    pub suggested_threshold: Option<f64>,
+    pub expected_precision: Option<f64>,
+    pub expected_recall: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CalibrationResponse {
+    pub sample_count: usize,
+    pub clause_types: Vec<ClauseTypeCalibration>,
+}
+
+// ── Metrics ───────────────────────────────────────────────────────────────────
+
+/// `(confidence, actually_present)` pairs for one clause type.
+type Points = Vec<(f64, bool)>;
+
+#[allow(clippy::cast_precision_loss)]
+pub fn calibration_curve(points: &[(f64, bool)], bins: usize) -> Vec<CurveBin> {
+    let bins = bins.max(1);
+    let mut buckets: Vec<Vec<(f64, bool)>> = vec![Vec::new(); bins];
+    for &(conf, label) in points {
+        let idx = ((conf.clamp(0.0, 1.0) * bins as f64) as usize).min(bins - 1);
+        buckets[idx].push((conf, label));
+    }
+    buckets
+        .into_iter()
+        .enumerate()
+        .filter(|(_, b)| !b.is_empty())
+        .map(|(i, b)| {
+            let n = b.len() as f64;
+            CurveBin {
+                lower: i as f64 / bins as f64,
+                upper: (i + 1) as f64 / bins as f64,
+                mean_confidence: b.iter().map(|p| p.0).sum::<f64>() / n,
+                observed_rate: b.iter().filter(|p| p.1).count() as f64 / n,
+                count: b.len(),
+            }
+        })
+        .collect()
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub fn brier_score(points: &[(f64, bool)]) -> f64 {
+    if points.is_empty() {
+        return 0.0;
+    }
+    points
+        .iter()
+        .map(|&(c, l)| (c - if l { 1.0 } else { 0.0 }).powi(2))
+        .sum::<f64>()
+        / points.len() as f64
+}
+
+/// One operating point per distinct non-zero confidence the detector emitted.
+#[allow(clippy::cast_precision_loss)]
+pub fn operating_points(points: &[(f64, bool)]) -> Vec<OperatingPoint> {
+    let mut thresholds: Vec<f64> = points.iter().map(|p| p.0).filter(|c| *c > 0.0).collect();
+    thresholds.sort_by(f64::total_cmp);
+    thresholds.dedup();
+
+    let positives = points.iter().filter(|p| p.1).count();
+    thresholds
+        .into_iter()
+        .map(|t| {
+            let tp = points.iter().filter(|p| p.0 >= t && p.1).count();
+            let predicted = points.iter().filter(|p| p.0 >= t).count();
+            let precision = if predicted == 0 {
+                0.0
+            } else {
+                tp as f64 / predicted as f64
+            };
+            let recall = if positives == 0 {
+                0.0
+            } else {
+                tp as f64 / positives as f64
+            };
+            let f1 = if precision + recall == 0.0 {
+                0.0
+            } else {
+                2.0 * precision * recall / (precision + recall)
+            };
+            OperatingPoint {
+                threshold: t,
+                precision,
+                recall,
+                f1,
+            }
+        })
+        .collect()
+}
+
+pub fn suggest(points: &[OperatingPoint], min_precision: Option<f64>) -> Option<&OperatingPoint> {
+    match min_precision {
+        Some(min) => points.iter().filter(|p| p.precision >= min).max_by(|a, b| {
+            a.recall
+                .total_cmp(&b.recall)
+                .then(b.threshold.total_cmp(&a.threshold))
+        }),
+        None => points.iter().max_by(|a, b| {
+            a.f1.total_cmp(&b.f1)
+                .then(b.threshold.total_cmp(&a.threshold))
+        }),
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn evaluate(
+    State(state): State<AppState>,
+    Json(req): Json<CalibrationRequest>,
+) -> Result<Json<CalibrationResponse>, StatusCode> {
+    if req.samples.is_empty() || req.samples.len() > MAX_SAMPLES || req.bins == 0 || req.bins > 100
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // Clause types are compared case-insensitively; the detector's spelling wins.
+    let mut per_type: BTreeMap<String, (String, Points)> = BTreeMap::new();
+    let detections: Vec<Vec<(String, f64)>> = req
+        .samples
+        .iter()
+        .map(|s| {
+            // Only confidences are compared, so no excerpt context is needed.
+            state
+                .clause_extractor
+                .extract(state.precompiled.keywords(), &s.document, 0)
+                .into_iter()
+                .map(|c| (c.clause_type, c.confidence))
+                .collect()
+        })
+        .collect();
+    for det in &detections {
+        for (ct, _) in det {
+            per_type
+                .entry(ct.to_lowercase())
+                .or_insert_with(|| (ct.clone(), Vec::new()));
+        }
+    }
+    for sample in &req.samples {
+        for ct in &sample.clause_types {
+            per_type
+                .entry(ct.to_lowercase())
+                .or_insert_with(|| (ct.clone(), Vec::new()));
+        }
+    }
+
+    for (sample, det) in req.samples.iter().zip(&detections) {
+        for (key, (_, points)) in per_type.iter_mut() {
+            let confidence = det
+                .iter()
+                .filter(|(ct, _)| ct.to_lowercase() == *key)
+                .map(|(_, c)| *c)
+                .fold(0.0, f64::max);
+            let present = sample
+                .clause_types
+                .iter()
+                .any(|ct| ct.to_lowercase() == *key);
+            points.push((confidence, present));
+        }
+    }
+
+    let clause_types: Vec<ClauseTypeCalibration> = per_type
+        .into_values()
+        .map(|(clause_type, points)| {
+            let ops = operating_points(&points);
+            let best = suggest(&ops, req.min_precision).cloned();
+            ClauseTypeCalibration {
+                clause_type,
+                positives: points.iter().filter(|p| p.1).count(),
+                brier_score: brier_score(&points),
+                curve: calibration_curve(&points, req.bins),
+                suggested_threshold: best.as_ref().map(|b| b.threshold),
+                expected_precision: best.as_ref().map(|b| b.precision),
+                expected_recall: best.as_ref().map(|b| b.recall),
+                operating_points: ops,
+            }
+        })
+        .collect();
+
+    info!(
+        samples = req.samples.len(),
+        clause_types = clause_types.len(),
+        "calibration evaluated"
+    );
+
+    Ok(Json(CalibrationResponse {
+        sample_count: req.samples.len(),
+        clause_types,
+    }))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curve_groups_by_confidence_bin() {
+        let points = vec![(0.9, true), (0.95, false), (0.35, false), (0.3, true)];
+        let curve = calibration_curve(&points, 10);
+        assert_eq!(curve.len(), 2);
+        assert_eq!(curve[0].count, 2);
+        assert!((curve[0].observed_rate - 0.5).abs() < 1e-9);
+        assert!((curve[1].mean_confidence - 0.925).abs() < 1e-9);
+    }
+
+    #[test]
+    fn confidence_of_one_lands_in_last_bin() {
+        let curve = calibration_curve(&[(1.0, true)], 4);
+        assert_eq!(curve[0].upper, 1.0);
+    }
+
+    #[test]
+    fn brier_score_perfect_and_worst() {
+        assert_eq!(brier_score(&[(1.0, true), (0.0, false)]), 0.0);
+        assert_eq!(brier_score(&[(0.0, true), (1.0, false)]), 1.0);
+    }
+
+    #[test]
+    fn suggestion_honours_min_precision() {
+        let points = vec![
+            (0.9, true),
+            (0.9, true),
+            (0.35, true),
+            (0.35, false),
+            (0.35, false),
+        ];
+        let ops = operating_points(&points);
+        assert_eq!(ops.len(), 2);
+
+        let strict = suggest(&ops, Some(0.9)).unwrap();
+        assert_eq!(strict.threshold, 0.9);
+        assert_eq!(strict.precision, 1.0);
+
+        let lenient = suggest(&ops, Some(0.5)).unwrap();
+        assert_eq!(lenient.threshold, 0.35);
+        assert_eq!(lenient.recall, 1.0);
+
+        assert!(suggest(&ops, Some(1.1)).is_none());
+    }
+}
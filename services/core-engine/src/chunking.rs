@@ -0,0 +1,263 @@
+//! Chunked classification for very large contracts. Above a size threshold
+//! the document is cut into chunks at section headings (or, inside an
+//! oversized section, at paragraph and line breaks), each chunk is
+//! classified on its own worker thread, and the clauses are merged back as
+//! the whole-document pass would report them, positioned in the full
+//! document. A 1 MB contract then uses every core instead of one.
+
+use std::{
+    ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{extraction::sections, quota, reposition, warmup::KeywordMatcher, AppState, Clause};
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct ChunkingConfig {
+    /// Documents at least this many bytes long are classified in chunks.
+    pub threshold_bytes: usize,
+    /// Target chunk size; a chunk ends at the last boundary before it.
+    pub chunk_bytes: usize,
+    /// Threads classifying the chunks of one document.
+    pub workers: usize,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            threshold_bytes: 256 * 1024,
+            chunk_bytes: 64 * 1024,
+            workers: std::thread::available_parallelism().map_or(4, |n| n.get().min(8)),
+        }
+    }
+}
+
+impl ChunkingConfig {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        let var = |name: &str, default: usize| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(default)
+        };
+        Self {
+            threshold_bytes: var("LEGAL_CHUNK_THRESHOLD_BYTES", d.threshold_bytes),
+            chunk_bytes: var("LEGAL_CHUNK_BYTES", d.chunk_bytes),
+            workers: var("LEGAL_CHUNK_WORKERS", d.workers),
+        }
+    }
+
+    pub fn applies(&self, document_bytes: usize) -> bool {
+        document_bytes >= self.threshold_bytes
+    }
+}
+
+// ── Splitting ─────────────────────────────────────────────────────────────────
+
+/// Byte ranges covering `document` in order, each at most `target` bytes
+/// unless a single line is longer. Cuts fall on the last section heading
+/// before the limit, else the last blank line, else the last line break.
+pub fn split(document: &str, target: usize) -> Vec<Range<usize>> {
+    let headings: Vec<usize> = sections(document).iter().map(|s| s.start).collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while document.len() - start > target {
+        let limit = start + target;
+        let window = floor_char_boundary(document, limit);
+        let heading = headings
+            .iter()
+            .rev()
+            .find(|&&h| h > start && h <= limit)
+            .copied();
+        let paragraph = || document[start..window].rfind("\n\n").map(|i| start + i + 2);
+        let line = || document[start..window].rfind('\n').map(|i| start + i + 1);
+        let end = heading
+            .or_else(paragraph)
+            .or_else(line)
+            .filter(|&end| end > start)
+            .unwrap_or_else(|| {
+                // No break within the limit: end at the line's own break.
+                document[window..]
+                    .find('\n')
+                    .map_or(document.len(), |i| window + i + 1)
+            });
+        chunks.push(start..end);
+        start = end;
+    }
+    if start < document.len() || chunks.is_empty() {
+        chunks.push(start..document.len());
+    }
+    chunks
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+// ── Classification ────────────────────────────────────────────────────────────
+
+/// Clauses of `document`, and the number of chunks when it was classified
+/// in chunks.
+pub fn classify(
+    state: &AppState,
+    keywords: &KeywordMatcher,
+    document: &str,
+    context_chars: usize,
+) -> (Vec<Clause>, Option<usize>) {
+    let config = &state.chunking;
+    let ranges = if config.applies(document.len()) {
+        split(document, config.chunk_bytes)
+    } else {
+        Vec::new()
+    };
+    if ranges.len() < 2 {
+        return (
+            state
+                .clause_extractor
+                .extract(keywords, document, context_chars),
+            None,
+        );
+    }
+
+    let next = AtomicUsize::new(0);
+    let classify_next = || {
+        quota::peak_memory(|| {
+            let mut done = Vec::new();
+            while let Some(range) = ranges.get(next.fetch_add(1, Ordering::Relaxed)) {
+                let chunk = &document[range.clone()];
+                let mut clauses = state
+                    .clause_extractor
+                    .extract(keywords, chunk, context_chars);
+                reposition(document, &mut clauses, &mut [], |offset| {
+                    range.start + offset
+                });
+                done.push((range.clone(), clauses));
+            }
+            done
+        })
+    };
+    let mut done: Vec<(Range<usize>, Vec<Clause>)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..config.workers.min(ranges.len()))
+            .map(|_| scope.spawn(classify_next))
+            .collect();
+        let (mut done, mut held) = (Vec::new(), 0);
+        for worker in workers {
+            let (chunks, bytes) = worker
+                .join()
+                .unwrap_or_else(|e| std::panic::resume_unwind(e));
+            done.extend(chunks);
+            held += bytes;
+        }
+        // The workers ran side by side, so their peaks add up.
+        quota::held_by_workers(held);
+        done
+    });
+    done.sort_by_key(|(range, ..)| range.start);
+
+    (
+        state.clause_extractor.merge(document, done),
+        Some(ranges.len()),
+    )
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extraction;
+    use std::sync::Arc;
+
+    fn contract(sections: usize) -> String {
+        let mut text =
+            String::from("MASTER SERVICES AGREEMENT\n\nPreamble between the parties.\n\n");
+        for i in 1..=sections {
+            let body = match i % 4 {
+                0 => "The Supplier shall indemnify the Customer against all claims.",
+                1 => "This Agreement is governed by the laws of New York.",
+                2 => "Either party may terminate this Agreement on 30 days notice.",
+                _ => "Each party keeps the other's information confidential.",
+            };
+            text.push_str(&format!(
+                "{i}. Section {i}. {body}\n{}\n\n",
+                "Filler text. ".repeat(40)
+            ));
+        }
+        text
+    }
+
+    fn state(extractor: &str, chunk_bytes: usize) -> AppState {
+        AppState {
+            chunking: Arc::new(ChunkingConfig {
+                threshold_bytes: 1,
+                chunk_bytes,
+                workers: 3,
+            }),
+            clause_extractor: extraction::by_name(extractor).unwrap(),
+            ..AppState::in_memory()
+        }
+    }
+
+    #[test]
+    fn chunks_cover_the_document_and_start_at_headings() {
+        let doc = contract(12);
+        let ranges = split(&doc, 2_000);
+        assert!(ranges.len() > 3);
+        assert_eq!(ranges[0].start, 0);
+        assert_eq!(ranges.last().unwrap().end, doc.len());
+        for pair in ranges.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+            assert!(pair[0].len() <= 2_000);
+            assert!(
+                doc[pair[1].clone()].starts_with(|c: char| c.is_ascii_digit()),
+                "chunk should start at a heading: {:?}",
+                &doc[pair[1].start..pair[1].start + 20]
+            );
+        }
+
+        // Without headings, blank lines and then line breaks are used.
+        let prose = "word ".repeat(30) + "\n\n" + &"word ".repeat(30) + "\n" + &"word ".repeat(30);
+        let ranges = split(&prose, 200);
+        assert_eq!(ranges.iter().map(|r| r.len()).sum::<usize>(), prose.len());
+        assert!(prose[..ranges[0].end].ends_with("\n\n"));
+        assert_eq!(split("short", 200), vec![0..5]);
+    }
+
+    #[test]
+    fn chunked_classification_matches_the_single_pass() {
+        let doc = contract(16);
+        for extractor in ["keyword", "structure"] {
+            let whole = state(extractor, usize::MAX);
+            let chunked = state(extractor, 1_500);
+            let keywords = whole.precompiled.keywords();
+            let (clauses, chunks) = classify(&whole, keywords, &doc, 40);
+            assert_eq!(chunks, None);
+            assert!(clauses.len() >= 4, "{extractor}");
+            let (merged, chunks) = classify(&chunked, keywords, &doc, 40);
+            assert!(chunks.unwrap() > 4, "{extractor}");
+
+            let view = |c: &Clause| {
+                let p = c.position.as_ref().unwrap();
+                (
+                    c.id.clone(),
+                    c.clause_type.clone(),
+                    p.start,
+                    p.start_line,
+                    p.section.clone(),
+                )
+            };
+            assert_eq!(
+                merged.iter().map(view).collect::<Vec<_>>(),
+                clauses.iter().map(view).collect::<Vec<_>>(),
+                "{extractor}"
+            );
+        }
+    }
+}
@@ -0,0 +1,633 @@
+//! Approved standard clauses, filed by kind and attributes such as risk
+//! posture or jurisdiction. Templates embed them with
+//! `{{clause:limitation_of_liability|risk=conservative}}`, which compile
+//! resolves to the latest approved text of a clause of that kind carrying
+//! every attribute given, so templates pick up newly approved language
+//! without being edited. An edit is a draft until it is approved; until
+//! then compile keeps using the previously approved version. The library is
+//! shared by every tenant, so changing it needs an admin credential, and the
+//! approver recorded is the credential that approved.
+
+use std::{collections::BTreeMap, fmt, sync::RwLock};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use crate::{
+    auth::Principal,
+    warmup::{ParsedTemplate, Segment},
+    AppState,
+};
+
+/// Opens a clause reference inside `{{…}}`.
+pub const CLAUSE_PREFIX: &str = "clause:";
+const MAX_NAME_LEN: usize = 64;
+
+// ── References ────────────────────────────────────────────────────────────────
+
+/// `clause:kind|name=value|…` as written in a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClauseRef {
+    pub kind: String,
+    pub attributes: BTreeMap<String, String>,
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+impl ClauseRef {
+    /// Parses the inside of `{{clause:…}}`, prefix included.
+    pub fn parse(tag: &str) -> Result<Self, String> {
+        let malformed = || format!("malformed clause reference {{{{{tag}}}}}");
+        let mut parts = tag
+            .strip_prefix(CLAUSE_PREFIX)
+            .ok_or_else(malformed)?
+            .split('|')
+            .map(str::trim);
+        let kind = parts
+            .next()
+            .filter(|k| valid_name(k))
+            .ok_or_else(malformed)?;
+        let mut attributes = BTreeMap::new();
+        for part in parts {
+            let (name, value) = part
+                .split_once('=')
+                .map(|(n, v)| (n.trim(), v.trim()))
+                .filter(|(n, v)| valid_name(n) && !v.is_empty() && !v.contains('}'))
+                .ok_or_else(malformed)?;
+            if attributes
+                .insert(name.to_string(), value.to_string())
+                .is_some()
+            {
+                return Err(format!("{} repeats attribute {name}", malformed()));
+            }
+        }
+        Ok(Self {
+            kind: kind.to_string(),
+            attributes,
+        })
+    }
+}
+
+impl fmt::Display for ClauseRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{CLAUSE_PREFIX}{}", self.kind)?;
+        for (name, value) in &self.attributes {
+            write!(f, "|{name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClauseStatus {
+    Draft,
+    Approved,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClauseVersion {
+    pub version: u32,
+    pub text: String,
+    pub note: Option<String>,
+    pub status: ClauseStatus,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approved_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approved_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryClause {
+    pub id: String,
+    pub kind: String,
+    pub attributes: BTreeMap<String, String>,
+    pub versions: Vec<ClauseVersion>,
+}
+
+impl LibraryClause {
+    /// Why a clause brought in whole (from a tenant archive) cannot be
+    /// filed, if it cannot.
+    pub fn validate(&self) -> Result<(), String> {
+        if !valid_name(&self.kind) {
+            return Err(format!("invalid kind {:?}", self.kind));
+        }
+        check_attributes(&self.attributes)?;
+        if self.versions.is_empty() {
+            return Err("clause has no versions".to_string());
+        }
+        self.versions.iter().try_for_each(|v| check_text(&v.text))
+    }
+
+    /// The approved version compile embeds.
+    fn approved(&self) -> Option<&ClauseVersion> {
+        self.versions
+            .iter()
+            .rev()
+            .find(|v| v.status == ClauseStatus::Approved)
+    }
+
+    fn matches(&self, reference: &ClauseRef) -> bool {
+        self.kind == reference.kind
+            && reference
+                .attributes
+                .iter()
+                .all(|(name, value)| self.attributes.get(name) == Some(value))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClauseSummary {
+    pub id: String,
+    pub kind: String,
+    pub attributes: BTreeMap<String, String>,
+    pub latest_version: u32,
+    /// `None` until a version is approved.
+    pub approved_version: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClausesResponse {
+    pub clauses: Vec<ClauseSummary>,
+    pub count: usize,
+}
+
+/// A library clause a compiled document embeds, and which version.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct EmbeddedClause {
+    pub reference: String,
+    pub clause_id: String,
+    pub version: u32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ClauseQuery {
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewClauseRequest {
+    pub kind: String,
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+    pub text: String,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClauseUpdateRequest {
+    pub text: String,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApproveRequest {
+    /// The latest version when unset.
+    #[serde(default)]
+    pub version: Option<u32>,
+    /// Only read with authentication off; otherwise the credential's ID is
+    /// recorded as the approver.
+    #[serde(default)]
+    pub approved_by: Option<String>,
+}
+
+// ── Validation ────────────────────────────────────────────────────────────────
+
+/// Clause text may use the template's values but no macros, includes or
+/// other clauses.
+fn check_text(text: &str) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Err("text is required".to_string());
+    }
+    let parsed = ParsedTemplate::parse(text)?;
+    let partial = parsed
+        .segments
+        .iter()
+        .any(|s| matches!(s, Segment::Tag(t) if t.starts_with('>')));
+    if partial || !parsed.clauses().is_empty() {
+        return Err("clause text cannot use macros, includes or other clauses".to_string());
+    }
+    Ok(())
+}
+
+fn check_attributes(attributes: &BTreeMap<String, String>) -> Result<(), String> {
+    for (name, value) in attributes {
+        if !valid_name(name) {
+            return Err(format!("invalid attribute name {name:?}"));
+        }
+        if value.trim().is_empty() || value.contains(['|', '}']) || value.trim() != value {
+            return Err(format!("invalid value for attribute {name}"));
+        }
+    }
+    Ok(())
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct ClauseLibrary {
+    clauses: RwLock<BTreeMap<String, LibraryClause>>,
+}
+
+impl ClauseLibrary {
+    pub fn get(&self, id: &str) -> Option<LibraryClause> {
+        self.clauses.read().unwrap().get(id).cloned()
+    }
+
+    pub fn all(&self) -> Vec<LibraryClause> {
+        self.clauses.read().unwrap().values().cloned().collect()
+    }
+
+    /// Files `clause` with its versions as they are, replacing any clause
+    /// with its ID.
+    pub fn put(&self, clause: LibraryClause) {
+        self.clauses
+            .write()
+            .unwrap()
+            .insert(clause.id.clone(), clause);
+    }
+
+    pub fn list(&self, query: &ClauseQuery) -> Vec<ClauseSummary> {
+        self.clauses
+            .read()
+            .unwrap()
+            .values()
+            .filter(|c| query.kind.as_ref().is_none_or(|k| c.kind == *k))
+            .map(|c| ClauseSummary {
+                id: c.id.clone(),
+                kind: c.kind.clone(),
+                attributes: c.attributes.clone(),
+                latest_version: c.versions.last().map_or(0, |v| v.version),
+                approved_version: c.approved().map(|v| v.version),
+            })
+            .collect()
+    }
+
+    pub fn create(
+        &self,
+        id: String,
+        req: NewClauseRequest,
+        at: DateTime<Utc>,
+    ) -> Result<LibraryClause, String> {
+        if !valid_name(&req.kind) {
+            return Err(format!("invalid kind {:?}", req.kind));
+        }
+        check_attributes(&req.attributes)?;
+        check_text(&req.text)?;
+        let clause = LibraryClause {
+            id: id.clone(),
+            kind: req.kind,
+            attributes: req.attributes,
+            versions: vec![ClauseVersion {
+                version: 1,
+                text: req.text,
+                note: req.note,
+                status: ClauseStatus::Draft,
+                created_at: at,
+                approved_at: None,
+                approved_by: None,
+            }],
+        };
+        self.clauses.write().unwrap().insert(id, clause.clone());
+        Ok(clause)
+    }
+
+    /// Adds the next version as a draft; `Ok(None)` for an unknown clause.
+    pub fn update(
+        &self,
+        id: &str,
+        req: ClauseUpdateRequest,
+        at: DateTime<Utc>,
+    ) -> Result<Option<LibraryClause>, String> {
+        check_text(&req.text)?;
+        let mut clauses = self.clauses.write().unwrap();
+        let Some(clause) = clauses.get_mut(id) else {
+            return Ok(None);
+        };
+        let version = clause.versions.last().map_or(0, |v| v.version) + 1;
+        clause.versions.push(ClauseVersion {
+            version,
+            text: req.text,
+            note: req.note,
+            status: ClauseStatus::Draft,
+            created_at: at,
+            approved_at: None,
+            approved_by: None,
+        });
+        Ok(Some(clause.clone()))
+    }
+
+    /// `Ok(None)` for an unknown clause, `Err` for an unknown or already
+    /// approved version.
+    pub fn approve(
+        &self,
+        id: &str,
+        version: Option<u32>,
+        by: &str,
+        at: DateTime<Utc>,
+    ) -> Result<Option<LibraryClause>, String> {
+        let mut clauses = self.clauses.write().unwrap();
+        let Some(clause) = clauses.get_mut(id) else {
+            return Ok(None);
+        };
+        let target = match version {
+            Some(n) => clause.versions.iter_mut().find(|v| v.version == n),
+            None => clause.versions.last_mut(),
+        }
+        .ok_or_else(|| format!("unknown version {}", version.unwrap_or_default()))?;
+        if target.status == ClauseStatus::Approved {
+            return Err(format!("version {} is already approved", target.version));
+        }
+        target.status = ClauseStatus::Approved;
+        target.approved_at = Some(at);
+        target.approved_by = Some(by.to_string());
+        Ok(Some(clause.clone()))
+    }
+
+    /// The approved text `reference` stands for. Of several matching
+    /// clauses, the one approved most recently wins.
+    pub fn resolve(&self, reference: &ClauseRef) -> Option<(EmbeddedClause, String)> {
+        self.clauses
+            .read()
+            .unwrap()
+            .values()
+            .filter(|c| c.matches(reference))
+            .filter_map(|c| c.approved().map(|v| (c, v)))
+            .max_by_key(|(_, v)| v.approved_at)
+            .map(|(c, v)| {
+                let embedded = EmbeddedClause {
+                    reference: reference.to_string(),
+                    clause_id: c.id.clone(),
+                    version: v.version,
+                };
+                (embedded, v.text.clone())
+            })
+    }
+
+    /// Approved text for each reference, in order, or the first one that
+    /// has none.
+    pub fn resolve_all(
+        &self,
+        references: &[ClauseRef],
+    ) -> Result<(Vec<EmbeddedClause>, Vec<String>), String> {
+        references
+            .iter()
+            .map(|r| {
+                self.resolve(r)
+                    .ok_or_else(|| format!("no approved clause matches {{{{{r}}}}}"))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|resolved| resolved.into_iter().unzip())
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+fn rejected(error: String) -> Response {
+    info!(error = %error, "library clause rejected");
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(json!({ "error": error })),
+    )
+        .into_response()
+}
+
+pub async fn list(
+    State(state): State<AppState>,
+    Query(query): Query<ClauseQuery>,
+) -> Json<ClausesResponse> {
+    let clauses = state.clause_library.list(&query);
+    let count = clauses.len();
+    Json(ClausesResponse { clauses, count })
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<LibraryClause>, StatusCode> {
+    state
+        .clause_library
+        .get(&id)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    Json(req): Json<NewClauseRequest>,
+) -> Result<(StatusCode, Json<LibraryClause>), Response> {
+    let id = format!("cl-{}", state.ids.next_id());
+    let created = state
+        .clause_library
+        .create(id.clone(), req, state.clock.now())
+        .map_err(rejected)?;
+    info!(clause_id = %id, kind = %created.kind, "library clause created");
+    state.record_audit(
+        "clause_library.created",
+        &id,
+        None,
+        json!({ "kind": created.kind, "attributes": created.attributes }),
+    );
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+pub async fn update(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<ClauseUpdateRequest>,
+) -> Result<Json<LibraryClause>, Response> {
+    let updated = state
+        .clause_library
+        .update(&id, req, state.clock.now())
+        .map_err(rejected)?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+    let version = updated.versions.len();
+    info!(clause_id = %id, version, "library clause drafted");
+    state.record_audit(
+        "clause_library.drafted",
+        &id,
+        None,
+        json!({ "version": version }),
+    );
+    Ok(Json(updated))
+}
+
+pub async fn approve(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+    Json(req): Json<ApproveRequest>,
+) -> Result<Json<LibraryClause>, Response> {
+    let approver = match &principal {
+        Some(Extension(p)) => p.key_id.as_str(),
+        None => req
+            .approved_by
+            .as_deref()
+            .map(str::trim)
+            .unwrap_or_default(),
+    };
+    if approver.is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+    let approved = state
+        .clause_library
+        .approve(&id, req.version, approver, state.clock.now())
+        .map_err(rejected)?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+    let version = approved.approved().map(|v| v.version);
+    info!(clause_id = %id, ?version, "library clause approved");
+    state.record_audit(
+        "clause_library.approved",
+        &id,
+        Some(approver),
+        json!({ "version": req.version.or(version) }),
+    );
+    Ok(Json(approved))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, hour, 0, 0).unwrap()
+    }
+
+    fn new(kind: &str, attributes: &[(&str, &str)], text: &str) -> NewClauseRequest {
+        NewClauseRequest {
+            kind: kind.to_string(),
+            attributes: attributes
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            text: text.to_string(),
+            note: None,
+        }
+    }
+
+    fn reference(tag: &str) -> ClauseRef {
+        ClauseRef::parse(tag).unwrap()
+    }
+
+    #[test]
+    fn references_parse_and_print_in_attribute_order() {
+        let r = reference("clause:limitation_of_liability | risk=conservative|law=ny");
+        assert_eq!(r.kind, "limitation_of_liability");
+        assert_eq!(r.attributes["risk"], "conservative");
+        assert_eq!(
+            r.to_string(),
+            "clause:limitation_of_liability|law=ny|risk=conservative"
+        );
+        for bad in ["clause:", "clause:Bad", "clause:x|risk", "clause:x|a=1|a=2"] {
+            assert!(ClauseRef::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn only_approved_text_of_a_matching_clause_resolves() {
+        let library = ClauseLibrary::default();
+        let lol = "clause:limitation_of_liability|risk=conservative";
+        library
+            .create(
+                "cl-1".to_string(),
+                new(
+                    "limitation_of_liability",
+                    &[("risk", "conservative"), ("law", "ny")],
+                    "Liability is capped at the fees paid.",
+                ),
+                at(9),
+            )
+            .unwrap();
+        assert!(library.resolve(&reference(lol)).is_none());
+
+        library
+            .approve("cl-1", None, "gc", at(10))
+            .unwrap()
+            .unwrap();
+        let edit = ClauseUpdateRequest {
+            text: "Liability is capped at twice the fees paid.".to_string(),
+            note: None,
+        };
+        library.update("cl-1", edit, at(11)).unwrap().unwrap();
+        let (embedded, text) = library.resolve(&reference(lol)).unwrap();
+        assert_eq!((embedded.clause_id.as_str(), embedded.version), ("cl-1", 1));
+        assert_eq!(text, "Liability is capped at the fees paid.");
+
+        library
+            .approve("cl-1", Some(2), "gc", at(12))
+            .unwrap()
+            .unwrap();
+        let (embedded, text) = library.resolve(&reference(lol)).unwrap();
+        assert_eq!(embedded.version, 2);
+        assert!(text.contains("twice"));
+        assert!(library
+            .resolve(&reference("clause:limitation_of_liability|risk=aggressive"))
+            .is_none());
+        assert_eq!(
+            library.approve("cl-1", Some(2), "gc", at(13)).unwrap_err(),
+            "version 2 is already approved"
+        );
+        assert!(library
+            .approve("cl-9", None, "gc", at(13))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn the_most_recently_approved_match_wins() {
+        let library = ClauseLibrary::default();
+        for (id, text, hour) in [("cl-1", "Old.", 9), ("cl-2", "New.", 10)] {
+            library
+                .create(id.to_string(), new("notices", &[], text), at(8))
+                .unwrap();
+            library.approve(id, None, "gc", at(hour)).unwrap();
+        }
+        let (embedded, _) = library.resolve(&reference("clause:notices")).unwrap();
+        assert_eq!(embedded.clause_id, "cl-2");
+        let missing = [reference("clause:notices"), reference("clause:waiver")];
+        assert_eq!(
+            library.resolve_all(&missing).unwrap_err(),
+            "no approved clause matches {{clause:waiver}}"
+        );
+    }
+
+    #[test]
+    fn clause_text_cannot_nest_fragments() {
+        let library = ClauseLibrary::default();
+        let create =
+            |text: &str| library.create("cl-1".to_string(), new("notices", &[], text), at(9));
+        assert!(create("Notices go to {{party_a}}.").is_ok());
+        assert!(create("{{> macro/notices}}").is_err());
+        assert!(create("{{clause:waiver}}").is_err());
+        assert!(library
+            .create(
+                "cl-2".to_string(),
+                new("notices", &[("risk", "a|b")], "x"),
+                at(9)
+            )
+            .is_err());
+    }
+}
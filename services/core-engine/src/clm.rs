@@ -0,0 +1,537 @@
+//! Outbound connectors that push contract records and their analysis into
+//! contract lifecycle management (CLM) platforms. Every connector turns the
+//! same normalized record into its platform's API call; a per-connector
+//! field map renames or drops fields, and each push is retried on transient
+//! failures and reported per connector.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tracing::{info, warn};
+
+use crate::{access, auth, risk_model::RiskModel, AppState};
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_RETRY_BACKOFF_MS: u64 = 500;
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClmKind {
+    Ironclad,
+    Conga,
+}
+
+fn default_max_attempts() -> u32 {
+    DEFAULT_MAX_ATTEMPTS
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    DEFAULT_RETRY_BACKOFF_MS
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectorConfig {
+    pub id: String,
+    pub kind: ClmKind,
+    /// API root, e.g. `https://na1.ironcladapp.com`.
+    pub base_url: String,
+    /// Environment variable holding the API token, so the file has no secrets.
+    #[serde(default)]
+    pub token_env: Option<String>,
+    /// Normalized field name to CLM field name; an empty name drops the field.
+    #[serde(default)]
+    pub field_map: BTreeMap<String, String>,
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Doubled after each failed attempt.
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+}
+
+impl ConnectorConfig {
+    fn connector(&self) -> Box<dyn ClmConnector> {
+        match self.kind {
+            ClmKind::Ironclad => Box::new(IroncladConnector),
+            ClmKind::Conga => Box::new(CongaConnector),
+        }
+    }
+
+    fn token(&self) -> Option<String> {
+        self.token_env
+            .as_ref()
+            .and_then(|var| std::env::var(var).ok())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClmConfig {
+    #[serde(default)]
+    pub connectors: Vec<ConnectorConfig>,
+}
+
+impl ClmConfig {
+    /// Reads `LEGAL_CLM_FILE` when set. A missing or malformed file stops
+    /// startup.
+    pub fn from_env() -> Self {
+        match std::env::var("LEGAL_CLM_FILE") {
+            Ok(path) => {
+                let raw = std::fs::read_to_string(&path)
+                    .unwrap_or_else(|e| panic!("cannot read CLM connectors {path}: {e}"));
+                let config: Self = serde_json::from_str(&raw)
+                    .unwrap_or_else(|e| panic!("invalid CLM connectors {path}: {e}"));
+                config
+                    .validate()
+                    .unwrap_or_else(|e| panic!("invalid CLM connectors {path}: {e}"));
+                config
+            }
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let mut ids = std::collections::BTreeSet::new();
+        for c in &self.connectors {
+            if c.id.trim().is_empty() || !ids.insert(c.id.as_str()) {
+                return Err(format!(
+                    "connector ids must be unique and non-empty: {:?}",
+                    c.id
+                ));
+            }
+            if !c.base_url.starts_with("http://") && !c.base_url.starts_with("https://") {
+                return Err(format!("{}: base_url must be an http(s) URL", c.id));
+            }
+            if c.max_attempts == 0 {
+                return Err(format!("{}: max_attempts must be at least 1", c.id));
+            }
+            if let Some(field) = c.field_map.keys().find(|f| !FIELDS.contains(&f.as_str())) {
+                return Err(format!("{}: unknown field {field}", c.id));
+            }
+        }
+        Ok(())
+    }
+}
+
+// ── Record ────────────────────────────────────────────────────────────────────
+
+/// Normalized record fields, in the order they are sent.
+pub const FIELDS: [&str; 14] = [
+    "analysis_id",
+    "family_id",
+    "parent_id",
+    "language",
+    "analyzed_at",
+    "parties",
+    "governing_law",
+    "termination_notice_days",
+    "auto_renewal",
+    "end_date",
+    "pricing",
+    "risk_score",
+    "risk_level",
+    "issue_count",
+];
+
+/// The platform-neutral contract record: key terms plus analysis results.
+pub fn normalize(record: &access::AnalysisRecord, model: &RiskModel) -> Vec<(&'static str, Value)> {
+    let terms = &record.commercial_terms;
+    let values = [
+        json!(record.analysis_id),
+        json!(record.family_id),
+        json!(record.parent_id),
+        json!(record.language),
+        json!(record.stored_at),
+        json!(record.parties.join("; ")),
+        json!(terms.governing_law),
+        json!(terms.termination_notice_days),
+        json!(terms.auto_renewal),
+        json!(record.payment.end_date),
+        json!(record.payment.pricing),
+        json!(record.risk_score),
+        json!(model.level(record.risk_score)),
+        json!(record.issues.len()),
+    ];
+    FIELDS.into_iter().zip(values).collect()
+}
+
+/// Renames fields through `field_map`, drops those mapped to `""` and
+/// fields without a value.
+pub fn map_fields(
+    fields: Vec<(&'static str, Value)>,
+    field_map: &BTreeMap<String, String>,
+) -> Map<String, Value> {
+    fields
+        .into_iter()
+        .filter(|(_, v)| !v.is_null())
+        .filter_map(|(name, v)| {
+            let target = field_map.get(name).map_or(name, String::as_str);
+            (!target.is_empty()).then(|| (target.to_string(), v))
+        })
+        .collect()
+}
+
+// ── Connectors ────────────────────────────────────────────────────────────────
+
+/// A CLM platform's API for creating contract records.
+pub trait ClmConnector: Send + Sync {
+    /// Path under the connector's `base_url` that records are posted to.
+    fn path(&self) -> &'static str;
+    fn payload(&self, analysis_id: &str, fields: Map<String, Value>) -> Value;
+}
+
+/// Ironclad records API: typed properties under `properties`.
+pub struct IroncladConnector;
+
+impl ClmConnector for IroncladConnector {
+    fn path(&self) -> &'static str {
+        "/public/api/v1/records"
+    }
+
+    fn payload(&self, analysis_id: &str, fields: Map<String, Value>) -> Value {
+        let properties: Map<String, Value> = fields
+            .into_iter()
+            .map(|(name, value)| {
+                let kind = match &value {
+                    Value::Bool(_) => "boolean",
+                    Value::Number(_) => "number",
+                    _ => "string",
+                };
+                (name, json!({ "type": kind, "value": value }))
+            })
+            .collect();
+        json!({
+            "type": "contract",
+            "name": format!("Contract {analysis_id}"),
+            "properties": properties,
+        })
+    }
+}
+
+/// Conga CLM agreements API: flat fields.
+pub struct CongaConnector;
+
+impl ClmConnector for CongaConnector {
+    fn path(&self) -> &'static str {
+        "/api/clm/v1/agreements"
+    }
+
+    fn payload(&self, analysis_id: &str, mut fields: Map<String, Value>) -> Value {
+        fields.insert("Name".to_string(), json!(format!("Contract {analysis_id}")));
+        Value::Object(fields)
+    }
+}
+
+// ── Delivery ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Delivered,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Delivery {
+    pub connector: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Running totals per connector, for the connectors listing.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConnectorStats {
+    pub delivered: u64,
+    pub failed: u64,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct ClmStats {
+    connectors: DashMap<String, ConnectorStats>,
+}
+
+impl ClmStats {
+    fn record(&self, delivery: &Delivery, now: DateTime<Utc>) {
+        let mut stats = self
+            .connectors
+            .entry(delivery.connector.clone())
+            .or_default();
+        stats.last_attempt_at = Some(now);
+        match delivery.status {
+            DeliveryStatus::Delivered => stats.delivered += 1,
+            DeliveryStatus::Failed => {
+                stats.failed += 1;
+                stats.last_error.clone_from(&delivery.error);
+            }
+        }
+    }
+
+    pub fn get(&self, connector: &str) -> ConnectorStats {
+        self.connectors
+            .get(connector)
+            .map(|s| s.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Server errors, rate limiting and timeouts are worth another attempt;
+/// other client errors will fail the same way again.
+pub fn retryable(status: reqwest::StatusCode) -> bool {
+    status.is_server_error()
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+}
+
+pub async fn push(
+    client: &reqwest::Client,
+    config: &ConnectorConfig,
+    record: &access::AnalysisRecord,
+    model: &RiskModel,
+) -> Delivery {
+    let connector = config.connector();
+    let fields = map_fields(normalize(record, model), &config.field_map);
+    let body = connector.payload(&record.analysis_id, fields);
+    let url = format!(
+        "{}{}",
+        config.base_url.trim_end_matches('/'),
+        connector.path()
+    );
+    let token = config.token();
+
+    let mut delivery = Delivery {
+        connector: config.id.clone(),
+        status: DeliveryStatus::Failed,
+        attempts: 0,
+        http_status: None,
+        error: None,
+    };
+    let mut backoff = Duration::from_millis(config.retry_backoff_ms);
+    while delivery.attempts < config.max_attempts {
+        if delivery.attempts > 0 {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+        delivery.attempts += 1;
+        let mut request = client.post(&url).json(&body);
+        if let Some(token) = &token {
+            request = request.bearer_auth(token);
+        }
+        match request.send().await {
+            Ok(resp) if resp.status().is_success() => {
+                delivery.status = DeliveryStatus::Delivered;
+                delivery.http_status = Some(resp.status().as_u16());
+                delivery.error = None;
+                return delivery;
+            }
+            Ok(resp) => {
+                let status = resp.status();
+                delivery.http_status = Some(status.as_u16());
+                delivery.error = Some(format!("{} responded {status}", config.id));
+                if !retryable(status) {
+                    return delivery;
+                }
+            }
+            Err(e) => {
+                delivery.http_status = None;
+                delivery.error = Some(e.to_string());
+            }
+        }
+    }
+    delivery
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PushRequest {
+    /// Connector IDs to push to; all configured connectors when absent.
+    #[serde(default)]
+    pub connectors: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PushResponse {
+    pub analysis_id: String,
+    pub deliveries: Vec<Delivery>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConnectorInfo {
+    pub id: String,
+    pub kind: ClmKind,
+    pub base_url: String,
+    pub field_map: BTreeMap<String, String>,
+    pub max_attempts: u32,
+    #[serde(flatten)]
+    pub stats: ConnectorStats,
+}
+
+pub async fn list_connectors(State(state): State<AppState>) -> Json<Vec<ConnectorInfo>> {
+    Json(
+        state
+            .clm
+            .connectors
+            .iter()
+            .map(|c| ConnectorInfo {
+                id: c.id.clone(),
+                kind: c.kind,
+                base_url: c.base_url.clone(),
+                field_map: c.field_map.clone(),
+                max_attempts: c.max_attempts,
+                stats: state.clm_stats.get(&c.id),
+            })
+            .collect(),
+    )
+}
+
+/// Pushes to every selected connector concurrently. `200` when all of them
+/// took the record, `502` with the same per-connector report otherwise.
+pub async fn push_analysis(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Option<Json<PushRequest>>,
+) -> Result<(StatusCode, Json<PushResponse>), StatusCode> {
+    let req = body.map(|Json(r)| r).unwrap_or_default();
+    let selected: Vec<&ConnectorConfig> = match &req.connectors {
+        Some(ids) => ids
+            .iter()
+            .map(|id| state.clm.connectors.iter().find(|c| &c.id == id))
+            .collect::<Option<_>>()
+            .ok_or(StatusCode::NOT_FOUND)?,
+        None => state.clm.connectors.iter().collect(),
+    };
+    if selected.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let doc = auth::owned_document(&state, &headers, &id).ok_or(StatusCode::NOT_FOUND)?;
+    let record = access::record(&state, doc);
+
+    let deliveries = futures_util::future::join_all(
+        selected
+            .iter()
+            .map(|config| push(&state.http, config, &record, &state.risk_model)),
+    )
+    .await;
+    let now = state.clock.now();
+    for d in &deliveries {
+        state.clm_stats.record(d, now);
+        match d.status {
+            DeliveryStatus::Delivered => {
+                info!(analysis_id = %id, connector = %d.connector, attempts = d.attempts, "CLM record pushed")
+            }
+            DeliveryStatus::Failed => {
+                warn!(analysis_id = %id, connector = %d.connector, attempts = d.attempts, error = ?d.error, "CLM push failed")
+            }
+        }
+    }
+    let status = if deliveries
+        .iter()
+        .all(|d| d.status == DeliveryStatus::Delivered)
+    {
+        StatusCode::OK
+    } else {
+        StatusCode::BAD_GATEWAY
+    };
+    Ok((
+        status,
+        Json(PushResponse {
+            analysis_id: id,
+            deliveries,
+        }),
+    ))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(kind: ClmKind) -> ConnectorConfig {
+        ConnectorConfig {
+            id: "clm".to_string(),
+            kind,
+            base_url: "https://clm.example".to_string(),
+            token_env: None,
+            field_map: BTreeMap::new(),
+            max_attempts: 3,
+            retry_backoff_ms: 0,
+        }
+    }
+
+    fn fields() -> Vec<(&'static str, Value)> {
+        vec![
+            ("analysis_id", json!("a1")),
+            ("family_id", Value::Null),
+            ("governing_law", json!("New York")),
+            ("auto_renewal", json!(true)),
+            ("risk_score", json!(0.4)),
+        ]
+    }
+
+    #[test]
+    fn field_map_renames_and_drops_fields() {
+        let map = BTreeMap::from([
+            ("governing_law".to_string(), "GoverningLaw__c".to_string()),
+            ("risk_score".to_string(), String::new()),
+        ]);
+        let mapped = map_fields(fields(), &map);
+        let keys: Vec<&str> = mapped.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["GoverningLaw__c", "analysis_id", "auto_renewal"]);
+    }
+
+    #[test]
+    fn adapters_shape_their_payloads() {
+        let mapped = map_fields(fields(), &BTreeMap::new());
+        let ironclad = config(ClmKind::Ironclad)
+            .connector()
+            .payload("a1", mapped.clone());
+        assert_eq!(ironclad["type"], "contract");
+        assert_eq!(
+            ironclad["properties"]["auto_renewal"],
+            json!({ "type": "boolean", "value": true })
+        );
+        assert_eq!(ironclad["properties"]["risk_score"]["type"], "number");
+
+        let conga = config(ClmKind::Conga).connector().payload("a1", mapped);
+        assert_eq!(conga["Name"], "Contract a1");
+        assert_eq!(conga["governing_law"], "New York");
+    }
+
+    #[test]
+    fn config_rejects_duplicates_and_unknown_fields() {
+        let mut c = ClmConfig {
+            connectors: vec![config(ClmKind::Conga), config(ClmKind::Ironclad)],
+        };
+        assert!(c.validate().is_err());
+        c.connectors.pop();
+        c.connectors[0]
+            .field_map
+            .insert("colour".to_string(), "x".to_string());
+        assert!(c.validate().unwrap_err().contains("unknown field colour"));
+        c.connectors[0].field_map.clear();
+        assert!(c.validate().is_ok());
+    }
+
+    #[test]
+    fn only_transient_statuses_are_retried() {
+        assert!(retryable(reqwest::StatusCode::BAD_GATEWAY));
+        assert!(retryable(reqwest::StatusCode::TOO_MANY_REQUESTS));
+        assert!(!retryable(reqwest::StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
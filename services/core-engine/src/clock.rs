@@ -0,0 +1,102 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Source of "now" for timestamps and date-based rules.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+}
+
+/// Source of unique IDs; callers add their own prefix (`esc-`, `wiz-`, …).
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> String;
+}
+
+// ── Production ────────────────────────────────────────────────────────────────
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+pub struct UuidGenerator;
+
+impl IdGenerator for UuidGenerator {
+    fn next_id(&self) -> String {
+        uuid::Uuid::new_v4().to_string()
+    }
+}
+
+// ── Deterministic ─────────────────────────────────────────────────────────────
+
+/// A clock that only moves when told to.
+pub struct FixedClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl FixedClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn advance(&self, by: chrono::Duration) {
+        *self.now.lock().expect("clock lock poisoned") += by;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().expect("clock lock poisoned")
+    }
+}
+
+/// UUID-shaped IDs counting up from 1, so outputs can be compared verbatim.
+#[derive(Default)]
+pub struct SequentialIds {
+    next: AtomicU64,
+}
+
+impl IdGenerator for SequentialIds {
+    fn next_id(&self) -> String {
+        let n = self.next.fetch_add(1, Ordering::Relaxed) + 1;
+        uuid::Uuid::from_u128(u128::from(n)).to_string()
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequential_ids_are_uuid_shaped_and_ordered() {
+        let ids = SequentialIds::default();
+        assert_eq!(ids.next_id(), "00000000-0000-0000-0000-000000000001");
+        assert_eq!(ids.next_id(), "00000000-0000-0000-0000-000000000002");
+    }
+
+    #[test]
+    fn fixed_clock_only_moves_when_advanced() {
+        let start = DateTime::parse_from_rfc3339("2024-01-31T23:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let clock = FixedClock::new(start);
+        assert_eq!(clock.now(), start);
+        clock.advance(chrono::Duration::hours(2));
+        assert_eq!(clock.today().to_string(), "2024-02-01");
+    }
+}
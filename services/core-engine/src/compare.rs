@@ -0,0 +1,377 @@
+//! Redline of two document versions at clause level: which clauses were
+//! added, removed, reworded or only moved, what the rewording changed, and
+//! how the risk factors moved, so a negotiator sees what a counterparty's
+//! edits actually do rather than a character diff.
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, DiffOp};
+use tracing::info;
+
+use crate::{
+    extraction::{self, clause_kind},
+    notifier,
+    revisions::{self, ChangeKind},
+    risk_model::RiskModel,
+    AppState,
+};
+
+/// Word edits listed per modified clause; the full texts carry the rest.
+const MAX_EDITS: usize = 5;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct CompareRequest {
+    pub original: String,
+    pub revised: String,
+}
+
+/// A clause that differs between the versions; indices are zero-based
+/// clause positions in the respective version.
+#[derive(Debug, Serialize)]
+pub struct ClauseDiff {
+    pub change: ChangeKind,
+    pub from_index: Option<usize>,
+    pub to_index: Option<usize>,
+    /// Section title, when the clause is a numbered section that has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub clause_type: String,
+    pub risk_level: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    /// False for moves and for edits that only touch case, punctuation or
+    /// spacing.
+    pub substantive: bool,
+    /// Word-level edits of a modified clause, e.g. `"30" → "60"`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub edits: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FactorChange {
+    pub factor: String,
+    pub weight: f64,
+    pub before: f64,
+    pub after: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RiskComparison {
+    pub overall_before: f64,
+    pub overall_after: f64,
+    pub level_before: String,
+    pub level_after: String,
+    /// Only factors whose score differs.
+    pub changed_factors: Vec<FactorChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompareSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub moved: usize,
+    pub unchanged: usize,
+    pub substantive_changes: usize,
+    /// One plain sentence per substantive change, risk moves last.
+    pub highlights: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompareResponse {
+    pub summary: CompareSummary,
+    /// Every clause except the unchanged ones, in revised-document order.
+    pub changes: Vec<ClauseDiff>,
+    pub risk: RiskComparison,
+}
+
+// ── Comparison ────────────────────────────────────────────────────────────────
+
+/// Numbered sections, plus any preamble before the first one, or else
+/// blank-line separated paragraphs; each as `(title, text)`.
+fn split_clauses(document: &str) -> Vec<(String, String)> {
+    let sections = extraction::sections(document);
+    let Some(first) = sections.first() else {
+        return document
+            .split("\n\n")
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(|p| (String::new(), p.to_string()))
+            .collect();
+    };
+    let preamble = document[..first.start].trim();
+    (!preamble.is_empty())
+        .then(|| (String::new(), preamble.to_string()))
+        .into_iter()
+        .chain(sections.iter().map(|s| {
+            (
+                s.title.to_string(),
+                document[s.start..s.end].trim().to_string(),
+            )
+        }))
+        .collect()
+}
+
+/// Lowercased words, so case, punctuation and spacing do not count.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn word_edits(before: &str, after: &str) -> Vec<String> {
+    let old: Vec<&str> = before.split_whitespace().collect();
+    let new: Vec<&str> = after.split_whitespace().collect();
+    similar::capture_diff_slices(Algorithm::Myers, &old, &new)
+        .into_iter()
+        .filter_map(|op| match op {
+            DiffOp::Equal { .. } => None,
+            DiffOp::Delete {
+                old_index, old_len, ..
+            } => Some(format!(
+                "removed \"{}\"",
+                old[old_index..old_index + old_len].join(" ")
+            )),
+            DiffOp::Insert {
+                new_index, new_len, ..
+            } => Some(format!(
+                "added \"{}\"",
+                new[new_index..new_index + new_len].join(" ")
+            )),
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => Some(format!(
+                "\"{}\" → \"{}\"",
+                old[old_index..old_index + old_len].join(" "),
+                new[new_index..new_index + new_len].join(" ")
+            )),
+        })
+        .take(MAX_EDITS)
+        .collect()
+}
+
+fn highlight(diff: &ClauseDiff) -> String {
+    let label = diff.title.as_deref().unwrap_or(&diff.clause_type);
+    match diff.change {
+        ChangeKind::Added => format!("{label} clause added"),
+        ChangeKind::Removed => format!("{label} clause removed"),
+        _ => format!("{label} clause changed: {}", diff.edits.join("; ")),
+    }
+}
+
+pub fn compare(
+    keywords: &crate::warmup::KeywordMatcher,
+    model: &RiskModel,
+    original: &str,
+    revised: &str,
+) -> CompareResponse {
+    let old = split_clauses(original);
+    let new = split_clauses(revised);
+    let old_texts: Vec<String> = old.iter().map(|(_, t)| t.clone()).collect();
+    let new_texts: Vec<String> = new.iter().map(|(_, t)| t.clone()).collect();
+    let all = revisions::diff_clauses(&old_texts, &new_texts);
+    let unchanged = all
+        .iter()
+        .filter(|c| c.change == ChangeKind::Unchanged)
+        .count();
+
+    let changes: Vec<ClauseDiff> = all
+        .into_iter()
+        .filter(|c| c.change != ChangeKind::Unchanged)
+        .map(|c| {
+            // Typed by the revised wording when there is one.
+            let (title, text) = match (c.to_index, c.from_index) {
+                (Some(j), _) => &new[j],
+                (None, Some(i)) => &old[i],
+                (None, None) => unreachable!("a change has at least one side"),
+            };
+            let (clause_type, risk_level) = match clause_kind(keywords, title, text) {
+                Some(kind) => (kind.name.to_string(), kind.risk),
+                None => ("General".to_string(), "low"),
+            };
+            let edits = match (&c.change, &c.before, &c.after) {
+                (ChangeKind::Modified, Some(b), Some(a)) => word_edits(b, a),
+                _ => Vec::new(),
+            };
+            let substantive = match c.change {
+                ChangeKind::Added | ChangeKind::Removed => true,
+                ChangeKind::Modified => {
+                    words(c.before.as_deref().unwrap_or_default())
+                        != words(c.after.as_deref().unwrap_or_default())
+                }
+                ChangeKind::Moved | ChangeKind::Unchanged => false,
+            };
+            ClauseDiff {
+                change: c.change,
+                from_index: c.from_index,
+                to_index: c.to_index,
+                title: (!title.is_empty()).then(|| title.clone()),
+                clause_type,
+                risk_level: risk_level.to_string(),
+                before: c.before,
+                after: c.after,
+                substantive,
+                edits,
+            }
+        })
+        .collect();
+
+    let (factors_before, factors_after) = (model.factors(original), model.factors(revised));
+    let overall = |fs: &[crate::RiskFactor]| fs.iter().map(|f| f.weight * f.score).sum::<f64>();
+    let risk = RiskComparison {
+        overall_before: overall(&factors_before),
+        overall_after: overall(&factors_after),
+        level_before: model.level(overall(&factors_before)),
+        level_after: model.level(overall(&factors_after)),
+        changed_factors: factors_before
+            .iter()
+            .zip(&factors_after)
+            .filter(|(b, a)| b.score != a.score)
+            .map(|(b, a)| FactorChange {
+                factor: b.factor.clone(),
+                weight: b.weight,
+                before: b.score,
+                after: a.score,
+            })
+            .collect(),
+    };
+
+    let mut highlights: Vec<String> = changes
+        .iter()
+        .filter(|c| c.substantive)
+        .map(highlight)
+        .collect();
+    if risk.level_before != risk.level_after {
+        highlights.push(format!(
+            "Overall risk moved from {} to {}",
+            risk.level_before, risk.level_after
+        ));
+    }
+    let count = |k: ChangeKind| changes.iter().filter(|c| c.change == k).count();
+    let summary = CompareSummary {
+        added: count(ChangeKind::Added),
+        removed: count(ChangeKind::Removed),
+        modified: count(ChangeKind::Modified),
+        moved: count(ChangeKind::Moved),
+        unchanged,
+        substantive_changes: changes.iter().filter(|c| c.substantive).count(),
+        highlights,
+    };
+    CompareResponse {
+        summary,
+        changes,
+        risk,
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn compare_documents(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CompareRequest>,
+) -> Result<Json<CompareResponse>, StatusCode> {
+    if req.original.trim().is_empty() || req.revised.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let tenant = notifier::tenant(&headers);
+    let keywords = state.lexicons.matcher(&tenant);
+    let keywords = keywords
+        .as_deref()
+        .unwrap_or_else(|| state.precompiled.keywords());
+    let response = compare(keywords, &state.risk_model, &req.original, &req.revised);
+    info!(
+        modified = response.summary.modified,
+        added = response.summary.added,
+        removed = response.summary.removed,
+        substantive = response.summary.substantive_changes,
+        "documents compared"
+    );
+    Ok(Json(response))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warmup::Precompiled;
+
+    const ORIGINAL: &str = "MASTER SERVICES AGREEMENT\n\n\
+        1. Governing Law. This Agreement is governed by the laws of New York.\n\
+        2. Fees. Fees are due monthly.\n\
+        3. Termination. Either party may terminate upon 30 days written notice.\n\
+        4. Confidentiality. Each party keeps the other's information secret.\n";
+
+    fn run(revised: &str) -> CompareResponse {
+        compare(
+            Precompiled::default().keywords(),
+            &RiskModel::default(),
+            ORIGINAL,
+            revised,
+        )
+    }
+
+    #[test]
+    fn identical_documents_have_no_changes() {
+        let r = run(ORIGINAL);
+        assert!(r.changes.is_empty());
+        assert_eq!(r.summary.unchanged, 5);
+        assert!(r.summary.highlights.is_empty());
+    }
+
+    #[test]
+    fn reworded_clause_lists_word_edits() {
+        let r = run(&ORIGINAL.replace("30 days", "90 days"));
+        assert_eq!(r.summary.modified, 1);
+        let c = &r.changes[0];
+        assert_eq!(c.title.as_deref(), Some("Termination"));
+        assert_eq!(c.clause_type, "Termination");
+        assert!(c.substantive);
+        assert_eq!(c.edits, ["\"30\" → \"90\""]);
+        assert_eq!(
+            r.summary.highlights,
+            ["Termination clause changed: \"30\" → \"90\""]
+        );
+    }
+
+    #[test]
+    fn punctuation_and_case_edits_are_not_substantive() {
+        let r = run(&ORIGINAL.replace("written notice.", "Written Notice;"));
+        assert_eq!(r.summary.modified, 1);
+        assert_eq!(r.summary.substantive_changes, 0);
+    }
+
+    #[test]
+    fn added_clause_moves_risk_factors() {
+        let revised =
+            format!("{ORIGINAL}5. Indemnification. The Vendor shall indemnify the Client.\n");
+        let r = run(&revised);
+        assert_eq!(r.summary.added, 1);
+        assert_eq!(r.changes[0].clause_type, "Indemnification");
+        assert_eq!(r.changes[0].risk_level, "high");
+        let factor = &r.risk.changed_factors[0];
+        assert_eq!(factor.factor, "Indemnification");
+        assert!(factor.after > factor.before);
+        assert!(r.risk.overall_after > r.risk.overall_before);
+    }
+
+    #[test]
+    fn reordered_sections_are_moves() {
+        let revised = ORIGINAL.replace("2. Fees. Fees are due monthly.\n", "")
+            + "2. Fees. Fees are due monthly.\n";
+        let r = run(&revised);
+        assert_eq!(r.summary.moved, 1);
+        assert_eq!(r.summary.substantive_changes, 0);
+    }
+}
@@ -0,0 +1,303 @@
+//! Compile results kept for repeated identical compiles, such as a preview
+//! compiled again on every edit of an unrelated field. An entry is keyed by
+//! a hash of the request and of everything the result depends on: the
+//! template body and version, the library clauses and macros it embeds,
+//! the jurisdiction policy and the tenant's drafting style. Changing any of
+//! those yields a new key, so nothing has to be invalidated; stale entries
+//! are evicted as the cache fills, least recently used first. The key
+//! doubles as the response's `ETag`, so a client that sends it back in
+//! `If-None-Match` gets `304` without a compile.
+//!
+//! Only JSON responses are cached: blocked compiles and compiles rendered
+//! to a file are done every time.
+
+use std::{
+    collections::BTreeMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use axum::{
+    body::Bytes,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use dashmap::DashMap;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    get_template_body,
+    jurisdiction_policy::{compile_checked, compile_enforced},
+    AppState, CompileRequest,
+};
+
+/// Entries kept unless `LEGAL_COMPILE_CACHE_ENTRIES` says otherwise.
+pub const DEFAULT_MAX_ENTRIES: usize = 512;
+/// `hit` or `miss` on every cacheable compile response.
+pub const CACHE_HEADER: &str = "x-compile-cache";
+
+struct Entry {
+    tenant: String,
+    body: Bytes,
+    /// Tick of the last read or write, for eviction.
+    used: u64,
+}
+
+pub struct CompileCache {
+    max_entries: usize,
+    entries: DashMap<String, Entry>,
+    tick: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Default for CompileCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_ENTRIES)
+    }
+}
+
+impl CompileCache {
+    /// A cache of at most `max_entries`; `0` turns caching off.
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            entries: DashMap::new(),
+            tick: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            std::env::var("LEGAL_COMPILE_CACHE_ENTRIES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_MAX_ENTRIES),
+        )
+    }
+
+    fn enabled(&self) -> bool {
+        self.max_entries > 0
+    }
+
+    fn next_tick(&self) -> u64 {
+        self.tick.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn get(&self, key: &str) -> Option<Bytes> {
+        let mut entry = self.entries.get_mut(key)?;
+        entry.used = self.next_tick();
+        Some(entry.body.clone())
+    }
+
+    fn insert(&self, key: String, tenant: &str, body: Bytes) {
+        if self.entries.len() >= self.max_entries && !self.entries.contains_key(&key) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|e| e.used)
+                .map(|e| e.key().clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        let entry = Entry {
+            tenant: tenant.to_string(),
+            body,
+            used: self.next_tick(),
+        };
+        self.entries.insert(key, entry);
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Drops the tenant's entries; returns how many there were.
+    pub fn remove_tenant(&self, tenant: &str) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|_, e| e.tenant != tenant);
+        before - self.entries.len()
+    }
+
+    /// Drops the entries whose tenant and compiled JSON `matches`; returns
+    /// how many.
+    pub fn remove_where(&self, matches: impl Fn(&str, &str) -> bool) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|_, e| {
+            !std::str::from_utf8(&e.body).is_ok_and(|body| matches(&e.tenant, body))
+        });
+        before - self.entries.len()
+    }
+}
+
+// ── Key ───────────────────────────────────────────────────────────────────────
+
+fn field(hasher: &mut Sha256, bytes: impl AsRef<[u8]>) {
+    hasher.update(bytes);
+    hasher.update([0]);
+}
+
+/// Hash of the request and the state its result depends on; `None` when
+/// the template or an embedded clause does not resolve, which the compile
+/// itself reports.
+fn key(state: &AppState, tenant: &str, req: &CompileRequest) -> Option<String> {
+    let mut hasher = Sha256::new();
+    field(&mut hasher, tenant);
+    field(&mut hasher, &req.template_id);
+    let mut custom = state
+        .custom_templates
+        .resolve(&state.orgs.lineage(tenant), &req.template_id);
+    if let Some(version) = req.template_version {
+        custom = Some(state.custom_templates.pinned(&*custom?, version)?);
+    }
+    match custom {
+        Some(custom) => {
+            let template = &custom.template;
+            field(&mut hasher, &template.tenant);
+            field(&mut hasher, template.version.to_string());
+            field(&mut hasher, &template.body);
+            field(&mut hasher, template.required_variables.join(","));
+            let (embedded, clauses) = state
+                .clause_library
+                .resolve_all(custom.parsed.clauses())
+                .ok()?;
+            for (clause, text) in embedded.iter().zip(&clauses) {
+                field(
+                    &mut hasher,
+                    format!("{}@{}", clause.clause_id, clause.version),
+                );
+                field(&mut hasher, text);
+            }
+            let versions = state.macros.versions();
+            for (name, body) in state.macros.bodies() {
+                field(
+                    &mut hasher,
+                    format!("{name}@{}", versions.get(&name).unwrap_or(&0)),
+                );
+                field(&mut hasher, body);
+            }
+        }
+        None => field(&mut hasher, get_template_body(&req.template_id)?),
+    }
+    field(
+        &mut hasher,
+        serde_json::to_vec(&state.jurisdiction_policy.get()).ok()?,
+    );
+    field(
+        &mut hasher,
+        serde_json::to_vec(&state.styles.get(tenant)).ok()?,
+    );
+    for (name, value) in req.variables.iter().collect::<BTreeMap<_, _>>() {
+        field(&mut hasher, name);
+        field(&mut hasher, value);
+    }
+    field(&mut hasher, serde_json::to_vec(&req.data).ok()?);
+    field(
+        &mut hasher,
+        [u8::from(req.annotations), u8::from(req.provenance)],
+    );
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+// ── Compile ───────────────────────────────────────────────────────────────────
+
+fn not_modified(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == etag))
+}
+
+fn respond(status: StatusCode, etag: String, cache: &'static str, body: Bytes) -> Response {
+    (
+        status,
+        [
+            (header::ETAG, etag),
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (
+                header::HeaderName::from_static(CACHE_HEADER),
+                cache.to_string(),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// `POST /compile` without an output format: the cached result when the
+/// same compile was done before, otherwise the compile, kept for next time.
+pub fn compile(
+    state: &AppState,
+    tenant: &str,
+    headers: &HeaderMap,
+    req: &CompileRequest,
+) -> Result<Response, StatusCode> {
+    let cache = &state.compile_cache;
+    let Some(key) = cache.enabled().then(|| key(state, tenant, req)).flatten() else {
+        return compile_checked(
+            state,
+            tenant,
+            &req.template_id,
+            req.template_version,
+            &req.variables,
+            &req.data,
+            req.extras(),
+        );
+    };
+    let etag = format!("\"{key}\"");
+    if not_modified(headers, &etag) {
+        cache.hits.fetch_add(1, Ordering::Relaxed);
+        return Ok(respond(StatusCode::NOT_MODIFIED, etag, "hit", Bytes::new()));
+    }
+    if let Some(body) = cache.get(&key) {
+        cache.hits.fetch_add(1, Ordering::Relaxed);
+        return Ok(respond(StatusCode::OK, etag, "hit", body));
+    }
+    let compiled = match compile_enforced(
+        state,
+        tenant,
+        &req.template_id,
+        req.template_version,
+        &req.variables,
+        &req.data,
+        req.extras(),
+    )? {
+        Ok(compiled) => compiled,
+        Err(rejection) => {
+            return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(rejection)).into_response())
+        }
+    };
+    cache.misses.fetch_add(1, Ordering::Relaxed);
+    let body =
+        Bytes::from(serde_json::to_vec(&compiled).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?);
+    cache.insert(key, tenant, body.clone());
+    Ok(respond(StatusCode::OK, etag, "miss", body))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_least_recently_used_entry_makes_room() {
+        let cache = CompileCache::new(2);
+        cache.insert("a".into(), "acme", Bytes::from_static(b"1"));
+        cache.insert("b".into(), "beta", Bytes::from_static(b"2"));
+        assert!(cache.get("a").is_some());
+        cache.insert("c".into(), "acme", Bytes::from_static(b"3"));
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a").as_deref(), Some(&b"1"[..]));
+
+        assert_eq!(cache.remove_where(|_, body| body == "3"), 1);
+        assert_eq!(cache.remove_tenant("acme"), 1);
+        assert!(cache.get("a").is_none());
+    }
+}
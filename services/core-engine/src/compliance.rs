@@ -0,0 +1,728 @@
+//! Compliance checklists: the provisions GDPR Art. 28, the CCPA's service
+//! provider rules and the HIPAA business associate rules require a contract
+//! to contain. Each requirement is found by the sentences that address it;
+//! a provision that is there but lacks one of its required elements fails,
+//! one that is not there at all is missing.
+
+use axum::{http::StatusCode, response::Json};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    evidence::{self, Position},
+    export_control::find_words,
+    subcontracting::sentences,
+};
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Framework {
+    Gdpr,
+    Ccpa,
+    Hipaa,
+}
+
+/// One required provision of a framework.
+struct Requirement {
+    id: &'static str,
+    title: &'static str,
+    citation: &'static str,
+    /// Whole words or phrases; a sentence with any of them addresses the
+    /// requirement.
+    anchors: &'static [&'static str],
+    /// What the provision must say, by name: any of the terms will do.
+    elements: &'static [(&'static str, &'static [&'static str])],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Pass,
+    /// The provision is there but lacks a required element.
+    Fail,
+    Missing,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RequirementResult {
+    pub id: String,
+    pub title: String,
+    pub citation: String,
+    pub status: Status,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_elements: Vec<String>,
+    /// The sentence that comes closest to meeting the requirement.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<Position>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Tally {
+    pub pass: usize,
+    pub fail: usize,
+    pub missing: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComplianceReport {
+    pub framework: Framework,
+    /// Every requirement passed.
+    pub compliant: bool,
+    pub summary: Tally,
+    pub requirements: Vec<RequirementResult>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ComplianceRequest {
+    pub document: String,
+    pub framework: Framework,
+}
+
+// ── Rule sets ─────────────────────────────────────────────────────────────────
+
+const PROHIBITION: &[&str] = &[
+    "shall not",
+    "will not",
+    "may not",
+    "must not",
+    "not to",
+    "prohibited",
+];
+const ASSIST: &[&str] = &["assist", "cooperate", "co-operate", "support"];
+const AT_END: &[&str] = &[
+    "termination",
+    "end of",
+    "expiry",
+    "expiration",
+    "completion",
+];
+
+const GDPR: &[Requirement] = &[
+    Requirement {
+        id: "processing_details",
+        title: "Subject matter, duration, nature and purpose of processing",
+        citation: "GDPR Art. 28(3)",
+        anchors: &[
+            "subject matter",
+            "nature and purpose",
+            "categories of data subjects",
+            "data types processed",
+            "types of personal data",
+        ],
+        elements: &[
+            ("purpose of processing", &["purpose", "subject matter"]),
+            (
+                "categories of data",
+                &["categories of", "types of personal data", "data types"],
+            ),
+        ],
+    },
+    Requirement {
+        id: "documented_instructions",
+        title: "Processing only on documented instructions",
+        citation: "GDPR Art. 28(3)(a)",
+        anchors: &["instructions"],
+        elements: &[("documented instructions", &["documented", "written"])],
+    },
+    Requirement {
+        id: "confidentiality",
+        title: "Confidentiality of authorised persons",
+        citation: "GDPR Art. 28(3)(b)",
+        anchors: &["confidentiality", "confidential"],
+        elements: &[(
+            "persons authorised to process",
+            &[
+                "authorised",
+                "authorized",
+                "personnel",
+                "employees",
+                "staff",
+            ],
+        )],
+    },
+    Requirement {
+        id: "security_measures",
+        title: "Technical and organisational security measures",
+        citation: "GDPR Art. 28(3)(c), Art. 32",
+        anchors: &[
+            "technical and organisational measures",
+            "technical and organizational measures",
+            "security measures",
+            "article 32",
+        ],
+        elements: &[("appropriate to the risk", &["appropriate"])],
+    },
+    Requirement {
+        id: "subprocessors",
+        title: "Engaging sub-processors",
+        citation: "GDPR Art. 28(2), Art. 28(4)",
+        anchors: &[
+            "sub-processor",
+            "sub-processors",
+            "subprocessor",
+            "subprocessors",
+            "another processor",
+        ],
+        elements: &[
+            (
+                "prior authorisation of the controller",
+                &[
+                    "prior written",
+                    "prior specific",
+                    "general written",
+                    "authorisation",
+                    "authorization",
+                    "consent",
+                ],
+            ),
+            (
+                "same data protection obligations",
+                &[
+                    "same data protection obligations",
+                    "same obligations",
+                    "equivalent obligations",
+                    "no less protective",
+                ],
+            ),
+        ],
+    },
+    Requirement {
+        id: "data_subject_rights",
+        title: "Assistance with data subject rights",
+        citation: "GDPR Art. 28(3)(e), Arts. 12-23",
+        anchors: &[
+            "data subject",
+            "data subjects",
+            "data subject's",
+            "data subjects'",
+        ],
+        elements: &[("assistance with requests", ASSIST)],
+    },
+    Requirement {
+        id: "breach_notification",
+        title: "Personal data breach notification",
+        citation: "GDPR Art. 28(3)(f), Art. 33",
+        anchors: &[
+            "personal data breach",
+            "data breach",
+            "security incident",
+            "breach",
+        ],
+        elements: &[
+            (
+                "notification of the controller",
+                &["notify", "notification", "inform"],
+            ),
+            ("deadline", &["without undue delay", "hours"]),
+        ],
+    },
+    Requirement {
+        id: "deletion_or_return",
+        title: "Deletion or return of personal data",
+        citation: "GDPR Art. 28(3)(g)",
+        anchors: &[
+            "delete", "deleted", "deletion", "return", "erase", "destroy",
+        ],
+        elements: &[("at the end of the services", AT_END)],
+    },
+    Requirement {
+        id: "audits",
+        title: "Audits and information to demonstrate compliance",
+        citation: "GDPR Art. 28(3)(h)",
+        anchors: &["audit", "audits", "inspection", "inspections"],
+        elements: &[],
+    },
+    Requirement {
+        id: "international_transfers",
+        title: "Transfers outside the EEA",
+        citation: "GDPR Arts. 44-46",
+        anchors: &[
+            "transfer",
+            "transfers",
+            "third country",
+            "third countries",
+            "outside the eea",
+            "outside the european economic area",
+        ],
+        elements: &[(
+            "transfer safeguard",
+            &[
+                "standard contractual clauses",
+                "sccs",
+                "adequacy",
+                "binding corporate rules",
+                "appropriate safeguards",
+            ],
+        )],
+    },
+];
+
+const CCPA: &[Requirement] = &[
+    Requirement {
+        id: "limited_purpose",
+        title: "Processing limited to specified business purposes",
+        citation: "Cal. Civ. Code § 1798.100(d)(1), 11 CCR § 7051(a)(2)",
+        anchors: &[
+            "business purpose",
+            "business purposes",
+            "specified purpose",
+            "specified purposes",
+            "purposes specified",
+        ],
+        elements: &[(
+            "limited to those purposes",
+            &["only", "solely", "limited", "exclusively"],
+        )],
+    },
+    Requirement {
+        id: "no_sale_or_sharing",
+        title: "No selling or sharing of personal information",
+        citation: "Cal. Civ. Code § 1798.140(ag)(1)(A)",
+        anchors: &[
+            "sell",
+            "sale",
+            "selling",
+            "share personal information",
+            "sharing",
+        ],
+        elements: &[("prohibition", PROHIBITION)],
+    },
+    Requirement {
+        id: "use_outside_relationship",
+        title: "No retention, use or disclosure outside the direct business relationship",
+        citation: "Cal. Civ. Code § 1798.140(ag)(1)(B)-(C)",
+        anchors: &[
+            "retain, use, or disclose",
+            "retain, use or disclose",
+            "direct business relationship",
+        ],
+        elements: &[("prohibition", PROHIBITION)],
+    },
+    Requirement {
+        id: "no_combining",
+        title: "No combining with other personal information",
+        citation: "Cal. Civ. Code § 1798.140(ag)(1)(D)",
+        anchors: &["combine", "combining"],
+        elements: &[("prohibition", PROHIBITION)],
+    },
+    Requirement {
+        id: "same_protection",
+        title: "Same level of privacy protection as the CCPA requires",
+        citation: "Cal. Civ. Code § 1798.100(d)(2)",
+        anchors: &[
+            "same level of privacy protection",
+            "same level of protection",
+            "comply with the ccpa",
+            "comply with applicable obligations under the ccpa",
+        ],
+        elements: &[],
+    },
+    Requirement {
+        id: "notice_of_inability",
+        title: "Notice when obligations can no longer be met",
+        citation: "Cal. Civ. Code § 1798.100(d)(4)",
+        anchors: &[
+            "no longer meet",
+            "can no longer",
+            "cannot meet",
+            "unable to meet",
+        ],
+        elements: &[(
+            "notification of the business",
+            &["notify", "notification", "inform"],
+        )],
+    },
+    Requirement {
+        id: "remediation",
+        title: "Right to stop and remediate unauthorized use",
+        citation: "Cal. Civ. Code § 1798.100(d)(3), (5)",
+        anchors: &["remediate", "unauthorized use", "unauthorised use"],
+        elements: &[("steps to stop and remediate", &["stop", "remediate"])],
+    },
+    Requirement {
+        id: "consumer_requests",
+        title: "Cooperation with consumer requests",
+        citation: "Cal. Civ. Code §§ 1798.105(c), 1798.130; 11 CCR § 7051(a)(7)",
+        anchors: &[
+            "consumer request",
+            "consumer requests",
+            "verifiable consumer request",
+            "requests from consumers",
+        ],
+        elements: &[("assistance", ASSIST)],
+    },
+    Requirement {
+        id: "subcontractors",
+        title: "Subcontractors bound by the same terms",
+        citation: "Cal. Civ. Code § 1798.140(ag)(2), 11 CCR § 7051(a)(10)",
+        anchors: &[
+            "subcontractor",
+            "subcontractors",
+            "sub-processor",
+            "sub-processors",
+            "subprocessor",
+            "subprocessors",
+        ],
+        elements: &[
+            ("notice to the business", &["notify", "notice", "inform"]),
+            (
+                "written contract with the same obligations",
+                &[
+                    "same obligations",
+                    "same terms",
+                    "same restrictions",
+                    "no less protective",
+                ],
+            ),
+        ],
+    },
+    Requirement {
+        id: "reasonable_security",
+        title: "Reasonable security procedures and practices",
+        citation: "Cal. Civ. Code §§ 1798.81.5, 1798.100(e)",
+        anchors: &[
+            "reasonable security",
+            "security procedures",
+            "security measures",
+            "security practices",
+        ],
+        elements: &[],
+    },
+];
+
+const HIPAA: &[Requirement] = &[
+    Requirement {
+        id: "permitted_uses",
+        title: "Permitted and required uses and disclosures",
+        citation: "45 CFR § 164.504(e)(2)(i)",
+        anchors: &[
+            "permitted uses",
+            "permitted and required uses",
+            "uses and disclosures",
+            "use or disclose protected health information",
+        ],
+        elements: &[],
+    },
+    Requirement {
+        id: "safeguards",
+        title: "Appropriate safeguards and the Security Rule",
+        citation: "45 CFR § 164.504(e)(2)(ii)(B), § 164.314(a)",
+        anchors: &[
+            "safeguards",
+            "security rule",
+            "subpart c of 45 cfr part 164",
+        ],
+        elements: &[("appropriate safeguards", &["appropriate", "reasonable"])],
+    },
+    Requirement {
+        id: "breach_reporting",
+        title: "Reporting breaches and security incidents",
+        citation: "45 CFR § 164.504(e)(2)(ii)(C), § 164.410",
+        anchors: &[
+            "breach",
+            "breaches",
+            "security incident",
+            "security incidents",
+        ],
+        elements: &[
+            (
+                "report to the covered entity",
+                &["report", "notify", "notification"],
+            ),
+            (
+                "unsecured PHI",
+                &["unsecured protected health information", "unsecured phi"],
+            ),
+        ],
+    },
+    Requirement {
+        id: "subcontractors",
+        title: "Subcontractors agree to the same restrictions",
+        citation: "45 CFR § 164.504(e)(2)(ii)(D), § 164.502(e)(1)(ii)",
+        anchors: &["subcontractor", "subcontractors", "agent", "agents"],
+        elements: &[(
+            "same restrictions and conditions",
+            &["same restrictions", "same conditions", "agree to the same"],
+        )],
+    },
+    Requirement {
+        id: "individual_access",
+        title: "Individuals' access to their PHI",
+        citation: "45 CFR § 164.504(e)(2)(ii)(E), § 164.524",
+        anchors: &[
+            "right of access",
+            "access to protected health information",
+            "designated record set",
+            "164.524",
+        ],
+        elements: &[],
+    },
+    Requirement {
+        id: "amendment",
+        title: "Amendment of PHI",
+        citation: "45 CFR § 164.504(e)(2)(ii)(F), § 164.526",
+        anchors: &[
+            "amend protected health information",
+            "amendment of protected health information",
+            "amendments to protected health information",
+            "164.526",
+        ],
+        elements: &[],
+    },
+    Requirement {
+        id: "accounting_of_disclosures",
+        title: "Accounting of disclosures",
+        citation: "45 CFR § 164.504(e)(2)(ii)(G), § 164.528",
+        anchors: &["accounting of disclosures", "164.528"],
+        elements: &[],
+    },
+    Requirement {
+        id: "hhs_access",
+        title: "Books and records available to HHS",
+        citation: "45 CFR § 164.504(e)(2)(ii)(I)",
+        anchors: &[
+            "secretary",
+            "department of health and human services",
+            "hhs",
+        ],
+        elements: &[(
+            "internal practices, books and records",
+            &["books", "records", "practices"],
+        )],
+    },
+    Requirement {
+        id: "return_or_destroy",
+        title: "Return or destruction of PHI at termination",
+        citation: "45 CFR § 164.504(e)(2)(ii)(J)",
+        anchors: &[
+            "return or destroy",
+            "return or destruction",
+            "destroy",
+            "destruction",
+        ],
+        elements: &[("at termination", AT_END)],
+    },
+    Requirement {
+        id: "termination",
+        title: "Termination for material breach",
+        citation: "45 CFR § 164.504(e)(2)(iii)",
+        anchors: &["terminate", "termination"],
+        elements: &[(
+            "material breach or violation",
+            &["material breach", "material term", "violated", "violation"],
+        )],
+    },
+];
+
+impl Framework {
+    fn requirements(self) -> &'static [Requirement] {
+        match self {
+            Self::Gdpr => GDPR,
+            Self::Ccpa => CCPA,
+            Self::Hipaa => HIPAA,
+        }
+    }
+}
+
+// ── Checking ──────────────────────────────────────────────────────────────────
+
+fn addresses(lower: &str, requirement: &Requirement) -> bool {
+    requirement
+        .anchors
+        .iter()
+        .any(|a| !find_words(lower, a).is_empty())
+}
+
+/// Names of `requirement`'s elements that `lower` does not mention.
+fn lacking(lower: &str, requirement: &Requirement) -> Vec<&'static str> {
+    requirement
+        .elements
+        .iter()
+        .filter(|(_, terms)| !terms.iter().any(|t| lower.contains(t)))
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+fn evaluate(document: &str, requirement: &Requirement) -> RequirementResult {
+    // Every addressing sentence, with the elements it leaves out.
+    let candidates: Vec<(usize, &str, Vec<&str>)> = sentences(document)
+        .into_iter()
+        .filter_map(|(offset, sentence)| {
+            let lower = sentence.to_ascii_lowercase();
+            addresses(&lower, requirement).then(|| (offset, sentence, lacking(&lower, requirement)))
+        })
+        .collect();
+    let mut result = RequirementResult {
+        id: requirement.id.to_string(),
+        title: requirement.title.to_string(),
+        citation: requirement.citation.to_string(),
+        status: Status::Missing,
+        missing_elements: Vec::new(),
+        excerpt: None,
+        position: None,
+    };
+    let Some((offset, sentence, _)) = candidates.iter().min_by_key(|(_, _, l)| l.len()) else {
+        return result;
+    };
+    // Elements may be spread over several sentences of the provision.
+    result.missing_elements = requirement
+        .elements
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| candidates.iter().all(|(_, _, l)| l.contains(name)))
+        .map(String::from)
+        .collect();
+    result.status = if result.missing_elements.is_empty() {
+        Status::Pass
+    } else {
+        Status::Fail
+    };
+    let start = offset + (sentence.len() - sentence.trim_start().len());
+    let end = offset + sentence.trim_end().len();
+    result.excerpt = Some(document[start..end].to_string());
+    result.position = Some(evidence::position(document, start, end));
+    result
+}
+
+pub fn check(document: &str, framework: Framework) -> ComplianceReport {
+    let requirements: Vec<RequirementResult> = framework
+        .requirements()
+        .iter()
+        .map(|r| evaluate(document, r))
+        .collect();
+    let mut summary = Tally::default();
+    for r in &requirements {
+        match r.status {
+            Status::Pass => summary.pass += 1,
+            Status::Fail => summary.fail += 1,
+            Status::Missing => summary.missing += 1,
+        }
+    }
+    ComplianceReport {
+        framework,
+        compliant: summary.fail == 0 && summary.missing == 0,
+        summary,
+        requirements,
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn check_document(
+    Json(req): Json<ComplianceRequest>,
+) -> Result<Json<ComplianceReport>, StatusCode> {
+    if req.document.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let report = check(&req.document, req.framework);
+    info!(
+        framework = ?req.framework,
+        pass = report.summary.pass,
+        fail = report.summary.fail,
+        missing = report.summary.missing,
+        "compliance checklist evaluated"
+    );
+    Ok(Json(report))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DPA: &str = "DATA PROCESSING AGREEMENT\n\n\
+        1. Scope. The subject matter is hosting; the purpose is support. \
+        Categories of data subjects: customers.\n\
+        2. Instructions. The Processor processes personal data only on documented instructions.\n\
+        3. Personnel. Employees authorised to process personal data are bound by confidentiality.\n\
+        4. Security. The Processor implements appropriate technical and organisational measures.\n\
+        5. Sub-processors. The Processor shall not engage a sub-processor without prior written \
+        authorisation. The same data protection obligations apply to every sub-processor.\n\
+        6. Rights. The Processor shall assist the Controller with data subject requests.\n\
+        7. Breach. The Processor shall notify the Controller of a personal data breach.\n\
+        8. Deletion. Personal data is deleted at the end of the services.\n\
+        9. Audit. The Controller may audit the Processor once a year.\n";
+
+    fn result<'a>(report: &'a ComplianceReport, id: &str) -> &'a RequirementResult {
+        report.requirements.iter().find(|r| r.id == id).unwrap()
+    }
+
+    #[test]
+    fn gdpr_provisions_pass_fail_or_go_missing() {
+        let report = check(DPA, Framework::Gdpr);
+        assert_eq!(
+            report.summary,
+            Tally {
+                pass: 8,
+                fail: 1,
+                missing: 1
+            }
+        );
+        assert!(!report.compliant);
+
+        // Both elements come from different sentences of clause 5.
+        let subprocessors = result(&report, "subprocessors");
+        assert_eq!(subprocessors.status, Status::Pass);
+        assert_eq!(subprocessors.citation, "GDPR Art. 28(2), Art. 28(4)");
+
+        let breach = result(&report, "breach_notification");
+        assert_eq!(breach.status, Status::Fail);
+        assert_eq!(breach.missing_elements, ["deadline"]);
+        assert_eq!(
+            breach.excerpt.as_deref(),
+            Some("The Processor shall notify the Controller of a personal data breach.")
+        );
+        assert_eq!(
+            breach.position.as_ref().unwrap().section.as_deref(),
+            Some("7. Breach")
+        );
+
+        let transfers = result(&report, "international_transfers");
+        assert_eq!(transfers.status, Status::Missing);
+        assert!(transfers.excerpt.is_none());
+    }
+
+    #[test]
+    fn adding_the_missing_terms_makes_the_document_compliant() {
+        let fixed = format!(
+            "{}10. Transfers. Transfers to a third country rely on the standard contractual clauses.\n",
+            DPA.replace("breach.", "breach without undue delay.")
+        );
+        let report = check(&fixed, Framework::Gdpr);
+        assert!(report.compliant, "{:?}", report.requirements);
+    }
+
+    #[test]
+    fn ccpa_prohibitions_must_be_prohibitions() {
+        let report = check(
+            "The Service Provider may sell personal information to partners. \
+             It shall not combine personal information with data from other sources.",
+            Framework::Ccpa,
+        );
+        assert_eq!(result(&report, "no_sale_or_sharing").status, Status::Fail);
+        assert_eq!(
+            result(&report, "no_sale_or_sharing").missing_elements,
+            ["prohibition"]
+        );
+        assert_eq!(result(&report, "no_combining").status, Status::Pass);
+        assert_eq!(result(&report, "consumer_requests").status, Status::Missing);
+    }
+
+    #[test]
+    fn hipaa_anchors_are_whole_words() {
+        let report = check(
+            "Business Associate shall ensure that any agents agree to the same restrictions.",
+            Framework::Hipaa,
+        );
+        assert_eq!(result(&report, "subcontractors").status, Status::Pass);
+        assert_eq!(result(&report, "amendment").status, Status::Missing);
+        assert_eq!(report.requirements.len(), HIPAA.len());
+        let report = check("Reagents are stored cold.", Framework::Hipaa);
+        assert_eq!(result(&report, "subcontractors").status, Status::Missing);
+    }
+}
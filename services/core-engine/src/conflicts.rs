@@ -0,0 +1,385 @@
+//! Clauses that repeat or contradict each other within one document — two
+//! governing-law sections, notice periods that disagree, the same paragraph
+//! pasted twice — usually left behind by copy-paste drafting.
+
+use std::collections::BTreeSet;
+
+use axum::{http::StatusCode, response::Json};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{diligence, evidence, subcontracting::sentences, Issue};
+
+/// Token overlap at which two paragraphs count as the same clause.
+const DUPLICATE_SIMILARITY: f64 = 0.9;
+/// Shorter paragraphs (headings, signature lines) are never duplicates.
+const MIN_DUPLICATE_WORDS: usize = 6;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// The same clause appears twice.
+    Duplicate,
+    GoverningLaw,
+    NoticePeriod,
+    PaymentTerm,
+}
+
+impl ConflictKind {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Duplicate => "clause text",
+            Self::GoverningLaw => "governing law",
+            Self::NoticePeriod => "termination notice period",
+            Self::PaymentTerm => "payment term",
+        }
+    }
+
+    fn severity(self) -> &'static str {
+        match self {
+            Self::Duplicate => "low",
+            Self::GoverningLaw => "high",
+            Self::NoticePeriod | Self::PaymentTerm => "medium",
+        }
+    }
+}
+
+/// A byte span of the document and, for term conflicts, the value read there.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Location {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    pub description: String,
+    pub first: Location,
+    pub second: Location,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckRequest {
+    pub document: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConflictReport {
+    pub conflicts: Vec<Conflict>,
+    pub count: usize,
+}
+
+// ── Terms ─────────────────────────────────────────────────────────────────────
+
+/// The first "<n> days" or "<n>-day" in a sentence; "sixty (60) days" reads 60.
+fn days(sentence: &str) -> Option<u32> {
+    let words: Vec<&str> = sentence.split_whitespace().collect();
+    words.iter().enumerate().find_map(|(i, w)| {
+        let lower = w.to_ascii_lowercase();
+        let (number, unit) = match lower.split_once('-') {
+            Some((n, u)) => (n.to_string(), u.to_string()),
+            None => (lower, words.get(i + 1)?.to_ascii_lowercase()),
+        };
+        let number = number.trim_matches(|c: char| !c.is_ascii_digit());
+        if unit.starts_with("day") {
+            number.parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+/// The term a sentence sets, normalized so that spelling variants compare
+/// equal, and the value as written.
+fn term(kind: ConflictKind, sentence: &str) -> Option<(String, String)> {
+    let lower = sentence.to_ascii_lowercase();
+    match kind {
+        ConflictKind::GoverningLaw => {
+            if !lower.contains("governed by") && !lower.contains("governing law") {
+                return None;
+            }
+            let law = diligence::governing_law(sentence)?;
+            let key = law.to_ascii_lowercase();
+            let key = key.strip_prefix("the ").unwrap_or(&key);
+            let key = key.strip_prefix("state of ").unwrap_or(key);
+            Some((key.to_string(), law))
+        }
+        ConflictKind::NoticePeriod => {
+            if !lower.contains("terminat") || !lower.contains("notice") {
+                return None;
+            }
+            let n = days(sentence)?;
+            Some((n.to_string(), format!("{n} days")))
+        }
+        ConflictKind::PaymentTerm => {
+            let about_payment = ["invoice", "payment", "payable", "shall pay"]
+                .iter()
+                .any(|w| lower.contains(w));
+            if !about_payment || lower.contains("terminat") {
+                return None;
+            }
+            let n = days(sentence)?;
+            Some((n.to_string(), format!("{n} days")))
+        }
+        ConflictKind::Duplicate => None,
+    }
+}
+
+/// Each value that disagrees with the first one stated, paired with it;
+/// repeats of an already reported value are not reported again.
+fn term_conflicts(document: &str, kind: ConflictKind) -> Vec<Conflict> {
+    let mut first: Option<(String, Location)> = None;
+    let mut reported = BTreeSet::new();
+    let mut conflicts = Vec::new();
+    for (offset, sentence) in sentences(document) {
+        let Some((key, value)) = term(kind, sentence) else {
+            continue;
+        };
+        let trimmed = sentence.trim();
+        let start = offset + (sentence.len() - sentence.trim_start().len());
+        let location = Location {
+            start,
+            end: start + trimmed.len(),
+            text: trimmed.to_string(),
+            value: Some(value),
+        };
+        match &first {
+            None => first = Some((key, location)),
+            Some((first_key, _)) if *first_key == key => {}
+            Some((_, earlier)) => {
+                if reported.insert(key) {
+                    conflicts.push(Conflict {
+                        kind,
+                        description: format!(
+                            "Conflicting {}: {} at offset {} but {} at offset {}.",
+                            kind.label(),
+                            earlier.value.as_deref().unwrap_or_default(),
+                            earlier.start,
+                            location.value.as_deref().unwrap_or_default(),
+                            location.start
+                        ),
+                        first: earlier.clone(),
+                        second: location,
+                    });
+                }
+            }
+        }
+    }
+    conflicts
+}
+
+// ── Duplicates ────────────────────────────────────────────────────────────────
+
+/// Lowercased words of a paragraph without its clause number.
+fn words(paragraph: &str) -> Vec<String> {
+    paragraph
+        .trim_start_matches(|c: char| c.is_ascii_digit() || ".()".contains(c) || c.is_whitespace())
+        .split_whitespace()
+        .map(|w| {
+            w.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn similarity(a: &BTreeSet<&str>, b: &BTreeSet<&str>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Paragraphs (non-empty lines) that repeat an earlier one; each repeat is
+/// paired with the first occurrence only.
+fn duplicates(document: &str) -> Vec<Conflict> {
+    let mut paragraphs = Vec::new();
+    let mut offset = 0;
+    for line in document.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let words = words(trimmed);
+        if words.len() >= MIN_DUPLICATE_WORDS {
+            let start = offset + (line.len() - line.trim_start().len());
+            paragraphs.push((start, trimmed, words));
+        }
+        offset += line.len();
+    }
+    let sets: Vec<BTreeSet<&str>> = paragraphs
+        .iter()
+        .map(|(_, _, w)| w.iter().map(String::as_str).collect())
+        .collect();
+
+    let mut repeat = vec![false; paragraphs.len()];
+    let mut conflicts = Vec::new();
+    for i in 0..paragraphs.len() {
+        if repeat[i] {
+            continue;
+        }
+        for j in i + 1..paragraphs.len() {
+            if repeat[j] || similarity(&sets[i], &sets[j]) < DUPLICATE_SIMILARITY {
+                continue;
+            }
+            repeat[j] = true;
+            let location = |k: usize| Location {
+                start: paragraphs[k].0,
+                end: paragraphs[k].0 + paragraphs[k].1.len(),
+                text: paragraphs[k].1.to_string(),
+                value: None,
+            };
+            let exact = paragraphs[i].2 == paragraphs[j].2;
+            conflicts.push(Conflict {
+                kind: ConflictKind::Duplicate,
+                description: format!(
+                    "Clause at offset {} {} the clause at offset {}.",
+                    paragraphs[j].0,
+                    if exact { "repeats" } else { "nearly repeats" },
+                    paragraphs[i].0
+                ),
+                first: location(i),
+                second: location(j),
+            });
+        }
+    }
+    conflicts
+}
+
+// ── Detection ─────────────────────────────────────────────────────────────────
+
+/// Matching steps `check` takes on `document`: a scan per term kind, plus
+/// a word-set comparison for every pair of paragraphs long enough to be
+/// duplicates, which is what makes it quadratic.
+pub fn match_steps(document: &str) -> u64 {
+    let (paragraphs, words) = document
+        .lines()
+        .map(|line| line.split_whitespace().count())
+        .filter(|&n| n >= MIN_DUPLICATE_WORDS)
+        .fold((0u64, 0u64), |(p, w), n| (p + 1, w + n as u64));
+    let pairs = paragraphs * paragraphs.saturating_sub(1) / 2;
+    let mean_words = words.checked_div(paragraphs).unwrap_or(0);
+    3 * document.len() as u64 + pairs * mean_words
+}
+
+/// Every conflict in the document, term conflicts first, in document order
+/// within each kind.
+pub fn check(document: &str) -> Vec<Conflict> {
+    let mut conflicts: Vec<Conflict> = [
+        ConflictKind::GoverningLaw,
+        ConflictKind::NoticePeriod,
+        ConflictKind::PaymentTerm,
+    ]
+    .into_iter()
+    .flat_map(|kind| term_conflicts(document, kind))
+    .collect();
+    conflicts.extend(duplicates(document));
+    conflicts
+}
+
+pub fn issues(document: &str, next_id: usize, context_chars: usize) -> Vec<Issue> {
+    check(document)
+        .into_iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let first = evidence::position(document, c.first.start, c.first.end);
+            let second = evidence::position(document, c.second.start, c.second.end);
+            Issue {
+                id: format!("issue-{:03}", next_id + i),
+                description: c.description,
+                severity: c.kind.severity().to_string(),
+                location: format!("{} and {}", first.label(), second.label()),
+                confidence: 0.75,
+                review_status: "auto".to_string(),
+                provenance: None,
+                excerpt: Some(evidence::excerpt(
+                    document,
+                    c.second.start,
+                    c.second.end,
+                    context_chars,
+                )),
+                position: Some(second),
+            }
+        })
+        .collect()
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn check_document(
+    Json(req): Json<CheckRequest>,
+) -> Result<Json<ConflictReport>, StatusCode> {
+    if req.document.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let conflicts = check(&req.document);
+    info!(conflicts = conflicts.len(), "internal conflict check");
+    let count = conflicts.len();
+    Ok(Json(ConflictReport { conflicts, count }))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_day_counts_in_common_spellings() {
+        assert_eq!(days("on sixty (60) days written notice"), Some(60));
+        assert_eq!(days("upon 30-day notice"), Some(30));
+        assert_eq!(days("promptly after notice"), None);
+    }
+
+    #[test]
+    fn finds_contradicting_terms_with_both_locations() {
+        let doc =
+            "1. Governing Law. This Agreement is governed by the laws of the State of New York.\n\
+                   2. Termination. Either party may terminate on 30 days written notice.\n\
+                   3. Fees. Invoices are payable within 45 days.\n\
+                   9. Governing Law. This Agreement is governed by the laws of England and Wales.\n\
+                   10. Termination. Either party may terminate on sixty (60) days notice.\n\
+                   11. Law. This Agreement is governed by the laws of New York.\n";
+        let conflicts = check(doc);
+        let kinds: Vec<ConflictKind> = conflicts.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            [ConflictKind::GoverningLaw, ConflictKind::NoticePeriod]
+        );
+        let law = &conflicts[0];
+        assert_eq!(law.first.value.as_deref(), Some("State of New York"));
+        assert_eq!(law.second.value.as_deref(), Some("England and Wales"));
+        assert!(doc[law.second.start..law.second.end].starts_with("This Agreement"));
+        assert_eq!(conflicts[1].second.value.as_deref(), Some("60 days"));
+    }
+
+    #[test]
+    fn pasted_clause_is_a_duplicate_of_its_first_occurrence() {
+        let clause = "Neither party shall be liable for any indirect or consequential damages.";
+        let doc =
+            format!("4. Liability. {clause}\nSomething else.\n\n12. {clause}\n13. {clause}\n");
+        let conflicts = check(&doc);
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts.iter().all(|c| c.kind == ConflictKind::Duplicate));
+        // The heading word makes the first pair near rather than exact.
+        assert!(conflicts[0].description.contains("nearly repeats"));
+        assert_eq!(conflicts[0].first.start, 0);
+        assert_eq!(conflicts[1].first.start, 0);
+        assert!(doc[conflicts[1].second.start..].starts_with("13."));
+    }
+
+    #[test]
+    fn duplicate_search_grows_with_the_square_of_the_paragraphs() {
+        let paragraph = "The Supplier shall deliver the goods on time.\n";
+        let cost = |n: usize| match_steps(&paragraph.repeat(n));
+        assert_eq!(cost(1), 3 * paragraph.len() as u64);
+        // 45 and 4950 pairs of eight-word paragraphs.
+        assert_eq!(cost(10) - 30 * paragraph.len() as u64, 45 * 8);
+        assert_eq!(cost(100) - 300 * paragraph.len() as u64, 4950 * 8);
+    }
+}
@@ -0,0 +1,516 @@
+//! Templates and risk rules read from a Git repository, so legal-ops can
+//! change content through pull requests instead of the admin endpoints.
+//!
+//! Layout at the configured ref:
+//!
+//! - `templates/<id>.txt` — a new revision of built-in template `<id>`
+//! - `rules/regulatory.json` — the complete list of deprecation rules
+//! - `rules/export-control.json` — the export-control country table
+//! - `rules/jurisdiction-policy.json` — the compile-time jurisdiction policy
+//!
+//! Missing files leave the matching content as it is. A refresh is all or
+//! nothing: if any file fails to parse or validate, nothing is applied.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path as FsPath, PathBuf},
+    process::Command,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    export_control::PolicyTable, includes, jurisdiction_policy::JurisdictionPolicy,
+    regulatory::DeprecationRule, share, warmup::ParsedTemplate, AppState,
+};
+
+pub const WEBHOOK_TOKEN_HEADER: &str = "x-content-webhook-token";
+
+const TEMPLATES_DIR: &str = "templates/";
+const TEMPLATE_EXT: &str = ".txt";
+const REGULATORY_RULES: &str = "rules/regulatory.json";
+const EXPORT_POLICY: &str = "rules/export-control.json";
+const JURISDICTION_POLICY: &str = "rules/jurisdiction-policy.json";
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct ContentRepoConfig {
+    /// Local clone to read from; fetched before each refresh if it has remotes.
+    pub path: PathBuf,
+    /// Branch, tag or commit; `origin/main` follows the remote branch.
+    pub git_ref: String,
+    /// Pull interval; `None` refreshes only on startup and webhook.
+    pub refresh_interval: Option<Duration>,
+    /// Required in the webhook header when set.
+    pub webhook_token: Option<String>,
+}
+
+impl ContentRepoConfig {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            git_ref: "HEAD".to_string(),
+            refresh_interval: None,
+            webhook_token: None,
+        }
+    }
+
+    /// `None` unless `LEGAL_CONTENT_GIT_PATH` is set.
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var("LEGAL_CONTENT_GIT_PATH").ok()?;
+        let d = Self::new(path);
+        Some(Self {
+            git_ref: std::env::var("LEGAL_CONTENT_GIT_REF").unwrap_or(d.git_ref),
+            refresh_interval: std::env::var("LEGAL_CONTENT_REFRESH_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs),
+            webhook_token: std::env::var("LEGAL_CONTENT_WEBHOOK_TOKEN")
+                .ok()
+                .filter(|t| !t.trim().is_empty()),
+            ..d
+        })
+    }
+}
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefreshError {
+    /// Git failed: bad path, unknown ref, unreachable remote.
+    Git(String),
+    /// The content at the ref is malformed; nothing was applied.
+    Invalid(String),
+}
+
+impl std::fmt::Display for RefreshError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Git(e) => write!(f, "git: {e}"),
+            Self::Invalid(e) => write!(f, "invalid content: {e}"),
+        }
+    }
+}
+
+impl RefreshError {
+    fn status(&self) -> StatusCode {
+        match self {
+            Self::Git(_) => StatusCode::BAD_GATEWAY,
+            Self::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+}
+
+/// What the last successful refresh changed.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Applied {
+    /// Templates that got a new revision.
+    pub templates: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regulatory_rules: Option<usize>,
+    pub export_policy: bool,
+    pub jurisdiction_policy: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContentStatus {
+    pub path: String,
+    pub git_ref: String,
+    /// Commit the served content was loaded from.
+    pub commit: Option<String>,
+    pub refreshed_at: Option<DateTime<Utc>>,
+    pub applied: Applied,
+    /// Error of the most recent attempt, cleared by the next success.
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Snapshot {
+    templates: BTreeMap<String, String>,
+    regulatory: Option<Vec<DeprecationRule>>,
+    export_policy: Option<PolicyTable>,
+    jurisdiction_policy: Option<JurisdictionPolicy>,
+}
+
+// ── Git ───────────────────────────────────────────────────────────────────────
+
+fn git(repo: &FsPath, args: &[&str]) -> Result<String, RefreshError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .map_err(|e| RefreshError::Git(e.to_string()))?;
+    if !output.status.success() {
+        return Err(RefreshError::Git(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|e| RefreshError::Git(e.to_string()))
+}
+
+fn parse_json<T: DeserializeOwned>(path: &str, text: &str) -> Result<T, RefreshError> {
+    serde_json::from_str(text).map_err(|e| RefreshError::Invalid(format!("{path}: {e}")))
+}
+
+/// Reads and validates everything at `commit` without touching the state.
+fn load(repo: &FsPath, commit: &str, state: &AppState) -> Result<Snapshot, RefreshError> {
+    let files = git(repo, &["ls-tree", "-r", "--name-only", commit])?;
+    let show = |path: &str| git(repo, &["show", &format!("{commit}:{path}")]);
+    let mut snapshot = Snapshot::default();
+    for path in files.lines() {
+        if let Some(id) = path
+            .strip_prefix(TEMPLATES_DIR)
+            .and_then(|p| p.strip_suffix(TEMPLATE_EXT))
+        {
+            if state.template_revisions.list(id).is_none() {
+                return Err(RefreshError::Invalid(format!("{path}: unknown template")));
+            }
+            snapshot.templates.insert(id.to_string(), show(path)?);
+        } else if path == REGULATORY_RULES {
+            let rules: Vec<DeprecationRule> = parse_json(path, &show(path)?)?;
+            if let Some((bad, error)) = rules
+                .iter()
+                .find_map(|r| r.validate().err().map(|e| (r, e)))
+            {
+                return Err(RefreshError::Invalid(format!(
+                    "{path}: rule {:?}: {error}",
+                    bad.id
+                )));
+            }
+            snapshot.regulatory = Some(rules);
+        } else if path == EXPORT_POLICY {
+            let table: PolicyTable = parse_json(path, &show(path)?)?;
+            if !table.is_valid() {
+                return Err(RefreshError::Invalid(format!(
+                    "{path}: every country needs a name and an alias"
+                )));
+            }
+            snapshot.export_policy = Some(table);
+        } else if path == JURISDICTION_POLICY {
+            let policy: JurisdictionPolicy = parse_json(path, &show(path)?)?;
+            if !policy.is_valid() {
+                return Err(RefreshError::Invalid(format!(
+                    "{path}: every rule needs a country and an alias"
+                )));
+            }
+            snapshot.jurisdiction_policy = Some(policy);
+        }
+    }
+    // Includes resolve against the incoming bodies first, so a commit may
+    // change several templates that include each other at once.
+    let lookup = |id: &str| {
+        snapshot
+            .templates
+            .get(id)
+            .cloned()
+            .or_else(|| state.template_revisions.latest(id))
+    };
+    for (id, body) in &snapshot.templates {
+        let path = format!("{TEMPLATES_DIR}{id}{TEMPLATE_EXT}");
+        let expanded = includes::expand(id, body, &lookup, &state.include_limits)
+            .map_err(|e| RefreshError::Invalid(format!("{path}: {e}")))?;
+        ParsedTemplate::parse(&expanded)
+            .map_err(|e| RefreshError::Invalid(format!("{path}: {e}")))?;
+    }
+    Ok(snapshot)
+}
+
+fn apply(state: &AppState, snapshot: Snapshot, commit: &str) -> Applied {
+    let short = &commit[..commit.len().min(10)];
+    let mut applied = Applied::default();
+    for (id, body) in snapshot.templates {
+        let current = state
+            .template_revisions
+            .list(&id)
+            .and_then(|revs| revs.last().map(|r| r.body.clone()));
+        if current.as_deref() == Some(body.as_str()) {
+            continue;
+        }
+        let note = Some(format!("git {short}: {TEMPLATES_DIR}{id}{TEMPLATE_EXT}"));
+        if state
+            .template_revisions
+            .add(&id, body, note, state.clock.now())
+            .is_some()
+        {
+            applied.templates.push(id);
+        }
+    }
+    if let Some(rules) = snapshot.regulatory {
+        applied.regulatory_rules = Some(rules.len());
+        state.regulatory.replace(rules);
+    }
+    if let Some(table) = snapshot.export_policy {
+        state.export_policy.replace(table);
+        applied.export_policy = true;
+    }
+    if let Some(policy) = snapshot.jurisdiction_policy {
+        state.jurisdiction_policy.replace(policy);
+        applied.jurisdiction_policy = true;
+    }
+    applied
+}
+
+// ── Provider ──────────────────────────────────────────────────────────────────
+
+pub struct ContentRepo {
+    config: ContentRepoConfig,
+    /// Held for the whole refresh, so timer and webhook never interleave.
+    status: Mutex<ContentStatus>,
+}
+
+impl ContentRepo {
+    pub fn new(config: ContentRepoConfig) -> Self {
+        let status = ContentStatus {
+            path: config.path.display().to_string(),
+            git_ref: config.git_ref.clone(),
+            commit: None,
+            refreshed_at: None,
+            applied: Applied::default(),
+            last_error: None,
+        };
+        Self {
+            config,
+            status: Mutex::new(status),
+        }
+    }
+
+    pub fn status(&self) -> ContentStatus {
+        self.status
+            .lock()
+            .expect("content status lock poisoned")
+            .clone()
+    }
+
+    /// Fetches, resolves the ref and applies its content if the commit
+    /// changed. Blocking: runs `git`.
+    pub fn refresh(&self, state: &AppState) -> Result<ContentStatus, RefreshError> {
+        let mut status = self.status.lock().expect("content status lock poisoned");
+        let result = self.pull(state, &status.commit);
+        match result {
+            Ok(Some((commit, applied))) => {
+                info!(commit = %commit, templates = applied.templates.len(), "content repository applied");
+                state.record_audit(
+                    "content.refreshed",
+                    &commit,
+                    None,
+                    serde_json::to_value(&applied).unwrap_or_default(),
+                );
+                status.commit = Some(commit);
+                status.applied = applied;
+                status.refreshed_at = Some(state.clock.now());
+                status.last_error = None;
+            }
+            Ok(None) => {
+                status.refreshed_at = Some(state.clock.now());
+                status.last_error = None;
+            }
+            Err(e) => {
+                warn!(error = %e, "content repository refresh failed; keeping current content");
+                status.last_error = Some(e.to_string());
+                return Err(e);
+            }
+        }
+        Ok(status.clone())
+    }
+
+    /// `None` when the ref still points at `loaded`.
+    fn pull(
+        &self,
+        state: &AppState,
+        loaded: &Option<String>,
+    ) -> Result<Option<(String, Applied)>, RefreshError> {
+        let repo = &self.config.path;
+        if !git(repo, &["remote"])?.trim().is_empty() {
+            git(repo, &["fetch", "--quiet", "--all"])?;
+        }
+        let spec = format!("{}^{{commit}}", self.config.git_ref);
+        let commit = git(repo, &["rev-parse", "--verify", "--quiet", &spec])
+            .map_err(|_| RefreshError::Git(format!("unknown ref {}", self.config.git_ref)))?
+            .trim()
+            .to_string();
+        if loaded.as_deref() == Some(commit.as_str()) {
+            return Ok(None);
+        }
+        let snapshot = load(repo, &commit, state)?;
+        Ok(Some((commit.clone(), apply(state, snapshot, &commit))))
+    }
+}
+
+/// Pulls on the configured interval for the life of the process.
+pub fn spawn_refresh(state: AppState) {
+    let Some(repo) = state.content_repo.clone() else {
+        return;
+    };
+    let Some(interval) = repo.config.refresh_interval else {
+        return;
+    };
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            let (repo, state) = (repo.clone(), state.clone());
+            // Failures are logged and kept in the status; the next tick retries.
+            let _ = tokio::task::spawn_blocking(move || repo.refresh(&state)).await;
+        }
+    });
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+fn configured(state: &AppState) -> Result<Arc<ContentRepo>, StatusCode> {
+    state.content_repo.clone().ok_or(StatusCode::NOT_FOUND)
+}
+
+pub async fn get_status(State(state): State<AppState>) -> Result<Json<ContentStatus>, StatusCode> {
+    Ok(Json(configured(&state)?.status()))
+}
+
+/// Webhook target for the content repository's push events. The route
+/// skips authentication, so without a token it does not exist.
+pub async fn refresh(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ContentStatus>, StatusCode> {
+    let repo = configured(&state)?;
+    let token = repo
+        .config
+        .webhook_token
+        .as_deref()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let given = headers
+        .get(WEBHOOK_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    // Digests have one length, so the comparison takes the same time
+    // whatever was sent.
+    if !share::same(&share::digest(&[given]), &share::digest(&[token])) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    tokio::task::spawn_blocking(move || repo.refresh(&state))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(Json)
+        .map_err(|e| e.status())
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit_files(repo: &FsPath, files: &[(&str, &str)]) {
+        for (path, body) in files {
+            let full = repo.join(path);
+            std::fs::create_dir_all(full.parent().unwrap()).unwrap();
+            std::fs::write(full, body).unwrap();
+        }
+        git(repo, &["add", "-A"]).unwrap();
+        git(
+            repo,
+            &[
+                "-c",
+                "user.name=legal-ops",
+                "-c",
+                "user.email=legal-ops@example.com",
+                "commit",
+                "--quiet",
+                "-m",
+                "content",
+            ],
+        )
+        .unwrap();
+    }
+
+    fn setup() -> (tempfile::TempDir, AppState, Arc<ContentRepo>) {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "--quiet"]).unwrap();
+        let repo = Arc::new(ContentRepo::new(ContentRepoConfig::new(dir.path())));
+        let state = AppState {
+            content_repo: Some(repo.clone()),
+            ..AppState::in_memory()
+        };
+        (dir, state, repo)
+    }
+
+    #[test]
+    fn refresh_applies_templates_and_rules_once_per_commit() {
+        let (dir, state, repo) = setup();
+        commit_files(
+            dir.path(),
+            &[
+                (
+                    "templates/nda.txt",
+                    "NDA between {{party_a}} and {{party_b}}.",
+                ),
+                (
+                    "rules/export-control.json",
+                    r#"{ "countries": [{ "country": "Atlantis", "aliases": ["atlantis"], "status": "embargoed", "reference": null }] }"#,
+                ),
+                ("README.md", "content for the legal engine"),
+            ],
+        );
+
+        let status = repo.refresh(&state).unwrap();
+        assert_eq!(status.applied.templates, ["nda"]);
+        assert!(status.applied.export_policy);
+        assert!(!status.applied.jurisdiction_policy);
+        let revs = state.template_revisions.list("nda").unwrap();
+        assert_eq!(revs.len(), 2);
+        assert!(revs[1]
+            .note
+            .as_deref()
+            .unwrap()
+            .ends_with("templates/nda.txt"));
+        assert_eq!(state.export_policy.get().countries[0].country, "Atlantis");
+
+        // Same commit: nothing is applied again.
+        let again = repo.refresh(&state).unwrap();
+        assert_eq!(again.commit, status.commit);
+        assert_eq!(state.template_revisions.list("nda").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn invalid_content_applies_nothing() {
+        let (dir, state, repo) = setup();
+        commit_files(
+            dir.path(),
+            &[
+                (
+                    "templates/nda.txt",
+                    "NDA between {{party_a}} and {{party_b}}.",
+                ),
+                ("templates/sla.txt", "Uptime {{uptime_percent"),
+            ],
+        );
+
+        let err = repo.refresh(&state).unwrap_err();
+        assert!(matches!(&err, RefreshError::Invalid(e) if e.starts_with("templates/sla.txt")));
+        assert_eq!(state.template_revisions.list("nda").unwrap().len(), 1);
+        let status = repo.status();
+        assert!(status.commit.is_none());
+        assert!(status.last_error.is_some());
+    }
+
+    #[test]
+    fn unknown_ref_is_a_git_error() {
+        let (dir, state, _) = setup();
+        commit_files(dir.path(), &[("README.md", "x")]);
+        let repo = ContentRepo::new(ContentRepoConfig {
+            git_ref: "release-2031".to_string(),
+            ..ContentRepoConfig::new(dir.path())
+        });
+        assert!(matches!(repo.refresh(&state), Err(RefreshError::Git(_))));
+    }
+}
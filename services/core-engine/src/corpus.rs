@@ -0,0 +1,526 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use similar::{Algorithm, DiffOp};
+use tracing::info;
+
+use crate::AppState;
+
+/// Longest base-to-version chain a read has to replay.
+const MAX_CHAIN_DEPTH: usize = 8;
+/// Preceding same-tenant documents, and separately full texts, compaction
+/// tries as a base besides the parent and family.
+const COMPACTION_WINDOW: usize = 8;
+/// Stored size of a copy op: two offsets.
+const COPY_OP_BYTES: usize = 16;
+/// Diffs that take longer than this fall back to a coarser result.
+const DIFF_DEADLINE: Duration = Duration::from_millis(100);
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredDocument {
+    pub id: String,
+    pub text: String,
+    pub language: String,
+    /// The `X-Tenant-Id` the document was submitted under.
+    pub tenant: String,
+    pub stored_at: DateTime<Utc>,
+    /// Set when the document was split out of a bundle or otherwise linked
+    /// to related agreements.
+    pub family_id: Option<String>,
+    /// The agreement this one amends or renews.
+    pub parent_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Op {
+    /// Lines `start..start + len` of the base.
+    Copy {
+        start: usize,
+        len: usize,
+    },
+    Insert(String),
+}
+
+#[derive(Debug, Clone)]
+enum Body {
+    Full(String),
+    /// `depth` is the number of deltas replayed to rebuild the text.
+    Delta {
+        base: String,
+        ops: Vec<Op>,
+        depth: usize,
+    },
+}
+
+impl Body {
+    fn stored_bytes(&self) -> usize {
+        match self {
+            Body::Full(text) => text.len(),
+            Body::Delta { ops, .. } => ops
+                .iter()
+                .map(|op| match op {
+                    Op::Copy { .. } => COPY_OP_BYTES,
+                    Op::Insert(text) => text.len(),
+                })
+                .sum(),
+        }
+    }
+
+    fn depth(&self) -> usize {
+        match self {
+            Body::Full(_) => 0,
+            Body::Delta { depth, .. } => *depth,
+        }
+    }
+}
+
+/// Metadata is kept as a `StoredDocument` with an empty `text`.
+#[derive(Debug, Clone)]
+struct Entry {
+    doc: StoredDocument,
+    body: Body,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CorpusStats {
+    pub documents: usize,
+    pub delta_encoded: usize,
+    /// Size of every document's full text.
+    pub text_bytes: usize,
+    /// What the store actually holds: full texts plus deltas.
+    pub stored_bytes: usize,
+    pub max_chain_depth: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactionReport {
+    pub before: CorpusStats,
+    pub after: CorpusStats,
+    pub duration_ms: u64,
+}
+
+// ── Delta encoding ────────────────────────────────────────────────────────────
+
+/// Line-level delta turning `base` into `text`.
+fn encode(base: &str, text: &str) -> Vec<Op> {
+    let old: Vec<&str> = base.split_inclusive('\n').collect();
+    let new: Vec<&str> = text.split_inclusive('\n').collect();
+    let deadline = Instant::now() + DIFF_DEADLINE;
+    let mut ops: Vec<Op> = Vec::new();
+    for op in similar::capture_diff_slices_deadline(Algorithm::Myers, &old, &new, Some(deadline)) {
+        match op {
+            DiffOp::Equal { old_index, len, .. } => ops.push(Op::Copy {
+                start: old_index,
+                len,
+            }),
+            DiffOp::Delete { .. } => {}
+            DiffOp::Insert {
+                new_index, new_len, ..
+            }
+            | DiffOp::Replace {
+                new_index, new_len, ..
+            } => {
+                let added = new[new_index..new_index + new_len].concat();
+                match ops.last_mut() {
+                    Some(Op::Insert(text)) => text.push_str(&added),
+                    _ => ops.push(Op::Insert(added)),
+                }
+            }
+        }
+    }
+    ops
+}
+
+fn apply(base: &str, ops: &[Op]) -> String {
+    let lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let mut text = String::with_capacity(base.len());
+    for op in ops {
+        match op {
+            Op::Copy { start, len } => text.extend(lines[*start..start + len].iter().copied()),
+            Op::Insert(added) => text.push_str(added),
+        }
+    }
+    text
+}
+
+/// `text` as a delta against `base` when that at least halves its size.
+fn delta_against(base_id: &str, base: &Entry, base_text: &str, text: &str) -> Option<Body> {
+    let depth = base.body.depth() + 1;
+    if depth > MAX_CHAIN_DEPTH {
+        return None;
+    }
+    let body = Body::Delta {
+        base: base_id.to_string(),
+        ops: encode(base_text, text),
+        depth,
+    };
+    (body.stored_bytes() * 2 <= text.len()).then_some(body)
+}
+
+fn materialize(entries: &HashMap<String, Entry>, id: &str) -> Option<String> {
+    let entry = entries.get(id)?;
+    match &entry.body {
+        Body::Full(text) => Some(text.clone()),
+        Body::Delta { base, ops, .. } => Some(apply(&materialize(entries, base)?, ops)),
+    }
+}
+
+fn stats(entries: &HashMap<String, Entry>) -> CorpusStats {
+    let mut stats = CorpusStats {
+        documents: entries.len(),
+        ..CorpusStats::default()
+    };
+    for (id, entry) in entries {
+        if matches!(entry.body, Body::Delta { .. }) {
+            stats.delta_encoded += 1;
+        }
+        stats.text_bytes += materialize(entries, id).map_or(0, |t| t.len());
+        stats.stored_bytes += entry.body.stored_bytes();
+        stats.max_chain_depth = stats.max_chain_depth.max(entry.body.depth());
+    }
+    stats
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+/// Every document submitted for analysis, keyed by its analysis ID, so
+/// corpus-wide sweeps can run without clients resubmitting contracts.
+///
+/// Amendments, renewals and other family members are stored as line deltas
+/// against an earlier version and rebuilt on read. One lock covers the whole
+/// map because replacing or removing a base has to rewrite its dependents.
+#[derive(Default)]
+pub struct CorpusStore {
+    entries: RwLock<HashMap<String, Entry>>,
+}
+
+impl CorpusStore {
+    /// Replaces any document with the same ID; versions built on the old
+    /// text are stored in full first.
+    pub fn insert(&self, mut doc: StoredDocument) {
+        let text = std::mem::take(&mut doc.text);
+        let mut entries = self.entries.write().unwrap();
+        detach_dependents(&mut entries, &doc.id);
+        let body = base_candidates(&entries, &doc)
+            .into_iter()
+            .find_map(|base_id| {
+                let base = entries.get(&base_id)?;
+                let base_text = materialize(&entries, &base_id)?;
+                delta_against(&base_id, base, &base_text, &text)
+            })
+            .unwrap_or(Body::Full(text));
+        entries.insert(doc.id.clone(), Entry { doc, body });
+    }
+
+    pub fn get(&self, id: &str) -> Option<StoredDocument> {
+        let entries = self.entries.read().unwrap();
+        let entry = entries.get(id)?;
+        Some(StoredDocument {
+            text: materialize(&entries, id)?,
+            ..entry.doc.clone()
+        })
+    }
+
+    pub fn remove(&self, id: &str) -> Option<StoredDocument> {
+        let mut entries = self.entries.write().unwrap();
+        let text = materialize(&entries, id)?;
+        detach_dependents(&mut entries, id);
+        entries
+            .remove(id)
+            .map(|entry| StoredDocument { text, ..entry.doc })
+    }
+
+    pub fn link_family(&self, ids: &[String], family_id: &str) {
+        let mut entries = self.entries.write().unwrap();
+        for id in ids {
+            if let Some(entry) = entries.get_mut(id) {
+                entry.doc.family_id = Some(family_id.to_string());
+            }
+        }
+    }
+
+    /// Snapshot of all documents, ordered by ID for stable output.
+    pub fn all(&self) -> Vec<StoredDocument> {
+        let entries = self.entries.read().unwrap();
+        let mut docs: Vec<StoredDocument> = entries
+            .iter()
+            .filter_map(|(id, entry)| {
+                Some(StoredDocument {
+                    text: materialize(&entries, id)?,
+                    ..entry.doc.clone()
+                })
+            })
+            .collect();
+        docs.sort_by(|a, b| a.id.cmp(&b.id));
+        docs
+    }
+
+    /// Every document ID, sorted, without rebuilding any text.
+    pub fn ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.entries.read().unwrap().keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    pub fn stats(&self) -> CorpusStats {
+        stats(&self.entries.read().unwrap())
+    }
+
+    /// Re-encodes every document against the best earlier version of the same
+    /// tenant: its parent, an earlier family member, one of the documents
+    /// submitted just before it, or a recent full text. Catches drafts that were resubmitted without
+    /// a link and chains left in full by erasure or replacement.
+    pub fn compact(&self) -> CompactionReport {
+        let started = Instant::now();
+        let mut entries = self.entries.write().unwrap();
+        let before = stats(&entries);
+
+        let mut order: Vec<(StoredDocument, String)> = entries
+            .iter()
+            .filter_map(|(id, e)| Some((e.doc.clone(), materialize(&entries, id)?)))
+            .collect();
+        order.sort_by(|(a, _), (b, _)| {
+            (&a.tenant, a.stored_at, &a.id).cmp(&(&b.tenant, b.stored_at, &b.id))
+        });
+
+        // Bases always come earlier in `order`, so the result has no cycles.
+        let mut compacted: HashMap<String, Entry> = HashMap::with_capacity(order.len());
+        for (i, (doc, text)) in order.iter().enumerate() {
+            let earlier = &order[..i];
+            let linked = earlier.iter().filter(|(d, _)| {
+                d.tenant == doc.tenant
+                    && (doc.parent_id.as_deref() == Some(d.id.as_str())
+                        || (doc.family_id.is_some() && d.family_id == doc.family_id))
+            });
+            let recent = earlier
+                .iter()
+                .rev()
+                .take_while(|(d, _)| d.tenant == doc.tenant)
+                .take(COMPACTION_WINDOW);
+            // Full texts keep long negotiations going once the recent
+            // drafts have all reached the depth limit.
+            let roots = earlier
+                .iter()
+                .rev()
+                .take_while(|(d, _)| d.tenant == doc.tenant)
+                .filter(|(d, _)| compacted.get(&d.id).is_some_and(|e| e.body.depth() == 0))
+                .take(COMPACTION_WINDOW);
+            let body = linked
+                .chain(recent)
+                .chain(roots)
+                .filter_map(|(d, base_text)| {
+                    delta_against(&d.id, compacted.get(&d.id)?, base_text, text)
+                })
+                .min_by_key(Body::stored_bytes)
+                .unwrap_or_else(|| Body::Full(text.clone()));
+            compacted.insert(
+                doc.id.clone(),
+                Entry {
+                    doc: doc.clone(),
+                    body,
+                },
+            );
+        }
+        *entries = compacted;
+
+        CompactionReport {
+            before,
+            after: stats(&entries),
+            duration_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// The parent, then the most recent other family member, of `doc`'s tenant.
+fn base_candidates(entries: &HashMap<String, Entry>, doc: &StoredDocument) -> Vec<String> {
+    let mut candidates: Vec<String> = doc
+        .parent_id
+        .iter()
+        .filter(|p| **p != doc.id && entries.get(*p).is_some_and(|e| e.doc.tenant == doc.tenant))
+        .cloned()
+        .collect();
+    if doc.family_id.is_some() {
+        let latest = entries
+            .values()
+            .map(|e| &e.doc)
+            .filter(|d| d.id != doc.id && d.tenant == doc.tenant && d.family_id == doc.family_id)
+            .max_by(|a, b| (a.stored_at, &a.id).cmp(&(b.stored_at, &b.id)));
+        candidates.extend(latest.map(|d| d.id.clone()));
+    }
+    candidates
+}
+
+/// Stores every version delta-encoded against `id` in full.
+fn detach_dependents(entries: &mut HashMap<String, Entry>, id: &str) {
+    let dependents: Vec<(String, String)> = entries
+        .iter()
+        .filter(|(_, e)| matches!(&e.body, Body::Delta { base, .. } if base == id))
+        .filter_map(|(dep, _)| Some((dep.clone(), materialize(entries, dep)?)))
+        .collect();
+    if dependents.is_empty() {
+        return;
+    }
+    for (dep, text) in dependents {
+        if let Some(entry) = entries.get_mut(&dep) {
+            entry.body = Body::Full(text);
+        }
+    }
+    // Anything stacked on a detached version is now one level shallower.
+    let depths: Vec<(String, usize)> = entries
+        .keys()
+        .map(|k| (k.clone(), chain_depth(entries, k)))
+        .collect();
+    for (k, depth) in depths {
+        if let Some(Entry {
+            body: Body::Delta { depth: d, .. },
+            ..
+        }) = entries.get_mut(&k)
+        {
+            *d = depth;
+        }
+    }
+}
+
+fn chain_depth(entries: &HashMap<String, Entry>, id: &str) -> usize {
+    match entries.get(id).map(|e| &e.body) {
+        Some(Body::Delta { base, .. }) => 1 + chain_depth(entries, base),
+        _ => 0,
+    }
+}
+
+/// Compacts on the configured interval for the life of the process.
+pub fn spawn_compaction(state: AppState, interval: Duration) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            let corpus = state.corpus.clone();
+            if let Ok(report) = tokio::task::spawn_blocking(move || corpus.compact()).await {
+                info!(
+                    stored_bytes = report.after.stored_bytes,
+                    text_bytes = report.after.text_bytes,
+                    "corpus compacted"
+                );
+            }
+        }
+    });
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn compact(State(state): State<AppState>) -> Json<CompactionReport> {
+    let report = state.corpus.compact();
+    info!(
+        documents = report.after.documents,
+        delta_encoded = report.after.delta_encoded,
+        stored_bytes_before = report.before.stored_bytes,
+        stored_bytes_after = report.after.stored_bytes,
+        "corpus compacted"
+    );
+    Json(report)
+}
+
+pub async fn storage_stats(State(state): State<AppState>) -> Json<CorpusStats> {
+    Json(state.corpus.stats())
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clause(n: usize) -> String {
+        format!(
+            "{n}. The Supplier shall perform obligation number {n} diligently and \
+             in accordance with good industry practice.\n"
+        )
+    }
+
+    fn draft(rounds: usize) -> String {
+        let mut text: String = (1..=40).map(clause).collect();
+        for r in 0..rounds {
+            text = text.replacen(
+                &clause(r + 1),
+                &format!("{}. Revised in round {r}.\n", r + 1),
+                1,
+            );
+        }
+        text
+    }
+
+    fn doc(id: &str, tenant: &str, text: &str, parent: Option<&str>) -> StoredDocument {
+        StoredDocument {
+            id: id.to_string(),
+            text: text.to_string(),
+            language: "en".to_string(),
+            tenant: tenant.to_string(),
+            stored_at: DateTime::<Utc>::from_timestamp(0, 0).unwrap(),
+            family_id: None,
+            parent_id: parent.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn delta_roundtrips() {
+        let base = "a\nb\nc\nd";
+        for text in ["a\nb\nc\nd", "a\nx\nc\nd\ne\n", "", "d", "b\na\n"] {
+            assert_eq!(apply(base, &encode(base, text)), text);
+        }
+    }
+
+    #[test]
+    fn amendment_is_stored_as_delta_and_reads_back_in_full() {
+        let store = CorpusStore::default();
+        store.insert(doc("v1", "t", &draft(0), None));
+        store.insert(doc("v2", "t", &draft(1), Some("v1")));
+        assert_eq!(store.get("v2").unwrap().text, draft(1));
+        assert_eq!(store.all()[1].text, draft(1));
+        let stats = store.stats();
+        assert_eq!(stats.delta_encoded, 1);
+        assert!(stats.stored_bytes < draft(0).len() + draft(1).len() / 10);
+    }
+
+    #[test]
+    fn other_tenants_are_never_used_as_base() {
+        let store = CorpusStore::default();
+        store.insert(doc("v1", "a", &draft(0), None));
+        store.insert(doc("v2", "b", &draft(1), Some("v1")));
+        assert_eq!(store.stats().delta_encoded, 0);
+    }
+
+    #[test]
+    fn replacing_or_removing_a_base_keeps_dependents_intact() {
+        let store = CorpusStore::default();
+        store.insert(doc("v1", "t", &draft(0), None));
+        store.insert(doc("v2", "t", &draft(1), Some("v1")));
+        store.insert(doc("v3", "t", &draft(2), Some("v2")));
+        store.insert(doc("v1", "t", "masked", None));
+        assert_eq!(store.get("v2").unwrap().text, draft(1));
+        store.remove("v2").unwrap();
+        assert_eq!(store.get("v3").unwrap().text, draft(2));
+        assert_eq!(store.stats().max_chain_depth, 0);
+    }
+
+    #[test]
+    fn compaction_links_unlinked_rounds_and_bounds_chains() {
+        let store = CorpusStore::default();
+        for r in 0..30 {
+            store.insert(doc(&format!("v{r:02}"), "t", &draft(r), None));
+        }
+        let report = store.compact();
+        assert_eq!(report.before.delta_encoded, 0);
+        assert_eq!(report.after.delta_encoded, 29);
+        assert!(report.after.stored_bytes * 10 < report.before.stored_bytes);
+        assert!(report.after.max_chain_depth <= MAX_CHAIN_DEPTH);
+        assert_eq!(report.after.text_bytes, report.before.text_bytes);
+        for r in 0..30 {
+            assert_eq!(store.get(&format!("v{r:02}")).unwrap().text, draft(r));
+        }
+    }
+}
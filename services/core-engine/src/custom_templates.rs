@@ -0,0 +1,966 @@
+//! Templates registered by legal teams next to the built-in ones. Each
+//! declares its variables and languages, is parsed when saved, and is
+//! listed and compiled like a built-in. With `LEGAL_CUSTOM_TEMPLATES_FILE`
+//! set they are kept in that JSON file, rewritten on every change, so they
+//! survive restarts. Each template belongs to the tenant that saved it and
+//! is invisible to the others, except to the entities below it in the
+//! organization hierarchy, which inherit it. An entity's template that
+//! `overrides` an inherited one takes its place for that entity and the
+//! entities below it. Every save is kept as a numbered version, and a
+//! compile can pin one, so the text behind a signed contract can always be
+//! shown.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::{
+    notifier::{self, DEFAULT_TENANT},
+    revisions::{diff_bodies, ClauseChange, DiffSummary},
+    warmup::ParsedTemplate,
+    AppState, BUILTIN_TEMPLATES,
+};
+
+const MAX_ID_LEN: usize = 64;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomTemplate {
+    pub id: String,
+    /// Files saved before templates were per tenant load as the default's.
+    #[serde(default = "default_tenant")]
+    pub tenant: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub required_variables: Vec<String>,
+    pub language_support: Vec<String>,
+    pub body: String,
+    /// The inherited template this one replaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overrides: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Saved before versioning: the first version.
+    #[serde(default = "first_version")]
+    pub version: u32,
+}
+
+fn default_tenant() -> String {
+    DEFAULT_TENANT.to_string()
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+/// A template as one save left it. Never changed afterwards, and kept when
+/// the template is deleted, so the text behind any compiled document can
+/// be recovered.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateVersion {
+    pub version: u32,
+    pub tenant: String,
+    pub name: String,
+    pub required_variables: Vec<String>,
+    pub language_support: Vec<String>,
+    pub body: String,
+    pub saved_at: DateTime<Utc>,
+}
+
+impl TemplateVersion {
+    fn of(template: &CustomTemplate) -> Self {
+        Self {
+            version: template.version,
+            tenant: template.tenant.clone(),
+            name: template.name.clone(),
+            required_variables: template.required_variables.clone(),
+            language_support: template.language_support.clone(),
+            body: template.body.clone(),
+            saved_at: template.updated_at,
+        }
+    }
+}
+
+/// The templates file. Files written before versioning hold the templates
+/// alone.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SavedFile<T, V> {
+    Versioned { templates: T, versions: V },
+    Unversioned(T),
+}
+
+/// Body of `POST` and `PUT`; `id` is taken from the path on `PUT`.
+#[derive(Debug, Deserialize)]
+pub struct TemplateRequest {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Must match the body's placeholders; derived from them when omitted.
+    #[serde(default)]
+    pub required_variables: Option<Vec<String>>,
+    #[serde(default)]
+    pub language_support: Option<Vec<String>>,
+    pub body: String,
+    /// ID of a template an ancestor entity owns, to replace for this tenant.
+    #[serde(default)]
+    pub overrides: Option<String>,
+}
+
+/// A saved template with its parsed body.
+#[derive(Debug, Clone)]
+pub struct Registered {
+    pub template: CustomTemplate,
+    pub parsed: ParsedTemplate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreError {
+    Exists,
+    NotFound,
+    /// The file could not be written; nothing was changed.
+    Io(String),
+}
+
+impl IntoResponse for StoreError {
+    fn into_response(self) -> Response {
+        match self {
+            StoreError::Exists => StatusCode::CONFLICT.into_response(),
+            StoreError::NotFound => StatusCode::NOT_FOUND.into_response(),
+            StoreError::Io(e) => {
+                warn!(error = %e, "custom templates not saved");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+// ── Validation ────────────────────────────────────────────────────────────────
+
+pub(crate) fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_'))
+}
+
+/// The saved form of `req`, or why it cannot be saved.
+fn build(
+    id: String,
+    tenant: &str,
+    req: TemplateRequest,
+    created_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<Registered, String> {
+    if req.name.trim().is_empty() {
+        return Err("name is required".to_string());
+    }
+    if req.body.trim().is_empty() {
+        return Err("body is required".to_string());
+    }
+    let parsed = ParsedTemplate::parse(&req.body)?;
+    let placeholders: BTreeSet<String> = parsed
+        .placeholders()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let required_variables = match req.required_variables {
+        Some(declared) => {
+            let set: BTreeSet<String> = declared.iter().cloned().collect();
+            if set != placeholders {
+                return Err(format!(
+                    "placeholders {placeholders:?} do not match required variables {set:?}"
+                ));
+            }
+            declared
+        }
+        None => placeholders.into_iter().collect(),
+    };
+    let language_support = req
+        .language_support
+        .unwrap_or_else(|| vec!["en".to_string()]);
+    if language_support.is_empty() || language_support.iter().any(|l| l.trim().is_empty()) {
+        return Err("language_support must list at least one language".to_string());
+    }
+    Ok(Registered {
+        template: CustomTemplate {
+            id,
+            tenant: tenant.to_string(),
+            name: req.name,
+            description: req.description,
+            required_variables,
+            language_support,
+            body: req.body,
+            overrides: req.overrides,
+            created_at,
+            updated_at: now,
+            version: 1,
+        },
+        parsed,
+    })
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct CustomTemplateStore {
+    /// Kept in memory only when `None`.
+    path: Option<PathBuf>,
+    templates: RwLock<BTreeMap<String, Arc<Registered>>>,
+    /// Oldest first, by template ID. Locked after `templates`.
+    versions: RwLock<BTreeMap<String, Vec<TemplateVersion>>>,
+}
+
+impl CustomTemplateStore {
+    /// Loads `path`, starting empty when it does not exist yet.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let saved: SavedFile<Vec<CustomTemplate>, BTreeMap<String, Vec<TemplateVersion>>> =
+            match std::fs::read_to_string(&path) {
+                Ok(raw) => serde_json::from_str(&raw).map_err(|e| e.to_string())?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    SavedFile::Unversioned(Vec::new())
+                }
+                Err(e) => return Err(e.to_string()),
+            };
+        let (saved, mut versions) = match saved {
+            SavedFile::Versioned {
+                templates,
+                versions,
+            } => (templates, versions),
+            SavedFile::Unversioned(templates) => (templates, BTreeMap::new()),
+        };
+        let mut templates = BTreeMap::new();
+        for template in saved {
+            let parsed = ParsedTemplate::parse(&template.body)
+                .map_err(|e| format!("{}: {e}", template.id))?;
+            versions
+                .entry(template.id.clone())
+                .or_insert_with(|| vec![TemplateVersion::of(&template)]);
+            templates.insert(
+                template.id.clone(),
+                Arc::new(Registered { template, parsed }),
+            );
+        }
+        Ok(Self {
+            path: Some(path),
+            templates: RwLock::new(templates),
+            versions: RwLock::new(versions),
+        })
+    }
+
+    pub fn from_env() -> Self {
+        match std::env::var("LEGAL_CUSTOM_TEMPLATES_FILE") {
+            Ok(path) => Self::open(&path)
+                .unwrap_or_else(|e| panic!("cannot load custom templates {path}: {e}")),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// IDs are unique across tenants, but each only sees its own.
+    pub fn get(&self, tenant: &str, id: &str) -> Option<Arc<Registered>> {
+        self.templates
+            .read()
+            .unwrap()
+            .get(id)
+            .filter(|r| r.template.tenant == tenant)
+            .cloned()
+    }
+
+    /// The tenant that saved template `id`, even if it has since deleted it.
+    pub fn owner(&self, id: &str) -> Option<String> {
+        let templates = self.templates.read().unwrap();
+        match templates.get(id) {
+            Some(r) => Some(r.template.tenant.clone()),
+            None => self
+                .versions
+                .read()
+                .unwrap()
+                .get(id)
+                .and_then(|h| h.last())
+                .map(|v| v.tenant.clone()),
+        }
+    }
+
+    /// Template `id` as the first tenant of `lineage` sees it: the nearest
+    /// template that is `id` or overrides it.
+    pub fn resolve(&self, lineage: &[String], id: &str) -> Option<Arc<Registered>> {
+        let templates = self.templates.read().unwrap();
+        lineage.iter().find_map(|tenant| {
+            templates
+                .values()
+                .filter(|r| r.template.tenant == *tenant)
+                .find(|r| r.template.id == id || r.template.overrides.as_deref() == Some(id))
+                .cloned()
+        })
+    }
+
+    /// Templates the first tenant of `lineage` sees: those of every tenant
+    /// in it, less the ones a nearer template overrides.
+    pub fn visible(&self, lineage: &[String]) -> Vec<CustomTemplate> {
+        let templates = self.templates.read().unwrap();
+        let owned: Vec<&CustomTemplate> = templates
+            .values()
+            .map(|r| &r.template)
+            .filter(|t| lineage.contains(&t.tenant))
+            .collect();
+        owned
+            .iter()
+            .filter(|t| !owned.iter().any(|o| o.overrides.as_ref() == Some(&t.id)))
+            .map(|t| (*t).clone())
+            .collect()
+    }
+
+    /// IDs of the templates that use macro `name`.
+    pub fn using_macro(&self, name: &str) -> Vec<String> {
+        self.templates
+            .read()
+            .unwrap()
+            .values()
+            .filter(|r| r.parsed.macros().contains(name))
+            .map(|r| r.template.id.clone())
+            .collect()
+    }
+
+    pub fn list(&self, tenant: &str) -> Vec<CustomTemplate> {
+        self.templates
+            .read()
+            .unwrap()
+            .values()
+            .filter(|r| r.template.tenant == tenant)
+            .map(|r| r.template.clone())
+            .collect()
+    }
+
+    /// Every saved version of template `id`, oldest first, with the tenant
+    /// that saved them.
+    pub fn versions(&self, id: &str) -> Option<Vec<TemplateVersion>> {
+        self.versions.read().unwrap().get(id).cloned()
+    }
+
+    /// `current` as it was at `version`, or `None` if it never had one.
+    pub fn pinned(&self, current: &Registered, version: u32) -> Option<Arc<Registered>> {
+        let versions = self.versions.read().unwrap();
+        let saved = versions
+            .get(&current.template.id)?
+            .iter()
+            .find(|v| v.version == version)?;
+        // Parsed when it was saved, so it parses now.
+        let parsed = ParsedTemplate::parse(&saved.body).ok()?;
+        let template = CustomTemplate {
+            name: saved.name.clone(),
+            required_variables: saved.required_variables.clone(),
+            language_support: saved.language_support.clone(),
+            body: saved.body.clone(),
+            updated_at: saved.saved_at,
+            version,
+            ..current.template.clone()
+        };
+        Some(Arc::new(Registered { template, parsed }))
+    }
+
+    /// Adds `registered`, or replaces an existing one when `replace` is set,
+    /// as the next version. The ID of a deleted template stays with its
+    /// tenant, whose next save continues the numbering.
+    pub fn save(
+        &self,
+        mut registered: Registered,
+        replace: bool,
+    ) -> Result<CustomTemplate, StoreError> {
+        let mut templates = self.templates.write().unwrap();
+        let mut versions = self.versions.write().unwrap();
+        let template = &mut registered.template;
+        let exists = templates.contains_key(&template.id);
+        match (exists, replace) {
+            (true, false) => return Err(StoreError::Exists),
+            (false, true) => return Err(StoreError::NotFound),
+            _ => {}
+        }
+        let history = versions.get(&template.id);
+        if history.is_some_and(|h| h.iter().any(|v| v.tenant != template.tenant)) {
+            return Err(StoreError::Exists);
+        }
+        template.version = history.and_then(|h| h.last()).map_or(1, |v| v.version + 1);
+        let mut next_versions = versions.clone();
+        next_versions
+            .entry(template.id.clone())
+            .or_default()
+            .push(TemplateVersion::of(template));
+        self.commit(&mut templates, &mut versions, registered, next_versions)
+    }
+
+    /// Puts `registered` back with the `history` it had elsewhere (a
+    /// tenant migration), replacing any template and history under its ID.
+    pub fn restore(
+        &self,
+        registered: Registered,
+        history: Vec<TemplateVersion>,
+    ) -> Result<CustomTemplate, StoreError> {
+        let mut templates = self.templates.write().unwrap();
+        let mut versions = self.versions.write().unwrap();
+        let mut next_versions = versions.clone();
+        next_versions.insert(registered.template.id.clone(), history);
+        self.commit(&mut templates, &mut versions, registered, next_versions)
+    }
+
+    fn commit(
+        &self,
+        templates: &mut BTreeMap<String, Arc<Registered>>,
+        versions: &mut BTreeMap<String, Vec<TemplateVersion>>,
+        registered: Registered,
+        next_versions: BTreeMap<String, Vec<TemplateVersion>>,
+    ) -> Result<CustomTemplate, StoreError> {
+        let saved = registered.template.clone();
+        let mut next = templates.clone();
+        next.insert(saved.id.clone(), Arc::new(registered));
+        self.persist(&next, &next_versions)?;
+        *templates = next;
+        *versions = next_versions;
+        Ok(saved)
+    }
+
+    pub fn remove(&self, tenant: &str, id: &str) -> Result<CustomTemplate, StoreError> {
+        let mut templates = self.templates.write().unwrap();
+        if templates
+            .get(id)
+            .is_none_or(|r| r.template.tenant != tenant)
+        {
+            return Err(StoreError::NotFound);
+        }
+        let mut next = templates.clone();
+        let removed = next.remove(id).ok_or(StoreError::NotFound)?;
+        self.persist(&next, &self.versions.read().unwrap())?;
+        *templates = next;
+        Ok(removed.template.clone())
+    }
+
+    /// Writes a sibling file and renames it over `path`, so a crash never
+    /// leaves a half-written file behind.
+    fn persist(
+        &self,
+        templates: &BTreeMap<String, Arc<Registered>>,
+        versions: &BTreeMap<String, Vec<TemplateVersion>>,
+    ) -> Result<(), StoreError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let saved = SavedFile::Versioned {
+            templates: templates.values().map(|r| &r.template).collect::<Vec<_>>(),
+            versions,
+        };
+        let raw =
+            serde_json::to_string_pretty(&saved).map_err(|e| StoreError::Io(e.to_string()))?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, raw)
+            .and_then(|_| std::fs::rename(&tmp, path))
+            .map_err(|e| StoreError::Io(e.to_string()))
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+fn rejected(id: &str, error: String) -> Response {
+    info!(template_id = %id, error = %error, "custom template rejected");
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(json!({ "error": error })),
+    )
+        .into_response()
+}
+
+/// Why `registered` cannot override what it names: the template must be
+/// owned by one of `tenant`'s ancestors and not overridden by another of
+/// `tenant`'s templates already.
+pub(crate) fn invalid_override(
+    state: &AppState,
+    tenant: &str,
+    registered: &Registered,
+) -> Option<String> {
+    let target = registered.template.overrides.as_deref()?;
+    let lineage = state.orgs.lineage(tenant);
+    let inherited = state
+        .custom_templates
+        .resolve(&lineage[1..], target)
+        .is_some_and(|r| r.template.id == target);
+    if !inherited {
+        return Some(format!(
+            "overrides {target}, which no parent entity of {tenant} owns"
+        ));
+    }
+    state
+        .custom_templates
+        .list(tenant)
+        .into_iter()
+        .find(|t| t.id != registered.template.id && t.overrides.as_deref() == Some(target))
+        .map(|t| format!("{target} is already overridden by {}", t.id))
+}
+
+/// Why `registered` cannot be compiled against the macro and clause
+/// libraries as they are: an unknown macro or an unresolvable clause.
+pub(crate) fn unknown_fragment(state: &AppState, registered: &Registered) -> Option<String> {
+    let parsed = &registered.parsed;
+    if let Some(name) = parsed
+        .macros()
+        .into_iter()
+        .find(|m| !state.macros.contains(m))
+    {
+        return Some(format!("unknown macro {name}"));
+    }
+    state.clause_library.resolve_all(parsed.clauses()).err()
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(mut req): Json<TemplateRequest>,
+) -> Result<(StatusCode, Json<CustomTemplate>), Response> {
+    let id = req.id.take().unwrap_or_default();
+    if !valid_id(&id) {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+    if BUILTIN_TEMPLATES.contains(&id.as_str()) {
+        return Err(StatusCode::CONFLICT.into_response());
+    }
+    let now = state.clock.now();
+    let tenant = notifier::tenant(&headers);
+    let registered = build(id.clone(), &tenant, req, now, now).map_err(|e| rejected(&id, e))?;
+    if let Some(e) = unknown_fragment(&state, &registered) {
+        return Err(rejected(&id, e));
+    }
+    if let Some(e) = invalid_override(&state, &tenant, &registered) {
+        return Err(rejected(&id, e));
+    }
+    let template = state
+        .custom_templates
+        .save(registered, false)
+        .map_err(IntoResponse::into_response)?;
+    info!(template_id = %id, "custom template created");
+    state.record_audit(
+        "template.created",
+        &id,
+        None,
+        json!({ "required_variables": template.required_variables }),
+    );
+    Ok((StatusCode::CREATED, Json(template)))
+}
+
+pub async fn update(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<TemplateRequest>,
+) -> Result<Json<CustomTemplate>, Response> {
+    let tenant = notifier::tenant(&headers);
+    if BUILTIN_TEMPLATES.contains(&id.as_str()) {
+        // Built-ins change through revisions.
+        return Err(StatusCode::CONFLICT.into_response());
+    }
+    if req.id.as_ref().is_some_and(|body_id| *body_id != id) {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+    let existing = state
+        .custom_templates
+        .get(&tenant, &id)
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+    let registered = build(
+        id.clone(),
+        &tenant,
+        req,
+        existing.template.created_at,
+        state.clock.now(),
+    )
+    .map_err(|e| rejected(&id, e))?;
+    if let Some(e) = unknown_fragment(&state, &registered) {
+        return Err(rejected(&id, e));
+    }
+    if let Some(e) = invalid_override(&state, &tenant, &registered) {
+        return Err(rejected(&id, e));
+    }
+    let template = state
+        .custom_templates
+        .save(registered, true)
+        .map_err(IntoResponse::into_response)?;
+    info!(template_id = %id, "custom template updated");
+    state.record_audit(
+        "template.updated",
+        &id,
+        None,
+        json!({ "required_variables": template.required_variables }),
+    );
+    Ok(Json(template))
+}
+
+#[derive(Debug, Serialize)]
+pub struct VersionEntry {
+    #[serde(flatten)]
+    pub version: TemplateVersion,
+    /// Against the version before; absent for the first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<DiffSummary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<ClauseChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VersionsResponse {
+    pub template_id: String,
+    pub versions: Vec<VersionEntry>,
+}
+
+/// The versions of template `id` as the tenant sees it, oldest first, each
+/// diffed against the one before. A deleted template keeps its history for
+/// the tenant that saved it. Built-ins change through revisions instead.
+pub async fn versions(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<VersionsResponse>, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    let store = &state.custom_templates;
+    let history = match store.resolve(&state.orgs.lineage(&tenant), &id) {
+        Some(registered) => store.versions(&registered.template.id),
+        None if store.owner(&id).as_deref() == Some(tenant.as_str()) => store.versions(&id),
+        None => None,
+    }
+    .ok_or(StatusCode::NOT_FOUND)?;
+    let mut previous: Option<&str> = None;
+    let mut versions = Vec::with_capacity(history.len());
+    for version in &history {
+        let (summary, changes) = match previous {
+            Some(before) => {
+                let (changes, summary) = diff_bodies(before, &version.body);
+                (Some(summary), changes)
+            }
+            None => (None, Vec::new()),
+        };
+        previous = Some(&version.body);
+        versions.push(VersionEntry {
+            version: version.clone(),
+            summary,
+            changes,
+        });
+    }
+    Ok(Json(VersionsResponse {
+        template_id: id,
+        versions,
+    }))
+}
+
+/// Saves version `n` of template `id` again as its next version, so rolling
+/// back adds to the history instead of rewriting it. A deleted template comes
+/// back for the tenant that saved it. Versions do not record the description
+/// or override, so those stay as the current template has them.
+pub async fn restore_version(
+    State(state): State<AppState>,
+    Path((id, n)): Path<(String, u32)>,
+    headers: HeaderMap,
+) -> Result<Json<CustomTemplate>, Response> {
+    let tenant = notifier::tenant(&headers);
+    if BUILTIN_TEMPLATES.contains(&id.as_str()) {
+        return Err(StatusCode::CONFLICT.into_response());
+    }
+    let store = &state.custom_templates;
+    if store.owner(&id).as_deref() != Some(tenant.as_str()) {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    }
+    let version = store
+        .versions(&id)
+        .and_then(|history| history.into_iter().find(|v| v.version == n))
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+    let existing = store.get(&tenant, &id);
+    let now = state.clock.now();
+    let (description, overrides, created_at) = match &existing {
+        Some(current) => (
+            current.template.description.clone(),
+            current.template.overrides.clone(),
+            current.template.created_at,
+        ),
+        None => (String::new(), None, now),
+    };
+    let req = TemplateRequest {
+        id: None,
+        name: version.name,
+        description,
+        required_variables: Some(version.required_variables),
+        language_support: Some(version.language_support),
+        body: version.body,
+        overrides,
+    };
+    let registered =
+        build(id.clone(), &tenant, req, created_at, now).map_err(|e| rejected(&id, e))?;
+    if let Some(e) = unknown_fragment(&state, &registered) {
+        return Err(rejected(&id, e));
+    }
+    if let Some(e) = invalid_override(&state, &tenant, &registered) {
+        return Err(rejected(&id, e));
+    }
+    let template = store
+        .save(registered, existing.is_some())
+        .map_err(IntoResponse::into_response)?;
+    info!(template_id = %id, from = n, version = template.version, "custom template restored");
+    state.record_audit(
+        "template.restored",
+        &id,
+        None,
+        json!({ "from_version": n, "version": template.version }),
+    );
+    Ok(Json(template))
+}
+
+pub async fn delete(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, Response> {
+    if BUILTIN_TEMPLATES.contains(&id.as_str()) {
+        return Err(StatusCode::CONFLICT.into_response());
+    }
+    state
+        .custom_templates
+        .remove(&notifier::tenant(&headers), &id)
+        .map_err(IntoResponse::into_response)?;
+    info!(template_id = %id, "custom template deleted");
+    state.record_audit("template.deleted", &id, None, json!({}));
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(body: &str, required: Option<&[&str]>) -> TemplateRequest {
+        TemplateRequest {
+            id: None,
+            name: "Consulting Agreement".to_string(),
+            description: String::new(),
+            required_variables: required.map(|r| r.iter().map(|s| s.to_string()).collect()),
+            language_support: None,
+            body: body.to_string(),
+            overrides: None,
+        }
+    }
+
+    fn registered(id: &str) -> Registered {
+        let now = Utc::now();
+        build(
+            id.to_string(),
+            DEFAULT_TENANT,
+            request("{{client}} engages {{consultant}}.", None),
+            now,
+            now,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn required_variables_must_match_placeholders() {
+        let now = Utc::now();
+        let body = "{{client}} engages {{consultant}}.";
+        let derived = build("c".into(), DEFAULT_TENANT, request(body, None), now, now).unwrap();
+        assert_eq!(
+            derived.template.required_variables,
+            ["client", "consultant"]
+        );
+        assert_eq!(derived.template.language_support, ["en"]);
+        let err = build(
+            "c".into(),
+            DEFAULT_TENANT,
+            request(body, Some(&["client"])),
+            now,
+            now,
+        )
+        .unwrap_err();
+        assert!(err.contains("do not match"), "{err}");
+        assert!(build(
+            "c".into(),
+            DEFAULT_TENANT,
+            request("{{#if x}}", None),
+            now,
+            now
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn ids_are_lowercase_slugs() {
+        assert!(valid_id("consulting-v2_en"));
+        assert!(!valid_id(""));
+        assert!(!valid_id("Consulting"));
+        assert!(!valid_id("a/b"));
+        assert!(!valid_id(&"a".repeat(MAX_ID_LEN + 1)));
+    }
+
+    #[test]
+    fn file_backed_store_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("templates.json");
+        let store = CustomTemplateStore::open(&path).unwrap();
+        store.save(registered("consulting"), false).unwrap();
+        store.save(registered("retainer"), false).unwrap();
+        assert_eq!(
+            store.save(registered("consulting"), false),
+            Err(StoreError::Exists)
+        );
+        store.remove(DEFAULT_TENANT, "retainer").unwrap();
+
+        let reopened = CustomTemplateStore::open(&path).unwrap();
+        let ids: Vec<String> = reopened
+            .list(DEFAULT_TENANT)
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(ids, ["consulting"]);
+        assert!(reopened.list("acme").is_empty());
+        assert!(reopened.get("acme", "consulting").is_none());
+        assert_eq!(
+            reopened.remove("acme", "consulting"),
+            Err(StoreError::NotFound)
+        );
+        assert_eq!(
+            reopened
+                .get(DEFAULT_TENANT, "consulting")
+                .unwrap()
+                .template
+                .name,
+            "Consulting Agreement"
+        );
+    }
+
+    #[test]
+    fn entities_inherit_and_override_their_ancestors_templates() {
+        let store = CustomTemplateStore::default();
+        let now = Utc::now();
+        let owned = |id: &str, tenant: &str, overrides: Option<&str>| {
+            let mut req = request("{{client}} engages {{consultant}}.", None);
+            req.overrides = overrides.map(str::to_string);
+            build(id.to_string(), tenant, req, now, now).unwrap()
+        };
+        store
+            .save(owned("consulting", "group", None), false)
+            .unwrap();
+        store.save(owned("retainer", "group", None), false).unwrap();
+        store
+            .save(owned("consulting-de", "de", Some("consulting")), false)
+            .unwrap();
+        let de = ["de".to_string(), "group".to_string()];
+
+        let resolved = store.resolve(&de, "consulting").unwrap();
+        assert_eq!(resolved.template.id, "consulting-de");
+        assert_eq!(
+            store.resolve(&de, "retainer").unwrap().template.tenant,
+            "group"
+        );
+        assert_eq!(
+            store.resolve(&de[1..], "consulting").unwrap().template.id,
+            "consulting"
+        );
+        assert!(store.resolve(&["us".to_string()], "consulting").is_none());
+
+        let ids: Vec<String> = store.visible(&de).into_iter().map(|t| t.id).collect();
+        assert_eq!(ids, ["consulting-de", "retainer"]);
+    }
+
+    #[test]
+    fn failed_write_leaves_store_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CustomTemplateStore::open(dir.path().join("missing/templates.json")).unwrap();
+        assert!(matches!(
+            store.save(registered("consulting"), false),
+            Err(StoreError::Io(_))
+        ));
+        assert!(store.get(DEFAULT_TENANT, "consulting").is_none());
+    }
+
+    #[test]
+    fn every_save_is_kept_as_a_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("templates.json");
+        let store = CustomTemplateStore::open(&path).unwrap();
+        assert_eq!(
+            store.save(registered("consulting"), false).unwrap().version,
+            1
+        );
+        let now = Utc::now();
+        let edited = build(
+            "consulting".into(),
+            DEFAULT_TENANT,
+            request("{{client}} retains {{consultant}}.", None),
+            now,
+            now,
+        )
+        .unwrap();
+        assert_eq!(store.save(edited, true).unwrap().version, 2);
+
+        let reopened = CustomTemplateStore::open(&path).unwrap();
+        let current = reopened.get(DEFAULT_TENANT, "consulting").unwrap();
+        let bodies: Vec<String> = reopened
+            .versions("consulting")
+            .unwrap()
+            .into_iter()
+            .map(|v| v.body)
+            .collect();
+        assert_eq!(
+            bodies,
+            [
+                "{{client}} engages {{consultant}}.",
+                "{{client}} retains {{consultant}}."
+            ]
+        );
+        let first = reopened.pinned(&current, 1).unwrap();
+        assert_eq!(first.template.body, "{{client}} engages {{consultant}}.");
+        assert!(reopened.pinned(&current, 3).is_none());
+
+        // Deleting keeps the history, and the ID with its tenant.
+        reopened.remove(DEFAULT_TENANT, "consulting").unwrap();
+        assert_eq!(reopened.versions("consulting").unwrap().len(), 2);
+        let mut other = registered("consulting");
+        other.template.tenant = "acme".to_string();
+        assert_eq!(reopened.save(other, false), Err(StoreError::Exists));
+        assert_eq!(
+            reopened
+                .save(registered("consulting"), false)
+                .unwrap()
+                .version,
+            3
+        );
+    }
+
+    #[test]
+    fn files_from_before_versioning_start_at_version_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("templates.json");
+        let mut legacy = serde_json::to_value(&registered("consulting").template).unwrap();
+        legacy.as_object_mut().unwrap().remove("version");
+        std::fs::write(&path, json!([legacy]).to_string()).unwrap();
+
+        let store = CustomTemplateStore::open(&path).unwrap();
+        assert_eq!(
+            store
+                .get(DEFAULT_TENANT, "consulting")
+                .unwrap()
+                .template
+                .version,
+            1
+        );
+        assert_eq!(store.versions("consulting").unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,284 @@
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::AppState;
+
+/// Client-supplied deadline: either a relative budget in milliseconds
+/// (`"1500"`) or an absolute RFC 3339 timestamp.
+pub const DEADLINE_HEADER: &str = "x-request-deadline";
+
+// Routes that run the analysis pipeline get the longer analysis timeout.
+const ANALYSIS_PATHS: [&str; 8] = [
+    "/api/v1/legal/analyze",
+    "/alice.legal.v1.LegalEngine/Analyze",
+    "/alice.legal.v1.LegalEngine/ScoreRisk",
+    "/api/v1/legal/diligence/export",
+    "/api/v1/legal/risk-score",
+    "/api/v1/legal/calibration/evaluate",
+    "/api/v1/legal/regulatory/scan",
+    "/api/v1/legal/export-control/check",
+];
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct TimeoutConfig {
+    pub default: Duration,
+    pub analyze: Duration,
+    pub compile: Duration,
+    /// Deep analysis stops starting optional stages once less than this
+    /// much of the deadline remains, leaving time to return partial results.
+    pub soft_margin: Duration,
+    /// Tokens the pipeline is assumed to process per millisecond when
+    /// deciding what fits in a latency budget.
+    pub bounded_tokens_per_ms: usize,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            default: Duration::from_secs(10),
+            analyze: Duration::from_secs(30),
+            compile: Duration::from_secs(5),
+            soft_margin: Duration::from_millis(250),
+            bounded_tokens_per_ms: 50,
+        }
+    }
+}
+
+fn env_millis(key: &str, fallback: Duration) -> Duration {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .map_or(fallback, Duration::from_millis)
+}
+
+impl TimeoutConfig {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        Self {
+            default: env_millis("LEGAL_TIMEOUT_DEFAULT_MS", d.default),
+            analyze: env_millis("LEGAL_TIMEOUT_ANALYZE_MS", d.analyze),
+            compile: env_millis("LEGAL_TIMEOUT_COMPILE_MS", d.compile),
+            soft_margin: env_millis("LEGAL_DEADLINE_SOFT_MARGIN_MS", d.soft_margin),
+            bounded_tokens_per_ms: std::env::var("LEGAL_BOUNDED_TOKENS_PER_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(d.bounded_tokens_per_ms),
+        }
+    }
+
+    /// A latency budget for one analysis, never longer than what is left of
+    /// the request's own deadline.
+    pub fn latency_budget(&self, budget: Duration, request: Deadline) -> LatencyBudget {
+        let budget = budget.min(request.remaining());
+        LatencyBudget {
+            deadline: Deadline::after(budget, self.soft_margin),
+            budget,
+            tokens_per_ms: self.bounded_tokens_per_ms,
+        }
+    }
+
+    pub fn for_path(&self, path: &str) -> Duration {
+        if ANALYSIS_PATHS.iter().any(|p| path.starts_with(p)) {
+            self.analyze
+        } else if path.ends_with("/compile") || path == "/alice.legal.v1.LegalEngine/Compile" {
+            self.compile
+        } else {
+            self.default
+        }
+    }
+}
+
+// ── Deadline ──────────────────────────────────────────────────────────────────
+
+/// The effective deadline of one request, handed to handlers as a request
+/// extension so pipeline stages can check how much time is left.
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    at: Instant,
+    soft_margin: Duration,
+}
+
+impl Deadline {
+    pub fn after(budget: Duration, soft_margin: Duration) -> Self {
+        Self {
+            at: Instant::now() + budget,
+            soft_margin,
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+
+    /// True once optional work should no longer be started.
+    pub fn soft_expired(&self) -> bool {
+        self.remaining() <= self.soft_margin
+    }
+}
+
+/// Latency-bounded analysis: the caller's budget and the throughput used to
+/// decide, pass by pass, what still fits in it.
+#[derive(Debug, Clone, Copy)]
+pub struct LatencyBudget {
+    pub deadline: Deadline,
+    pub budget: Duration,
+    pub tokens_per_ms: usize,
+}
+
+impl LatencyBudget {
+    /// Input size the whole budget can process; larger documents are truncated.
+    pub fn max_tokens(&self) -> usize {
+        let ms = usize::try_from(self.budget.as_millis()).unwrap_or(usize::MAX);
+        ms.saturating_mul(self.tokens_per_ms).max(1)
+    }
+
+    /// Whether a pass over `tokens` tokens can finish before the soft deadline.
+    pub fn fits(&self, tokens: usize) -> bool {
+        let usable = self
+            .deadline
+            .remaining()
+            .saturating_sub(self.deadline.soft_margin);
+        tokens as u128 <= usable.as_millis() * self.tokens_per_ms as u128
+    }
+}
+
+/// Remaining budget requested by the client; `None` if unparseable.
+/// Deadlines already in the past yield a zero budget.
+pub fn parse_client_deadline(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(ms) = value.parse::<u64>() {
+        return Some(Duration::from_millis(ms));
+    }
+    let at = DateTime::parse_from_rfc3339(value).ok()?;
+    Some(
+        (at.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+// ── Middleware ────────────────────────────────────────────────────────────────
+
+/// Applies the route timeout, tightened by `X-Request-Deadline` when the
+/// client's budget is shorter, and answers 504 when it runs out.
+pub async fn enforce(State(state): State<AppState>, mut req: Request, next: Next) -> Response {
+    let mut budget = state.timeouts.for_path(req.uri().path());
+    if let Some(header) = req.headers().get(DEADLINE_HEADER) {
+        let client = header
+            .to_str()
+            .ok()
+            // Deadlines are real time, even when the app clock is fixed.
+            .and_then(|v| parse_client_deadline(v, Utc::now()));
+        match client {
+            Some(client) => budget = budget.min(client),
+            None => return StatusCode::BAD_REQUEST.into_response(),
+        }
+    }
+    if budget.is_zero() {
+        return StatusCode::GATEWAY_TIMEOUT.into_response();
+    }
+
+    let path = req.uri().path().to_string();
+    req.extensions_mut()
+        .insert(Deadline::after(budget, state.timeouts.soft_margin));
+    match tokio::time::timeout(budget, next.run(req)).await {
+        Ok(resp) => resp,
+        Err(_) => {
+            warn!(path = %path, budget_ms = budget.as_millis() as u64, "request deadline exceeded");
+            StatusCode::GATEWAY_TIMEOUT.into_response()
+        }
+    }
+}
+
+// ── Analysis modes ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisMode {
+    /// Caps input size, truncating oversized documents with a report.
+    Quick,
+    #[default]
+    Standard,
+    /// Runs the optional stages too, skipping whichever would start after
+    /// the soft deadline.
+    Deep,
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn analysis_routes_get_the_longer_timeout() {
+        let cfg = TimeoutConfig::default();
+        assert_eq!(cfg.for_path("/api/v1/legal/analyze"), cfg.analyze);
+        assert_eq!(cfg.for_path("/api/v1/legal/analyze/bundle"), cfg.analyze);
+        assert_eq!(cfg.for_path("/api/v1/legal/compile"), cfg.compile);
+        assert_eq!(
+            cfg.for_path("/api/v1/legal/wizard-sessions/x/compile"),
+            cfg.compile
+        );
+        assert_eq!(cfg.for_path("/health"), cfg.default);
+    }
+
+    #[test]
+    fn client_deadline_formats() {
+        let now = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            parse_client_deadline("1500", now),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            parse_client_deadline("2026-01-01T00:00:02Z", now),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            parse_client_deadline("2025-12-31T23:59:00Z", now),
+            Some(Duration::ZERO)
+        );
+        assert!(parse_client_deadline("soon", now).is_none());
+    }
+
+    #[test]
+    fn soft_deadline_precedes_hard_deadline() {
+        let d = Deadline::after(Duration::from_millis(100), Duration::from_millis(250));
+        assert!(d.soft_expired());
+        assert!(d.remaining() > Duration::ZERO);
+        let d = Deadline::after(Duration::from_secs(60), Duration::from_millis(250));
+        assert!(!d.soft_expired());
+    }
+
+    #[test]
+    fn latency_budget_is_capped_by_the_request_and_sizes_passes() {
+        let cfg = TimeoutConfig::default();
+        let request = Deadline::after(Duration::from_secs(1), cfg.soft_margin);
+        let b = cfg.latency_budget(Duration::from_secs(5), request);
+        assert!(b.budget <= Duration::from_secs(1));
+
+        let b = cfg.latency_budget(
+            Duration::from_secs(2),
+            Deadline::after(Duration::from_secs(30), cfg.soft_margin),
+        );
+        assert_eq!(b.max_tokens(), 100_000);
+        assert!(b.fits(10_000));
+        // 1.75 s of usable time at 50 tokens/ms.
+        assert!(!b.fits(90_000));
+    }
+}
@@ -0,0 +1,380 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+    auth,
+    corpus::StoredDocument,
+    evidence::DEFAULT_CONTEXT_CHARS,
+    export_control, regulatory,
+    workbook::{self, Cell, Sheet},
+    AppState, Issue,
+};
+
+const MAX_DOCUMENTS: usize = 2_000;
+
+const CONTRACT_COLUMNS: [&str; 24] = [
+    "document_id",
+    "title",
+    "family_id",
+    "language",
+    "word_count",
+    "governing_law",
+    "termination_notice_days",
+    "auto_renewal",
+    "assignment_restricted",
+    "assignment_consent_required",
+    "change_of_control",
+    "exclusivity",
+    "non_compete",
+    "liability_cap",
+    "indemnification",
+    "clauses_detected",
+    "export_control_findings",
+    "deprecated_language",
+    "critical_issues",
+    "high_issues",
+    "total_issues",
+    "risk_score",
+    "risk_level",
+    "top_risk_factor",
+];
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Xlsx,
+    Csv,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiligenceRequest {
+    /// Analysis IDs of the documents in the data room.
+    #[serde(default)]
+    pub document_ids: Vec<String>,
+    /// Alternatively (or additionally), every member of these families.
+    #[serde(default)]
+    pub family_ids: Vec<String>,
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Key commercial terms a due-diligence reviewer tabulates per contract.
+#[derive(Debug, Default, PartialEq)]
+pub struct KeyTerms {
+    pub governing_law: Option<String>,
+    pub termination_notice_days: Option<u32>,
+    pub auto_renewal: bool,
+    pub assignment_restricted: bool,
+    pub assignment_consent_required: bool,
+    pub change_of_control: bool,
+    pub exclusivity: bool,
+    pub non_compete: bool,
+    pub liability_cap: bool,
+    pub indemnification: bool,
+}
+
+// ── Extraction ────────────────────────────────────────────────────────────────
+
+fn sentences(text: &str) -> impl Iterator<Item = &str> {
+    text.split(['.', ';', '\n'])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+pub fn governing_law(text: &str) -> Option<String> {
+    let lower = text.to_ascii_lowercase();
+    let start = [
+        "governed by the laws of",
+        "governed by the law of",
+        "laws of",
+    ]
+    .iter()
+    .find_map(|p| lower.find(p).map(|i| i + p.len()))?;
+    let rest = text[start..].trim_start();
+    let end = rest.find(['.', ',', ';', '\n', '(']).unwrap_or(rest.len());
+    let law = rest[..end].trim();
+    let law = law.strip_prefix("the ").unwrap_or(law).trim();
+    (!law.is_empty() && law.len() <= 80).then(|| law.to_string())
+}
+
+/// The first "<n> days" in a sentence about termination or notice.
+fn termination_notice_days(text: &str) -> Option<u32> {
+    sentences(text)
+        .filter(|s| {
+            let l = s.to_ascii_lowercase();
+            l.contains("terminat") && (l.contains("notice") || l.contains("days"))
+        })
+        .find_map(|s| {
+            let words: Vec<&str> = s.split_whitespace().collect();
+            words.windows(2).find_map(|w| {
+                let n = w[0].trim_matches(|c: char| !c.is_ascii_digit());
+                let unit = w[1].to_ascii_lowercase();
+                if unit.starts_with("day") {
+                    n.parse().ok()
+                } else {
+                    None
+                }
+            })
+        })
+}
+
+pub fn key_terms(text: &str) -> KeyTerms {
+    let lower = text.to_lowercase();
+    let sentence_has =
+        |all: &[&str]| sentences(&lower).any(|s| all.iter().all(|needle| s.contains(needle)));
+    KeyTerms {
+        governing_law: governing_law(text),
+        termination_notice_days: termination_notice_days(text),
+        auto_renewal: lower.contains("automatically renew")
+            || lower.contains("auto-renew")
+            || lower.contains("automatic renewal"),
+        assignment_restricted: sentence_has(&["assign", " not "])
+            || sentence_has(&["neither party may assign"])
+            || sentence_has(&["assign", "consent"]),
+        assignment_consent_required: sentence_has(&["assign", "consent"]),
+        change_of_control: lower.contains("change of control")
+            || lower.contains("change in control"),
+        exclusivity: (lower.contains("exclusive") && !lower.contains("non-exclusive"))
+            || lower.contains("exclusivity"),
+        non_compete: lower.contains("non-compet") || lower.contains("not compete"),
+        liability_cap: sentence_has(&["liabilit", "exceed"])
+            || sentence_has(&["liabilit", "limited to"])
+            || sentence_has(&["liabilit", "cap"]),
+        indemnification: lower.contains("indemnif"),
+    }
+}
+
+fn contract_row(state: &AppState, doc: &StoredDocument) -> (Vec<Cell>, Vec<Issue>) {
+    let terms = key_terms(&doc.text);
+    let clauses = state.clause_extractor.extract(
+        state.precompiled.keywords(),
+        &doc.text,
+        DEFAULT_CONTEXT_CHARS,
+    );
+    // Only count clauses the detector actually found evidence for.
+    let detected = clauses
+        .iter()
+        .filter(|c| c.confidence >= state.escalation_config.threshold)
+        .count();
+
+    let mut issues = export_control::issues(
+        &doc.text,
+        &state.export_policy.get(),
+        1,
+        DEFAULT_CONTEXT_CHARS,
+    );
+    let export_findings = issues.len();
+    let next_id = issues.len() + 1;
+    let deprecated = regulatory::issues(
+        doc,
+        &state.regulatory.list(),
+        state.clock.today(),
+        next_id,
+        DEFAULT_CONTEXT_CHARS,
+        None,
+    );
+    let deprecated_count = deprecated.len();
+    issues.extend(deprecated);
+
+    let factors = state.risk_model.factors(&doc.text);
+    let score: f64 = factors.iter().map(|f| f.weight * f.score).sum();
+    let top_factor = factors
+        .iter()
+        .max_by(|a, b| (a.weight * a.score).total_cmp(&(b.weight * b.score)))
+        .map(|f| f.factor.clone());
+    let severity = |s: &str| issues.iter().filter(|i| i.severity == s).count();
+
+    let title = doc
+        .text
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(|l| l.chars().take(120).collect::<String>());
+
+    let row = vec![
+        doc.id.clone().into(),
+        title.into(),
+        doc.family_id.clone().into(),
+        doc.language.clone().into(),
+        doc.text.split_whitespace().count().into(),
+        terms.governing_law.into(),
+        terms.termination_notice_days.into(),
+        terms.auto_renewal.into(),
+        terms.assignment_restricted.into(),
+        terms.assignment_consent_required.into(),
+        terms.change_of_control.into(),
+        terms.exclusivity.into(),
+        terms.non_compete.into(),
+        terms.liability_cap.into(),
+        terms.indemnification.into(),
+        detected.into(),
+        export_findings.into(),
+        deprecated_count.into(),
+        severity("critical").into(),
+        severity("high").into(),
+        issues.len().into(),
+        score.into(),
+        state.risk_model.level(score).into(),
+        top_factor.into(),
+    ];
+    (row, issues)
+}
+
+pub fn build_sheets(state: &AppState, docs: &[StoredDocument]) -> Vec<Sheet> {
+    let mut contracts = Vec::with_capacity(docs.len());
+    let mut issue_rows = Vec::new();
+    for doc in docs {
+        let (row, issues) = contract_row(state, doc);
+        contracts.push(row);
+        issue_rows.extend(issues.into_iter().map(|i| {
+            vec![
+                doc.id.clone().into(),
+                i.id.into(),
+                i.severity.into(),
+                i.description.into(),
+                i.location.into(),
+                i.confidence.into(),
+                i.excerpt.into(),
+            ]
+        }));
+    }
+    vec![
+        Sheet {
+            name: "contracts".to_string(),
+            headers: CONTRACT_COLUMNS.iter().map(|c| c.to_string()).collect(),
+            rows: contracts,
+        },
+        Sheet {
+            name: "issues".to_string(),
+            headers: [
+                "document_id",
+                "issue_id",
+                "severity",
+                "description",
+                "location",
+                "confidence",
+                "excerpt",
+            ]
+            .iter()
+            .map(|c| c.to_string())
+            .collect(),
+            rows: issue_rows,
+        },
+    ]
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn export(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<DiligenceRequest>,
+) -> Result<Response, StatusCode> {
+    if req.document_ids.is_empty() && req.family_ids.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut docs: Vec<StoredDocument> = Vec::new();
+    for id in &req.document_ids {
+        docs.push(auth::owned_document(&state, &headers, id).ok_or(StatusCode::NOT_FOUND)?);
+    }
+    for doc in auth::tenant_documents(&headers, &state) {
+        let in_family = doc
+            .family_id
+            .as_ref()
+            .is_some_and(|f| req.family_ids.contains(f));
+        if in_family && !docs.iter().any(|d| d.id == doc.id) {
+            docs.push(doc);
+        }
+    }
+    if docs.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if docs.len() > MAX_DOCUMENTS {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let sheets = build_sheets(&state, &docs);
+    let (bytes, content_type, filename) = match req.format {
+        ExportFormat::Xlsx => (
+            workbook::to_xlsx(&sheets),
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            "due-diligence.xlsx",
+        ),
+        ExportFormat::Csv => (
+            workbook::to_csv_bundle(&sheets),
+            "application/zip",
+            "due-diligence.zip",
+        ),
+    };
+    let bytes = bytes.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    info!(
+        documents = docs.len(),
+        bytes = bytes.len(),
+        "due-diligence export built"
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_key_terms() {
+        let text = "SUPPLY AGREEMENT\n\
+            This Agreement is governed by the laws of the State of Delaware.\n\
+            Either party may terminate this Agreement on sixty (60) days written notice.\n\
+            Neither party may assign this Agreement without the prior written consent of the other.\n\
+            A Change of Control of Supplier is deemed an assignment.\n\
+            In no event shall liability exceed the fees paid.\n\
+            This Agreement shall automatically renew for successive one-year terms.";
+        let terms = key_terms(text);
+        assert_eq!(terms.governing_law.as_deref(), Some("State of Delaware"));
+        assert_eq!(terms.termination_notice_days, Some(60));
+        assert!(terms.auto_renewal);
+        assert!(terms.assignment_restricted);
+        assert!(terms.assignment_consent_required);
+        assert!(terms.change_of_control);
+        assert!(terms.liability_cap);
+        assert!(!terms.indemnification);
+        assert!(!terms.exclusivity);
+    }
+
+    #[test]
+    fn quiet_contract_has_no_flags() {
+        assert_eq!(key_terms("A short letter."), KeyTerms::default());
+    }
+
+    #[test]
+    fn notice_days_in_plain_digits() {
+        assert_eq!(
+            termination_notice_days("Customer may terminate upon 30 days notice."),
+            Some(30)
+        );
+        assert_eq!(termination_notice_days("Payment within 30 days."), None);
+    }
+}
@@ -0,0 +1,381 @@
+//! Email renderings of an analysis summary: the risk score, the most severe
+//! issues, the risk model's recommendations and a link to the analysis, as
+//! a plaintext part and an HTML part that mail clients display without
+//! stylesheets or images. Email notification routes send both, and
+//! `GET /api/v1/legal/analyses/:id/summary` returns them for mailers of
+//! our own.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::{
+    html_export::escape,
+    notifier::{self, NotifyEvent, Summary, DEFAULT_TOP_ISSUES},
+    AppState,
+};
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Email {
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum SummaryFormat {
+    /// The HTML part, as `text/html`.
+    #[serde(rename = "email-html")]
+    Html,
+    /// The plaintext part, as `text/plain`.
+    #[serde(rename = "email-text")]
+    Text,
+    /// Subject and both parts as JSON.
+    #[serde(rename = "email")]
+    Json,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SummaryParams {
+    pub format: SummaryFormat,
+    #[serde(default)]
+    pub top_issues: Option<usize>,
+}
+
+// ── Rendering ─────────────────────────────────────────────────────────────────
+
+/// Border colour of a severity badge; the Teams card uses the same palette.
+fn severity_color(severity: &str) -> &'static str {
+    match severity {
+        "critical" | "high" => "#D13438",
+        "medium" => "#FFB900",
+        _ => "#107C10",
+    }
+}
+
+fn subject(summary: &Summary) -> String {
+    match summary.event {
+        NotifyEvent::AnalysisCompleted => format!(
+            "{}: {} risk ({:.2})",
+            summary.doc_name, summary.risk_level, summary.risk_score
+        ),
+        NotifyEvent::Escalation => format!(
+            "{}: {} finding(s) need review",
+            summary.doc_name, summary.escalated
+        ),
+        NotifyEvent::ObligationOverdue => format!(
+            "{}: {} obligation(s) overdue",
+            summary.doc_name, summary.overdue
+        ),
+    }
+}
+
+fn overdue(summary: &Summary) -> bool {
+    summary.event == NotifyEvent::ObligationOverdue
+}
+
+fn text(summary: &Summary, top_issues: usize) -> String {
+    let mut out = if overdue(summary) {
+        format!(
+            "{}\n\nObligations past their due date: {}\n",
+            summary.doc_name, summary.overdue
+        )
+    } else {
+        format!(
+            "{}\n\nRisk: {} ({:.2})\n",
+            summary.doc_name, summary.risk_level, summary.risk_score
+        )
+    };
+    if summary.escalated > 0 {
+        out.push_str(&format!(
+            "Findings awaiting human review: {}\n",
+            summary.escalated
+        ));
+    }
+    out.push_str(if overdue(summary) {
+        "\nOverdue obligations\n"
+    } else {
+        "\nTop issues\n"
+    });
+    if summary.issues.is_empty() {
+        out.push_str("No issues found.\n");
+    }
+    for (severity, description) in summary.issues.iter().take(top_issues) {
+        out.push_str(&format!("- [{severity}] {description}\n"));
+    }
+    let more = summary.issues.len().saturating_sub(top_issues);
+    if more > 0 {
+        out.push_str(&format!("- and {more} more\n"));
+    }
+    if !summary.recommendations.is_empty() {
+        out.push_str("\nRecommendations\n");
+        for recommendation in &summary.recommendations {
+            out.push_str(&format!("- {recommendation}\n"));
+        }
+    }
+    out.push_str(&format!("\nView the analysis: {}\n", summary.link));
+    out
+}
+
+fn html(summary: &Summary, top_issues: usize) -> String {
+    const FONT: &str = "font-family:Arial,Helvetica,sans-serif;color:#201F1E";
+    let mut rows = String::new();
+    for (severity, description) in summary.issues.iter().take(top_issues) {
+        rows.push_str(&format!(
+            "<tr><td style=\"padding:4px 8px;border-left:4px solid {};\
+             font-weight:bold;white-space:nowrap\">{}</td>\
+             <td style=\"padding:4px 8px\">{}</td></tr>\n",
+            severity_color(severity),
+            escape(severity),
+            escape(description)
+        ));
+    }
+    let more = summary.issues.len().saturating_sub(top_issues);
+    if more > 0 {
+        rows.push_str(&format!(
+            "<tr><td></td><td style=\"padding:4px 8px;color:#605E5C\">and {more} more</td></tr>\n"
+        ));
+    }
+    let issues = if summary.issues.is_empty() {
+        "<p>No issues found.</p>\n".to_string()
+    } else {
+        format!(
+            "<table role=\"presentation\" cellpadding=\"0\" cellspacing=\"0\">\n{rows}</table>\n"
+        )
+    };
+    let escalated = if summary.escalated > 0 {
+        format!(
+            "<p>Findings awaiting human review: <strong>{}</strong></p>\n",
+            summary.escalated
+        )
+    } else {
+        String::new()
+    };
+    let recommendations = if summary.recommendations.is_empty() {
+        String::new()
+    } else {
+        let items: String = summary
+            .recommendations
+            .iter()
+            .map(|r| format!("<li>{}</li>\n", escape(r)))
+            .collect();
+        format!("<h2 style=\"font-size:16px\">Recommendations</h2>\n<ul>\n{items}</ul>\n")
+    };
+    let (headline, heading) = if overdue(summary) {
+        (
+            format!(
+                "<p>Obligations past their due date: <strong>{}</strong></p>\n",
+                summary.overdue
+            ),
+            "Overdue obligations",
+        )
+    } else {
+        (
+            format!(
+                "<p>Risk: <strong style=\"color:{}\">{}</strong> ({:.2})</p>\n",
+                severity_color(&summary.risk_level),
+                escape(&summary.risk_level),
+                summary.risk_score
+            ),
+            "Top issues",
+        )
+    };
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n\
+         <body style=\"margin:0;padding:16px;{FONT}\">\n\
+         <div style=\"max-width:600px\">\n\
+         <h1 style=\"font-size:20px\">{title}</h1>\n\
+         {headline}{escalated}\
+         <h2 style=\"font-size:16px\">{heading}</h2>\n{issues}{recommendations}\
+         <p><a href=\"{link}\" style=\"color:#0F6CBD\">View the analysis</a></p>\n\
+         </div>\n</body>\n</html>\n",
+        title = escape(&summary.doc_name),
+        link = escape(&summary.link),
+    )
+}
+
+/// Subject, plaintext and HTML for `summary`, listing at most `top_issues`.
+pub fn render(summary: &Summary, top_issues: usize) -> Email {
+    Email {
+        subject: subject(summary),
+        text: text(summary, top_issues),
+        html: html(summary, top_issues),
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// The summary of a stored analysis or risk score, from its saved response.
+fn stored_summary(state: &AppState, id: &str, tenant: &str, result: &Value) -> Summary {
+    let risk_score = result["risk_score"]
+        .as_f64()
+        .or_else(|| result["overall_score"].as_f64())
+        .unwrap_or_default();
+    let issues = result["issues"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|i| {
+            Some((
+                i["severity"].as_str()?.to_string(),
+                i["description"].as_str()?.to_string(),
+            ))
+        })
+        .collect();
+    let risk_level = state.risk_model.level(risk_score);
+    Summary {
+        event: NotifyEvent::AnalysisCompleted,
+        tenant: tenant.to_string(),
+        analysis_id: id.to_string(),
+        doc_name: format!("Analysis {id}"),
+        recommendations: state.risk_model.recommendations(&risk_level),
+        risk_level,
+        risk_score,
+        issues: notifier::by_severity(issues),
+        escalated: result["escalations"].as_array().map_or(0, Vec::len),
+        overdue: 0,
+        link: notifier::link(state, id),
+    }
+}
+
+pub async fn analysis_summary(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Query(params): Query<SummaryParams>,
+) -> Result<Response, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    let record = state
+        .history
+        .get(&id)
+        .map_err(|e| {
+            warn!(analysis_id = %id, error = %e, "analysis history lookup failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .filter(|r| r.tenant == tenant)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let summary = stored_summary(&state, &id, &tenant, &record.result);
+    let email = render(&summary, params.top_issues.unwrap_or(DEFAULT_TOP_ISSUES));
+    info!(analysis_id = %id, format = ?params.format, "analysis summary rendered");
+    Ok(match params.format {
+        SummaryFormat::Html => (
+            [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+            email.html,
+        )
+            .into_response(),
+        SummaryFormat::Text => (
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            email.text,
+        )
+            .into_response(),
+        SummaryFormat::Json => Json(email).into_response(),
+    })
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary() -> Summary {
+        Summary {
+            event: NotifyEvent::AnalysisCompleted,
+            tenant: "acme".to_string(),
+            analysis_id: "a-1".to_string(),
+            doc_name: "Acme <MSA>".to_string(),
+            risk_level: "high".to_string(),
+            risk_score: 0.554,
+            issues: vec![
+                ("high".to_string(), "No liability cap.".to_string()),
+                (
+                    "medium".to_string(),
+                    "Auto-renewal & price increases.".to_string(),
+                ),
+                ("low".to_string(), "No notice address.".to_string()),
+            ],
+            escalated: 1,
+            overdue: 0,
+            recommendations: vec!["Negotiate a liability cap.".to_string()],
+            link: "https://legal.example.com/analyses/a-1?x=1&y=2".to_string(),
+        }
+    }
+
+    #[test]
+    fn plaintext_lists_top_issues_recommendations_and_link() {
+        let email = render(&summary(), 2);
+        assert_eq!(email.subject, "Acme <MSA>: high risk (0.55)");
+        assert_eq!(
+            email.text,
+            "Acme <MSA>\n\nRisk: high (0.55)\nFindings awaiting human review: 1\n\n\
+             Top issues\n- [high] No liability cap.\n\
+             - [medium] Auto-renewal & price increases.\n- and 1 more\n\n\
+             Recommendations\n- Negotiate a liability cap.\n\n\
+             View the analysis: https://legal.example.com/analyses/a-1?x=1&y=2\n"
+        );
+    }
+
+    #[test]
+    fn html_escapes_everything_it_is_given() {
+        let html = render(&summary(), 3).html;
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Acme &lt;MSA&gt;</title>"));
+        assert!(html.contains("Auto-renewal &amp; price increases."));
+        assert!(html.contains("href=\"https://legal.example.com/analyses/a-1?x=1&amp;y=2\""));
+        assert!(html.contains("border-left:4px solid #D13438"));
+        assert!(!html.contains("more</td>"));
+        assert!(!html.contains("<MSA>"));
+    }
+
+    #[test]
+    fn empty_analyses_say_so() {
+        let empty = Summary {
+            issues: Vec::new(),
+            escalated: 0,
+            recommendations: Vec::new(),
+            ..summary()
+        };
+        let email = render(&empty, 3);
+        assert!(email.text.contains("Top issues\nNo issues found.\n"));
+        assert!(!email.text.contains("review"));
+        assert!(!email.html.contains("Recommendations"));
+        let escalation = Summary {
+            event: NotifyEvent::Escalation,
+            ..summary()
+        };
+        assert_eq!(
+            render(&escalation, 3).subject,
+            "Acme <MSA>: 1 finding(s) need review"
+        );
+    }
+
+    #[test]
+    fn overdue_obligations_replace_the_risk_summary() {
+        let overdue = Summary {
+            event: NotifyEvent::ObligationOverdue,
+            issues: vec![(
+                "due 2026-01-16".to_string(),
+                "Vendor shall deliver the migration plan.".to_string(),
+            )],
+            escalated: 0,
+            overdue: 1,
+            recommendations: Vec::new(),
+            ..summary()
+        };
+        let email = render(&overdue, 3);
+        assert_eq!(email.subject, "Acme <MSA>: 1 obligation(s) overdue");
+        assert!(email.text.starts_with(
+            "Acme <MSA>\n\nObligations past their due date: 1\n\n\
+             Overdue obligations\n- [due 2026-01-16] Vendor shall deliver the migration plan.\n"
+        ));
+        assert!(!email.html.contains("Risk:"));
+        assert!(email.html.contains(">Overdue obligations</h2>"));
+    }
+}
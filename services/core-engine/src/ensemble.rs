@@ -0,0 +1,662 @@
+//! Merges the keyword heuristics with an optional ML classification
+//! backend. Findings both backends report are combined by confidence
+//! weight; where they disagree on risk or severity a conflict rule picks
+//! the answer and the confidence drops to the winner's share of the
+//! support, so contested findings tend to land with a reviewer. Every
+//! merged finding records which backend produced it.
+
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+use futures_util::future::join_all;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::warn;
+
+use crate::{
+    model_calls::{self, Caller, Purpose},
+    models::{ModelKind, RegisteredModel},
+    packing::{self, PackingReport, Plan},
+    timings::Stopwatch,
+    AnalyzeRequest, AppState, Clause, Issue,
+};
+
+/// Added to the weighted confidence when both backends agree.
+const AGREEMENT_BONUS: f64 = 0.1;
+/// Word overlap at which two issue descriptions count as the same issue.
+const ISSUE_MATCH_SIMILARITY: f64 = 0.5;
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+/// Which answer wins when the backends disagree on a finding's level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictRule {
+    /// The side with the larger weight × confidence.
+    Weighted,
+    Heuristic,
+    Ml,
+    /// The higher risk or severity, erring towards review.
+    Stricter,
+}
+
+impl ConflictRule {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "weighted" => Some(Self::Weighted),
+            "heuristic" => Some(Self::Heuristic),
+            "ml" => Some(Self::Ml),
+            "stricter" => Some(Self::Stricter),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EnsembleConfig {
+    /// Receives `{"document", "language"}` and answers with [`MlFindings`].
+    pub ml_url: Option<String>,
+    pub ml_timeout: Duration,
+    pub heuristic_weight: f64,
+    pub ml_weight: f64,
+    pub conflict_rule: ConflictRule,
+}
+
+impl Default for EnsembleConfig {
+    fn default() -> Self {
+        Self {
+            ml_url: None,
+            ml_timeout: Duration::from_secs(3),
+            heuristic_weight: 0.4,
+            ml_weight: 0.6,
+            conflict_rule: ConflictRule::Weighted,
+        }
+    }
+}
+
+impl EnsembleConfig {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        let weight = |name: &str, default: f64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse::<f64>().ok())
+                .filter(|w| w.is_finite() && *w > 0.0)
+                .unwrap_or(default)
+        };
+        Self {
+            ml_url: std::env::var("LEGAL_ML_BACKEND_URL")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
+            ml_timeout: std::env::var("LEGAL_ML_BACKEND_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map_or(d.ml_timeout, Duration::from_millis),
+            heuristic_weight: weight("LEGAL_ENSEMBLE_HEURISTIC_WEIGHT", d.heuristic_weight),
+            ml_weight: weight("LEGAL_ENSEMBLE_ML_WEIGHT", d.ml_weight),
+            conflict_rule: std::env::var("LEGAL_ENSEMBLE_CONFLICT_RULE")
+                .ok()
+                .and_then(|v| ConflictRule::parse(&v))
+                .unwrap_or(d.conflict_rule),
+        }
+    }
+
+    /// Weights scaled to sum to one.
+    fn shares(&self) -> (f64, f64) {
+        let total = self.heuristic_weight + self.ml_weight;
+        (self.heuristic_weight / total, self.ml_weight / total)
+    }
+}
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    Heuristic,
+    Ml,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Agreement {
+    /// Both backends found it and agree on the level.
+    Agreed,
+    /// Both found it but disagree on the level; the conflict rule decided.
+    Conflict,
+    /// Only one backend found it.
+    Single,
+}
+
+/// What one backend said about a finding.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Vote {
+    /// Risk level for clauses, severity for issues.
+    pub level: String,
+    pub confidence: f64,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Provenance {
+    pub sources: Vec<Backend>,
+    pub agreement: Agreement,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heuristic: Option<Vote>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ml: Option<Vote>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MlClause {
+    pub clause_type: String,
+    pub risk_level: String,
+    pub confidence: f64,
+    #[serde(default)]
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MlIssue {
+    pub description: String,
+    pub severity: String,
+    pub confidence: f64,
+    #[serde(default)]
+    pub location: Option<String>,
+}
+
+/// The ML backend's answer.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MlFindings {
+    #[serde(default)]
+    pub clauses: Vec<MlClause>,
+    #[serde(default)]
+    pub issues: Vec<MlIssue>,
+    /// How long the backend took to answer.
+    #[serde(skip)]
+    pub elapsed_ms: f64,
+    /// What was sent, when the document was over the token budget.
+    #[serde(skip)]
+    pub packing: Option<PackingReport>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct EnsembleSummary {
+    pub heuristic_weight: f64,
+    pub ml_weight: f64,
+    pub conflict_rule: ConflictRule,
+    pub agreed: usize,
+    pub conflicts: usize,
+    pub heuristic_only: usize,
+    pub ml_only: usize,
+}
+
+// ── Backend ───────────────────────────────────────────────────────────────────
+
+/// What asking the ML backend produced.
+#[derive(Debug, Default)]
+pub struct MlAnswer {
+    pub findings: Option<MlFindings>,
+    /// The recorded model call; `None` when no backend was asked.
+    pub call_id: Option<String>,
+}
+
+/// Asks the ML backend about the document; no findings when none is
+/// configured, it fails, or the request has a latency budget it could not
+/// wait for. A tenant with its own classification model is only ever sent
+/// to that. Documents over the backend's token budget are packed first;
+/// see [`packing`].
+pub async fn ml_findings(state: &AppState, tenant: &str, req: &AnalyzeRequest) -> MlAnswer {
+    if req.document.trim().is_empty() || req.latency_budget_ms.is_some() {
+        return MlAnswer::default();
+    }
+    let model = state.models.get(tenant, ModelKind::Classification);
+    if model.is_none() && state.ensemble.ml_url.is_none() {
+        return MlAnswer::default();
+    }
+    let watch = Stopwatch::start(state.clock.as_ref());
+    let caller = Caller::new(state, tenant, Purpose::Classification);
+    let keywords = state.lexicons.matcher(tenant);
+    let keywords = keywords
+        .as_deref()
+        .unwrap_or_else(|| state.precompiled.keywords());
+    let findings = match packing::plan(&state.packing, keywords, &req.document, &req.ml_context) {
+        Plan::Whole => {
+            ask(
+                state,
+                tenant,
+                model.as_ref(),
+                &caller,
+                &req.document,
+                &req.language,
+            )
+            .await
+        }
+        Plan::Packed(texts, report) => {
+            let calls = texts
+                .iter()
+                .map(|text| ask(state, tenant, model.as_ref(), &caller, text, &req.language));
+            let answers = join_all(calls).await;
+            // Partial coverage beats none: only fail when every call did.
+            let answers: Vec<MlFindings> = answers.into_iter().flatten().collect();
+            let findings = match answers.len() {
+                0 => None,
+                1 if texts.len() == 1 => answers.into_iter().next(),
+                _ => Some(packing::reduce(answers)),
+            };
+            findings.map(|f| MlFindings {
+                packing: Some(report),
+                ..f
+            })
+        }
+    };
+    MlAnswer {
+        findings: findings.map(|f| MlFindings {
+            elapsed_ms: watch.total(),
+            ..f
+        }),
+        call_id: Some(caller.id),
+    }
+}
+
+/// One call to the tenant's model, or else the shared backend.
+async fn ask(
+    state: &AppState,
+    tenant: &str,
+    model: Option<&Arc<RegisteredModel>>,
+    caller: &Caller<'_>,
+    text: &str,
+    language: &str,
+) -> Option<MlFindings> {
+    if let Some(model) = model {
+        return model
+            .endpoint
+            .classify(state, Some(caller), text, language)
+            .await
+            .inspect_err(|e| warn!(tenant, error = %e, "tenant classification model failed; using heuristics only"))
+            .ok();
+    }
+    let url = state.ensemble.ml_url.as_deref()?;
+    let request = state.http.post(url).timeout(state.ensemble.ml_timeout);
+    let body = json!({ "document": text, "language": language });
+    model_calls::exchange(state, Some(caller), request, url, &body)
+        .await
+        .and_then(|answer| {
+            serde_json::from_value::<MlFindings>(answer)
+                .map_err(|e| format!("unreadable answer: {e}"))
+        })
+        .inspect_err(|e| warn!(error = %e, "ML backend failed; using heuristics only"))
+        .ok()
+}
+
+// ── Merging ───────────────────────────────────────────────────────────────────
+
+fn level_rank(level: &str) -> u8 {
+    match level.to_ascii_lowercase().as_str() {
+        "critical" => 4,
+        "high" => 3,
+        "medium" => 2,
+        "low" => 1,
+        _ => 0,
+    }
+}
+
+fn words(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+pub(crate) fn same_issue(a: &str, b: &str) -> bool {
+    let (a, b) = (words(a), words(b));
+    let union = a.union(&b).count();
+    union > 0 && a.intersection(&b).count() as f64 / union as f64 >= ISSUE_MATCH_SIMILARITY
+}
+
+/// Level and confidence of a finding both backends reported.
+fn resolve(config: &EnsembleConfig, heuristic: &Vote, ml: &Vote) -> (String, f64, Agreement) {
+    let (wh, wm) = config.shares();
+    let support_h = wh * heuristic.confidence;
+    let support_m = wm * ml.confidence;
+    if heuristic.level.eq_ignore_ascii_case(&ml.level) {
+        let confidence = (support_h + support_m + AGREEMENT_BONUS).min(1.0);
+        return (heuristic.level.clone(), confidence, Agreement::Agreed);
+    }
+    let ml_wins = match config.conflict_rule {
+        ConflictRule::Weighted => support_m > support_h,
+        ConflictRule::Heuristic => false,
+        ConflictRule::Ml => true,
+        ConflictRule::Stricter => match level_rank(&ml.level).cmp(&level_rank(&heuristic.level)) {
+            std::cmp::Ordering::Equal => support_m > support_h,
+            order => order.is_gt(),
+        },
+    };
+    let (winner, support) = if ml_wins {
+        (ml, support_m)
+    } else {
+        (heuristic, support_h)
+    };
+    let total = support_h + support_m;
+    let confidence = if total > 0.0 { support / total } else { 0.0 };
+    (winner.level.clone(), confidence, Agreement::Conflict)
+}
+
+/// Confidence of a finding only `backend` reported, discounted by how far
+/// its weight falls short of the other backend's.
+fn single(config: &EnsembleConfig, backend: Backend, confidence: f64) -> f64 {
+    let (wh, wm) = config.shares();
+    let own = match backend {
+        Backend::Heuristic => wh,
+        Backend::Ml => wm,
+    };
+    confidence * own / wh.max(wm)
+}
+
+fn provenance(heuristic: Option<Vote>, ml: Option<Vote>, agreement: Agreement) -> Provenance {
+    let sources = [
+        heuristic.as_ref().map(|_| Backend::Heuristic),
+        ml.as_ref().map(|_| Backend::Ml),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    Provenance {
+        sources,
+        agreement,
+        heuristic,
+        ml,
+    }
+}
+
+/// Folds the ML findings into the heuristic ones in place; ML-only
+/// findings are appended with the next free ids.
+pub(crate) fn merge(
+    config: &EnsembleConfig,
+    clauses: &mut Vec<Clause>,
+    issues: &mut Vec<Issue>,
+    ml: &MlFindings,
+) -> EnsembleSummary {
+    let mut summary = EnsembleSummary {
+        heuristic_weight: config.heuristic_weight,
+        ml_weight: config.ml_weight,
+        conflict_rule: config.conflict_rule,
+        agreed: 0,
+        conflicts: 0,
+        heuristic_only: 0,
+        ml_only: 0,
+    };
+    let mut count = |agreement: Agreement, source: Backend| match (agreement, source) {
+        (Agreement::Agreed, _) => summary.agreed += 1,
+        (Agreement::Conflict, _) => summary.conflicts += 1,
+        (Agreement::Single, Backend::Heuristic) => summary.heuristic_only += 1,
+        (Agreement::Single, Backend::Ml) => summary.ml_only += 1,
+    };
+
+    let mut matched = vec![None; clauses.len()];
+    let mut ml_only_clauses = Vec::new();
+    for m in &ml.clauses {
+        let found = clauses.iter().enumerate().position(|(i, c)| {
+            matched[i].is_none() && c.clause_type.eq_ignore_ascii_case(&m.clause_type)
+        });
+        match found {
+            Some(i) => matched[i] = Some(m),
+            None => ml_only_clauses.push(m),
+        }
+    }
+    for (clause, m) in clauses.iter_mut().zip(matched) {
+        let h = Vote {
+            level: clause.risk_level.clone(),
+            confidence: clause.confidence,
+        };
+        let agreement = match m {
+            Some(m) => {
+                let v = Vote {
+                    level: m.risk_level.clone(),
+                    confidence: m.confidence.clamp(0.0, 1.0),
+                };
+                let (level, confidence, agreement) = resolve(config, &h, &v);
+                clause.risk_level = level;
+                clause.confidence = confidence;
+                clause.provenance = Some(provenance(Some(h), Some(v), agreement));
+                agreement
+            }
+            None => {
+                clause.confidence = single(config, Backend::Heuristic, h.confidence);
+                clause.provenance = Some(provenance(Some(h), None, Agreement::Single));
+                Agreement::Single
+            }
+        };
+        count(agreement, Backend::Heuristic);
+    }
+    for m in ml_only_clauses {
+        let v = Vote {
+            level: m.risk_level.clone(),
+            confidence: m.confidence.clamp(0.0, 1.0),
+        };
+        clauses.push(Clause {
+            id: format!("clause-{:03}", clauses.len() + 1),
+            text: m.text.clone(),
+            clause_type: m.clause_type.clone(),
+            risk_level: m.risk_level.clone(),
+            confidence: single(config, Backend::Ml, v.confidence),
+            review_status: "auto".to_string(),
+            provenance: Some(provenance(None, Some(v), Agreement::Single)),
+            excerpt: None,
+            position: None,
+        });
+        count(Agreement::Single, Backend::Ml);
+    }
+
+    let mut matched = vec![None; issues.len()];
+    let mut ml_only_issues = Vec::new();
+    for m in &ml.issues {
+        let found = issues.iter().enumerate().position(|(i, issue)| {
+            matched[i].is_none() && same_issue(&issue.description, &m.description)
+        });
+        match found {
+            Some(i) => matched[i] = Some(m),
+            None => ml_only_issues.push(m),
+        }
+    }
+    for (issue, m) in issues.iter_mut().zip(matched) {
+        let h = Vote {
+            level: issue.severity.clone(),
+            confidence: issue.confidence,
+        };
+        let agreement = match m {
+            Some(m) => {
+                let v = Vote {
+                    level: m.severity.clone(),
+                    confidence: m.confidence.clamp(0.0, 1.0),
+                };
+                let (level, confidence, agreement) = resolve(config, &h, &v);
+                issue.severity = level;
+                issue.confidence = confidence;
+                issue.provenance = Some(provenance(Some(h), Some(v), agreement));
+                agreement
+            }
+            None => {
+                issue.confidence = single(config, Backend::Heuristic, h.confidence);
+                issue.provenance = Some(provenance(Some(h), None, Agreement::Single));
+                Agreement::Single
+            }
+        };
+        count(agreement, Backend::Heuristic);
+    }
+    for m in ml_only_issues {
+        let v = Vote {
+            level: m.severity.clone(),
+            confidence: m.confidence.clamp(0.0, 1.0),
+        };
+        issues.push(Issue {
+            id: format!("issue-{:03}", issues.len() + 1),
+            description: m.description.clone(),
+            severity: m.severity.clone(),
+            location: m.location.clone().unwrap_or_else(|| "Document".to_string()),
+            confidence: single(config, Backend::Ml, v.confidence),
+            review_status: "auto".to_string(),
+            provenance: Some(provenance(None, Some(v), Agreement::Single)),
+            excerpt: None,
+            position: None,
+        });
+        count(Agreement::Single, Backend::Ml);
+    }
+    summary
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clause(clause_type: &str, risk_level: &str, confidence: f64) -> Clause {
+        Clause {
+            id: "clause-001".to_string(),
+            text: String::new(),
+            clause_type: clause_type.to_string(),
+            risk_level: risk_level.to_string(),
+            confidence,
+            review_status: "auto".to_string(),
+            provenance: None,
+            excerpt: None,
+            position: None,
+        }
+    }
+
+    fn ml_clause(clause_type: &str, risk_level: &str, confidence: f64) -> MlClause {
+        MlClause {
+            clause_type: clause_type.to_string(),
+            risk_level: risk_level.to_string(),
+            confidence,
+            text: "Either party may terminate.".to_string(),
+        }
+    }
+
+    fn merged(config: &EnsembleConfig, h: Clause, m: MlClause) -> (Vec<Clause>, EnsembleSummary) {
+        let mut clauses = vec![h];
+        let ml = MlFindings {
+            clauses: vec![m],
+            issues: Vec::new(),
+            ..MlFindings::default()
+        };
+        let summary = merge(config, &mut clauses, &mut Vec::new(), &ml);
+        (clauses, summary)
+    }
+
+    #[test]
+    fn agreement_raises_confidence_and_lists_both_sources() {
+        let (clauses, summary) = merged(
+            &EnsembleConfig::default(),
+            clause("Liability", "high", 0.6),
+            ml_clause("liability", "High", 0.8),
+        );
+        let c = &clauses[0];
+        // 0.4 × 0.6 + 0.6 × 0.8 + 0.1
+        assert!((c.confidence - 0.82).abs() < 1e-9);
+        let p = c.provenance.as_ref().unwrap();
+        assert_eq!(p.sources, [Backend::Heuristic, Backend::Ml]);
+        assert_eq!(p.agreement, Agreement::Agreed);
+        assert_eq!(summary.agreed, 1);
+    }
+
+    #[test]
+    fn conflicts_follow_the_rule_and_lower_confidence() {
+        let h = || clause("Liability", "low", 0.9);
+        let m = || ml_clause("Liability", "high", 0.7);
+        // 0.36 heuristic support against 0.42 for the model.
+        let (clauses, summary) = merged(&EnsembleConfig::default(), h(), m());
+        assert_eq!(clauses[0].risk_level, "high");
+        assert!((clauses[0].confidence - 0.42 / 0.78).abs() < 1e-9);
+        assert_eq!(summary.conflicts, 1);
+
+        let heuristic = EnsembleConfig {
+            conflict_rule: ConflictRule::Heuristic,
+            ..EnsembleConfig::default()
+        };
+        let (clauses, _) = merged(&heuristic, h(), m());
+        assert_eq!(clauses[0].risk_level, "low");
+
+        let stricter = EnsembleConfig {
+            heuristic_weight: 0.9,
+            ml_weight: 0.1,
+            conflict_rule: ConflictRule::Stricter,
+            ..EnsembleConfig::default()
+        };
+        let (clauses, _) = merged(&stricter, h(), m());
+        assert_eq!(clauses[0].risk_level, "high");
+        assert!(clauses[0].confidence < 0.5);
+    }
+
+    #[test]
+    fn single_source_findings_are_discounted_by_weight() {
+        let (clauses, summary) = merged(
+            &EnsembleConfig::default(),
+            clause("Jurisdiction", "low", 0.9),
+            ml_clause("Termination", "medium", 0.9),
+        );
+        assert_eq!(clauses.len(), 2);
+        assert!((clauses[0].confidence - 0.6).abs() < 1e-9);
+        assert!((clauses[1].confidence - 0.9).abs() < 1e-9);
+        assert_eq!(clauses[1].id, "clause-002");
+        assert_eq!(
+            clauses[1].provenance.as_ref().unwrap().sources,
+            [Backend::Ml]
+        );
+        assert_eq!((summary.heuristic_only, summary.ml_only), (1, 1));
+    }
+
+    #[test]
+    fn issues_match_on_description_wording() {
+        let mut issues = vec![Issue {
+            id: "issue-001".to_string(),
+            description: "Ambiguous indemnification clause detected.".to_string(),
+            severity: "high".to_string(),
+            location: "Section 4.2".to_string(),
+            confidence: 0.35,
+            review_status: "auto".to_string(),
+            provenance: None,
+            excerpt: None,
+            position: None,
+        }];
+        let ml = MlFindings {
+            clauses: Vec::new(),
+            issues: vec![
+                MlIssue {
+                    description: "Indemnification clause is ambiguous".to_string(),
+                    severity: "high".to_string(),
+                    confidence: 0.9,
+                    location: None,
+                },
+                MlIssue {
+                    description: "No cap on consequential damages".to_string(),
+                    severity: "medium".to_string(),
+                    confidence: 0.7,
+                    location: None,
+                },
+            ],
+            ..MlFindings::default()
+        };
+        let summary = merge(
+            &EnsembleConfig::default(),
+            &mut Vec::new(),
+            &mut issues,
+            &ml,
+        );
+        assert_eq!((summary.agreed, summary.ml_only), (1, 1));
+        assert!(issues[0].confidence > 0.35);
+        assert_eq!(issues[1].id, "issue-002");
+        assert_eq!(issues[1].location, "Document");
+    }
+
+    #[test]
+    fn conflict_rules_parse() {
+        assert_eq!(
+            ConflictRule::parse(" Stricter"),
+            Some(ConflictRule::Stricter)
+        );
+        assert_eq!(ConflictRule::parse("ml"), Some(ConflictRule::Ml));
+        assert_eq!(ConflictRule::parse("vote"), None);
+    }
+}
@@ -0,0 +1,794 @@
+//! Named entities in a contract: the contracting parties, defined terms,
+//! monetary amounts, percentages and dates, each with its byte span in the
+//! text and a normalized value. Formats follow the document's language:
+//! German and French write "1.250,50 €" and "1 250,50 €" where English and
+//! Japanese write "€1,250.50", and an English "03/04/2026" is 4 March where
+//! a German or French one is 3 April. Month names of all four languages are
+//! read whatever the language, as are Japanese dates (2026年3月1日, with the
+//! Reiwa and Heisei eras), 万/億 amounts and full-width digits.
+
+use std::sync::OnceLock;
+
+use chrono::NaiveDate;
+use regex::{Captures, Regex};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Currency spellings and their ISO 4217 codes. Symbols may come before or
+/// after the amount.
+const CURRENCIES: &[(&str, &str)] = &[
+    ("US$", "USD"),
+    ("$", "USD"),
+    ("USD", "USD"),
+    ("US-Dollar", "USD"),
+    ("dollars", "USD"),
+    ("€", "EUR"),
+    ("EUR", "EUR"),
+    ("Euro", "EUR"),
+    ("euros", "EUR"),
+    ("d'euros", "EUR"),
+    ("£", "GBP"),
+    ("GBP", "GBP"),
+    ("¥", "JPY"),
+    ("￥", "JPY"),
+    ("円", "JPY"),
+    ("JPY", "JPY"),
+    ("yen", "JPY"),
+    ("CHF", "CHF"),
+];
+
+const MULTIPLIERS: &[(&str, f64)] = &[
+    ("million", 1e6),
+    ("millions", 1e6),
+    ("Millionen", 1e6),
+    ("Mio.", 1e6),
+    ("billion", 1e9),
+    ("milliard", 1e9),
+    ("milliards", 1e9),
+    ("Milliarden", 1e9),
+    ("Mrd.", 1e9),
+    ("万", 1e4),
+    ("億", 1e8),
+];
+
+const MONTHS: &[(&str, u32)] = &[
+    ("january", 1),
+    ("february", 2),
+    ("march", 3),
+    ("april", 4),
+    ("may", 5),
+    ("june", 6),
+    ("july", 7),
+    ("august", 8),
+    ("september", 9),
+    ("october", 10),
+    ("november", 11),
+    ("december", 12),
+    ("januar", 1),
+    ("jänner", 1),
+    ("februar", 2),
+    ("märz", 3),
+    ("mai", 5),
+    ("juni", 6),
+    ("juli", 7),
+    ("oktober", 10),
+    ("dezember", 12),
+    ("janvier", 1),
+    ("février", 2),
+    ("mars", 3),
+    ("avril", 4),
+    ("juin", 6),
+    ("juillet", 7),
+    ("août", 8),
+    ("septembre", 9),
+    ("octobre", 10),
+    ("novembre", 11),
+    ("décembre", 12),
+];
+
+/// Japanese eras and the year before their first.
+const ERAS: &[(&str, i32)] = &[("令和", 2018), ("平成", 1988)];
+
+/// Where "<party A> and <party B>" starts and what joins the two, by language.
+const BETWEEN: &[(&str, &str, &str)] = &[
+    ("en", "between ", " and "),
+    ("de", "zwischen ", " und "),
+    ("fr", "entre ", " et "),
+];
+
+/// Words after a comma that keep it in a party's name ("Acme, Inc.").
+const NAME_SUFFIXES: &[&str] = &[
+    "inc", "ltd", "llc", "co", "corp", "limited", "gmbh", "ag", "sa",
+];
+const ARTICLES: &[&str] = &[
+    "the ",
+    "der ",
+    "die ",
+    "das ",
+    "la société ",
+    "la ",
+    "le ",
+    "l'",
+];
+
+/// Words after a quoted term that define it.
+const DEFINING: &[&str] = &[
+    "means",
+    "shall mean",
+    "has the meaning",
+    "bedeutet",
+    "bezeichnet",
+    "désigne",
+    "signifie",
+    "とは",
+    "という",
+];
+/// How far before a quoted term its opening parenthesis may be.
+const PARENTHESIS_REACH: usize = 60;
+const MAX_TERM_CHARS: usize = 60;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Party {
+    pub name: String,
+    /// Byte offsets of the name; `end` is exclusive.
+    pub start: usize,
+    pub end: usize,
+    /// The defined term the contract calls it by, e.g. "Supplier" or "甲".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub defined_as: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct DefinedTerm {
+    /// The term without its quotes or a leading article.
+    pub term: String,
+    /// Where it is defined, quotes included.
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Amount {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+    /// ISO 4217 code.
+    pub currency: String,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Percentage {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct DateMention {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+    pub date: NaiveDate,
+}
+
+/// Everything found, each list in document order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, JsonSchema)]
+pub struct Entities {
+    pub parties: Vec<Party>,
+    pub defined_terms: Vec<DefinedTerm>,
+    pub amounts: Vec<Amount>,
+    pub percentages: Vec<Percentage>,
+    pub dates: Vec<DateMention>,
+}
+
+impl Entities {
+    /// Maps every span through `offset`, e.g. from truncated text onto the
+    /// submitted document.
+    pub fn relocate(&mut self, offset: impl Fn(usize) -> usize) {
+        let spans = self
+            .parties
+            .iter_mut()
+            .map(|p| (&mut p.start, &mut p.end))
+            .chain(
+                self.defined_terms
+                    .iter_mut()
+                    .map(|t| (&mut t.start, &mut t.end)),
+            )
+            .chain(self.amounts.iter_mut().map(|a| (&mut a.start, &mut a.end)))
+            .chain(
+                self.percentages
+                    .iter_mut()
+                    .map(|p| (&mut p.start, &mut p.end)),
+            )
+            .chain(self.dates.iter_mut().map(|d| (&mut d.start, &mut d.end)));
+        for (start, end) in spans {
+            *start = offset(*start);
+            *end = offset(*end);
+        }
+    }
+}
+
+// ── Patterns ──────────────────────────────────────────────────────────────────
+
+struct Patterns {
+    amount_before: Regex,
+    amount_after: Regex,
+    percentage: Regex,
+    iso_date: Regex,
+    numeric_date: Regex,
+    kanji_date: Regex,
+    day_month: Regex,
+    month_day: Regex,
+    quoted: Regex,
+    ja_party: Regex,
+}
+
+/// Alternation of `words`, longest first, with a word boundary after the
+/// ones ending in a letter so "EUR" does not match the start of "Europe".
+fn alternation<'a>(words: impl Iterator<Item = &'a str>) -> String {
+    let mut words: Vec<&str> = words.collect();
+    words.sort_by_key(|w| std::cmp::Reverse(w.len()));
+    words
+        .iter()
+        .map(|w| {
+            let escaped = regex::escape(w);
+            if w.ends_with(|c: char| c.is_ascii_alphabetic()) {
+                format!(r"{escaped}\b")
+            } else {
+                escaped
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let compile = |pattern: &str| Regex::new(pattern).expect("valid entity pattern");
+        // Digits grouped by commas, dots, spaces or apostrophes, then an
+        // optional decimal part; the lead character keeps a match from
+        // starting inside a longer number.
+        let number = r"\d{1,3}(?:[,.\x{a0}\x{202f} '’]\d{3})+(?:[.,]\d+)?|\d+(?:[.,]\d+)?";
+        let lead = r"(?:^|[^\d.,'’])";
+        let currency = alternation(CURRENCIES.iter().map(|(c, _)| *c));
+        let multiplier = alternation(MULTIPLIERS.iter().map(|(m, _)| *m));
+        let months = alternation(MONTHS.iter().map(|(m, _)| *m));
+        Patterns {
+            amount_before: compile(&format!(
+                r"(?P<all>(?P<cur>{currency})\s?(?P<num>{number})(?:\s?(?P<mult>{multiplier}))?)"
+            )),
+            amount_after: compile(&format!(
+                r"{lead}(?P<all>(?P<num>{number})(?:\s?(?P<mult>{multiplier}))?\s?(?P<cur>{currency}))"
+            )),
+            percentage: compile(&format!(
+                r"{lead}(?P<all>(?P<num>{number})\s?(?:%|％|percent\b|per cent\b|Prozent\b|pour cent\b|パーセント))"
+            )),
+            iso_date: compile(r"(?P<all>(?P<y>\d{4})[-/](?P<m>\d{1,2})[-/](?P<d>\d{1,2}))"),
+            numeric_date: compile(&format!(
+                r"{lead}(?P<all>(?P<a>\d{{1,2}})(?P<sep>[./])(?P<b>\d{{1,2}})[./](?P<y>\d{{4}}))"
+            )),
+            kanji_date: compile(
+                r"(?P<all>(?:(?P<y>\d{4})|(?P<era>令和|平成)(?P<ey>\d{1,2}|元))\s*年\s*(?P<m>\d{1,2})\s*月\s*(?P<d>\d{1,2})\s*日)",
+            ),
+            day_month: compile(&format!(
+                r"(?i)(?P<all>\b(?P<d>\d{{1,2}})(?:st|nd|rd|th|er|\.)?\s+(?:day\s+of\s+)?(?P<m>{months}),?\s+(?P<y>\d{{4}}))"
+            )),
+            month_day: compile(&format!(
+                r"(?i)(?P<all>\b(?P<m>{months})\s+(?P<d>\d{{1,2}})(?:st|nd|rd|th)?,?\s+(?P<y>\d{{4}}))"
+            )),
+            quoted: compile(r#"["“„«「]\s*(?P<term>[^"“”„«»「」\n]{1,80}?)\s*["”“»」]"#),
+            ja_party: compile(
+                r"(?P<name>[^\s、。，,（）()「」]{2,40}?)\s*[（(]以下[、,]?\s*「(?P<alias>[^」]{1,20})」(?:と)?(?:いう|称する)。?[）)]",
+            ),
+        }
+    })
+}
+
+// ── Parsing ───────────────────────────────────────────────────────────────────
+
+/// Full-width digits as ASCII.
+fn ascii_digits(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '０'..='９' => char::from(b'0' + (c as u32 - '０' as u32) as u8),
+            c => c,
+        })
+        .collect()
+}
+
+/// A number as written in `language`. With both separators the later one is
+/// the decimal point; with one, it is a thousands separator if it repeats or
+/// is followed by exactly three digits, unless the language writes decimals
+/// with it ("1.250" is 1.25 in English and 1250 in German).
+fn parse_number(raw: &str, language: &str) -> Option<f64> {
+    let digits: String = ascii_digits(raw)
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '\u{a0}' | '\u{202f}' | '\'' | '’'))
+        .collect();
+    let decimal_comma = matches!(language, "de" | "fr");
+    let point = match (digits.rfind(','), digits.rfind('.')) {
+        (Some(c), Some(d)) => Some(c.max(d)),
+        (Some(i), None) | (None, Some(i)) => {
+            let sep = digits.as_bytes()[i] as char;
+            let once = digits.matches(sep).count() == 1;
+            let grouped = digits.len() - i - 1 == 3;
+            let decimal_sep = if decimal_comma { ',' } else { '.' };
+            (once && (!grouped || sep == decimal_sep)).then_some(i)
+        }
+        (None, None) => None,
+    };
+    let plain: String = digits
+        .char_indices()
+        .filter_map(|(i, c)| match c {
+            _ if Some(i) == point => Some('.'),
+            '0'..='9' => Some(c),
+            _ => None,
+        })
+        .collect();
+    plain.parse().ok()
+}
+
+fn int(caps: &Captures, name: &str) -> Option<u32> {
+    ascii_digits(caps.name(name)?.as_str()).parse().ok()
+}
+
+fn month(name: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    MONTHS.iter().find(|(m, _)| *m == name).map(|&(_, n)| n)
+}
+
+fn date(caps: &Captures, year: i32, month: u32, day: u32) -> Option<DateMention> {
+    let all = caps.name("all")?;
+    Some(DateMention {
+        text: all.as_str().to_string(),
+        start: all.start(),
+        end: all.end(),
+        date: NaiveDate::from_ymd_opt(year, month, day)?,
+    })
+}
+
+/// Keeps the first of any overlapping spans, in document order.
+fn without_overlaps<T>(mut found: Vec<T>, span: impl Fn(&T) -> (usize, usize)) -> Vec<T> {
+    found.sort_by_key(&span);
+    let mut end = 0;
+    found.retain(|t| {
+        let (s, e) = span(t);
+        let keep = s >= end;
+        if keep {
+            end = e;
+        }
+        keep
+    });
+    found
+}
+
+// ── Extraction ────────────────────────────────────────────────────────────────
+
+fn amounts(text: &str, language: &str) -> Vec<Amount> {
+    let p = patterns();
+    let found = p
+        .amount_before
+        .captures_iter(text)
+        .chain(p.amount_after.captures_iter(text))
+        .filter_map(|caps| {
+            let all = caps.name("all")?;
+            let token = caps.name("cur")?.as_str();
+            let (_, currency) = CURRENCIES.iter().find(|(c, _)| *c == token)?;
+            let factor = caps.name("mult").map_or(Some(1.0), |m| {
+                MULTIPLIERS
+                    .iter()
+                    .find(|(w, _)| *w == m.as_str())
+                    .map(|&(_, f)| f)
+            })?;
+            Some(Amount {
+                text: all.as_str().to_string(),
+                start: all.start(),
+                end: all.end(),
+                currency: currency.to_string(),
+                value: parse_number(caps.name("num")?.as_str(), language)? * factor,
+            })
+        })
+        .collect();
+    without_overlaps(found, |a| (a.start, a.end))
+}
+
+fn percentages(text: &str, language: &str) -> Vec<Percentage> {
+    patterns()
+        .percentage
+        .captures_iter(text)
+        .filter_map(|caps| {
+            let all = caps.name("all")?;
+            Some(Percentage {
+                text: all.as_str().to_string(),
+                start: all.start(),
+                end: all.end(),
+                value: parse_number(caps.name("num")?.as_str(), language)?,
+            })
+        })
+        .collect()
+}
+
+fn dates(text: &str, language: &str) -> Vec<DateMention> {
+    let p = patterns();
+    let mut found = Vec::new();
+    for caps in p.iso_date.captures_iter(text) {
+        found.extend(date(
+            &caps,
+            int(&caps, "y")
+                .and_then(|y| i32::try_from(y).ok())
+                .unwrap_or(0),
+            int(&caps, "m").unwrap_or(0),
+            int(&caps, "d").unwrap_or(0),
+        ));
+    }
+    // English puts the month first; German and French the day.
+    let month_first = !matches!(language, "de" | "fr");
+    for caps in p.numeric_date.captures_iter(text) {
+        let (a, b) = (int(&caps, "a").unwrap_or(0), int(&caps, "b").unwrap_or(0));
+        let slash = caps.name("sep").is_some_and(|s| s.as_str() == "/");
+        let (m, d) = if slash && month_first { (a, b) } else { (b, a) };
+        let y = int(&caps, "y")
+            .and_then(|y| i32::try_from(y).ok())
+            .unwrap_or(0);
+        found.extend(date(&caps, y, m, d));
+    }
+    for caps in p.kanji_date.captures_iter(text) {
+        let year = match caps.name("era") {
+            Some(era) => {
+                let base = ERAS
+                    .iter()
+                    .find(|(e, _)| *e == era.as_str())
+                    .map_or(0, |e| e.1);
+                let n = match caps.name("ey").map(|y| y.as_str()) {
+                    Some("元") => 1,
+                    _ => int(&caps, "ey").unwrap_or(0),
+                };
+                base + n as i32
+            }
+            None => int(&caps, "y").map_or(0, |y| y as i32),
+        };
+        found.extend(date(
+            &caps,
+            year,
+            int(&caps, "m").unwrap_or(0),
+            int(&caps, "d").unwrap_or(0),
+        ));
+    }
+    for caps in p
+        .day_month
+        .captures_iter(text)
+        .chain(p.month_day.captures_iter(text))
+    {
+        let m = caps.name("m").and_then(|m| month(m.as_str())).unwrap_or(0);
+        let y = int(&caps, "y").map_or(0, |y| y as i32);
+        found.extend(date(&caps, y, m, int(&caps, "d").unwrap_or(0)));
+    }
+    without_overlaps(found, |d| (d.start, d.end))
+}
+
+/// Whether `at` is inside a parenthesis opened shortly before it.
+fn in_parentheses(text: &str, at: usize) -> bool {
+    let mut from = at.saturating_sub(PARENTHESIS_REACH);
+    while !text.is_char_boundary(from) {
+        from -= 1;
+    }
+    let before = &text[from..at];
+    let open = before.rfind(['(', '（']);
+    let close = before.rfind([')', '）']);
+    open.is_some_and(|o| close.is_none_or(|c| c < o))
+}
+
+fn strip_article(term: &str) -> &str {
+    ARTICLES
+        .iter()
+        .find_map(|a| {
+            term.get(..a.len())
+                .filter(|head| head.eq_ignore_ascii_case(a))
+                .map(|_| &term[a.len()..])
+        })
+        .unwrap_or(term)
+}
+
+/// Quoted terms defined in a parenthesis (`("Supplier")`, `（以下「甲」という。）`)
+/// or followed by "means" and its equivalents; the first definition of
+/// each term counts.
+fn defined_terms(text: &str) -> Vec<DefinedTerm> {
+    let mut terms: Vec<DefinedTerm> = Vec::new();
+    for caps in patterns().quoted.captures_iter(text) {
+        let (Some(all), Some(term)) = (caps.get(0), caps.name("term")) else {
+            continue;
+        };
+        let term = strip_article(term.as_str().trim());
+        let after = text[all.end()..].trim_start().to_lowercase();
+        let defining =
+            in_parentheses(text, all.start()) || DEFINING.iter().any(|d| after.starts_with(d));
+        let latin = term.starts_with(|c: char| c.is_ascii_alphabetic());
+        if !defining
+            || term.chars().count() > MAX_TERM_CHARS
+            || latin && !term.starts_with(char::is_uppercase)
+            || terms.iter().any(|t| t.term == term)
+        {
+            continue;
+        }
+        terms.push(DefinedTerm {
+            term: term.to_string(),
+            start: all.start(),
+            end: all.end(),
+        });
+    }
+    terms
+}
+
+/// The span of a party's name in `raw`, which starts at `offset` in the
+/// text: up to its parenthesis and to a comma that does not precede an
+/// entity suffix, without a leading article.
+fn party_name(raw: &str, offset: usize) -> Option<(usize, usize)> {
+    let mut end = raw.find(['(', '（']).unwrap_or(raw.len());
+    let cut = raw[..end].match_indices(',').map(|(i, _)| i).find(|&i| {
+        let word = raw[i + 1..].split_whitespace().next().unwrap_or("");
+        let word = word.trim_end_matches(['.', ',']).to_ascii_lowercase();
+        !NAME_SUFFIXES.contains(&word.as_str())
+    });
+    end = cut.unwrap_or(end);
+    let name = raw[..end].trim_end();
+    let trimmed = name.trim_start();
+    let stripped = strip_article(trimmed);
+    let start = name.len() - stripped.len();
+    (!stripped.is_empty()).then_some((offset + start, offset + name.len()))
+}
+
+/// The two parties of "between A and B" in the document's language (English
+/// for the others), or of
+/// Japanese "A（以下「甲」という。）とB（以下「乙」という。）"; a party's
+/// defined term is the first one inside its part of the sentence.
+fn parties(text: &str, language: &str, terms: &[DefinedTerm]) -> Vec<Party> {
+    let alias = |from: usize, to: usize| {
+        terms
+            .iter()
+            .find(|t| t.start >= from && t.end <= to)
+            .map(|t| t.term.clone())
+    };
+    let ja: Vec<Party> = patterns()
+        .ja_party
+        .captures_iter(text)
+        .filter_map(|caps| {
+            let name = caps.name("name")?;
+            let trimmed = ["及び", "および", "と"]
+                .iter()
+                .find_map(|p| name.as_str().strip_prefix(p))
+                .unwrap_or(name.as_str());
+            let start = name.end() - trimmed.len();
+            Some(Party {
+                name: trimmed.to_string(),
+                start,
+                end: name.end(),
+                defined_as: caps.name("alias").map(|a| a.as_str().to_string()),
+            })
+        })
+        .take(2)
+        .collect();
+    if !ja.is_empty() {
+        return ja;
+    }
+
+    let lower = text.to_ascii_lowercase();
+    let &(_, between, and) = BETWEEN
+        .iter()
+        .find(|(l, _, _)| *l == language)
+        .unwrap_or(&BETWEEN[0]);
+    let Some(start) = lower.find(between).map(|i| i + between.len()) else {
+        return Vec::new();
+    };
+    // A period only ends the sentence before a capital or the end of the
+    // text, so "Inc." stays part of a name.
+    let rest = &text[start..];
+    let end = start
+        + rest
+            .char_indices()
+            .find(|&(i, c)| match c {
+                ';' | ':' | '\n' => true,
+                '.' => {
+                    let after = &rest[i + 1..];
+                    after.is_empty()
+                        || after.starts_with(char::is_whitespace)
+                            && after.trim_start().starts_with(char::is_uppercase)
+                }
+                _ => false,
+            })
+            .map_or(rest.len(), |(i, _)| i);
+    let Some(split) = lower[start..end].find(and).map(|i| start + i) else {
+        return Vec::new();
+    };
+    [(start, split), (split + and.len(), end)]
+        .into_iter()
+        .filter_map(|(from, to)| {
+            let (s, e) = party_name(&text[from..to], from)?;
+            Some(Party {
+                name: text[s..e].to_string(),
+                start: s,
+                end: e,
+                defined_as: alias(from, to),
+            })
+        })
+        .collect()
+}
+
+/// Every entity in `text`, read as `language`.
+pub fn extract(text: &str, language: &str) -> Entities {
+    let defined_terms = defined_terms(text);
+    Entities {
+        parties: parties(text, language, &defined_terms),
+        defined_terms,
+        amounts: amounts(text, language),
+        percentages: percentages(text, language),
+        dates: dates(text, language),
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn values(amounts: &[Amount]) -> Vec<(&str, f64)> {
+        amounts
+            .iter()
+            .map(|a| (a.currency.as_str(), a.value))
+            .collect()
+    }
+
+    #[test]
+    fn english_parties_terms_amounts_and_dates() {
+        let text = "This Agreement is made on March 1, 2026 between Acme Corp, a Delaware \
+                    corporation (\"Supplier\"), and Beta, Inc. (the \"Customer\").\n\
+                    \"Confidential Information\" means any non-public information.\n\
+                    Customer shall pay USD 1,250,000.00 per year, rising by 3.5% annually, \
+                    and a one-off fee of €2.5 million by 04/05/2026.";
+        let entities = extract(text, "en");
+
+        let parties: Vec<(&str, Option<&str>)> = entities
+            .parties
+            .iter()
+            .map(|p| (p.name.as_str(), p.defined_as.as_deref()))
+            .collect();
+        assert_eq!(
+            parties,
+            [
+                ("Acme Corp", Some("Supplier")),
+                ("Beta, Inc.", Some("Customer"))
+            ]
+        );
+        let acme = &entities.parties[0];
+        assert_eq!(&text[acme.start..acme.end], "Acme Corp");
+
+        let terms: Vec<&str> = entities
+            .defined_terms
+            .iter()
+            .map(|t| t.term.as_str())
+            .collect();
+        assert_eq!(terms, ["Supplier", "Customer", "Confidential Information"]);
+
+        assert_eq!(
+            values(&entities.amounts),
+            [("USD", 1_250_000.0), ("EUR", 2_500_000.0)]
+        );
+        assert_eq!(entities.amounts[1].text, "€2.5 million");
+        assert_eq!(entities.percentages[0].value, 3.5);
+
+        let dates: Vec<NaiveDate> = entities.dates.iter().map(|d| d.date).collect();
+        assert_eq!(dates, [ymd(2026, 3, 1), ymd(2026, 4, 5)]);
+        let first = &entities.dates[0];
+        assert_eq!(&text[first.start..first.end], "March 1, 2026");
+    }
+
+    #[test]
+    fn german_and_french_numbers_and_dates() {
+        let de = "Vertrag zwischen der Acme GmbH, Musterstraße 1, Berlin (nachfolgend \
+                  „Auftraggeber“) und der Beta AG (nachfolgend „Auftragnehmer“):\n\
+                  Die Vergütung beträgt 1.250.000,50 € bzw. 2 Mio. EUR, zzgl. 19 % USt., \
+                  fällig am 1. März 2026 oder 03.04.2026.";
+        let entities = extract(de, "de");
+        let parties: Vec<(&str, Option<&str>)> = entities
+            .parties
+            .iter()
+            .map(|p| (p.name.as_str(), p.defined_as.as_deref()))
+            .collect();
+        assert_eq!(
+            parties,
+            [
+                ("Acme GmbH", Some("Auftraggeber")),
+                ("Beta AG", Some("Auftragnehmer"))
+            ]
+        );
+        assert_eq!(
+            values(&entities.amounts),
+            [("EUR", 1_250_000.5), ("EUR", 2_000_000.0)]
+        );
+        assert_eq!(entities.percentages[0].value, 19.0);
+        let dates: Vec<NaiveDate> = entities.dates.iter().map(|d| d.date).collect();
+        assert_eq!(dates, [ymd(2026, 3, 1), ymd(2026, 4, 3)]);
+
+        let fr = "Contrat entre Alpha SAS (ci-après « le Fournisseur ») et Gamma SA \
+                  (ci-après « le Client »). Le prix est de 1\u{202f}250,50 € et 2 millions \
+                  d'euros, avec une remise de 2,5 %, payable le 1er mars 2026 ou le 03/04/2026.";
+        let entities = extract(fr, "fr");
+        assert_eq!(entities.parties[0].name, "Alpha SAS");
+        assert_eq!(entities.parties[1].defined_as.as_deref(), Some("Client"));
+        assert_eq!(
+            values(&entities.amounts),
+            [("EUR", 1250.5), ("EUR", 2_000_000.0)]
+        );
+        assert_eq!(entities.percentages[0].value, 2.5);
+        let dates: Vec<NaiveDate> = entities.dates.iter().map(|d| d.date).collect();
+        assert_eq!(dates, [ymd(2026, 3, 1), ymd(2026, 4, 3)]);
+    }
+
+    #[test]
+    fn japanese_parties_amounts_and_era_dates() {
+        let text =
+            "株式会社アルファ（以下「甲」という。）と株式会社ベータ（以下「乙」という。）は、\
+                    令和8年3月1日付で次のとおり契約する。\n\
+                    委託料は金１００万円（消費税10％別）とし、2026年4月30日までに支払う。";
+        let entities = extract(text, "ja");
+        let parties: Vec<(&str, Option<&str>)> = entities
+            .parties
+            .iter()
+            .map(|p| (p.name.as_str(), p.defined_as.as_deref()))
+            .collect();
+        assert_eq!(
+            parties,
+            [
+                ("株式会社アルファ", Some("甲")),
+                ("株式会社ベータ", Some("乙"))
+            ]
+        );
+        let beta = &entities.parties[1];
+        assert_eq!(&text[beta.start..beta.end], "株式会社ベータ");
+        let terms: Vec<&str> = entities
+            .defined_terms
+            .iter()
+            .map(|t| t.term.as_str())
+            .collect();
+        assert_eq!(terms, ["甲", "乙"]);
+        assert_eq!(values(&entities.amounts), [("JPY", 1_000_000.0)]);
+        assert_eq!(entities.percentages[0].value, 10.0);
+        let dates: Vec<NaiveDate> = entities.dates.iter().map(|d| d.date).collect();
+        assert_eq!(dates, [ymd(2026, 3, 1), ymd(2026, 4, 30)]);
+    }
+
+    #[test]
+    fn numbers_follow_the_language_and_invalid_dates_are_dropped() {
+        assert_eq!(parse_number("1.250", "en"), Some(1.25));
+        assert_eq!(parse_number("1.250", "de"), Some(1250.0));
+        assert_eq!(parse_number("1,250", "en"), Some(1250.0));
+        assert_eq!(parse_number("1,25", "fr"), Some(1.25));
+        assert_eq!(parse_number("1'000'000", "de"), Some(1_000_000.0));
+        assert!(extract("Due on 31/02/2026 or 2026-13-01.", "fr")
+            .dates
+            .is_empty());
+        // A number inside a longer one is not an amount of its own.
+        assert_eq!(
+            values(&extract("Invoice 2026 100 € due.", "fr").amounts),
+            [("EUR", 100.0)]
+        );
+    }
+
+    #[test]
+    fn relocation_moves_every_span() {
+        let mut entities = extract("Fee: $5 on 2026-01-02 at 5%.", "en");
+        entities.relocate(|o| o + 10);
+        assert_eq!(entities.amounts[0].start, 15);
+        assert_eq!(entities.dates[0].start, 21);
+        assert_eq!(entities.percentages[0].start, 35);
+    }
+}
@@ -0,0 +1,441 @@
+//! Legal entity suffixes on party names: normalizes their spelling and
+//! checks them against where the party says it is incorporated, the
+//! governing law and its address, so a "GmbH" incorporated in Delaware with
+//! a US address is caught as a data-quality problem.
+
+use std::collections::HashMap;
+
+use axum::{http::StatusCode, response::Json};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diligence, evidence,
+    export_control::{find_words, ADDRESS_MARKERS},
+    preview::{kind_of, SampleKind},
+    renewal, Issue,
+};
+
+const US: &[&str] = &["United States"];
+const UK_STYLE: &[&str] = &[
+    "United Kingdom",
+    "Ireland",
+    "Hong Kong",
+    "New Zealand",
+    "India",
+    "Israel",
+    "Cyprus",
+    "Malta",
+    "Canada",
+];
+const GERMAN_STYLE: &[&str] = &["Germany", "Austria", "Switzerland"];
+const ROMANCE_SA: &[&str] = &[
+    "France",
+    "Spain",
+    "Belgium",
+    "Luxembourg",
+    "Switzerland",
+    "Portugal",
+    "Poland",
+    "Mexico",
+    "Brazil",
+    "Argentina",
+    "Chile",
+];
+
+/// Suffix spelling (lowercase, without dots or commas), its canonical form
+/// and the countries that use it.
+type Suffix = (&'static str, &'static str, &'static [&'static str]);
+
+/// Longer suffixes come first.
+const SUFFIXES: &[Suffix] = &[
+    ("kabushiki kaisha", "Kabushiki Kaisha", &["Japan"]),
+    ("godo kaisha", "Godo Kaisha", &["Japan"]),
+    ("pty ltd", "Pty Ltd", &["Australia", "South Africa"]),
+    ("pte ltd", "Pte. Ltd.", &["Singapore"]),
+    (
+        "co ltd",
+        "Co., Ltd.",
+        &["Japan", "China", "South Korea", "Taiwan", "Thailand"],
+    ),
+    ("inc", "Inc.", US),
+    ("incorporated", "Incorporated", US),
+    ("corp", "Corp.", US),
+    ("llc", "LLC", US),
+    ("ltd", "Ltd", UK_STYLE),
+    ("limited", "Limited", UK_STYLE),
+    ("plc", "plc", &["United Kingdom", "Ireland"]),
+    ("llp", "LLP", &["United Kingdom", "United States", "India"]),
+    ("gmbh", "GmbH", GERMAN_STYLE),
+    ("ag", "AG", GERMAN_STYLE),
+    ("kk", "K.K.", &["Japan"]),
+    ("gk", "G.K.", &["Japan"]),
+    ("sa", "S.A.", ROMANCE_SA),
+    ("sas", "SAS", &["France"]),
+    ("sarl", "SARL", &["France", "Luxembourg"]),
+    ("sl", "S.L.", &["Spain"]),
+    ("bv", "B.V.", &["Netherlands", "Belgium"]),
+    ("nv", "N.V.", &["Netherlands", "Belgium"]),
+    ("spa", "S.p.A.", &["Italy"]),
+    ("srl", "S.r.l.", &["Italy", "Romania"]),
+    ("ab", "AB", &["Sweden"]),
+    ("oy", "Oy", &["Finland"]),
+];
+
+/// Place names, lowercase, mapped to a country. Where matches overlap the
+/// longer one wins, so "northern ireland" is not read as Ireland.
+const PLACES: &[(&str, &str)] = &[
+    ("united states", "United States"),
+    ("usa", "United States"),
+    ("delaware", "United States"),
+    ("new york", "United States"),
+    ("california", "United States"),
+    ("texas", "United States"),
+    ("florida", "United States"),
+    ("illinois", "United States"),
+    ("massachusetts", "United States"),
+    ("nevada", "United States"),
+    ("new jersey", "United States"),
+    ("pennsylvania", "United States"),
+    ("united kingdom", "United Kingdom"),
+    ("england", "United Kingdom"),
+    ("wales", "United Kingdom"),
+    ("scotland", "United Kingdom"),
+    ("northern ireland", "United Kingdom"),
+    ("ireland", "Ireland"),
+    ("germany", "Germany"),
+    ("austria", "Austria"),
+    ("switzerland", "Switzerland"),
+    ("japan", "Japan"),
+    ("tokyo", "Japan"),
+    ("france", "France"),
+    ("spain", "Spain"),
+    ("belgium", "Belgium"),
+    ("luxembourg", "Luxembourg"),
+    ("netherlands", "Netherlands"),
+    ("italy", "Italy"),
+    ("sweden", "Sweden"),
+    ("finland", "Finland"),
+    ("singapore", "Singapore"),
+    ("australia", "Australia"),
+    ("hong kong", "Hong Kong"),
+    ("india", "India"),
+    ("china", "China"),
+    ("korea", "South Korea"),
+    ("canada", "Canada"),
+    ("ontario", "Canada"),
+    ("brazil", "Brazil"),
+    ("mexico", "Mexico"),
+];
+
+const INCORPORATION_MARKERS: [&str; 6] = [
+    "incorporated",
+    "organized under",
+    "organised under",
+    "existing under",
+    " corporation",
+    " company",
+];
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Incorporation,
+    GoverningLaw,
+    Address,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Mismatch {
+    pub source: Source,
+    pub country: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct EntityCheck {
+    pub party: String,
+    pub normalized_name: String,
+    /// Canonical suffix, e.g. "GmbH"; `None` when the name has none.
+    pub entity_type: Option<String>,
+    pub entity_countries: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl EntityCheck {
+    /// Worth reporting: a mismatch, or a suffix spelled differently from its
+    /// canonical form.
+    pub fn has_findings(&self) -> bool {
+        !self.mismatches.is_empty() || self.normalized_name != self.party
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValidateRequest {
+    pub parties: Vec<String>,
+    /// Governing law or jurisdiction the parties are checked against.
+    pub jurisdiction: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidateResponse {
+    pub checks: Vec<EntityCheck>,
+}
+
+/// Where a party says it is, as found in the contract text.
+#[derive(Debug, Default)]
+pub struct Declared {
+    pub incorporation: Option<&'static str>,
+    pub governing_law: Option<&'static str>,
+    pub address: Option<&'static str>,
+}
+
+// ── Detection ─────────────────────────────────────────────────────────────────
+
+/// The first country named in `text`.
+pub fn country_in(text: &str) -> Option<&'static str> {
+    let lower = text.to_ascii_lowercase();
+    PLACES
+        .iter()
+        .filter_map(|(place, country)| {
+            let start = *find_words(&lower, place).first()?;
+            Some(((start, usize::MAX - place.len()), *country))
+        })
+        .min_by_key(|&(key, _)| key)
+        .map(|(_, country)| country)
+}
+
+/// The name with its suffix spelled canonically, and the suffix entry.
+pub fn normalize(name: &str) -> (String, Option<&'static Suffix>) {
+    let tokens: Vec<&str> = name.split_whitespace().collect();
+    for n in (1..=2.min(tokens.len().saturating_sub(1))).rev() {
+        let (prefix, tail) = tokens.split_at(tokens.len() - n);
+        let key = tail.join(" ").replace(['.', ','], "").to_ascii_lowercase();
+        if let Some(entry) = SUFFIXES.iter().find(|(k, _, _)| *k == key) {
+            // A comma before the suffix ("Acme, Inc.") stays on the prefix.
+            return (format!("{} {}", prefix.join(" "), entry.1), Some(entry));
+        }
+    }
+    (name.to_string(), None)
+}
+
+pub fn check(party: &str, declared: &Declared) -> EntityCheck {
+    let (normalized_name, entry) = normalize(party);
+    let mut mismatches = Vec::new();
+    if let Some((_, suffix, countries)) = entry {
+        for (source, country) in [
+            (Source::Incorporation, declared.incorporation),
+            (Source::GoverningLaw, declared.governing_law),
+            (Source::Address, declared.address),
+        ] {
+            let Some(country) = country else { continue };
+            if countries.contains(&country) {
+                continue;
+            }
+            let what = match source {
+                Source::Incorporation => "incorporated in",
+                Source::GoverningLaw => "governed by the law of",
+                Source::Address => "has its address in",
+            };
+            mismatches.push(Mismatch {
+                source,
+                country: country.to_string(),
+                message: format!(
+                    "\"{party}\" is a {suffix}, used in {}, but is {what} {country}.",
+                    countries.join("/")
+                ),
+            });
+        }
+    }
+    EntityCheck {
+        party: party.to_string(),
+        normalized_name,
+        entity_type: entry.map(|(_, suffix, _)| suffix.to_string()),
+        entity_countries: entry.map_or_else(Vec::new, |(_, _, c)| {
+            c.iter().map(|s| s.to_string()).collect()
+        }),
+        mismatches,
+    }
+}
+
+/// What the text right after a party's name says about it, up to the other
+/// party's name or the end of the line.
+fn declared_for(
+    document: &str,
+    party: &str,
+    other: &str,
+    governing_law: Option<&'static str>,
+) -> Declared {
+    let mut declared = Declared {
+        governing_law,
+        ..Declared::default()
+    };
+    let Some(at) = document.find(party) else {
+        return declared;
+    };
+    let rest = &document[at + party.len()..];
+    let mut end = rest.find('\n').unwrap_or(rest.len());
+    if let Some(i) = rest.find(other) {
+        end = end.min(i);
+    }
+    let segment = &rest[..end];
+    let lower = segment.to_ascii_lowercase();
+    if INCORPORATION_MARKERS.iter().any(|m| lower.contains(m)) {
+        declared.incorporation = country_in(segment);
+    }
+    if let Some(i) = ADDRESS_MARKERS.iter().filter_map(|m| lower.find(m)).min() {
+        // The last place named in the address is usually the country.
+        let address = &lower[i..];
+        declared.address = PLACES
+            .iter()
+            .filter_map(|(place, country)| {
+                let end = find_words(address, place).last()? + place.len();
+                Some(((end, place.len()), *country))
+            })
+            .max_by_key(|&(key, _)| key)
+            .map(|(_, country)| country);
+    }
+    declared
+}
+
+/// Checks the "between A and B" parties of a contract.
+pub fn check_document(document: &str) -> Vec<EntityCheck> {
+    let Some((a, b)) = renewal::parties(document) else {
+        return Vec::new();
+    };
+    let governing_law = diligence::key_terms(document)
+        .governing_law
+        .and_then(|law| country_in(&law));
+    vec![
+        check(&a, &declared_for(document, &a, &b, governing_law)),
+        check(&b, &declared_for(document, &b, &a, governing_law)),
+    ]
+}
+
+/// Mismatches rendered as analysis issues; `next_id` continues the caller's numbering.
+pub fn issues(document: &str, next_id: usize, context_chars: usize) -> Vec<Issue> {
+    check_document(document)
+        .into_iter()
+        .filter(|c| !c.mismatches.is_empty())
+        .enumerate()
+        .map(|(i, c)| Issue {
+            id: format!("issue-{:03}", next_id + i),
+            description: c
+                .mismatches
+                .iter()
+                .map(|m| m.message.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            severity: "low".to_string(),
+            location: "Parties".to_string(),
+            confidence: 0.7,
+            review_status: "auto".to_string(),
+            provenance: None,
+            excerpt: document
+                .find(&c.party)
+                .map(|at| evidence::excerpt(document, at, at + c.party.len(), context_chars)),
+            position: document
+                .find(&c.party)
+                .map(|at| evidence::position(document, at, at + c.party.len())),
+        })
+        .collect()
+}
+
+/// Party-name template variables checked against the template's
+/// jurisdiction variables; only checks with something to report.
+pub fn check_variables(variables: &HashMap<String, String>) -> Vec<EntityCheck> {
+    let governing_law = ["governing_law", "jurisdiction"]
+        .iter()
+        .find_map(|k| variables.get(*k))
+        .and_then(|v| country_in(v));
+    let declared = Declared {
+        governing_law,
+        ..Declared::default()
+    };
+    let mut names: Vec<(&String, &String)> = variables
+        .iter()
+        .filter(|(k, _)| kind_of(k) == SampleKind::Organization)
+        .collect();
+    names.sort();
+    names
+        .into_iter()
+        .map(|(_, v)| check(v, &declared))
+        .filter(EntityCheck::has_findings)
+        .collect()
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn validate(
+    Json(req): Json<ValidateRequest>,
+) -> Result<Json<ValidateResponse>, StatusCode> {
+    if req.parties.iter().all(|p| p.trim().is_empty()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let declared = Declared {
+        governing_law: req.jurisdiction.as_deref().and_then(country_in),
+        ..Declared::default()
+    };
+    Ok(Json(ValidateResponse {
+        checks: req
+            .parties
+            .iter()
+            .map(|p| check(p.trim(), &declared))
+            .collect(),
+    }))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suffixes_are_normalized() {
+        assert_eq!(normalize("Acme inc").0, "Acme Inc.");
+        assert_eq!(normalize("Acme, Inc.").0, "Acme, Inc.");
+        assert_eq!(normalize("Fabrikam Gmbh").0, "Fabrikam GmbH");
+        assert_eq!(normalize("Litware KK").0, "Litware K.K.");
+        assert_eq!(normalize("Toyota Motor Co Ltd").0, "Toyota Motor Co., Ltd.");
+        assert_eq!(normalize("Globex").1, None);
+        // A bare suffix is not a name.
+        assert_eq!(normalize("GmbH").1, None);
+    }
+
+    #[test]
+    fn gmbh_in_delaware_with_us_address_is_flagged() {
+        let doc = "This Agreement is made between Fabrikam GmbH, a Delaware corporation with \
+            offices at 1 Main Street, Dover, Delaware, USA, and Litware K.K., a company \
+            incorporated in Japan.\nThis Agreement is governed by the laws of the State of Delaware.";
+        let checks = check_document(doc);
+        let sources: Vec<Source> = checks[0].mismatches.iter().map(|m| m.source).collect();
+        assert_eq!(
+            sources,
+            [Source::Incorporation, Source::GoverningLaw, Source::Address]
+        );
+        assert_eq!(checks[0].mismatches[0].country, "United States");
+        // Japan matches K.K.; only the governing law is foreign to it.
+        let sources: Vec<Source> = checks[1].mismatches.iter().map(|m| m.source).collect();
+        assert_eq!(sources, [Source::GoverningLaw]);
+    }
+
+    #[test]
+    fn template_variables_report_only_findings() {
+        let vars: HashMap<String, String> = [
+            ("party_a", "Northwind Traders Ltd"),
+            ("party_b", "Fabrikam gmbh"),
+            ("jurisdiction", "England and Wales"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let checks = check_variables(&vars);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].normalized_name, "Fabrikam GmbH");
+        assert_eq!(checks[0].mismatches[0].source, Source::GoverningLaw);
+    }
+}
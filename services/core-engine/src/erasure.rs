@@ -0,0 +1,637 @@
+//! Erasure requests. Deleting a tenant's data removes its documents and
+//! everything derived from them (stored analyses, escalations, reviewer
+//! comments, signature records, share links, finished jobs, model call
+//! records) plus its lexicon, playbook and signing authority matrix.
+//! Erasing a person masks their names and identifiers wherever they appear
+//! in free text and replaces them with a pseudonym where they are the value
+//! of a field, in the caller's tenant or, for an admin, in every tenant. The audit trail is never deleted from: entries that point at
+//! erased data are pseudonymized instead. Either way the caller gets a
+//! report of what was touched.
+
+use std::collections::BTreeSet;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::{
+    access::ROLE_HEADER,
+    audit::AuditEntry,
+    auth::{self, Principal},
+    corpus::StoredDocument,
+    notifier, share,
+    signature::{GateResult, Signatory},
+    AnalyzeResponse, AppState,
+};
+
+/// Shorter identifiers would mask unrelated words across the corpus.
+const MIN_IDENTIFIER_CHARS: usize = 3;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct ErasureRequest {
+    /// Names, e-mail addresses and other identifiers of the data subject.
+    pub identifiers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErasureScope {
+    Tenant,
+    Subject,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ErasureCounts {
+    pub documents_deleted: usize,
+    pub documents_redacted: usize,
+    pub occurrences_masked: usize,
+    pub analyses_deleted: usize,
+    pub escalations_deleted: usize,
+    pub review_comments_deleted: usize,
+    pub approvals_deleted: usize,
+    pub dispatches_deleted: usize,
+    pub executions_deleted: usize,
+    pub share_links_deleted: usize,
+    pub jobs_deleted: usize,
+    /// Corpus scores kept under each ruleset version.
+    pub scores_deleted: usize,
+    pub history_records_deleted: usize,
+    pub history_records_masked: usize,
+    pub outcome_labels_deleted: usize,
+    pub address_book_entries_deleted: usize,
+    pub obligations_deleted: usize,
+    /// Cached compile results for the tenant, or mentioning the person.
+    pub compile_results_deleted: usize,
+    pub lexicon_deleted: bool,
+    pub playbook_deleted: bool,
+    pub authority_matrix_deleted: bool,
+    pub model_calls_deleted: usize,
+    /// Model call records whose fully logged prompt mentioned the person.
+    pub model_call_prompts_masked: usize,
+    pub webhooks_deleted: usize,
+    pub variable_extractions_deleted: usize,
+    /// Reviewer, author, approver and signatory fields given a pseudonym.
+    pub records_pseudonymized: usize,
+    pub audit_entries_pseudonymized: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErasureReport {
+    pub erasure_id: String,
+    pub scope: ErasureScope,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    pub completed_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub counts: ErasureCounts,
+}
+
+// ── Masking ───────────────────────────────────────────────────────────────────
+
+/// Stable within one erasure, so erased entries stay linkable to each other
+/// but not to the value they replace.
+fn pseudonym(erasure_id: &str, value: &str) -> String {
+    let hash = share::digest(&[erasure_id, &value.trim().to_lowercase()]);
+    format!("erased-{}", &hash[..12])
+}
+
+/// The identifiers of one data subject, matched ignoring ASCII case.
+pub struct Subject {
+    needles: Vec<String>,
+}
+
+impl Subject {
+    pub fn new(identifiers: &[String]) -> Result<Self, String> {
+        let needles: Vec<String> = identifiers
+            .iter()
+            .map(|i| i.trim().to_ascii_lowercase())
+            .collect();
+        if needles.is_empty() {
+            return Err("at least one identifier is required".to_string());
+        }
+        if let Some(short) = needles
+            .iter()
+            .find(|n| n.chars().count() < MIN_IDENTIFIER_CHARS)
+        {
+            return Err(format!(
+                "identifier {short:?} is shorter than {MIN_IDENTIFIER_CHARS} characters"
+            ));
+        }
+        Ok(Self { needles })
+    }
+
+    pub fn mentioned_in(&self, text: &str) -> bool {
+        let lower = text.to_ascii_lowercase();
+        self.needles.iter().any(|n| lower.contains(n.as_str()))
+    }
+
+    /// Overwrites every mention with `*`, byte for byte, so offsets into the
+    /// text (comment anchors, excerpts) stay valid. Returns the count.
+    pub fn mask(&self, text: &mut String) -> usize {
+        let lower = text.to_ascii_lowercase();
+        let mut spans = Vec::new();
+        for needle in &self.needles {
+            spans.extend(
+                lower
+                    .match_indices(needle.as_str())
+                    .map(|(at, m)| at..at + m.len()),
+            );
+        }
+        if spans.is_empty() {
+            return 0;
+        }
+        let mut bytes = std::mem::take(text).into_bytes();
+        for span in &spans {
+            bytes[span.clone()].fill(b'*');
+        }
+        // Matches start and end on character boundaries, so whole
+        // characters are replaced and the result is still UTF-8.
+        *text = String::from_utf8(bytes).expect("masked text stays UTF-8");
+        spans.len()
+    }
+
+    fn mask_option(&self, text: &mut Option<String>) -> usize {
+        text.as_mut().map_or(0, |t| self.mask(t))
+    }
+
+    fn mask_analysis(&self, analysis: &mut AnalyzeResponse) -> usize {
+        let clauses: usize = analysis
+            .clauses
+            .iter_mut()
+            .map(|c| self.mask(&mut c.text) + self.mask_option(&mut c.excerpt))
+            .sum();
+        let issues: usize = analysis
+            .issues
+            .iter_mut()
+            .map(|i| self.mask(&mut i.description) + self.mask_option(&mut i.excerpt))
+            .sum();
+        clauses + issues
+    }
+
+    /// Masks every string in a JSON value; true if anything changed.
+    fn mask_json(&self, value: &mut Value) -> bool {
+        match value {
+            Value::String(s) => self.mask(s) > 0,
+            Value::Array(items) => items.iter_mut().fold(false, |changed, v| {
+                // `|` rather than `||`: every element must be visited.
+                changed | self.mask_json(v)
+            }),
+            Value::Object(map) => map
+                .values_mut()
+                .fold(false, |changed, v| changed | self.mask_json(v)),
+            _ => false,
+        }
+    }
+}
+
+/// Replaces `field` with a pseudonym if it names the subject.
+fn pseudonymize(subject: &Subject, erasure_id: &str, field: &mut String) -> bool {
+    if !subject.mentioned_in(field) {
+        return false;
+    }
+    *field = pseudonym(erasure_id, field);
+    true
+}
+
+/// Replaces strings equal to an erased ID anywhere in a JSON value.
+fn pseudonymize_ids(erased: &BTreeSet<String>, erasure_id: &str, value: &mut Value) -> bool {
+    match value {
+        Value::String(s) if erased.contains(s.as_str()) => {
+            *s = pseudonym(erasure_id, s);
+            true
+        }
+        Value::Array(items) => items.iter_mut().fold(false, |changed, v| {
+            changed | pseudonymize_ids(erased, erasure_id, v)
+        }),
+        Value::Object(map) => map.values_mut().fold(false, |changed, v| {
+            changed | pseudonymize_ids(erased, erasure_id, v)
+        }),
+        _ => false,
+    }
+}
+
+// ── Erasure ───────────────────────────────────────────────────────────────────
+
+/// Fails only when the history database does; it goes first, so nothing
+/// has been erased yet when it does.
+pub fn erase_tenant(
+    state: &AppState,
+    tenant: &str,
+    erasure_id: &str,
+) -> Result<ErasureCounts, String> {
+    let mut counts = ErasureCounts {
+        history_records_deleted: state.history.delete_tenant(tenant)?,
+        ..ErasureCounts::default()
+    };
+    let ids: Vec<String> = state
+        .corpus
+        .all()
+        .into_iter()
+        .filter(|d| d.tenant == tenant)
+        .map(|d| d.id)
+        .collect();
+    for id in &ids {
+        state.corpus.remove(id);
+        let (analysis, escalations) = state.escalations.remove_analysis(id);
+        counts.analyses_deleted += usize::from(analysis);
+        counts.escalations_deleted += escalations;
+        counts.review_comments_deleted += state.reviews.remove(id);
+        let (approvals, dispatches, executions) = state.signatures.remove(id);
+        counts.approvals_deleted += approvals;
+        counts.dispatches_deleted += dispatches;
+        counts.executions_deleted += executions;
+        counts.share_links_deleted += state.shares.remove_for(id);
+        counts.scores_deleted += state.rescorer.remove(id);
+    }
+    counts.documents_deleted = ids.len();
+    counts.jobs_deleted = state.jobs.remove_for(&ids);
+    counts.outcome_labels_deleted = state.outcomes.remove(tenant);
+    counts.address_book_entries_deleted = state.address_book.remove_tenant(tenant);
+    counts.obligations_deleted = state.obligations.remove_tenant(tenant);
+    counts.compile_results_deleted = state.compile_cache.remove_tenant(tenant);
+    counts.lexicon_deleted = state.lexicons.remove(tenant);
+    counts.playbook_deleted = state.playbooks.remove(tenant);
+    counts.authority_matrix_deleted = state.authority.remove(tenant);
+    counts.model_calls_deleted = state.model_calls.remove_tenant(tenant);
+    counts.webhooks_deleted = state.webhooks.remove_tenant(tenant);
+    counts.variable_extractions_deleted = state.variable_inference.remove_tenant(tenant);
+
+    let mut erased: BTreeSet<String> = ids.into_iter().collect();
+    erased.insert(tenant.to_string());
+    counts.audit_entries_pseudonymized = state.audit.rewrite(|entry: &mut AuditEntry| {
+        let mut changed = false;
+        for field in std::iter::once(&mut entry.subject).chain(entry.actor.as_mut()) {
+            if erased.contains(field.as_str()) {
+                *field = pseudonym(erasure_id, field);
+                changed = true;
+            }
+        }
+        pseudonymize_ids(&erased, erasure_id, &mut entry.detail) || changed
+    });
+    Ok(counts)
+}
+
+/// Erases the person from `tenant`'s data, or from every tenant's with
+/// `None`. Fails only when the history database does, before anything else
+/// is masked.
+pub fn erase_subject(
+    state: &AppState,
+    subject: &Subject,
+    tenant: Option<&str>,
+    erasure_id: &str,
+) -> Result<ErasureCounts, String> {
+    let in_tenant = |t: &str| tenant.is_none_or(|own| own == t);
+    let docs: Vec<StoredDocument> = state
+        .corpus
+        .all()
+        .into_iter()
+        .filter(|d| in_tenant(&d.tenant))
+        .collect();
+    // Records kept per document belong to the document's tenant.
+    let ids: BTreeSet<String> = docs.iter().map(|d| d.id.clone()).collect();
+    let in_scope = |id: &str| tenant.is_none() || ids.contains(id);
+
+    let mut counts = ErasureCounts {
+        history_records_masked: state
+            .history
+            .rewrite(&mut |r| in_tenant(&r.tenant) && subject.mask_json(&mut r.result))?,
+        ..ErasureCounts::default()
+    };
+    for mut doc in docs {
+        let masked = subject.mask(&mut doc.text);
+        if masked > 0 {
+            counts.documents_redacted += 1;
+            counts.occurrences_masked += masked;
+            state.corpus.insert(doc);
+        }
+    }
+
+    let mut masked = 0;
+    let mut pseudonymized = 0;
+    state.escalations.for_each_analysis_mut(|a| {
+        if in_scope(&a.analysis_id) {
+            masked += subject.mask_analysis(a);
+        }
+    });
+    state.jobs.for_each_result_mut(|t, a| {
+        if in_tenant(t) {
+            masked += subject.mask_analysis(a);
+        }
+    });
+    state.escalations.for_each_mut(|e| {
+        if !in_scope(&e.analysis_id) {
+            return;
+        }
+        if let Some(reviewer) = e.reviewer.as_mut() {
+            pseudonymized += usize::from(pseudonymize(subject, erasure_id, reviewer));
+        }
+        masked += subject.mask_option(&mut e.note);
+    });
+    state.reviews.for_each_mut(|analysis_id, c| {
+        if !in_scope(analysis_id) {
+            return;
+        }
+        if let Some(author) = c.author.as_mut() {
+            pseudonymized += usize::from(pseudonymize(subject, erasure_id, author));
+        }
+        masked += subject.mask(&mut c.comment) + subject.mask_option(&mut c.quote);
+    });
+    state.signatures.for_each_approval_mut(|document_id, a| {
+        if in_scope(document_id) {
+            pseudonymized += usize::from(pseudonymize(subject, erasure_id, &mut a.approver));
+        }
+    });
+    let mut signed = |signatories: &mut Vec<Signatory>, gate: &mut GateResult| {
+        for s in signatories {
+            pseudonymized += usize::from(pseudonymize(subject, erasure_id, &mut s.name));
+        }
+        for check in &mut gate.checks {
+            masked += check
+                .details
+                .iter_mut()
+                .map(|t| subject.mask(t))
+                .sum::<usize>();
+        }
+    };
+    state.signatures.for_each_dispatch_mut(|d| {
+        if in_scope(&d.document_id) {
+            signed(&mut d.signatories, &mut d.gate);
+        }
+    });
+    state.signatures.for_each_execution_mut(|e| {
+        if in_scope(&e.document_id) {
+            signed(&mut e.signatories, &mut e.gate);
+        }
+    });
+    state.wizards.for_each_mut(|t, s| {
+        if !in_tenant(t) {
+            return;
+        }
+        for value in s.answers.values_mut().chain(s.variables.values_mut()) {
+            masked += subject.mask(value);
+        }
+    });
+    state.variable_inference.for_each_mut(|e| {
+        if !in_tenant(&e.tenant) {
+            return;
+        }
+        for inferred in e.variables.values_mut() {
+            masked += subject.mask(&mut inferred.value);
+        }
+    });
+    state.obligations.for_each_mut(|t, o| {
+        if !in_tenant(t) {
+            return;
+        }
+        for name in o.raci.names_mut() {
+            pseudonymized += usize::from(pseudonymize(subject, erasure_id, name));
+        }
+        masked += subject.mask_option(&mut o.note) + subject.mask(&mut o.obligation.text);
+    });
+    counts.occurrences_masked += masked;
+    counts.records_pseudonymized = pseudonymized;
+    counts.model_call_prompts_masked = state
+        .model_calls
+        .rewrite_prompts(|t, prompt| in_tenant(t) && subject.mask_json(prompt));
+    // An address book entry exists only to reach the person, so it goes.
+    counts.address_book_entries_deleted = state.address_book.remove_where(|t, c| {
+        in_tenant(t)
+            && [&c.name, &c.email, &c.address]
+                .into_iter()
+                .flatten()
+                .any(|field| subject.mentioned_in(field))
+    });
+    counts.compile_results_deleted = state
+        .compile_cache
+        .remove_where(|t, compiled| in_tenant(t) && subject.mentioned_in(compiled));
+
+    // The audit log names no tenant; a tenant's entries are those about its
+    // documents or the tenant itself.
+    counts.audit_entries_pseudonymized = state.audit.rewrite(|entry: &mut AuditEntry| {
+        if tenant.is_some_and(|t| entry.subject != t) && !in_scope(&entry.subject) {
+            return false;
+        }
+        let mut changed = false;
+        for field in std::iter::once(&mut entry.subject).chain(entry.actor.as_mut()) {
+            changed |= pseudonymize(subject, erasure_id, field);
+        }
+        subject.mask_json(&mut entry.detail) || changed
+    });
+    Ok(counts)
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// Erasure is irreversible, so only roles that may see everything can ask.
+fn authorize(state: &AppState, headers: &HeaderMap) -> Result<String, StatusCode> {
+    let role = headers
+        .get(ROLE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or(StatusCode::FORBIDDEN)?;
+    let full_access = state
+        .access_policy
+        .roles
+        .get(role)
+        .is_some_and(|paths| paths.iter().any(|p| p == "*"));
+    if !full_access {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(role.to_string())
+}
+
+fn finish(
+    state: &AppState,
+    erasure_id: String,
+    scope: ErasureScope,
+    tenant: Option<String>,
+    role: &str,
+    counts: ErasureCounts,
+) -> ErasureReport {
+    let report = ErasureReport {
+        erasure_id,
+        scope,
+        tenant,
+        completed_at: state.clock.now(),
+        counts,
+    };
+    info!(
+        erasure_id = %report.erasure_id,
+        scope = ?scope,
+        documents_deleted = report.counts.documents_deleted,
+        documents_redacted = report.counts.documents_redacted,
+        "erasure completed"
+    );
+    // Recorded after the rewrite and without identifiers, so it survives as is.
+    state.record_audit(
+        "erasure.completed",
+        &report.erasure_id,
+        Some(role),
+        serde_json::to_value(&report).unwrap_or(Value::Null),
+    );
+    report
+}
+
+pub async fn delete_tenant_data(
+    State(state): State<AppState>,
+    Path(tenant): Path<String>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<ErasureReport>, StatusCode> {
+    let role = authorize(&state, &headers)?;
+    // A tenant key may only erase its own tenant.
+    if !auth::cross_tenant(&state, principal.as_deref()) && notifier::tenant(&headers) != tenant {
+        warn!(key_id = ?principal.map(|p| p.0.key_id), tenant = %tenant, "erasure of another tenant refused");
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let erasure_id = format!("erasure-{}", state.ids.next_id());
+    let counts = erase_tenant(&state, &tenant, &erasure_id).map_err(|e| {
+        warn!(erasure_id = %erasure_id, error = %e, "erasure aborted: analysis history unavailable");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    // The report names the tenant; it is the requester's own identifier.
+    Ok(Json(finish(
+        &state,
+        erasure_id,
+        ErasureScope::Tenant,
+        Some(tenant),
+        &role,
+        counts,
+    )))
+}
+
+pub async fn erase(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
+    Json(req): Json<ErasureRequest>,
+) -> Result<Json<ErasureReport>, StatusCode> {
+    let role = authorize(&state, &headers)?;
+    let subject = Subject::new(&req.identifiers).map_err(|e| {
+        info!(error = %e, "erasure request rejected");
+        StatusCode::UNPROCESSABLE_ENTITY
+    })?;
+    // A tenant key reaches the person in its own tenant's data only.
+    let tenant =
+        (!auth::cross_tenant(&state, principal.as_deref())).then(|| notifier::tenant(&headers));
+    let erasure_id = format!("erasure-{}", state.ids.next_id());
+    let counts = erase_subject(&state, &subject, tenant.as_deref(), &erasure_id).map_err(|e| {
+        warn!(erasure_id = %erasure_id, error = %e, "erasure aborted: analysis history unavailable");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(finish(
+        &state,
+        erasure_id,
+        ErasureScope::Subject,
+        tenant,
+        &role,
+        counts,
+    )))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subject(ids: &[&str]) -> Subject {
+        Subject::new(&ids.iter().map(|s| s.to_string()).collect::<Vec<_>>()).unwrap()
+    }
+
+    fn doc(id: &str, tenant: &str, text: &str) -> StoredDocument {
+        StoredDocument {
+            id: id.to_string(),
+            text: text.to_string(),
+            language: "en".to_string(),
+            tenant: tenant.to_string(),
+            stored_at: Utc::now(),
+            family_id: None,
+            parent_id: None,
+        }
+    }
+
+    #[test]
+    fn masking_keeps_offsets_and_ignores_case() {
+        let s = subject(&["jane doe", "jdoe@example.com", "Müller"]);
+        let mut text = "Signed by JANE DOE (jdoe@example.com) and Herr Müller.".to_string();
+        let len = text.len();
+        assert_eq!(s.mask(&mut text), 3);
+        assert_eq!(text.len(), len);
+        assert_eq!(
+            text,
+            "Signed by ******** (****************) and Herr *******."
+        );
+        assert_eq!(s.mask(&mut text), 0);
+    }
+
+    #[test]
+    fn short_or_missing_identifiers_are_rejected() {
+        assert!(Subject::new(&[]).is_err());
+        assert!(Subject::new(&["JD".to_string()]).is_err());
+    }
+
+    #[test]
+    fn tenant_erasure_deletes_documents_and_pseudonymizes_audit() {
+        let state = AppState::in_memory();
+        state.corpus.insert(doc("a-1", "acme", "Acme contract"));
+        state.corpus.insert(doc("b-1", "globex", "Globex contract"));
+        state.record_audit(
+            "renewal.drafted",
+            "a-1",
+            None,
+            serde_json::json!({ "draft_id": "a-1" }),
+        );
+        state.record_audit("renewal.drafted", "b-1", None, serde_json::json!({}));
+
+        let counts = erase_tenant(&state, "acme", "erasure-1").unwrap();
+        assert_eq!(counts.documents_deleted, 1);
+        assert_eq!(counts.audit_entries_pseudonymized, 1);
+        assert!(state.corpus.get("a-1").is_none());
+        assert!(state.corpus.get("b-1").is_some());
+
+        let entries = state.audit.query(&Default::default());
+        let expected = pseudonym("erasure-1", "a-1");
+        assert_eq!(entries[0].subject, expected);
+        assert_eq!(entries[0].detail["draft_id"], expected.as_str());
+        assert_eq!(entries[1].subject, "b-1");
+    }
+
+    #[test]
+    fn subject_erasure_masks_text_and_pseudonymizes_names() {
+        let state = AppState::in_memory();
+        state
+            .corpus
+            .insert(doc("a-1", "acme", "Notices to Jane Doe."));
+        state.record_audit(
+            "signature.approved",
+            "a-1",
+            Some("Jane Doe"),
+            serde_json::json!({ "note": "approved by jane doe" }),
+        );
+
+        let counts = erase_subject(&state, &subject(&["Jane Doe"]), None, "erasure-1").unwrap();
+        assert_eq!(counts.documents_redacted, 1);
+        assert_eq!(counts.occurrences_masked, 1);
+        assert_eq!(counts.audit_entries_pseudonymized, 1);
+        assert_eq!(
+            state.corpus.get("a-1").unwrap().text,
+            "Notices to ********."
+        );
+
+        let entry = &state.audit.query(&Default::default())[0];
+        assert_eq!(
+            entry.actor.as_deref(),
+            Some(pseudonym("erasure-1", "jane doe").as_str())
+        );
+        assert_eq!(entry.detail["note"], "approved by ********");
+    }
+}
@@ -0,0 +1,666 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{auth, clock::IdGenerator, webhooks, AnalyzeResponse, AppState};
+
+/// Escalated analyses kept unless `LEGAL_ESCALATION_MAX_ANALYSES` says otherwise.
+pub const DEFAULT_MAX_ANALYSES: usize = 10_000;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EscalationConfig {
+    pub threshold: f64,
+    pub webhook_url: Option<String>,
+    /// Signs deliveries to `webhook_url`; without one nothing is sent.
+    #[serde(skip)]
+    pub webhook_secret: Option<String>,
+}
+
+impl EscalationConfig {
+    pub fn from_env() -> Self {
+        let threshold = std::env::var("LEGAL_ESCALATION_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or(0.5)
+            .clamp(0.0, 1.0);
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.trim().is_empty());
+        let webhook_url = var("LEGAL_REVIEWER_WEBHOOK_URL");
+        let webhook_secret = var("LEGAL_REVIEWER_WEBHOOK_SECRET");
+        if webhook_url.is_some() && webhook_secret.is_none() {
+            warn!("LEGAL_REVIEWER_WEBHOOK_URL is set without LEGAL_REVIEWER_WEBHOOK_SECRET; reviewers will not be notified");
+        }
+        Self {
+            threshold,
+            webhook_url,
+            webhook_secret,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    Clause,
+    Issue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Confirm,
+    Reject,
+    Override,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Escalation {
+    pub id: String,
+    pub analysis_id: String,
+    pub item_kind: ItemKind,
+    pub item_id: String,
+    pub confidence: f64,
+    pub status: String,
+    pub verdict: Option<Verdict>,
+    pub reviewer: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveRequest {
+    pub verdict: Verdict,
+    pub reviewer: String,
+    /// Override values; only used with `"verdict": "override"`.
+    pub clause_type: Option<String>,
+    pub risk_level: Option<String>,
+    pub severity: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResolveResponse {
+    pub escalation: Escalation,
+    pub analysis: AnalyzeResponse,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EscalationsResponse {
+    pub escalations: Vec<Escalation>,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct ReviewerPayload<'a> {
+    escalation: &'a Escalation,
+    analysis_id: &'a str,
+    item: serde_json::Value,
+    resolve_path: String,
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+struct Stored {
+    analysis: AnalyzeResponse,
+    /// Tick of when it was escalated, for eviction.
+    stored: u64,
+}
+
+/// Pending human reviews plus the analyses they belong to, so a verdict can be
+/// merged back into the result the client originally received. Once full,
+/// the oldest analysis with nothing left to review makes room for a new one;
+/// only when every stored analysis still awaits a verdict is the oldest of
+/// those dropped, pending escalations and all.
+pub struct EscalationStore {
+    max_analyses: usize,
+    escalations: DashMap<String, Escalation>,
+    analyses: DashMap<String, Stored>,
+    tick: AtomicU64,
+}
+
+impl Default for EscalationStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_ANALYSES)
+    }
+}
+
+impl EscalationStore {
+    /// A store of at most `max_analyses` escalated analyses, and at least one.
+    pub fn new(max_analyses: usize) -> Self {
+        Self {
+            max_analyses: max_analyses.max(1),
+            escalations: DashMap::new(),
+            analyses: DashMap::new(),
+            tick: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            std::env::var("LEGAL_ESCALATION_MAX_ANALYSES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_MAX_ANALYSES),
+        )
+    }
+
+    /// Evicts analyses until one more fits, reviewed ones first.
+    fn make_room(&self, incoming: &str) {
+        while self.analyses.len() >= self.max_analyses && !self.analyses.contains_key(incoming) {
+            let victim = self
+                .analyses
+                .iter()
+                .min_by_key(|s| (!s.analysis.escalations.is_empty(), s.stored))
+                .map(|s| (s.key().clone(), s.analysis.escalations.len()));
+            let Some((analysis_id, pending)) = victim else {
+                return;
+            };
+            self.remove_analysis(&analysis_id);
+            if pending > 0 {
+                warn!(analysis_id = %analysis_id, pending, "escalation store full; dropped an analysis awaiting review");
+            }
+        }
+    }
+
+    /// Marks every clause/issue below `threshold` as pending human review and
+    /// returns the escalations created for it.
+    pub fn escalate(
+        &self,
+        analysis: &mut AnalyzeResponse,
+        threshold: f64,
+        ids: &dyn IdGenerator,
+    ) -> Vec<Escalation> {
+        let mut created = Vec::new();
+        for clause in analysis
+            .clauses
+            .iter_mut()
+            .filter(|c| c.confidence < threshold)
+        {
+            clause.review_status = "pending_human".to_string();
+            created.push(new_escalation(
+                ids,
+                &analysis.analysis_id,
+                ItemKind::Clause,
+                &clause.id,
+                clause.confidence,
+            ));
+        }
+        for issue in analysis
+            .issues
+            .iter_mut()
+            .filter(|i| i.confidence < threshold)
+        {
+            issue.review_status = "pending_human".to_string();
+            created.push(new_escalation(
+                ids,
+                &analysis.analysis_id,
+                ItemKind::Issue,
+                &issue.id,
+                issue.confidence,
+            ));
+        }
+        if created.is_empty() {
+            return created;
+        }
+
+        analysis.escalations = created.iter().map(|e| e.id.clone()).collect();
+        self.make_room(&analysis.analysis_id);
+        for esc in &created {
+            self.escalations.insert(esc.id.clone(), esc.clone());
+        }
+        let stored = Stored {
+            analysis: analysis.clone(),
+            stored: self.tick.fetch_add(1, Ordering::Relaxed),
+        };
+        self.analyses.insert(analysis.analysis_id.clone(), stored);
+        created
+    }
+
+    /// The stored analysis with any verdicts applied, if it was escalated.
+    pub fn analysis(&self, analysis_id: &str) -> Option<AnalyzeResponse> {
+        self.analyses.get(analysis_id).map(|s| s.analysis.clone())
+    }
+
+    /// Drops a stored analysis and its escalations; returns whether the
+    /// analysis was stored and how many escalations went with it.
+    pub fn remove_analysis(&self, analysis_id: &str) -> (bool, usize) {
+        let before = self.escalations.len();
+        self.escalations.retain(|_, e| e.analysis_id != analysis_id);
+        (
+            self.analyses.remove(analysis_id).is_some(),
+            before - self.escalations.len(),
+        )
+    }
+
+    pub fn for_each_mut(&self, mut f: impl FnMut(&mut Escalation)) {
+        self.escalations
+            .iter_mut()
+            .for_each(|mut e| f(e.value_mut()));
+    }
+
+    pub fn for_each_analysis_mut(&self, mut f: impl FnMut(&mut AnalyzeResponse)) {
+        self.analyses
+            .iter_mut()
+            .for_each(|mut s| f(&mut s.value_mut().analysis));
+    }
+
+    pub fn get(&self, id: &str) -> Option<Escalation> {
+        self.escalations.get(id).map(|e| e.value().clone())
+    }
+
+    pub fn pending(&self) -> Vec<Escalation> {
+        let mut pending: Vec<Escalation> = self
+            .escalations
+            .iter()
+            .filter(|e| e.verdict.is_none())
+            .map(|e| e.value().clone())
+            .collect();
+        pending.sort_by(|a, b| a.id.cmp(&b.id));
+        pending
+    }
+
+    /// Escalations of one analysis that already have a verdict.
+    pub fn resolved_for(&self, analysis_id: &str) -> Vec<Escalation> {
+        let mut resolved: Vec<Escalation> = self
+            .escalations
+            .iter()
+            .filter(|e| e.analysis_id == analysis_id && e.verdict.is_some())
+            .map(|e| e.value().clone())
+            .collect();
+        resolved.sort_by(|a, b| a.id.cmp(&b.id));
+        resolved
+    }
+
+    pub fn resolve(&self, id: &str, req: ResolveRequest) -> Result<ResolveResponse, StatusCode> {
+        let mut esc = self.escalations.get_mut(id).ok_or(StatusCode::NOT_FOUND)?;
+        if esc.verdict.is_some() {
+            return Err(StatusCode::CONFLICT);
+        }
+        let mut stored = self
+            .analyses
+            .get_mut(&esc.analysis_id)
+            .ok_or(StatusCode::NOT_FOUND)?;
+        let analysis = &mut stored.analysis;
+
+        apply_verdict(analysis, &esc, &req)?;
+
+        esc.verdict = Some(req.verdict);
+        esc.status = "resolved".to_string();
+        esc.reviewer = Some(req.reviewer);
+        esc.note = req.note;
+        analysis.escalations.retain(|e| e != id);
+
+        Ok(ResolveResponse {
+            escalation: esc.clone(),
+            analysis: analysis.clone(),
+        })
+    }
+}
+
+fn new_escalation(
+    ids: &dyn IdGenerator,
+    analysis_id: &str,
+    item_kind: ItemKind,
+    item_id: &str,
+    confidence: f64,
+) -> Escalation {
+    Escalation {
+        id: format!("esc-{}", ids.next_id()),
+        analysis_id: analysis_id.to_string(),
+        item_kind,
+        item_id: item_id.to_string(),
+        confidence,
+        status: "pending_human".to_string(),
+        verdict: None,
+        reviewer: None,
+        note: None,
+    }
+}
+
+fn apply_verdict(
+    analysis: &mut AnalyzeResponse,
+    esc: &Escalation,
+    req: &ResolveRequest,
+) -> Result<(), StatusCode> {
+    match esc.item_kind {
+        ItemKind::Clause => {
+            let pos = analysis
+                .clauses
+                .iter()
+                .position(|c| c.id == esc.item_id)
+                .ok_or(StatusCode::NOT_FOUND)?;
+            match req.verdict {
+                Verdict::Reject => {
+                    analysis.clauses.remove(pos);
+                }
+                Verdict::Confirm => {
+                    let clause = &mut analysis.clauses[pos];
+                    clause.confidence = 1.0;
+                    clause.review_status = "human_confirmed".to_string();
+                }
+                Verdict::Override => {
+                    let clause = &mut analysis.clauses[pos];
+                    if let Some(ct) = &req.clause_type {
+                        clause.clause_type.clone_from(ct);
+                    }
+                    if let Some(rl) = &req.risk_level {
+                        clause.risk_level.clone_from(rl);
+                    }
+                    clause.confidence = 1.0;
+                    clause.review_status = "human_overridden".to_string();
+                }
+            }
+        }
+        ItemKind::Issue => {
+            let pos = analysis
+                .issues
+                .iter()
+                .position(|i| i.id == esc.item_id)
+                .ok_or(StatusCode::NOT_FOUND)?;
+            match req.verdict {
+                Verdict::Reject => {
+                    analysis.issues.remove(pos);
+                }
+                Verdict::Confirm => {
+                    let issue = &mut analysis.issues[pos];
+                    issue.confidence = 1.0;
+                    issue.review_status = "human_confirmed".to_string();
+                }
+                Verdict::Override => {
+                    let issue = &mut analysis.issues[pos];
+                    if let Some(sev) = &req.severity {
+                        issue.severity.clone_from(sev);
+                    }
+                    issue.confidence = 1.0;
+                    issue.review_status = "human_overridden".to_string();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// ── Reviewer webhook ──────────────────────────────────────────────────────────
+
+/// Sends each escalation as an `escalation_created` webhook: to the tenant's
+/// subscribed webhooks and to the configured reviewer endpoint. The reviewer
+/// answers asynchronously via the resolve endpoint.
+pub fn notify_reviewers(
+    state: &AppState,
+    tenant: &str,
+    analysis: &AnalyzeResponse,
+    created: &[Escalation],
+) {
+    let config = &state.escalation_config;
+    let reviewer = config
+        .webhook_url
+        .as_deref()
+        .zip(config.webhook_secret.as_deref());
+    for esc in created {
+        let item = match esc.item_kind {
+            ItemKind::Clause => analysis
+                .clauses
+                .iter()
+                .find(|c| c.id == esc.item_id)
+                .and_then(|c| serde_json::to_value(c).ok()),
+            ItemKind::Issue => analysis
+                .issues
+                .iter()
+                .find(|i| i.id == esc.item_id)
+                .and_then(|i| serde_json::to_value(i).ok()),
+        }
+        .unwrap_or(serde_json::Value::Null);
+
+        let data = match serde_json::to_value(ReviewerPayload {
+            escalation: esc,
+            analysis_id: &analysis.analysis_id,
+            item,
+            resolve_path: format!("/api/v1/legal/escalations/{}/resolve", esc.id),
+        }) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        webhooks::escalation_created(state, tenant, reviewer, data);
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn list_escalations(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Json<EscalationsResponse> {
+    let escalations: Vec<Escalation> = state
+        .escalations
+        .pending()
+        .into_iter()
+        .filter(|e| auth::owned_document(&state, &headers, &e.analysis_id).is_some())
+        .collect();
+    let count = escalations.len();
+    Json(EscalationsResponse { escalations, count })
+}
+
+pub async fn resolve_escalation(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ResolveRequest>,
+) -> Result<Json<ResolveResponse>, StatusCode> {
+    if req.reviewer.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let owned = state
+        .escalations
+        .get(&id)
+        .is_some_and(|e| auth::owned_document(&state, &headers, &e.analysis_id).is_some());
+    if !owned {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let resolved = state.escalations.resolve(&id, req)?;
+
+    info!(
+        escalation_id = %id,
+        analysis_id = %resolved.analysis.analysis_id,
+        verdict = ?resolved.escalation.verdict,
+        "escalation resolved"
+    );
+
+    Ok(Json(resolved))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clock::SequentialIds,
+        deadline::AnalysisMode,
+        paper::{PaperDetection, PaperSource},
+        Clause, Issue,
+    };
+
+    fn analysis() -> AnalyzeResponse {
+        AnalyzeResponse {
+            analysis_id: "an-1".to_string(),
+            risk_score: 0.4,
+            clauses: vec![
+                Clause {
+                    id: "clause-001".to_string(),
+                    text: "Governed by the laws of Japan.".to_string(),
+                    clause_type: "Jurisdiction".to_string(),
+                    risk_level: "low".to_string(),
+                    confidence: 0.9,
+                    review_status: "auto".to_string(),
+                    provenance: None,
+                    excerpt: None,
+                    position: None,
+                },
+                Clause {
+                    id: "clause-002".to_string(),
+                    text: "Limitation of liability.".to_string(),
+                    clause_type: "Liability".to_string(),
+                    risk_level: "high".to_string(),
+                    confidence: 0.35,
+                    review_status: "auto".to_string(),
+                    provenance: None,
+                    excerpt: None,
+                    position: None,
+                },
+            ],
+            issues: vec![Issue {
+                id: "issue-001".to_string(),
+                description: "Ambiguous indemnification clause detected.".to_string(),
+                severity: "high".to_string(),
+                location: "Section 4.2".to_string(),
+                confidence: 0.35,
+                review_status: "auto".to_string(),
+                provenance: None,
+                excerpt: None,
+                position: None,
+            }],
+            escalations: Vec::new(),
+            language: "en".to_string(),
+            language_detection: crate::language::detect("", "en"),
+            word_count: 10,
+            partial: false,
+            skipped_stages: Vec::new(),
+            paper: PaperDetection {
+                source: PaperSource::Counterparty,
+                template_id: None,
+                revision: None,
+                similarity: 0.0,
+                review_profile: AnalysisMode::Deep,
+            },
+            truncation: None,
+            style_deviations: Vec::new(),
+            ensemble: None,
+            resource_limits: None,
+            fallback_clauses: Vec::new(),
+            entities: Default::default(),
+            favorability: None,
+            playbook: Vec::new(),
+            metadata: Default::default(),
+        }
+    }
+
+    fn request(verdict: Verdict) -> ResolveRequest {
+        ResolveRequest {
+            verdict,
+            reviewer: "counsel@example.com".to_string(),
+            clause_type: Some("Indemnification".to_string()),
+            risk_level: Some("medium".to_string()),
+            severity: Some("low".to_string()),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn escalates_only_low_confidence_items() {
+        let store = EscalationStore::default();
+        let mut a = analysis();
+        let created = store.escalate(&mut a, 0.5, &SequentialIds::default());
+        assert_eq!(created.len(), 2);
+        assert_eq!(a.escalations.len(), 2);
+        assert_eq!(a.clauses[0].review_status, "auto");
+        assert_eq!(a.clauses[1].review_status, "pending_human");
+        assert_eq!(a.issues[0].review_status, "pending_human");
+    }
+
+    #[test]
+    fn nothing_stored_above_threshold() {
+        let store = EscalationStore::default();
+        let mut a = analysis();
+        assert!(store
+            .escalate(&mut a, 0.1, &SequentialIds::default())
+            .is_empty());
+        assert!(store.pending().is_empty());
+    }
+
+    #[test]
+    fn override_merges_into_stored_analysis() {
+        let store = EscalationStore::default();
+        let mut a = analysis();
+        let created = store.escalate(&mut a, 0.5, &SequentialIds::default());
+        let clause_esc = created
+            .iter()
+            .find(|e| e.item_kind == ItemKind::Clause)
+            .unwrap();
+
+        let resolved = store
+            .resolve(&clause_esc.id, request(Verdict::Override))
+            .unwrap();
+        let clause = &resolved.analysis.clauses[1];
+        assert_eq!(clause.clause_type, "Indemnification");
+        assert_eq!(clause.risk_level, "medium");
+        assert_eq!(clause.review_status, "human_overridden");
+        assert_eq!(resolved.analysis.escalations.len(), 1);
+        assert_eq!(store.pending().len(), 1);
+    }
+
+    #[test]
+    fn reject_removes_item_and_double_resolve_conflicts() {
+        let store = EscalationStore::default();
+        let mut a = analysis();
+        let created = store.escalate(&mut a, 0.5, &SequentialIds::default());
+        let issue_esc = created
+            .iter()
+            .find(|e| e.item_kind == ItemKind::Issue)
+            .unwrap();
+
+        let resolved = store
+            .resolve(&issue_esc.id, request(Verdict::Reject))
+            .unwrap();
+        assert!(resolved.analysis.issues.is_empty());
+        assert_eq!(
+            store
+                .resolve(&issue_esc.id, request(Verdict::Confirm))
+                .unwrap_err(),
+            StatusCode::CONFLICT
+        );
+    }
+
+    #[test]
+    fn full_store_evicts_reviewed_analyses_first() {
+        let store = EscalationStore::new(2);
+        let ids = SequentialIds::default();
+        let escalate = |id: &str| {
+            let mut a = analysis();
+            a.analysis_id = id.to_string();
+            store.escalate(&mut a, 0.5, &ids)
+        };
+        escalate("an-1");
+        for esc in escalate("an-2") {
+            store.resolve(&esc.id, request(Verdict::Confirm)).unwrap();
+        }
+
+        escalate("an-3");
+        assert!(store.analyses.contains_key("an-1"));
+        assert!(!store.analyses.contains_key("an-2"));
+        assert!(store.escalations.iter().all(|e| e.analysis_id != "an-2"));
+
+        // Nothing reviewed left: the oldest pending analysis goes.
+        escalate("an-4");
+        assert!(!store.analyses.contains_key("an-1"));
+        assert!(store.pending().iter().all(|e| e.analysis_id != "an-1"));
+        assert_eq!(store.pending().len(), 4);
+    }
+
+    #[test]
+    fn unknown_escalation_is_not_found() {
+        let store = EscalationStore::default();
+        assert_eq!(
+            store
+                .resolve("esc-missing", request(Verdict::Confirm))
+                .unwrap_err(),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use axum::http::StatusCode;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::extraction::{line_around, section_heading};
+
+/// Characters of surrounding text kept on each side of a finding.
+pub const DEFAULT_CONTEXT_CHARS: usize = 60;
+/// Upper bound so a single excerpt cannot carry most of a long contract.
+pub const MAX_CONTEXT_CHARS: usize = 500;
+
+/// Client-tunable `analysis_options` shared by the analysis endpoints.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
+pub struct EvidenceOptions {
+    pub evidence_context_chars: Option<usize>,
+    /// Check the contract type's mandatory clauses and return fallback
+    /// language for the missing ones.
+    #[serde(default)]
+    pub fallback_clauses: bool,
+}
+
+impl EvidenceOptions {
+    /// The requested window, or 400 when it is out of bounds.
+    pub fn context_chars(&self) -> Result<usize, StatusCode> {
+        match self.evidence_context_chars {
+            None => Ok(DEFAULT_CONTEXT_CHARS),
+            Some(n) if n <= MAX_CONTEXT_CHARS => Ok(n),
+            Some(_) => Err(StatusCode::BAD_REQUEST),
+        }
+    }
+}
+
+/// Where a finding's evidence is in the submitted document, for
+/// highlighting it.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Position {
+    /// Byte offsets of the matched text; `end` is exclusive.
+    pub start: usize,
+    pub end: usize,
+    /// 1-based lines of the first and last matched characters.
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Heading of the numbered section the match is in, e.g. "4. Termination".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+}
+
+impl Position {
+    /// The section heading, or the line when the match precedes every
+    /// numbered section.
+    pub fn label(&self) -> String {
+        self.section
+            .clone()
+            .unwrap_or_else(|| format!("Line {}", self.start_line))
+    }
+}
+
+/// The position of `text[start..end]`: its lines and the nearest numbered
+/// heading at or above it.
+pub fn position(text: &str, start: usize, end: usize) -> Position {
+    let start_line = text[..start].matches('\n').count() + 1;
+    let end_line = start_line
+        + text[start..end]
+            .trim_end_matches('\n')
+            .matches('\n')
+            .count();
+    let line = line_around(text, start);
+    let section = std::iter::once(&text[line.clone()])
+        .chain(text[..line.start].lines().rev())
+        .find_map(section_heading);
+    Position {
+        start,
+        end,
+        start_line,
+        end_line,
+        section,
+    }
+}
+
+/// `text[start..end]` plus up to `context_chars` characters either side,
+/// trimmed. `start` and `end` are byte offsets on char boundaries.
+pub fn excerpt(text: &str, start: usize, end: usize, context_chars: usize) -> String {
+    let from = match context_chars {
+        0 => start,
+        n => text[..start]
+            .char_indices()
+            .rev()
+            .nth(n - 1)
+            .map_or(0, |(i, _)| i),
+    };
+    let to = text[end..]
+        .char_indices()
+        .nth(context_chars)
+        .map_or(text.len(), |(i, _)| end + i);
+    text[from..to].trim().to_string()
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_counts_characters_not_bytes() {
+        let text = "契約契約 match 条項条項";
+        let start = text.find("match").unwrap();
+        assert_eq!(excerpt(text, start, start + 5, 3), "契約 match 条項");
+        assert_eq!(excerpt(text, start, start + 5, 0), "match");
+        assert_eq!(excerpt(text, start, start + 5, 100), text);
+    }
+
+    #[test]
+    fn positions_give_lines_and_the_enclosing_section() {
+        let text = "MASTER AGREEMENT\nRecitals.\n4. Termination. Either party\nmay terminate.\n\
+                    Section 4.2 Notice. Notices are in writing.\n";
+        let start = text.find("Either").unwrap();
+        let end = text.find("terminate.").unwrap() + 9;
+        let found = position(text, start, end);
+        assert_eq!((found.start_line, found.end_line), (3, 4));
+        assert_eq!(found.section.as_deref(), Some("4. Termination"));
+
+        let notices = text.find("Notices").unwrap();
+        assert_eq!(
+            position(text, notices, notices + 7).label(),
+            "Section 4.2 Notice"
+        );
+        let recitals = text.find("Recitals").unwrap();
+        assert_eq!(position(text, recitals, recitals + 8).label(), "Line 2");
+    }
+
+    #[test]
+    fn context_chars_are_bounded() {
+        let opts = |n| EvidenceOptions {
+            evidence_context_chars: n,
+            ..EvidenceOptions::default()
+        };
+        assert_eq!(opts(None).context_chars(), Ok(DEFAULT_CONTEXT_CHARS));
+        assert_eq!(opts(Some(0)).context_chars(), Ok(0));
+        assert_eq!(
+            opts(Some(MAX_CONTEXT_CHARS + 1)).context_chars(),
+            Err(StatusCode::BAD_REQUEST)
+        );
+    }
+}
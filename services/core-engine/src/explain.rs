@@ -0,0 +1,391 @@
+//! Plain-language explanation of a risk score: the factors that drive it,
+//! where in the contract their evidence sits, and one short paragraph built
+//! from that in the request language. Phrasing is rule-based for the
+//! supported languages; other languages go to an optional LLM endpoint and
+//! fall back to English when none answers.
+
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::{
+    evidence::{self, DEFAULT_CONTEXT_CHARS},
+    model_calls::{self, Caller, Purpose},
+    risk_model::RiskModel,
+    AppState, RiskFactor,
+};
+
+/// Built-in factors with localized wording; other factors of a configured
+/// risk model are named as they are.
+const PHRASED_FACTORS: [&str; 4] = [
+    "Liability Clauses",
+    "Indemnification",
+    "Termination Rights",
+    "IP Assignment",
+];
+/// Factors named in the paragraph, most significant first.
+const MAX_DRIVERS: usize = 2;
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct ExplainConfig {
+    /// Receives the factors and evidence as JSON and answers `{"text": …}`.
+    pub llm_url: Option<String>,
+    pub llm_timeout: Duration,
+}
+
+impl Default for ExplainConfig {
+    fn default() -> Self {
+        Self {
+            llm_url: None,
+            llm_timeout: Duration::from_secs(3),
+        }
+    }
+}
+
+impl ExplainConfig {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        Self {
+            llm_url: std::env::var("LEGAL_EXPLAIN_LLM_URL")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
+            llm_timeout: std::env::var("LEGAL_EXPLAIN_LLM_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map_or(d.llm_timeout, Duration::from_millis),
+        }
+    }
+}
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExplanationSource {
+    Rules,
+    Llm,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct FactorEvidence {
+    pub factor: String,
+    pub contribution: f64,
+    /// Clause number the evidence sits under, as written ("9", "4.2").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    pub excerpt: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Explanation {
+    pub text: String,
+    /// Language of `text`; English when the requested one was unavailable.
+    pub language: String,
+    pub source: ExplanationSource,
+    pub evidence: Vec<FactorEvidence>,
+    /// The recorded explanation model call, when one was made.
+    #[serde(skip)]
+    pub model_call: Option<String>,
+}
+
+// ── Evidence ──────────────────────────────────────────────────────────────────
+
+/// The clause number at the start of the line holding `offset`.
+fn section(document: &str, offset: usize) -> Option<String> {
+    let start = document[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = document[start..].trim_start();
+    let line = ["Section ", "Article ", "Clause "]
+        .iter()
+        .find_map(|p| line.strip_prefix(p))
+        .unwrap_or(line);
+    let number: String = line
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let number = number.trim_end_matches('.');
+    (!number.is_empty() && number.starts_with(|c: char| c.is_ascii_digit()))
+        .then(|| number.to_string())
+}
+
+/// Evidence for every factor the document raised, largest contribution
+/// first; `factors` are scored by `model`, in its order.
+pub fn evidence(model: &RiskModel, document: &str, factors: &[RiskFactor]) -> Vec<FactorEvidence> {
+    let lower = document.to_ascii_lowercase();
+    let mut found: Vec<FactorEvidence> = factors
+        .iter()
+        .zip(&model.factors)
+        .filter_map(|(factor, definition)| {
+            let (start, trigger) = definition
+                .keywords
+                .iter()
+                .filter_map(|t| lower.find(t.as_str()).map(|at| (at, t)))
+                .min()?;
+            Some(FactorEvidence {
+                factor: factor.factor.clone(),
+                contribution: factor.weight * factor.score,
+                section: section(document, start),
+                excerpt: evidence::excerpt(
+                    document,
+                    start,
+                    start + trigger.len(),
+                    DEFAULT_CONTEXT_CHARS,
+                ),
+            })
+        })
+        .collect();
+    found.sort_by(|a, b| b.contribution.total_cmp(&a.contribution));
+    found
+}
+
+// ── Phrasing ──────────────────────────────────────────────────────────────────
+
+/// Localized wording; `finding` is indexed like `PHRASED_FACTORS`.
+struct Phrases {
+    levels: [&'static str; 4],
+    finding: [&'static str; 4],
+    section: fn(&str) -> String,
+    drivers: fn(&str, &str, &str) -> String,
+    baseline: fn(&str, &str) -> String,
+    and: &'static str,
+}
+
+fn phrases(language: &str) -> Option<Phrases> {
+    let lang = language.split(['-', '_']).next().unwrap_or(language);
+    match lang {
+        "en" => Some(Phrases {
+            levels: ["low", "medium", "high", "critical"],
+            finding: [
+                "a limitation of liability clause",
+                "an indemnification obligation",
+                "termination provisions",
+                "an intellectual property transfer",
+            ],
+            section: |n| format!(" in Section {n}"),
+            drivers: |level, score, drivers| {
+                format!("The score is {level} ({score}) primarily because the contract contains {drivers}.")
+            },
+            baseline: |level, score| {
+                format!("The score is {level} ({score}); none of the weighted risk clauses were found, so it reflects baseline exposure and document length only.")
+            },
+            and: ", and ",
+        }),
+        "de" => Some(Phrases {
+            levels: ["niedrig", "mittel", "hoch", "kritisch"],
+            finding: [
+                "eine Haftungsbeschränkung",
+                "eine Freistellungsverpflichtung",
+                "Kündigungsregelungen",
+                "eine Übertragung geistigen Eigentums",
+            ],
+            section: |n| format!(" in Abschnitt {n}"),
+            drivers: |level, score, drivers| {
+                format!("Der Risikowert ist {level} ({score}), vor allem weil der Vertrag {drivers} enthält.")
+            },
+            baseline: |level, score| {
+                format!("Der Risikowert ist {level} ({score}); es wurden keine gewichteten Risikoklauseln gefunden, er spiegelt nur das Grundrisiko und die Dokumentlänge wider.")
+            },
+            and: " sowie ",
+        }),
+        "fr" => Some(Phrases {
+            levels: ["faible", "moyen", "élevé", "critique"],
+            finding: [
+                "une clause de limitation de responsabilité",
+                "une obligation d'indemnisation",
+                "des dispositions de résiliation",
+                "une cession de propriété intellectuelle",
+            ],
+            section: |n| format!(" à l'article {n}"),
+            drivers: |level, score, drivers| {
+                format!("Le score de risque est {level} ({score}), principalement parce que le contrat contient {drivers}.")
+            },
+            baseline: |level, score| {
+                format!("Le score de risque est {level} ({score}) ; aucune clause de risque pondérée n'a été trouvée, il reflète uniquement le risque de base et la longueur du document.")
+            },
+            and: ", ainsi que ",
+        }),
+        "ja" => Some(Phrases {
+            levels: ["低", "中", "高", "重大"],
+            finding: ["責任制限条項", "補償義務", "解除条項", "知的財産権の譲渡"],
+            section: |n| format!("第{n}条の"),
+            drivers: |level, score, drivers| {
+                format!("リスクスコアは{level}（{score}）です。主な要因は{drivers}です。")
+            },
+            baseline: |level, score| {
+                format!("リスクスコアは{level}（{score}）です。重み付けされたリスク条項は見つからず、基礎リスクと文書の長さのみを反映しています。")
+            },
+            and: "、および",
+        }),
+        _ => None,
+    }
+}
+
+fn level_index(level: &str) -> Option<usize> {
+    match level {
+        "critical" => Some(3),
+        "high" => Some(2),
+        "medium" => Some(1),
+        "low" => Some(0),
+        _ => None,
+    }
+}
+
+/// The rule-based paragraph, or `None` when the language has no phrasing.
+pub fn rule_based(
+    level: &str,
+    score: f64,
+    evidence: &[FactorEvidence],
+    language: &str,
+) -> Option<String> {
+    let p = phrases(language)?;
+    // Levels a configured model adds keep their own name.
+    let level = level_index(level).map_or(level, |i| p.levels[i]);
+    let score = format!("{score:.2}");
+    if evidence.is_empty() {
+        return Some((p.baseline)(level, &score));
+    }
+    let drivers: Vec<String> = evidence
+        .iter()
+        .take(MAX_DRIVERS)
+        .map(|e| {
+            let finding = PHRASED_FACTORS
+                .iter()
+                .position(|f| *f == e.factor)
+                .map_or(e.factor.as_str(), |i| p.finding[i]);
+            match &e.section {
+                // Japanese puts the location before the noun.
+                Some(n) if p.levels[0] == "低" => format!("{}{finding}", (p.section)(n)),
+                Some(n) => format!("{finding}{}", (p.section)(n)),
+                None => finding.to_string(),
+            }
+        })
+        .collect();
+    Some((p.drivers)(level, &score, &drivers.join(p.and)))
+}
+
+// ── Generation ────────────────────────────────────────────────────────────────
+
+async fn from_llm(
+    state: &AppState,
+    caller: &Caller<'_>,
+    url: &str,
+    body: &Value,
+) -> Option<String> {
+    let request = state.http.post(url).timeout(state.explain.llm_timeout);
+    let answer = model_calls::exchange(state, Some(caller), request, url, body)
+        .await
+        .inspect_err(|e| warn!(error = %e, "explanation model failed"))
+        .ok()?;
+    answer["text"]
+        .as_str()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+}
+
+pub async fn explain(
+    state: &AppState,
+    tenant: &str,
+    document: &str,
+    factors: &[RiskFactor],
+    level: &str,
+    score: f64,
+    language: &str,
+) -> Explanation {
+    let evidence = evidence(&state.risk_model, document, factors);
+    if let Some(text) = rule_based(level, score, &evidence, language) {
+        return Explanation {
+            text,
+            language: language.to_string(),
+            source: ExplanationSource::Rules,
+            evidence,
+            model_call: None,
+        };
+    }
+    let mut model_call = None;
+    if let Some(url) = &state.explain.llm_url {
+        let body = json!({
+            "language": language,
+            "overall_score": score,
+            "risk_level": level,
+            "risk_factors": factors,
+            "evidence": evidence,
+        });
+        let caller = Caller::new(state, tenant, Purpose::Explanation);
+        let text = from_llm(state, &caller, url, &body).await;
+        model_call = Some(caller.id);
+        if let Some(text) = text {
+            return Explanation {
+                text,
+                language: language.to_string(),
+                source: ExplanationSource::Llm,
+                evidence,
+                model_call,
+            };
+        }
+    }
+    Explanation {
+        text: rule_based(level, score, &evidence, "en").unwrap_or_default(),
+        language: "en".to_string(),
+        source: ExplanationSource::Rules,
+        evidence,
+        model_call,
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "1. Services. Vendor provides services.\n\
+        9. Indemnification. Vendor shall indemnify Customer without limit.\n\
+        Section 12 Limitation of Liability. Capped at fees paid.\n";
+
+    #[test]
+    fn evidence_is_ranked_and_located() {
+        let model = RiskModel::default();
+        let factors = model.factors(DOC);
+        let found = evidence(&model, DOC, &factors);
+        let names: Vec<&str> = found.iter().map(|e| e.factor.as_str()).collect();
+        assert_eq!(names, ["Liability Clauses", "Indemnification"]);
+        assert_eq!(found[0].section.as_deref(), Some("12"));
+        assert_eq!(found[1].section.as_deref(), Some("9"));
+        assert!(found[1].excerpt.contains("Indemnification"));
+    }
+
+    #[test]
+    fn paragraph_names_the_top_drivers_in_each_language() {
+        let model = RiskModel::default();
+        let factors = model.factors(DOC);
+        let found = evidence(&model, DOC, &factors);
+        let en = rule_based("high", 0.58, &found, "en").unwrap();
+        assert_eq!(
+            en,
+            "The score is high (0.58) primarily because the contract contains a limitation \
+             of liability clause in Section 12, and an indemnification obligation in Section 9."
+        );
+        let ja = rule_based("high", 0.58, &found, "ja-JP").unwrap();
+        assert!(ja.starts_with("リスクスコアは高（0.58）です。"));
+        assert!(ja.contains("第9条の補償義務"));
+        assert!(rule_based("high", 0.58, &found, "de")
+            .unwrap()
+            .contains("in Abschnitt 9"));
+        assert!(rule_based("high", 0.58, &found, "pt").is_none());
+    }
+
+    #[test]
+    fn no_evidence_explains_baseline() {
+        let model = RiskModel::default();
+        let factors = model.factors("A short letter.");
+        let found = evidence(&model, "A short letter.", &factors);
+        assert!(found.is_empty());
+        let en = rule_based("low", 0.24, &found, "en").unwrap();
+        assert!(en.contains("baseline exposure"));
+    }
+}
@@ -0,0 +1,470 @@
+use std::sync::RwLock;
+
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    evidence::{self, Position},
+    AppState, Issue,
+};
+
+const TECH_KEYWORDS: [&str; 8] = [
+    "software",
+    "source code",
+    "technical data",
+    "technology",
+    "encryption",
+    "cryptograph",
+    "firmware",
+    "semiconductor",
+];
+
+const EXPORT_CLAUSE_KEYWORDS: [&str; 7] = [
+    "export control",
+    "export administration regulations",
+    "international traffic in arms",
+    "itar",
+    "ofac",
+    "economic sanctions",
+    "re-export",
+];
+
+pub const ADDRESS_MARKERS: [&str; 5] = [
+    "address",
+    "located at",
+    "registered office",
+    "principal place of business",
+    "with offices at",
+];
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CountryStatus {
+    Standard,
+    /// Licence requirements commonly apply to software and technical data.
+    Restricted,
+    /// Comprehensive sanctions; transactions generally prohibited.
+    Embargoed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountryPolicy {
+    pub country: String,
+    pub aliases: Vec<String>,
+    pub status: CountryStatus,
+    pub reference: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyTable {
+    pub countries: Vec<CountryPolicy>,
+}
+
+impl PolicyTable {
+    /// Every entry names a country and at least one alias.
+    pub fn is_valid(&self) -> bool {
+        self.countries
+            .iter()
+            .all(|c| !c.country.trim().is_empty() && c.aliases.iter().any(|a| !a.trim().is_empty()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CountryMention {
+    pub country: String,
+    pub status: CountryStatus,
+    pub matched_text: String,
+    pub offset: usize,
+    pub in_address: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportFinding {
+    pub kind: String,
+    pub severity: String,
+    pub description: String,
+    pub country: Option<String>,
+    pub reference: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportControlReport {
+    pub involves_controlled_items: bool,
+    pub controlled_item_terms: Vec<String>,
+    pub has_export_clause: bool,
+    pub countries: Vec<CountryMention>,
+    pub findings: Vec<ExportFinding>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckRequest {
+    pub document: String,
+}
+
+// ── Policy ────────────────────────────────────────────────────────────────────
+
+fn policy(
+    country: &str,
+    aliases: &[&str],
+    status: CountryStatus,
+    reference: &str,
+) -> CountryPolicy {
+    CountryPolicy {
+        country: country.to_string(),
+        aliases: aliases.iter().map(|a| a.to_string()).collect(),
+        status,
+        reference: Some(reference.to_string()),
+    }
+}
+
+impl Default for PolicyTable {
+    fn default() -> Self {
+        use CountryStatus::{Embargoed, Restricted};
+        Self {
+            countries: vec![
+                policy(
+                    "Cuba",
+                    &["cuba"],
+                    Embargoed,
+                    "31 CFR Part 515; EAR Part 746.2",
+                ),
+                policy(
+                    "Iran",
+                    &["iran", "islamic republic of iran"],
+                    Embargoed,
+                    "31 CFR Part 560; EAR Part 746.7",
+                ),
+                policy(
+                    "North Korea",
+                    &[
+                        "north korea",
+                        "dprk",
+                        "democratic people's republic of korea",
+                    ],
+                    Embargoed,
+                    "31 CFR Part 510; EAR Part 746.4",
+                ),
+                policy(
+                    "Syria",
+                    &["syria", "syrian arab republic"],
+                    Embargoed,
+                    "31 CFR Part 542; EAR Part 746.9",
+                ),
+                policy(
+                    "Crimea / occupied regions of Ukraine",
+                    &["crimea", "sevastopol", "donetsk", "luhansk"],
+                    Embargoed,
+                    "EAR Part 746.6",
+                ),
+                policy(
+                    "Russia",
+                    &["russia", "russian federation"],
+                    Restricted,
+                    "EAR Part 746.8",
+                ),
+                policy("Belarus", &["belarus"], Restricted, "EAR Part 746.8"),
+                policy(
+                    "China",
+                    &["china", "people's republic of china", "prc"],
+                    Restricted,
+                    "EAR Part 744",
+                ),
+                policy("Venezuela", &["venezuela"], Restricted, "EAR Part 744.21"),
+                policy(
+                    "Myanmar",
+                    &["myanmar", "burma"],
+                    Restricted,
+                    "EAR Part 744.21",
+                ),
+            ],
+        }
+    }
+}
+
+/// The active country table; replaced wholesale through the policy endpoint.
+#[derive(Default)]
+pub struct ExportPolicyStore {
+    table: RwLock<PolicyTable>,
+}
+
+impl ExportPolicyStore {
+    pub fn get(&self) -> PolicyTable {
+        self.table
+            .read()
+            .expect("export policy lock poisoned")
+            .clone()
+    }
+
+    pub fn replace(&self, table: PolicyTable) {
+        *self.table.write().expect("export policy lock poisoned") = table;
+    }
+}
+
+// ── Detection ─────────────────────────────────────────────────────────────────
+
+/// ASCII word-boundary search over an ASCII-lowercased haystack.
+pub fn find_words(lower: &str, needle: &str) -> Vec<usize> {
+    let bytes = lower.as_bytes();
+    lower
+        .match_indices(needle)
+        .map(|(i, _)| i)
+        .filter(|&i| {
+            let before = i == 0 || !bytes[i - 1].is_ascii_alphanumeric();
+            let end = i + needle.len();
+            let after = end >= bytes.len() || !bytes[end].is_ascii_alphanumeric();
+            before && after
+        })
+        .collect()
+}
+
+fn line_at(text: &str, offset: usize) -> &str {
+    let start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let end = text[offset..].find('\n').map_or(text.len(), |i| offset + i);
+    &text[start..end]
+}
+
+pub fn check(document: &str, table: &PolicyTable) -> ExportControlReport {
+    let lower = document.to_ascii_lowercase();
+
+    let controlled_item_terms: Vec<String> = TECH_KEYWORDS
+        .iter()
+        .filter(|k| lower.contains(*k))
+        .map(|k| k.to_string())
+        .collect();
+    // "EAR" is only meaningful in capitals; lowercase "ear" is just a word.
+    let has_export_clause = EXPORT_CLAUSE_KEYWORDS
+        .iter()
+        .any(|k| !find_words(&lower, k).is_empty())
+        || !find_words(document, "EAR").is_empty();
+
+    let mut countries = Vec::new();
+    for entry in &table.countries {
+        for alias in &entry.aliases {
+            let alias = alias.to_ascii_lowercase();
+            for offset in find_words(&lower, &alias) {
+                let line = line_at(&lower, offset);
+                countries.push(CountryMention {
+                    country: entry.country.clone(),
+                    status: entry.status,
+                    matched_text: document[offset..offset + alias.len()].to_string(),
+                    offset,
+                    in_address: ADDRESS_MARKERS.iter().any(|m| line.contains(m)),
+                });
+            }
+        }
+    }
+    countries.sort_by_key(|m| m.offset);
+
+    let mut findings = Vec::new();
+    let involves = !controlled_item_terms.is_empty();
+    let mut seen: Vec<&str> = Vec::new();
+    for mention in &countries {
+        if seen.contains(&mention.country.as_str()) {
+            continue;
+        }
+        seen.push(&mention.country);
+        let reference = table
+            .countries
+            .iter()
+            .find(|c| c.country == mention.country)
+            .and_then(|c| c.reference.clone());
+        match mention.status {
+            CountryStatus::Embargoed => findings.push(ExportFinding {
+                kind: "embargoed_destination".to_string(),
+                severity: "critical".to_string(),
+                description: format!(
+                    "Contract references {}, an embargoed destination{}.",
+                    mention.country,
+                    if mention.in_address { " in a party address" } else { "" }
+                ),
+                country: Some(mention.country.clone()),
+                reference,
+            }),
+            CountryStatus::Restricted if involves && !has_export_clause => findings.push(ExportFinding {
+                kind: "missing_export_clause".to_string(),
+                severity: "high".to_string(),
+                description: format!(
+                    "Software or technical data with a counterparty nexus to {} but no export-control (EAR/ITAR) clause.",
+                    mention.country
+                ),
+                country: Some(mention.country.clone()),
+                reference,
+            }),
+            _ => {}
+        }
+    }
+    if involves
+        && !has_export_clause
+        && countries
+            .iter()
+            .any(|m| m.status == CountryStatus::Embargoed)
+    {
+        findings.push(ExportFinding {
+            kind: "missing_export_clause".to_string(),
+            severity: "high".to_string(),
+            description: "Software or technical data is in scope but the contract has no export-control (EAR/ITAR) clause.".to_string(),
+            country: None,
+            reference: None,
+        });
+    }
+
+    ExportControlReport {
+        involves_controlled_items: involves,
+        controlled_item_terms,
+        has_export_clause,
+        countries,
+        findings,
+    }
+}
+
+/// Findings rendered as analysis issues; `next_id` continues the caller's numbering.
+pub fn issues(
+    document: &str,
+    table: &PolicyTable,
+    next_id: usize,
+    context_chars: usize,
+) -> Vec<Issue> {
+    let report = check(document, table);
+    report
+        .findings
+        .into_iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let mention = f
+                .country
+                .as_ref()
+                .and_then(|c| report.countries.iter().find(|m| &m.country == c));
+            let position = mention
+                .map(|m| evidence::position(document, m.offset, m.offset + m.matched_text.len()));
+            Issue {
+                id: format!("issue-{:03}", next_id + i),
+                location: position
+                    .as_ref()
+                    .map_or_else(|| "Document".to_string(), Position::label),
+                excerpt: mention.map(|m| {
+                    evidence::excerpt(
+                        document,
+                        m.offset,
+                        m.offset + m.matched_text.len(),
+                        context_chars,
+                    )
+                }),
+                description: f.description,
+                severity: f.severity,
+                confidence: 0.8,
+                review_status: "auto".to_string(),
+                provenance: None,
+                position,
+            }
+        })
+        .collect()
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn check_document(
+    State(state): State<AppState>,
+    Json(req): Json<CheckRequest>,
+) -> Result<Json<ExportControlReport>, StatusCode> {
+    if req.document.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let report = check(&req.document, &state.export_policy.get());
+    info!(
+        countries = report.countries.len(),
+        findings = report.findings.len(),
+        "export control check completed"
+    );
+    Ok(Json(report))
+}
+
+pub async fn get_policy(State(state): State<AppState>) -> Json<PolicyTable> {
+    Json(state.export_policy.get())
+}
+
+pub async fn put_policy(
+    State(state): State<AppState>,
+    Json(table): Json<PolicyTable>,
+) -> Result<Json<PolicyTable>, StatusCode> {
+    if !table.is_valid() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    info!(
+        countries = table.countries.len(),
+        "export control policy replaced"
+    );
+    state.export_policy.replace(table.clone());
+    Ok(Json(table))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embargoed_address_is_critical() {
+        let doc = "Licensee: Acme Trading, registered office at 12 Street, Havana, Cuba.\nThe software is licensed...";
+        let report = check(doc, &PolicyTable::default());
+        assert!(report.countries[0].in_address);
+        assert!(report
+            .findings
+            .iter()
+            .any(|f| f.kind == "embargoed_destination" && f.severity == "critical"));
+        assert!(report
+            .findings
+            .iter()
+            .any(|f| f.kind == "missing_export_clause"));
+    }
+
+    #[test]
+    fn restricted_country_needs_clause_only_for_tech() {
+        let table = PolicyTable::default();
+        let tech = "Licensor grants a software licence to a company located at Minsk, Belarus.";
+        assert_eq!(check(tech, &table).findings.len(), 1);
+
+        let goods = "Supplier delivers office chairs to a company located at Minsk, Belarus.";
+        assert!(check(goods, &table).findings.is_empty());
+    }
+
+    #[test]
+    fn export_clause_satisfies_requirement() {
+        let doc = "Software delivered to Moscow, Russia. Licensee shall comply with the U.S. Export Administration Regulations (EAR).";
+        let report = check(doc, &PolicyTable::default());
+        assert!(report.has_export_clause);
+        assert!(report.findings.is_empty());
+    }
+
+    #[test]
+    fn word_boundaries_avoid_false_matches() {
+        // "prc" inside another word and lowercase "ear" are not hits.
+        let report = check(
+            "Software for the procurement year; near Paris.",
+            &PolicyTable::default(),
+        );
+        assert!(report.countries.is_empty());
+        assert!(!report.has_export_clause);
+    }
+
+    #[test]
+    fn custom_policy_table_is_respected() {
+        let table = PolicyTable {
+            countries: vec![policy(
+                "Atlantis",
+                &["atlantis"],
+                CountryStatus::Embargoed,
+                "Internal policy 7",
+            )],
+        };
+        let report = check("Address: 1 Coral Way, Atlantis", &table);
+        assert_eq!(
+            report.findings[0].reference.as_deref(),
+            Some("Internal policy 7")
+        );
+        assert!(check("Address: Havana, Cuba", &table).findings.is_empty());
+    }
+}
@@ -0,0 +1,360 @@
+//! Clause extraction strategies behind one trait, chosen at startup with
+//! `LEGAL_CLAUSE_EXTRACTOR`. A new strategy implements `ClauseExtractor`
+//! and adds its name to `by_name`; embedders can also set
+//! `AppState::clause_extractor` directly.
+
+use std::{collections::HashSet, ops::Range, sync::Arc};
+
+use crate::{
+    evidence, extract_first_sentence, keyword_confidence,
+    style::section_label,
+    taxonomy::{self, ClauseKind, TAXONOMY},
+    warmup::KeywordMatcher,
+    Clause,
+};
+
+pub trait ClauseExtractor: Send + Sync {
+    /// Name used in `LEGAL_CLAUSE_EXTRACTOR`.
+    fn name(&self) -> &'static str;
+
+    /// Clauses of `document`, matching through the caller's (possibly
+    /// tenant-specific) `keywords`, with `context_chars` of evidence context.
+    fn extract(
+        &self,
+        keywords: &KeywordMatcher,
+        document: &str,
+        context_chars: usize,
+    ) -> Vec<Clause>;
+
+    /// Joins the clauses `extract` found in consecutive chunks of
+    /// `document`, already positioned in it, into the document's clauses.
+    /// By default every chunk's clauses are kept.
+    fn merge(&self, _document: &str, chunks: Vec<(Range<usize>, Vec<Clause>)>) -> Vec<Clause> {
+        renumber(
+            chunks
+                .into_iter()
+                .flat_map(|(_, clauses)| clauses)
+                .collect(),
+        )
+    }
+}
+
+/// Numbers merged clauses in order, as `extract` numbers its own.
+pub fn renumber(mut clauses: Vec<Clause>) -> Vec<Clause> {
+    for (i, clause) in clauses.iter_mut().enumerate() {
+        clause.id = format!("clause-{:03}", i + 1);
+    }
+    clauses
+}
+
+pub const DEFAULT_EXTRACTOR: &str = "keyword";
+
+/// The built-in extractor called `name`.
+pub fn by_name(name: &str) -> Option<Arc<dyn ClauseExtractor>> {
+    match name {
+        "keyword" => Some(Arc::new(KeywordRules)),
+        "structure" => Some(Arc::new(SectionStructure)),
+        _ => None,
+    }
+}
+
+/// The extractor named by `LEGAL_CLAUSE_EXTRACTOR`, `keyword` when unset.
+pub fn from_env() -> Arc<dyn ClauseExtractor> {
+    let name =
+        std::env::var("LEGAL_CLAUSE_EXTRACTOR").unwrap_or_else(|_| DEFAULT_EXTRACTOR.to_string());
+    by_name(name.trim()).expect("unknown LEGAL_CLAUSE_EXTRACTOR")
+}
+
+/// The clause type named by `title`, or else first mentioned in `body`.
+pub fn clause_kind(
+    keywords: &KeywordMatcher,
+    title: &str,
+    body: &str,
+) -> Option<&'static ClauseKind> {
+    let titled = keywords.hits(title);
+    TAXONOMY
+        .iter()
+        .find(|k| titled.contains_key(k.group))
+        .or_else(|| taxonomy::detect(keywords, body).first().map(|&(k, _)| k))
+}
+
+/// Byte range of the line around `offset`, without its line break.
+pub fn line_around(text: &str, offset: usize) -> Range<usize> {
+    let start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let end = text[offset..].find('\n').map_or(text.len(), |i| offset + i);
+    start..end
+}
+
+// ── Keyword rules ─────────────────────────────────────────────────────────────
+
+/// One clause per taxonomy type whose keywords occur in the document,
+/// in order of first mention, quoting the line of that mention.
+pub struct KeywordRules;
+
+impl ClauseExtractor for KeywordRules {
+    fn name(&self) -> &'static str {
+        "keyword"
+    }
+
+    fn extract(
+        &self,
+        keywords: &KeywordMatcher,
+        document: &str,
+        context_chars: usize,
+    ) -> Vec<Clause> {
+        taxonomy::detect(keywords, document)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (kind, (start, end)))| Clause {
+                id: format!("clause-{:03}", i + 1),
+                text: extract_first_sentence(&document[line_around(document, start)]),
+                clause_type: kind.name.to_string(),
+                risk_level: kind.risk.to_string(),
+                confidence: keyword_confidence(true),
+                review_status: "auto".to_string(),
+                provenance: None,
+                excerpt: Some(evidence::excerpt(document, start, end, context_chars)),
+                position: Some(evidence::position(document, start, end)),
+            })
+            .collect()
+    }
+
+    /// A type's first mention is in the first chunk that mentions it.
+    fn merge(&self, _document: &str, chunks: Vec<(Range<usize>, Vec<Clause>)>) -> Vec<Clause> {
+        let mut seen = HashSet::new();
+        renumber(
+            chunks
+                .into_iter()
+                .flat_map(|(_, clauses)| clauses)
+                .filter(|c| seen.insert(c.clause_type.clone()))
+                .collect(),
+        )
+    }
+}
+
+// ── Section structure ─────────────────────────────────────────────────────────
+
+/// One clause per numbered section ("4.", "4.2", "Section 4."), typed by
+/// the keyword group its heading names, or else the first one in its body.
+/// Documents without numbered sections fall back to `KeywordRules`.
+pub struct SectionStructure;
+
+/// Headings longer than this are taken for running text after the number.
+const MAX_TITLE_WORDS: usize = 8;
+
+#[derive(Debug, PartialEq)]
+pub struct Section<'a> {
+    /// Byte range of the whole section, heading included.
+    pub start: usize,
+    pub end: usize,
+    pub heading: &'a str,
+    pub title: &'a str,
+}
+
+/// The title after a heading's label: the text up to its first full
+/// stop, or nothing when that runs on like body text.
+fn heading_title(rest: &str) -> &str {
+    let title = rest.split('.').next().unwrap_or_default().trim();
+    if title.split_whitespace().count() <= MAX_TITLE_WORDS {
+        title
+    } else {
+        ""
+    }
+}
+
+/// The label and title of a numbered heading line, e.g. "4. Termination"
+/// for "4. Termination. Either party may...".
+pub fn section_heading(line: &str) -> Option<String> {
+    let line = line.trim();
+    let (label_len, _) = section_label(line)?;
+    let label = line[..label_len].trim_end();
+    Some(match heading_title(&line[label_len..]) {
+        "" => label.to_string(),
+        title => format!("{label} {title}"),
+    })
+}
+
+/// The numbered sections of `document`, in order.
+pub fn sections(document: &str) -> Vec<Section<'_>> {
+    let mut out: Vec<Section> = Vec::new();
+    let mut offset = 0;
+    for line in document.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start().len();
+        let heading = line.trim();
+        if let Some((label_len, _)) = section_label(heading) {
+            let title = heading_title(heading[label_len..].trim_start());
+            if let Some(prev) = out.last_mut() {
+                prev.end = offset + indent;
+            }
+            out.push(Section {
+                start: offset + indent,
+                end: document.len(),
+                heading,
+                title,
+            });
+        }
+        offset += line.len();
+    }
+    out
+}
+
+impl ClauseExtractor for SectionStructure {
+    fn name(&self) -> &'static str {
+        "structure"
+    }
+
+    fn extract(
+        &self,
+        keywords: &KeywordMatcher,
+        document: &str,
+        context_chars: usize,
+    ) -> Vec<Clause> {
+        let sections = sections(document);
+        if sections.is_empty() {
+            return KeywordRules.extract(keywords, document, context_chars);
+        }
+        sections
+            .iter()
+            .enumerate()
+            .map(|(i, section)| {
+                let body = &document[section.start..section.end];
+                let hits = keywords.hits(body);
+                let kind = clause_kind(keywords, section.title, body);
+                let (clause_type, risk_level) = match kind {
+                    Some(kind) => (kind.name.to_string(), kind.risk),
+                    None if section.title.is_empty() => ("General".to_string(), "low"),
+                    None => (section.title.to_string(), "low"),
+                };
+                let (start, end) = kind
+                    .and_then(|kind| hits.get(kind.group))
+                    .map_or((0, section.heading.len()), |&span| span);
+                let (start, end) = (section.start + start, section.start + end);
+                Clause {
+                    id: format!("clause-{:03}", i + 1),
+                    text: extract_first_sentence(section.heading),
+                    clause_type,
+                    risk_level: risk_level.to_string(),
+                    confidence: keyword_confidence(kind.is_some()),
+                    review_status: "auto".to_string(),
+                    provenance: None,
+                    excerpt: Some(evidence::excerpt(document, start, end, context_chars)),
+                    position: Some(evidence::position(document, start, end)),
+                }
+            })
+            .collect()
+    }
+
+    /// Chunks are cut at headings, so each section is found once. A chunk
+    /// without a heading of its own (the preamble, or the rest of an
+    /// oversized section) fell back to keywords where the whole document
+    /// would not, and is left out.
+    fn merge(&self, document: &str, chunks: Vec<(Range<usize>, Vec<Clause>)>) -> Vec<Clause> {
+        if sections(document).is_empty() {
+            return KeywordRules.merge(document, chunks);
+        }
+        renumber(
+            chunks
+                .into_iter()
+                .filter(|(range, _)| !sections(&document[range.clone()]).is_empty())
+                .flat_map(|(_, clauses)| clauses)
+                .collect(),
+        )
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warmup::Precompiled;
+
+    const CONTRACT: &str = "MASTER SERVICES AGREEMENT\n\n\
+        1. Governing Law. This Agreement is governed by the laws of New York.\n\
+        2. Fees. Fees are due monthly. Liability for late fees is capped.\n\
+        2.1 Taxes. The Customer pays all taxes.\n\
+        Section 3. Termination. Either party may terminate on notice.\n";
+
+    #[test]
+    fn sections_split_at_numbered_headings() {
+        let found: Vec<(&str, &str)> = sections(CONTRACT)
+            .iter()
+            .map(|s| (s.title, &CONTRACT[s.start..s.end]))
+            .collect();
+        assert_eq!(found.len(), 4);
+        assert_eq!(found[0].0, "Governing Law");
+        assert!(found[0].1.starts_with("1. Governing Law.") && found[0].1.ends_with("York.\n"));
+        assert_eq!(
+            found[2],
+            ("Taxes", "2.1 Taxes. The Customer pays all taxes.\n")
+        );
+        assert_eq!(found[3].0, "Termination");
+    }
+
+    #[test]
+    fn structure_types_each_section() {
+        let pre = Precompiled::default();
+        let clauses = SectionStructure.extract(pre.keywords(), CONTRACT, 0);
+        let types: Vec<(&str, &str, &str)> = clauses
+            .iter()
+            .map(|c| (c.id.as_str(), c.clause_type.as_str(), c.risk_level.as_str()))
+            .collect();
+        assert_eq!(
+            types,
+            [
+                ("clause-001", "Jurisdiction", "low"),
+                ("clause-002", "Liability", "high"),
+                ("clause-003", "Taxes", "low"),
+                ("clause-004", "Termination", "medium"),
+            ]
+        );
+        assert_eq!(clauses[0].excerpt.as_deref(), Some("Governing Law"));
+        assert_eq!(clauses[1].excerpt.as_deref(), Some("Liability"));
+        assert_eq!(clauses[2].confidence, keyword_confidence(false));
+    }
+
+    #[test]
+    fn keyword_rules_report_only_detected_types() {
+        let pre = Precompiled::default();
+        let text = "This Agreement renews automatically each year.\n\
+                    Neither party shall compete with the other; the Supplier shall not \
+                    compete in the Territory.\n\
+                    Each party keeps the other's Confidential Information secret.";
+        let clauses = KeywordRules.extract(pre.keywords(), text, 0);
+        let types: Vec<(&str, &str, &str)> = clauses
+            .iter()
+            .map(|c| (c.id.as_str(), c.clause_type.as_str(), c.risk_level.as_str()))
+            .collect();
+        assert_eq!(
+            types,
+            [
+                ("clause-001", "Auto-Renewal", "medium"),
+                ("clause-002", "Non-Compete", "high"),
+                ("clause-003", "Confidentiality", "medium"),
+            ]
+        );
+        assert_eq!(
+            clauses[0].text,
+            "This Agreement renews automatically each year."
+        );
+        assert_eq!(clauses[2].excerpt.as_deref(), Some("Confidential"));
+        assert!(KeywordRules
+            .extract(pre.keywords(), "The parties met.", 0)
+            .is_empty());
+    }
+
+    #[test]
+    fn unnumbered_documents_fall_back_to_keyword_rules() {
+        let pre = Precompiled::default();
+        let keywords = pre.keywords();
+        let text = "This Agreement is governed by the laws of Ohio.";
+        let ids = |clauses: Vec<Clause>| -> Vec<String> {
+            clauses.into_iter().map(|c| c.clause_type).collect()
+        };
+        assert_eq!(
+            ids(SectionStructure.extract(keywords, text, 0)),
+            ids(KeywordRules.extract(keywords, text, 0))
+        );
+        assert!(by_name("structure").is_some() && by_name("llm").is_none());
+    }
+}
@@ -0,0 +1,348 @@
+//! Mandatory clause checklist with fallback language. The contract type is
+//! read from the title line; each type has clause types it must contain,
+//! and each one that is missing becomes an issue with a clause from the
+//! clause bank, numbered in the document's own style and placed after its
+//! last section so it can be pasted in as is.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    extraction::line_around,
+    style::{format_label, section_label, Numbering},
+    taxonomy,
+    warmup::KeywordMatcher,
+    Issue,
+};
+
+struct Checklist {
+    /// Any of these in the upper-cased title selects the checklist.
+    title_words: &'static [&'static str],
+    contract_type: &'static str,
+    /// Keyword groups the contract must mention.
+    mandatory: &'static [&'static str],
+}
+
+/// Earlier entries win, so the catch-all comes last.
+const CHECKLISTS: [Checklist; 5] = [
+    Checklist {
+        title_words: &["NON-DISCLOSURE", "CONFIDENTIALITY AGREEMENT"],
+        contract_type: "non-disclosure agreement",
+        mandatory: &["confidentiality", "termination", "jurisdiction"],
+    },
+    Checklist {
+        title_words: &["DATA PROCESSING"],
+        contract_type: "data processing agreement",
+        mandatory: &["confidentiality", "retention", "liability", "jurisdiction"],
+    },
+    Checklist {
+        title_words: &["SERVICES"],
+        contract_type: "services agreement",
+        mandatory: &[
+            "confidentiality",
+            "liability",
+            "indemnification",
+            "termination",
+            "jurisdiction",
+            "payment",
+        ],
+    },
+    Checklist {
+        title_words: &["LICENSE", "LICENCE"],
+        contract_type: "license agreement",
+        mandatory: &[
+            "intellectual_property",
+            "warranty",
+            "liability",
+            "termination",
+            "jurisdiction",
+        ],
+    },
+    Checklist {
+        title_words: &["AGREEMENT", "CONTRACT"],
+        contract_type: "agreement",
+        mandatory: &["liability", "termination", "jurisdiction"],
+    },
+];
+
+/// Fallback language by keyword group: heading and body. Bracketed text is
+/// for the drafter to fill in.
+const CLAUSE_BANK: [(&str, &str, &str); 9] = [
+    (
+        "confidentiality",
+        "Confidentiality",
+        "Each party shall keep the other party's confidential information \
+         confidential, use it only to perform this Agreement and disclose it only \
+         to those who need to know it and are bound by equivalent obligations. \
+         These obligations survive termination for five years.",
+    ),
+    (
+        "liability",
+        "Limitation of Liability",
+        "Neither party shall be liable for indirect, incidental or consequential \
+         damages. Each party's total liability under this Agreement shall not \
+         exceed the fees paid or payable in the twelve months before the claim arose.",
+    ),
+    (
+        "indemnification",
+        "Indemnification",
+        "Each party shall indemnify and hold harmless the other party against \
+         third-party claims arising from its breach of this Agreement or its \
+         negligence or wilful misconduct.",
+    ),
+    (
+        "termination",
+        "Termination",
+        "Either party may terminate this Agreement on thirty days' written notice, \
+         or immediately by notice if the other party materially breaches it and \
+         fails to cure the breach within thirty days.",
+    ),
+    (
+        "jurisdiction",
+        "Governing Law",
+        "This Agreement is governed by the laws of [jurisdiction], and the courts \
+         of [jurisdiction] have exclusive jurisdiction over any dispute arising \
+         from it.",
+    ),
+    (
+        "payment",
+        "Payment Terms",
+        "Invoices are payable within thirty days of receipt. Undisputed amounts \
+         not paid when due bear interest at the lower of 1% per month and the \
+         maximum rate permitted by law.",
+    ),
+    (
+        "retention",
+        "Data Retention",
+        "Personal data is retained only as long as needed to perform this \
+         Agreement and is deleted or returned within ninety days after termination.",
+    ),
+    (
+        "intellectual_property",
+        "Intellectual Property",
+        "Each party keeps the intellectual property it owned before this \
+         Agreement. No licence is granted except as this Agreement expressly \
+         provides.",
+    ),
+    (
+        "warranty",
+        "Warranty",
+        "Each party warrants that it has full power and authority to enter into \
+         this Agreement and that performing it will not breach any other \
+         agreement binding on it.",
+    ),
+];
+
+/// Ready-to-insert language for a missing mandatory clause.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct FallbackClause {
+    /// The issue reporting the clause missing.
+    pub issue_id: String,
+    pub clause_type: String,
+    /// The clause, numbered like the document's sections when it has any.
+    pub text: String,
+    /// Byte offset in the submitted document to insert `text` at.
+    pub insert_at: usize,
+    /// Label of the section it goes after, e.g. "5." or "Section 12.".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after_section: Option<String>,
+}
+
+/// The numbered outline: the style of the first heading, the last
+/// top-level section's label and number, and where that section ends.
+struct Outline {
+    numbering: Numbering,
+    last: Option<(String, u32)>,
+    end: usize,
+}
+
+fn outline(document: &str) -> Outline {
+    let mut numbering = None;
+    let mut last = None;
+    let mut offset = 0;
+    for line in document.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if let Some((len, number)) = section_label(trimmed) {
+            let lower = trimmed.to_ascii_lowercase();
+            numbering.get_or_insert(if lower.starts_with("section ") {
+                Numbering::Section
+            } else if lower.starts_with("article ") {
+                Numbering::Article
+            } else {
+                Numbering::Decimal
+            });
+            if let Ok(n) = number.parse::<u32>() {
+                last = Some((trimmed[..len].to_string(), n, offset));
+            }
+        }
+        offset += line.len();
+    }
+    // The last section runs to the signature block, if there is one after it.
+    let from = last.as_ref().map_or(0, |(_, _, at)| *at);
+    let end = document[from..]
+        .to_ascii_lowercase()
+        .find("in witness whereof")
+        .map_or(document.len(), |i| line_around(document, from + i).start);
+    Outline {
+        numbering: numbering.unwrap_or(Numbering::Decimal),
+        last: last.map(|(label, n, _)| (label, n)),
+        end,
+    }
+}
+
+fn checklist(document: &str) -> Option<&'static Checklist> {
+    let title = document
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())?
+        .to_uppercase();
+    CHECKLISTS
+        .iter()
+        .find(|c| c.title_words.iter().any(|w| title.contains(w)))
+}
+
+/// The checklist issues for `document`, numbered from `next_id`, with the
+/// fallback language for each.
+pub fn missing(
+    keywords: &KeywordMatcher,
+    document: &str,
+    next_id: usize,
+) -> (Vec<Issue>, Vec<FallbackClause>) {
+    let Some(checklist) = checklist(document) else {
+        return (Vec::new(), Vec::new());
+    };
+    let hits = keywords.hits(document);
+    let outline = outline(document);
+    // Inserted text starts on a line of its own.
+    let newline = if outline.end > 0 && !document[..outline.end].ends_with('\n') {
+        "\n"
+    } else {
+        ""
+    };
+    let mut issues = Vec::new();
+    let mut fallbacks = Vec::new();
+    let mut number = outline.last.as_ref().map(|(_, n)| *n);
+    for group in checklist
+        .mandatory
+        .iter()
+        .filter(|g| !hits.contains_key(*g))
+    {
+        let (Some(kind), Some((_, heading, body))) = (
+            taxonomy::by_group(group),
+            CLAUSE_BANK.iter().find(|(g, _, _)| g == group),
+        ) else {
+            continue;
+        };
+        let id = format!("issue-{:03}", next_id + issues.len());
+        issues.push(Issue {
+            id: id.clone(),
+            description: format!(
+                "Missing mandatory {} clause for a {}.",
+                kind.name, checklist.contract_type
+            ),
+            severity: if kind.risk == "high" {
+                "high"
+            } else {
+                "medium"
+            }
+            .to_string(),
+            location: "Document".to_string(),
+            confidence: 0.8,
+            review_status: "auto".to_string(),
+            provenance: None,
+            excerpt: None,
+            position: None,
+        });
+        let label = number.as_mut().map(|n| {
+            *n += 1;
+            format!("{} ", format_label(&n.to_string(), outline.numbering))
+        });
+        fallbacks.push(FallbackClause {
+            issue_id: id,
+            clause_type: kind.name.to_string(),
+            text: format!("{newline}{}{heading}. {body}\n", label.unwrap_or_default()),
+            insert_at: outline.end,
+            after_section: outline.last.as_ref().map(|(label, _)| label.clone()),
+        });
+    }
+    (issues, fallbacks)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warmup::Precompiled;
+
+    #[test]
+    fn missing_clauses_are_numbered_after_the_last_section() {
+        let pre = Precompiled::default();
+        let doc = "MASTER SERVICES AGREEMENT\n\n\
+                   Section 1. Governing Law. Governed by the laws of Ohio.\n\
+                   Section 2. Liability. Neither party is liable for lost profits.\n\
+                   Section 2.1 Indemnification. Supplier shall indemnify Customer.\n\
+                   Section 3. Termination. Either party may terminate on notice.\n\
+                   Section 4. Fees. Invoices are payable monthly.\n\n\
+                   IN WITNESS WHEREOF the parties have signed.\n";
+        let (issues, fallbacks) = missing(pre.keywords(), doc, 4);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].id, "issue-004");
+        assert_eq!(
+            issues[0].description,
+            "Missing mandatory Confidentiality clause for a services agreement."
+        );
+        let fallback = &fallbacks[0];
+        assert_eq!(fallback.issue_id, "issue-004");
+        assert!(fallback
+            .text
+            .starts_with("Section 5. Confidentiality. Each party"));
+        assert_eq!(fallback.after_section.as_deref(), Some("Section 4."));
+        assert_eq!(
+            &doc[fallback.insert_at..],
+            "IN WITNESS WHEREOF the parties have signed.\n"
+        );
+    }
+
+    #[test]
+    fn unnumbered_documents_get_unnumbered_clauses_at_the_end() {
+        let pre = Precompiled::default();
+        let doc = "NON-DISCLOSURE AGREEMENT\nThe recipient keeps all information confidential.";
+        let (issues, fallbacks) = missing(pre.keywords(), doc, 1);
+        let types: Vec<&str> = fallbacks.iter().map(|f| f.clause_type.as_str()).collect();
+        assert_eq!(types, ["Termination", "Jurisdiction"]);
+        assert_eq!(issues[1].id, "issue-002");
+        assert_eq!(fallbacks[0].insert_at, doc.len());
+        assert!(fallbacks[0].text.starts_with("\nTermination. Either party"));
+        assert_eq!(fallbacks[1].after_section, None);
+
+        // Inserting the fallbacks satisfies the checklist.
+        let mut fixed = doc.to_string();
+        for f in fallbacks.iter().rev() {
+            fixed.insert_str(f.insert_at, &f.text);
+        }
+        assert!(missing(pre.keywords(), &fixed, 1).0.is_empty());
+    }
+
+    #[test]
+    fn documents_without_a_contract_title_have_no_checklist() {
+        let pre = Precompiled::default();
+        assert!(missing(pre.keywords(), "Dear Sir, thank you.", 1)
+            .0
+            .is_empty());
+        assert!(missing(pre.keywords(), "", 1).1.is_empty());
+    }
+
+    #[test]
+    fn every_checklist_clause_is_in_the_bank_and_detected_by_its_keywords() {
+        let pre = Precompiled::default();
+        for checklist in CHECKLISTS {
+            for group in checklist.mandatory {
+                let (_, heading, body) = CLAUSE_BANK.iter().find(|(g, _, _)| g == group).unwrap();
+                assert!(taxonomy::by_group(group).is_some(), "{group}");
+                let hits = pre.keywords().hits(&format!("{heading}. {body}"));
+                assert!(hits.contains_key(group), "{group}");
+            }
+        }
+    }
+}
@@ -0,0 +1,374 @@
+//! Roll-up risk for a contract family (MSA, SOWs, amendments). Each risk
+//! factor takes the worst effective score across the family's agreements,
+//! where an amendment that rewrites or deletes a clause of its parent
+//! replaces the parent's score for that factor. Every component names the
+//! document it comes from.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use serde::Serialize;
+use tracing::info;
+
+use crate::{
+    auth, corpus::StoredDocument, risk_model::RiskModel, subcontracting::sentences, AppState,
+};
+
+/// Wording that changes an earlier clause rather than adding to it.
+const OVERRIDE_MARKERS: [&str; 7] = [
+    "hereby amended",
+    "amended to read",
+    "amended and restated",
+    "replaced",
+    "superseded",
+    "deleted",
+    "shall no longer apply",
+];
+const DELETION_MARKERS: [&str; 3] = ["deleted", "struck", "shall no longer apply"];
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Master,
+    StatementOfWork,
+    Amendment,
+    Agreement,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Override {
+    pub amendment_id: String,
+    pub target_id: String,
+    pub factor: String,
+    pub from_score: f64,
+    pub to_score: f64,
+    /// The amendment deletes the clause rather than rewriting it.
+    pub deleted: bool,
+    pub sentence: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FamilyMember {
+    pub document_id: String,
+    pub role: Role,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// The document's own score, before any amendment.
+    pub standalone_score: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FamilyComponent {
+    pub factor: String,
+    pub weight: f64,
+    pub score: f64,
+    pub contribution: f64,
+    /// Document whose clause sets this score: an amendment when its
+    /// override is the worst remaining exposure.
+    pub source_document: String,
+    pub explanation: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FamilyRiskReport {
+    pub family_id: String,
+    pub overall_score: f64,
+    pub risk_level: String,
+    pub components: Vec<FamilyComponent>,
+    pub members: Vec<FamilyMember>,
+    pub overrides: Vec<Override>,
+}
+
+// ── Roll-up ───────────────────────────────────────────────────────────────────
+
+pub fn role(doc: &StoredDocument) -> Role {
+    let head: String = doc
+        .text
+        .chars()
+        .take(400)
+        .collect::<String>()
+        .to_lowercase();
+    if doc.parent_id.is_some() || head.contains("amendment") || head.contains("addendum") {
+        Role::Amendment
+    } else if head.contains("statement of work") || head.contains("sow ") {
+        Role::StatementOfWork
+    } else if head.contains("master") {
+        Role::Master
+    } else {
+        Role::Agreement
+    }
+}
+
+fn weighted(factors: &[crate::RiskFactor]) -> f64 {
+    factors.iter().map(|f| f.weight * f.score).sum()
+}
+
+/// The amendment's override sentences per factor, in model order; length
+/// factors have no topics and get none.
+fn override_sentences(model: &RiskModel, text: &str) -> Vec<Vec<String>> {
+    let mut out = vec![Vec::new(); model.factors.len()];
+    for (_, sentence) in sentences(text) {
+        let lower = sentence.to_lowercase();
+        if !OVERRIDE_MARKERS.iter().any(|m| lower.contains(m)) {
+            continue;
+        }
+        for (i, factor) in model.factors.iter().enumerate() {
+            if factor.topics().iter().any(|t| lower.contains(t.as_str())) {
+                out[i].push(sentence.trim().to_string());
+            }
+        }
+    }
+    out
+}
+
+pub fn roll_up(
+    model: &RiskModel,
+    family_id: &str,
+    mut docs: Vec<StoredDocument>,
+) -> FamilyRiskReport {
+    docs.sort_by(|a, b| a.stored_at.cmp(&b.stored_at).then_with(|| a.id.cmp(&b.id)));
+    let roles: Vec<Role> = docs.iter().map(role).collect();
+    let standalone: Vec<Vec<crate::RiskFactor>> =
+        docs.iter().map(|d| model.factors(&d.text)).collect();
+    let baseline = model.factors("");
+
+    // Effective (score, source index) per agreement and factor.
+    let mut effective: Vec<Option<Vec<(f64, usize)>>> = standalone
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            (roles[i] != Role::Amendment).then(|| f.iter().map(|f| (f.score, i)).collect())
+        })
+        .collect();
+    let master = (0..docs.len())
+        .find(|&i| roles[i] == Role::Master)
+        .or_else(|| (0..docs.len()).find(|&i| roles[i] != Role::Amendment));
+
+    let mut overrides = Vec::new();
+    for (a, doc) in docs.iter().enumerate() {
+        if roles[a] != Role::Amendment {
+            continue;
+        }
+        let target = doc
+            .parent_id
+            .as_ref()
+            .and_then(|p| docs.iter().position(|d| &d.id == p))
+            .filter(|&t| roles[t] != Role::Amendment)
+            .or(master);
+        let Some(target) = target else { continue };
+        for (f, found) in override_sentences(model, &doc.text).into_iter().enumerate() {
+            if found.is_empty() {
+                continue;
+            }
+            let lower = found.join(" ").to_lowercase();
+            let deleted =
+                DELETION_MARKERS.iter().any(|m| lower.contains(m)) && !lower.contains("replaced");
+            let to_score = if deleted {
+                baseline[f].score
+            } else {
+                model.factors(&found.join(" "))[f].score
+            };
+            let Some(scores) = effective[target].as_mut() else {
+                continue;
+            };
+            overrides.push(Override {
+                amendment_id: doc.id.clone(),
+                target_id: docs[target].id.clone(),
+                factor: baseline[f].factor.clone(),
+                from_score: scores[f].0,
+                to_score,
+                deleted,
+                sentence: found[0].clone(),
+            });
+            scores[f] = (to_score, a);
+        }
+    }
+
+    let total_words: usize = docs.iter().map(|d| d.text.split_whitespace().count()).sum();
+    let components: Vec<FamilyComponent> = baseline
+        .iter()
+        .enumerate()
+        .map(|(f, factor)| {
+            let definition = &model.factors[f];
+            // Length factors score the family's combined length.
+            let (score, source, explanation) = if definition.is_length() {
+                (
+                    definition.score("", total_words),
+                    docs.iter()
+                        .max_by_key(|d| d.text.len())
+                        .map_or(String::new(), |d| d.id.clone()),
+                    format!("{total_words} words across {} documents.", docs.len()),
+                )
+            } else {
+                let worst = effective
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, e)| e.as_ref().map(|s| (i, s[f])))
+                    .max_by(|a, b| a.1 .0.total_cmp(&b.1 .0));
+                match worst {
+                    Some((owner, (score, source))) if source != owner => (
+                        score,
+                        docs[source].id.clone(),
+                        format!(
+                            "Worst among the family's agreements: {} as amended by {}.",
+                            docs[owner].id, docs[source].id
+                        ),
+                    ),
+                    Some((owner, (score, _))) => (
+                        score,
+                        docs[owner].id.clone(),
+                        format!(
+                            "Worst among the family's agreements, in {}.",
+                            docs[owner].id
+                        ),
+                    ),
+                    None => (
+                        factor.score,
+                        String::new(),
+                        "No agreement in the family.".to_string(),
+                    ),
+                }
+            };
+            FamilyComponent {
+                factor: factor.factor.clone(),
+                weight: factor.weight,
+                score,
+                contribution: factor.weight * score,
+                source_document: source,
+                explanation,
+            }
+        })
+        .collect();
+
+    let overall_score: f64 = components.iter().map(|c| c.contribution).sum();
+    FamilyRiskReport {
+        family_id: family_id.to_string(),
+        overall_score,
+        risk_level: model.level(overall_score),
+        components,
+        members: docs
+            .iter()
+            .zip(&roles)
+            .zip(&standalone)
+            .map(|((d, role), factors)| FamilyMember {
+                document_id: d.id.clone(),
+                role: *role,
+                parent_id: d.parent_id.clone(),
+                standalone_score: weighted(factors),
+            })
+            .collect(),
+        overrides,
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn family_risk(
+    State(state): State<AppState>,
+    Path(family_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<FamilyRiskReport>, StatusCode> {
+    let docs: Vec<StoredDocument> = auth::tenant_documents(&headers, &state)
+        .into_iter()
+        .filter(|d| d.family_id.as_deref() == Some(family_id.as_str()))
+        .collect();
+    if docs.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let report = roll_up(&state.risk_model, &family_id, docs);
+    info!(
+        family_id = %family_id,
+        members = report.members.len(),
+        overrides = report.overrides.len(),
+        overall_score = report.overall_score,
+        "family risk rolled up"
+    );
+    Ok(Json(report))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn doc(id: &str, text: &str, parent: Option<&str>, minutes: i64) -> StoredDocument {
+        StoredDocument {
+            id: id.to_string(),
+            text: text.to_string(),
+            language: "en".to_string(),
+            tenant: "default".to_string(),
+            stored_at: Utc::now() + Duration::minutes(minutes),
+            family_id: Some("fam-1".to_string()),
+            parent_id: parent.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn amendment_deleting_a_clause_replaces_the_parent_score() {
+        let msa = doc(
+            "msa",
+            "MASTER SERVICES AGREEMENT. Supplier shall indemnify Customer against all claims.",
+            None,
+            0,
+        );
+        let sow = doc(
+            "sow",
+            "STATEMENT OF WORK 1. Supplier will build the portal.",
+            None,
+            1,
+        );
+        let amendment = doc(
+            "amd",
+            "AMENDMENT NO. 1. Section 9 (Indemnification) of the Agreement is hereby deleted.",
+            Some("msa"),
+            2,
+        );
+        let report = roll_up(&RiskModel::default(), "fam-1", vec![amendment, sow, msa]);
+        assert_eq!(report.members[0].role, Role::Master);
+        assert_eq!(report.members[1].role, Role::StatementOfWork);
+        assert_eq!(report.members[2].role, Role::Amendment);
+
+        assert_eq!(report.overrides.len(), 1);
+        let o = &report.overrides[0];
+        assert_eq!(
+            (o.amendment_id.as_str(), o.target_id.as_str()),
+            ("amd", "msa")
+        );
+        assert!(o.deleted);
+        assert!(o.to_score < o.from_score);
+
+        let indemnity = &report.components[1];
+        assert_eq!(indemnity.factor, "Indemnification");
+        assert!((indemnity.score - 0.2).abs() < 1e-9);
+        let total: f64 = report.components.iter().map(|c| c.contribution).sum();
+        assert!((report.overall_score - total).abs() < 1e-9);
+    }
+
+    #[test]
+    fn worst_agreement_supplies_each_component() {
+        let msa = doc("msa", "MASTER AGREEMENT. Fees are due monthly.", None, 0);
+        let sow = doc(
+            "sow",
+            "STATEMENT OF WORK. All intellectual property vests in Customer.",
+            None,
+            1,
+        );
+        let amendment = doc(
+            "amd",
+            "AMENDMENT. Clause 4 is hereby amended to read: all intellectual property and copyright vest in Customer.",
+            None,
+            2,
+        );
+        let report = roll_up(&RiskModel::default(), "fam-1", vec![msa, sow, amendment]);
+        let ip = &report.components[3];
+        assert_eq!(ip.source_document, "sow");
+        // Without a parent the amendment targets the master agreement.
+        assert_eq!(report.overrides[0].target_id, "msa");
+    }
+}
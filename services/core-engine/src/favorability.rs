@@ -0,0 +1,598 @@
+//! Which party each clause favors, and how one-sided the paper is overall.
+//! Every sentence of a clause is read for a directional cue at its verb: a
+//! right ("may", "is entitled to") or a protection ("shall not be liable")
+//! favors the sentence's subject, an obligation ("shall", "must") or a
+//! waiver burdens it. Sentences whose subject is both parties ("Either
+//! party may terminate") pull the clause towards neutral.
+//!
+//! "Us" is the party named in the request, or the tenant's registered
+//! entity. Without either, clauses still lean towards a named party, but
+//! which side that is stays unresolved.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{entities::Party, extraction::line_around, subcontracting::sentences, Clause};
+
+/// Below this lean a clause is neutral.
+const NEUTRAL_BELOW: f64 = 0.2;
+/// Below this balance the document is balanced; from the second, one-sided.
+const BALANCED_BELOW: f64 = 0.15;
+const ONE_SIDED_FROM: f64 = 0.5;
+/// Subjects longer than this are not taken for a party when none was found.
+const MAX_SUBJECT_WORDS: usize = 3;
+
+const MUTUAL: [&str; 6] = [
+    "each party",
+    "either party",
+    "neither party",
+    "both parties",
+    "the parties",
+    "each of the parties",
+];
+
+/// Cues as they appear after the subject, with what they do for it and
+/// their weight. The earliest cue in a sentence wins, the longest of those
+/// starting at the same place, so "shall not be liable" beats "shall".
+const CUES: [(&str, Effect, f64); 24] = [
+    (" shall not be liable", Effect::Protection, 2.0),
+    (" will not be liable", Effect::Protection, 2.0),
+    (" is not liable", Effect::Protection, 2.0),
+    (" shall have no liability", Effect::Protection, 2.0),
+    (" shall not be responsible", Effect::Protection, 2.0),
+    (" may ", Effect::Right, 1.0),
+    (" is entitled to ", Effect::Right, 1.0),
+    (" shall be entitled to ", Effect::Right, 1.0),
+    (" will be entitled to ", Effect::Right, 1.0),
+    (" has the right to ", Effect::Right, 1.0),
+    (" shall have the right to ", Effect::Right, 1.0),
+    (" reserves the right to ", Effect::Right, 1.0),
+    (" may not ", Effect::Burden, 1.0),
+    (" shall indemnify", Effect::Burden, 2.0),
+    (" shall defend", Effect::Burden, 2.0),
+    (" shall hold harmless", Effect::Burden, 2.0),
+    (" agrees to indemnify", Effect::Burden, 2.0),
+    (" waives ", Effect::Burden, 2.0),
+    (" hereby waives ", Effect::Burden, 2.0),
+    (" shall ", Effect::Burden, 1.0),
+    (" must ", Effect::Burden, 1.0),
+    (" will ", Effect::Burden, 1.0),
+    (" agrees to ", Effect::Burden, 1.0),
+    (" undertakes to ", Effect::Burden, 1.0),
+];
+
+/// Added to a right exercised at the holder's discretion.
+const DISCRETION_BONUS: f64 = 1.0;
+const DISCRETION: [&str; 2] = ["sole discretion", "absolute discretion"];
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Effect {
+    Right,
+    Protection,
+    Burden,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Favor {
+    Us,
+    Counterparty,
+    Neutral,
+    /// Leans towards a party, but which one is us is not known.
+    Unresolved,
+}
+
+/// Where "us" came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Perspective {
+    /// The request's `our_party`.
+    Request,
+    /// The tenant's registered entity name.
+    Entity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Assessment {
+    Balanced,
+    FavorsUs,
+    FavorsCounterparty,
+    OneSidedUs,
+    OneSidedCounterparty,
+    /// Not balanced, but which side is us is not known.
+    Unbalanced,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct FavorCue {
+    pub party: String,
+    pub effect: Effect,
+    /// The cue as written, e.g. "shall indemnify".
+    pub phrase: String,
+    pub sentence: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ClauseFavor {
+    pub clause_id: String,
+    pub favors: Favor,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favored_party: Option<String>,
+    /// How far the clause leans, 0 (even) to 1 (every cue one way).
+    pub strength: f64,
+    pub cues: Vec<FavorCue>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct FavorCounts {
+    pub us: usize,
+    pub counterparty: usize,
+    pub neutral: usize,
+    pub unresolved: usize,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Favorability {
+    /// The parties clauses were read against, by name.
+    pub parties: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub our_party: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub perspective: Option<Perspective>,
+    /// The party a positive balance favors: us when known, otherwise the
+    /// first party.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_party: Option<String>,
+    /// Mean lean of the clauses with cues, -1 to 1 towards `reference_party`.
+    pub balance: f64,
+    pub assessment: Assessment,
+    pub counts: FavorCounts,
+    pub clauses: Vec<ClauseFavor>,
+}
+
+// ── Parties ───────────────────────────────────────────────────────────────────
+
+/// A party and the lowercase names a sentence may call it by.
+#[derive(Debug, Clone)]
+struct Side {
+    name: String,
+    aliases: Vec<String>,
+}
+
+impl Side {
+    fn named(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            aliases: vec![name.to_lowercase()],
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        let name = name.trim().to_lowercase();
+        !name.is_empty()
+            && self
+                .aliases
+                .iter()
+                .any(|a| *a == name || a.contains(&name) || name.contains(a.as_str()))
+    }
+}
+
+/// The rightmost whole-word occurrence of `needle` in `haystack`.
+fn last_word_match(haystack: &str, needle: &str) -> Option<usize> {
+    let boundary = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric());
+    haystack.rmatch_indices(needle).map(|(i, _)| i).find(|&i| {
+        boundary(haystack[..i].chars().next_back())
+            && boundary(haystack[i + needle.len()..].chars().next())
+    })
+}
+
+fn strip_article(subject: &str) -> &str {
+    let trimmed = subject.trim();
+    ["The ", "the "]
+        .iter()
+        .find_map(|a| trimmed.strip_prefix(a))
+        .unwrap_or(trimmed)
+}
+
+/// A capitalized subject of a few words, e.g. "the Vendor", taken for a
+/// party when the preamble named none.
+fn subject_party(subject: &str) -> Option<String> {
+    let subject = strip_article(subject);
+    let words: Vec<&str> = subject.split_whitespace().collect();
+    let lower = subject.to_lowercase();
+    ((1..=MAX_SUBJECT_WORDS).contains(&words.len())
+        && words
+            .iter()
+            .all(|w| w.starts_with(|c: char| c.is_uppercase()))
+        && !MUTUAL
+            .iter()
+            .any(|m| lower.ends_with(m.trim_start_matches("the "))))
+    .then(|| subject.to_string())
+}
+
+// ── Scoring ───────────────────────────────────────────────────────────────────
+
+/// Who a sentence's subject names.
+enum Subject {
+    Party(usize),
+    Mutual,
+}
+
+struct Reading<'a> {
+    subject: &'a str,
+    effect: Effect,
+    weight: f64,
+    phrase: &'a str,
+}
+
+/// The cue a sentence turns on, and what comes before it.
+fn read(sentence: &str) -> Option<Reading<'_>> {
+    let padded = format!(" {}", sentence.to_ascii_lowercase());
+    let (at, cue, effect, weight) = CUES
+        .iter()
+        .filter_map(|&(cue, effect, weight)| padded.find(cue).map(|i| (i, cue, effect, weight)))
+        .min_by(|a, b| a.0.cmp(&b.0).then(b.1.len().cmp(&a.1.len())))?;
+    // `padded` leads with a space, so `at` is the space before the cue.
+    let subject = &sentence[..at.saturating_sub(1)];
+    let phrase = sentence[at..(at + cue.len() - 1).min(sentence.len())].trim();
+    let discretion = effect == Effect::Right && DISCRETION.iter().any(|d| padded.contains(d));
+    Some(Reading {
+        subject,
+        effect,
+        weight: weight + if discretion { DISCRETION_BONUS } else { 0.0 },
+        phrase,
+    })
+}
+
+/// The party or parties a subject names; the one named last when several
+/// are, since it sits next to the verb ("If Customer fails to pay, Vendor may").
+fn subject_of(sides: &[Side], subject: &str) -> Option<Subject> {
+    let lower = subject.to_lowercase();
+    let lower = lower.as_str();
+    let mutual = MUTUAL
+        .iter()
+        .filter_map(|m| last_word_match(lower, m))
+        .max()
+        .map(|at| (at, Subject::Mutual));
+    let party = sides
+        .iter()
+        .enumerate()
+        .flat_map(|(i, side)| {
+            side.aliases
+                .iter()
+                .filter_map(move |a| last_word_match(lower, a).map(|at| (at, Subject::Party(i))))
+        })
+        .max_by_key(|(at, _)| *at);
+    match (mutual, party) {
+        (Some(m), Some(p)) => Some(if m.0 >= p.0 { m.1 } else { p.1 }),
+        (m, p) => m.or(p).map(|(_, s)| s),
+    }
+}
+
+/// The text a clause is read from: the line of its evidence, or its quote.
+pub fn clause_text<'a>(document: &'a str, clause: &'a Clause) -> &'a str {
+    clause
+        .position
+        .as_ref()
+        .map(|p| p.start)
+        .filter(|&start| start <= document.len() && document.is_char_boundary(start))
+        .map_or(clause.text.as_str(), |start| {
+            &document[line_around(document, start)]
+        })
+}
+
+fn score_clause(
+    sides: &[Side],
+    reference: usize,
+    resolved: bool,
+    clause: &Clause,
+    text: &str,
+) -> (ClauseFavor, Option<f64>) {
+    let mut lean = 0.0;
+    let mut total = 0.0;
+    let mut cues = Vec::new();
+    for (_, sentence) in sentences(text) {
+        let sentence = sentence.trim();
+        let Some(reading) = read(sentence) else {
+            continue;
+        };
+        let Some(subject) = subject_of(sides, reading.subject) else {
+            continue;
+        };
+        total += reading.weight;
+        let Subject::Party(party) = subject else {
+            continue;
+        };
+        let helps = reading.effect != Effect::Burden;
+        lean += if helps == (party == reference) {
+            reading.weight
+        } else {
+            -reading.weight
+        };
+        cues.push(FavorCue {
+            party: sides[party].name.clone(),
+            effect: reading.effect,
+            phrase: reading.phrase.to_string(),
+            sentence: sentence.to_string(),
+        });
+    }
+    let lean = if total > 0.0 { lean / total } else { 0.0 };
+    let (favors, favored_party) = if lean.abs() < NEUTRAL_BELOW {
+        (Favor::Neutral, None)
+    } else {
+        let other = (sides.len() == 2).then(|| sides[1 - reference].name.clone());
+        let favored = if lean > 0.0 {
+            Some(sides[reference].name.clone())
+        } else {
+            other
+        };
+        let favors = match (resolved, lean > 0.0) {
+            (false, _) => Favor::Unresolved,
+            (true, true) => Favor::Us,
+            (true, false) => Favor::Counterparty,
+        };
+        (favors, favored)
+    };
+    (
+        ClauseFavor {
+            clause_id: clause.id.clone(),
+            favors,
+            favored_party,
+            strength: (lean.abs() * 100.0).round() / 100.0,
+            cues,
+        },
+        (total > 0.0).then_some(lean),
+    )
+}
+
+/// Reads every clause for the party it favors and rolls them up.
+/// `our_party` and `entity_name` are tried in that order to find us among
+/// the parties; the preamble's parties are used when there are two or
+/// more, otherwise the capitalized subjects of the clauses.
+pub fn assess(
+    document: &str,
+    clauses: &[Clause],
+    parties: &[Party],
+    our_party: Option<&str>,
+    entity_name: Option<&str>,
+) -> Favorability {
+    let mut sides: Vec<Side> = parties
+        .iter()
+        .map(|p| {
+            let mut side = Side::named(&p.name);
+            side.aliases
+                .extend(p.defined_as.iter().map(|d| d.to_lowercase()));
+            side
+        })
+        .collect();
+    if sides.len() < 2 {
+        for clause in clauses {
+            for (_, sentence) in sentences(clause_text(document, clause)) {
+                let Some(name) = read(sentence.trim()).and_then(|r| subject_party(r.subject))
+                else {
+                    continue;
+                };
+                if !sides.iter().any(|s| s.matches(&name)) {
+                    sides.push(Side::named(&name));
+                }
+            }
+        }
+    }
+
+    let found = [
+        (our_party, Perspective::Request),
+        (entity_name, Perspective::Entity),
+    ]
+    .into_iter()
+    .find_map(|(name, from)| {
+        let name = name?;
+        sides
+            .iter()
+            .position(|s| s.matches(name))
+            .map(|i| (i, from))
+    });
+    let reference = found.map_or(0, |(i, _)| i);
+
+    let mut counts = FavorCounts::default();
+    let mut leans = Vec::new();
+    let scored: Vec<ClauseFavor> = clauses
+        .iter()
+        .map(|clause| {
+            let (favor, lean) = if sides.is_empty() {
+                (
+                    ClauseFavor {
+                        clause_id: clause.id.clone(),
+                        favors: Favor::Neutral,
+                        favored_party: None,
+                        strength: 0.0,
+                        cues: Vec::new(),
+                    },
+                    None,
+                )
+            } else {
+                let text = clause_text(document, clause);
+                score_clause(&sides, reference, found.is_some(), clause, text)
+            };
+            leans.extend(lean);
+            match favor.favors {
+                Favor::Us => counts.us += 1,
+                Favor::Counterparty => counts.counterparty += 1,
+                Favor::Neutral => counts.neutral += 1,
+                Favor::Unresolved => counts.unresolved += 1,
+            }
+            favor
+        })
+        .collect();
+
+    let balance = if leans.is_empty() {
+        0.0
+    } else {
+        leans.iter().sum::<f64>() / leans.len() as f64
+    };
+    let assessment = match (found.is_some(), balance.abs(), balance > 0.0) {
+        (_, b, _) if b < BALANCED_BELOW => Assessment::Balanced,
+        (false, _, _) => Assessment::Unbalanced,
+        (true, b, true) if b < ONE_SIDED_FROM => Assessment::FavorsUs,
+        (true, b, false) if b < ONE_SIDED_FROM => Assessment::FavorsCounterparty,
+        (true, _, true) => Assessment::OneSidedUs,
+        (true, _, false) => Assessment::OneSidedCounterparty,
+    };
+    Favorability {
+        parties: sides.iter().map(|s| s.name.clone()).collect(),
+        our_party: found.map(|(i, _)| sides[i].name.clone()),
+        perspective: found.map(|(_, from)| from),
+        reference_party: sides.get(reference).map(|s| s.name.clone()),
+        balance: (balance * 100.0).round() / 100.0,
+        assessment,
+        counts,
+        clauses: scored,
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entities, evidence};
+
+    const PAPER: &str = "This Agreement is made between Acme Corp (\"Supplier\") and Beta, Inc. (the \"Customer\").\n\
+        1. Indemnification. Customer shall indemnify and hold harmless Supplier against all claims.\n\
+        2. Liability. Supplier shall not be liable for any indirect or consequential damages.\n\
+        3. Termination. Supplier may terminate this Agreement at any time in its sole discretion.\n\
+        4. Confidentiality. Each party shall keep the other party's information confidential.\n";
+
+    /// One clause per numbered line, positioned at its start.
+    fn clauses(document: &str) -> Vec<Clause> {
+        document
+            .lines()
+            .filter(|l| l.starts_with(|c: char| c.is_ascii_digit()))
+            .enumerate()
+            .map(|(i, line)| {
+                let start = document.find(line).unwrap();
+                Clause {
+                    id: format!("clause-{:03}", i + 1),
+                    text: line.to_string(),
+                    clause_type: "General".to_string(),
+                    risk_level: "low".to_string(),
+                    confidence: 0.9,
+                    review_status: "auto".to_string(),
+                    provenance: None,
+                    excerpt: None,
+                    position: Some(evidence::position(document, start, start + line.len())),
+                }
+            })
+            .collect()
+    }
+
+    fn favors(result: &Favorability) -> Vec<Favor> {
+        result.clauses.iter().map(|c| c.favors).collect()
+    }
+
+    #[test]
+    fn clauses_lean_towards_the_party_with_rights_and_away_from_obligations() {
+        let parties = entities::extract(PAPER, "en").parties;
+        let result = assess(PAPER, &clauses(PAPER), &parties, Some("Beta"), None);
+        assert_eq!(result.our_party.as_deref(), Some("Beta, Inc."));
+        assert_eq!(result.perspective, Some(Perspective::Request));
+        assert_eq!(
+            favors(&result),
+            [
+                Favor::Counterparty,
+                Favor::Counterparty,
+                Favor::Counterparty,
+                Favor::Neutral
+            ]
+        );
+        let indemnity = &result.clauses[0];
+        assert_eq!(indemnity.favored_party.as_deref(), Some("Acme Corp"));
+        assert_eq!(indemnity.strength, 1.0);
+        assert_eq!(indemnity.cues[0].party, "Beta, Inc.");
+        assert_eq!(indemnity.cues[0].effect, Effect::Burden);
+        assert_eq!(indemnity.cues[0].phrase, "shall indemnify");
+        let termination = &result.clauses[2].cues[0];
+        assert_eq!(
+            (termination.effect, termination.phrase.as_str()),
+            (Effect::Right, "may")
+        );
+        assert!(result.clauses[3].cues.is_empty());
+
+        assert_eq!(result.balance, -0.75);
+        assert_eq!(result.assessment, Assessment::OneSidedCounterparty);
+        assert_eq!(result.counts.counterparty, 3);
+        assert_eq!(result.counts.neutral, 1);
+    }
+
+    #[test]
+    fn the_tenant_entity_is_us_when_the_request_names_no_one() {
+        let parties = entities::extract(PAPER, "en").parties;
+        let ours = assess(PAPER, &clauses(PAPER), &parties, None, Some("ACME CORP"));
+        assert_eq!(ours.perspective, Some(Perspective::Entity));
+        assert_eq!(ours.our_party.as_deref(), Some("Acme Corp"));
+        assert_eq!(ours.balance, 0.75);
+        assert_eq!(ours.assessment, Assessment::OneSidedUs);
+
+        // A request naming a party that is not in the contract falls back too.
+        let fallback = assess(
+            PAPER,
+            &clauses(PAPER),
+            &parties,
+            Some("Gamma"),
+            Some("Supplier"),
+        );
+        assert_eq!(fallback.our_party.as_deref(), Some("Acme Corp"));
+    }
+
+    #[test]
+    fn without_a_perspective_clauses_lean_towards_named_parties_only() {
+        let parties = entities::extract(PAPER, "en").parties;
+        let result = assess(PAPER, &clauses(PAPER), &parties, None, None);
+        assert_eq!(result.our_party, None);
+        assert_eq!(result.reference_party.as_deref(), Some("Acme Corp"));
+        assert_eq!(favors(&result)[0], Favor::Unresolved);
+        assert_eq!(
+            result.clauses[0].favored_party.as_deref(),
+            Some("Acme Corp")
+        );
+        assert_eq!(result.assessment, Assessment::Unbalanced);
+        assert_eq!(result.counts.unresolved, 3);
+    }
+
+    #[test]
+    fn capitalized_subjects_stand_in_for_a_missing_preamble() {
+        let doc = "1. Payment. The Client shall pay each invoice within 30 days.\n\
+                   2. Warranty. The Vendor shall repair defects; the Vendor must refund \
+                   fees if it cannot.\n\
+                   3. Termination. Either party may terminate on 30 days notice.\n";
+        let result = assess(doc, &clauses(doc), &[], Some("Client"), None);
+        assert_eq!(result.parties, ["Client", "Vendor"]);
+        assert_eq!(
+            favors(&result),
+            [Favor::Counterparty, Favor::Us, Favor::Neutral]
+        );
+        // One clause each way evens out.
+        assert_eq!(result.balance, 0.0);
+        assert_eq!(result.assessment, Assessment::Balanced);
+    }
+
+    #[test]
+    fn the_party_next_to_the_verb_is_the_subject() {
+        let sides = [Side::named("Customer"), Side::named("Vendor")];
+        let reading =
+            read("If the Customer fails to pay, the Vendor may suspend the services.").unwrap();
+        assert!(matches!(
+            subject_of(&sides, reading.subject),
+            Some(Subject::Party(1))
+        ));
+        let mutual =
+            read("The Vendor and the Customer, as the parties, shall meet monthly.").unwrap();
+        assert!(matches!(
+            subject_of(&sides, mutual.subject),
+            Some(Subject::Mutual)
+        ));
+    }
+}
@@ -0,0 +1,438 @@
+//! The gRPC API (`proto/legal.proto`), for consumers that only speak gRPC.
+//! `Analyze`, `AnalyzeStream`, `ScoreRisk` and `Compile` go through the
+//! same functions as their HTTP routes, so results, stored analyses,
+//! history, notifications and webhooks are the same whichever API a caller
+//! used. The service is served as an axum router behind the HTTP API's
+//! authentication, rate limit and deadline middleware; metadata entries
+//! are the headers those read, `x-tenant-id` included.
+//!
+//! HTTP status codes map onto gRPC codes. A contained panic is `INTERNAL`
+//! with the error ID, and a compile blocked by the jurisdiction policy is
+//! `FAILED_PRECONDITION` naming the violations. `AnalyzeStream` ends with
+//! the summary, or with `INTERNAL` in its place.
+
+use std::{collections::HashMap, pin::Pin};
+
+use axum::{http::StatusCode, middleware, Router};
+use futures_util::Stream;
+use serde_json::{Map, Value};
+use tonic::{Request, Response, Status};
+use tracing::warn;
+
+use crate::{
+    analyze_checked, auth,
+    deadline::{self, AnalysisMode, Deadline},
+    ensemble,
+    evidence::{self, EvidenceOptions},
+    inflight::Origin,
+    isolation::{self, Panicked, Site},
+    jurisdiction_policy, notifier, ratelimit, score_request,
+    stream::{self, RiskSummary, StreamEvent},
+    AnalyzeRequest, AppState, CompileExtras, RiskRequest,
+};
+
+pub mod proto {
+    #![allow(clippy::all, clippy::pedantic)]
+    tonic::include_proto!("alice.legal.v1");
+}
+
+use proto::legal_engine_server::{LegalEngine, LegalEngineServer};
+
+/// The gRPC service with the middleware of the public API.
+pub fn router(state: AppState) -> Router {
+    tonic::service::Routes::new(LegalEngineServer::new(GrpcApi {
+        state: state.clone(),
+    }))
+    .into_axum_router()
+    .layer(middleware::from_fn_with_state(
+        state.clone(),
+        deadline::enforce,
+    ))
+    .layer(middleware::from_fn_with_state(
+        state.clone(),
+        ratelimit::limit,
+    ))
+    .layer(middleware::from_fn_with_state(state, auth::authenticate))
+}
+
+pub struct GrpcApi {
+    state: AppState,
+}
+
+// ── Conversions ──────────────────────────────────────────────────────────────
+
+fn status(code: StatusCode) -> Status {
+    let message = code.canonical_reason().unwrap_or("request failed");
+    match code {
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+            Status::invalid_argument(message)
+        }
+        StatusCode::UNAUTHORIZED => Status::unauthenticated(message),
+        StatusCode::FORBIDDEN => Status::permission_denied(message),
+        StatusCode::NOT_FOUND => Status::not_found(message),
+        StatusCode::TOO_MANY_REQUESTS => Status::resource_exhausted(message),
+        StatusCode::SERVICE_UNAVAILABLE => Status::unavailable(message),
+        StatusCode::GATEWAY_TIMEOUT => Status::deadline_exceeded(message),
+        _ => Status::internal(message),
+    }
+}
+
+fn panicked(panicked: Panicked) -> Status {
+    Status::internal(format!("{} ({})", panicked.error, panicked.error_id))
+}
+
+/// The tenant and deadline the middleware left on the request.
+fn context<T>(request: &Request<T>, state: &AppState) -> (String, Deadline) {
+    let deadline = request
+        .extensions()
+        .get::<Deadline>()
+        .copied()
+        .unwrap_or_else(|| Deadline::after(state.timeouts.analyze, state.timeouts.soft_margin));
+    (
+        notifier::tenant(&request.metadata().clone().into_headers()),
+        deadline,
+    )
+}
+
+/// The HTTP request the call amounts to; `None` for an unknown mode.
+fn analyze_request(req: proto::AnalyzeRequest) -> Option<AnalyzeRequest> {
+    let mode = match proto::AnalysisMode::try_from(req.mode).ok()? {
+        proto::AnalysisMode::Quick => AnalysisMode::Quick,
+        proto::AnalysisMode::Unspecified | proto::AnalysisMode::Standard => AnalysisMode::Standard,
+        proto::AnalysisMode::Deep => AnalysisMode::Deep,
+    };
+    Some(AnalyzeRequest {
+        document: req.document,
+        language: req.language,
+        document_name: req.document_name,
+        mode,
+        analysis_options: EvidenceOptions {
+            evidence_context_chars: req.evidence_context_chars.map(|n| n as usize),
+            fallback_clauses: req.fallback_clauses,
+        },
+        latency_budget_ms: req.latency_budget_ms,
+        our_party: req.our_party,
+        ml_context: Default::default(),
+    })
+}
+
+fn position(position: &evidence::Position) -> proto::Position {
+    proto::Position {
+        start: position.start as u64,
+        end: position.end as u64,
+        start_line: position.start_line as u64,
+        end_line: position.end_line as u64,
+        section: position.section.clone(),
+    }
+}
+
+fn clause(clause: &crate::Clause) -> proto::Clause {
+    proto::Clause {
+        id: clause.id.clone(),
+        text: clause.text.clone(),
+        clause_type: clause.clause_type.clone(),
+        risk_level: clause.risk_level.clone(),
+        confidence: clause.confidence,
+        review_status: clause.review_status.clone(),
+        excerpt: clause.excerpt.clone(),
+        position: clause.position.as_ref().map(position),
+    }
+}
+
+fn issue(issue: &crate::Issue) -> proto::Issue {
+    proto::Issue {
+        id: issue.id.clone(),
+        description: issue.description.clone(),
+        severity: issue.severity.clone(),
+        location: issue.location.clone(),
+        confidence: issue.confidence,
+        review_status: issue.review_status.clone(),
+        excerpt: issue.excerpt.clone(),
+        position: issue.position.as_ref().map(position),
+    }
+}
+
+fn summary(summary: RiskSummary) -> proto::AnalysisSummary {
+    proto::AnalysisSummary {
+        analysis_id: summary.analysis_id,
+        risk_score: summary.risk_score,
+        clause_count: summary.clause_count as u64,
+        issue_count: summary.issue_count as u64,
+        escalations: summary.escalations,
+        language: summary.language,
+        word_count: summary.word_count as u64,
+        partial: summary.partial,
+        skipped_stages: summary.skipped_stages,
+    }
+}
+
+fn event(event: StreamEvent) -> Result<proto::AnalyzeEvent, Panicked> {
+    use proto::analyze_event::Event;
+    let event = match event {
+        StreamEvent::Clause(c) => Event::Clause(clause(&c)),
+        StreamEvent::Issue(i) => Event::Issue(issue(&i)),
+        StreamEvent::Summary(s) => Event::Summary(summary(s)),
+        StreamEvent::Error(p) => return Err(p),
+    };
+    Ok(proto::AnalyzeEvent { event: Some(event) })
+}
+
+fn json(value: &impl serde::Serialize) -> String {
+    serde_json::to_string(value).unwrap_or_else(|e| {
+        warn!(error = %e, "gRPC response JSON not serialized");
+        String::new()
+    })
+}
+
+// ── Service ──────────────────────────────────────────────────────────────────
+
+type EventStream = Pin<Box<dyn Stream<Item = Result<proto::AnalyzeEvent, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl LegalEngine for GrpcApi {
+    async fn analyze(
+        &self,
+        request: Request<proto::AnalyzeRequest>,
+    ) -> Result<Response<proto::AnalyzeResponse>, Status> {
+        let (tenant, deadline) = context(&request, &self.state);
+        let req = analyze_request(request.into_inner())
+            .ok_or_else(|| Status::invalid_argument("unknown analysis mode"))?;
+        let ml = ensemble::ml_findings(&self.state, &tenant, &req).await;
+        let state = self.state.clone();
+        let analysis = tokio::task::spawn_blocking(move || {
+            analyze_checked(&state, deadline, &tenant, &req, ml, Origin::Grpc)
+        })
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?
+        .map_err(status)?
+        .map_err(panicked)?;
+        Ok(Response::new(proto::AnalyzeResponse {
+            json: json(&analysis),
+            analysis_id: analysis.analysis_id,
+            risk_score: analysis.risk_score,
+            clauses: analysis.clauses.iter().map(clause).collect(),
+            issues: analysis.issues.iter().map(issue).collect(),
+            escalations: analysis.escalations,
+            language: analysis.language,
+            word_count: analysis.word_count as u64,
+            partial: analysis.partial,
+            skipped_stages: analysis.skipped_stages,
+        }))
+    }
+
+    type AnalyzeStreamStream = EventStream;
+
+    async fn analyze_stream(
+        &self,
+        request: Request<proto::AnalyzeRequest>,
+    ) -> Result<Response<Self::AnalyzeStreamStream>, Status> {
+        let (tenant, deadline) = context(&request, &self.state);
+        let req = analyze_request(request.into_inner())
+            .ok_or_else(|| Status::invalid_argument("unknown analysis mode"))?;
+        let receiver = stream::start(self.state.clone(), deadline, tenant, req, Origin::Grpc)
+            .await
+            .map_err(status)?;
+        let events = futures_util::stream::unfold(receiver, |mut receiver| async move {
+            receiver
+                .recv()
+                .await
+                .map(|e| (event(e).map_err(panicked), receiver))
+        });
+        Ok(Response::new(Box::pin(events)))
+    }
+
+    async fn score_risk(
+        &self,
+        request: Request<proto::RiskScoreRequest>,
+    ) -> Result<Response<proto::RiskScoreResponse>, Status> {
+        let (tenant, _) = context(&request, &self.state);
+        let req = request.into_inner();
+        let response = score_request(
+            &self.state,
+            &tenant,
+            RiskRequest {
+                document: req.document,
+                explain: req.explain,
+                language: req.language,
+            },
+        )
+        .await
+        .map_err(status)?;
+        Ok(Response::new(proto::RiskScoreResponse {
+            analysis_id: response.analysis_id,
+            overall_score: response.overall_score,
+            risk_level: response.risk_level,
+            risk_factors: response
+                .risk_factors
+                .into_iter()
+                .map(|f| proto::RiskFactor {
+                    factor: f.factor,
+                    weight: f.weight,
+                    score: f.score,
+                    description: f.description,
+                })
+                .collect(),
+            waterfall: response
+                .waterfall
+                .into_iter()
+                .map(|s| proto::WaterfallStep {
+                    label: s.label,
+                    contribution: s.contribution,
+                    contribution_percent: s.contribution_percent,
+                    running_total: s.running_total,
+                })
+                .collect(),
+            recommendations: response.recommendations,
+            explanation: response.explanation.map(|e| e.text),
+        }))
+    }
+
+    async fn compile(
+        &self,
+        request: Request<proto::CompileRequest>,
+    ) -> Result<Response<proto::CompileResponse>, Status> {
+        let (tenant, _) = context(&request, &self.state);
+        let req = request.into_inner();
+        if req.template_id.trim().is_empty() {
+            return Err(status(StatusCode::BAD_REQUEST));
+        }
+        let data: Map<String, Value> = match req.data_json.as_deref() {
+            Some(data) => serde_json::from_str(data)
+                .map_err(|e| Status::invalid_argument(format!("data_json: {e}")))?,
+            None => Map::new(),
+        };
+        let variables: HashMap<String, String> = req.variables;
+        let extras = CompileExtras {
+            annotations: req.annotations,
+            ..CompileExtras::default()
+        };
+        let compiled = isolation::contain(&self.state, Site::Compile, || {
+            jurisdiction_policy::compile_enforced(
+                &self.state,
+                &tenant,
+                &req.template_id,
+                req.template_version,
+                &variables,
+                &data,
+                extras,
+            )
+        })
+        .map_err(panicked)?
+        .map_err(status)?
+        .map_err(|rejection| {
+            let violations: Vec<String> = rejection
+                .violations
+                .iter()
+                .map(|v| v.message.clone())
+                .collect();
+            Status::failed_precondition(format!(
+                "{} is blocked by the jurisdiction policy: {}",
+                rejection.template_id,
+                violations.join("; ")
+            ))
+        })?;
+        Ok(Response::new(proto::CompileResponse {
+            json: json(&compiled),
+            policy_warnings: compiled
+                .policy_warnings
+                .iter()
+                .map(|w| proto::PolicyWarning {
+                    variable: w.variable.clone(),
+                    country: w.country.clone(),
+                    message: w.message.clone(),
+                })
+                .collect(),
+            template_id: compiled.template_id,
+            template_version: compiled.template_version,
+            compiled_document: compiled.compiled_document,
+            variables_applied: compiled.variables_applied as u64,
+            missing_variables: compiled.missing_variables,
+        }))
+    }
+}
+
+// ── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_tests::SAMPLE_CONTRACT;
+    use proto::{analyze_event::Event, legal_engine_client::LegalEngineClient};
+    use tonic::{transport::Channel, Code};
+
+    async fn serve(state: AppState) -> LegalEngineClient<Channel> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+        LegalEngineClient::connect(format!("http://{addr}"))
+            .await
+            .unwrap()
+    }
+
+    fn analyze(document: &str) -> proto::AnalyzeRequest {
+        proto::AnalyzeRequest {
+            document: document.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn grpc_calls_run_the_http_pipeline() {
+        let state = AppState::in_memory().trusting_identity_headers();
+        let mut client = serve(state.clone()).await;
+
+        let mut request = Request::new(analyze(SAMPLE_CONTRACT));
+        request
+            .metadata_mut()
+            .insert("x-tenant-id", "acme".parse().unwrap());
+        let analysis = client.analyze(request).await.unwrap().into_inner();
+        assert!(!analysis.clauses.is_empty());
+        assert_eq!(
+            state.corpus.get(&analysis.analysis_id).unwrap().tenant,
+            "acme"
+        );
+        let full: Value = serde_json::from_str(&analysis.json).unwrap();
+        assert_eq!(full["analysis_id"], analysis.analysis_id.as_str());
+        assert_eq!(
+            client.analyze(analyze(" ")).await.unwrap_err().code(),
+            Code::InvalidArgument
+        );
+
+        let mut events = client
+            .analyze_stream(analyze(SAMPLE_CONTRACT))
+            .await
+            .unwrap()
+            .into_inner();
+        let (mut findings, mut summary) = (0, None);
+        while let Some(event) = events.message().await.unwrap() {
+            match event.event.unwrap() {
+                Event::Summary(s) => summary = Some(s),
+                Event::Clause(_) | Event::Issue(_) => findings += 1,
+            }
+        }
+        let summary = summary.unwrap();
+        assert_eq!(findings, summary.clause_count + summary.issue_count);
+
+        let risk = client
+            .score_risk(proto::RiskScoreRequest {
+                document: SAMPLE_CONTRACT.to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(risk.waterfall.len(), risk.risk_factors.len() + 1);
+
+        let compile = |template_id: &str| proto::CompileRequest {
+            template_id: template_id.to_string(),
+            variables: HashMap::from([("party_a".to_string(), "Acme Corp".to_string())]),
+            ..Default::default()
+        };
+        let nda = client.compile(compile("nda")).await.unwrap().into_inner();
+        assert!(nda.compiled_document.contains("Acme Corp"));
+        assert_eq!(
+            client.compile(compile("lease")).await.unwrap_err().code(),
+            Code::NotFound
+        );
+    }
+}
@@ -0,0 +1,766 @@
+//! Analysis history: every `analyze` run and `risk-score` call is kept
+//! under its ID so clients can list the reviews of a contract and fetch
+//! one again after a restart. Backed by SQLite or Postgres when
+//! `LEGAL_HISTORY_DATABASE_URL` is set, otherwise by memory only.
+
+use std::sync::Mutex;
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::{notifier, share, AnalyzeResponse, AppState};
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryKind {
+    Analysis,
+    RiskScore,
+}
+
+impl HistoryKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Analysis => "analysis",
+            Self::RiskScore => "risk_score",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "analysis" => Ok(Self::Analysis),
+            "risk_score" => Ok(Self::RiskScore),
+            other => Err(format!("unknown history kind {other:?}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistorySummary {
+    pub id: String,
+    pub kind: HistoryKind,
+    pub created_at: DateTime<Utc>,
+    /// Identifies the contract across reviews; SHA-256 of the submitted text.
+    pub document_sha256: String,
+    pub risk_score: f64,
+}
+
+/// One stored call: the summary plus the full response it returned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryRecord {
+    pub summary: HistorySummary,
+    pub tenant: String,
+    pub result: Value,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    pub tenant: String,
+    pub document_sha256: Option<String>,
+    pub kind: Option<HistoryKind>,
+    pub limit: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListParams {
+    #[serde(default)]
+    pub document_sha256: Option<String>,
+    #[serde(default)]
+    pub kind: Option<HistoryKind>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryList {
+    pub tenant: String,
+    pub analyses: Vec<HistorySummary>,
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+/// Persistence for analysis history. Lists are newest first.
+pub trait AnalysisStore: Send + Sync {
+    fn backend(&self) -> &'static str;
+    /// Inserts the record, or replaces the one with the same ID.
+    fn save(&self, record: &HistoryRecord) -> Result<(), String>;
+    fn get(&self, id: &str) -> Result<Option<HistoryRecord>, String>;
+    fn list(&self, query: &HistoryQuery) -> Result<Vec<HistorySummary>, String>;
+    /// Deletes every record of `tenant`, returning how many there were.
+    fn delete_tenant(&self, tenant: &str) -> Result<usize, String>;
+    /// Runs `f` over every record and saves those it reports as changed.
+    fn rewrite(&self, f: &mut dyn FnMut(&mut HistoryRecord) -> bool) -> Result<usize, String>;
+    /// Checks the store answers, for the readiness probe.
+    fn ping(&self) -> Result<(), String>;
+}
+
+fn newest_first(summaries: &mut [(u64, HistorySummary)]) {
+    summaries.sort_by(|(sa, a), (sb, b)| b.created_at.cmp(&a.created_at).then(sb.cmp(sa)));
+}
+
+#[derive(Default)]
+pub struct MemoryAnalysisStore {
+    /// Record and insertion sequence, which breaks ties between equal times.
+    records: DashMap<String, (u64, HistoryRecord)>,
+    seq: std::sync::atomic::AtomicU64,
+}
+
+impl AnalysisStore for MemoryAnalysisStore {
+    fn backend(&self) -> &'static str {
+        "memory"
+    }
+
+    fn ping(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn save(&self, record: &HistoryRecord) -> Result<(), String> {
+        let seq = match self.records.get(&record.summary.id) {
+            Some(existing) => existing.0,
+            None => self.seq.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+        };
+        self.records
+            .insert(record.summary.id.clone(), (seq, record.clone()));
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<HistoryRecord>, String> {
+        Ok(self.records.get(id).map(|r| r.value().1.clone()))
+    }
+
+    fn list(&self, query: &HistoryQuery) -> Result<Vec<HistorySummary>, String> {
+        let mut found: Vec<(u64, HistorySummary)> = self
+            .records
+            .iter()
+            .map(|r| r.value().clone())
+            .filter(|(_, r)| r.tenant == query.tenant)
+            .filter(|(_, r)| {
+                query
+                    .document_sha256
+                    .as_ref()
+                    .is_none_or(|h| *h == r.summary.document_sha256)
+            })
+            .filter(|(_, r)| query.kind.is_none_or(|k| k == r.summary.kind))
+            .map(|(seq, r)| (seq, r.summary))
+            .collect();
+        newest_first(&mut found);
+        Ok(found
+            .into_iter()
+            .take(query.limit)
+            .map(|(_, s)| s)
+            .collect())
+    }
+
+    fn delete_tenant(&self, tenant: &str) -> Result<usize, String> {
+        let before = self.records.len();
+        self.records.retain(|_, (_, r)| r.tenant != tenant);
+        Ok(before - self.records.len())
+    }
+
+    fn rewrite(&self, f: &mut dyn FnMut(&mut HistoryRecord) -> bool) -> Result<usize, String> {
+        Ok(self
+            .records
+            .iter_mut()
+            .filter_map(|mut r| f(&mut r.value_mut().1).then_some(()))
+            .count())
+    }
+}
+
+// ── SQL ───────────────────────────────────────────────────────────────────────
+
+/// Statements shared by both SQL backends; `{seq}` is the dialect's
+/// auto-increment key and `{real}` its double type.
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS analysis_history (
+    seq {seq},
+    id TEXT NOT NULL UNIQUE,
+    kind TEXT NOT NULL,
+    tenant TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    document_sha256 TEXT NOT NULL,
+    risk_score {real} NOT NULL,
+    result TEXT NOT NULL
+)";
+const CREATE_INDEX: &str = "CREATE INDEX IF NOT EXISTS analysis_history_tenant \
+    ON analysis_history (tenant, created_at)";
+const UPSERT: &str = "INSERT INTO analysis_history \
+    (id, kind, tenant, created_at, document_sha256, risk_score, result) \
+    VALUES ($1, $2, $3, $4, $5, $6, $7) \
+    ON CONFLICT (id) DO UPDATE SET kind = excluded.kind, tenant = excluded.tenant, \
+    created_at = excluded.created_at, document_sha256 = excluded.document_sha256, \
+    risk_score = excluded.risk_score, result = excluded.result";
+const SELECT_ONE: &str =
+    "SELECT id, kind, tenant, created_at, document_sha256, risk_score, result \
+    FROM analysis_history WHERE id = $1";
+const SELECT_ALL: &str =
+    "SELECT id, kind, tenant, created_at, document_sha256, risk_score, result \
+    FROM analysis_history";
+/// Empty strings stand for "any" in the optional filters.
+const SELECT_LIST: &str = "SELECT id, kind, created_at, document_sha256, risk_score \
+    FROM analysis_history WHERE tenant = $1 \
+    AND ($2 = '' OR document_sha256 = $2) AND ($3 = '' OR kind = $3) \
+    ORDER BY created_at DESC, seq DESC LIMIT $4";
+const DELETE_TENANT: &str = "DELETE FROM analysis_history WHERE tenant = $1";
+
+/// Fixed-width UTC so stored times sort as text.
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn parse_time(s: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| format!("bad created_at {s:?}: {e}"))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn from_row(
+    id: String,
+    kind: &str,
+    tenant: String,
+    created_at: &str,
+    document_sha256: String,
+    risk_score: f64,
+    result: &str,
+) -> Result<HistoryRecord, String> {
+    Ok(HistoryRecord {
+        summary: HistorySummary {
+            id,
+            kind: HistoryKind::parse(kind)?,
+            created_at: parse_time(created_at)?,
+            document_sha256,
+            risk_score,
+        },
+        tenant,
+        result: serde_json::from_str(result).map_err(|e| e.to_string())?,
+    })
+}
+
+pub struct SqliteAnalysisStore {
+    conn: Mutex<rusqlite::Connection>,
+}
+
+impl SqliteAnalysisStore {
+    /// Opens (creating if needed) the database at `path`; `:memory:` works too.
+    pub fn open(path: &str) -> Result<Self, String> {
+        let conn = rusqlite::Connection::open(path).map_err(|e| e.to_string())?;
+        conn.execute_batch(&format!(
+            "{};\n{};",
+            CREATE_TABLE
+                .replace("{seq}", "INTEGER PRIMARY KEY AUTOINCREMENT")
+                .replace("{real}", "REAL"),
+            CREATE_INDEX
+        ))
+        .map_err(|e| e.to_string())?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, rusqlite::Connection> {
+        self.conn.lock().unwrap_or_else(|p| p.into_inner())
+    }
+
+    fn record(row: &rusqlite::Row<'_>) -> rusqlite::Result<Result<HistoryRecord, String>> {
+        Ok(from_row(
+            row.get(0)?,
+            &row.get::<_, String>(1)?,
+            row.get(2)?,
+            &row.get::<_, String>(3)?,
+            row.get(4)?,
+            row.get(5)?,
+            &row.get::<_, String>(6)?,
+        ))
+    }
+
+    fn upsert(conn: &rusqlite::Connection, r: &HistoryRecord) -> Result<(), String> {
+        conn.execute(
+            UPSERT,
+            rusqlite::params![
+                r.summary.id,
+                r.summary.kind.as_str(),
+                r.tenant,
+                timestamp(r.summary.created_at),
+                r.summary.document_sha256,
+                r.summary.risk_score,
+                r.result.to_string(),
+            ],
+        )
+        .map(drop)
+        .map_err(|e| e.to_string())
+    }
+}
+
+impl AnalysisStore for SqliteAnalysisStore {
+    fn backend(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn ping(&self) -> Result<(), String> {
+        self.conn()
+            .execute_batch("SELECT 1")
+            .map_err(|e| e.to_string())
+    }
+
+    fn save(&self, record: &HistoryRecord) -> Result<(), String> {
+        Self::upsert(&self.conn(), record)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<HistoryRecord>, String> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(SELECT_ONE).map_err(|e| e.to_string())?;
+        let mut rows = stmt
+            .query_map([id], Self::record)
+            .map_err(|e| e.to_string())?;
+        rows.next()
+            .transpose()
+            .map_err(|e| e.to_string())?
+            .transpose()
+    }
+
+    fn list(&self, query: &HistoryQuery) -> Result<Vec<HistorySummary>, String> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(SELECT_LIST).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(
+                rusqlite::params![
+                    query.tenant,
+                    query.document_sha256.as_deref().unwrap_or_default(),
+                    query.kind.map_or("", HistoryKind::as_str),
+                    query.limit as i64,
+                ],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, f64>(4)?,
+                    ))
+                },
+            )
+            .map_err(|e| e.to_string())?;
+        rows.map(|row| {
+            let (id, kind, created_at, document_sha256, risk_score) =
+                row.map_err(|e| e.to_string())?;
+            Ok(HistorySummary {
+                id,
+                kind: HistoryKind::parse(&kind)?,
+                created_at: parse_time(&created_at)?,
+                document_sha256,
+                risk_score,
+            })
+        })
+        .collect()
+    }
+
+    fn delete_tenant(&self, tenant: &str) -> Result<usize, String> {
+        self.conn()
+            .execute(DELETE_TENANT, [tenant])
+            .map_err(|e| e.to_string())
+    }
+
+    fn rewrite(&self, f: &mut dyn FnMut(&mut HistoryRecord) -> bool) -> Result<usize, String> {
+        let mut conn = self.conn();
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let records: Vec<HistoryRecord> = {
+            let mut stmt = tx.prepare(SELECT_ALL).map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([], Self::record)
+                .map_err(|e| e.to_string())?;
+            rows.map(|r| r.map_err(|e| e.to_string())?)
+                .collect::<Result<_, String>>()?
+        };
+        let mut changed = 0;
+        for mut record in records {
+            if f(&mut record) {
+                Self::upsert(&tx, &record)?;
+                changed += 1;
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(changed)
+    }
+}
+
+#[cfg(feature = "postgres")]
+pub struct PostgresAnalysisStore {
+    client: Mutex<postgres::Client>,
+}
+
+#[cfg(feature = "postgres")]
+impl PostgresAnalysisStore {
+    pub fn connect(url: &str) -> Result<Self, String> {
+        let mut client =
+            postgres::Client::connect(url, postgres::NoTls).map_err(|e| e.to_string())?;
+        client
+            .batch_execute(&format!(
+                "{};\n{};",
+                CREATE_TABLE
+                    .replace("{seq}", "BIGSERIAL PRIMARY KEY")
+                    .replace("{real}", "DOUBLE PRECISION"),
+                CREATE_INDEX
+            ))
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            client: Mutex::new(client),
+        })
+    }
+
+    /// The blocking client drives its own runtime, so it must not run on an
+    /// async worker thread.
+    fn with<T>(
+        &self,
+        f: impl FnOnce(&mut postgres::Client) -> Result<T, String>,
+    ) -> Result<T, String> {
+        tokio::task::block_in_place(|| {
+            let mut client = self.client.lock().unwrap_or_else(|p| p.into_inner());
+            f(&mut client)
+        })
+    }
+
+    fn record(row: &postgres::Row) -> Result<HistoryRecord, String> {
+        from_row(
+            row.get(0),
+            row.get(1),
+            row.get(2),
+            row.get(3),
+            row.get(4),
+            row.get(5),
+            row.get(6),
+        )
+    }
+
+    fn upsert(client: &mut impl postgres::GenericClient, r: &HistoryRecord) -> Result<(), String> {
+        client
+            .execute(
+                UPSERT,
+                &[
+                    &r.summary.id,
+                    &r.summary.kind.as_str(),
+                    &r.tenant,
+                    &timestamp(r.summary.created_at),
+                    &r.summary.document_sha256,
+                    &r.summary.risk_score,
+                    &r.result.to_string(),
+                ],
+            )
+            .map(drop)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl AnalysisStore for PostgresAnalysisStore {
+    fn backend(&self) -> &'static str {
+        "postgres"
+    }
+
+    fn ping(&self) -> Result<(), String> {
+        self.with(|c| {
+            c.simple_query("SELECT 1")
+                .map(drop)
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn save(&self, record: &HistoryRecord) -> Result<(), String> {
+        self.with(|c| Self::upsert(c, record))
+    }
+
+    fn get(&self, id: &str) -> Result<Option<HistoryRecord>, String> {
+        self.with(|c| {
+            c.query_opt(SELECT_ONE, &[&id])
+                .map_err(|e| e.to_string())?
+                .as_ref()
+                .map(Self::record)
+                .transpose()
+        })
+    }
+
+    fn list(&self, query: &HistoryQuery) -> Result<Vec<HistorySummary>, String> {
+        self.with(|c| {
+            c.query(
+                SELECT_LIST,
+                &[
+                    &query.tenant,
+                    &query.document_sha256.as_deref().unwrap_or_default(),
+                    &query.kind.map_or("", HistoryKind::as_str),
+                    &(query.limit as i64),
+                ],
+            )
+            .map_err(|e| e.to_string())?
+            .iter()
+            .map(|row| {
+                Ok(HistorySummary {
+                    id: row.get(0),
+                    kind: HistoryKind::parse(row.get(1))?,
+                    created_at: parse_time(row.get(2))?,
+                    document_sha256: row.get(3),
+                    risk_score: row.get(4),
+                })
+            })
+            .collect()
+        })
+    }
+
+    fn delete_tenant(&self, tenant: &str) -> Result<usize, String> {
+        self.with(|c| {
+            c.execute(DELETE_TENANT, &[&tenant])
+                .map(|n| n as usize)
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn rewrite(&self, f: &mut dyn FnMut(&mut HistoryRecord) -> bool) -> Result<usize, String> {
+        self.with(|c| {
+            let mut tx = c.transaction().map_err(|e| e.to_string())?;
+            let records = tx
+                .query(SELECT_ALL, &[])
+                .map_err(|e| e.to_string())?
+                .iter()
+                .map(Self::record)
+                .collect::<Result<Vec<_>, String>>()?;
+            let mut changed = 0;
+            for mut record in records {
+                if f(&mut record) {
+                    Self::upsert(&mut tx, &record)?;
+                    changed += 1;
+                }
+            }
+            tx.commit().map_err(|e| e.to_string())?;
+            Ok(changed)
+        })
+    }
+}
+
+/// The store named by `LEGAL_HISTORY_DATABASE_URL`: `sqlite:<path>` or
+/// `postgres://…`. Memory only when unset; startup fails when the database
+/// cannot be opened.
+pub fn from_env() -> std::sync::Arc<dyn AnalysisStore> {
+    let Ok(url) = std::env::var("LEGAL_HISTORY_DATABASE_URL") else {
+        return std::sync::Arc::new(MemoryAnalysisStore::default());
+    };
+    let store: Result<std::sync::Arc<dyn AnalysisStore>, String> =
+        if let Some(path) = url.strip_prefix("sqlite:") {
+            let path = path.strip_prefix("//").unwrap_or(path);
+            SqliteAnalysisStore::open(path).map(|s| std::sync::Arc::new(s) as _)
+        } else if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+            postgres_store(&url)
+        } else {
+            Err("expected a sqlite: or postgres:// URL".into())
+        };
+    match store {
+        Ok(store) => store,
+        Err(e) => panic!("analysis history database failed to open: {e}"),
+    }
+}
+
+#[cfg(feature = "postgres")]
+fn postgres_store(url: &str) -> Result<std::sync::Arc<dyn AnalysisStore>, String> {
+    PostgresAnalysisStore::connect(url).map(|s| std::sync::Arc::new(s) as _)
+}
+
+#[cfg(not(feature = "postgres"))]
+fn postgres_store(_url: &str) -> Result<std::sync::Arc<dyn AnalysisStore>, String> {
+    Err("built without the postgres feature".into())
+}
+
+// ── Recording ─────────────────────────────────────────────────────────────────
+
+/// Stores one call's response. History is best effort: a failing database
+/// is logged and does not fail the call.
+pub fn record(
+    state: &AppState,
+    kind: HistoryKind,
+    id: &str,
+    tenant: &str,
+    document: &str,
+    risk_score: f64,
+    result: Value,
+) {
+    let record = HistoryRecord {
+        summary: HistorySummary {
+            id: id.to_string(),
+            kind,
+            created_at: state.clock.now(),
+            document_sha256: share::digest(&[document]),
+            risk_score,
+        },
+        tenant: tenant.to_string(),
+        result,
+    };
+    if let Err(e) = state.history.save(&record) {
+        warn!(id, backend = state.history.backend(), error = %e, "analysis history not saved");
+    }
+}
+
+pub fn record_analysis(state: &AppState, tenant: &str, document: &str, response: &AnalyzeResponse) {
+    match serde_json::to_value(response) {
+        Ok(result) => record(
+            state,
+            HistoryKind::Analysis,
+            &response.analysis_id,
+            tenant,
+            document,
+            response.risk_score,
+            result,
+        ),
+        Err(e) => {
+            warn!(analysis_id = %response.analysis_id, error = %e, "analysis history not saved")
+        }
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn list(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<ListParams>,
+) -> Result<Json<HistoryList>, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    let limit = match params.limit {
+        None => DEFAULT_LIMIT,
+        Some(n) if (1..=MAX_LIMIT).contains(&n) => n,
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+    };
+    let query = HistoryQuery {
+        tenant: tenant.clone(),
+        document_sha256: params.document_sha256,
+        kind: params.kind,
+        limit,
+    };
+    let analyses = state.history.list(&query).map_err(|e| {
+        warn!(backend = state.history.backend(), error = %e, "analysis history list failed");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    info!(tenant = %tenant, count = analyses.len(), "analysis history listed");
+    Ok(Json(HistoryList { tenant, analyses }))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn rec(id: &str, tenant: &str, hash: &str, minute: u32, kind: HistoryKind) -> HistoryRecord {
+        HistoryRecord {
+            summary: HistorySummary {
+                id: id.to_string(),
+                kind,
+                created_at: Utc.with_ymd_and_hms(2024, 5, 1, 9, minute, 0).unwrap(),
+                document_sha256: hash.to_string(),
+                risk_score: 0.5,
+            },
+            tenant: tenant.to_string(),
+            result: json!({ "analysis_id": id, "clauses": [{ "text": "Jane Roe signs" }] }),
+        }
+    }
+
+    fn exercise(store: &dyn AnalysisStore) {
+        let a = HistoryKind::Analysis;
+        store.save(&rec("a1", "acme", "h1", 0, a)).unwrap();
+        store.save(&rec("a2", "acme", "h1", 5, a)).unwrap();
+        store
+            .save(&rec("r1", "acme", "h1", 5, HistoryKind::RiskScore))
+            .unwrap();
+        store.save(&rec("b1", "beta", "h1", 1, a)).unwrap();
+        store.save(&rec("a3", "acme", "h2", 2, a)).unwrap();
+
+        let ids = |q: HistoryQuery| -> Vec<String> {
+            store.list(&q).unwrap().into_iter().map(|s| s.id).collect()
+        };
+        let acme = HistoryQuery {
+            tenant: "acme".into(),
+            limit: 10,
+            ..HistoryQuery::default()
+        };
+        // Newest first; equal times in insertion order, latest first.
+        assert_eq!(ids(acme.clone()), ["r1", "a2", "a3", "a1"]);
+        assert_eq!(
+            ids(HistoryQuery {
+                document_sha256: Some("h1".into()),
+                kind: Some(a),
+                ..acme.clone()
+            }),
+            ["a2", "a1"]
+        );
+        assert_eq!(
+            ids(HistoryQuery {
+                limit: 1,
+                ..acme.clone()
+            }),
+            ["r1"]
+        );
+
+        let got = store.get("a2").unwrap().unwrap();
+        assert_eq!(got, rec("a2", "acme", "h1", 5, a));
+        assert!(store.get("zz").unwrap().is_none());
+
+        // Saving an existing ID replaces it in place.
+        let mut again = rec("a1", "acme", "h1", 0, a);
+        again.summary.risk_score = 0.9;
+        store.save(&again).unwrap();
+        assert_eq!(store.get("a1").unwrap().unwrap().summary.risk_score, 0.9);
+
+        let changed = store
+            .rewrite(&mut |r| {
+                let hit = r.tenant == "beta";
+                if hit {
+                    r.result = json!({ "masked": true });
+                }
+                hit
+            })
+            .unwrap();
+        assert_eq!(changed, 1);
+        assert_eq!(
+            store.get("b1").unwrap().unwrap().result,
+            json!({ "masked": true })
+        );
+
+        assert_eq!(store.delete_tenant("acme").unwrap(), 4);
+        assert!(ids(acme).is_empty());
+        assert!(store.get("b1").unwrap().is_some());
+    }
+
+    #[test]
+    fn memory_store_lists_filters_and_rewrites() {
+        exercise(&MemoryAnalysisStore::default());
+    }
+
+    #[test]
+    fn sqlite_store_behaves_like_memory() {
+        exercise(&SqliteAnalysisStore::open(":memory:").unwrap());
+    }
+
+    #[test]
+    fn sqlite_history_survives_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        let path = path.to_str().unwrap();
+        SqliteAnalysisStore::open(path)
+            .unwrap()
+            .save(&rec("a1", "acme", "h1", 0, HistoryKind::Analysis))
+            .unwrap();
+        let reopened = SqliteAnalysisStore::open(path).unwrap();
+        assert_eq!(
+            reopened.get("a1").unwrap().unwrap(),
+            rec("a1", "acme", "h1", 0, HistoryKind::Analysis)
+        );
+    }
+}
@@ -0,0 +1,413 @@
+//! Compiled documents as standalone, accessible HTML for publishing terms of
+//! service and privacy policies on a website: a `lang` attribute, one `h1`,
+//! numbered clauses as nested sections whose heading levels never skip,
+//! real list and table markup, and every value escaped.
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+    isolation::{self, Site},
+    jurisdiction_policy, notifier, AppState, CompileExtras,
+};
+
+/// Numbered-clause titles longer than this are treated as body text.
+const MAX_TITLE_WORDS: usize = 8;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct HtmlCompileRequest {
+    pub template_id: String,
+    /// A saved version of a custom template, as in `compile`.
+    #[serde(default)]
+    pub template_version: Option<u32>,
+    pub variables: HashMap<String, String>,
+    /// Structured values for `{{#if}}` and `{{#each}}`, as in `compile`.
+    #[serde(default)]
+    pub data: serde_json::Map<String, serde_json::Value>,
+    /// BCP 47 language tag of the document text.
+    #[serde(default = "default_lang")]
+    pub lang: String,
+    /// Page title; defaults to the document's own title line.
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+fn default_lang() -> String {
+    "en".to_string()
+}
+
+pub(crate) enum Block<'a> {
+    /// A numbered clause: its number as written ("1.", "1.2"), optional
+    /// short title, and the text after the title.
+    Clause {
+        depth: usize,
+        number: &'a str,
+        title: Option<&'a str>,
+        body: &'a str,
+    },
+    List {
+        ordered: Option<&'static str>,
+        items: Vec<&'a str>,
+    },
+    Table(Vec<Vec<&'a str>>),
+    Paragraph(&'a str),
+}
+
+// ── Parsing ───────────────────────────────────────────────────────────────────
+
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Loose BCP 47 shape: alphanumeric subtags of up to 8 characters.
+fn valid_lang(lang: &str) -> bool {
+    !lang.is_empty()
+        && lang
+            .split('-')
+            .all(|t| (1..=8).contains(&t.len()) && t.bytes().all(|b| b.is_ascii_alphanumeric()))
+}
+
+/// A title line: letters present and none lowercase.
+fn is_title(line: &str) -> bool {
+    line.chars().any(char::is_alphabetic) && !line.chars().any(char::is_lowercase)
+}
+
+fn clause(line: &str) -> Option<Block<'_>> {
+    let (number, rest) = line.split_once(' ')?;
+    let path = number.strip_suffix('.').unwrap_or(number);
+    let parts: Vec<&str> = path.split('.').collect();
+    if path.is_empty()
+        || !parts
+            .iter()
+            .all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return None;
+    }
+    let rest = rest.trim();
+    let (title, body) = match rest.split_once(". ") {
+        Some((title, body))
+            if title.split_whitespace().count() <= MAX_TITLE_WORDS
+                && title.starts_with(char::is_uppercase) =>
+        {
+            (Some(title), body.trim())
+        }
+        _ => (None, rest),
+    };
+    Some(Block::Clause {
+        depth: parts.len(),
+        number,
+        title,
+        body,
+    })
+}
+
+/// List marker and item text: bullets, "(a)", "a)", "(1)", "(iv)".
+fn list_item(line: &str) -> Option<(Option<&'static str>, &str)> {
+    if let Some(text) = ["- ", "* ", "• "].iter().find_map(|m| line.strip_prefix(m)) {
+        return Some((None, text.trim()));
+    }
+    let (marker, text) = line.split_once(' ')?;
+    let inner = marker
+        .strip_prefix('(')
+        .unwrap_or(marker)
+        .strip_suffix(')')?;
+    let kind = if !inner.is_empty() && inner.bytes().all(|b| b.is_ascii_digit()) {
+        "1"
+    } else if ["i", "ii", "iii", "iv", "v", "vi", "vii", "viii", "ix", "x"].contains(&inner) {
+        "i"
+    } else if inner.len() == 1 && inner.bytes().all(|b| b.is_ascii_lowercase()) {
+        "a"
+    } else {
+        return None;
+    };
+    Some((Some(kind), text.trim()))
+}
+
+/// Cells of a `a | b | c` row; `None` for lines that are not table rows.
+fn table_row(line: &str) -> Option<Vec<&str>> {
+    let cells: Vec<&str> = line.trim_matches('|').split('|').map(str::trim).collect();
+    (cells.len() >= 2).then_some(cells)
+}
+
+fn is_separator(row: &[&str]) -> bool {
+    row.iter()
+        .all(|c| !c.is_empty() && c.chars().all(|ch| matches!(ch, '-' | ':' | ' ')))
+}
+
+fn blocks<'a>(lines: &[&'a str]) -> Vec<Block<'a>> {
+    let mut out: Vec<Block> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if let Some((ordered, item)) = list_item(line) {
+            match out.last_mut() {
+                Some(Block::List { ordered: o, items }) if *o == ordered => items.push(item),
+                _ => out.push(Block::List {
+                    ordered,
+                    items: vec![item],
+                }),
+            }
+        } else if let Some(block) = clause(line) {
+            out.push(block);
+        } else if table_row(line).is_some()
+            && lines.get(i + 1).is_some_and(|l| table_row(l).is_some())
+        {
+            let mut rows = Vec::new();
+            while let Some(row) = lines.get(i).and_then(|l| table_row(l)) {
+                if !is_separator(&row) {
+                    rows.push(row);
+                }
+                i += 1;
+            }
+            out.push(Block::Table(rows));
+            continue;
+        } else {
+            out.push(Block::Paragraph(line));
+        }
+        i += 1;
+    }
+    out
+}
+
+/// The leading all-caps title line, if any, and the blocks after it.
+pub(crate) fn outline(text: &str) -> (Option<&str>, Vec<Block<'_>>) {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    match lines.split_first() {
+        Some((first, rest)) if is_title(first) => (Some(*first), blocks(rest)),
+        _ => (None, blocks(&lines)),
+    }
+}
+
+// ── Rendering ─────────────────────────────────────────────────────────────────
+
+fn render_table(out: &mut String, rows: &[Vec<&str>]) {
+    out.push_str("<table>\n");
+    if let Some((head, body)) = rows.split_first() {
+        out.push_str("<thead><tr>");
+        for cell in head {
+            out.push_str(&format!("<th scope=\"col\">{}</th>", escape(cell)));
+        }
+        out.push_str("</tr></thead>\n<tbody>\n");
+        for row in body {
+            out.push_str("<tr>");
+            for (i, cell) in row.iter().enumerate() {
+                if i == 0 {
+                    out.push_str(&format!("<th scope=\"row\">{}</th>", escape(cell)));
+                } else {
+                    out.push_str(&format!("<td>{}</td>", escape(cell)));
+                }
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("</tbody>\n");
+    }
+    out.push_str("</table>\n");
+}
+
+/// A complete HTML document for compiled `text`. A leading all-caps line is
+/// the `h1`; otherwise `title` is.
+pub fn render(text: &str, lang: &str, title: Option<&str>) -> String {
+    render_signed(text, lang, title, &[])
+}
+
+/// `render` followed by a signature block for each of `signatories`.
+pub fn render_signed(
+    text: &str,
+    lang: &str,
+    title: Option<&str>,
+    signatories: &[String],
+) -> String {
+    let (heading, blocks) = outline(text);
+    let h1 = heading.or(title).unwrap_or("Document");
+    let page_title = title.unwrap_or(h1);
+
+    let mut body = format!("<h1>{}</h1>\n", escape(h1));
+    // (depth, heading level) of each open section.
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    for block in blocks {
+        match block {
+            Block::Clause {
+                depth,
+                number,
+                title,
+                body: text,
+            } => {
+                while open.last().is_some_and(|&(d, _)| d >= depth) {
+                    open.pop();
+                    body.push_str("</section>\n");
+                }
+                let Some(title) = title else {
+                    body.push_str(&format!("<p>{}</p>\n", escape(&format!("{number} {text}"))));
+                    continue;
+                };
+                let level = (open.last().map_or(2, |&(_, l)| l + 1)).min(6);
+                let path = number.strip_suffix('.').unwrap_or(number);
+                let id = format!("clause-{}", path.replace('.', "-"));
+                body.push_str(&format!(
+                    "<section aria-labelledby=\"{id}\">\n<h{level} id=\"{id}\">{}</h{level}>\n",
+                    escape(&format!("{number} {title}"))
+                ));
+                if !text.is_empty() {
+                    body.push_str(&format!("<p>{}</p>\n", escape(text)));
+                }
+                open.push((depth, level));
+            }
+            Block::List { ordered, items } => {
+                let (open_tag, close_tag) = match ordered {
+                    None => ("<ul>".to_string(), "</ul>"),
+                    Some(kind) => (format!("<ol type=\"{kind}\">"), "</ol>"),
+                };
+                body.push_str(&open_tag);
+                body.push('\n');
+                for item in items {
+                    body.push_str(&format!("<li>{}</li>\n", escape(item)));
+                }
+                body.push_str(close_tag);
+                body.push('\n');
+            }
+            Block::Table(rows) => render_table(&mut body, &rows),
+            Block::Paragraph(text) => body.push_str(&format!("<p>{}</p>\n", escape(text))),
+        }
+    }
+    for _ in open {
+        body.push_str("</section>\n");
+    }
+    if !signatories.is_empty() {
+        body.push_str(
+            "<section aria-labelledby=\"signatures\">\n<h2 id=\"signatures\">Signatures</h2>\n",
+        );
+        for party in signatories {
+            body.push_str(&format!(
+                "<div>\n<p>Signed for and on behalf of <strong>{}</strong></p>\n\
+                 <p>By: ____________________</p>\n<p>Name: ____________________</p>\n\
+                 <p>Title: ____________________</p>\n<p>Date: ____________________</p>\n</div>\n",
+                escape(party)
+            ));
+        }
+        body.push_str("</section>\n");
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n</head>\n<body>\n<main>\n<article>\n{body}</article>\n</main>\n</body>\n</html>\n",
+        escape(lang),
+        escape(page_title)
+    )
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+fn build(state: &AppState, tenant: &str, req: &HtmlCompileRequest) -> Result<Response, StatusCode> {
+    let compiled = match jurisdiction_policy::compile_enforced(
+        state,
+        tenant,
+        &req.template_id,
+        req.template_version,
+        &req.variables,
+        &req.data,
+        CompileExtras::default(),
+    )? {
+        Ok(compiled) => compiled,
+        Err(rejection) => {
+            return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(rejection)).into_response())
+        }
+    };
+    if !compiled.missing_variables.is_empty() {
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(compiled)).into_response());
+    }
+    let html = render(&compiled.compiled_document, &req.lang, req.title.as_deref());
+    info!(template_id = %req.template_id, lang = %req.lang, bytes = html.len(), "HTML export built");
+    Ok(([(header::CONTENT_TYPE, "text/html; charset=utf-8")], html).into_response())
+}
+
+/// Same policy checks as `compile`. Unfilled variables would be published
+/// verbatim, so they are refused with the JSON compile result (`422`).
+pub async fn compile_html(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<HtmlCompileRequest>,
+) -> Result<Response, StatusCode> {
+    if req.template_id.trim().is_empty() || !valid_lang(&req.lang) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let tenant = notifier::tenant(&headers);
+    isolation::contain(&state, Site::Compile, || build(&state, &tenant, &req))
+        .unwrap_or_else(|panicked| Ok(panicked.into_response()))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbered_clauses_nest_without_skipping_levels() {
+        let html = render(
+            "TERMS OF SERVICE\n\n1. Use. You may use the service.\n1.1 Accounts. Keep your password safe.\n\n2. Law. Governed by the laws of <Utopia>.",
+            "en-GB",
+            None,
+        );
+        assert!(html.contains("<html lang=\"en-GB\">"));
+        assert!(html.contains("<title>TERMS OF SERVICE</title>"));
+        assert!(html.contains("<h1>TERMS OF SERVICE</h1>"));
+        assert!(html.contains("<h2 id=\"clause-1\">1. Use</h2>"));
+        assert!(html.contains("<h3 id=\"clause-1-1\">1.1 Accounts</h3>"));
+        assert!(html.contains("&lt;Utopia&gt;"));
+        // Clause 1.1 closes inside clause 1, before clause 2 opens.
+        let second = html.find("clause-2").unwrap();
+        assert_eq!(html[..second].matches("</section>").count(), 2);
+        assert_eq!(
+            html.matches("<section").count(),
+            html.matches("</section>").count()
+        );
+    }
+
+    #[test]
+    fn lists_and_tables_get_semantic_markup() {
+        let html = render(
+            "We collect:\n- your email\n- usage data\n(a) first\n(b) second\nData | Retention\n--- | ---\nLogs | 30 days",
+            "en",
+            Some("Privacy"),
+        );
+        assert!(html.contains("<h1>Privacy</h1>"));
+        assert!(html.contains("<ul>\n<li>your email</li>\n<li>usage data</li>\n</ul>"));
+        assert!(html.contains("<ol type=\"a\">\n<li>first</li>"));
+        assert!(html.contains("<th scope=\"col\">Retention</th>"));
+        assert!(html.contains("<th scope=\"row\">Logs</th><td>30 days</td>"));
+        assert!(!html.contains("---"));
+    }
+
+    #[test]
+    fn language_tags_are_validated() {
+        assert!(valid_lang("en"));
+        assert!(valid_lang("pt-BR"));
+        assert!(!valid_lang("en\"><script>"));
+        assert!(!valid_lang(""));
+    }
+}
@@ -0,0 +1,353 @@
+//! Template composition: a body pulls in the current revision of another
+//! template with `{{> template_id}}`. Expansion is depth-first and stops on
+//! a cycle, past the include depth limit or once the expanded text outgrows
+//! the size limit, so a bad include is rejected when it is saved instead of
+//! looping or exhausting the stack later.
+
+use std::fmt;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{AppState, BUILTIN_TEMPLATES};
+
+pub const INCLUDE_OPEN: &str = "{{>";
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct IncludeLimits {
+    /// Includes nested deeper than this are rejected; 1 allows direct includes only.
+    pub max_depth: usize,
+    /// Upper bound on a template's fully expanded body.
+    pub max_bytes: usize,
+}
+
+impl Default for IncludeLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 8,
+            max_bytes: 1024 * 1024,
+        }
+    }
+}
+
+impl IncludeLimits {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        let var = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|n| *n > 0)
+        };
+        Self {
+            max_depth: var("LEGAL_TEMPLATE_INCLUDE_MAX_DEPTH").unwrap_or(d.max_depth),
+            max_bytes: var("LEGAL_TEMPLATE_MAX_BYTES").unwrap_or(d.max_bytes),
+        }
+    }
+}
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IncludeError {
+    /// Starts and ends with the same template.
+    Cycle {
+        cycle: Vec<String>,
+    },
+    TooDeep {
+        chain: Vec<String>,
+        max_depth: usize,
+    },
+    TooLarge {
+        template_id: String,
+        max_bytes: usize,
+    },
+    Unknown {
+        template_id: String,
+        included_by: String,
+    },
+    Malformed {
+        template_id: String,
+    },
+}
+
+impl fmt::Display for IncludeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cycle { cycle } => write!(f, "include cycle: {}", cycle.join(" -> ")),
+            Self::TooDeep { chain, max_depth } => write!(
+                f,
+                "includes nested deeper than {max_depth}: {}",
+                chain.join(" -> ")
+            ),
+            Self::TooLarge {
+                template_id,
+                max_bytes,
+            } => write!(
+                f,
+                "template {template_id} expands to more than {max_bytes} bytes"
+            ),
+            Self::Unknown {
+                template_id,
+                included_by,
+            } => write!(
+                f,
+                "template {included_by} includes unknown template {template_id}"
+            ),
+            Self::Malformed { template_id } => {
+                write!(f, "template {template_id} has an unterminated include")
+            }
+        }
+    }
+}
+
+impl IntoResponse for IncludeError {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({ "error": self.to_string(), "include": self })),
+        )
+            .into_response()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExpandedTemplate {
+    pub template_id: String,
+    pub revision: u32,
+    pub body: String,
+}
+
+// ── Expansion ─────────────────────────────────────────────────────────────────
+
+struct Expander<'a> {
+    lookup: &'a dyn Fn(&str) -> Option<String>,
+    limits: &'a IncludeLimits,
+    /// Templates being expanded, outermost first.
+    chain: Vec<String>,
+    out: String,
+}
+
+impl Expander<'_> {
+    fn push(&mut self, text: &str) -> Result<(), IncludeError> {
+        if self.out.len() + text.len() > self.limits.max_bytes {
+            return Err(IncludeError::TooLarge {
+                template_id: self.chain[0].clone(),
+                max_bytes: self.limits.max_bytes,
+            });
+        }
+        self.out.push_str(text);
+        Ok(())
+    }
+
+    fn expand(&mut self, body: &str) -> Result<(), IncludeError> {
+        let current = self.chain.last().cloned().unwrap_or_default();
+        let mut rest = body;
+        while let Some(start) = rest.find(INCLUDE_OPEN) {
+            self.push(&rest[..start])?;
+            let after = &rest[start + INCLUDE_OPEN.len()..];
+            let malformed = || IncludeError::Malformed {
+                template_id: current.clone(),
+            };
+            let end = after.find("}}").ok_or_else(malformed)?;
+            let id = after[..end].trim();
+            if id.is_empty() {
+                return Err(malformed());
+            }
+            if let Some(at) = self.chain.iter().position(|t| t == id) {
+                let mut cycle = self.chain[at..].to_vec();
+                cycle.push(id.to_string());
+                return Err(IncludeError::Cycle { cycle });
+            }
+            if self.chain.len() > self.limits.max_depth {
+                let mut chain = self.chain.clone();
+                chain.push(id.to_string());
+                return Err(IncludeError::TooDeep {
+                    chain,
+                    max_depth: self.limits.max_depth,
+                });
+            }
+            let included = (self.lookup)(id).ok_or_else(|| IncludeError::Unknown {
+                template_id: id.to_string(),
+                included_by: current.clone(),
+            })?;
+            self.chain.push(id.to_string());
+            self.expand(&included)?;
+            self.chain.pop();
+            rest = &after[end + 2..];
+        }
+        self.push(rest)
+    }
+}
+
+/// `body` of `template_id` with every include replaced by the included
+/// template's body, recursively. `lookup` returns current bodies by ID.
+pub fn expand(
+    template_id: &str,
+    body: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    limits: &IncludeLimits,
+) -> Result<String, IncludeError> {
+    let mut expander = Expander {
+        lookup,
+        limits,
+        chain: vec![template_id.to_string()],
+        out: String::new(),
+    };
+    expander.expand(body)?;
+    Ok(expander.out)
+}
+
+/// Expands every template, so a change to one is also checked from each
+/// template that includes it.
+pub fn validate_all(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    limits: &IncludeLimits,
+) -> Result<(), IncludeError> {
+    for id in BUILTIN_TEMPLATES {
+        if let Some(body) = lookup(id) {
+            expand(id, &body, lookup, limits)?;
+        }
+    }
+    Ok(())
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn get_expanded(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ExpandedTemplate>, Response> {
+    let latest = state
+        .template_revisions
+        .list(&id)
+        .and_then(|revs| revs.last().cloned())
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+    let lookup = |t: &str| state.template_revisions.latest(t);
+    let body = expand(&id, &latest.body, &lookup, &state.include_limits)
+        .map_err(IntoResponse::into_response)?;
+    Ok(Json(ExpandedTemplate {
+        template_id: id,
+        revision: latest.revision,
+        body,
+    }))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn bodies(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn run(
+        pairs: &[(&str, &str)],
+        root: &str,
+        limits: &IncludeLimits,
+    ) -> Result<String, IncludeError> {
+        let map = bodies(pairs);
+        let lookup = |id: &str| map.get(id).cloned();
+        expand(root, &map[root], &lookup, limits)
+    }
+
+    #[test]
+    fn includes_expand_recursively() {
+        let out = run(
+            &[
+                ("msa", "Terms.\n\n{{> nda}}\n\n{{> sla }}"),
+                ("nda", "Confidentiality with {{party_a}}."),
+                ("sla", "Uptime {{> nda}}"),
+            ],
+            "msa",
+            &IncludeLimits::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "Terms.\n\nConfidentiality with {{party_a}}.\n\nUptime Confidentiality with {{party_a}}."
+        );
+    }
+
+    #[test]
+    fn cycles_are_reported_with_the_path() {
+        let limits = IncludeLimits::default();
+        let err = run(&[("nda", "A {{> nda}}")], "nda", &limits).unwrap_err();
+        assert_eq!(err.to_string(), "include cycle: nda -> nda");
+
+        let err = run(
+            &[
+                ("msa", "{{> sla}}"),
+                ("sla", "{{> dpa}}"),
+                ("dpa", "{{> sla}}"),
+            ],
+            "msa",
+            &limits,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            IncludeError::Cycle {
+                cycle: vec!["sla".into(), "dpa".into(), "sla".into()]
+            }
+        );
+    }
+
+    #[test]
+    fn depth_and_size_limits_apply() {
+        let chain = [("a", "{{> b}}"), ("b", "{{> c}}"), ("c", "end")];
+        let shallow = IncludeLimits {
+            max_depth: 1,
+            ..IncludeLimits::default()
+        };
+        assert_eq!(
+            run(&chain, "a", &shallow).unwrap_err().to_string(),
+            "includes nested deeper than 1: a -> b -> c"
+        );
+        assert_eq!(run(&chain, "a", &IncludeLimits::default()).unwrap(), "end");
+
+        // Each level doubles the text; the byte limit stops the blow-up.
+        let doubling = [
+            ("a", "{{> b}}{{> b}}"),
+            ("b", "{{> c}}{{> c}}"),
+            ("c", "0123456789"),
+        ];
+        let small = IncludeLimits {
+            max_bytes: 30,
+            ..IncludeLimits::default()
+        };
+        assert!(matches!(
+            run(&doubling, "a", &small),
+            Err(IncludeError::TooLarge { ref template_id, .. }) if template_id == "a"
+        ));
+    }
+
+    #[test]
+    fn unknown_and_malformed_includes_fail() {
+        let limits = IncludeLimits::default();
+        assert_eq!(
+            run(&[("a", "{{> zzz}}")], "a", &limits)
+                .unwrap_err()
+                .to_string(),
+            "template a includes unknown template zzz"
+        );
+        assert!(matches!(
+            run(&[("a", "x {{> b")], "a", &limits),
+            Err(IncludeError::Malformed { .. })
+        ));
+    }
+}
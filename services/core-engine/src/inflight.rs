@@ -0,0 +1,278 @@
+//! What the instance is working on right now: every analysis in the
+//! pipeline with its origin, stage, tenant and document size, the jobs
+//! still waiting for a slot, and how busy the job and batch pools are. An
+//! operator looking at a stuck instance reads this instead of attaching a
+//! debugger.
+//!
+//! The pipeline registers itself on entry and moves its entry along as
+//! stages finish; the entry is dropped with the tracker, so a panicking
+//! analysis leaves the list too.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::{extract::State, response::Json};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+
+use crate::{jobs::Tier, AppState};
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+/// Which entry point started an analysis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin<'a> {
+    /// `POST /analyze` and its upload variant.
+    Request,
+    Stream,
+    Bundle,
+    Batch,
+    Job(&'a str),
+    /// The gRPC `Analyze` and `AnalyzeStream` calls.
+    Grpc,
+    /// A direct library call.
+    Embedded,
+}
+
+impl Origin<'_> {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Request => "request",
+            Self::Stream => "stream",
+            Self::Bundle => "bundle",
+            Self::Batch => "batch",
+            Self::Job(_) => "job",
+            Self::Grpc => "grpc",
+            Self::Embedded => "embedded",
+        }
+    }
+}
+
+/// The pipeline stage an analysis is in, named after its timings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Parse,
+    Segmentation,
+    Classification,
+    Scoring,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InflightAnalysis {
+    /// Set once the pipeline has allocated it.
+    pub analysis_id: Option<String>,
+    pub origin: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    pub tenant: String,
+    pub stage: Stage,
+    pub document_bytes: usize,
+    pub started_at: DateTime<Utc>,
+    pub age_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedJob {
+    pub job_id: String,
+    pub tenant: String,
+    pub tier: Tier,
+    pub queued_at: DateTime<Utc>,
+    pub age_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolUsage {
+    pub workers: usize,
+    pub busy: usize,
+    /// `busy / workers`, 0 to 1.
+    pub utilization: f64,
+}
+
+impl PoolUsage {
+    pub fn new(workers: usize, free: usize) -> Self {
+        let busy = workers.saturating_sub(free);
+        Self {
+            workers,
+            busy,
+            utilization: if workers == 0 {
+                0.0
+            } else {
+                busy as f64 / workers as f64
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Pools {
+    pub jobs: PoolUsage,
+    pub batches: PoolUsage,
+}
+
+/// Body of `GET /admin/inflight`; both lists oldest first.
+#[derive(Debug, Serialize)]
+pub struct InflightReport {
+    pub analyses: Vec<InflightAnalysis>,
+    pub queued_jobs: Vec<QueuedJob>,
+    pub pools: Pools,
+}
+
+// ── Registry ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default)]
+pub struct InflightRegistry {
+    next: AtomicU64,
+    running: DashMap<u64, InflightAnalysis>,
+}
+
+impl InflightRegistry {
+    /// Registers an analysis in the parse stage; it is listed until the
+    /// tracker is dropped.
+    pub fn begin(
+        &self,
+        origin: Origin,
+        tenant: &str,
+        document_bytes: usize,
+        now: DateTime<Utc>,
+    ) -> Tracker<'_> {
+        let key = self.next.fetch_add(1, Ordering::Relaxed);
+        self.running.insert(
+            key,
+            InflightAnalysis {
+                analysis_id: None,
+                origin: origin.label(),
+                job_id: match origin {
+                    Origin::Job(id) => Some(id.to_string()),
+                    _ => None,
+                },
+                tenant: tenant.to_string(),
+                stage: Stage::Parse,
+                document_bytes,
+                started_at: now,
+                age_ms: 0,
+            },
+        );
+        Tracker {
+            registry: self,
+            key,
+        }
+    }
+
+    pub fn running(&self) -> usize {
+        self.running.len()
+    }
+
+    /// The running analyses, oldest first, aged as of `now`.
+    pub fn snapshot(&self, now: DateTime<Utc>) -> Vec<InflightAnalysis> {
+        let mut analyses: Vec<InflightAnalysis> = self
+            .running
+            .iter()
+            .map(|entry| {
+                let mut analysis = entry.value().clone();
+                analysis.age_ms = (now - analysis.started_at).num_milliseconds().max(0);
+                analysis
+            })
+            .collect();
+        analyses.sort_by_key(|a| a.started_at);
+        analyses
+    }
+}
+
+/// One analysis's entry in the registry.
+pub struct Tracker<'a> {
+    registry: &'a InflightRegistry,
+    key: u64,
+}
+
+impl Tracker<'_> {
+    pub fn stage(&self, stage: Stage) {
+        if let Some(mut entry) = self.registry.running.get_mut(&self.key) {
+            entry.stage = stage;
+        }
+    }
+
+    pub fn identify(&self, analysis_id: &str) {
+        if let Some(mut entry) = self.registry.running.get_mut(&self.key) {
+            entry.analysis_id = Some(analysis_id.to_string());
+        }
+    }
+}
+
+impl Drop for Tracker<'_> {
+    fn drop(&mut self) {
+        self.registry.running.remove(&self.key);
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn list(State(state): State<AppState>) -> Json<InflightReport> {
+    let now = state.clock.now();
+    let queued_jobs = state
+        .jobs
+        .queued()
+        .into_iter()
+        .map(|(job_id, tenant, tier, queued_at)| QueuedJob {
+            job_id,
+            tenant,
+            tier,
+            queued_at,
+            age_ms: (now - queued_at).num_milliseconds().max(0),
+        })
+        .collect();
+    Json(InflightReport {
+        analyses: state.inflight.snapshot(now),
+        queued_jobs,
+        pools: Pools {
+            jobs: PoolUsage::new(state.jobs.workers(), state.jobs.free_slots()),
+            batches: PoolUsage::new(state.batches.config.concurrency, state.batches.free_slots()),
+        },
+    })
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn entries_follow_the_pipeline_and_leave_when_dropped() {
+        let registry = InflightRegistry::default();
+        let t0 = Utc::now();
+        let first = registry.begin(Origin::Job("job-1"), "acme", 1200, t0);
+        let second = registry.begin(Origin::Request, "beta", 80, t0 + Duration::seconds(2));
+        first.stage(Stage::Classification);
+        first.identify("a-1");
+
+        let listed = registry.snapshot(t0 + Duration::seconds(5));
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].origin, "job");
+        assert_eq!(listed[0].job_id.as_deref(), Some("job-1"));
+        assert_eq!(listed[0].analysis_id.as_deref(), Some("a-1"));
+        assert_eq!(listed[0].stage, Stage::Classification);
+        assert_eq!(listed[0].document_bytes, 1200);
+        assert_eq!(listed[0].age_ms, 5000);
+        assert_eq!(listed[1].tenant, "beta");
+        assert_eq!(listed[1].stage, Stage::Parse);
+        assert_eq!(listed[1].job_id, None);
+        assert_eq!(listed[1].age_ms, 3000);
+
+        drop(first);
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+            let _held = second;
+            panic!("analysis blew up");
+        }));
+        assert!(registry.snapshot(t0).is_empty());
+    }
+
+    #[test]
+    fn utilization_is_the_busy_share_of_the_pool() {
+        let usage = PoolUsage::new(4, 1);
+        assert_eq!(usage.busy, 3);
+        assert!((usage.utilization - 0.75).abs() < 1e-9);
+        assert_eq!(PoolUsage::new(0, 0).utilization, 0.0);
+    }
+}
@@ -0,0 +1,650 @@
+//! File uploads for analysis: text is pulled out of a PDF or DOCX body on
+//! the server and fed through the same pipeline as `/analyze`. Extraction
+//! keeps line breaks, headings and list numbering so section detection still
+//! sees one clause heading per line.
+
+use std::io::{Cursor, Read};
+
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    response::Response,
+    Extension,
+};
+use flate2::read::ZlibDecoder;
+use serde::Deserialize;
+
+use crate::{
+    deadline::{AnalysisMode, Deadline},
+    evidence::EvidenceOptions,
+    reviews::{attr, tag_name, walk, zip_entry, XmlEvent, DOCX_CONTENT_TYPE},
+    AnalyzeRequest, AppState,
+};
+
+pub const PDF_CONTENT_TYPE: &str = "application/pdf";
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct IngestConfig {
+    /// Largest accepted upload, in bytes.
+    pub max_bytes: usize,
+}
+
+impl Default for IngestConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: 20 * 1024 * 1024,
+        }
+    }
+}
+
+impl IngestConfig {
+    pub fn from_env() -> Self {
+        Self {
+            max_bytes: std::env::var("LEGAL_UPLOAD_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or_else(|| Self::default().max_bytes),
+        }
+    }
+}
+
+// ── DOCX ──────────────────────────────────────────────────────────────────────
+
+/// Paragraph text of `word/document.xml`, one paragraph per line. Tabs and
+/// manual breaks are kept, headings get a blank line before them, and list
+/// paragraphs are prefixed with decimal outline numbers ("1.", "1.1").
+/// Numbering formats from `numbering.xml` are not applied, and all lists
+/// share one set of counters.
+pub fn docx_text(bytes: &[u8]) -> Result<String, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    let document_xml = zip_entry(&mut archive, "word/document.xml")?;
+
+    let mut out = String::new();
+    let mut para = String::new();
+    let mut in_props = false;
+    let mut heading = false;
+    let mut level: Option<usize> = None;
+    let mut counters: Vec<u32> = Vec::new();
+    walk(&document_xml, |event| match event {
+        XmlEvent::Tag(tag) => {
+            let closing = tag.starts_with("</");
+            match tag_name(tag) {
+                "w:p" if tag.ends_with("/>") => out.push('\n'),
+                "w:p" if !closing => {
+                    para.clear();
+                    heading = false;
+                    level = None;
+                }
+                "w:p" => {
+                    if heading && !out.is_empty() && !out.ends_with("\n\n") {
+                        out.push('\n');
+                    }
+                    if let Some(lvl) = level {
+                        counters.resize(lvl + 1, 0);
+                        counters[lvl] += 1;
+                        let number: Vec<String> = counters.iter().map(u32::to_string).collect();
+                        out.push_str(&number.join("."));
+                        out.push_str(if lvl == 0 { ". " } else { " " });
+                    }
+                    out.push_str(para.trim_end());
+                    out.push('\n');
+                }
+                "w:pPr" => in_props = !closing && !tag.ends_with("/>"),
+                "w:pStyle" => {
+                    heading = attr(tag, "w:val")
+                        .is_some_and(|v| v.starts_with("Heading") || v == "Title");
+                }
+                "w:ilvl" if in_props => {
+                    let lvl = attr(tag, "w:val").and_then(|v| v.parse().ok());
+                    level = Some(lvl.unwrap_or(0).min(8));
+                }
+                "w:numId" if in_props => {
+                    // `numId` 0 switches inherited numbering off.
+                    if attr(tag, "w:val").as_deref() == Some("0") {
+                        level = None;
+                    } else {
+                        level.get_or_insert(0);
+                    }
+                }
+                // Tab stops inside paragraph properties are not text.
+                "w:tab" if !in_props => para.push('\t'),
+                "w:br" | "w:cr" => para.push('\n'),
+                _ => {}
+            }
+        }
+        XmlEvent::Text(text) => para.push_str(&text),
+    });
+    Ok(out)
+}
+
+// ── PDF ───────────────────────────────────────────────────────────────────────
+
+/// Text drawn by the content streams of an unencrypted PDF. Uncompressed
+/// and FlateDecode streams are read; other filters (images, fonts) are
+/// skipped. String bytes are read as Latin-1, or UTF-16 with a byte order
+/// mark, so fonts that need a ToUnicode map come out garbled, and scanned
+/// pages yield nothing.
+pub fn pdf_text(bytes: &[u8]) -> Result<String, String> {
+    if !bytes.starts_with(b"%PDF") {
+        return Err("missing %PDF header".into());
+    }
+    if find(bytes, b"/Encrypt", 0).is_some() {
+        return Err("encrypted PDFs are not supported".into());
+    }
+    let mut pages = Vec::new();
+    for (dict, data) in streams(bytes) {
+        let decoded = if find(dict, b"/Filter", 0).is_none() {
+            data.to_vec()
+        } else if find(dict, b"/FlateDecode", 0).is_some() && find(dict, b"[", 0).is_none() {
+            let mut out = Vec::new();
+            if ZlibDecoder::new(data).read_to_end(&mut out).is_err() {
+                continue;
+            }
+            out
+        } else {
+            continue;
+        };
+        let text = show_text(&decoded);
+        if !text.trim().is_empty() {
+            pages.push(text);
+        }
+    }
+    Ok(tidy(&pages.join("\n\n")))
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|i| i + from)
+}
+
+/// `(dictionary, raw data)` of each `stream … endstream` object.
+fn streams(bytes: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while let Some(kw) = find(bytes, b"stream", pos) {
+        let mut start = kw + b"stream".len();
+        if bytes[start..].starts_with(b"\r\n") {
+            start += 2;
+        } else if bytes[start..].starts_with(b"\n") {
+            start += 1;
+        } else {
+            pos = start;
+            continue;
+        }
+        let Some(end) = find(bytes, b"endstream", start) else {
+            break;
+        };
+        let dict_start = bytes[..kw]
+            .windows(3)
+            .rposition(|w| w == b"obj")
+            .unwrap_or(0);
+        let mut data_end = end;
+        while data_end > start && matches!(bytes[data_end - 1], b'\r' | b'\n') {
+            data_end -= 1;
+        }
+        out.push((&bytes[dict_start..kw], &bytes[start..data_end]));
+        pos = end + b"endstream".len();
+    }
+    out
+}
+
+enum Operand {
+    Number(f64),
+    Str(Vec<u8>),
+    Array(Vec<Operand>),
+    Other,
+}
+
+struct Lexer<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+enum Token<'a> {
+    Operand(Operand),
+    ArrayOpen,
+    ArrayClose,
+    Operator(&'a [u8]),
+}
+
+fn is_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\r' | b'\n' | b'\x0c' | b'\0')
+}
+
+fn is_delimiter(b: u8) -> bool {
+    is_space(b) || b"()<>[]{}/%".contains(&b)
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn word(&mut self) -> &'a [u8] {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| !is_delimiter(b)) {
+            self.pos += 1;
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn literal(&mut self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut depth = 0;
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            match b {
+                b'(' => {
+                    depth += 1;
+                    out.push(b);
+                }
+                b')' if depth == 0 => break,
+                b')' => {
+                    depth -= 1;
+                    out.push(b);
+                }
+                b'\\' => {
+                    let Some(e) = self.peek() else { break };
+                    self.pos += 1;
+                    match e {
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'b' => out.push(0x08),
+                        b'f' => out.push(0x0c),
+                        b'0'..=b'7' => {
+                            let mut value = u32::from(e - b'0');
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(d @ b'0'..=b'7') => {
+                                        value = value * 8 + u32::from(d - b'0');
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            out.push(value as u8);
+                        }
+                        // Line continuation.
+                        b'\r' | b'\n' => {
+                            if e == b'\r' && self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        _ => out.push(e),
+                    }
+                }
+                _ => out.push(b),
+            }
+        }
+        out
+    }
+
+    fn hex(&mut self) -> Vec<u8> {
+        let mut digits = Vec::new();
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            if b == b'>' {
+                break;
+            }
+            if let Some(d) = (b as char).to_digit(16) {
+                digits.push(d as u8);
+            }
+        }
+        if digits.len() % 2 == 1 {
+            digits.push(0);
+        }
+        digits.chunks(2).map(|p| p[0] << 4 | p[1]).collect()
+    }
+
+    fn next_token(&mut self) -> Option<Token<'a>> {
+        loop {
+            let b = self.peek()?;
+            if is_space(b) {
+                self.pos += 1;
+            } else if b == b'%' {
+                while self.peek().is_some_and(|b| b != b'\n' && b != b'\r') {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+        let b = self.peek()?;
+        self.pos += 1;
+        Some(match b {
+            b'(' => Token::Operand(Operand::Str(self.literal())),
+            b'<' if self.peek() == Some(b'<') => {
+                self.pos += 1;
+                Token::Operand(Operand::Other)
+            }
+            b'<' => Token::Operand(Operand::Str(self.hex())),
+            b'>' => {
+                if self.peek() == Some(b'>') {
+                    self.pos += 1;
+                }
+                Token::Operand(Operand::Other)
+            }
+            b'[' => Token::ArrayOpen,
+            b']' => Token::ArrayClose,
+            b'/' => {
+                self.word();
+                Token::Operand(Operand::Other)
+            }
+            b'{' | b'}' | b')' => Token::Operand(Operand::Other),
+            _ => {
+                self.pos -= 1;
+                let word = self.word();
+                match std::str::from_utf8(word).ok().and_then(|w| w.parse().ok()) {
+                    Some(n) => Token::Operand(Operand::Number(n)),
+                    None => Token::Operator(word),
+                }
+            }
+        })
+    }
+
+    /// Skips inline image data up to and including `EI`.
+    fn skip_inline_image(&mut self) {
+        while let Some(at) = find(self.src, b"EI", self.pos) {
+            self.pos = at + 2;
+            let before = at.checked_sub(1).map(|i| self.src[i]);
+            if before.is_some_and(is_space) && self.peek().is_none_or(is_space) {
+                return;
+            }
+        }
+        self.pos = self.src.len();
+    }
+}
+
+fn decode(bytes: &[u8]) -> String {
+    if let Some(rest) = bytes.strip_prefix(&[0xfe, 0xff]) {
+        let units: Vec<u16> = rest
+            .chunks_exact(2)
+            .map(|p| u16::from_be_bytes([p[0], p[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+/// Kerning in a `TJ` array past this (thousandths of an em) reads as a space.
+const TJ_SPACE: f64 = -200.0;
+
+/// Interprets the text operators of one content stream.
+fn show_text(content: &[u8]) -> String {
+    let mut lexer = Lexer {
+        src: content,
+        pos: 0,
+    };
+    let mut out = String::new();
+    let mut stack: Vec<Operand> = Vec::new();
+    let mut arrays: Vec<Vec<Operand>> = Vec::new();
+    // Smallest downward line step seen; a much bigger one starts a paragraph.
+    let mut line_step: Option<f64> = None;
+    let mut last_y: Option<f64> = None;
+
+    let new_line = |out: &mut String, dy: f64, line_step: &mut Option<f64>| {
+        if out.is_empty() || dy == 0.0 {
+            return;
+        }
+        let dy = dy.abs();
+        let paragraph = line_step.is_some_and(|step| dy > step * 1.5);
+        *line_step = Some(line_step.map_or(dy, |step| step.min(dy)));
+        out.push('\n');
+        if paragraph {
+            out.push('\n');
+        }
+    };
+
+    while let Some(token) = lexer.next_token() {
+        let op = match token {
+            Token::Operand(o) => {
+                match arrays.last_mut() {
+                    Some(array) => array.push(o),
+                    None => stack.push(o),
+                }
+                continue;
+            }
+            Token::ArrayOpen => {
+                arrays.push(Vec::new());
+                continue;
+            }
+            Token::ArrayClose => {
+                let array = Operand::Array(arrays.pop().unwrap_or_default());
+                match arrays.last_mut() {
+                    Some(outer) => outer.push(array),
+                    None => stack.push(array),
+                }
+                continue;
+            }
+            Token::Operator(op) => op,
+        };
+        let num = |i: usize| match stack.len().checked_sub(i).map(|at| &stack[at]) {
+            Some(Operand::Number(n)) => *n,
+            _ => 0.0,
+        };
+        match op {
+            b"ET" if !out.is_empty() && !out.ends_with('\n') => out.push('\n'),
+            b"Td" | b"TD" => new_line(&mut out, num(1), &mut line_step),
+            b"Tm" => {
+                let y = num(1);
+                if let Some(prev) = last_y {
+                    new_line(&mut out, prev - y, &mut line_step);
+                }
+                last_y = Some(y);
+            }
+            b"T*" => out.push('\n'),
+            b"Tj" | b"'" | b"\"" => {
+                if op != b"Tj" {
+                    out.push('\n');
+                }
+                if let Some(Operand::Str(s)) = stack.last() {
+                    out.push_str(&decode(s));
+                }
+            }
+            b"TJ" => {
+                if let Some(Operand::Array(items)) = stack.last() {
+                    for item in items {
+                        match item {
+                            Operand::Str(s) => out.push_str(&decode(s)),
+                            Operand::Number(n) if *n < TJ_SPACE && !out.ends_with(' ') => {
+                                out.push(' ')
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            b"ID" => lexer.skip_inline_image(),
+            _ => {}
+        }
+        stack.clear();
+    }
+    out
+}
+
+/// Trims line ends and collapses runs of blank lines.
+fn tidy(text: &str) -> String {
+    let mut out = String::new();
+    let mut blank = 0;
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() {
+            blank += 1;
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blank > 0 { "\n\n" } else { "\n" });
+        }
+        blank = 0;
+        out.push_str(line);
+    }
+    out
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct FileQuery {
+    #[serde(default = "default_language")]
+    language: String,
+    #[serde(default)]
+    document_name: Option<String>,
+    #[serde(default)]
+    mode: AnalysisMode,
+    #[serde(default)]
+    evidence_context_chars: Option<usize>,
+    #[serde(default)]
+    fallback_clauses: bool,
+    #[serde(default)]
+    latency_budget_ms: Option<u64>,
+    #[serde(default)]
+    our_party: Option<String>,
+}
+
+fn default_language() -> String {
+    "en".into()
+}
+
+/// Body is the file itself; `Content-Type` picks the extractor and the
+/// `/analyze` request fields come from the query string.
+pub async fn analyze_file(
+    State(state): State<AppState>,
+    Extension(deadline): Extension<Deadline>,
+    Query(query): Query<FileQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, StatusCode> {
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let extracted = if content_type.starts_with(PDF_CONTENT_TYPE) {
+        pdf_text(&body)
+    } else if content_type.starts_with(DOCX_CONTENT_TYPE) {
+        docx_text(&body)
+    } else {
+        return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    };
+    let document = extracted.map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    if document.trim().is_empty() {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    let req = AnalyzeRequest {
+        document,
+        language: query.language,
+        document_name: query.document_name,
+        mode: query.mode,
+        analysis_options: EvidenceOptions {
+            evidence_context_chars: query.evidence_context_chars,
+            fallback_clauses: query.fallback_clauses,
+        },
+        latency_budget_ms: query.latency_budget_ms,
+        our_party: query.our_party,
+        ml_context: Default::default(),
+    };
+    let ml = crate::ensemble::ml_findings(&state, &crate::notifier::tenant(&headers), &req).await;
+    crate::analyze_off_runtime(state, deadline, headers, req, ml).await
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use flate2::{write::ZlibEncoder, Compression};
+    use std::io::Write;
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    pub fn docx(document: &str) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("word/document.xml", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(document.as_bytes()).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    /// A one-page PDF whose single content stream is `content`.
+    pub fn pdf(content: &[u8], compress: bool) -> Vec<u8> {
+        let (filter, data) = if compress {
+            let mut enc = ZlibEncoder::new(Vec::new(), Compression::default());
+            enc.write_all(content).unwrap();
+            (" /Filter /FlateDecode", enc.finish().unwrap())
+        } else {
+            ("", content.to_vec())
+        };
+        let mut out = b"%PDF-1.4\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n".to_vec();
+        out.extend_from_slice(
+            format!("4 0 obj\n<< /Length {}{filter} >>\nstream\n", data.len()).as_bytes(),
+        );
+        out.extend_from_slice(&data);
+        out.extend_from_slice(b"\nendstream\nendobj\ntrailer\n<< /Root 1 0 R >>\n%%EOF\n");
+        out
+    }
+
+    #[test]
+    fn docx_keeps_paragraphs_headings_and_numbering() {
+        let document = r#"<w:document><w:body>
+            <w:p><w:pPr><w:pStyle w:val="Title"/></w:pPr><w:r><w:t>Services Agreement</w:t></w:r></w:p>
+            <w:p><w:pPr><w:pStyle w:val="Heading1"/><w:numPr><w:ilvl w:val="0"/><w:numId w:val="3"/></w:numPr></w:pPr>
+              <w:r><w:t>Liability</w:t></w:r></w:p>
+            <w:p><w:pPr><w:numPr><w:ilvl w:val="1"/><w:numId w:val="3"/></w:numPr>
+              <w:tabs><w:tab w:val="left" w:pos="720"/></w:tabs></w:pPr>
+              <w:r><w:t xml:space="preserve">Liability is </w:t></w:r><w:r><w:t>capped.</w:t><w:tab/><w:t>See &amp; note</w:t></w:r></w:p>
+            <w:p><w:pPr><w:numPr><w:ilvl w:val="1"/><w:numId w:val="3"/></w:numPr></w:pPr><w:r><w:t>Line one</w:t><w:br/><w:t>line two</w:t></w:r></w:p>
+            <w:p/>
+            <w:p><w:pPr><w:pStyle w:val="Heading1"/><w:numPr><w:ilvl w:val="0"/><w:numId w:val="3"/></w:numPr></w:pPr><w:r><w:t>Termination</w:t></w:r></w:p>
+            <w:p><w:pPr><w:numPr><w:numId w:val="0"/></w:numPr></w:pPr><w:r><w:t>Plain text.</w:t></w:r></w:p>
+            </w:body></w:document>"#;
+        let text = docx_text(&docx(document)).unwrap();
+        assert_eq!(
+            text,
+            "Services Agreement\n\n1. Liability\n1.1 Liability is capped.\tSee & note\n\
+             1.2 Line one\nline two\n\n2. Termination\nPlain text.\n"
+        );
+    }
+
+    #[test]
+    fn pdf_text_follows_lines_and_paragraphs() {
+        let content = br"BT /F1 12 Tf 72 720 Td (1. Governing Law) Tj
+            0 -14 Td (This Agreement is governed by the laws of \(New York\).) Tj
+            0 -40 Td [(2. Li) -20 (ability)] TJ
+            0 -14 Td [(Total) -300 (liability) -300 (is) -300 (capped.)] TJ
+            T* <4361702e> Tj ET";
+        let expected = "1. Governing Law\n\
+            This Agreement is governed by the laws of (New York).\n\n\
+            2. Liability\n\
+            Total liability is capped.\n\
+            Cap.";
+        assert_eq!(pdf_text(&pdf(content, false)).unwrap(), expected);
+        assert_eq!(pdf_text(&pdf(content, true)).unwrap(), expected);
+    }
+
+    #[test]
+    fn pdf_strings_decode_escapes_and_utf16() {
+        let content = b"BT (caf\\351 \\\nok) Tj 0 -12 Td <FEFF00E9007A> Tj ET";
+        assert_eq!(
+            pdf_text(&pdf(content, false)).unwrap(),
+            "caf\u{e9} ok\n\u{e9}z"
+        );
+    }
+
+    #[test]
+    fn unsupported_pdfs_are_rejected() {
+        assert!(pdf_text(b"not a pdf").is_err());
+        let mut encrypted = pdf(b"BT (x) Tj ET", false);
+        encrypted.extend_from_slice(b"<< /Encrypt 5 0 R >>");
+        assert!(pdf_text(&encrypted).is_err());
+        // Image-only pages have no text operators.
+        assert_eq!(
+            pdf_text(&pdf(b"q 100 0 0 100 0 0 cm /Im1 Do Q", false)).unwrap(),
+            ""
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Panic containment for template compilation and the analysis pipeline. A
+//! panic inside one request is caught where the handler calls into the
+//! engine, logged under an error ID that the client gets back in a `500`,
+//! and counted, so a malformed template or document cannot drop the
+//! connection, wedge a job slot or take a runtime worker with it.
+
+use std::{
+    any::Any,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+use tracing::error;
+
+use crate::AppState;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+/// Where a panic was caught; the `site` label of the panic counter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Site {
+    Analysis,
+    Compile,
+}
+
+impl Site {
+    const ALL: [Site; 2] = [Site::Analysis, Site::Compile];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Analysis => "analysis",
+            Self::Compile => "compile",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PanicCounters {
+    analysis: AtomicU64,
+    compile: AtomicU64,
+}
+
+impl PanicCounters {
+    fn counter(&self, site: Site) -> &AtomicU64 {
+        match site {
+            Site::Analysis => &self.analysis,
+            Site::Compile => &self.compile,
+        }
+    }
+
+    pub fn get(&self, site: Site) -> u64 {
+        self.counter(site).load(Ordering::Relaxed)
+    }
+}
+
+/// The `500` body: the panic message stays in the log, keyed by `error_id`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Panicked {
+    pub error: String,
+    pub error_id: String,
+}
+
+impl IntoResponse for Panicked {
+    fn into_response(self) -> Response {
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(self)).into_response()
+    }
+}
+
+// ── Containment ───────────────────────────────────────────────────────────────
+
+fn message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+/// Runs `f`, turning a panic into a counted, logged [`Panicked`].
+pub fn contain<T>(state: &AppState, site: Site, f: impl FnOnce() -> T) -> Result<T, Panicked> {
+    catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let error_id = format!("err-{}", state.ids.next_id());
+        state.panics.counter(site).fetch_add(1, Ordering::Relaxed);
+        error!(
+            error_id = %error_id,
+            site = site.label(),
+            message = message(payload.as_ref()),
+            "panic contained"
+        );
+        Panicked {
+            error: "internal error".to_string(),
+            error_id,
+        }
+    })
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// Counters in the Prometheus text format.
+pub async fn metrics(State(state): State<AppState>) -> Response {
+    let mut body = String::from(
+        "# HELP legal_engine_panics_total Panics caught and turned into 500 responses.\n\
+         # TYPE legal_engine_panics_total counter\n",
+    );
+    for site in Site::ALL {
+        body.push_str(&format!(
+            "legal_engine_panics_total{{site=\"{}\"}} {}\n",
+            site.label(),
+            state.panics.get(site)
+        ));
+    }
+    body.push_str(&format!(
+        "# HELP legal_engine_rate_limited_total Requests turned away with 429.\n\
+         # TYPE legal_engine_rate_limited_total counter\n\
+         legal_engine_rate_limited_total {}\n",
+        state.rate_limit.throttled()
+    ));
+    body.push_str(&format!(
+        "# HELP legal_engine_compile_cache_total Compiles answered from the cache or compiled.\n\
+         # TYPE legal_engine_compile_cache_total counter\n\
+         legal_engine_compile_cache_total{{result=\"hit\"}} {}\n\
+         legal_engine_compile_cache_total{{result=\"miss\"}} {}\n",
+        state.compile_cache.hits(),
+        state.compile_cache.misses()
+    ));
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panic_becomes_counted_error_with_id() {
+        let state = AppState::in_memory();
+        assert_eq!(contain(&state, Site::Compile, || 7).unwrap(), 7);
+
+        let err = contain(&state, Site::Compile, || -> u32 {
+            panic!("malformed template")
+        })
+        .unwrap_err();
+        assert!(err.error_id.starts_with("err-"));
+        assert_eq!(state.panics.get(Site::Compile), 1);
+        assert_eq!(state.panics.get(Site::Analysis), 0);
+
+        let again = contain(&state, Site::Compile, || panic!("{}", String::from("x"))).unwrap_err();
+        assert_ne!(again.error_id, err.error_id);
+        assert_eq!(state.panics.get(Site::Compile), 2);
+    }
+
+    #[test]
+    fn reads_both_panic_payload_kinds() {
+        assert_eq!(message(&"static"), "static");
+        assert_eq!(message(&String::from("owned")), "owned");
+        assert_eq!(message(&42), "non-string panic payload");
+    }
+}
@@ -0,0 +1,910 @@
+//! Background analysis jobs on a fixed pool of worker slots, with
+//! cooperative cancellation: the pipeline checks the job's cancel flag
+//! between stages, and a job that does not stop within the grace period has
+//! its slot released anyway and its eventual result discarded.
+//!
+//! Queued jobs are triaged into tiers by contract value, due date and the
+//! requester's role. A freed slot goes to a tier by smooth weighted
+//! round-robin, so urgent work goes first without starving routine work,
+//! and within a tier to the highest score, oldest first.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{info, warn};
+
+use crate::{
+    access::ROLE_HEADER,
+    deadline::Deadline,
+    ensemble,
+    inflight::Origin,
+    isolation::{self, Site},
+    notifier, run_analysis,
+    warmup::KeywordMatcher,
+    webhooks, AnalysisOptions, AnalyzeRequest, AnalyzeResponse, AppState,
+};
+
+/// Contract values are scored on a log scale between these.
+const VALUE_FLOOR: f64 = 10_000.0;
+const VALUE_CEILING: f64 = 10_000_000.0;
+/// Due within this many hours is fully urgent; beyond the second, not at all.
+const URGENT_WITHIN_HOURS: f64 = 24.0;
+const RELAXED_AFTER_HOURS: f64 = 168.0;
+const VALUE_WEIGHT: f64 = 0.45;
+const URGENCY_WEIGHT: f64 = 0.35;
+const ROLE_WEIGHT: f64 = 0.2;
+const URGENT_SCORE: f64 = 0.6;
+const HIGH_SCORE: f64 = 0.3;
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct JobConfig {
+    /// Jobs analyzed at once; the rest wait queued.
+    pub workers: usize,
+    /// How long a cancelled job may keep its slot before it is taken back.
+    pub cancel_grace: Duration,
+    /// Share of freed slots per tier, urgent first, while all are waiting.
+    pub tier_weights: [u32; 3],
+    /// Priority each access role lends its jobs, 0 to 1; others get 0.
+    pub role_priority: HashMap<String, f64>,
+}
+
+impl Default for JobConfig {
+    fn default() -> Self {
+        Self {
+            workers: 4,
+            cancel_grace: Duration::from_secs(2),
+            tier_weights: [4, 2, 1],
+            role_priority: HashMap::from([
+                ("legal".to_string(), 1.0),
+                ("finance".to_string(), 0.5),
+                ("sales".to_string(), 0.3),
+            ]),
+        }
+    }
+}
+
+impl JobConfig {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        Self {
+            workers: std::env::var("LEGAL_JOB_WORKERS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(d.workers),
+            cancel_grace: std::env::var("LEGAL_JOB_CANCEL_GRACE_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map_or(d.cancel_grace, Duration::from_millis),
+            tier_weights: std::env::var("LEGAL_JOB_TIER_WEIGHTS")
+                .ok()
+                .and_then(|v| parse_weights(&v))
+                .unwrap_or(d.tier_weights),
+            role_priority: std::env::var("LEGAL_JOB_ROLE_PRIORITY")
+                .ok()
+                .and_then(|v| parse_roles(&v))
+                .unwrap_or(d.role_priority),
+        }
+    }
+}
+
+/// `urgent,high,normal`, each at least 1.
+fn parse_weights(raw: &str) -> Option<[u32; 3]> {
+    let weights: Vec<u32> = raw
+        .split(',')
+        .map(|w| w.trim().parse().ok().filter(|w| *w > 0))
+        .collect::<Option<_>>()?;
+    weights.try_into().ok()
+}
+
+/// `role=priority,…` with priorities from 0 to 1.
+fn parse_roles(raw: &str) -> Option<HashMap<String, f64>> {
+    raw.split(',')
+        .map(|pair| {
+            let (role, priority) = pair.split_once('=')?;
+            let priority: f64 = priority.trim().parse().ok()?;
+            (0.0..=1.0)
+                .contains(&priority)
+                .then(|| (role.trim().to_string(), priority))
+        })
+        .collect()
+}
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    /// Cancellation requested; waiting for the pipeline to reach a checkpoint.
+    Cancelling,
+    Completed,
+    Cancelled,
+    /// The pipeline panicked; `error_id` keys the log entry.
+    Failed,
+}
+
+impl JobStatus {
+    fn finished(self) -> bool {
+        matches!(self, Self::Completed | Self::Cancelled | Self::Failed)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Tier {
+    Urgent,
+    High,
+    Normal,
+}
+
+impl Tier {
+    fn of(score: f64) -> Self {
+        if score >= URGENT_SCORE {
+            Self::Urgent
+        } else if score >= HIGH_SCORE {
+            Self::High
+        } else {
+            Self::Normal
+        }
+    }
+}
+
+/// What the submitter knows about the contract; both optional.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct Triage {
+    pub contract_value: Option<f64>,
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+/// Body of `POST /jobs`: an analysis request plus its triage metadata.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct JobRequest {
+    #[serde(flatten)]
+    analysis: AnalyzeRequest,
+    #[serde(default)]
+    triage: Triage,
+}
+
+/// The job's place in the queue and the parts it was scored from, each 0 to 1.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct JobPriority {
+    pub tier: Tier,
+    pub score: f64,
+    pub value: f64,
+    pub urgency: f64,
+    pub role: f64,
+}
+
+/// Scores a job. The role comes from the access role header, which an API
+/// key sets, so a caller cannot promote its own jobs.
+pub fn prioritize(
+    config: &JobConfig,
+    triage: &Triage,
+    role: Option<&str>,
+    now: DateTime<Utc>,
+) -> JobPriority {
+    let value = triage.contract_value.filter(|v| *v > 0.0).map_or(0.0, |v| {
+        ((v / VALUE_FLOOR).log10() / (VALUE_CEILING / VALUE_FLOOR).log10()).clamp(0.0, 1.0)
+    });
+    let urgency = triage.due_at.map_or(0.0, |due| {
+        let hours = (due - now).num_minutes() as f64 / 60.0;
+        (1.0 - (hours - URGENT_WITHIN_HOURS) / (RELAXED_AFTER_HOURS - URGENT_WITHIN_HOURS))
+            .clamp(0.0, 1.0)
+    });
+    let role = role
+        .and_then(|r| config.role_priority.get(r))
+        .copied()
+        .unwrap_or(0.0);
+    let score = VALUE_WEIGHT * value + URGENCY_WEIGHT * urgency + ROLE_WEIGHT * role;
+    JobPriority {
+        tier: Tier::of(score),
+        score,
+        value,
+        urgency,
+        role,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct JobRecord {
+    pub id: String,
+    pub status: JobStatus,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub priority: JobPriority,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_id: Option<String>,
+    /// True when the slot was taken back before the pipeline stopped.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub forced: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<AnalyzeResponse>,
+    /// Whatever stages finished before a cancelled job stopped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial_result: Option<AnalyzeResponse>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CancelQuery {
+    pub reason: Option<String>,
+}
+
+/// Checked by the pipeline between stages.
+#[derive(Debug, Default)]
+pub struct CancelFlag(AtomicBool);
+
+impl CancelFlag {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+struct Job {
+    record: JobRecord,
+    tenant: String,
+    cancel: Arc<CancelFlag>,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+/// A queued job's work, held until it is given a slot.
+struct Pending {
+    id: String,
+    score: f64,
+    seq: u64,
+    request: AnalyzeRequest,
+    context_chars: usize,
+    keywords: Option<Arc<KeywordMatcher>>,
+    tenant: String,
+}
+
+/// Queues per tier, urgent first, with each tier's round-robin credit.
+#[derive(Default)]
+struct Scheduler {
+    queues: [Vec<Pending>; 3],
+    credit: [i64; 3],
+    next_seq: u64,
+}
+
+impl Scheduler {
+    fn push(&mut self, tier: Tier, mut pending: Pending) {
+        pending.seq = self.next_seq;
+        self.next_seq += 1;
+        self.queues[tier as usize].push(pending);
+    }
+
+    /// Smooth weighted round-robin over the tiers with work: each gains its
+    /// weight, the richest is served and pays the total. Idle tiers bank
+    /// nothing, so a quiet tier cannot burst ahead when work arrives.
+    fn pop(&mut self, weights: &[u32; 3]) -> Option<Pending> {
+        let ready: Vec<usize> = (0..3).filter(|&t| !self.queues[t].is_empty()).collect();
+        for t in (0..3).filter(|t| !ready.contains(t)) {
+            self.credit[t] = 0;
+        }
+        let total: i64 = ready.iter().map(|&t| i64::from(weights[t])).sum();
+        for &t in &ready {
+            self.credit[t] += i64::from(weights[t]);
+        }
+        let tier = *ready
+            .iter()
+            .max_by_key(|&&t| (self.credit[t], std::cmp::Reverse(t)))?;
+        self.credit[tier] -= total;
+        let queue = &mut self.queues[tier];
+        let best = (0..queue.len()).max_by(|&a, &b| {
+            queue[a]
+                .score
+                .total_cmp(&queue[b].score)
+                .then(queue[b].seq.cmp(&queue[a].seq))
+        })?;
+        Some(queue.remove(best))
+    }
+
+    fn remove(&mut self, id: &str) {
+        for queue in &mut self.queues {
+            queue.retain(|p| p.id != id);
+        }
+    }
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+pub struct JobStore {
+    config: JobConfig,
+    slots: Arc<Semaphore>,
+    jobs: DashMap<String, Mutex<Job>>,
+    queue: Mutex<Scheduler>,
+}
+
+impl Default for JobStore {
+    fn default() -> Self {
+        Self::new(JobConfig::default())
+    }
+}
+
+impl JobStore {
+    pub fn new(config: JobConfig) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(config.workers)),
+            config,
+            jobs: DashMap::new(),
+            queue: Mutex::default(),
+        }
+    }
+
+    pub fn free_slots(&self) -> usize {
+        self.slots.available_permits()
+    }
+
+    pub fn workers(&self) -> usize {
+        self.config.workers
+    }
+
+    /// Jobs queued, running or still cancelling.
+    pub fn pending(&self) -> usize {
+        self.jobs
+            .iter()
+            .filter(|entry| {
+                !entry
+                    .lock()
+                    .expect("job lock poisoned")
+                    .record
+                    .status
+                    .finished()
+            })
+            .count()
+    }
+
+    /// Jobs waiting for a slot as `(id, tenant, tier, queued at)`, oldest
+    /// first.
+    pub fn queued(&self) -> Vec<(String, String, Tier, DateTime<Utc>)> {
+        let mut queued: Vec<_> = self
+            .jobs
+            .iter()
+            .filter_map(|entry| {
+                let job = entry.lock().expect("job lock poisoned");
+                (job.record.status == JobStatus::Queued).then(|| {
+                    (
+                        job.record.id.clone(),
+                        job.tenant.clone(),
+                        job.record.priority.tier,
+                        job.record.created_at,
+                    )
+                })
+            })
+            .collect();
+        queued.sort_by_key(|(_, _, _, at)| *at);
+        queued
+    }
+
+    fn with_job<T>(&self, id: &str, f: impl FnOnce(&mut Job) -> T) -> Option<T> {
+        let entry = self.jobs.get(id)?;
+        let mut job = entry.lock().expect("job lock poisoned");
+        Some(f(&mut job))
+    }
+
+    pub fn get(&self, id: &str) -> Option<JobRecord> {
+        self.with_job(id, |j| j.record.clone())
+    }
+
+    /// The tenant that submitted the job.
+    pub fn owner(&self, id: &str) -> Option<String> {
+        self.with_job(id, |j| j.tenant.clone())
+    }
+
+    pub fn create(
+        &self,
+        id: &str,
+        tenant: &str,
+        priority: JobPriority,
+        now: DateTime<Utc>,
+    ) -> JobRecord {
+        let record = JobRecord {
+            id: id.to_string(),
+            status: JobStatus::Queued,
+            created_at: now,
+            started_at: None,
+            finished_at: None,
+            priority,
+            cancel_reason: None,
+            error_id: None,
+            forced: false,
+            result: None,
+            partial_result: None,
+        };
+        let job = Job {
+            record: record.clone(),
+            tenant: tenant.to_string(),
+            cancel: Arc::new(CancelFlag::default()),
+            permit: None,
+        };
+        self.jobs.insert(id.to_string(), Mutex::new(job));
+        record
+    }
+
+    fn enqueue(&self, tier: Tier, pending: Pending) {
+        self.queue
+            .lock()
+            .expect("job queue lock poisoned")
+            .push(tier, pending);
+    }
+
+    fn next_queued(&self) -> Option<Pending> {
+        self.queue
+            .lock()
+            .expect("job queue lock poisoned")
+            .pop(&self.config.tier_weights)
+    }
+
+    /// Moves a queued job onto its slot. `None` if it was cancelled while
+    /// waiting, in which case the slot is handed straight back.
+    pub fn start(
+        &self,
+        id: &str,
+        permit: OwnedSemaphorePermit,
+        now: DateTime<Utc>,
+    ) -> Option<Arc<CancelFlag>> {
+        self.with_job(id, |job| {
+            if job.record.status != JobStatus::Queued {
+                return None;
+            }
+            job.record.status = JobStatus::Running;
+            job.record.started_at = Some(now);
+            job.permit = Some(permit);
+            Some(job.cancel.clone())
+        })
+        .flatten()
+    }
+
+    /// Records the pipeline's result and frees the slot. Results of jobs
+    /// whose slot was already taken back are dropped.
+    pub fn finish(&self, id: &str, response: AnalyzeResponse, now: DateTime<Utc>) {
+        self.with_job(id, |job| {
+            if job.record.status.finished() {
+                return;
+            }
+            job.permit = None;
+            job.record.finished_at = Some(now);
+            if job.cancel.is_cancelled() {
+                job.record.status = JobStatus::Cancelled;
+                job.record.partial_result = Some(response);
+            } else {
+                job.record.status = JobStatus::Completed;
+                job.record.result = Some(response);
+            }
+        });
+    }
+
+    /// Drops finished jobs whose result or partial result belongs to one of
+    /// `analysis_ids`; running jobs are left to finish. Returns the count.
+    pub fn remove_for(&self, analysis_ids: &[String]) -> usize {
+        let before = self.jobs.len();
+        self.jobs.retain(|_, job| {
+            let job = job.get_mut().expect("job lock poisoned");
+            !(job.record.status.finished()
+                && [&job.record.result, &job.record.partial_result]
+                    .into_iter()
+                    .flatten()
+                    .any(|r| analysis_ids.contains(&r.analysis_id)))
+        });
+        before - self.jobs.len()
+    }
+
+    /// Runs `f` over every job result along with the job's tenant.
+    pub fn for_each_result_mut(&self, mut f: impl FnMut(&str, &mut AnalyzeResponse)) {
+        for mut job in self.jobs.iter_mut() {
+            let job = job.value_mut().get_mut().expect("job lock poisoned");
+            let record = &mut job.record;
+            record
+                .result
+                .iter_mut()
+                .chain(record.partial_result.iter_mut())
+                .for_each(|r| f(&job.tenant, r));
+        }
+    }
+
+    /// Marks a job whose pipeline panicked and frees its slot.
+    pub fn fail(&self, id: &str, error_id: String, now: DateTime<Utc>) {
+        self.with_job(id, |job| {
+            if job.record.status.finished() {
+                return;
+            }
+            job.permit = None;
+            job.record.finished_at = Some(now);
+            job.record.status = JobStatus::Failed;
+            job.record.error_id = Some(error_id);
+        });
+    }
+
+    /// Flags the job. Queued jobs are cancelled on the spot; running ones
+    /// stop at their next checkpoint.
+    pub fn cancel(
+        &self,
+        id: &str,
+        reason: String,
+        now: DateTime<Utc>,
+    ) -> Result<JobRecord, StatusCode> {
+        self.with_job(id, |job| {
+            if job.record.status.finished() || job.record.status == JobStatus::Cancelling {
+                return Err(StatusCode::CONFLICT);
+            }
+            job.cancel.cancel();
+            job.record.cancel_reason = Some(reason);
+            if job.record.status == JobStatus::Queued {
+                job.record.status = JobStatus::Cancelled;
+                job.record.finished_at = Some(now);
+                self.queue
+                    .lock()
+                    .expect("job queue lock poisoned")
+                    .remove(id);
+            } else {
+                job.record.status = JobStatus::Cancelling;
+            }
+            Ok(job.record.clone())
+        })
+        .ok_or(StatusCode::NOT_FOUND)?
+    }
+
+    /// Ends a job still cancelling after the grace period and frees its slot.
+    pub fn force_release(&self, id: &str, now: DateTime<Utc>) -> bool {
+        self.with_job(id, |job| {
+            if job.record.status != JobStatus::Cancelling {
+                return false;
+            }
+            job.permit = None;
+            job.record.status = JobStatus::Cancelled;
+            job.record.forced = true;
+            job.record.finished_at = Some(now);
+            true
+        })
+        .unwrap_or(false)
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn submit(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(JobRequest {
+        analysis: req,
+        triage,
+    }): Json<JobRequest>,
+) -> Result<(StatusCode, Json<JobRecord>), StatusCode> {
+    if req.document.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    // A draining instance finishes its jobs; new ones go to another.
+    if state.lifecycle.is_draining() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    let context_chars = req.analysis_options.context_chars()?;
+    let tenant = notifier::tenant(&headers);
+    let keywords = state.lexicons.matcher(&tenant);
+    let id = format!("job-{}", state.ids.next_id());
+    let now = state.clock.now();
+    let role = headers.get(ROLE_HEADER).and_then(|v| v.to_str().ok());
+    let priority = prioritize(&state.jobs.config, &triage, role, now);
+    let tier = priority.tier;
+    let score = priority.score;
+    let record = state.jobs.create(&id, &tenant, priority, now);
+
+    info!(job_id = %id, tier = ?tier, score, free_slots = state.jobs.free_slots(), "analysis job queued");
+    state.jobs.enqueue(
+        tier,
+        Pending {
+            id,
+            score,
+            seq: 0,
+            request: req,
+            context_chars,
+            keywords,
+            tenant,
+        },
+    );
+    dispatch(&state);
+    Ok((StatusCode::ACCEPTED, Json(record)))
+}
+
+/// Hands free slots to queued jobs in scheduler order. Called whenever a
+/// job is queued or gives up its slot.
+fn dispatch(state: &AppState) {
+    loop {
+        let Ok(permit) = state.jobs.slots.clone().try_acquire_owned() else {
+            return;
+        };
+        let Some(next) = state.jobs.next_queued() else {
+            return;
+        };
+        // Cancelled while queued: the slot goes back and the loop moves on.
+        if let Some(cancel) = state.jobs.start(&next.id, permit, state.clock.now()) {
+            tokio::spawn(run(state.clone(), next, cancel));
+        }
+    }
+}
+
+async fn run(state: AppState, job: Pending, cancel: Arc<CancelFlag>) {
+    let Pending {
+        id: job_id,
+        request: req,
+        context_chars,
+        keywords,
+        tenant,
+        ..
+    } = job;
+    let deadline = Deadline::after(state.timeouts.analyze, state.timeouts.soft_margin);
+    let ml = ensemble::ml_findings(&state, &tenant, &req).await;
+    let worker = state.clone();
+    let running = job_id.clone();
+    let response = tokio::task::spawn_blocking(move || {
+        let opts = AnalysisOptions {
+            mode: req.mode,
+            deadline,
+            context_chars,
+            fallback_clauses: req.analysis_options.fallback_clauses,
+            cancel: Some(cancel.as_ref()),
+            latency: None,
+            keywords: keywords.as_deref(),
+            tenant: &tenant,
+            ml: ml.findings.as_ref(),
+            model_call: ml.call_id.as_deref(),
+            progress: None,
+            origin: Origin::Job(&running),
+            our_party: req.our_party.as_deref(),
+        };
+        isolation::contain(&worker, Site::Analysis, || {
+            run_analysis(&worker, &req.document, &req.language, opts)
+        })
+    })
+    .await;
+    match response {
+        Ok(Ok(response)) => {
+            state.jobs.finish(&job_id, response, state.clock.now());
+            if let (Some(record), Some(owner)) =
+                (state.jobs.get(&job_id), state.jobs.owner(&job_id))
+            {
+                webhooks::job_finished(&state, &owner, &record);
+            }
+        }
+        Ok(Err(panicked)) => {
+            state
+                .jobs
+                .fail(&job_id, panicked.error_id, state.clock.now());
+        }
+        Err(e) => warn!(job_id = %job_id, error = %e, "analysis job worker failed"),
+    }
+    dispatch(&state);
+}
+
+/// Another tenant's job reads as unknown.
+fn check_owner(state: &AppState, headers: &HeaderMap, id: &str) -> Result<(), StatusCode> {
+    match state.jobs.owner(id) {
+        Some(owner) if owner == notifier::tenant(headers) => Ok(()),
+        _ => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+pub async fn get_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<JobRecord>, StatusCode> {
+    check_owner(&state, &headers, &id)?;
+    state.jobs.get(&id).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+pub async fn cancel_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Query(q): Query<CancelQuery>,
+) -> Result<(StatusCode, Json<JobRecord>), StatusCode> {
+    check_owner(&state, &headers, &id)?;
+    let reason = q
+        .reason
+        .unwrap_or_else(|| "cancelled by client".to_string());
+    let record = state.jobs.cancel(&id, reason, state.clock.now())?;
+    info!(job_id = %id, status = ?record.status, "analysis job cancellation requested");
+
+    if record.status == JobStatus::Cancelling {
+        let grace = state.jobs.config.cancel_grace;
+        tokio::spawn(async move {
+            tokio::time::sleep(grace).await;
+            if state.jobs.force_release(&id, state.clock.now()) {
+                warn!(job_id = %id, "cancelled job missed its grace period; slot released");
+                dispatch(&state);
+            }
+        });
+    }
+    Ok((StatusCode::ACCEPTED, Json(record)))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn priority(tier: Tier) -> JobPriority {
+        JobPriority {
+            tier,
+            score: 0.0,
+            value: 0.0,
+            urgency: 0.0,
+            role: 0.0,
+        }
+    }
+
+    fn store(workers: usize) -> JobStore {
+        JobStore::new(JobConfig {
+            workers,
+            ..JobConfig::default()
+        })
+    }
+
+    #[test]
+    fn queued_job_is_cancelled_immediately_and_never_starts() {
+        let jobs = store(1);
+        let now = Utc::now();
+        jobs.create("j1", "acme", priority(Tier::Normal), now);
+        let record = jobs.cancel("j1", "superseded".to_string(), now).unwrap();
+        assert_eq!(record.status, JobStatus::Cancelled);
+        assert_eq!(record.cancel_reason.as_deref(), Some("superseded"));
+
+        let permit = jobs.slots.clone().try_acquire_owned().unwrap();
+        assert!(jobs.start("j1", permit, now).is_none());
+        assert_eq!(jobs.free_slots(), 1);
+        assert_eq!(
+            jobs.cancel("j1", String::new(), now).unwrap_err(),
+            StatusCode::CONFLICT
+        );
+    }
+
+    #[test]
+    fn running_job_keeps_its_slot_until_released() {
+        let jobs = store(1);
+        let now = Utc::now();
+        jobs.create("j1", "acme", priority(Tier::Normal), now);
+        let permit = jobs.slots.clone().try_acquire_owned().unwrap();
+        let flag = jobs.start("j1", permit, now).unwrap();
+        assert_eq!(jobs.free_slots(), 0);
+
+        let record = jobs.cancel("j1", "too slow".to_string(), now).unwrap();
+        assert_eq!(record.status, JobStatus::Cancelling);
+        assert!(flag.is_cancelled());
+        assert_eq!(jobs.free_slots(), 0);
+
+        assert!(jobs.force_release("j1", now));
+        assert_eq!(jobs.free_slots(), 1);
+        let record = jobs.get("j1").unwrap();
+        assert_eq!(record.status, JobStatus::Cancelled);
+        assert!(record.forced);
+    }
+
+    #[test]
+    fn failed_job_frees_its_slot_and_keeps_the_error_id() {
+        let jobs = store(1);
+        let now = Utc::now();
+        jobs.create("j1", "acme", priority(Tier::Normal), now);
+        let permit = jobs.slots.clone().try_acquire_owned().unwrap();
+        jobs.start("j1", permit, now).unwrap();
+
+        jobs.fail("j1", "err-7".to_string(), now);
+        assert_eq!(jobs.free_slots(), 1);
+        let record = jobs.get("j1").unwrap();
+        assert_eq!(record.status, JobStatus::Failed);
+        assert_eq!(record.error_id.as_deref(), Some("err-7"));
+        assert_eq!(
+            jobs.cancel("j1", String::new(), now).unwrap_err(),
+            StatusCode::CONFLICT
+        );
+    }
+
+    fn pending(id: &str, score: f64) -> Pending {
+        Pending {
+            id: id.to_string(),
+            score,
+            seq: 0,
+            request: serde_json::from_value(serde_json::json!({
+                "document": "x",
+                "language": "en",
+            }))
+            .unwrap(),
+            context_chars: 0,
+            keywords: None,
+            tenant: "acme".to_string(),
+        }
+    }
+
+    #[test]
+    fn big_contract_due_tomorrow_is_urgent_and_bare_jobs_are_normal() {
+        let config = JobConfig::default();
+        let now = Utc::now();
+        let msa = Triage {
+            contract_value: Some(5_000_000.0),
+            due_at: Some(now + chrono::Duration::hours(20)),
+        };
+        let p = prioritize(&config, &msa, None, now);
+        assert_eq!(p.tier, Tier::Urgent);
+        assert!((p.value - 0.9).abs() < 0.01, "{}", p.value);
+        assert_eq!(p.urgency, 1.0);
+
+        let far = Triage {
+            contract_value: Some(5_000_000.0),
+            due_at: Some(now + chrono::Duration::days(30)),
+        };
+        assert_eq!(prioritize(&config, &far, None, now).tier, Tier::High);
+        let nda = prioritize(&config, &Triage::default(), Some("sales"), now);
+        assert_eq!(nda.tier, Tier::Normal);
+        assert_eq!(nda.role, 0.3);
+        assert_eq!(
+            prioritize(&config, &Triage::default(), Some("intern"), now).score,
+            0.0
+        );
+    }
+
+    #[test]
+    fn scheduler_shares_slots_by_tier_weight_without_starving() {
+        let mut queue = Scheduler::default();
+        for i in 0..7 {
+            queue.push(Tier::Urgent, pending(&format!("u{i}"), 0.7));
+            queue.push(Tier::High, pending(&format!("h{i}"), 0.4));
+            queue.push(Tier::Normal, pending(&format!("n{i}"), 0.0));
+        }
+        let order: String = (0..7)
+            .map(|_| queue.pop(&[4, 2, 1]).unwrap().id[..1].to_string())
+            .collect();
+        assert_eq!(order.matches('u').count(), 4);
+        assert_eq!(order.matches('h').count(), 2);
+        assert_eq!(order.matches('n').count(), 1);
+        assert!(order.starts_with('u'));
+    }
+
+    #[test]
+    fn scheduler_serves_highest_score_then_oldest_within_a_tier() {
+        let mut queue = Scheduler::default();
+        queue.push(Tier::High, pending("first", 0.4));
+        queue.push(Tier::High, pending("bigger", 0.5));
+        queue.push(Tier::High, pending("second", 0.4));
+        queue.remove("second");
+        let ids: Vec<String> = std::iter::from_fn(|| queue.pop(&[4, 2, 1]))
+            .map(|p| p.id)
+            .collect();
+        assert_eq!(ids, ["bigger", "first"]);
+    }
+
+    #[test]
+    fn tier_weights_and_role_priorities_parse_strictly() {
+        assert_eq!(parse_weights("8, 3, 1"), Some([8, 3, 1]));
+        assert_eq!(parse_weights("4,2"), None);
+        assert_eq!(parse_weights("4,0,1"), None);
+        let roles = parse_roles("legal=1, sales=0.2").unwrap();
+        assert_eq!(roles["sales"], 0.2);
+        assert!(parse_roles("legal=2").is_none());
+        assert!(parse_roles("legal").is_none());
+    }
+}
@@ -0,0 +1,414 @@
+//! Compile-time jurisdiction rules: documents naming a sanctioned
+//! jurisdiction are not generated at all, and jurisdictions that need
+//! Standard Contractual Clauses for personal-data transfers produce warnings.
+
+use std::{collections::HashMap, sync::RwLock};
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tracing::{info, warn};
+
+use crate::{
+    compile_parsed, compile_template_with, entity,
+    export_control::find_words,
+    provenance::Origin,
+    style,
+    warmup::{Fragments, ParsedTemplate},
+    AppState, CompileExtras, CompileResponse,
+};
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleKind {
+    /// Compilation fails.
+    Blocked,
+    /// Compilation succeeds with a warning that SCCs must be attached.
+    RequiresScc,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JurisdictionRule {
+    pub country: String,
+    pub aliases: Vec<String>,
+    pub kind: RuleKind,
+    pub reference: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JurisdictionPolicy {
+    /// Template variables whose values are checked.
+    pub variables: Vec<String>,
+    pub rules: Vec<JurisdictionRule>,
+}
+
+impl JurisdictionPolicy {
+    /// Every rule names a country and at least one alias.
+    pub fn is_valid(&self) -> bool {
+        self.rules
+            .iter()
+            .all(|r| !r.country.trim().is_empty() && r.aliases.iter().any(|a| !a.trim().is_empty()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PolicyViolation {
+    pub variable: String,
+    pub value: String,
+    pub country: String,
+    pub kind: RuleKind,
+    pub reference: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PolicyRejection {
+    pub template_id: String,
+    pub violations: Vec<PolicyViolation>,
+}
+
+// ── Policy ────────────────────────────────────────────────────────────────────
+
+fn rule(country: &str, aliases: &[&str], kind: RuleKind, reference: &str) -> JurisdictionRule {
+    JurisdictionRule {
+        country: country.to_string(),
+        aliases: aliases.iter().map(|a| a.to_string()).collect(),
+        kind,
+        reference: Some(reference.to_string()),
+    }
+}
+
+impl Default for JurisdictionPolicy {
+    fn default() -> Self {
+        use RuleKind::{Blocked, RequiresScc};
+        const SCC: &str = "GDPR Art. 46(2)(c); Commission Implementing Decision (EU) 2021/914";
+        Self {
+            variables: vec!["jurisdiction".to_string(), "governing_law".to_string()],
+            rules: vec![
+                rule("Cuba", &["cuba"], Blocked, "31 CFR Part 515"),
+                rule(
+                    "Iran",
+                    &["iran", "islamic republic of iran"],
+                    Blocked,
+                    "31 CFR Part 560",
+                ),
+                rule(
+                    "North Korea",
+                    &[
+                        "north korea",
+                        "dprk",
+                        "democratic people's republic of korea",
+                    ],
+                    Blocked,
+                    "31 CFR Part 510",
+                ),
+                rule(
+                    "Syria",
+                    &["syria", "syrian arab republic"],
+                    Blocked,
+                    "31 CFR Part 542",
+                ),
+                rule(
+                    "Crimea / occupied regions of Ukraine",
+                    &["crimea", "sevastopol", "donetsk", "luhansk"],
+                    Blocked,
+                    "31 CFR Part 589; EO 14065",
+                ),
+                rule(
+                    "Russia",
+                    &["russia", "russian federation"],
+                    RequiresScc,
+                    SCC,
+                ),
+                rule(
+                    "China",
+                    &["china", "people's republic of china", "prc"],
+                    RequiresScc,
+                    SCC,
+                ),
+                rule("India", &["india"], RequiresScc, SCC),
+                rule("Brazil", &["brazil"], RequiresScc, SCC),
+                rule(
+                    "United States",
+                    &["united states", "usa"],
+                    RequiresScc,
+                    "GDPR Art. 46(2)(c) unless the importer is certified under the EU-US Data Privacy Framework",
+                ),
+            ],
+        }
+    }
+}
+
+/// The active policy; replaced wholesale through the policy endpoint.
+#[derive(Default)]
+pub struct JurisdictionPolicyStore {
+    policy: RwLock<JurisdictionPolicy>,
+}
+
+impl JurisdictionPolicyStore {
+    pub fn get(&self) -> JurisdictionPolicy {
+        self.policy
+            .read()
+            .expect("jurisdiction policy lock poisoned")
+            .clone()
+    }
+
+    pub fn replace(&self, policy: JurisdictionPolicy) {
+        *self
+            .policy
+            .write()
+            .expect("jurisdiction policy lock poisoned") = policy;
+    }
+}
+
+// ── Checks ────────────────────────────────────────────────────────────────────
+
+/// Every rule matched by a checked variable, in variable order.
+pub fn check(
+    policy: &JurisdictionPolicy,
+    variables: &HashMap<String, String>,
+) -> Vec<PolicyViolation> {
+    let mut out = Vec::new();
+    for name in &policy.variables {
+        let Some(value) = variables.get(name) else {
+            continue;
+        };
+        let lower = value.to_ascii_lowercase();
+        for rule in &policy.rules {
+            let hit = rule
+                .aliases
+                .iter()
+                .any(|a| !find_words(&lower, &a.to_ascii_lowercase()).is_empty());
+            if !hit {
+                continue;
+            }
+            let message = match rule.kind {
+                RuleKind::Blocked => format!(
+                    "{name} \"{value}\" names {}, a sanctioned jurisdiction.",
+                    rule.country
+                ),
+                RuleKind::RequiresScc => format!(
+                    "{name} \"{value}\" names {}; personal-data transfers there need Standard Contractual Clauses.",
+                    rule.country
+                ),
+            };
+            out.push(PolicyViolation {
+                variable: name.clone(),
+                value: value.clone(),
+                country: rule.country.clone(),
+                kind: rule.kind,
+                reference: rule.reference.clone(),
+                message,
+            });
+        }
+    }
+    out
+}
+
+/// Compiles unless a blocked jurisdiction is named (`422` with the violations);
+/// SCC rules come back as `policy_warnings`, party-name problems as
+/// `entity_warnings`.
+pub fn compile_checked(
+    state: &AppState,
+    tenant: &str,
+    template_id: &str,
+    template_version: Option<u32>,
+    variables: &HashMap<String, String>,
+    data: &Map<String, Value>,
+    extras: CompileExtras,
+) -> Result<Response, StatusCode> {
+    Ok(
+        match compile_enforced(
+            state,
+            tenant,
+            template_id,
+            template_version,
+            variables,
+            data,
+            extras,
+        )? {
+            Ok(compiled) => Json(compiled).into_response(),
+            Err(rejection) => (StatusCode::UNPROCESSABLE_ENTITY, Json(rejection)).into_response(),
+        },
+    )
+}
+
+/// `compile_checked` for callers that render the document themselves: the
+/// compiled document in `tenant`'s drafting style, or the violations that
+/// blocked it. A `template_version` pins a custom template to that saved
+/// version; built-ins have none.
+pub fn compile_enforced(
+    state: &AppState,
+    tenant: &str,
+    template_id: &str,
+    template_version: Option<u32>,
+    variables: &HashMap<String, String>,
+    data: &Map<String, Value>,
+    extras: CompileExtras,
+) -> Result<Result<CompileResponse, PolicyRejection>, StatusCode> {
+    let mut custom = state
+        .custom_templates
+        .resolve(&state.orgs.lineage(tenant), template_id);
+    if let Some(version) = template_version {
+        let current = custom.ok_or(StatusCode::NOT_FOUND)?;
+        custom = Some(
+            state
+                .custom_templates
+                .pinned(&current, version)
+                .ok_or(StatusCode::NOT_FOUND)?,
+        );
+    }
+    let mut compiled = match &custom {
+        Some(custom) => {
+            let (embedded, clauses) = state
+                .clause_library
+                .resolve_all(custom.parsed.clauses())
+                .map_err(|e| {
+                    info!(template_id = %template_id, error = %e, "template clause unresolved");
+                    StatusCode::UNPROCESSABLE_ENTITY
+                })?;
+            let fragments = Fragments {
+                macros: state.macros.bodies(),
+                clauses,
+            };
+            let origin = Origin::custom(template_id, custom, state.macros.versions(), embedded);
+            compile_parsed(
+                &origin,
+                &custom.parsed,
+                custom.template.required_variables.clone(),
+                variables,
+                data,
+                &fragments,
+                extras,
+            )
+        }
+        None => compile_template_with(&state.precompiled, template_id, variables, data, extras),
+    }?;
+    let violations = check(&state.jurisdiction_policy.get(), variables);
+    if violations.iter().any(|v| v.kind == RuleKind::Blocked) {
+        let countries: Vec<&str> = violations
+            .iter()
+            .filter(|v| v.kind == RuleKind::Blocked)
+            .map(|v| v.country.as_str())
+            .collect();
+        info!(
+            template_id,
+            ?countries,
+            "compile blocked by jurisdiction policy"
+        );
+        state.record_audit(
+            "compile.blocked",
+            template_id,
+            None,
+            json!({ "countries": countries }),
+        );
+        return Ok(Err(PolicyRejection {
+            template_id: template_id.to_string(),
+            violations,
+        }));
+    }
+    compiled.policy_warnings = violations;
+    compiled.entity_warnings = entity::check_variables(variables);
+    if let Some(profile) = state.styles.get(tenant) {
+        let (text, changes) = style::apply(&profile, &compiled.compiled_document);
+        compiled.compiled_document = text;
+        for a in compiled.annotations.iter_mut().flatten() {
+            a.start = style::remap(&changes, a.start);
+            a.end = style::remap(&changes, a.end);
+        }
+        if let Some(provenance) = &mut compiled.provenance {
+            provenance
+                .restyle(&changes, &compiled.compiled_document)
+                .map_err(|e| {
+                    warn!(template_id = %template_id, error = %e, "provenance restyle failed");
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+        }
+    }
+    let conditions = match &custom {
+        Some(custom) => custom.parsed.conditions(),
+        None => state
+            .precompiled
+            .template(template_id)
+            .map(ParsedTemplate::conditions)
+            .unwrap_or_default(),
+    };
+    state.template_analytics.record_compile(
+        tenant,
+        template_id,
+        &conditions,
+        variables,
+        data,
+        &compiled,
+        state.clock.now(),
+    );
+    Ok(Ok(compiled))
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn get_policy(State(state): State<AppState>) -> Json<JurisdictionPolicy> {
+    Json(state.jurisdiction_policy.get())
+}
+
+pub async fn put_policy(
+    State(state): State<AppState>,
+    Json(policy): Json<JurisdictionPolicy>,
+) -> Result<Json<JurisdictionPolicy>, StatusCode> {
+    if !policy.is_valid() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    info!(
+        rules = policy.rules.len(),
+        variables = policy.variables.len(),
+        "jurisdiction policy replaced"
+    );
+    state.jurisdiction_policy.replace(policy.clone());
+    Ok(Json(policy))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn sanctioned_and_scc_jurisdictions_are_classified() {
+        let policy = JurisdictionPolicy::default();
+        let found = check(&policy, &vars(&[("governing_law", "the laws of Iran")]));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, RuleKind::Blocked);
+        assert_eq!(found[0].reference.as_deref(), Some("31 CFR Part 560"));
+
+        let found = check(
+            &policy,
+            &vars(&[("jurisdiction", "People's Republic of China")]),
+        );
+        assert_eq!(found[0].kind, RuleKind::RequiresScc);
+        assert_eq!(found[0].country, "China");
+    }
+
+    #[test]
+    fn only_configured_variables_are_checked() {
+        let policy = JurisdictionPolicy::default();
+        assert!(check(&policy, &vars(&[("party_b", "Havana Cigars, Cuba")])).is_empty());
+        assert!(check(&policy, &vars(&[("jurisdiction", "Japan")])).is_empty());
+        // Word boundaries: "Indiana" is not India.
+        assert!(check(&policy, &vars(&[("jurisdiction", "Indiana")])).is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+//! Language detection for submitted documents. The request's `language` is
+//! what the caller believes; the text is checked against it, and a reliable
+//! detection that disagrees wins, so the clause rules for the language the
+//! contract is actually written in are the ones that run.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Detection reads at most this many bytes; the opening of a contract is
+/// as telling as the whole of it.
+const SAMPLE_BYTES: usize = 16 * 1024;
+
+/// Used when nothing was declared and nothing could be detected.
+const FALLBACK: &str = "en";
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct LanguageDetection {
+    /// The language the request named, if it named one.
+    pub declared: Option<String>,
+    /// ISO 639-1 code where one exists, otherwise ISO 639-3.
+    pub detected: Option<String>,
+    /// The detector's confidence, 0 to 1.
+    pub confidence: f64,
+    /// Whether the detection was confident enough to act on.
+    pub reliable: bool,
+    /// Set when a reliable detection disagrees with the declared language.
+    pub mismatch: bool,
+}
+
+impl LanguageDetection {
+    /// The language to analyze the document as.
+    pub fn effective(&self) -> &str {
+        match (&self.declared, &self.detected) {
+            (_, Some(detected)) if self.mismatch => detected,
+            (Some(declared), _) => declared,
+            (None, Some(detected)) => detected,
+            (None, None) => FALLBACK,
+        }
+    }
+}
+
+/// Maps whatlang's ISO 639-3 codes to the two-letter codes used everywhere
+/// else in the API.
+fn code(lang: whatlang::Lang) -> String {
+    let code = lang.code();
+    match code {
+        "eng" => "en",
+        "jpn" => "ja",
+        "deu" => "de",
+        "fra" => "fr",
+        "spa" => "es",
+        "ita" => "it",
+        "por" => "pt",
+        "nld" => "nl",
+        "cmn" => "zh",
+        "kor" => "ko",
+        "rus" => "ru",
+        other => other,
+    }
+    .to_string()
+}
+
+fn sample(document: &str) -> &str {
+    if document.len() <= SAMPLE_BYTES {
+        return document;
+    }
+    let mut end = SAMPLE_BYTES;
+    while !document.is_char_boundary(end) {
+        end -= 1;
+    }
+    &document[..end]
+}
+
+/// Checks `document` against the `declared` language; empty means the
+/// caller left it to detection.
+pub fn detect(document: &str, declared: &str) -> LanguageDetection {
+    let declared = declared.trim().to_ascii_lowercase();
+    let declared = (!declared.is_empty()).then_some(declared);
+    let info = whatlang::detect(sample(document));
+    let detected = info.as_ref().map(|i| code(i.lang()));
+    let reliable = info.as_ref().is_some_and(whatlang::Info::is_reliable);
+    let mismatch =
+        reliable && matches!((&declared, &detected), (Some(d), Some(found)) if d != found);
+    LanguageDetection {
+        declared,
+        detected,
+        confidence: info.map_or(0.0, |i| (i.confidence() * 1000.0).round() / 1000.0),
+        reliable,
+        mismatch,
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GERMAN: &str = "Die Haftung des Auftragnehmers ist auf den Auftragswert \
+        beschränkt. Dieser Vertrag unterliegt deutschem Recht und kann von jeder \
+        Partei mit einer Frist von drei Monaten gekündigt werden.";
+    const ENGLISH: &str = "The liability of the supplier is limited to the fees \
+        paid under this agreement. This agreement is governed by the laws of \
+        England and may be terminated by either party on three months notice.";
+
+    #[test]
+    fn a_reliable_detection_overrides_a_wrong_declaration() {
+        let found = detect(GERMAN, "en");
+        assert_eq!(found.declared.as_deref(), Some("en"));
+        assert_eq!(found.detected.as_deref(), Some("de"));
+        assert!(found.reliable && found.mismatch);
+        assert!(found.confidence > 0.0 && found.confidence <= 1.0);
+        assert_eq!(found.effective(), "de");
+    }
+
+    #[test]
+    fn agreeing_or_missing_declarations_are_settled_without_a_mismatch() {
+        let found = detect(ENGLISH, "EN");
+        assert_eq!(found.declared.as_deref(), Some("en"));
+        assert!(!found.mismatch);
+        assert_eq!(found.effective(), "en");
+
+        let inferred = detect(GERMAN, " ");
+        assert_eq!(inferred.declared, None);
+        assert!(!inferred.mismatch);
+        assert_eq!(inferred.effective(), "de");
+    }
+
+    #[test]
+    fn undetectable_text_keeps_the_declared_language() {
+        let found = detect("12 34 56", "fr");
+        assert!(!found.reliable && !found.mismatch);
+        assert_eq!(found.effective(), "fr");
+        assert_eq!(detect("", "").effective(), FALLBACK);
+    }
+
+    #[test]
+    fn long_documents_are_sampled_on_a_char_boundary() {
+        let text = "ä".repeat(SAMPLE_BYTES);
+        let cut = sample(&text);
+        assert!(cut.len() <= SAMPLE_BYTES && cut.len() > SAMPLE_BYTES - 4);
+    }
+}
@@ -0,0 +1,276 @@
+//! Tenant dictionaries: synonym groups ("hold harmless" ≈ "indemnify") that
+//! widen every pattern one of their terms contains, and stopwords skipped
+//! on both sides of a match, so "governed exclusively by" still reads as
+//! "governed by". Applied to the analysis keyword matcher and to the
+//! regulatory scan; match offsets always refer to the original text.
+
+use std::{collections::BTreeSet, sync::Arc};
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{notifier, warmup::KeywordMatcher, AppState};
+
+/// Upper bounds that keep a tenant's automaton small.
+const MAX_GROUPS: usize = 200;
+const MAX_TERMS: usize = 2_000;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Lexicon {
+    #[serde(default)]
+    pub synonyms: Vec<Vec<String>>,
+    #[serde(default)]
+    pub stopwords: Vec<String>,
+}
+
+/// `text` with stopwords dropped and whitespace collapsed to single spaces;
+/// `origin[i]` is the offset in the original text of byte `i`.
+#[derive(Debug)]
+pub struct TextView {
+    pub text: String,
+    origin: Vec<usize>,
+}
+
+impl TextView {
+    /// The original span behind `start..end` of the view.
+    pub fn span(&self, start: usize, end: usize) -> (usize, usize) {
+        (self.origin[start], self.origin[end - 1] + 1)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LexiconResponse {
+    pub tenant: String,
+    #[serde(flatten)]
+    pub lexicon: Lexicon,
+}
+
+// ── Matching ──────────────────────────────────────────────────────────────────
+
+fn bare(token: &str) -> String {
+    token
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+impl Lexicon {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.synonyms.len() > MAX_GROUPS {
+            return Err(format!("at most {MAX_GROUPS} synonym groups"));
+        }
+        let terms = self.synonyms.iter().map(Vec::len).sum::<usize>() + self.stopwords.len();
+        if terms > MAX_TERMS {
+            return Err(format!("at most {MAX_TERMS} terms in total"));
+        }
+        for (i, group) in self.synonyms.iter().enumerate() {
+            if group.len() < 2 {
+                return Err(format!("synonym group {i} needs at least two terms"));
+            }
+            if group.iter().any(|t| self.normalize(t).is_empty()) {
+                return Err(format!(
+                    "synonym group {i} has a term that is empty without stopwords"
+                ));
+            }
+        }
+        if let Some(bad) = self.stopwords.iter().find(|s| {
+            let mut words = s.split_whitespace();
+            words.next().is_none() || words.next().is_some()
+        }) {
+            return Err(format!("stopword {bad:?} must be a single word"));
+        }
+        Ok(())
+    }
+
+    fn stopwords(&self) -> BTreeSet<String> {
+        self.stopwords.iter().map(|s| bare(s)).collect()
+    }
+
+    /// `phrase` ASCII-lowercased, without stopwords, on single spaces.
+    pub fn normalize(&self, phrase: &str) -> String {
+        let stop = self.stopwords();
+        phrase
+            .split_whitespace()
+            .filter(|t| !stop.contains(&bare(t)))
+            .map(str::to_ascii_lowercase)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// `pattern` and the terms of every synonym group with a term that
+    /// contains it, normalized and without repeats.
+    pub fn expand(&self, pattern: &str) -> Vec<String> {
+        let needle = self.normalize(pattern);
+        let mut out = vec![needle.clone()];
+        for group in &self.synonyms {
+            let terms: Vec<String> = group.iter().map(|t| self.normalize(t)).collect();
+            if terms.iter().any(|t| t.contains(&needle)) {
+                out.extend(terms);
+            }
+        }
+        let mut seen = BTreeSet::new();
+        out.retain(|t| !t.is_empty() && seen.insert(t.clone()));
+        out
+    }
+
+    pub fn view(&self, text: &str) -> TextView {
+        let stop = self.stopwords();
+        let mut view = String::with_capacity(text.len());
+        let mut origin = Vec::with_capacity(text.len());
+        for token in text.split_whitespace() {
+            if stop.contains(&bare(token)) {
+                continue;
+            }
+            // Offset of the token within `text`.
+            let at = token.as_ptr() as usize - text.as_ptr() as usize;
+            if !view.is_empty() {
+                view.push(' ');
+                origin.push(at);
+            }
+            view.push_str(token);
+            origin.extend(at..at + token.len());
+        }
+        TextView { text: view, origin }
+    }
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+/// Each tenant's keyword matcher, rebuilt whenever its lexicon changes.
+#[derive(Default)]
+pub struct LexiconStore {
+    tenants: DashMap<String, Arc<KeywordMatcher>>,
+}
+
+impl LexiconStore {
+    pub fn matcher(&self, tenant: &str) -> Option<Arc<KeywordMatcher>> {
+        self.tenants.get(tenant).map(|m| Arc::clone(m.value()))
+    }
+
+    pub fn put(&self, tenant: &str, lexicon: Lexicon) -> Result<(), String> {
+        lexicon.validate()?;
+        let matcher = KeywordMatcher::with_lexicon(lexicon)?;
+        self.tenants.insert(tenant.to_string(), Arc::new(matcher));
+        Ok(())
+    }
+
+    pub fn remove(&self, tenant: &str) -> bool {
+        self.tenants.remove(tenant).is_some()
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn get_lexicon(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<LexiconResponse>, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    let matcher = state
+        .lexicons
+        .matcher(&tenant)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(LexiconResponse {
+        tenant,
+        lexicon: matcher.lexicon().cloned().unwrap_or_default(),
+    }))
+}
+
+pub async fn put_lexicon(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(lexicon): Json<Lexicon>,
+) -> Result<Json<LexiconResponse>, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    if let Err(e) = state.lexicons.put(&tenant, lexicon.clone()) {
+        info!(tenant = %tenant, error = %e, "lexicon rejected");
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    info!(
+        tenant = %tenant,
+        synonym_groups = lexicon.synonyms.len(),
+        stopwords = lexicon.stopwords.len(),
+        "lexicon saved"
+    );
+    state.record_audit(
+        "lexicon.updated",
+        &tenant,
+        None,
+        serde_json::json!({
+            "synonym_groups": lexicon.synonyms.len(),
+            "stopwords": lexicon.stopwords.len(),
+        }),
+    );
+    Ok(Json(LexiconResponse { tenant, lexicon }))
+}
+
+pub async fn delete_lexicon(State(state): State<AppState>, headers: HeaderMap) -> StatusCode {
+    let tenant = notifier::tenant(&headers);
+    if state.lexicons.remove(&tenant) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lexicon() -> Lexicon {
+        Lexicon {
+            synonyms: vec![
+                vec!["hold harmless".to_string(), "indemnify".to_string()],
+                vec!["MSA".to_string(), "master services agreement".to_string()],
+            ],
+            stopwords: vec!["exclusively".to_string(), "the".to_string()],
+        }
+    }
+
+    #[test]
+    fn patterns_expand_to_groups_that_contain_them() {
+        let lex = lexicon();
+        assert_eq!(
+            lex.expand("indemnif"),
+            ["indemnif", "hold harmless", "indemnify"]
+        );
+        assert_eq!(lex.expand("MSA"), ["msa", "master services agreement"]);
+        assert_eq!(lex.expand("retention"), ["retention"]);
+    }
+
+    #[test]
+    fn view_skips_stopwords_and_maps_back_to_original_offsets() {
+        let lex = lexicon();
+        let text = "Governed  exclusively by the laws";
+        let view = lex.view(text);
+        assert_eq!(view.text, "Governed by laws");
+        let start = view.text.find("by laws").unwrap();
+        let (s, e) = view.span(start, start + "by laws".len());
+        assert_eq!(&text[s..e], "by the laws");
+    }
+
+    #[test]
+    fn invalid_lexicons_are_rejected() {
+        let mut lex = lexicon();
+        lex.synonyms.push(vec!["alone".to_string()]);
+        assert!(lex.validate().is_err());
+
+        let mut lex = lexicon();
+        lex.stopwords.push("two words".to_string());
+        assert!(lex.validate().is_err());
+
+        let mut lex = lexicon();
+        lex.synonyms.push(vec!["the".to_string(), "x".to_string()]);
+        assert!(lex.validate().is_err());
+        assert!(lexicon().validate().is_ok());
+    }
+}
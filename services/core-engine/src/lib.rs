@@ -0,0 +1,2197 @@
+//! The ALICE legal engine as a library: contract analysis, risk scoring and
+//! template compilation over an [`AppState`], and the HTTP routers that
+//! serve them. The `alice-legal-server` binary is a thin wrapper that reads
+//! the environment and binds the listeners; embedders build a state, call
+//! [`AppState::start`] inside their Tokio runtime, and either call the
+//! engine directly or mount [`build_router`] in their own service.
+//!
+//! ```no_run
+//! # async fn embed() {
+//! let engine = alice_legal_core::AppState::in_memory();
+//! engine.start().expect("engine failed to start");
+//! let analysis = engine.analyze_document("acme", "This Agreement is governed by ...", "en");
+//! let risk = engine.score_risk("This Agreement is governed by ...");
+//! println!("{} / {}", analysis.risk_score, risk.risk_level);
+//! # }
+//! ```
+
+mod access;
+mod annotations;
+mod audit;
+mod auth;
+mod authority;
+mod backtest;
+mod batch;
+mod calibration;
+mod chunking;
+mod clause_library;
+mod clm;
+mod clock;
+mod compare;
+mod compile_cache;
+mod compliance;
+mod conflicts;
+mod content_repo;
+mod corpus;
+mod custom_templates;
+mod deadline;
+mod diligence;
+mod email;
+mod ensemble;
+mod entities;
+mod entity;
+mod erasure;
+mod escalation;
+mod evidence;
+mod explain;
+mod export_control;
+mod extraction;
+mod fallback;
+mod favorability;
+mod grpc;
+mod history;
+mod family_risk;
+mod html_export;
+#[cfg(test)]
+mod http_tests;
+mod includes;
+mod inflight;
+mod ingest;
+mod isolation;
+mod jobs;
+mod jurisdiction_policy;
+mod language;
+mod lexicon;
+mod lifecycle;
+mod macros;
+mod migration;
+mod model_calls;
+mod models;
+mod notices;
+mod notifier;
+mod obligations;
+mod openapi;
+mod orgs;
+mod packing;
+mod paper;
+mod playbook;
+mod patterns;
+mod preview;
+mod provenance;
+mod quota;
+mod ratelimit;
+mod regulatory;
+mod render;
+mod renewal;
+mod rescore;
+mod revisions;
+mod reviews;
+mod risk_model;
+mod share;
+mod signature;
+mod spill;
+mod style;
+mod splitting;
+mod stream;
+mod subcontracting;
+mod summary;
+mod taxonomy;
+mod template_analytics;
+mod timings;
+mod training_export;
+mod truncation;
+mod variable_inference;
+mod warmup;
+mod webhooks;
+mod wizard;
+mod workbook;
+
+use axum::{
+    extract::{DefaultBodyLimit, State},
+    http::{HeaderMap, StatusCode},
+    middleware,
+    response::{IntoResponse, Json, Response},
+    routing::{delete, get, patch, post, put},
+    Extension, Router,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+use access::AccessPolicy;
+use audit::AuditLog;
+use auth::AuthConfig;
+use authority::AuthorityStore;
+use backtest::OutcomeStore;
+use batch::{BatchConfig, BatchPool};
+use chunking::ChunkingConfig;
+use clause_library::{ClauseLibrary, EmbeddedClause};
+use clm::{ClmConfig, ClmStats};
+use clock::{Clock, FixedClock, IdGenerator, SequentialIds, SystemClock, UuidGenerator};
+use compile_cache::CompileCache;
+use content_repo::{ContentRepo, ContentRepoConfig};
+use corpus::{CorpusStore, StoredDocument};
+use custom_templates::CustomTemplateStore;
+use deadline::{AnalysisMode, Deadline, LatencyBudget, TimeoutConfig};
+use ensemble::{EnsembleConfig, EnsembleSummary, MlAnswer, MlFindings, Provenance};
+use entities::Entities;
+use escalation::{EscalationConfig, EscalationStore};
+use evidence::{EvidenceOptions, Position};
+use explain::{ExplainConfig, Explanation};
+use export_control::ExportPolicyStore;
+use extraction::ClauseExtractor;
+use fallback::FallbackClause;
+use favorability::Favorability;
+use history::{AnalysisStore, HistoryKind, MemoryAnalysisStore};
+use includes::IncludeLimits;
+use inflight::{InflightRegistry, Stage};
+use ingest::IngestConfig;
+use isolation::{PanicCounters, Panicked, Site};
+use jurisdiction_policy::{JurisdictionPolicyStore, PolicyViolation};
+use jobs::{CancelFlag, JobConfig, JobStore};
+use language::LanguageDetection;
+use lexicon::LexiconStore;
+use lifecycle::{Lifecycle, ShutdownConfig};
+use macros::MacroStore;
+use model_calls::{ModelCallLog, PromptLogging};
+use models::ModelRegistry;
+use notices::AddressBookStore;
+use notifier::NotificationConfig;
+use obligations::ObligationRegister;
+use orgs::OrgStore;
+use packing::{ContextOptions, PackingConfig};
+use paper::{PaperDetection, PaperSource};
+use playbook::{ClausePlaybook, PlaybookStore};
+use provenance::{Origin, ProvenanceManifest};
+use ratelimit::{RateLimitConfig, RateLimiter};
+use regulatory::RegulatoryStore;
+use render::{Delivery, OutputFormat, RenderStore, RenderedDocument};
+use revisions::RevisionStore;
+use rescore::{RescoreConfig, Rescorer};
+use risk_model::RiskModel;
+use reviews::ReviewStore;
+use share::ShareStore;
+use signature::{SignatureGateConfig, SignatureStore};
+use spill::MemoryConfig;
+use stream::Progress;
+use style::{StyleDeviation, StyleStore};
+use template_analytics::TemplateAnalytics;
+use timings::{AnalysisMetadata, Stopwatch};
+use quota::{QuotaConfig, ResourceReport};
+use truncation::{Truncated, TruncationConfig, TruncationReport};
+use variable_inference::InferenceStore;
+use warmup::{Fragments, KeywordMatcher, ParsedTemplate, Precompiled};
+use webhooks::{WebhookConfig, WebhookStore};
+use wizard::WizardStore;
+
+// ── AppState ──────────────────────────────────────────────────────────────────
+
+#[derive(Clone)]
+pub struct AppState {
+    start_time: Arc<Instant>,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    http: reqwest::Client,
+    escalation_config: Arc<EscalationConfig>,
+    escalations: Arc<EscalationStore>,
+    corpus: Arc<CorpusStore>,
+    regulatory: Arc<RegulatoryStore>,
+    wizards: Arc<WizardStore>,
+    export_policy: Arc<ExportPolicyStore>,
+    timeouts: Arc<TimeoutConfig>,
+    truncation: Arc<TruncationConfig>,
+    chunking: Arc<ChunkingConfig>,
+    packing: Arc<PackingConfig>,
+    quotas: Arc<QuotaConfig>,
+    batches: Arc<BatchPool>,
+    template_revisions: Arc<RevisionStore>,
+    custom_templates: Arc<CustomTemplateStore>,
+    macros: Arc<MacroStore>,
+    clause_library: Arc<ClauseLibrary>,
+    audit: Arc<AuditLog>,
+    signature_gate: Arc<SignatureGateConfig>,
+    signatures: Arc<SignatureStore>,
+    authority: Arc<AuthorityStore>,
+    precompiled: Arc<Precompiled>,
+    access_policy: Arc<AccessPolicy>,
+    notifications: Arc<NotificationConfig>,
+    jurisdiction_policy: Arc<JurisdictionPolicyStore>,
+    reviews: Arc<ReviewStore>,
+    memory: Arc<MemoryConfig>,
+    jobs: Arc<JobStore>,
+    /// Analyses in the pipeline right now, for the in-flight endpoint.
+    inflight: Arc<InflightRegistry>,
+    /// Whether the instance is draining for shutdown, and for how long.
+    lifecycle: Arc<Lifecycle>,
+    /// Git-backed templates and rules; `None` when not configured.
+    content_repo: Option<Arc<ContentRepo>>,
+    /// API keys and the signed-token secret; `None` when neither is set.
+    auth: Option<Arc<AuthConfig>>,
+    /// Take the tenant and role headers as sent when `auth` is `None`; see
+    /// [`AppState::trusting_identity_headers`].
+    trust_identity_headers: bool,
+    panics: Arc<PanicCounters>,
+    lexicons: Arc<LexiconStore>,
+    /// Tenant-hosted embedding and classification models.
+    models: Arc<ModelRegistry>,
+    /// Calls made to external model backends, with the tenants' logging settings.
+    model_calls: Arc<ModelCallLog>,
+    clm: Arc<ClmConfig>,
+    clm_stats: Arc<ClmStats>,
+    explain: Arc<ExplainConfig>,
+    ensemble: Arc<EnsembleConfig>,
+    risk_model: Arc<RiskModel>,
+    /// Scores of the stored corpus by ruleset version.
+    rescorer: Arc<Rescorer>,
+    shares: Arc<ShareStore>,
+    include_limits: Arc<IncludeLimits>,
+    ingest: Arc<IngestConfig>,
+    styles: Arc<StyleStore>,
+    /// Negotiation positions per clause type, by tenant.
+    playbooks: Arc<PlaybookStore>,
+    template_analytics: Arc<TemplateAnalytics>,
+    clause_extractor: Arc<dyn ClauseExtractor>,
+    history: Arc<dyn AnalysisStore>,
+    outcomes: Arc<OutcomeStore>,
+    address_book: Arc<AddressBookStore>,
+    obligations: Arc<ObligationRegister>,
+    /// Parents of tenants that are subsidiaries or business units.
+    orgs: Arc<OrgStore>,
+    rate_limit: Arc<RateLimiter>,
+    /// Compiled documents waiting behind a download URL.
+    renderings: Arc<RenderStore>,
+    /// Results of recent compiles, for repeats of the same compile.
+    compile_cache: Arc<CompileCache>,
+    /// Variables inferred from uploaded contracts, and how well each
+    /// profile's inferences held up.
+    variable_inference: Arc<InferenceStore>,
+    /// Tenant-registered callback endpoints.
+    webhooks: Arc<WebhookStore>,
+}
+
+impl AppState {
+    /// Fully in-memory state with default settings: no environment lookups,
+    /// no outbound webhooks, nothing on disk. Used by tests and embedders.
+    pub fn in_memory() -> Self {
+        Self {
+            start_time: Arc::new(Instant::now()),
+            clock: Arc::new(SystemClock),
+            ids: Arc::new(UuidGenerator),
+            http: reqwest::Client::new(),
+            escalation_config: Arc::new(EscalationConfig {
+                threshold: 0.5,
+                webhook_url: None,
+                webhook_secret: None,
+            }),
+            escalations: Arc::new(EscalationStore::default()),
+            corpus: Arc::new(CorpusStore::default()),
+            regulatory: Arc::new(RegulatoryStore::default()),
+            wizards: Arc::new(WizardStore::default()),
+            export_policy: Arc::new(ExportPolicyStore::default()),
+            timeouts: Arc::new(TimeoutConfig::default()),
+            truncation: Arc::new(TruncationConfig::default()),
+            chunking: Arc::new(ChunkingConfig::default()),
+            packing: Arc::new(PackingConfig::default()),
+            quotas: Arc::new(QuotaConfig::default()),
+            batches: Arc::new(BatchPool::default()),
+            template_revisions: Arc::new(RevisionStore::default()),
+            custom_templates: Arc::new(CustomTemplateStore::default()),
+            macros: Arc::new(MacroStore::default()),
+            clause_library: Arc::new(ClauseLibrary::default()),
+            audit: Arc::new(AuditLog::default()),
+            signature_gate: Arc::new(SignatureGateConfig::default()),
+            signatures: Arc::new(SignatureStore::default()),
+            authority: Arc::new(AuthorityStore::default()),
+            precompiled: Arc::new(Precompiled::default()),
+            access_policy: Arc::new(AccessPolicy::default()),
+            notifications: Arc::new(NotificationConfig::default()),
+            jurisdiction_policy: Arc::new(JurisdictionPolicyStore::default()),
+            reviews: Arc::new(ReviewStore::default()),
+            memory: Arc::new(MemoryConfig::default()),
+            jobs: Arc::new(JobStore::default()),
+            inflight: Arc::new(InflightRegistry::default()),
+            lifecycle: Arc::new(Lifecycle::default()),
+            content_repo: None,
+            auth: None,
+            trust_identity_headers: false,
+            panics: Arc::new(PanicCounters::default()),
+            lexicons: Arc::new(LexiconStore::default()),
+            models: Arc::new(ModelRegistry::default()),
+            model_calls: Arc::new(ModelCallLog::default()),
+            clm: Arc::new(ClmConfig::default()),
+            clm_stats: Arc::new(ClmStats::default()),
+            explain: Arc::new(ExplainConfig::default()),
+            ensemble: Arc::new(EnsembleConfig::default()),
+            risk_model: Arc::new(RiskModel::default()),
+            rescorer: Arc::new(Rescorer::default()),
+            shares: Arc::new(ShareStore::default()),
+            include_limits: Arc::new(IncludeLimits::default()),
+            ingest: Arc::new(IngestConfig::default()),
+            styles: Arc::new(StyleStore::default()),
+            playbooks: Arc::new(PlaybookStore::default()),
+            template_analytics: Arc::new(TemplateAnalytics::default()),
+            clause_extractor: Arc::new(extraction::KeywordRules),
+            history: Arc::new(MemoryAnalysisStore::default()),
+            outcomes: Arc::new(OutcomeStore::default()),
+            address_book: Arc::new(AddressBookStore::default()),
+            obligations: Arc::new(ObligationRegister::default()),
+            orgs: Arc::new(OrgStore::default()),
+            rate_limit: Arc::new(RateLimiter::default()),
+            renderings: Arc::new(RenderStore::default()),
+            compile_cache: Arc::new(CompileCache::default()),
+            variable_inference: Arc::new(InferenceStore::default()),
+            webhooks: Arc::new(WebhookStore::default()),
+        }
+    }
+
+    /// Takes the tenant and role headers as sent while no API keys or token
+    /// secret are configured, for embedders that authenticate in front of
+    /// the router. Without it those headers are dropped.
+    pub fn trusting_identity_headers(self) -> Self {
+        Self {
+            trust_identity_headers: true,
+            ..self
+        }
+    }
+
+    /// In-memory state whose clock stands still at `now` and whose IDs count
+    /// up from 1, so identical requests produce identical responses.
+    pub fn reproducible(now: chrono::DateTime<chrono::Utc>) -> Self {
+        Self {
+            clock: Arc::new(FixedClock::new(now)),
+            ids: Arc::new(SequentialIds::default()),
+            ..Self::in_memory()
+        }
+    }
+
+    /// Settings from the `LEGAL_*` environment variables. A malformed
+    /// configuration file stops startup. `LEGAL_ALLOW_UNAUTHENTICATED` trusts
+    /// the identity headers when no credentials are configured.
+    pub fn from_env() -> Self {
+        let base = match std::env::var("LEGAL_REPRODUCIBLE_AT") {
+            Ok(at) => Self::reproducible(
+                chrono::DateTime::parse_from_rfc3339(&at)
+                    .expect("invalid LEGAL_REPRODUCIBLE_AT")
+                    .with_timezone(&chrono::Utc),
+            ),
+            Err(_) => Self::in_memory(),
+        };
+        Self {
+            escalation_config: Arc::new(EscalationConfig::from_env()),
+            escalations: Arc::new(EscalationStore::from_env()),
+            timeouts: Arc::new(TimeoutConfig::from_env()),
+            truncation: Arc::new(TruncationConfig::from_env()),
+            chunking: Arc::new(ChunkingConfig::from_env()),
+            packing: Arc::new(PackingConfig::from_env()),
+            quotas: Arc::new(QuotaConfig::from_env()),
+            batches: Arc::new(BatchPool::new(BatchConfig::from_env())),
+            signature_gate: Arc::new(SignatureGateConfig::from_env()),
+            access_policy: Arc::new(AccessPolicy::from_env()),
+            notifications: Arc::new(NotificationConfig::from_env()),
+            memory: Arc::new(MemoryConfig::from_env()),
+            jobs: Arc::new(JobStore::new(JobConfig::from_env())),
+            lifecycle: Arc::new(Lifecycle::new(ShutdownConfig::from_env())),
+            content_repo: ContentRepoConfig::from_env().map(|c| Arc::new(ContentRepo::new(c))),
+            auth: AuthConfig::from_env().map(Arc::new),
+            trust_identity_headers: std::env::var("LEGAL_ALLOW_UNAUTHENTICATED")
+                .is_ok_and(|v| v == "true" || v == "1"),
+            clm: Arc::new(ClmConfig::from_env()),
+            explain: Arc::new(ExplainConfig::from_env()),
+            model_calls: Arc::new(ModelCallLog::new(PromptLogging::from_env())),
+            ensemble: Arc::new(EnsembleConfig::from_env()),
+            risk_model: Arc::new(RiskModel::from_env()),
+            rescorer: Arc::new(Rescorer::new(RescoreConfig::from_env())),
+            include_limits: Arc::new(IncludeLimits::from_env()),
+            ingest: Arc::new(IngestConfig::from_env()),
+            clause_extractor: extraction::from_env(),
+            history: history::from_env(),
+            rate_limit: Arc::new(RateLimiter::new(RateLimitConfig::from_env())),
+            renderings: Arc::new(RenderStore::default()),
+            compile_cache: Arc::new(CompileCache::from_env()),
+            custom_templates: Arc::new(CustomTemplateStore::from_env()),
+            webhooks: Arc::new(WebhookStore::new(WebhookConfig::from_env())),
+            ..base
+        }
+    }
+
+    /// Precompiles matchers and templates, loads the Git-backed content and
+    /// starts the background refresh and compaction. Runs inside a Tokio
+    /// runtime; an error means the engine must not serve.
+    pub fn start(&self) -> Result<(), String> {
+        // Fail at startup, not on the first request that needs a broken template.
+        self.precompiled
+            .warm()
+            .map_err(|e| format!("warm-up failed: {e}"))?;
+        info!("matchers and templates precompiled");
+        info!(
+            clause_extractor = self.clause_extractor.name(),
+            "clause extractor selected"
+        );
+        if let Some(repo) = &self.content_repo {
+            repo.refresh(self)
+                .map_err(|e| format!("content repository failed to load: {e}"))?;
+            content_repo::spawn_refresh(self.clone());
+        }
+        if let Some(interval) = std::env::var("LEGAL_CORPUS_COMPACT_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+        {
+            corpus::spawn_compaction(self.clone(), Duration::from_secs(interval));
+        }
+        if let Some(interval) = std::env::var("LEGAL_OBLIGATION_CHECK_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+        {
+            obligations::spawn_overdue_checks(self.clone(), Duration::from_secs(interval));
+        }
+        Ok(())
+    }
+
+    /// Fails unless requests will be authenticated or serving without
+    /// authentication was asked for with `LEGAL_ALLOW_UNAUTHENTICATED` or
+    /// [`AppState::trusting_identity_headers`]. Servers check it before
+    /// they listen.
+    pub fn require_authentication(&self) -> Result<(), String> {
+        if self.auth.is_some() {
+            return Ok(());
+        }
+        if !self.trust_identity_headers {
+            return Err("neither LEGAL_AUTH_FILE nor LEGAL_JWT_SECRET is set; set LEGAL_ALLOW_UNAUTHENTICATED=true to serve without authentication".to_string());
+        }
+        warn!("serving without authentication: callers choose their tenant and role");
+        Ok(())
+    }
+
+    /// Starts a graceful shutdown: `/readyz` turns `503` and new jobs are
+    /// refused while the running ones finish.
+    pub fn begin_shutdown(&self) {
+        self.lifecycle.begin();
+    }
+
+    /// Resolves once a shutdown has begun and the queued and running jobs
+    /// and in-flight analyses have finished, or `LEGAL_SHUTDOWN_GRACE_SECS`
+    /// has passed. Pass it to `axum::serve(…).with_graceful_shutdown`.
+    pub fn stopped(&self) -> impl std::future::Future<Output = ()> + Send + 'static {
+        lifecycle::stopped(self.clone())
+    }
+
+    /// Analyzes `document` for `tenant` with the heuristics alone, as a batch
+    /// document is: no ML backend and no notifications. The analysis is
+    /// stored and escalated like any other.
+    pub fn analyze_document(&self, tenant: &str, document: &str, language: &str) -> AnalyzeResponse {
+        let keywords = self.lexicons.matcher(tenant);
+        let opts = AnalysisOptions {
+            mode: AnalysisMode::default(),
+            deadline: Deadline::after(self.timeouts.analyze, self.timeouts.soft_margin),
+            context_chars: evidence::DEFAULT_CONTEXT_CHARS,
+            fallback_clauses: false,
+            cancel: None,
+            latency: None,
+            keywords: keywords.as_deref(),
+            tenant,
+            ml: None,
+            model_call: None,
+            progress: None,
+            origin: inflight::Origin::Embedded,
+            our_party: None,
+        };
+        run_analysis(self, document, language, opts)
+    }
+
+    /// The risk model's score for `document`, without an explanation.
+    pub fn score_risk(&self, document: &str) -> RiskScoreResponse {
+        let risk_factors = self.risk_model.factors(document);
+        let overall_score: f64 = risk_factors.iter().map(|f| f.weight * f.score).sum::<f64>();
+        let risk_level = self.risk_model.level(overall_score);
+        RiskScoreResponse {
+            analysis_id: self.ids.next_id(),
+            overall_score,
+            recommendations: self.risk_model.recommendations(&risk_level),
+            waterfall: build_waterfall(&risk_factors),
+            risk_level,
+            risk_factors,
+            explanation: None,
+        }
+    }
+
+    /// Renders a built-in template; `404` for an unknown ID and `422` when
+    /// the variables cannot be rendered.
+    pub fn compile_document(
+        &self,
+        template_id: &str,
+        variables: &HashMap<String, String>,
+    ) -> Result<CompileResponse, StatusCode> {
+        compile_template(&self.precompiled, template_id, variables, CompileExtras::default())
+    }
+
+    fn record_audit(
+        &self,
+        action: &str,
+        subject: &str,
+        actor: Option<&str>,
+        detail: serde_json::Value,
+    ) -> audit::AuditEntry {
+        self.audit.record(
+            format!("audit-{}", self.ids.next_id()),
+            self.clock.now(),
+            action,
+            subject,
+            actor,
+            detail,
+        )
+    }
+}
+
+// ── Request / Response types ──────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct AnalyzeRequest {
+    document: String,
+    /// What the caller believes the document is written in; checked against
+    /// the text, and inferred from it when empty.
+    #[serde(default)]
+    language: String,
+    /// Shown in notifications; defaults to the analysis ID.
+    #[serde(default)]
+    document_name: Option<String>,
+    #[serde(default)]
+    mode: AnalysisMode,
+    #[serde(default)]
+    analysis_options: EvidenceOptions,
+    /// Latency-bounded mode: respond within this many milliseconds, skipping
+    /// optional passes that do not fit.
+    #[serde(default)]
+    latency_budget_ms: Option<u64>,
+    /// The party the caller is, by name or defined term, e.g. "Customer";
+    /// favorability is read from its side. Defaults to the tenant's entity.
+    #[serde(default)]
+    our_party: Option<String>,
+    /// What the ML backend is sent of a document over its token budget.
+    #[serde(default)]
+    ml_context: ContextOptions,
+}
+
+/// Per-call knobs for `run_analysis`.
+#[derive(Debug, Clone, Copy)]
+struct AnalysisOptions<'a> {
+    mode: AnalysisMode,
+    deadline: Deadline,
+    /// Characters of context around each finding's excerpt.
+    context_chars: usize,
+    /// Run the mandatory clause checklist, with fallback language.
+    fallback_clauses: bool,
+    /// Set for background jobs; checked between stages.
+    cancel: Option<&'a CancelFlag>,
+    latency: Option<LatencyBudget>,
+    /// The tenant's lexicon-aware matcher; the built-in one when `None`.
+    keywords: Option<&'a KeywordMatcher>,
+    /// Owner of the stored document.
+    tenant: &'a str,
+    /// The ML backend's findings, merged into the heuristic ones when set.
+    ml: Option<&'a MlFindings>,
+    /// The recorded call that produced `ml`, linked to the analysis.
+    model_call: Option<&'a str>,
+    /// Receives findings as each stage produces them, for streaming.
+    progress: Option<&'a Progress>,
+    /// The entry point, as listed by the in-flight endpoint.
+    origin: inflight::Origin<'a>,
+    /// The caller's party, for favorability; the tenant's entity when `None`.
+    our_party: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Clause {
+    pub id: String,
+    pub text: String,
+    pub clause_type: String,
+    pub risk_level: String,
+    pub confidence: f64,
+    pub review_status: String,
+    /// Which backends produced it; set when the ML backend took part.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+    /// The matched evidence with surrounding text, when there is a match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+    /// Where the matched text is, when there is a match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<Position>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Issue {
+    pub id: String,
+    pub description: String,
+    pub severity: String,
+    /// The section heading or line of the evidence, when there is any.
+    pub location: String,
+    pub confidence: f64,
+    pub review_status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<Position>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct AnalyzeResponse {
+    pub analysis_id: String,
+    pub risk_score: f64,
+    pub clauses: Vec<Clause>,
+    pub issues: Vec<Issue>,
+    pub escalations: Vec<String>,
+    /// The language the document was analyzed as.
+    pub language: String,
+    /// The declared and detected languages the one above was settled from.
+    pub language_detection: LanguageDetection,
+    pub word_count: usize,
+    /// Set when deep analysis hit its soft deadline and skipped stages, or
+    /// stages were skipped to stay within the resource limits.
+    pub partial: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_stages: Vec<String>,
+    /// Whose template the contract is on, and the review profile that chose.
+    pub paper: PaperDetection,
+    /// Present when quick mode cut the document down to its token limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncation: Option<TruncationReport>,
+    /// Departures from the tenant's drafting style profile, if it has one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub style_deviations: Vec<StyleDeviation>,
+    /// How heuristic and ML findings were merged, when the ML backend answered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ensemble: Option<EnsembleSummary>,
+    /// The resource limits the analysis hit, when it degraded to stay within them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_limits: Option<ResourceReport>,
+    /// Ready-to-insert language for missing mandatory clauses, when asked for.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fallback_clauses: Vec<FallbackClause>,
+    /// Parties, defined terms, amounts, percentages and dates.
+    pub entities: Entities,
+    /// Which party each clause favors, and the balance of the document;
+    /// `None` when the stage was skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favorability: Option<Favorability>,
+    /// Each clause graded against the tenant's playbook, if it has one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub playbook: Vec<ClausePlaybook>,
+    pub metadata: AnalysisMetadata,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CompileRequest {
+    template_id: String,
+    /// Compile this saved version of a custom template instead of the
+    /// current one.
+    #[serde(default)]
+    template_version: Option<u32>,
+    variables: HashMap<String, String>,
+    /// Structured values for `{{#if}}` and `{{#each}}`, e.g. lists of schedules.
+    #[serde(default)]
+    data: serde_json::Map<String, serde_json::Value>,
+    /// Also return the clause map with risk metadata and playbook positions.
+    #[serde(default)]
+    annotations: bool,
+    /// Also return where each paragraph came from.
+    #[serde(default)]
+    provenance: bool,
+    /// Also render the document as a file in this format.
+    #[serde(default)]
+    output_format: Option<OutputFormat>,
+    /// Whether the file comes back inline or behind a download URL.
+    #[serde(default)]
+    delivery: Delivery,
+    /// Parties given a signature block; defaults to the party variables.
+    #[serde(default)]
+    signatories: Option<Vec<String>>,
+}
+
+impl CompileRequest {
+    fn extras(&self) -> CompileExtras {
+        CompileExtras {
+            annotations: self.annotations,
+            provenance: self.provenance,
+        }
+    }
+}
+
+/// What a compile returns besides the document, each part worked out only
+/// when asked for.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompileExtras {
+    pub annotations: bool,
+    pub provenance: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CompileResponse {
+    pub template_id: String,
+    /// Version of the custom template compiled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_version: Option<u32>,
+    pub compiled_document: String,
+    pub variables_applied: usize,
+    pub missing_variables: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Vec<annotations::ClauseAnnotation>>,
+    /// Jurisdictions that need SCCs; blocked ones fail the compile instead.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub policy_warnings: Vec<PolicyViolation>,
+    /// Party names with a misspelled or foreign entity suffix.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub entity_warnings: Vec<entity::EntityCheck>,
+    /// Clause library versions embedded through `{{clause:…}}`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub library_clauses: Vec<EmbeddedClause>,
+    /// The document as a file, when an `output_format` was asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rendered: Option<RenderedDocument>,
+    /// Where each paragraph of `compiled_document` came from, when asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<ProvenanceManifest>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct TemplateInfo {
+    id: String,
+    name: String,
+    description: String,
+    required_variables: Vec<String>,
+    language_support: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct TemplatesResponse {
+    templates: Vec<TemplateInfo>,
+    count: usize,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct RiskRequest {
+    document: String,
+    /// Adds a plain-language paragraph on what drives the score.
+    #[serde(default)]
+    explain: bool,
+    /// Language of the explanation; defaults to English.
+    #[serde(default)]
+    language: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RiskFactor {
+    pub factor: String,
+    pub weight: f64,
+    pub score: f64,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WaterfallStep {
+    pub label: String,
+    pub contribution: f64,
+    pub contribution_percent: f64,
+    pub running_total: f64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RiskScoreResponse {
+    pub analysis_id: String,
+    pub overall_score: f64,
+    pub risk_level: String,
+    pub risk_factors: Vec<RiskFactor>,
+    pub waterfall: Vec<WaterfallStep>,
+    pub recommendations: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Explanation>,
+}
+
+#[derive(Debug, Serialize)]
+struct HealthResponse {
+    status: String,
+    uptime_secs: u64,
+    service: String,
+    version: String,
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+async fn health(State(state): State<AppState>) -> Json<HealthResponse> {
+    let uptime = state.start_time.elapsed().as_secs();
+    Json(HealthResponse {
+        status: "ok".to_string(),
+        uptime_secs: uptime,
+        service: "alice-legal-engine".to_string(),
+        version: "1.0.0".to_string(),
+    })
+}
+
+async fn analyze(
+    State(state): State<AppState>,
+    Extension(deadline): Extension<Deadline>,
+    headers: HeaderMap,
+    Json(req): Json<AnalyzeRequest>,
+) -> Result<Response, StatusCode> {
+    let ml = ensemble::ml_findings(&state, &notifier::tenant(&headers), &req).await;
+    analyze_off_runtime(state, deadline, headers, req, ml).await
+}
+
+/// `analyze_request`, on a blocking thread when the document is large
+/// enough to be classified in chunks, so the chunk workers it waits for do
+/// not hold up an async worker.
+async fn analyze_off_runtime(
+    state: AppState,
+    deadline: Deadline,
+    headers: HeaderMap,
+    req: AnalyzeRequest,
+    ml: MlAnswer,
+) -> Result<Response, StatusCode> {
+    if !state.chunking.applies(req.document.len()) {
+        return analyze_request(&state, deadline, &headers, req, ml);
+    }
+    tokio::task::spawn_blocking(move || analyze_request(&state, deadline, &headers, req, ml))
+        .await
+        .unwrap_or(Err(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+/// The request's latency budget, if it set one; a zero budget is rejected.
+fn latency_budget(
+    state: &AppState,
+    req: &AnalyzeRequest,
+    deadline: Deadline,
+) -> Result<Option<LatencyBudget>, StatusCode> {
+    match req.latency_budget_ms {
+        Some(0) => Err(StatusCode::BAD_REQUEST),
+        Some(ms) => Ok(Some(
+            state
+                .timeouts
+                .latency_budget(Duration::from_millis(ms), deadline),
+        )),
+        None => Ok(None),
+    }
+}
+
+/// `analyze` after the body is read; shared with the file upload variant.
+fn analyze_request(
+    state: &AppState,
+    deadline: Deadline,
+    headers: &HeaderMap,
+    req: AnalyzeRequest,
+    ml: MlAnswer,
+) -> Result<Response, StatusCode> {
+    let tenant = notifier::tenant(headers);
+    let analysis = analyze_checked(state, deadline, &tenant, &req, ml, inflight::Origin::Request)?;
+    let response = match analysis {
+        Ok(response) => response,
+        Err(panicked) => return Ok(panicked.into_response()),
+    };
+    // In latency-bounded mode skipped passes are the agreed outcome, not a
+    // timeout; so are passes skipped to stay within the resource limits.
+    if response.partial && req.latency_budget_ms.is_none() && response.resource_limits.is_none()
+    {
+        return Ok((StatusCode::GATEWAY_TIMEOUT, Json(response)).into_response());
+    }
+    Ok(Json(response).into_response())
+}
+
+/// Checks and runs an analyze request, then notifies the tenant's routes and
+/// webhooks; the inner `Err` is a contained panic. Shared with the gRPC API.
+fn analyze_checked(
+    state: &AppState,
+    deadline: Deadline,
+    tenant: &str,
+    req: &AnalyzeRequest,
+    ml: MlAnswer,
+    origin: inflight::Origin,
+) -> Result<Result<AnalyzeResponse, Panicked>, StatusCode> {
+    if req.document.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let keywords = state.lexicons.matcher(tenant);
+    let opts = AnalysisOptions {
+        mode: req.mode,
+        deadline,
+        context_chars: req.analysis_options.context_chars()?,
+        fallback_clauses: req.analysis_options.fallback_clauses,
+        cancel: None,
+        latency: latency_budget(state, req, deadline)?,
+        keywords: keywords.as_deref(),
+        tenant,
+        ml: ml.findings.as_ref(),
+        model_call: ml.call_id.as_deref(),
+        progress: None,
+        origin,
+        our_party: req.our_party.as_deref(),
+    };
+    let response = isolation::contain(state, Site::Analysis, || {
+        run_analysis(state, &req.document, &req.language, opts)
+    });
+    if let Ok(response) = &response {
+        notifier::analysis_completed(state, tenant, req.document_name.as_deref(), response);
+        webhooks::risk_scored(state, tenant, response);
+    }
+    Ok(response)
+}
+
+/// Shared analysis pipeline: extracts findings, stores the document in the
+/// corpus, and escalates low-confidence items.
+fn run_analysis(
+    state: &AppState,
+    document: &str,
+    language: &str,
+    opts: AnalysisOptions,
+) -> AnalyzeResponse {
+    let mut watch = Stopwatch::start(state.clock.as_ref());
+    let tracker = state
+        .inflight
+        .begin(opts.origin, opts.tenant, document.len(), state.clock.now());
+    let quotas = state.orgs.quotas(opts.tenant).apply(&state.quotas);
+    let mut guard = quota::Guard::start(&quotas);
+    let mut metadata = AnalysisMetadata::default();
+    let cancelled = || opts.cancel.is_some_and(CancelFlag::is_cancelled);
+    let mut skipped_stages = Vec::new();
+    let deadline = opts.latency.map_or(opts.deadline, |l| l.deadline);
+    metadata.latency_budget_ms = opts
+        .latency
+        .map(|l| u64::try_from(l.budget.as_millis()).unwrap_or(u64::MAX));
+    let word_count = document.split_whitespace().count();
+    let detection = language::detect(document, language);
+    if detection.mismatch {
+        warn!(
+            declared = ?detection.declared,
+            detected = ?detection.detected,
+            confidence = detection.confidence,
+            "document language differs from the declared one"
+        );
+    }
+    let language = detection.effective().to_string();
+    let paper = paper::detect(state, document, opts.mode);
+    let mode = paper.review_profile;
+    metadata.timings.parse_ms = watch.lap();
+    tracker.stage(Stage::Segmentation);
+
+    // Latency-bounded mode also truncates, to what the budget can process,
+    // and every mode to the hard token limit.
+    let quick = (mode == AnalysisMode::Quick).then_some(state.truncation.quick_max_tokens);
+    let bounded = opts.latency.map(|l| l.max_tokens());
+    guard.truncated(word_count);
+    let truncated = quick
+        .into_iter()
+        .chain(bounded)
+        .chain(Some(guard.max_tokens()))
+        .min()
+        .and_then(|limit| truncation::truncate(document, limit));
+    metadata.timings.segmentation_ms = watch.lap();
+    tracker.stage(Stage::Classification);
+    let analyzed = truncated.as_ref().map_or(document, |t| t.text.as_str());
+    let analyzed_tokens = truncated.as_ref().map_or(word_count, |t| t.report.kept_tokens);
+    // Optional passes in latency-bounded mode run only if they still fit,
+    // and in every mode only within the resource limits.
+    let fits = || opts.latency.is_none_or(|l| l.fits(analyzed_tokens));
+    let scan_steps = analyzed.len() as u64;
+    let report = |clauses: &[Clause], issues: &[Issue]| {
+        if let Some(progress) = opts.progress {
+            progress.found(clauses, issues);
+        }
+    };
+    let (mut clauses, mut issues, fallback_clauses, entities) = if cancelled() {
+        skipped_stages.push("classification".to_string());
+        (Vec::new(), Vec::new(), Vec::new(), Entities::default())
+    } else {
+        let keywords = opts
+            .keywords
+            .unwrap_or_else(|| state.precompiled.keywords())
+            .in_language(&language);
+        let (mut clauses, chunks) = guard.required("classification", scan_steps, || {
+            chunking::classify(state, &keywords, analyzed, opts.context_chars)
+        });
+        metadata.chunks = chunks;
+        relocate(truncated.as_ref(), document, &mut clauses, &mut []);
+        let mut issues = Vec::new();
+        report(&clauses, &issues);
+        let next_id = issues.len() + 1;
+        let found = fits().then(|| {
+            guard.optional("export_control", scan_steps, || {
+                export_control::issues(
+                    analyzed,
+                    &state.export_policy.get(),
+                    next_id,
+                    opts.context_chars,
+                )
+            })
+        });
+        match found.flatten() {
+            Some(mut found) => {
+                relocate(truncated.as_ref(), document, &mut [], &mut found);
+                issues.extend(found);
+            }
+            None => skipped_stages.push("export_control".to_string()),
+        }
+        report(&clauses, &issues);
+        let next_id = issues.len() + 1;
+        let found = fits().then(|| {
+            guard.optional("entity", scan_steps, || {
+                entity::issues(analyzed, next_id, opts.context_chars)
+            })
+        });
+        match found.flatten() {
+            Some(mut found) => {
+                relocate(truncated.as_ref(), document, &mut [], &mut found);
+                issues.extend(found);
+            }
+            None => skipped_stages.push("entity".to_string()),
+        }
+        report(&clauses, &issues);
+        let next_id = issues.len() + 1;
+        let found = fits().then(|| {
+            guard.optional("conflicts", conflicts::match_steps(analyzed), || {
+                conflicts::issues(analyzed, next_id, opts.context_chars)
+            })
+        });
+        match found.flatten() {
+            Some(mut found) => {
+                relocate(truncated.as_ref(), document, &mut [], &mut found);
+                issues.extend(found);
+            }
+            None => skipped_stages.push("conflicts".to_string()),
+        }
+        let found = fits().then(|| {
+            guard.optional("entities", scan_steps, || {
+                entities::extract(analyzed, &language)
+            })
+        });
+        let entities = match found.flatten() {
+            Some(mut found) => {
+                if let Some(t) = truncated.as_ref() {
+                    found.relocate(|offset| t.original_offset(offset));
+                }
+                found
+            }
+            None => {
+                skipped_stages.push("entities".to_string());
+                Entities::default()
+            }
+        };
+        // The checklist reads the whole document, since the clause it looks
+        // for may be in a span truncation skipped, and the fallback language
+        // is positioned in it.
+        let mut fallbacks = Vec::new();
+        if opts.fallback_clauses {
+            let next_id = issues.len() + 1;
+            let found = fits().then(|| {
+                guard.optional("checklist", document.len() as u64, || {
+                    fallback::missing(&keywords, document, next_id)
+                })
+            });
+            match found.flatten() {
+                Some((found, language)) => {
+                    issues.extend(found);
+                    fallbacks = language;
+                }
+                None => skipped_stages.push("checklist".to_string()),
+            }
+            report(&clauses, &issues);
+        }
+        (clauses, issues, fallbacks, entities)
+    };
+
+    let analysis_id = state.ids.next_id();
+    tracker.identify(&analysis_id);
+    if let Some(call) = opts.model_call {
+        state.model_calls.link(call, &analysis_id);
+    }
+    let stored = StoredDocument {
+        id: analysis_id.clone(),
+        text: document.to_string(),
+        language: language.clone(),
+        tenant: opts.tenant.to_string(),
+        stored_at: state.clock.now(),
+        family_id: None,
+        parent_id: None,
+    };
+
+    let playbook = state.playbooks.resolve(&state.orgs.lineage(opts.tenant));
+
+    // Deep-only stages, each skipped once the soft deadline has passed or
+    // the job was cancelled.
+    if mode == AnalysisMode::Deep {
+        let rules = state.regulatory.list();
+        let next_id = issues.len() + 1;
+        let steps = (document.len() * rules.len().max(1)) as u64;
+        let found = (!deadline.soft_expired() && !cancelled() && fits()).then(|| {
+            guard.optional("regulatory", steps, || {
+                regulatory::issues(
+                    &stored,
+                    &rules,
+                    state.clock.today(),
+                    next_id,
+                    opts.context_chars,
+                    opts.keywords.and_then(KeywordMatcher::lexicon),
+                )
+            })
+        });
+        match found.flatten() {
+            Some(found) => issues.extend(found),
+            None => skipped_stages.push("regulatory".to_string()),
+        }
+        report(&clauses, &issues);
+        if paper.source == PaperSource::Counterparty {
+            let next_id = issues.len() + 1;
+            let found = (!deadline.soft_expired() && !cancelled() && fits()).then(|| {
+                guard.optional("playbook", document.len() as u64, || {
+                    paper::playbook_issues(
+                        &diligence::key_terms(document),
+                        &playbook.checks,
+                        next_id,
+                    )
+                })
+            });
+            match found.flatten() {
+                Some(found) => issues.extend(found),
+                None => skipped_stages.push("playbook".to_string()),
+            }
+            report(&clauses, &issues);
+        }
+    }
+
+    metadata.timings.classification_ms = watch.lap();
+    tracker.stage(Stage::Scoring);
+
+    // The risk model's score, as `/risk-score` gives it for the document.
+    let risk_score: f64 = state
+        .risk_model
+        .factors(document)
+        .iter()
+        .map(|f| f.weight * f.score)
+        .sum();
+
+    // Findings with no evidence in the kept text may be wrong about the
+    // full document, e.g. a "missing" clause that sits in a skipped span.
+    let truncation = truncated.map(|t| {
+        let mut report = t.report;
+        report.affected_findings = clauses
+            .iter()
+            .filter(|c| c.excerpt.is_none())
+            .map(|c| c.id.clone())
+            .chain(issues.iter().filter(|i| i.excerpt.is_none()).map(|i| i.id.clone()))
+            .collect();
+        report
+    });
+
+    // The ML backend read the whole document, so its findings are merged
+    // after the truncation report; none are merged into cancelled work.
+    let ensemble = opts
+        .ml
+        .filter(|_| !cancelled())
+        .map(|ml| ensemble::merge(&state.ensemble, &mut clauses, &mut issues, ml));
+
+    info!(
+        language = %language,
+        word_count,
+        risk_score,
+        "document analyzed"
+    );
+
+    let style_deviations = state
+        .styles
+        .get(opts.tenant)
+        .map(|profile| style::check(&profile, document))
+        .unwrap_or_default();
+
+    state.corpus.insert(stored);
+
+    // Optional like the deep stages: skipped past the soft deadline, once
+    // cancelled, or when they no longer fit.
+    let entity = state.orgs.get(opts.tenant);
+    let steps = document.len() as u64;
+    let found = (!deadline.soft_expired() && !cancelled() && fits()).then(|| {
+        guard.optional("favorability", steps, || {
+            favorability::assess(
+                document,
+                &clauses,
+                &entities.parties,
+                opts.our_party,
+                entity.as_ref().map(|e| e.name.as_str()),
+            )
+        })
+    });
+    let favorability = found.flatten();
+    if favorability.is_none() {
+        skipped_stages.push("favorability".to_string());
+    }
+    let mut graded = Vec::new();
+    if !playbook.rules.is_empty() && !clauses.is_empty() {
+        let found = (!deadline.soft_expired() && !cancelled() && fits()).then(|| {
+            guard.optional("playbook_review", steps, || {
+                playbook.review(document, &clauses)
+            })
+        });
+        match found.flatten() {
+            Some(found) => graded = found,
+            None => skipped_stages.push("playbook_review".to_string()),
+        }
+    }
+
+    // The backend is called before the pipeline starts, so its wait is
+    // added to the total rather than lapped.
+    let backend_ms = opts.ml.map_or(0.0, |ml| ml.elapsed_ms);
+    metadata.timings.backend_calls_ms = backend_ms;
+    metadata.ml_context = opts.ml.and_then(|ml| ml.packing.clone());
+
+    let mut response = AnalyzeResponse {
+        analysis_id,
+        risk_score,
+        clauses,
+        issues,
+        escalations: Vec::new(),
+        language,
+        language_detection: detection,
+        word_count,
+        partial: !skipped_stages.is_empty(),
+        skipped_stages,
+        paper,
+        truncation,
+        style_deviations,
+        ensemble,
+        resource_limits: guard.report(),
+        fallback_clauses,
+        entities,
+        favorability,
+        playbook: graded,
+        metadata,
+    };
+
+    // Cancelled work is not worth a reviewer's time.
+    if cancelled() {
+        response.partial = true;
+        response.skipped_stages.push("escalation".to_string());
+        response.metadata.timings.total_ms = watch.total() + backend_ms;
+        return response;
+    }
+    let created = state
+        .escalations
+        .escalate(
+            &mut response,
+            state.escalation_config.threshold,
+            state.ids.as_ref(),
+        );
+    if !created.is_empty() {
+        info!(
+            analysis_id = %response.analysis_id,
+            escalated = created.len(),
+            "low-confidence findings escalated for human review"
+        );
+        escalation::notify_reviewers(state, opts.tenant, &response, &created);
+    }
+    response.metadata.timings.scoring_ms = watch.lap();
+    response.metadata.timings.total_ms = watch.total() + backend_ms;
+    history::record_analysis(state, opts.tenant, document, &response);
+
+    response
+}
+
+/// Moves the positions of findings in the kept text of a truncated
+/// document onto the submitted one; locations read from a position follow it.
+fn relocate(
+    truncated: Option<&Truncated>,
+    document: &str,
+    clauses: &mut [Clause],
+    issues: &mut [Issue],
+) {
+    if let Some(t) = truncated {
+        reposition(document, clauses, issues, |offset| t.original_offset(offset));
+    }
+}
+
+/// Moves findings made in an excerpt of `document` to the document, with
+/// `to_document` mapping excerpt offsets to document offsets.
+fn reposition(
+    document: &str,
+    clauses: &mut [Clause],
+    issues: &mut [Issue],
+    to_document: impl Fn(usize) -> usize,
+) {
+    let moved =
+        |p: &Position| evidence::position(document, to_document(p.start), to_document(p.end));
+    for clause in clauses {
+        clause.position = clause.position.as_ref().map(moved);
+    }
+    for issue in issues {
+        if let Some(position) = issue.position.take() {
+            let relocated = moved(&position);
+            if issue.location == position.label() {
+                issue.location = relocated.label();
+            }
+            issue.position = Some(relocated);
+        }
+    }
+}
+
+async fn compile(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CompileRequest>,
+) -> Result<Response, StatusCode> {
+    if req.template_id.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    isolation::contain(&state, Site::Compile, || {
+        let tenant = notifier::tenant(&headers);
+        let Some(format) = req.output_format else {
+            return compile_cache::compile(&state, &tenant, &headers, &req);
+        };
+        let mut compiled = match jurisdiction_policy::compile_enforced(
+            &state,
+            &tenant,
+            &req.template_id,
+            req.template_version,
+            &req.variables,
+            &req.data,
+            req.extras(),
+        )? {
+            Ok(compiled) => compiled,
+            Err(rejection) => {
+                return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(rejection)).into_response())
+            }
+        };
+        let signatories = render::signatories(req.signatories.as_deref(), &req.variables);
+        compiled.rendered = Some(render::deliver(
+            &state,
+            &tenant,
+            &req.template_id,
+            &compiled.compiled_document,
+            format,
+            req.delivery,
+            &signatories,
+        )?);
+        Ok(Json(compiled).into_response())
+    })
+    .unwrap_or_else(|panicked| Ok(panicked.into_response()))
+}
+
+fn compile_template(
+    pre: &Precompiled,
+    template_id: &str,
+    variables: &HashMap<String, String>,
+    extras: CompileExtras,
+) -> Result<CompileResponse, StatusCode> {
+    compile_template_with(pre, template_id, variables, &serde_json::Map::new(), extras)
+}
+
+fn compile_template_with(
+    pre: &Precompiled,
+    template_id: &str,
+    variables: &HashMap<String, String>,
+    data: &serde_json::Map<String, serde_json::Value>,
+    extras: CompileExtras,
+) -> Result<CompileResponse, StatusCode> {
+    let template = pre.template(template_id).ok_or(StatusCode::NOT_FOUND)?;
+    let body = get_template_body(template_id).ok_or(StatusCode::NOT_FOUND)?;
+    compile_parsed(
+        &Origin::built_in(template_id, &body),
+        template,
+        get_required_variables(template_id),
+        variables,
+        data,
+        &Fragments::default(),
+        extras,
+    )
+}
+
+/// Renders an already looked-up template, built-in or custom, with
+/// `fragments` for the macros and library clauses it uses, and, when
+/// `extras` asks for provenance, traces each paragraph back to its `origin`.
+fn compile_parsed(
+    origin: &Origin,
+    template: &ParsedTemplate,
+    required: Vec<String>,
+    variables: &HashMap<String, String>,
+    data: &serde_json::Map<String, serde_json::Value>,
+    fragments: &Fragments,
+    extras: CompileExtras,
+) -> Result<CompileResponse, StatusCode> {
+    let template_id = origin.template.template_id.as_str();
+    // Missing placeholders stay visible; a block that cannot render is the caller's data.
+    let failed = |e: String| {
+        info!(template_id = %template_id, error = %e, "template render failed");
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+    let (rendered, provenance) = if extras.provenance {
+        let traced = template
+            .render_traced(variables, data, fragments)
+            .map_err(failed)?;
+        let provenance = provenance::manifest(origin, &traced, variables);
+        (traced.rendered, Some(provenance))
+    } else {
+        let rendered = template
+            .render_with_fragments(variables, data, fragments)
+            .map_err(failed)?;
+        (rendered, None)
+    };
+    let annotations = extras
+        .annotations
+        .then(|| annotations::annotate(template_id, &rendered.paragraphs));
+
+    let (applied, missing_variables): (Vec<String>, Vec<String>) =
+        required.into_iter().partition(|var| variables.contains_key(var));
+    let variables_applied = applied.len();
+
+    info!(
+        template_id = %template_id,
+        variables_applied,
+        missing = missing_variables.len(),
+        "template compiled"
+    );
+
+    Ok(CompileResponse {
+        template_id: template_id.to_string(),
+        template_version: origin.template.version,
+        compiled_document: rendered.text,
+        variables_applied,
+        missing_variables,
+        annotations,
+        policy_warnings: Vec::new(),
+        entity_warnings: Vec::new(),
+        library_clauses: origin.library_clauses.clone(),
+        rendered: None,
+        provenance,
+    })
+}
+
+async fn templates(State(state): State<AppState>, headers: HeaderMap) -> Json<TemplatesResponse> {
+    let mut templates = vec![
+        TemplateInfo {
+            id: "nda".to_string(),
+            name: "Non-Disclosure Agreement".to_string(),
+            description: "Mutual or one-way NDA for confidential information protection.".to_string(),
+            required_variables: vec![
+                "party_a".to_string(),
+                "party_b".to_string(),
+                "effective_date".to_string(),
+                "jurisdiction".to_string(),
+            ],
+            language_support: vec!["en".to_string(), "ja".to_string(), "de".to_string()],
+        },
+        TemplateInfo {
+            id: "sla".to_string(),
+            name: "Service Level Agreement".to_string(),
+            description: "SLA defining uptime guarantees, response times, and remedies.".to_string(),
+            required_variables: vec![
+                "service_provider".to_string(),
+                "customer".to_string(),
+                "uptime_percent".to_string(),
+                "response_time_hours".to_string(),
+            ],
+            language_support: vec!["en".to_string(), "ja".to_string()],
+        },
+        TemplateInfo {
+            id: "dpa".to_string(),
+            name: "Data Processing Agreement".to_string(),
+            description: "GDPR-compliant DPA for data controller/processor relationships.".to_string(),
+            required_variables: vec![
+                "controller".to_string(),
+                "processor".to_string(),
+                "data_types".to_string(),
+                "retention_period".to_string(),
+            ],
+            language_support: vec!["en".to_string(), "de".to_string(), "fr".to_string()],
+        },
+        TemplateInfo {
+            id: "tos".to_string(),
+            name: "Terms of Service".to_string(),
+            description: "User-facing terms governing use of a product or platform.".to_string(),
+            required_variables: vec![
+                "company_name".to_string(),
+                "product_name".to_string(),
+                "governing_law".to_string(),
+            ],
+            language_support: vec!["en".to_string(), "ja".to_string(), "fr".to_string()],
+        },
+        TemplateInfo {
+            id: "privacy".to_string(),
+            name: "Privacy Policy".to_string(),
+            description: "GDPR/CCPA-compliant privacy policy for data collection disclosure.".to_string(),
+            required_variables: vec![
+                "company_name".to_string(),
+                "contact_email".to_string(),
+                "data_collected".to_string(),
+            ],
+            language_support: vec!["en".to_string(), "ja".to_string(), "de".to_string(), "fr".to_string()],
+        },
+        TemplateInfo {
+            id: "employment".to_string(),
+            name: "Employment Agreement".to_string(),
+            description: "Standard employment contract with salary, IP assignment, and non-compete.".to_string(),
+            required_variables: vec![
+                "employer".to_string(),
+                "employee".to_string(),
+                "start_date".to_string(),
+                "salary".to_string(),
+                "position".to_string(),
+            ],
+            language_support: vec!["en".to_string(), "ja".to_string()],
+        },
+        TemplateInfo {
+            id: "license".to_string(),
+            name: "Software License Agreement".to_string(),
+            description: "Commercial software license with usage restrictions and royalties.".to_string(),
+            required_variables: vec![
+                "licensor".to_string(),
+                "licensee".to_string(),
+                "software_name".to_string(),
+                "license_fee".to_string(),
+            ],
+            language_support: vec!["en".to_string(), "de".to_string()],
+        },
+        TemplateInfo {
+            id: "renewal".to_string(),
+            name: "Renewal and Extension Amendment".to_string(),
+            description: "Extends an existing agreement's term, optionally with new pricing.".to_string(),
+            required_variables: vec![
+                "agreement_reference".to_string(),
+                "party_a".to_string(),
+                "party_b".to_string(),
+                "effective_date".to_string(),
+                "current_end_date".to_string(),
+                "new_end_date".to_string(),
+                "pricing".to_string(),
+            ],
+            language_support: vec!["en".to_string()],
+        },
+    ];
+    let tenant = notifier::tenant(&headers);
+    let lineage = state.orgs.lineage(&tenant);
+    templates.extend(state.custom_templates.visible(&lineage).into_iter().map(|t| TemplateInfo {
+        id: t.id,
+        name: t.name,
+        description: t.description,
+        required_variables: t.required_variables,
+        language_support: t.language_support,
+    }));
+
+    let count = templates.len();
+    Json(TemplatesResponse { templates, count })
+}
+
+async fn risk_score(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<RiskRequest>,
+) -> Result<Json<RiskScoreResponse>, StatusCode> {
+    score_request(&state, &notifier::tenant(&headers), req)
+        .await
+        .map(Json)
+}
+
+/// `risk_score` after the body is read: scores, explains when asked and
+/// records the history. Shared with the gRPC API.
+async fn score_request(
+    state: &AppState,
+    tenant: &str,
+    req: RiskRequest,
+) -> Result<RiskScoreResponse, StatusCode> {
+    if req.document.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let word_count = req.document.split_whitespace().count();
+    let mut response = state.score_risk(&req.document);
+    if req.explain {
+        let language = req.language.as_deref().unwrap_or("en");
+        response.explanation = Some(
+            explain::explain(
+                state,
+                tenant,
+                &req.document,
+                &response.risk_factors,
+                &response.risk_level,
+                response.overall_score,
+                language,
+            )
+            .await,
+        );
+    }
+
+    info!(
+        overall_score = response.overall_score,
+        risk_level = %response.risk_level,
+        word_count,
+        "risk score computed"
+    );
+
+    if let Some(call) = response
+        .explanation
+        .as_ref()
+        .and_then(|e| e.model_call.as_deref())
+    {
+        state.model_calls.link(call, &response.analysis_id);
+    }
+    match serde_json::to_value(&response) {
+        Ok(result) => history::record(
+            state,
+            HistoryKind::RiskScore,
+            &response.analysis_id,
+            tenant,
+            &req.document,
+            response.overall_score,
+            result,
+        ),
+        Err(e) => warn!(error = %e, "risk score history not saved"),
+    }
+
+    Ok(response)
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+fn extract_first_sentence(text: &str) -> String {
+    text.chars()
+        .take(120)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn keyword_confidence(hit: bool) -> f64 {
+    // Findings backed by an explicit keyword hit are trusted; the rest are guesses.
+    if hit {
+        0.9
+    } else {
+        0.35
+    }
+}
+
+/// Baseline → +factor → … → total, so UIs can chart what drives the score.
+/// Percentages are each factor's share of the overall score.
+fn build_waterfall(factors: &[RiskFactor]) -> Vec<WaterfallStep> {
+    let total: f64 = factors.iter().map(|f| f.weight * f.score).sum();
+    let mut running_total = 0.0;
+    let mut steps = vec![WaterfallStep {
+        label: "Baseline".to_string(),
+        contribution: 0.0,
+        contribution_percent: 0.0,
+        running_total,
+    }];
+    for f in factors {
+        let contribution = f.weight * f.score;
+        running_total += contribution;
+        steps.push(WaterfallStep {
+            label: f.factor.clone(),
+            contribution,
+            contribution_percent: if total > 0.0 { contribution / total * 100.0 } else { 0.0 },
+            running_total,
+        });
+    }
+    steps
+}
+
+/// IDs accepted by `get_template_body`, parsed and validated at startup.
+const BUILTIN_TEMPLATES: [&str; 8] = [
+    "nda", "sla", "dpa", "tos", "privacy", "employment", "license", "renewal",
+];
+
+fn get_template_body(template_id: &str) -> Option<String> {
+    match template_id {
+        "nda" => Some(
+            "NON-DISCLOSURE AGREEMENT\n\nThis Agreement is entered into between {{party_a}} \
+            and {{party_b}}, effective {{effective_date}}, governed by the laws of {{jurisdiction}}.\n\
+            \nAll confidential information shared between the parties shall remain strictly \
+            confidential for a period of three (3) years.".to_string()
+        ),
+        "sla" => Some(
+            "SERVICE LEVEL AGREEMENT\n\n{{service_provider}} agrees to provide services to \
+            {{customer}} with a minimum uptime of {{uptime_percent}}%.\n\
+            \nIncident response time shall not exceed {{response_time_hours}} hours.".to_string()
+        ),
+        "dpa" => Some(
+            "DATA PROCESSING AGREEMENT\n\n{{controller}} (Controller) and {{processor}} (Processor) \
+            enter into this DPA pursuant to GDPR Article 28.\n\
+            \nData types processed: {{data_types}}. Retention period: {{retention_period}}.\
+            {{#if data_categories}}\n\nSchedule 1. Categories of personal data:\n\
+            {{#each data_categories}}\n- {{name}}{{#if retention}}, retained for {{retention}}{{/if}}\n\
+            {{/each}}{{/if}}".to_string()
+        ),
+        "tos" => Some(
+            "TERMS OF SERVICE\n\n{{company_name}} operates {{product_name}}. By using our service, \
+            you agree to these terms.\n\
+            \nThis agreement is governed by the laws of {{governing_law}}.".to_string()
+        ),
+        "privacy" => Some(
+            "PRIVACY POLICY\n\n{{company_name}} is committed to protecting your privacy. \
+            Contact us at {{contact_email}}.\n\
+            \nWe collect the following data: {{data_collected}}.".to_string()
+        ),
+        "employment" => Some(
+            "EMPLOYMENT AGREEMENT\n\n{{employer}} employs {{employee}} as {{position}}, \
+            commencing {{start_date}}, at an annual salary of {{salary}}.".to_string()
+        ),
+        "license" => Some(
+            "SOFTWARE LICENSE AGREEMENT\n\n{{licensor}} grants {{licensee}} a non-exclusive license \
+            to use {{software_name}} subject to payment of {{license_fee}}.".to_string()
+        ),
+        "renewal" => Some(
+            "RENEWAL AND EXTENSION AMENDMENT\n\nThis Amendment to agreement {{agreement_reference}} \
+            is entered into between {{party_a}} and {{party_b}}, effective {{effective_date}}.\n\
+            \n1. Term. The term of the agreement is extended from {{current_end_date}} \
+            to {{new_end_date}}.\n\
+            \n2. Pricing. For the extended term the fees are {{pricing}}.\n\
+            \n3. All other terms of the agreement remain in full force and effect.".to_string()
+        ),
+        _ => None,
+    }
+}
+
+fn get_required_variables(template_id: &str) -> Vec<String> {
+    match template_id {
+        "nda" => vec!["party_a", "party_b", "effective_date", "jurisdiction"],
+        "sla" => vec!["service_provider", "customer", "uptime_percent", "response_time_hours"],
+        "dpa" => vec!["controller", "processor", "data_types", "retention_period"],
+        "tos" => vec!["company_name", "product_name", "governing_law"],
+        "privacy" => vec!["company_name", "contact_email", "data_collected"],
+        "employment" => vec!["employer", "employee", "start_date", "salary", "position"],
+        "license" => vec!["licensor", "licensee", "software_name", "license_fee"],
+        "renewal" => vec![
+            "agreement_reference",
+            "party_a",
+            "party_b",
+            "effective_date",
+            "current_end_date",
+            "new_end_date",
+            "pricing",
+        ],
+        _ => vec![],
+    }
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+// ── Router ────────────────────────────────────────────────────────────────────
+
+/// Every route on one listener, as served when no admin address is set.
+pub fn build_router(state: AppState) -> Router {
+    public_router(state.clone()).merge(admin_router(state))
+}
+
+/// Health, metrics, `/admin/inflight` and `/api/v1/legal/admin/*`, which
+/// `LEGAL_ADMIN_ADDR` moves onto their own listener.
+pub fn admin_router(state: AppState) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/health/warm", get(warmup::health_warm))
+        .route("/healthz", get(lifecycle::healthz))
+        .route("/readyz", get(lifecycle::readyz))
+        .route("/metrics", get(isolation::metrics))
+        .route(
+            "/api/v1/legal/admin/corpus/export",
+            get(training_export::export_corpus),
+        )
+        .route("/api/v1/legal/admin/corpus/storage", get(corpus::storage_stats))
+        .route("/api/v1/legal/admin/corpus/compact", post(corpus::compact))
+        .route(
+            "/api/v1/legal/admin/corpus/:id/scores",
+            get(rescore::history),
+        )
+        .route(
+            "/api/v1/legal/admin/rescore",
+            post(rescore::start)
+                .get(rescore::status)
+                .delete(rescore::pause),
+        )
+        .route("/api/v1/legal/admin/content", get(content_repo::get_status))
+        .route(
+            "/api/v1/legal/admin/content/refresh",
+            post(content_repo::refresh),
+        )
+        .route(
+            "/api/v1/legal/admin/tenants/:tenant/models",
+            get(models::list_models),
+        )
+        .route(
+            "/api/v1/legal/admin/tenants/:tenant/models/:kind",
+            put(models::put_model).delete(models::delete_model),
+        )
+        .route(
+            "/api/v1/legal/admin/tenants/:tenant/entity",
+            get(orgs::get_entity)
+                .put(orgs::put_entity)
+                .delete(orgs::delete_entity),
+        )
+        .route(
+            "/api/v1/legal/admin/variable-profiles",
+            get(variable_inference::list_profiles),
+        )
+        .route("/admin/inflight", get(inflight::list))
+        // The gateway only forwards `/api/v1`.
+        .route("/api/v1/legal/admin/inflight", get(inflight::list))
+        .route(
+            "/api/v1/legal/admin/tenants/:tenant/archive",
+            get(migration::export_tenant)
+                .post(migration::import_tenant)
+                .layer(DefaultBodyLimit::max(migration::MAX_ARCHIVE_BYTES)),
+        )
+        .layer(middleware::from_fn_with_state(state.clone(), deadline::enforce))
+        .layer(middleware::from_fn_with_state(state.clone(), ratelimit::limit))
+        .layer(middleware::from_fn_with_state(state.clone(), auth::authenticate_admin))
+        .with_state(state)
+}
+
+/// The gRPC API, which `LEGAL_GRPC_ADDR` serves on its own listener.
+pub fn grpc_router(state: AppState) -> Router {
+    grpc::router(state)
+}
+
+/// The API, OpenAPI document and Swagger UI.
+pub fn public_router(state: AppState) -> Router {
+    Router::new()
+        .route(openapi::SPEC_PATH, get(openapi::spec))
+        .route(openapi::DOCS_PATH, get(openapi::docs))
+        .route("/api/v1/legal/analyze", post(analyze))
+        .route(
+            "/api/v1/legal/analyze/batch",
+            post(batch::analyze_batch).layer(DefaultBodyLimit::max(state.ingest.max_bytes)),
+        )
+        .route("/api/v1/legal/analyze/bundle", post(splitting::analyze_bundle))
+        .route(
+            "/api/v1/legal/analyze/file",
+            post(ingest::analyze_file).layer(DefaultBodyLimit::max(state.ingest.max_bytes)),
+        )
+        .route("/api/v1/legal/analyze/stream", post(stream::analyze_stream))
+        .route("/api/v1/legal/analyze/async", post(jobs::submit))
+        .route("/api/v1/legal/jobs", post(jobs::submit))
+        .route(
+            "/api/v1/legal/jobs/:id",
+            get(jobs::get_job).delete(jobs::cancel_job),
+        )
+        .route("/api/v1/legal/compare", post(compare::compare_documents))
+        .route("/api/v1/legal/compile", post(compile))
+        .route("/api/v1/legal/compile/html", post(html_export::compile_html))
+        .route("/api/v1/legal/renderings/:id", get(render::download))
+        .route(
+            "/api/v1/legal/templates",
+            get(templates).post(custom_templates::create),
+        )
+        .route(
+            "/api/v1/legal/templates/analytics",
+            get(template_analytics::analytics),
+        )
+        .route(
+            "/api/v1/legal/templates/:id",
+            put(custom_templates::update).delete(custom_templates::delete),
+        )
+        .route(
+            "/api/v1/legal/templates/:id/versions",
+            get(custom_templates::versions),
+        )
+        .route(
+            "/api/v1/legal/templates/:id/versions/:n/restore",
+            post(custom_templates::restore_version),
+        )
+        .route("/api/v1/legal/macros", get(macros::list).post(macros::create))
+        .route(
+            "/api/v1/legal/macros/:name",
+            get(macros::get)
+                .put(macros::update)
+                .delete(macros::delete),
+        )
+        .route(
+            "/api/v1/legal/clause-library",
+            get(clause_library::list).post(clause_library::create),
+        )
+        .route(
+            "/api/v1/legal/clause-library/:id",
+            get(clause_library::get).put(clause_library::update),
+        )
+        .route(
+            "/api/v1/legal/clause-library/:id/approve",
+            post(clause_library::approve),
+        )
+        .route("/api/v1/legal/templates/:id/wizard", get(wizard::get_wizard))
+        .route("/api/v1/legal/templates/:id/preview", get(preview::preview))
+        .route(
+            "/api/v1/legal/templates/:id/revisions",
+            get(revisions::list_revisions).post(revisions::add_revision),
+        )
+        .route(
+            "/api/v1/legal/templates/:id/revisions/:a/diff/:b",
+            get(revisions::diff_revisions),
+        )
+        .route("/api/v1/legal/templates/:id/expanded", get(includes::get_expanded))
+        .route(
+            "/api/v1/legal/extract-variables",
+            post(variable_inference::extract),
+        )
+        .route(
+            "/api/v1/legal/extract-variables/:id/feedback",
+            post(variable_inference::feedback),
+        )
+        .route("/api/v1/legal/risk-score", post(risk_score))
+        .route("/api/v1/legal/risk-model", get(risk_model::active_model))
+        .route("/api/v1/legal/entities/validate", post(entity::validate))
+        .route("/api/v1/legal/conflicts/check", post(conflicts::check_document))
+        .route("/api/v1/legal/wizard-sessions", post(wizard::create_session))
+        .route("/api/v1/legal/wizard-sessions/:id", get(wizard::get_session))
+        .route("/api/v1/legal/wizard-sessions/:id/answers", post(wizard::answer))
+        .route(
+            "/api/v1/legal/wizard-sessions/:id/compile",
+            post(wizard::compile_session),
+        )
+        .route("/api/v1/legal/calibration/evaluate", post(calibration::evaluate))
+        .route(
+            "/api/v1/legal/outcomes",
+            get(backtest::list_outcomes)
+                .post(backtest::upload_outcomes)
+                .delete(backtest::delete_outcomes),
+        )
+        .route("/api/v1/legal/backtest", post(backtest::run_backtest))
+        .route("/api/v1/legal/compliance", post(compliance::check_document))
+        .route(
+            "/api/v1/legal/export-control/check",
+            post(export_control::check_document),
+        )
+        .route(
+            "/api/v1/legal/subcontracting/check",
+            post(subcontracting::check_document),
+        )
+        .route(
+            "/api/v1/legal/families/:id/flow-down",
+            get(subcontracting::family_flow_down),
+        )
+        .route(
+            "/api/v1/legal/families/:id/risk",
+            get(family_risk::family_risk),
+        )
+        .route(
+            "/api/v1/legal/export-control/policy",
+            get(export_control::get_policy).put(export_control::put_policy),
+        )
+        .route(
+            "/api/v1/legal/compile/policy",
+            get(jurisdiction_policy::get_policy).put(jurisdiction_policy::put_policy),
+        )
+        .route(
+            "/api/v1/legal/documents/:id/approvals",
+            post(signature::add_approval),
+        )
+        .route(
+            "/api/v1/legal/documents/:id/signature-gate",
+            post(signature::check_gate),
+        )
+        .route(
+            "/api/v1/legal/documents/:id/send-for-signature",
+            post(signature::send_for_signature),
+        )
+        .route(
+            "/api/v1/legal/documents/:id/execution",
+            post(signature::record_execution),
+        )
+        .route(
+            "/api/v1/legal/signing-authority",
+            get(authority::get_matrix)
+                .put(authority::put_matrix)
+                .delete(authority::delete_matrix),
+        )
+        .route("/api/v1/legal/diligence/export", post(diligence::export))
+        .route("/api/v1/legal/renewals/draft", post(renewal::draft))
+        .route("/api/v1/legal/analyses", get(history::list))
+        .route("/api/v1/legal/analyses/:id", get(access::get_analysis))
+        .route("/api/v1/legal/analyses/:id/summary", get(email::analysis_summary))
+        .route("/api/v1/legal/analyses/:id/clm-push", post(clm::push_analysis))
+        .route("/api/v1/legal/clm/connectors", get(clm::list_connectors))
+        .route("/api/v1/legal/analyses/:id/share", post(share::create_share))
+        .route("/api/v1/legal/analyses/:id/shares", get(share::list_shares))
+        .route("/api/v1/legal/shares/:id", delete(share::revoke_share))
+        .route("/api/v1/legal/shared/:token", get(share::open_share))
+        .route("/api/v1/legal/tenants/:id/data", delete(erasure::delete_tenant_data))
+        .route("/api/v1/legal/erasure", post(erasure::erase))
+        .route(
+            "/api/v1/legal/analyses/:id/annotations",
+            get(reviews::history),
+        )
+        .route(
+            "/api/v1/legal/analyses/:id/annotations/import",
+            post(reviews::import),
+        )
+        .route("/api/v1/legal/audit", get(audit::list_entries))
+        .route("/api/v1/legal/abstract", post(summary::abstract_document))
+        .route(
+            "/api/v1/legal/obligations",
+            post(obligations::extract_obligations),
+        )
+        .route(
+            "/api/v1/legal/analyses/:id/obligations/sync",
+            post(obligations::sync_obligations),
+        )
+        .route(
+            "/api/v1/legal/obligations/register",
+            get(obligations::get_register),
+        )
+        .route(
+            "/api/v1/legal/obligations/register/:id",
+            patch(obligations::update_obligation),
+        )
+        .route("/api/v1/legal/notices/extract", post(notices::extract_contacts))
+        .route(
+            "/api/v1/legal/analyses/:id/notice-contacts/sync",
+            post(notices::sync_contacts),
+        )
+        .route("/api/v1/legal/address-book", get(notices::get_address_book))
+        .route("/api/v1/legal/escalations", get(escalation::list_escalations))
+        .route(
+            "/api/v1/legal/escalations/:id/resolve",
+            post(escalation::resolve_escalation),
+        )
+        .route(
+            "/api/v1/legal/regulatory/rules",
+            get(regulatory::list_rules).post(regulatory::upsert_rule),
+        )
+        .route("/api/v1/legal/regulatory/rules/:id", delete(regulatory::delete_rule))
+        .route("/api/v1/legal/regulatory/scan", post(regulatory::scan))
+        .route(
+            "/api/v1/legal/lexicon",
+            get(lexicon::get_lexicon)
+                .put(lexicon::put_lexicon)
+                .delete(lexicon::delete_lexicon),
+        )
+        .route("/api/v1/legal/embeddings", post(models::embed))
+        .route("/api/v1/legal/model-calls", get(model_calls::list_calls))
+        .route(
+            "/api/v1/legal/model-calls/settings",
+            get(model_calls::get_settings).put(model_calls::put_settings),
+        )
+        .route(
+            "/api/v1/legal/style-profile",
+            get(style::get_profile)
+                .put(style::put_profile)
+                .delete(style::delete_profile),
+        )
+        .route("/api/v1/legal/style/check", post(style::check_document))
+        .route(
+            "/api/v1/legal/playbook",
+            get(playbook::get_playbook)
+                .put(playbook::put_playbook)
+                .delete(playbook::delete_playbook),
+        )
+        .route("/api/v1/legal/org", get(orgs::get_org))
+        .route("/api/v1/legal/org/rollup", get(orgs::rollup))
+        .route("/api/v1/usage", get(ratelimit::usage))
+        // The path the endpoint was first served at.
+        .route("/api/v1/legal/usage", get(ratelimit::usage))
+        .route("/api/v1/webhooks", get(webhooks::list).post(webhooks::register))
+        .route("/api/v1/webhooks/:id", delete(webhooks::delete))
+        .layer(middleware::from_fn_with_state(state.clone(), deadline::enforce))
+        .layer(middleware::from_fn_with_state(state.clone(), ratelimit::limit))
+        .layer(middleware::from_fn_with_state(state.clone(), auth::authenticate))
+        .with_state(state)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn factor(name: &str, weight: f64, score: f64) -> RiskFactor {
+        RiskFactor {
+            factor: name.to_string(),
+            weight,
+            score,
+            description: String::new(),
+        }
+    }
+
+    #[test]
+    fn waterfall_accumulates_to_overall_score() {
+        let factors = vec![factor("Liability", 0.5, 0.8), factor("Indemnity", 0.5, 0.4)];
+        let steps = build_waterfall(&factors);
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[0].label, "Baseline");
+        assert!((steps[1].running_total - 0.4).abs() < 1e-9);
+        assert!((steps[2].running_total - 0.6).abs() < 1e-9);
+        let pct: f64 = steps.iter().map(|s| s.contribution_percent).sum();
+        assert!((pct - 100.0).abs() < 1e-9);
+        assert!((steps[1].contribution_percent - 66.666_666_666).abs() < 1e-6);
+    }
+
+    #[test]
+    fn waterfall_zero_score_has_zero_percentages() {
+        let steps = build_waterfall(&[factor("Liability", 0.3, 0.0)]);
+        assert!(steps.iter().all(|s| s.contribution_percent == 0.0));
+    }
+
+    #[test]
+    fn cancelled_analysis_stops_before_classification() {
+        let state = AppState::in_memory();
+        let cancel = CancelFlag::default();
+        cancel.cancel();
+        let opts = AnalysisOptions {
+            mode: AnalysisMode::Standard,
+            deadline: Deadline::after(std::time::Duration::from_secs(60), std::time::Duration::ZERO),
+            context_chars: 0,
+            fallback_clauses: false,
+            cancel: Some(&cancel),
+            latency: None,
+            keywords: None,
+            tenant: notifier::DEFAULT_TENANT,
+            ml: None,
+            model_call: None,
+            progress: None,
+            origin: inflight::Origin::Request,
+            our_party: None,
+        };
+        let response = run_analysis(&state, "A short letter.", "en", opts);
+        assert!(response.partial);
+        // Paper detection may pick deep review; its stages are skipped too.
+        assert_eq!(response.skipped_stages.first().map(String::as_str), Some("classification"));
+        assert_eq!(response.skipped_stages.last().map(String::as_str), Some("escalation"));
+        assert!(response.clauses.is_empty());
+        assert!(state.escalations.pending().is_empty());
+    }
+
+    #[test]
+    fn serving_needs_credentials_or_an_explicit_opt_out() {
+        assert!(AppState::in_memory().require_authentication().is_err());
+        let trusting = AppState::in_memory().trusting_identity_headers();
+        assert!(trusting.require_authentication().is_ok());
+        let keyed = AppState {
+            auth: Some(Arc::new(AuthConfig::new(Vec::new()).with_jwt_secret("shared"))),
+            ..AppState::in_memory()
+        };
+        assert!(keyed.require_authentication().is_ok());
+    }
+
+    #[test]
+    fn the_engine_analyzes_scores_and_compiles_without_http() {
+        let state = AppState::in_memory();
+        state.start().unwrap();
+        let document = "Either party may terminate this Agreement upon 30 days notice. \
+                        The Vendor shall indemnify the Client.";
+        let analysis = state.analyze_document("acme", document, "en");
+        assert!(!analysis.clauses.is_empty());
+        assert_eq!(state.corpus.get(&analysis.analysis_id).unwrap().tenant, "acme");
+
+        let risk = state.score_risk(document);
+        assert_eq!(risk.waterfall.len(), risk.risk_factors.len() + 1);
+        assert!(risk.explanation.is_none());
+
+        let variables = HashMap::from([("party_a".to_string(), "Acme Corp".to_string())]);
+        let nda = state.compile_document("nda", &variables).unwrap();
+        assert!(nda.compiled_document.contains("Acme Corp"));
+        assert_eq!(nda.variables_applied, 1);
+        assert_eq!(
+            state.compile_document("lease", &variables).err(),
+            Some(StatusCode::NOT_FOUND)
+        );
+    }
+}
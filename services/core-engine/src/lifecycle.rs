@@ -0,0 +1,266 @@
+//! Probes and shutdown. `/healthz` says the process is alive; `/readyz`
+//! says it should get traffic: templates loaded, the history store
+//! answering, jobs accepted and, when configured, the content repository
+//! loaded. On shutdown the instance turns unready and stops taking jobs,
+//! then waits up to the grace period for queued and running jobs and
+//! in-flight analyses before its listeners close, so a rollout does not
+//! kill work half done.
+
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde::Serialize;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::AppState;
+
+/// How often a drain checks whether the work has finished.
+const DRAIN_POLL: Duration = Duration::from_millis(100);
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct ShutdownConfig {
+    /// How long a shutdown waits for in-flight work before closing anyway.
+    pub grace: Duration,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            grace: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ShutdownConfig {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        Self {
+            grace: std::env::var("LEGAL_SHUTDOWN_GRACE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map_or(d.grace, Duration::from_secs),
+        }
+    }
+}
+
+// ── State ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug)]
+pub struct Lifecycle {
+    config: ShutdownConfig,
+    /// `true` once a shutdown has begun.
+    draining: watch::Sender<bool>,
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self::new(ShutdownConfig::default())
+    }
+}
+
+impl Lifecycle {
+    pub fn new(config: ShutdownConfig) -> Self {
+        Self {
+            config,
+            draining: watch::channel(false).0,
+        }
+    }
+
+    pub fn is_draining(&self) -> bool {
+        *self.draining.borrow()
+    }
+
+    /// Turns the instance unready and stops job intake. Idempotent.
+    pub fn begin(&self) {
+        if !self.draining.send_replace(true) {
+            info!(
+                grace_secs = self.config.grace.as_secs(),
+                "shutdown requested; draining"
+            );
+        }
+    }
+
+    /// Resolves once a shutdown has begun.
+    async fn begun(&self) {
+        let mut rx = self.draining.subscribe();
+        // The sender lives as long as `self`, so this only ends on `true`.
+        let _ = rx.wait_for(|draining| *draining).await;
+    }
+}
+
+/// Work that a shutdown waits for.
+fn busy(state: &AppState) -> usize {
+    state.jobs.pending() + state.inflight.running()
+}
+
+/// Waits until no job is queued or running and no analysis is in the
+/// pipeline, or the grace period is over. `true` when everything finished.
+pub async fn drain(state: &AppState) -> bool {
+    let deadline = tokio::time::Instant::now() + state.lifecycle.config.grace;
+    loop {
+        let busy = busy(state);
+        if busy == 0 {
+            info!("in-flight work drained");
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            warn!(busy, "shutdown grace period over with work in flight");
+            return false;
+        }
+        tokio::time::sleep(DRAIN_POLL).await;
+    }
+}
+
+/// Resolves once a shutdown has begun and the work has drained; passed to
+/// the server's graceful shutdown, which then closes the listeners and
+/// waits for open requests.
+pub async fn stopped(state: AppState) {
+    state.lifecycle.begun().await;
+    drain(&state).await;
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct Liveness {
+    pub status: &'static str,
+    pub uptime_secs: u64,
+}
+
+/// Liveness: answers as long as the process serves requests, draining or not.
+pub async fn healthz(State(state): State<AppState>) -> Json<Liveness> {
+    Json(Liveness {
+        status: "alive",
+        uptime_secs: state.start_time.elapsed().as_secs(),
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessCheck {
+    pub name: &'static str,
+    pub ready: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    pub status: &'static str,
+    pub draining: bool,
+    pub checks: Vec<ReadinessCheck>,
+}
+
+fn check(name: &'static str, result: Result<(), String>) -> ReadinessCheck {
+    ReadinessCheck {
+        name,
+        ready: result.is_ok(),
+        detail: result.err(),
+    }
+}
+
+/// Readiness: 200 when every dependency is available, 503 otherwise and
+/// from the moment a shutdown begins.
+pub async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<Readiness>) {
+    let mut checks = vec![
+        check(
+            "templates",
+            if state.precompiled.is_warm() {
+                Ok(())
+            } else {
+                Err("not precompiled yet".to_string())
+            },
+        ),
+        check("history_store", state.history.ping()),
+        check(
+            "job_queue",
+            if state.lifecycle.is_draining() {
+                Err("draining for shutdown".to_string())
+            } else {
+                Ok(())
+            },
+        ),
+    ];
+    if let Some(repo) = &state.content_repo {
+        let status = repo.status();
+        checks.push(check(
+            "content_repository",
+            match (status.commit, status.last_error) {
+                (Some(_), _) => Ok(()),
+                (None, Some(e)) => Err(e),
+                (None, None) => Err("not loaded yet".to_string()),
+            },
+        ));
+    }
+    let ready = checks.iter().all(|c| c.ready);
+    (
+        if ready {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        },
+        Json(Readiness {
+            status: if ready { "ready" } else { "not_ready" },
+            draining: state.lifecycle.is_draining(),
+            checks,
+        }),
+    )
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inflight::Origin;
+
+    fn state(grace: Duration) -> AppState {
+        AppState {
+            lifecycle: std::sync::Arc::new(Lifecycle::new(ShutdownConfig { grace })),
+            ..AppState::in_memory()
+        }
+    }
+
+    #[tokio::test]
+    async fn stops_once_the_running_analysis_finishes() {
+        let state = state(Duration::from_secs(5));
+        let stopped = tokio::spawn(stopped(state.clone()));
+
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let worker = state.clone();
+        let analysis = tokio::task::spawn_blocking(move || {
+            let _tracker = worker
+                .inflight
+                .begin(Origin::Request, "acme", 10, worker.clock.now());
+            let _ = rx.blocking_recv();
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        state.lifecycle.begin();
+        state.lifecycle.begin();
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(
+            !stopped.is_finished(),
+            "stopped before the analysis finished"
+        );
+
+        tx.send(()).unwrap();
+        analysis.await.unwrap();
+        tokio::time::timeout(Duration::from_secs(2), stopped)
+            .await
+            .expect("drain did not notice the finished analysis")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_grace_period() {
+        let state = state(Duration::from_millis(150));
+        let _stuck = state
+            .inflight
+            .begin(Origin::Stream, "acme", 10, state.clock.now());
+        state.lifecycle.begin();
+        assert!(!drain(&state).await);
+        assert!(state.lifecycle.is_draining());
+    }
+}
@@ -0,0 +1,505 @@
+//! Shared boilerplate (notices, severability, entire agreement,
+//! counterparts, …) that templates pull in with
+//! `{{> macro/name key=value}}`. Every edit is a new version, and compile
+//! always renders the latest one, so a fix to a clause reaches every
+//! template that uses it the next time it is compiled.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::RwLock,
+};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use crate::{warmup::ParsedTemplate, AppState};
+
+const MAX_NAME_LEN: usize = 64;
+
+/// Seeded as version 1 of each built-in macro.
+const BUILTIN_MACROS: [(&str, &str, &str); 4] = [
+    (
+        "notices",
+        "Where and how formal notices are delivered.",
+        "Notices. All notices under this Agreement shall be in writing and delivered \
+         to {{party_a}} at {{party_a_address}} and to {{party_b}} at \
+         {{party_b_address}}, or to such other address as a party designates by notice.\n",
+    ),
+    (
+        "severability",
+        "Keeps the rest of the agreement in force if one provision fails.",
+        "Severability. If any provision of this Agreement is held invalid or \
+         unenforceable, the remaining provisions shall continue in full force and effect.\n",
+    ),
+    (
+        "entire_agreement",
+        "Supersedes prior agreements between the parties.",
+        "Entire Agreement. This Agreement constitutes the entire agreement between \
+         {{party_a}} and {{party_b}} with respect to its subject matter and supersedes \
+         all prior agreements and understandings.\n",
+    ),
+    (
+        "counterparts",
+        "Allows signing in counterparts and electronically.",
+        "Counterparts. This Agreement may be executed in counterparts, including by \
+         electronic signature, each of which is an original and all of which together \
+         constitute one instrument.\n",
+    ),
+];
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MacroVersion {
+    pub version: u32,
+    pub body: String,
+    pub note: Option<String>,
+    /// `None` for the built-in version.
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Macro {
+    pub name: String,
+    pub description: String,
+    /// Placeholders of the latest body; pass them as `key=value` or let
+    /// them resolve against the template's own values.
+    pub parameters: Vec<String>,
+    pub built_in: bool,
+    pub versions: Vec<MacroVersion>,
+}
+
+impl Macro {
+    fn latest(&self) -> &MacroVersion {
+        self.versions
+            .last()
+            .expect("a macro has at least one version")
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct MacroSummary {
+    pub name: String,
+    pub description: String,
+    pub parameters: Vec<String>,
+    pub built_in: bool,
+    pub version: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MacrosResponse {
+    pub macros: Vec<MacroSummary>,
+    pub count: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewMacroRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MacroUpdateRequest {
+    pub body: String,
+    /// Replaces the description when set.
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+// ── Validation ────────────────────────────────────────────────────────────────
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Parameters of `body`, if it parses and every macro it uses exists in
+/// `macros` (with `name` given `body`) without forming a cycle.
+fn check(macros: &BTreeMap<String, Macro>, name: &str, body: &str) -> Result<Vec<String>, String> {
+    let parsed = ParsedTemplate::parse(body)?;
+    let uses = |m: &str| -> Result<BTreeSet<String>, String> {
+        let body = if m == name {
+            body.to_string()
+        } else {
+            macros
+                .get(m)
+                .map(|existing| existing.latest().body.clone())
+                .ok_or_else(|| format!("unknown macro {m}"))?
+        };
+        Ok(ParsedTemplate::parse(&body)?
+            .macros()
+            .into_iter()
+            .map(str::to_string)
+            .collect())
+    };
+    // Depth-first from `name`; the only possible new cycle runs through it.
+    let mut stack: Vec<(String, Vec<String>)> = vec![(name.to_string(), vec![name.to_string()])];
+    let mut seen = BTreeSet::new();
+    while let Some((current, path)) = stack.pop() {
+        for next in uses(&current)? {
+            if next == name {
+                return Err(format!("macro cycle: {} -> {name}", path.join(" -> ")));
+            }
+            if seen.insert(next.clone()) {
+                let mut path = path.clone();
+                path.push(next.clone());
+                stack.push((next, path));
+            }
+        }
+    }
+    Ok(parsed
+        .placeholders()
+        .into_iter()
+        .map(str::to_string)
+        .collect())
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+/// The macro library, seeded with the built-in macros. One lock covers it
+/// so a save is checked for cycles against a consistent library.
+pub struct MacroStore {
+    macros: RwLock<BTreeMap<String, Macro>>,
+}
+
+impl Default for MacroStore {
+    fn default() -> Self {
+        let macros = BUILTIN_MACROS
+            .iter()
+            .map(|(name, description, body)| {
+                let parameters = ParsedTemplate::parse(body)
+                    .expect("built-in macros parse")
+                    .placeholders()
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect();
+                (
+                    name.to_string(),
+                    Macro {
+                        name: name.to_string(),
+                        description: description.to_string(),
+                        parameters,
+                        built_in: true,
+                        versions: vec![MacroVersion {
+                            version: 1,
+                            body: body.to_string(),
+                            note: Some("built-in".to_string()),
+                            created_at: None,
+                        }],
+                    },
+                )
+            })
+            .collect();
+        Self {
+            macros: RwLock::new(macros),
+        }
+    }
+}
+
+impl MacroStore {
+    pub fn get(&self, name: &str) -> Option<Macro> {
+        self.macros.read().unwrap().get(name).cloned()
+    }
+
+    pub fn list(&self) -> Vec<MacroSummary> {
+        self.macros
+            .read()
+            .unwrap()
+            .values()
+            .map(|m| MacroSummary {
+                name: m.name.clone(),
+                description: m.description.clone(),
+                parameters: m.parameters.clone(),
+                built_in: m.built_in,
+                version: m.latest().version,
+            })
+            .collect()
+    }
+
+    /// Latest body of every macro, as compile renders them.
+    pub fn bodies(&self) -> BTreeMap<String, String> {
+        self.macros
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, m)| (name.clone(), m.latest().body.clone()))
+            .collect()
+    }
+
+    /// Latest version of every macro, by name.
+    pub fn versions(&self) -> BTreeMap<String, u32> {
+        self.macros
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, m)| (name.clone(), m.latest().version))
+            .collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.macros.read().unwrap().contains_key(name)
+    }
+
+    /// `Ok(None)` when the name is taken.
+    pub fn create(&self, req: NewMacroRequest, at: DateTime<Utc>) -> Result<Option<Macro>, String> {
+        let mut macros = self.macros.write().unwrap();
+        if macros.contains_key(&req.name) {
+            return Ok(None);
+        }
+        let parameters = check(&macros, &req.name, &req.body)?;
+        let created = Macro {
+            name: req.name.clone(),
+            description: req.description,
+            parameters,
+            built_in: false,
+            versions: vec![MacroVersion {
+                version: 1,
+                body: req.body,
+                note: None,
+                created_at: Some(at),
+            }],
+        };
+        macros.insert(req.name, created.clone());
+        Ok(Some(created))
+    }
+
+    /// Adds the next version; `Ok(None)` for an unknown macro.
+    pub fn update(
+        &self,
+        name: &str,
+        req: MacroUpdateRequest,
+        at: DateTime<Utc>,
+    ) -> Result<Option<Macro>, String> {
+        let mut macros = self.macros.write().unwrap();
+        if !macros.contains_key(name) {
+            return Ok(None);
+        }
+        let parameters = check(&macros, name, &req.body)?;
+        let Some(existing) = macros.get_mut(name) else {
+            return Ok(None);
+        };
+        let version = existing.latest().version + 1;
+        existing.versions.push(MacroVersion {
+            version,
+            body: req.body,
+            note: req.note,
+            created_at: Some(at),
+        });
+        existing.parameters = parameters;
+        if let Some(description) = req.description {
+            existing.description = description;
+        }
+        Ok(Some(existing.clone()))
+    }
+
+    /// Names of the other macros whose latest body uses `name`.
+    fn used_by(macros: &BTreeMap<String, Macro>, name: &str) -> Vec<String> {
+        macros
+            .values()
+            .filter(|m| m.name != name)
+            .filter(|m| {
+                ParsedTemplate::parse(&m.latest().body).is_ok_and(|p| p.macros().contains(name))
+            })
+            .map(|m| m.name.clone())
+            .collect()
+    }
+
+    /// `Err` with the macros that still use it, `Ok(false)` when unknown.
+    pub fn remove(&self, name: &str) -> Result<bool, Vec<String>> {
+        let mut macros = self.macros.write().unwrap();
+        let users = Self::used_by(&macros, name);
+        if !users.is_empty() {
+            return Err(users);
+        }
+        Ok(macros.remove(name).is_some())
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+fn rejected(name: &str, error: String) -> Response {
+    info!(name = %name, error = %error, "macro rejected");
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(json!({ "error": error })),
+    )
+        .into_response()
+}
+
+pub async fn list(State(state): State<AppState>) -> Json<MacrosResponse> {
+    let macros = state.macros.list();
+    let count = macros.len();
+    Json(MacrosResponse { macros, count })
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Macro>, StatusCode> {
+    state
+        .macros
+        .get(&name)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    Json(req): Json<NewMacroRequest>,
+) -> Result<(StatusCode, Json<Macro>), Response> {
+    if !valid_name(&req.name) || req.body.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+    let name = req.name.clone();
+    let created = state
+        .macros
+        .create(req, state.clock.now())
+        .map_err(|e| rejected(&name, e))?
+        .ok_or_else(|| StatusCode::CONFLICT.into_response())?;
+    info!(name = %name, "macro created");
+    state.record_audit("macro.created", &name, None, json!({ "version": 1 }));
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+pub async fn update(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(req): Json<MacroUpdateRequest>,
+) -> Result<Json<Macro>, Response> {
+    if req.body.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+    let updated = state
+        .macros
+        .update(&name, req, state.clock.now())
+        .map_err(|e| rejected(&name, e))?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+    let version = updated.latest().version;
+    info!(name = %name, version, "macro updated");
+    state.record_audit("macro.updated", &name, None, json!({ "version": version }));
+    Ok(Json(updated))
+}
+
+pub async fn delete(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, Response> {
+    let templates = state.custom_templates.using_macro(&name);
+    if !templates.is_empty() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "macro is in use", "templates": templates })),
+        )
+            .into_response());
+    }
+    match state.macros.remove(&name) {
+        Ok(true) => {}
+        Ok(false) => return Err(StatusCode::NOT_FOUND.into_response()),
+        Err(macros) => {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({ "error": "macro is in use", "macros": macros })),
+            )
+                .into_response())
+        }
+    }
+    info!(name = %name, "macro deleted");
+    state.record_audit("macro.deleted", &name, None, json!({}));
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new(name: &str, body: &str) -> NewMacroRequest {
+        NewMacroRequest {
+            name: name.to_string(),
+            description: String::new(),
+            body: body.to_string(),
+        }
+    }
+
+    fn edit(body: &str) -> MacroUpdateRequest {
+        MacroUpdateRequest {
+            body: body.to_string(),
+            description: None,
+            note: Some("fix".to_string()),
+        }
+    }
+
+    #[test]
+    fn builtins_are_seeded_with_their_parameters() {
+        let store = MacroStore::default();
+        let notices = store.get("notices").unwrap();
+        assert!(notices.built_in);
+        assert_eq!(
+            notices.parameters,
+            ["party_a", "party_a_address", "party_b", "party_b_address"]
+        );
+        assert_eq!(store.list().len(), BUILTIN_MACROS.len());
+    }
+
+    #[test]
+    fn updates_add_versions_and_change_the_rendered_body() {
+        let store = MacroStore::default();
+        let now = Utc::now();
+        let updated = store
+            .update("severability", edit("Severability. {{scope}}\n"), now)
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.versions.len(), 2);
+        assert_eq!(updated.parameters, ["scope"]);
+        assert_eq!(store.bodies()["severability"], "Severability. {{scope}}\n");
+        assert!(store.update("nope", edit("x"), now).unwrap().is_none());
+    }
+
+    #[test]
+    fn unknown_macros_and_cycles_are_rejected() {
+        let store = MacroStore::default();
+        let now = Utc::now();
+        let err = store
+            .create(new("closing", "{{> macro/missing}}"), now)
+            .unwrap_err();
+        assert_eq!(err, "unknown macro missing");
+        store
+            .create(new("closing", "{{> macro/counterparts}}"), now)
+            .unwrap()
+            .unwrap();
+        assert!(store.create(new("closing", "x"), now).unwrap().is_none());
+        let err = store
+            .update("counterparts", edit("{{> macro/closing}}"), now)
+            .unwrap_err();
+        assert!(err.starts_with("macro cycle"), "{err}");
+        assert_eq!(
+            store.remove("counterparts"),
+            Err(vec!["closing".to_string()])
+        );
+        assert_eq!(store.remove("closing"), Ok(true));
+        assert_eq!(store.remove("closing"), Ok(false));
+    }
+
+    #[test]
+    fn names_are_lowercase_identifiers() {
+        assert!(valid_name("entire_agreement2"));
+        assert!(!valid_name("entire-agreement"));
+        assert!(!valid_name("Notices"));
+        assert!(!valid_name(""));
+    }
+}
@@ -0,0 +1,964 @@
+//! Tenant migration archives, for moving a tenant between instances (staging
+//! to production, one region to another) without scripts. The export is a
+//! zip of one JSON file per section — the tenant's custom templates,
+//! lexicon, signing authority matrix, playbook and analysis history, plus
+//! the clause library and regulatory rules its templates and scans rely on,
+//! which are instance-wide — and a manifest with the SHA-256 of each. The
+//! import checks every file against the manifest and every record against
+//! the rules its own endpoint applies before anything is written, then
+//! reports what it created, updated, left alone or skipped. Records that
+//! exist with different content are conflicts, resolved as the caller chose.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{Cursor, Read, Write},
+};
+
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use crate::{
+    authority::AuthorityMatrix,
+    clause_library::LibraryClause,
+    custom_templates::{self, CustomTemplate, Registered, TemplateVersion},
+    history::{HistoryQuery, HistoryRecord, HistorySummary},
+    lexicon::Lexicon,
+    playbook::Playbook,
+    regulatory::DeprecationRule,
+    warmup::ParsedTemplate,
+    AppState, BUILTIN_TEMPLATES,
+};
+
+pub const ARCHIVE_FORMAT: &str = "alice-legal-tenant-archive";
+/// Bumped when a section changes shape; older archives stay importable.
+pub const ARCHIVE_VERSION: u32 = 3;
+/// Largest archive `POST` accepts.
+pub const MAX_ARCHIVE_BYTES: usize = 64 * 1024 * 1024;
+const MANIFEST: &str = "manifest.json";
+
+const TEMPLATES: &str = "templates";
+/// Added in version 2.
+const TEMPLATE_VERSIONS: &str = "template_versions";
+const CLAUSES: &str = "clauses";
+const REGULATORY_RULES: &str = "regulatory_rules";
+const LEXICON: &str = "lexicon";
+const AUTHORITY_MATRIX: &str = "authority_matrix";
+/// Added in version 3.
+const PLAYBOOK: &str = "playbook";
+const ANALYSES: &str = "analyses";
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: String,
+    pub version: u32,
+    /// The tenant the archive was exported from.
+    pub tenant: String,
+    pub exported_at: DateTime<Utc>,
+    pub engine_version: String,
+    /// By file name.
+    pub files: BTreeMap<String, ManifestFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub sha256: String,
+    pub bytes: u64,
+    pub records: usize,
+}
+
+/// One analysis history record as archived.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedAnalysis {
+    #[serde(flatten)]
+    pub summary: HistorySummary,
+    pub result: Value,
+}
+
+/// Everything an archive carries.
+#[derive(Debug, Default)]
+struct Contents {
+    templates: Vec<CustomTemplate>,
+    template_versions: BTreeMap<String, Vec<TemplateVersion>>,
+    clauses: Vec<LibraryClause>,
+    regulatory_rules: Vec<DeprecationRule>,
+    lexicon: Option<Lexicon>,
+    authority_matrix: Option<AuthorityMatrix>,
+    playbook: Option<Playbook>,
+    analyses: Vec<ArchivedAnalysis>,
+}
+
+/// What to do with a record that exists with different content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnConflict {
+    /// Import nothing and answer `409` with the conflicts.
+    #[default]
+    Fail,
+    /// Keep what is there.
+    Skip,
+    /// Replace it with the archived record.
+    Overwrite,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub on_conflict: OnConflict,
+    /// Check and report without writing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    Skipped,
+    Overwritten,
+    /// `on_conflict=fail`: the import was refused.
+    Unresolved,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportConflict {
+    pub section: &'static str,
+    pub id: String,
+    pub resolution: Resolution,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SectionCounts {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub skipped: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub tenant: String,
+    pub source_tenant: String,
+    pub exported_at: DateTime<Utc>,
+    pub on_conflict: OnConflict,
+    pub dry_run: bool,
+    pub sections: BTreeMap<&'static str, SectionCounts>,
+    pub conflicts: Vec<ImportConflict>,
+    /// Imported templates that will not compile here as they stand, e.g.
+    /// for a macro this instance lacks.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    /// Not a zip, or no readable manifest.
+    Unreadable(String),
+    /// A file is missing, altered or not in the manifest.
+    Integrity(String),
+    /// A record its own endpoint would reject.
+    Invalid(String),
+    Conflicts(Box<ImportReport>),
+    /// A store failed; records before it may have been written.
+    Store(String),
+}
+
+impl IntoResponse for ImportError {
+    fn into_response(self) -> Response {
+        let (status, body) = match self {
+            Self::Unreadable(e) | Self::Integrity(e) | Self::Invalid(e) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, json!({ "error": e }))
+            }
+            Self::Conflicts(report) => (
+                StatusCode::CONFLICT,
+                serde_json::to_value(&report).unwrap_or(Value::Null),
+            ),
+            Self::Store(e) => {
+                warn!(error = %e, "tenant import aborted");
+                (StatusCode::INTERNAL_SERVER_ERROR, json!({ "error": e }))
+            }
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+// ── Export ────────────────────────────────────────────────────────────────────
+
+fn collect(state: &AppState, tenant: &str) -> Result<Contents, String> {
+    let query = HistoryQuery {
+        tenant: tenant.to_string(),
+        limit: i64::MAX as usize,
+        ..HistoryQuery::default()
+    };
+    let mut analyses = Vec::new();
+    // Oldest first, so an import replays them in the order they happened.
+    for summary in state.history.list(&query)?.into_iter().rev() {
+        if let Some(record) = state.history.get(&summary.id)? {
+            analyses.push(ArchivedAnalysis {
+                summary: record.summary,
+                result: record.result,
+            });
+        }
+    }
+    let templates = state.custom_templates.list(tenant);
+    let template_versions = templates
+        .iter()
+        .filter_map(|t| Some((t.id.clone(), state.custom_templates.versions(&t.id)?)))
+        .collect();
+    Ok(Contents {
+        templates,
+        template_versions,
+        clauses: state.clause_library.all(),
+        regulatory_rules: state.regulatory.list(),
+        lexicon: state
+            .lexicons
+            .matcher(tenant)
+            .and_then(|m| m.lexicon().cloned()),
+        authority_matrix: state.authority.get(tenant),
+        playbook: state.playbooks.get(tenant),
+        analyses,
+    })
+}
+
+fn section<T: Serialize>(name: &str, records: usize, value: &T) -> (String, usize, Vec<u8>) {
+    let bytes = serde_json::to_vec_pretty(value).expect("archive sections serialize");
+    (format!("{name}.json"), records, bytes)
+}
+
+/// The archive of `tenant`'s data, and its manifest.
+pub fn export(state: &AppState, tenant: &str) -> Result<(Vec<u8>, Manifest), String> {
+    let contents = collect(state, tenant)?;
+    let files = [
+        section(TEMPLATES, contents.templates.len(), &contents.templates),
+        section(
+            TEMPLATE_VERSIONS,
+            contents.template_versions.values().map(Vec::len).sum(),
+            &contents.template_versions,
+        ),
+        section(CLAUSES, contents.clauses.len(), &contents.clauses),
+        section(
+            REGULATORY_RULES,
+            contents.regulatory_rules.len(),
+            &contents.regulatory_rules,
+        ),
+        section(
+            LEXICON,
+            usize::from(contents.lexicon.is_some()),
+            &contents.lexicon,
+        ),
+        section(
+            AUTHORITY_MATRIX,
+            usize::from(contents.authority_matrix.is_some()),
+            &contents.authority_matrix,
+        ),
+        section(
+            PLAYBOOK,
+            usize::from(contents.playbook.is_some()),
+            &contents.playbook,
+        ),
+        section(ANALYSES, contents.analyses.len(), &contents.analyses),
+    ];
+    let manifest = Manifest {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        tenant: tenant.to_string(),
+        exported_at: state.clock.now(),
+        engine_version: env!("CARGO_PKG_VERSION").to_string(),
+        files: files
+            .iter()
+            .map(|(name, records, bytes)| {
+                let file = ManifestFile {
+                    sha256: format!("{:x}", Sha256::digest(bytes)),
+                    bytes: bytes.len() as u64,
+                    records: *records,
+                };
+                (name.clone(), file)
+            })
+            .collect(),
+    };
+    let write = || -> zip::result::ZipResult<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(MANIFEST, SimpleFileOptions::default())?;
+        zip.write_all(&serde_json::to_vec_pretty(&manifest).expect("manifest serializes"))?;
+        for (name, _, bytes) in &files {
+            zip.start_file(name.as_str(), SimpleFileOptions::default())?;
+            zip.write_all(bytes)?;
+        }
+        Ok(zip.finish()?.into_inner())
+    };
+    let archive = write().map_err(|e| e.to_string())?;
+    Ok((archive, manifest))
+}
+
+// ── Reading ───────────────────────────────────────────────────────────────────
+
+/// The manifest and contents of `archive`, once every file is verified.
+fn read(archive: &[u8]) -> Result<(Manifest, Contents), ImportError> {
+    let mut zip = ZipArchive::new(Cursor::new(archive))
+        .map_err(|e| ImportError::Unreadable(format!("not a zip archive: {e}")))?;
+    let mut members = BTreeMap::new();
+    for i in 0..zip.len() {
+        let mut member = zip
+            .by_index(i)
+            .map_err(|e| ImportError::Unreadable(e.to_string()))?;
+        if member.is_dir() {
+            continue;
+        }
+        let mut bytes = Vec::new();
+        member
+            .read_to_end(&mut bytes)
+            .map_err(|e| ImportError::Unreadable(format!("{}: {e}", member.name())))?;
+        members.insert(member.name().to_string(), bytes);
+    }
+    let manifest: Manifest = members
+        .remove(MANIFEST)
+        .ok_or_else(|| ImportError::Unreadable(format!("no {MANIFEST}")))
+        .and_then(|raw| {
+            serde_json::from_slice(&raw)
+                .map_err(|e| ImportError::Unreadable(format!("{MANIFEST}: {e}")))
+        })?;
+    if manifest.format != ARCHIVE_FORMAT {
+        return Err(ImportError::Unreadable(format!(
+            "not a tenant archive: format {:?}",
+            manifest.format
+        )));
+    }
+    if manifest.version > ARCHIVE_VERSION {
+        return Err(ImportError::Unreadable(format!(
+            "archive version {} is newer than this engine reads ({ARCHIVE_VERSION})",
+            manifest.version
+        )));
+    }
+    if let Some(extra) = members.keys().find(|n| !manifest.files.contains_key(*n)) {
+        return Err(ImportError::Integrity(format!(
+            "{extra} is not listed in the manifest"
+        )));
+    }
+    for (name, file) in &manifest.files {
+        let bytes = members
+            .get(name)
+            .ok_or_else(|| ImportError::Integrity(format!("{name} is missing")))?;
+        if bytes.len() as u64 != file.bytes || format!("{:x}", Sha256::digest(bytes)) != file.sha256
+        {
+            return Err(ImportError::Integrity(format!(
+                "{name} does not match its checksum"
+            )));
+        }
+    }
+
+    fn parse<T: DeserializeOwned + Default>(
+        members: &BTreeMap<String, Vec<u8>>,
+        name: &str,
+    ) -> Result<T, ImportError> {
+        let file = format!("{name}.json");
+        members.get(&file).map_or(Ok(T::default()), |bytes| {
+            serde_json::from_slice(bytes).map_err(|e| ImportError::Invalid(format!("{file}: {e}")))
+        })
+    }
+    let contents = Contents {
+        templates: parse(&members, TEMPLATES)?,
+        template_versions: parse(&members, TEMPLATE_VERSIONS)?,
+        clauses: parse(&members, CLAUSES)?,
+        regulatory_rules: parse(&members, REGULATORY_RULES)?,
+        lexicon: parse(&members, LEXICON)?,
+        authority_matrix: parse(&members, AUTHORITY_MATRIX)?,
+        playbook: parse(&members, PLAYBOOK)?,
+        analyses: parse(&members, ANALYSES)?,
+    };
+    Ok((manifest, contents))
+}
+
+/// Why a record cannot be imported, checked as its own endpoint would.
+fn validate(contents: &Contents) -> Result<(), String> {
+    for t in &contents.templates {
+        if !custom_templates::valid_id(&t.id) || BUILTIN_TEMPLATES.contains(&t.id.as_str()) {
+            return Err(format!("{TEMPLATES}/{}: invalid template id", t.id));
+        }
+        ParsedTemplate::parse(&t.body).map_err(|e| format!("{TEMPLATES}/{}: {e}", t.id))?;
+    }
+    for (id, history) in &contents.template_versions {
+        let Some(t) = contents.templates.iter().find(|t| t.id == *id) else {
+            return Err(format!("{TEMPLATE_VERSIONS}/{id}: no such template"));
+        };
+        let ascending = history.windows(2).all(|w| w[0].version < w[1].version);
+        let current = history
+            .last()
+            .is_some_and(|v| v.version == t.version && v.body == t.body);
+        if !ascending || !current {
+            return Err(format!(
+                "{TEMPLATE_VERSIONS}/{id}: history does not end at the template"
+            ));
+        }
+    }
+    for c in &contents.clauses {
+        c.validate()
+            .map_err(|e| format!("{CLAUSES}/{}: {e}", c.id))?;
+    }
+    for r in &contents.regulatory_rules {
+        r.validate()
+            .map_err(|e| format!("{REGULATORY_RULES}/{}: {e}", r.id))?;
+    }
+    if let Some(lexicon) = &contents.lexicon {
+        lexicon.validate().map_err(|e| format!("{LEXICON}: {e}"))?;
+    }
+    if let Some(matrix) = &contents.authority_matrix {
+        matrix
+            .validate()
+            .map_err(|e| format!("{AUTHORITY_MATRIX}: {e}"))?;
+    }
+    if let Some(playbook) = &contents.playbook {
+        playbook
+            .validate()
+            .map_err(|e| format!("{PLAYBOOK}: {e}"))?;
+    }
+    Ok(())
+}
+
+// ── Planning ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Create,
+    Overwrite,
+    Keep,
+}
+
+/// What the instance holds under an archived record's ID.
+enum Found<T> {
+    Nothing,
+    Here(T),
+    /// Owned by another tenant, which an import never overwrites.
+    Elsewhere(String),
+}
+
+impl<T> From<Option<T>> for Found<T> {
+    fn from(existing: Option<T>) -> Self {
+        existing.map_or(Self::Nothing, Self::Here)
+    }
+}
+
+struct Planner<'a> {
+    on_conflict: OnConflict,
+    report: &'a mut ImportReport,
+}
+
+impl Planner<'_> {
+    fn decide<T: Serialize>(
+        &mut self,
+        section: &'static str,
+        id: &str,
+        incoming: &T,
+        found: Found<T>,
+    ) -> Action {
+        let counts = self.report.sections.entry(section).or_default();
+        let reason = match found {
+            Found::Nothing => {
+                counts.created += 1;
+                return Action::Create;
+            }
+            Found::Here(existing)
+                if serde_json::to_value(&existing).ok() == serde_json::to_value(incoming).ok() =>
+            {
+                counts.unchanged += 1;
+                return Action::Keep;
+            }
+            Found::Here(_) => None,
+            Found::Elsewhere(owner) => Some(format!("the ID belongs to tenant {owner}")),
+        };
+        let resolution = match (self.on_conflict, &reason) {
+            (OnConflict::Fail, _) => Resolution::Unresolved,
+            (OnConflict::Overwrite, None) => Resolution::Overwritten,
+            _ => Resolution::Skipped,
+        };
+        let action = if resolution == Resolution::Overwritten {
+            counts.updated += 1;
+            Action::Overwrite
+        } else {
+            counts.skipped += 1;
+            Action::Keep
+        };
+        self.report.conflicts.push(ImportConflict {
+            section,
+            id: id.to_string(),
+            resolution,
+            reason: reason.unwrap_or_else(|| "differs from the existing record".to_string()),
+        });
+        action
+    }
+}
+
+/// The archived records to write, each with whether it replaces one.
+#[derive(Default)]
+struct Plan {
+    templates: Vec<(CustomTemplate, Action)>,
+    template_versions: BTreeMap<String, Vec<TemplateVersion>>,
+    clauses: Vec<LibraryClause>,
+    regulatory_rules: Vec<DeprecationRule>,
+    lexicon: Option<Lexicon>,
+    authority_matrix: Option<AuthorityMatrix>,
+    playbook: Option<Playbook>,
+    analyses: Vec<HistoryRecord>,
+}
+
+fn plan(
+    state: &AppState,
+    tenant: &str,
+    contents: Contents,
+    planner: &mut Planner,
+) -> Result<Plan, String> {
+    let mut plan = Plan {
+        template_versions: contents.template_versions,
+        ..Plan::default()
+    };
+    for mut template in contents.templates {
+        template.tenant = tenant.to_string();
+        // IDs are unique across tenants.
+        let found = match state.custom_templates.owner(&template.id) {
+            Some(owner) if owner != tenant => Found::Elsewhere(owner),
+            _ => state
+                .custom_templates
+                .get(tenant, &template.id)
+                .map(|r| r.template.clone())
+                .into(),
+        };
+        let action = planner.decide(TEMPLATES, &template.id, &template, found);
+        if action != Action::Keep {
+            plan.templates.push((template, action));
+        }
+    }
+    for clause in contents.clauses {
+        let found = state.clause_library.get(&clause.id).into();
+        if planner.decide(CLAUSES, &clause.id, &clause, found) != Action::Keep {
+            plan.clauses.push(clause);
+        }
+    }
+    let mut rules: BTreeMap<String, DeprecationRule> = state
+        .regulatory
+        .list()
+        .into_iter()
+        .map(|r| (r.id.clone(), r))
+        .collect();
+    for rule in contents.regulatory_rules {
+        let found = rules.remove(&rule.id).into();
+        if planner.decide(REGULATORY_RULES, &rule.id, &rule, found) != Action::Keep {
+            plan.regulatory_rules.push(rule);
+        }
+    }
+    if let Some(lexicon) = contents.lexicon {
+        let found = state
+            .lexicons
+            .matcher(tenant)
+            .and_then(|m| m.lexicon().cloned())
+            .into();
+        if planner.decide(LEXICON, tenant, &lexicon, found) != Action::Keep {
+            plan.lexicon = Some(lexicon);
+        }
+    }
+    if let Some(matrix) = contents.authority_matrix {
+        let found = state.authority.get(tenant).into();
+        if planner.decide(AUTHORITY_MATRIX, tenant, &matrix, found) != Action::Keep {
+            plan.authority_matrix = Some(matrix);
+        }
+    }
+    if let Some(playbook) = contents.playbook {
+        let found = state.playbooks.get(tenant).into();
+        if planner.decide(PLAYBOOK, tenant, &playbook, found) != Action::Keep {
+            plan.playbook = Some(playbook);
+        }
+    }
+    for analysis in contents.analyses {
+        let id = analysis.summary.id.clone();
+        let found = match state.history.get(&id)? {
+            Some(record) if record.tenant != tenant => Found::Elsewhere(record.tenant),
+            existing => existing
+                .map(|r| ArchivedAnalysis {
+                    summary: r.summary,
+                    result: r.result,
+                })
+                .into(),
+        };
+        if planner.decide(ANALYSES, &id, &analysis, found) != Action::Keep {
+            plan.analyses.push(HistoryRecord {
+                summary: analysis.summary,
+                tenant: tenant.to_string(),
+                result: analysis.result,
+            });
+        }
+    }
+    Ok(plan)
+}
+
+// ── Import ────────────────────────────────────────────────────────────────────
+
+/// Imports `archive` into `tenant`. Nothing is written when a file fails
+/// its checksum, a record is invalid, a conflict is unresolved under
+/// `on_conflict=fail`, or `dry_run` is set.
+pub fn import(
+    state: &AppState,
+    tenant: &str,
+    archive: &[u8],
+    query: &ImportQuery,
+) -> Result<ImportReport, ImportError> {
+    let (manifest, contents) = read(archive)?;
+    validate(&contents).map_err(ImportError::Invalid)?;
+    let mut report = ImportReport {
+        tenant: tenant.to_string(),
+        source_tenant: manifest.tenant,
+        exported_at: manifest.exported_at,
+        on_conflict: query.on_conflict,
+        dry_run: query.dry_run,
+        sections: BTreeMap::new(),
+        conflicts: Vec::new(),
+        warnings: Vec::new(),
+    };
+    let mut plan = plan(
+        state,
+        tenant,
+        contents,
+        &mut Planner {
+            on_conflict: query.on_conflict,
+            report: &mut report,
+        },
+    )
+    .map_err(ImportError::Store)?;
+    if report
+        .conflicts
+        .iter()
+        .any(|c| c.resolution == Resolution::Unresolved)
+    {
+        return Err(ImportError::Conflicts(Box::new(report)));
+    }
+    if query.dry_run {
+        return Ok(report);
+    }
+
+    // Clauses first: templates embed them.
+    for clause in plan.clauses {
+        state.clause_library.put(clause);
+    }
+    for rule in plan.regulatory_rules {
+        state.regulatory.upsert(rule);
+    }
+    if let Some(lexicon) = plan.lexicon {
+        state
+            .lexicons
+            .put(tenant, lexicon)
+            .map_err(ImportError::Invalid)?;
+    }
+    if let Some(matrix) = plan.authority_matrix {
+        state
+            .authority
+            .put(tenant, matrix)
+            .map_err(ImportError::Invalid)?;
+    }
+    if let Some(playbook) = plan.playbook {
+        state
+            .playbooks
+            .put(tenant, playbook)
+            .map_err(ImportError::Invalid)?;
+    }
+    let mut imported = BTreeSet::new();
+    for (template, action) in plan.templates {
+        let parsed = ParsedTemplate::parse(&template.body).map_err(ImportError::Invalid)?;
+        imported.insert(template.id.clone());
+        let history = plan.template_versions.remove(&template.id);
+        let registered = Registered { template, parsed };
+        // A template new here brings its history; one that already has
+        // history here gets the archived text as its next version.
+        let saved = match history {
+            Some(mut history)
+                if state
+                    .custom_templates
+                    .versions(&registered.template.id)
+                    .is_none() =>
+            {
+                for version in &mut history {
+                    version.tenant = tenant.to_string();
+                }
+                state.custom_templates.restore(registered, history)
+            }
+            _ => state
+                .custom_templates
+                .save(registered, action == Action::Overwrite),
+        };
+        saved.map_err(|e| ImportError::Store(format!("{e:?}")))?;
+    }
+    for record in &plan.analyses {
+        state.history.save(record).map_err(ImportError::Store)?;
+    }
+    for id in imported {
+        let Some(registered) = state.custom_templates.get(tenant, &id) else {
+            continue;
+        };
+        let problems = custom_templates::unknown_fragment(state, &registered)
+            .into_iter()
+            .chain(custom_templates::invalid_override(
+                state,
+                tenant,
+                &registered,
+            ));
+        report
+            .warnings
+            .extend(problems.map(|e| format!("{TEMPLATES}/{id}: {e}")));
+    }
+    Ok(report)
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn export_tenant(
+    State(state): State<AppState>,
+    Path(tenant): Path<String>,
+) -> Result<Response, StatusCode> {
+    let (archive, manifest) = export(&state, &tenant).map_err(|e| {
+        warn!(tenant = %tenant, error = %e, "tenant export failed");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let records: BTreeMap<&str, usize> = manifest
+        .files
+        .iter()
+        .map(|(name, f)| (name.trim_end_matches(".json"), f.records))
+        .collect();
+    info!(tenant = %tenant, bytes = archive.len(), "tenant archive exported");
+    state.record_audit(
+        "tenant.exported",
+        &tenant,
+        None,
+        json!({ "records": records }),
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{tenant}-archive.zip\""),
+            ),
+        ],
+        archive,
+    )
+        .into_response())
+}
+
+pub async fn import_tenant(
+    State(state): State<AppState>,
+    Path(tenant): Path<String>,
+    Query(query): Query<ImportQuery>,
+    body: Bytes,
+) -> Result<Json<ImportReport>, ImportError> {
+    let report = import(&state, &tenant, &body, &query).inspect_err(|e| {
+        if !matches!(e, ImportError::Store(_)) {
+            info!(tenant = %tenant, error = ?e, "tenant archive rejected");
+        }
+    })?;
+    if !report.dry_run {
+        info!(
+            tenant = %tenant,
+            source_tenant = %report.source_tenant,
+            conflicts = report.conflicts.len(),
+            "tenant archive imported"
+        );
+        state.record_audit(
+            "tenant.imported",
+            &tenant,
+            None,
+            json!({
+                "source_tenant": report.source_tenant,
+                "exported_at": report.exported_at,
+                "on_conflict": report.on_conflict,
+                "sections": report.sections,
+                "conflicts": report.conflicts.len(),
+            }),
+        );
+    }
+    Ok(Json(report))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rezip(archive: &[u8], edit: impl Fn(&str, String) -> Option<String>) -> Vec<u8> {
+        let mut source = ZipArchive::new(Cursor::new(archive)).unwrap();
+        let mut out = ZipWriter::new(Cursor::new(Vec::new()));
+        for i in 0..source.len() {
+            let mut file = source.by_index(i).unwrap();
+            let mut text = String::new();
+            file.read_to_string(&mut text).unwrap();
+            if let Some(text) = edit(file.name(), text) {
+                out.start_file(file.name(), SimpleFileOptions::default())
+                    .unwrap();
+                out.write_all(text.as_bytes()).unwrap();
+            }
+        }
+        out.finish().unwrap().into_inner()
+    }
+
+    fn refusal(state: &AppState, archive: &[u8]) -> String {
+        match import(state, "acme", archive, &ImportQuery::default()) {
+            Err(ImportError::Unreadable(e) | ImportError::Integrity(e)) => e,
+            other => panic!("expected a refusal, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn an_unchanged_instance_imports_its_own_export_as_unchanged() {
+        let state = AppState::in_memory();
+        state
+            .authority
+            .put(
+                "acme",
+                serde_json::from_value(
+                    json!({ "signers": [{ "name": "Ada", "entity": "Acme Ltd" }] }),
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        let (archive, manifest) = export(&state, "acme").unwrap();
+        assert_eq!(manifest.files["authority_matrix.json"].records, 1);
+        assert_eq!(manifest.files["lexicon.json"].records, 0);
+
+        let report = import(&state, "acme", &archive, &ImportQuery::default()).unwrap();
+        assert!(report.conflicts.is_empty());
+        assert_eq!(report.sections[AUTHORITY_MATRIX].unchanged, 1);
+        assert_eq!(report.sections[REGULATORY_RULES].created, 0);
+    }
+
+    #[test]
+    fn playbooks_travel_with_the_tenant() {
+        let source = AppState::in_memory();
+        let playbook: Playbook = serde_json::from_value(json!({
+            "rules": [{
+                "clause_type": "Liability",
+                "preferred": "liability is capped at the fees paid",
+                "walk_away": ["unlimited liability"],
+            }],
+            "checks": { "liability_cap": "off" },
+        }))
+        .unwrap();
+        source.playbooks.put("acme", playbook.clone()).unwrap();
+        let (archive, manifest) = export(&source, "acme").unwrap();
+        assert_eq!(manifest.files["playbook.json"].records, 1);
+
+        let target = AppState::in_memory();
+        let report = import(&target, "acme-eu", &archive, &ImportQuery::default()).unwrap();
+        assert_eq!(report.sections[PLAYBOOK].created, 1);
+        assert_eq!(target.playbooks.get("acme-eu"), Some(playbook));
+
+        target
+            .playbooks
+            .put("acme-eu", Playbook::default())
+            .unwrap();
+        let refused = import(&target, "acme-eu", &archive, &ImportQuery::default());
+        let Err(ImportError::Conflicts(report)) = refused else {
+            panic!("expected a conflict, got {refused:?}");
+        };
+        assert_eq!(report.conflicts[0].section, PLAYBOOK);
+
+        let mut checks = BTreeMap::new();
+        checks.insert("liability_cap".to_string(), "loud".to_string());
+        let contents = Contents {
+            playbook: Some(Playbook {
+                checks,
+                ..Playbook::default()
+            }),
+            ..Contents::default()
+        };
+        assert!(validate(&contents)
+            .unwrap_err()
+            .starts_with("playbook: checks.liability_cap must be one of"));
+    }
+
+    #[test]
+    fn template_history_must_end_at_the_template() {
+        let template: CustomTemplate = serde_json::from_value(json!({
+            "id": "consulting",
+            "tenant": "acme",
+            "name": "Consulting",
+            "description": "",
+            "required_variables": ["client"],
+            "language_support": ["en"],
+            "body": "{{client}} retains us.",
+            "overrides": null,
+            "created_at": "2026-01-01T00:00:00Z",
+            "updated_at": "2026-02-01T00:00:00Z",
+            "version": 2,
+        }))
+        .unwrap();
+        let version = |version: u32, body: &str| TemplateVersion {
+            version,
+            tenant: "acme".to_string(),
+            name: template.name.clone(),
+            required_variables: template.required_variables.clone(),
+            language_support: template.language_support.clone(),
+            body: body.to_string(),
+            saved_at: template.updated_at,
+        };
+        let mut contents = Contents {
+            templates: vec![template.clone()],
+            ..Contents::default()
+        };
+        contents.template_versions.insert(
+            "consulting".to_string(),
+            vec![
+                version(1, "{{client}} engages us."),
+                version(2, &template.body),
+            ],
+        );
+        assert_eq!(validate(&contents), Ok(()));
+
+        contents
+            .template_versions
+            .get_mut("consulting")
+            .unwrap()
+            .pop();
+        assert_eq!(
+            validate(&contents),
+            Err("template_versions/consulting: history does not end at the template".to_string())
+        );
+    }
+
+    #[test]
+    fn archives_that_do_not_check_out_are_refused() {
+        let state = AppState::in_memory();
+        let (archive, _) = export(&state, "acme").unwrap();
+
+        let newer = rezip(&archive, |name, text| {
+            Some(if name == MANIFEST {
+                text.replace("\"version\": 3", "\"version\": 4")
+            } else {
+                text
+            })
+        });
+        assert_eq!(
+            refusal(&state, &newer),
+            "archive version 4 is newer than this engine reads (3)"
+        );
+        let missing = rezip(&archive, |name, text| {
+            (name != "clauses.json").then_some(text)
+        });
+        assert_eq!(refusal(&state, &missing), "clauses.json is missing");
+        let mut extra = ZipWriter::new_append(Cursor::new(archive)).unwrap();
+        extra
+            .start_file("notes.txt", SimpleFileOptions::default())
+            .unwrap();
+        extra.write_all(b"hello").unwrap();
+        let extra = extra.finish().unwrap().into_inner();
+        assert_eq!(
+            refusal(&state, &extra),
+            "notes.txt is not listed in the manifest"
+        );
+    }
+}
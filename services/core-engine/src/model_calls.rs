@@ -0,0 +1,473 @@
+//! Right-to-audit trail of calls to external model backends: the ML
+//! classification backend, a tenant's own models and the explanation
+//! model. Every call made for a tenant is recorded with its endpoint,
+//! model, timing, outcome and token counts, and linked to the analysis or
+//! risk score it served, so a privacy officer can see what left the
+//! system. How much of the prompt is kept is the tenant's choice: none of
+//! it, its SHA-256 (the default, `LEGAL_PROMPT_LOGGING`), or the body as
+//! sent. Credentials, query strings and fragments are stripped from
+//! recorded endpoints.
+
+use std::sync::Mutex;
+
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+use crate::{notifier, timings::Stopwatch, AppState};
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+/// How much of each prompt a tenant's call records keep.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptLogging {
+    /// Only its size.
+    Off,
+    /// Its SHA-256, to match against a copy without keeping one.
+    #[default]
+    Hashed,
+    /// The request body as sent, plus its SHA-256.
+    Full,
+}
+
+impl PromptLogging {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "off" => Some(Self::Off),
+            "hashed" => Some(Self::Hashed),
+            "full" => Some(Self::Full),
+            _ => None,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        std::env::var("LEGAL_PROMPT_LOGGING")
+            .ok()
+            .and_then(|v| Self::parse(&v))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Purpose {
+    Classification,
+    Embedding,
+    Explanation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallOutcome {
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelCall {
+    pub id: String,
+    pub tenant: String,
+    pub purpose: Purpose,
+    /// The analysis or risk score the call was made for, once it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analysis_id: Option<String>,
+    /// Scheme, host and path only.
+    pub endpoint: String,
+    /// As named in the backend's answer, when it names one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub at: DateTime<Utc>,
+    pub duration_ms: f64,
+    pub outcome: CallOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub prompt_bytes: usize,
+    /// From the answer's `usage`; unset when the backend does not report it.
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    /// The tenant's setting when the call was made.
+    pub prompt_logging: PromptLogging,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ModelCallQuery {
+    pub analysis_id: Option<String>,
+    pub purpose: Option<Purpose>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelCallsResponse {
+    pub tenant: String,
+    pub prompt_logging: PromptLogging,
+    pub calls: Vec<ModelCall>,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoggingSettings {
+    pub prompt_logging: PromptLogging,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoggingResponse {
+    pub tenant: String,
+    pub prompt_logging: PromptLogging,
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+/// Every recorded call, plus the tenants that changed the default logging.
+#[derive(Default)]
+pub struct ModelCallLog {
+    calls: Mutex<Vec<ModelCall>>,
+    default_logging: PromptLogging,
+    logging: DashMap<String, PromptLogging>,
+}
+
+impl ModelCallLog {
+    pub fn new(default_logging: PromptLogging) -> Self {
+        Self {
+            default_logging,
+            ..Self::default()
+        }
+    }
+
+    pub fn logging(&self, tenant: &str) -> PromptLogging {
+        self.logging
+            .get(tenant)
+            .map_or(self.default_logging, |l| *l)
+    }
+
+    pub fn set_logging(&self, tenant: &str, logging: PromptLogging) {
+        self.logging.insert(tenant.to_string(), logging);
+    }
+
+    fn record(&self, call: ModelCall) {
+        self.calls
+            .lock()
+            .expect("model call log lock poisoned")
+            .push(call);
+    }
+
+    /// Ties a call to the analysis it was made for, once that has an ID.
+    pub fn link(&self, call_id: &str, analysis_id: &str) {
+        let mut calls = self.calls.lock().expect("model call log lock poisoned");
+        // A map-reduce run records one call per chunk under the same id.
+        for call in calls.iter_mut().filter(|c| c.id == call_id) {
+            call.analysis_id = Some(analysis_id.to_string());
+        }
+    }
+
+    pub fn list(&self, tenant: &str, query: &ModelCallQuery) -> Vec<ModelCall> {
+        self.calls
+            .lock()
+            .expect("model call log lock poisoned")
+            .iter()
+            .filter(|c| c.tenant == tenant)
+            .filter(|c| {
+                query
+                    .analysis_id
+                    .as_ref()
+                    .is_none_or(|id| c.analysis_id.as_ref() == Some(id))
+            })
+            .filter(|c| query.purpose.is_none_or(|p| c.purpose == p))
+            .cloned()
+            .collect()
+    }
+
+    /// Drops the tenant's calls and setting; returns how many calls went.
+    pub fn remove_tenant(&self, tenant: &str) -> usize {
+        self.logging.remove(tenant);
+        let mut calls = self.calls.lock().expect("model call log lock poisoned");
+        let before = calls.len();
+        calls.retain(|c| c.tenant != tenant);
+        before - calls.len()
+    }
+
+    /// Rewrites the prompts kept in full, e.g. to mask an erased person;
+    /// `f` gets each call's tenant and returns whether it changed the prompt.
+    /// Returns how many it changed.
+    pub fn rewrite_prompts(&self, mut f: impl FnMut(&str, &mut Value) -> bool) -> usize {
+        self.calls
+            .lock()
+            .expect("model call log lock poisoned")
+            .iter_mut()
+            .filter_map(|c| Some((c.tenant.as_str(), c.prompt.as_mut()?)))
+            .map(|(tenant, prompt)| f(tenant, prompt))
+            .filter(|changed| *changed)
+            .count()
+    }
+}
+
+// ── Calls ─────────────────────────────────────────────────────────────────────
+
+/// A call about to be made for a tenant. Its ID is known before the call,
+/// so a failed call can still be linked to its analysis.
+pub struct Caller<'a> {
+    pub id: String,
+    tenant: &'a str,
+    purpose: Purpose,
+}
+
+impl<'a> Caller<'a> {
+    pub fn new(state: &AppState, tenant: &'a str, purpose: Purpose) -> Self {
+        Self {
+            id: state.ids.next_id(),
+            tenant,
+            purpose,
+        }
+    }
+}
+
+/// `url` without credentials, query or fragment.
+fn endpoint(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(mut url) => {
+            // Only fails for URLs that cannot carry credentials anyway.
+            let _ = url.set_username("");
+            let _ = url.set_password(None);
+            url.set_query(None);
+            url.set_fragment(None);
+            url.to_string()
+        }
+        Err(_) => "(invalid url)".to_string(),
+    }
+}
+
+/// The first of `keys` the answer's `usage` object reports.
+fn tokens(answer: &Value, keys: [&str; 2]) -> Option<u64> {
+    keys.iter().find_map(|k| answer["usage"][*k].as_u64())
+}
+
+async fn send(request: reqwest::RequestBuilder, body: &Value) -> Result<Value, String> {
+    let resp = request
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| format!("unreachable: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("answered {}", resp.status()));
+    }
+    resp.json()
+        .await
+        .map_err(|e| format!("unreadable answer: {e}"))
+}
+
+/// Posts `body` with `request`, which must target `url`, and reads the
+/// JSON answer; the call is recorded when it is made for a `caller`.
+pub async fn exchange(
+    state: &AppState,
+    caller: Option<&Caller<'_>>,
+    request: reqwest::RequestBuilder,
+    url: &str,
+    body: &Value,
+) -> Result<Value, String> {
+    let at = state.clock.now();
+    let watch = Stopwatch::start(state.clock.as_ref());
+    let answer = send(request, body).await;
+    let Some(caller) = caller else {
+        return answer;
+    };
+    let logging = state.model_calls.logging(caller.tenant);
+    let prompt = serde_json::to_vec(body).unwrap_or_default();
+    let ok = answer.as_ref().ok();
+    state.model_calls.record(ModelCall {
+        id: caller.id.clone(),
+        tenant: caller.tenant.to_string(),
+        purpose: caller.purpose,
+        analysis_id: None,
+        endpoint: endpoint(url),
+        model: ok.and_then(|a| a["model"].as_str()).map(str::to_string),
+        at,
+        duration_ms: watch.total(),
+        outcome: if ok.is_some() {
+            CallOutcome::Succeeded
+        } else {
+            CallOutcome::Failed
+        },
+        error: answer.as_ref().err().cloned(),
+        prompt_bytes: prompt.len(),
+        prompt_tokens: ok.and_then(|a| tokens(a, ["prompt_tokens", "input_tokens"])),
+        completion_tokens: ok.and_then(|a| tokens(a, ["completion_tokens", "output_tokens"])),
+        prompt_logging: logging,
+        prompt_sha256: (logging != PromptLogging::Off)
+            .then(|| format!("{:x}", Sha256::digest(&prompt))),
+        prompt: (logging == PromptLogging::Full).then(|| body.clone()),
+    });
+    answer
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn list_calls(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ModelCallQuery>,
+) -> Json<ModelCallsResponse> {
+    let tenant = notifier::tenant(&headers);
+    let calls = state.model_calls.list(&tenant, &query);
+    Json(ModelCallsResponse {
+        prompt_logging: state.model_calls.logging(&tenant),
+        count: calls.len(),
+        calls,
+        tenant,
+    })
+}
+
+pub async fn get_settings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Json<LoggingResponse> {
+    let tenant = notifier::tenant(&headers);
+    Json(LoggingResponse {
+        prompt_logging: state.model_calls.logging(&tenant),
+        tenant,
+    })
+}
+
+pub async fn put_settings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(settings): Json<LoggingSettings>,
+) -> Json<LoggingResponse> {
+    let tenant = notifier::tenant(&headers);
+    let previous = state.model_calls.logging(&tenant);
+    state
+        .model_calls
+        .set_logging(&tenant, settings.prompt_logging);
+    info!(tenant = %tenant, prompt_logging = ?settings.prompt_logging, "prompt logging changed");
+    state.record_audit(
+        "model_calls.prompt_logging",
+        &tenant,
+        None,
+        json!({ "from": previous, "to": settings.prompt_logging }),
+    );
+    Json(LoggingResponse {
+        tenant,
+        prompt_logging: settings.prompt_logging,
+    })
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Router};
+
+    async fn backend() -> String {
+        let app = Router::new().route(
+            "/v1/classify",
+            post(|| async {
+                Json(json!({
+                    "model": "clf-2",
+                    "clauses": [],
+                    "usage": { "prompt_tokens": 12, "completion_tokens": 3 },
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://user:secret@{addr}/v1/classify?key=abc")
+    }
+
+    async fn call(state: &AppState, tenant: &str, url: &str) -> String {
+        let caller = Caller::new(state, tenant, Purpose::Classification);
+        let body = json!({ "document": "Confidential terms.", "language": "en" });
+        let _ = exchange(state, Some(&caller), state.http.post(url), url, &body).await;
+        caller.id
+    }
+
+    #[test]
+    fn endpoints_lose_credentials_and_query() {
+        assert_eq!(
+            endpoint("https://u:p@ml.example.com/v1/run?api_key=x#top"),
+            "https://ml.example.com/v1/run"
+        );
+        assert_eq!(endpoint("not a url"), "(invalid url)");
+    }
+
+    #[tokio::test]
+    async fn calls_keep_only_what_the_tenant_allows() {
+        let state = AppState::in_memory();
+        let url = backend().await;
+        state.model_calls.set_logging("acme", PromptLogging::Full);
+        state.model_calls.set_logging("globex", PromptLogging::Off);
+        let id = call(&state, "acme", &url).await;
+        call(&state, "globex", &url).await;
+        call(&state, "initech", &url).await;
+        state.model_calls.link(&id, "an-1");
+
+        let acme = state.model_calls.list("acme", &ModelCallQuery::default());
+        assert_eq!(acme.len(), 1);
+        let c = &acme[0];
+        assert_eq!(c.analysis_id.as_deref(), Some("an-1"));
+        assert_eq!(c.outcome, CallOutcome::Succeeded);
+        assert!(!c.endpoint.contains("secret") && !c.endpoint.contains("key="));
+        assert_eq!(c.model.as_deref(), Some("clf-2"));
+        assert_eq!((c.prompt_tokens, c.completion_tokens), (Some(12), Some(3)));
+        assert_eq!(
+            c.prompt.as_ref().unwrap()["document"],
+            "Confidential terms."
+        );
+        let sent = serde_json::to_vec(c.prompt.as_ref().unwrap()).unwrap();
+        assert_eq!(c.prompt_bytes, sent.len());
+        assert_eq!(
+            c.prompt_sha256.as_deref(),
+            Some(format!("{:x}", Sha256::digest(&sent)).as_str())
+        );
+
+        let globex = &state.model_calls.list("globex", &ModelCallQuery::default())[0];
+        assert!(globex.prompt_sha256.is_none() && globex.prompt.is_none());
+        let initech = &state
+            .model_calls
+            .list("initech", &ModelCallQuery::default())[0];
+        assert_eq!(initech.prompt_logging, PromptLogging::Hashed);
+        assert!(initech.prompt_sha256.is_some() && initech.prompt.is_none());
+
+        let query = ModelCallQuery {
+            analysis_id: Some("an-2".to_string()),
+            purpose: None,
+        };
+        assert!(state.model_calls.list("acme", &query).is_empty());
+        assert_eq!(state.model_calls.remove_tenant("acme"), 1);
+        assert!(state
+            .model_calls
+            .list("acme", &ModelCallQuery::default())
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_calls_are_recorded_too() {
+        let state = AppState::in_memory();
+        call(&state, "acme", "http://127.0.0.1:9/v1/classify").await;
+        let calls = state.model_calls.list("acme", &ModelCallQuery::default());
+        assert_eq!(calls[0].outcome, CallOutcome::Failed);
+        assert!(calls[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with("unreachable"));
+        assert_eq!(calls[0].prompt_tokens, None);
+    }
+
+    #[test]
+    fn unknown_env_values_keep_the_default() {
+        assert_eq!(PromptLogging::parse(" FULL "), Some(PromptLogging::Full));
+        assert_eq!(PromptLogging::parse("verbose"), None);
+    }
+}
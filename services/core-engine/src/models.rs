@@ -0,0 +1,642 @@
+//! Model endpoints a tenant hosts itself, for deployments whose documents
+//! may not leave their own network. An admin registers a tenant's
+//! embedding or classification model by URL; it is accepted only after a
+//! health probe and a test call answer as its settings promise. A tenant's
+//! classification model then replaces `LEGAL_ML_BACKEND_URL` for that
+//! tenant's analyses, and its embedding model serves
+//! `POST /api/v1/legal/embeddings`. Registrations, credentials included,
+//! are kept in memory only.
+
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::{
+    ensemble::MlFindings,
+    model_calls::{self, Caller, Purpose},
+    notifier,
+    timings::Stopwatch,
+    AppState,
+};
+
+const DEFAULT_TIMEOUT_MS: u64 = 3_000;
+const MAX_TIMEOUT_MS: u64 = 60_000;
+const MAX_DIMENSIONS: usize = 16_384;
+/// Texts one embeddings request may carry.
+const MAX_TEXTS: usize = 256;
+
+/// Sent to a model being registered; it must answer as it would in use.
+const TEST_TEXT: &str =
+    "The Supplier's total liability under this Agreement is limited to the fees paid.";
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    /// Receives `{"texts": [...]}`, answers `{"embeddings": [[...], ...]}`.
+    Embedding,
+    /// Receives `{"document", "language"}`, answers like the ML backend.
+    Classification,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ModelAuth {
+    Bearer { token: String },
+    Header { name: String, value: String },
+}
+
+impl ModelAuth {
+    /// How the endpoint is authenticated, without the secret.
+    fn scheme(&self) -> String {
+        match self {
+            Self::Bearer { .. } => "bearer".to_string(),
+            Self::Header { name, .. } => format!("header {}", name.to_ascii_lowercase()),
+        }
+    }
+}
+
+fn default_timeout_ms() -> u64 {
+    DEFAULT_TIMEOUT_MS
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModelEndpoint {
+    pub url: String,
+    /// Probed with `GET` before registering; `/health` on the model's host
+    /// when unset.
+    #[serde(default)]
+    pub health_url: Option<String>,
+    #[serde(default)]
+    pub auth: Option<ModelAuth>,
+    /// Length of every embedding; required for embedding models only.
+    #[serde(default)]
+    pub dimensions: Option<usize>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+/// Timings of the checks a model passed when it was registered.
+#[derive(Debug, Clone, Serialize)]
+pub struct Probe {
+    pub health_ms: f64,
+    pub test_ms: f64,
+}
+
+#[derive(Debug)]
+pub struct RegisteredModel {
+    pub tenant: String,
+    pub kind: ModelKind,
+    pub endpoint: ModelEndpoint,
+    pub registered_at: DateTime<Utc>,
+    pub probe: Probe,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelSummary {
+    pub tenant: String,
+    pub kind: ModelKind,
+    pub url: String,
+    pub health_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<usize>,
+    pub timeout_ms: u64,
+    pub registered_at: DateTime<Utc>,
+    pub probe: Probe,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelsResponse {
+    pub tenant: String,
+    pub models: Vec<ModelSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EmbedRequest {
+    pub texts: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmbedResponse {
+    pub dimensions: usize,
+    pub embeddings: Vec<Vec<f32>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbedAnswer {
+    embeddings: Vec<Vec<f32>>,
+}
+
+// ── Endpoint calls ────────────────────────────────────────────────────────────
+
+fn http_url(field: &str, value: &str) -> Result<reqwest::Url, String> {
+    let url = reqwest::Url::parse(value).map_err(|e| format!("{field}: {e}"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("{field} must be an http or https URL"));
+    }
+    Ok(url)
+}
+
+impl ModelEndpoint {
+    pub fn validate(&self, kind: ModelKind) -> Result<(), String> {
+        http_url("url", &self.url)?;
+        if let Some(health) = &self.health_url {
+            http_url("health_url", health)?;
+        }
+        if !(1..=MAX_TIMEOUT_MS).contains(&self.timeout_ms) {
+            return Err(format!("timeout_ms must be between 1 and {MAX_TIMEOUT_MS}"));
+        }
+        match (kind, self.dimensions) {
+            (ModelKind::Embedding, None) => {
+                return Err("embedding models need dimensions".to_string())
+            }
+            (ModelKind::Embedding, Some(d)) if !(1..=MAX_DIMENSIONS).contains(&d) => {
+                return Err(format!("dimensions must be between 1 and {MAX_DIMENSIONS}"))
+            }
+            (ModelKind::Classification, Some(_)) => {
+                return Err("dimensions only apply to embedding models".to_string())
+            }
+            _ => {}
+        }
+        match &self.auth {
+            Some(ModelAuth::Bearer { token }) => {
+                HeaderValue::from_str(&format!("Bearer {token}"))
+                    .map_err(|_| "auth token is not a valid header value".to_string())?;
+            }
+            Some(ModelAuth::Header { name, value }) => {
+                HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| format!("auth header name {name:?} is invalid"))?;
+                HeaderValue::from_str(value)
+                    .map_err(|_| "auth header value is not a valid header value".to_string())?;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn health_url(&self) -> String {
+        self.health_url.clone().unwrap_or_else(|| {
+            reqwest::Url::parse(&self.url)
+                .and_then(|url| url.join("/health"))
+                .map_or_else(|_| self.url.clone(), String::from)
+        })
+    }
+
+    fn request(
+        &self,
+        http: &reqwest::Client,
+        method: reqwest::Method,
+        url: &str,
+    ) -> reqwest::RequestBuilder {
+        let builder = http
+            .request(method, url)
+            .timeout(Duration::from_millis(self.timeout_ms));
+        match &self.auth {
+            Some(ModelAuth::Bearer { token }) => builder.bearer_auth(token),
+            Some(ModelAuth::Header { name, value }) => builder.header(name.as_str(), value),
+            None => builder,
+        }
+    }
+
+    /// Recorded in the model call log when made for a `caller`; probes
+    /// are not.
+    async fn call<T: serde::de::DeserializeOwned>(
+        &self,
+        state: &AppState,
+        caller: Option<&Caller<'_>>,
+        body: serde_json::Value,
+    ) -> Result<T, String> {
+        let request = self.request(&state.http, reqwest::Method::POST, &self.url);
+        let answer = model_calls::exchange(state, caller, request, &self.url, &body).await?;
+        serde_json::from_value(answer).map_err(|e| format!("unreadable answer: {e}"))
+    }
+
+    async fn health(&self, http: &reqwest::Client) -> Result<(), String> {
+        let resp = self
+            .request(http, reqwest::Method::GET, &self.health_url())
+            .send()
+            .await
+            .map_err(|e| format!("health probe unreachable: {e}"))?;
+        if !resp.status().is_success() {
+            return Err(format!("health probe answered {}", resp.status()));
+        }
+        Ok(())
+    }
+
+    /// One embedding per text, each of the registered length.
+    pub async fn embed(
+        &self,
+        state: &AppState,
+        caller: Option<&Caller<'_>>,
+        texts: &[String],
+    ) -> Result<Vec<Vec<f32>>, String> {
+        let answer: EmbedAnswer = self.call(state, caller, json!({ "texts": texts })).await?;
+        if answer.embeddings.len() != texts.len() {
+            return Err(format!(
+                "returned {} embeddings for {} texts",
+                answer.embeddings.len(),
+                texts.len()
+            ));
+        }
+        let dimensions = self.dimensions.unwrap_or_default();
+        for vector in &answer.embeddings {
+            if vector.len() != dimensions {
+                return Err(format!(
+                    "returned an embedding of {} dimensions, expected {dimensions}",
+                    vector.len()
+                ));
+            }
+            if vector.iter().any(|x| !x.is_finite()) || vector.iter().all(|&x| x == 0.0) {
+                return Err("returned a zero or non-finite embedding".to_string());
+            }
+        }
+        Ok(answer.embeddings)
+    }
+
+    /// Findings in the ML backend's format, with confidences in `0..=1`.
+    pub async fn classify(
+        &self,
+        state: &AppState,
+        caller: Option<&Caller<'_>>,
+        document: &str,
+        language: &str,
+    ) -> Result<MlFindings, String> {
+        let findings: MlFindings = self
+            .call(
+                state,
+                caller,
+                json!({ "document": document, "language": language }),
+            )
+            .await?;
+        let confidences = findings
+            .clauses
+            .iter()
+            .map(|c| c.confidence)
+            .chain(findings.issues.iter().map(|i| i.confidence));
+        for confidence in confidences {
+            if !(0.0..=1.0).contains(&confidence) {
+                return Err(format!("returned confidence {confidence} outside 0..=1"));
+            }
+        }
+        Ok(findings)
+    }
+
+    /// The health probe, then a test call with [`TEST_TEXT`].
+    async fn probe(&self, state: &AppState, kind: ModelKind) -> Result<Probe, String> {
+        let mut watch = Stopwatch::start(state.clock.as_ref());
+        self.health(&state.http).await?;
+        let health_ms = watch.lap();
+        match kind {
+            ModelKind::Embedding => {
+                self.embed(state, None, &[TEST_TEXT.to_string()]).await?;
+            }
+            ModelKind::Classification => {
+                self.classify(state, None, TEST_TEXT, "en").await?;
+            }
+        }
+        Ok(Probe {
+            health_ms,
+            test_ms: watch.lap(),
+        })
+    }
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct ModelRegistry {
+    models: DashMap<(String, ModelKind), Arc<RegisteredModel>>,
+}
+
+impl ModelRegistry {
+    pub fn get(&self, tenant: &str, kind: ModelKind) -> Option<Arc<RegisteredModel>> {
+        self.models
+            .get(&(tenant.to_string(), kind))
+            .map(|m| Arc::clone(m.value()))
+    }
+
+    /// Registers `model`, replacing the tenant's model of its kind; `true`
+    /// when there was one.
+    pub fn put(&self, model: RegisteredModel) -> bool {
+        self.models
+            .insert((model.tenant.clone(), model.kind), Arc::new(model))
+            .is_some()
+    }
+
+    pub fn remove(&self, tenant: &str, kind: ModelKind) -> bool {
+        self.models.remove(&(tenant.to_string(), kind)).is_some()
+    }
+
+    pub fn list(&self, tenant: &str) -> Vec<Arc<RegisteredModel>> {
+        let mut models: Vec<_> = self
+            .models
+            .iter()
+            .filter(|m| m.key().0 == tenant)
+            .map(|m| Arc::clone(m.value()))
+            .collect();
+        models.sort_by_key(|m| m.kind);
+        models
+    }
+}
+
+impl RegisteredModel {
+    pub fn summary(&self) -> ModelSummary {
+        ModelSummary {
+            tenant: self.tenant.clone(),
+            kind: self.kind,
+            url: self.endpoint.url.clone(),
+            health_url: self.endpoint.health_url(),
+            auth: self.endpoint.auth.as_ref().map(ModelAuth::scheme),
+            dimensions: self.endpoint.dimensions,
+            timeout_ms: self.endpoint.timeout_ms,
+            registered_at: self.registered_at,
+            probe: self.probe.clone(),
+        }
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+fn rejected(status: StatusCode, tenant: &str, kind: ModelKind, error: String) -> Response {
+    info!(tenant = %tenant, ?kind, error = %error, "model endpoint rejected");
+    (status, Json(json!({ "error": error }))).into_response()
+}
+
+pub async fn list_models(
+    State(state): State<AppState>,
+    Path(tenant): Path<String>,
+) -> Json<ModelsResponse> {
+    let models = state
+        .models
+        .list(&tenant)
+        .iter()
+        .map(|m| m.summary())
+        .collect();
+    Json(ModelsResponse { tenant, models })
+}
+
+pub async fn put_model(
+    State(state): State<AppState>,
+    Path((tenant, kind)): Path<(String, ModelKind)>,
+    Json(endpoint): Json<ModelEndpoint>,
+) -> Result<(StatusCode, Json<ModelSummary>), Response> {
+    endpoint
+        .validate(kind)
+        .map_err(|e| rejected(StatusCode::UNPROCESSABLE_ENTITY, &tenant, kind, e))?;
+    let probe = endpoint
+        .probe(&state, kind)
+        .await
+        .map_err(|e| rejected(StatusCode::BAD_GATEWAY, &tenant, kind, e))?;
+    let model = RegisteredModel {
+        tenant: tenant.clone(),
+        kind,
+        endpoint,
+        registered_at: state.clock.now(),
+        probe,
+    };
+    let summary = model.summary();
+    let replaced = state.models.put(model);
+    info!(tenant = %tenant, ?kind, url = %summary.url, "model endpoint registered");
+    state.record_audit(
+        "model.registered",
+        &tenant,
+        None,
+        json!({ "kind": kind, "url": summary.url, "replaced": replaced }),
+    );
+    let status = if replaced {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    Ok((status, Json(summary)))
+}
+
+pub async fn delete_model(
+    State(state): State<AppState>,
+    Path((tenant, kind)): Path<(String, ModelKind)>,
+) -> StatusCode {
+    if !state.models.remove(&tenant, kind) {
+        return StatusCode::NOT_FOUND;
+    }
+    state.record_audit("model.removed", &tenant, None, json!({ "kind": kind }));
+    StatusCode::NO_CONTENT
+}
+
+pub async fn embed(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<EmbedRequest>,
+) -> Result<Json<EmbedResponse>, StatusCode> {
+    if req.texts.is_empty() || req.texts.len() > MAX_TEXTS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let tenant = notifier::tenant(&headers);
+    let model = state
+        .models
+        .get(&tenant, ModelKind::Embedding)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let caller = Caller::new(&state, &tenant, Purpose::Embedding);
+    let embeddings = model
+        .endpoint
+        .embed(&state, Some(&caller), &req.texts)
+        .await
+        .map_err(|e| {
+            warn!(tenant = %tenant, error = %e, "tenant embedding model failed");
+            StatusCode::BAD_GATEWAY
+        })?;
+    Ok(Json(EmbedResponse {
+        dimensions: model.endpoint.dimensions.unwrap_or_default(),
+        embeddings,
+    }))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(url: &str) -> ModelEndpoint {
+        ModelEndpoint {
+            url: url.to_string(),
+            health_url: None,
+            auth: None,
+            dimensions: Some(384),
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+        }
+    }
+
+    #[test]
+    fn endpoints_are_validated_for_their_kind() {
+        let ok = endpoint("https://models.internal:8443/v1/embed");
+        assert!(ok.validate(ModelKind::Embedding).is_ok());
+        assert!(ok.validate(ModelKind::Classification).is_err());
+        assert_eq!(ok.health_url(), "https://models.internal:8443/health");
+
+        let bad = [
+            endpoint("ftp://models.internal/embed"),
+            ModelEndpoint {
+                dimensions: None,
+                ..endpoint("http://models.internal/embed")
+            },
+            ModelEndpoint {
+                timeout_ms: 0,
+                ..endpoint("http://models.internal/embed")
+            },
+            ModelEndpoint {
+                auth: Some(ModelAuth::Header {
+                    name: "bad header".to_string(),
+                    value: "x".to_string(),
+                }),
+                ..endpoint("http://models.internal/embed")
+            },
+        ];
+        for e in bad {
+            assert!(e.validate(ModelKind::Embedding).is_err(), "{e:?}");
+        }
+    }
+
+    /// A model server on a local port: `/health`, `/embed` (three
+    /// dimensions, bearer token `t0k` required) and `/classify`.
+    async fn model_server() -> String {
+        use axum::{
+            routing::{get, post},
+            Router,
+        };
+        let app = Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .route(
+                "/embed",
+                post(
+                    |headers: HeaderMap, Json(body): Json<serde_json::Value>| async move {
+                        if headers.get("authorization").and_then(|v| v.to_str().ok())
+                            != Some("Bearer t0k")
+                        {
+                            return Err(StatusCode::UNAUTHORIZED);
+                        }
+                        let n = body["texts"].as_array().map_or(0, Vec::len);
+                        Ok(Json(json!({ "embeddings": vec![[0.5, 0.25, 1.0]; n] })))
+                    },
+                ),
+            )
+            .route(
+                "/classify",
+                post(|| async {
+                    Json(json!({ "clauses": [
+                        { "clause_type": "Liability", "risk_level": "high", "confidence": 0.8 }
+                    ] }))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn models_must_pass_their_probe_and_serve_only_their_tenant() {
+        let base = model_server().await;
+        let state = AppState::in_memory();
+        let embed = ModelEndpoint {
+            auth: Some(ModelAuth::Bearer {
+                token: "t0k".to_string(),
+            }),
+            dimensions: Some(3),
+            ..endpoint(&format!("{base}/embed"))
+        };
+        assert!(embed.probe(&state, ModelKind::Embedding).await.is_ok());
+        let failures = [
+            ModelEndpoint {
+                auth: None,
+                ..embed.clone()
+            },
+            ModelEndpoint {
+                dimensions: Some(4),
+                ..embed.clone()
+            },
+            ModelEndpoint {
+                health_url: Some(format!("{base}/missing")),
+                ..embed.clone()
+            },
+        ];
+        let errors: Vec<String> = futures_util::future::join_all(
+            failures
+                .iter()
+                .map(|e| e.probe(&state, ModelKind::Embedding)),
+        )
+        .await
+        .into_iter()
+        .map(|r| r.unwrap_err())
+        .collect();
+        assert!(errors[0].contains("401"), "{}", errors[0]);
+        assert!(errors[1].contains("expected 4"), "{}", errors[1]);
+        assert!(
+            errors[2].starts_with("health probe answered 404"),
+            "{}",
+            errors[2]
+        );
+
+        let classify = ModelEndpoint {
+            dimensions: None,
+            ..endpoint(&format!("{base}/classify"))
+        };
+        let probe = classify
+            .probe(&state, ModelKind::Classification)
+            .await
+            .unwrap();
+        state.models.put(RegisteredModel {
+            tenant: "acme".to_string(),
+            kind: ModelKind::Classification,
+            endpoint: classify,
+            registered_at: state.clock.now(),
+            probe,
+        });
+        let req: crate::AnalyzeRequest = serde_json::from_value(
+            json!({ "document": "Liability is capped at the fees paid.", "language": "en" }),
+        )
+        .unwrap();
+        let answer = crate::ensemble::ml_findings(&state, "acme", &req).await;
+        assert_eq!(answer.findings.unwrap().clauses[0].clause_type, "Liability");
+        let calls = state.model_calls.list("acme", &Default::default());
+        assert_eq!(answer.call_id.as_ref(), Some(&calls[0].id));
+        assert!(crate::ensemble::ml_findings(&state, "globex", &req)
+            .await
+            .findings
+            .is_none());
+    }
+
+    #[test]
+    fn summaries_name_the_auth_scheme_but_not_the_secret() {
+        let model = RegisteredModel {
+            tenant: "acme".to_string(),
+            kind: ModelKind::Embedding,
+            endpoint: ModelEndpoint {
+                auth: Some(ModelAuth::Header {
+                    name: "X-Api-Key".to_string(),
+                    value: "s3cret".to_string(),
+                }),
+                ..endpoint("http://models.internal/embed")
+            },
+            registered_at: DateTime::UNIX_EPOCH,
+            probe: Probe {
+                health_ms: 1.0,
+                test_ms: 2.0,
+            },
+        };
+        let json = serde_json::to_string(&model.summary()).unwrap();
+        assert!(json.contains("\"auth\":\"header x-api-key\""), "{json}");
+        assert!(!json.contains("s3cret"));
+    }
+}
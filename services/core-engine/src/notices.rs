@@ -0,0 +1,471 @@
+//! Notice recipients: reads the notices clause of a contract into
+//! structured contacts and merges them into a per-tenant address book, so
+//! operations know where formal notices go for each contract.
+
+use std::collections::BTreeSet;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{extraction::sections, notifier, AppState};
+
+/// Phrases that open one recipient's block inside the notices clause.
+const RECIPIENT_MARKERS: [&str; 3] = [
+    "if to ",
+    "with a copy to",
+    "with a copy (which shall not constitute notice) to",
+];
+const ATTENTION_PREFIXES: [&str; 3] = ["attention:", "attn:", "attn."];
+const IGNORED_PREFIXES: [&str; 5] = ["phone:", "tel:", "tel.", "telephone:", "fax:"];
+/// Longest part still taken for a job title after the recipient's name.
+const MAX_TITLE_WORDS: usize = 5;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct NoticeContact {
+    /// The contract party the block is addressed for ("Supplier").
+    pub party: Option<String>,
+    pub organization: Option<String>,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub email: Option<String>,
+    pub address: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressBookEntry {
+    pub id: String,
+    #[serde(flatten)]
+    pub contact: NoticeContact,
+    /// Analyses whose notices clause names this contact.
+    pub analysis_ids: BTreeSet<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncAction {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncedContact {
+    pub action: SyncAction,
+    #[serde(flatten)]
+    pub entry: AddressBookEntry,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExtractRequest {
+    pub document: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExtractResponse {
+    pub contacts: Vec<NoticeContact>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncResponse {
+    pub analysis_id: String,
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub contacts: Vec<SyncedContact>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AddressBook {
+    pub tenant: String,
+    pub contacts: Vec<AddressBookEntry>,
+}
+
+// ── Extraction ────────────────────────────────────────────────────────────────
+
+/// The numbered section titled "Notices" (or "Notice"), after its heading line.
+fn notices_clause(document: &str) -> Option<&str> {
+    sections(document)
+        .into_iter()
+        .find(|s| s.title.to_ascii_lowercase().starts_with("notice"))
+        .map(|s| &document[s.start + s.heading.len()..s.end])
+}
+
+/// Splits the clause into one block per recipient, each with the party it
+/// names. A copy recipient inherits the party of the block before it.
+fn recipient_blocks(clause: &str) -> Vec<(Option<String>, &str)> {
+    let lower = clause.to_ascii_lowercase();
+    let mut starts: Vec<(usize, usize)> = RECIPIENT_MARKERS
+        .iter()
+        .flat_map(|m| lower.match_indices(m).map(|(at, m)| (at, at + m.len())))
+        .collect();
+    starts.sort_unstable();
+    if starts.is_empty() {
+        return vec![(None, clause)];
+    }
+
+    let mut blocks = Vec::new();
+    let mut party: Option<String> = None;
+    for (i, &(at, body)) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map_or(clause.len(), |n| n.0);
+        let text = &clause[body..end];
+        let is_copy = lower[at..].starts_with("with a copy");
+        let (named, rest) = match text.split_once(':') {
+            Some((head, rest)) if !is_copy && head.split_whitespace().count() <= 4 => (
+                Some(head.trim().trim_start_matches("the ").to_string()),
+                rest,
+            ),
+            Some((head, rest)) if is_copy && head.split_whitespace().count() <= 1 => (None, rest),
+            _ => (None, text),
+        };
+        if named.is_some() {
+            party = named;
+        }
+        blocks.push((party.clone(), rest));
+    }
+    blocks
+}
+
+fn strip_prefix_ci<'a>(part: &'a str, prefixes: &[&str]) -> Option<&'a str> {
+    let lower = part.to_ascii_lowercase();
+    prefixes
+        .iter()
+        .find(|p| lower.starts_with(*p))
+        .map(|p| part[p.len()..].trim())
+}
+
+fn email_in(part: &str) -> Option<String> {
+    part.split_whitespace()
+        .find(|w| w.contains('@'))
+        .map(|w| {
+            w.trim_matches(|c: char| {
+                !(c.is_alphanumeric() || c == '@' || c == '.' || c == '_' || c == '-' || c == '+')
+            })
+            .trim_end_matches('.')
+            .to_string()
+        })
+        .filter(|e| e.contains('@') && e.contains('.'))
+}
+
+fn starts_address(part: &str) -> bool {
+    part.starts_with(|c: char| c.is_ascii_digit())
+        || part.to_ascii_lowercase().starts_with("p.o. box")
+        || part.to_ascii_lowercase().starts_with("po box")
+}
+
+fn contact(party: Option<String>, block: &str) -> Option<NoticeContact> {
+    let mut found = NoticeContact {
+        party,
+        ..NoticeContact::default()
+    };
+    let mut address: Vec<&str> = Vec::new();
+    let mut after_name = false;
+    for part in block.split([',', ';', '\n']) {
+        let part = part.trim().trim_end_matches('.').trim();
+        if part.is_empty() {
+            continue;
+        }
+        let was_after_name = std::mem::take(&mut after_name);
+        if strip_prefix_ci(part, &IGNORED_PREFIXES).is_some() {
+            continue;
+        }
+        if let Some(email) = email_in(part) {
+            found.email.get_or_insert(email);
+        } else if let Some(name) = strip_prefix_ci(part, &ATTENTION_PREFIXES) {
+            found.name.get_or_insert_with(|| name.to_string());
+            after_name = true;
+        } else if !address.is_empty() || starts_address(part) {
+            address.push(part);
+        } else if was_after_name
+            && found.title.is_none()
+            && part.split_whitespace().count() <= MAX_TITLE_WORDS
+        {
+            found.title = Some(part.to_string());
+        } else if found.organization.is_none() {
+            found.organization = Some(part.to_string());
+        }
+    }
+    if !address.is_empty() {
+        found.address = Some(address.join(", "));
+    }
+    (found.name.is_some() || found.email.is_some() || found.address.is_some()).then_some(found)
+}
+
+/// Everyone the notices clause of `document` names, in order.
+pub fn extract(document: &str) -> Vec<NoticeContact> {
+    notices_clause(document)
+        .map(|clause| {
+            recipient_blocks(clause)
+                .into_iter()
+                .filter_map(|(party, block)| contact(party, block))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// ── Address book ──────────────────────────────────────────────────────────────
+
+/// Contacts are the same person when their emails match, or, without an
+/// email, their name and organization do. Both ignore case.
+fn same_contact(a: &NoticeContact, b: &NoticeContact) -> bool {
+    let lower = |s: &Option<String>| s.as_deref().map(str::to_lowercase);
+    match (lower(&a.email), lower(&b.email)) {
+        (Some(x), Some(y)) => x == y,
+        (None, None) => {
+            a.name.is_some()
+                && lower(&a.name) == lower(&b.name)
+                && lower(&a.organization) == lower(&b.organization)
+        }
+        _ => false,
+    }
+}
+
+/// Newer values win; fields the newer contract leaves out are kept.
+fn merge(into: &mut NoticeContact, from: NoticeContact) -> bool {
+    let mut changed = false;
+    for (slot, value) in [
+        (&mut into.party, from.party),
+        (&mut into.organization, from.organization),
+        (&mut into.name, from.name),
+        (&mut into.title, from.title),
+        (&mut into.email, from.email),
+        (&mut into.address, from.address),
+    ] {
+        if value.is_some() && *slot != value {
+            *slot = value;
+            changed = true;
+        }
+    }
+    changed
+}
+
+#[derive(Default)]
+pub struct AddressBookStore {
+    tenants: DashMap<String, Vec<AddressBookEntry>>,
+}
+
+impl AddressBookStore {
+    pub fn sync(
+        &self,
+        tenant: &str,
+        analysis_id: &str,
+        contacts: Vec<NoticeContact>,
+        now: DateTime<Utc>,
+        next_id: &mut dyn FnMut() -> String,
+    ) -> Vec<SyncedContact> {
+        let mut book = self.tenants.entry(tenant.to_string()).or_default();
+        contacts
+            .into_iter()
+            .map(|contact| {
+                let Some(entry) = book.iter_mut().find(|e| same_contact(&e.contact, &contact))
+                else {
+                    let entry = AddressBookEntry {
+                        id: next_id(),
+                        contact,
+                        analysis_ids: BTreeSet::from([analysis_id.to_string()]),
+                        updated_at: now,
+                    };
+                    book.push(entry.clone());
+                    return SyncedContact {
+                        action: SyncAction::Created,
+                        entry,
+                    };
+                };
+                let linked = entry.analysis_ids.insert(analysis_id.to_string());
+                let action = if merge(&mut entry.contact, contact) || linked {
+                    entry.updated_at = now;
+                    SyncAction::Updated
+                } else {
+                    SyncAction::Unchanged
+                };
+                SyncedContact {
+                    action,
+                    entry: entry.clone(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn list(&self, tenant: &str) -> Vec<AddressBookEntry> {
+        self.tenants
+            .get(tenant)
+            .map(|b| b.clone())
+            .unwrap_or_default()
+    }
+
+    /// Drops the tenant's address book, returning how many entries it had.
+    pub fn remove_tenant(&self, tenant: &str) -> usize {
+        self.tenants.remove(tenant).map_or(0, |(_, b)| b.len())
+    }
+
+    /// Drops every entry, in any tenant, that `matches` given the tenant;
+    /// returns the count.
+    pub fn remove_where(&self, matches: impl Fn(&str, &NoticeContact) -> bool) -> usize {
+        self.tenants
+            .iter_mut()
+            .map(|mut book| {
+                let (tenant, book) = book.pair_mut();
+                let before = book.len();
+                book.retain(|e| !matches(tenant, &e.contact));
+                before - book.len()
+            })
+            .sum()
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn extract_contacts(
+    Json(req): Json<ExtractRequest>,
+) -> Result<Json<ExtractResponse>, StatusCode> {
+    if req.document.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(Json(ExtractResponse {
+        contacts: extract(&req.document),
+    }))
+}
+
+pub async fn sync_contacts(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<SyncResponse>, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    let doc = state
+        .corpus
+        .get(&id)
+        .filter(|d| d.tenant == tenant)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let contacts = state.address_book.sync(
+        &tenant,
+        &id,
+        extract(&doc.text),
+        state.clock.now(),
+        &mut || format!("contact-{}", state.ids.next_id()),
+    );
+    let count = |action| contacts.iter().filter(|c| c.action == action).count();
+    let response = SyncResponse {
+        analysis_id: id,
+        created: count(SyncAction::Created),
+        updated: count(SyncAction::Updated),
+        unchanged: count(SyncAction::Unchanged),
+        contacts,
+    };
+    info!(
+        tenant = %tenant,
+        analysis_id = %response.analysis_id,
+        created = response.created,
+        updated = response.updated,
+        "notice contacts synced"
+    );
+    Ok(Json(response))
+}
+
+pub async fn get_address_book(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Json<AddressBook> {
+    let tenant = notifier::tenant(&headers);
+    Json(AddressBook {
+        contacts: state.address_book.list(&tenant),
+        tenant,
+    })
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const CONTRACT: &str = "SUPPLY AGREEMENT\n\n\
+        1. Term. This Agreement runs for two years.\n\
+        12. Notices. All notices shall be in writing and sent to the addresses below.\n\
+        If to Supplier: Acme Corp, Attention: Jane Smith, General Counsel, \
+        123 Main Street, Springfield, IL 62701, Email: legal@acme.com\n\
+        If to Customer:\n\
+        Beta LLC\n\
+        Attn: John Doe\n\
+        1 Market St, San Francisco, CA 94105\n\
+        Tel: +1 415 555 0100\n\
+        with a copy to: Law Offices LLP, Attn: Pat Lee, pat.lee@lawllp.com.\n\
+        13. Assignment. Neither party may assign this Agreement.\n";
+
+    #[test]
+    fn reads_each_recipient_of_the_notices_clause() {
+        let contacts = extract(CONTRACT);
+        assert_eq!(contacts.len(), 3);
+        assert_eq!(
+            contacts[0],
+            NoticeContact {
+                party: Some("Supplier".into()),
+                organization: Some("Acme Corp".into()),
+                name: Some("Jane Smith".into()),
+                title: Some("General Counsel".into()),
+                email: Some("legal@acme.com".into()),
+                address: Some("123 Main Street, Springfield, IL 62701".into()),
+            }
+        );
+        assert_eq!(contacts[1].party.as_deref(), Some("Customer"));
+        assert_eq!(contacts[1].name.as_deref(), Some("John Doe"));
+        assert_eq!(contacts[1].title, None);
+        assert_eq!(
+            contacts[1].address.as_deref(),
+            Some("1 Market St, San Francisco, CA 94105")
+        );
+        // The copy goes to the customer's counsel.
+        assert_eq!(contacts[2].party.as_deref(), Some("Customer"));
+        assert_eq!(contacts[2].email.as_deref(), Some("pat.lee@lawllp.com"));
+        assert!(extract("1. Term. One year.\n2. Fees. Monthly.\n").is_empty());
+    }
+
+    #[test]
+    fn sync_dedupes_by_email_and_keeps_known_fields() {
+        let store = AddressBookStore::default();
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let mut n = 0;
+        let mut next_id = || {
+            n += 1;
+            format!("contact-{n}")
+        };
+        let first = store.sync("acme", "a-1", extract(CONTRACT), now, &mut next_id);
+        assert!(first.iter().all(|c| c.action == SyncAction::Created));
+
+        let again = store.sync("acme", "a-1", extract(CONTRACT), now, &mut next_id);
+        assert!(again.iter().all(|c| c.action == SyncAction::Unchanged));
+
+        let moved = NoticeContact {
+            email: Some("LEGAL@acme.com".into()),
+            address: Some("9 Elm Road, Springfield".into()),
+            ..NoticeContact::default()
+        };
+        let synced = store.sync("acme", "a-2", vec![moved], now, &mut next_id);
+        assert_eq!(synced[0].action, SyncAction::Updated);
+        assert_eq!(synced[0].entry.id, "contact-1");
+        assert_eq!(synced[0].entry.contact.name.as_deref(), Some("Jane Smith"));
+        assert_eq!(
+            synced[0].entry.contact.address.as_deref(),
+            Some("9 Elm Road, Springfield")
+        );
+        assert_eq!(synced[0].entry.analysis_ids.len(), 2);
+
+        assert_eq!(store.list("acme").len(), 3);
+        assert!(store.list("beta").is_empty());
+        assert_eq!(
+            store.remove_where(|_, c| c.name.as_deref() == Some("John Doe")),
+            1
+        );
+        assert_eq!(store.remove_tenant("acme"), 2);
+    }
+}
@@ -0,0 +1,544 @@
+//! Chat and email notifications for analysis outcomes and overdue contract
+//! obligations. Each tenant routes events to its own Slack or Microsoft
+//! Teams incoming webhooks, or to a mail relay that receives the rendered
+//! email, with optional per-route message templates.
+
+use std::collections::{BTreeMap, HashMap};
+
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::{email, warmup::ParsedTemplate, AnalyzeResponse, AppState};
+
+/// Set by the gateway from the caller's credentials.
+pub const TENANT_HEADER: &str = "x-tenant-id";
+/// Routes used for requests without a tenant, or for tenants with no routes.
+pub const DEFAULT_TENANT: &str = "default";
+
+pub const DEFAULT_TOP_ISSUES: usize = 3;
+const PLACEHOLDERS: [&str; 9] = [
+    "tenant",
+    "analysis_id",
+    "doc_name",
+    "risk_level",
+    "risk_score",
+    "top_issues",
+    "escalated",
+    "overdue",
+    "link",
+];
+const ANALYSIS_TEMPLATE: &str = "Analysis complete: *{{doc_name}}*\n\
+    Risk: {{risk_level}} ({{risk_score}})\n{{top_issues}}\n{{link}}";
+const ESCALATION_TEMPLATE: &str = "{{escalated}} finding(s) in *{{doc_name}}* \
+    need human review.\n{{top_issues}}\n{{link}}";
+const OVERDUE_TEMPLATE: &str = "{{overdue}} obligation(s) in *{{doc_name}}* \
+    are past their due date.\n{{top_issues}}\n{{link}}";
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyEvent {
+    AnalysisCompleted,
+    Escalation,
+    /// Obligations in the register went past their due date.
+    ObligationOverdue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifierKind {
+    Slack,
+    Teams,
+    /// A mail relay; receives `{"subject", "text", "html"}`.
+    Email,
+}
+
+/// What a route without `events` gets: overdue obligations are opt-in.
+fn analysis_events() -> Vec<NotifyEvent> {
+    vec![NotifyEvent::AnalysisCompleted, NotifyEvent::Escalation]
+}
+
+fn default_top_issues() -> usize {
+    DEFAULT_TOP_ISSUES
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Route {
+    pub kind: NotifierKind,
+    pub webhook_url: String,
+    #[serde(default = "analysis_events")]
+    pub events: Vec<NotifyEvent>,
+    /// Highest-severity issues listed in the message.
+    #[serde(default = "default_top_issues")]
+    pub top_issues: usize,
+    /// Overrides the built-in message per event; `{{placeholder}}` syntax.
+    #[serde(default)]
+    pub templates: BTreeMap<NotifyEvent, String>,
+}
+
+impl Route {
+    fn notifier(&self) -> Box<dyn Notifier> {
+        match self.kind {
+            NotifierKind::Slack => Box::new(SlackNotifier {
+                webhook_url: self.webhook_url.clone(),
+            }),
+            NotifierKind::Teams => Box::new(TeamsNotifier {
+                webhook_url: self.webhook_url.clone(),
+            }),
+            NotifierKind::Email => Box::new(EmailNotifier {
+                webhook_url: self.webhook_url.clone(),
+                top_issues: self.top_issues,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    /// Base URL of the review UI; links point at `{link_base}/analyses/{id}`.
+    #[serde(default)]
+    pub link_base: Option<String>,
+    #[serde(default)]
+    pub tenants: BTreeMap<String, Vec<Route>>,
+}
+
+impl NotificationConfig {
+    /// Reads `LEGAL_NOTIFICATIONS_FILE` when set. A missing or malformed file,
+    /// or a template with unknown placeholders, stops startup.
+    pub fn from_env() -> Self {
+        match std::env::var("LEGAL_NOTIFICATIONS_FILE") {
+            Ok(path) => {
+                let raw = std::fs::read_to_string(&path)
+                    .unwrap_or_else(|e| panic!("cannot read notifications {path}: {e}"));
+                let config: Self = serde_json::from_str(&raw)
+                    .unwrap_or_else(|e| panic!("invalid notifications {path}: {e}"));
+                config
+                    .validate()
+                    .unwrap_or_else(|e| panic!("invalid notifications {path}: {e}"));
+                config
+            }
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (tenant, routes) in &self.tenants {
+            for route in routes {
+                for (event, template) in &route.templates {
+                    let parsed = ParsedTemplate::parse(template)
+                        .map_err(|e| format!("{tenant} {event:?}: {e}"))?;
+                    let unknown = parsed
+                        .placeholders()
+                        .into_iter()
+                        .find(|p| !PLACEHOLDERS.contains(p))
+                        .map(str::to_string);
+                    if let Some(p) = unknown {
+                        return Err(format!("{tenant} {event:?}: unknown placeholder {p}"));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn routes(&self, tenant: &str) -> &[Route] {
+        self.tenants
+            .get(tenant)
+            .or_else(|| self.tenants.get(DEFAULT_TENANT))
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+// ── Notifiers ─────────────────────────────────────────────────────────────────
+
+/// What a message is about, before it is rendered for a route.
+#[derive(Debug, Clone)]
+pub struct Summary {
+    pub event: NotifyEvent,
+    pub tenant: String,
+    pub analysis_id: String,
+    pub doc_name: String,
+    pub risk_level: String,
+    pub risk_score: f64,
+    /// `(severity, description)`, most severe first.
+    pub issues: Vec<(String, String)>,
+    pub escalated: usize,
+    /// Obligations past their due date, for `obligation_overdue`.
+    pub overdue: usize,
+    /// The risk model's advice for `risk_level`.
+    pub recommendations: Vec<String>,
+    pub link: String,
+}
+
+/// A chat service reached through an incoming webhook.
+pub trait Notifier: Send + Sync {
+    fn webhook_url(&self) -> &str;
+    /// The webhook body carrying `text`, the rendered message.
+    fn payload(&self, summary: &Summary, text: &str) -> Value;
+}
+
+pub struct SlackNotifier {
+    pub webhook_url: String,
+}
+
+impl Notifier for SlackNotifier {
+    fn webhook_url(&self) -> &str {
+        &self.webhook_url
+    }
+
+    fn payload(&self, _summary: &Summary, text: &str) -> Value {
+        json!({ "text": text })
+    }
+}
+
+pub struct TeamsNotifier {
+    pub webhook_url: String,
+}
+
+impl Notifier for TeamsNotifier {
+    fn webhook_url(&self) -> &str {
+        &self.webhook_url
+    }
+
+    fn payload(&self, summary: &Summary, text: &str) -> Value {
+        let color = match summary.risk_level.as_str() {
+            "critical" | "high" => "D13438",
+            "medium" => "FFB900",
+            _ => "107C10",
+        };
+        json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "summary": format!("{}: {}", summary.doc_name, summary.risk_level),
+            "themeColor": color,
+            "title": summary.doc_name,
+            // Teams renders single newlines as spaces.
+            "text": text.replace('\n', "\n\n"),
+        })
+    }
+}
+
+pub struct EmailNotifier {
+    pub webhook_url: String,
+    pub top_issues: usize,
+}
+
+impl Notifier for EmailNotifier {
+    fn webhook_url(&self) -> &str {
+        &self.webhook_url
+    }
+
+    /// `text` is the plaintext part; the HTML part is always the module's.
+    fn payload(&self, summary: &Summary, text: &str) -> Value {
+        let email = email::render(summary, self.top_issues);
+        json!({ "subject": email.subject, "text": text, "html": email.html })
+    }
+}
+
+// ── Delivery ──────────────────────────────────────────────────────────────────
+
+fn severity_rank(severity: &str) -> u8 {
+    match severity {
+        "critical" => 0,
+        "high" => 1,
+        "medium" => 2,
+        _ => 3,
+    }
+}
+
+/// `issues` most severe first, keeping their order within a severity.
+pub fn by_severity(mut issues: Vec<(String, String)>) -> Vec<(String, String)> {
+    issues.sort_by_key(|(severity, _)| severity_rank(severity));
+    issues
+}
+
+pub fn render(route: &Route, summary: &Summary) -> String {
+    let template = route.templates.get(&summary.event).map_or(
+        match summary.event {
+            NotifyEvent::AnalysisCompleted => ANALYSIS_TEMPLATE,
+            NotifyEvent::Escalation => ESCALATION_TEMPLATE,
+            NotifyEvent::ObligationOverdue => OVERDUE_TEMPLATE,
+        },
+        String::as_str,
+    );
+    let top_issues = summary
+        .issues
+        .iter()
+        .take(route.top_issues)
+        .map(|(severity, description)| format!("• [{severity}] {description}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let values = HashMap::from([
+        ("tenant".to_string(), summary.tenant.clone()),
+        ("analysis_id".to_string(), summary.analysis_id.clone()),
+        ("doc_name".to_string(), summary.doc_name.clone()),
+        ("risk_level".to_string(), summary.risk_level.clone()),
+        (
+            "risk_score".to_string(),
+            format!("{:.2}", summary.risk_score),
+        ),
+        ("top_issues".to_string(), top_issues),
+        ("escalated".to_string(), summary.escalated.to_string()),
+        ("overdue".to_string(), summary.overdue.to_string()),
+        ("link".to_string(), summary.link.clone()),
+    ]);
+    // Templates are validated at startup; fall back to the raw text otherwise.
+    ParsedTemplate::parse(template)
+        .and_then(|parsed| parsed.render(&values))
+        .map_or_else(|_| template.to_string(), |rendered| rendered.text)
+}
+
+/// Webhook URL and body for every route of the tenant subscribed to the event.
+pub fn messages(config: &NotificationConfig, summary: &Summary) -> Vec<(String, Value)> {
+    config
+        .routes(&summary.tenant)
+        .iter()
+        .filter(|r| r.events.contains(&summary.event))
+        .map(|route| {
+            let notifier = route.notifier();
+            let text = match route.kind {
+                // Without a template of its own, the plaintext email layout.
+                NotifierKind::Email if !route.templates.contains_key(&summary.event) => {
+                    email::render(summary, route.top_issues).text
+                }
+                _ => render(route, summary),
+            };
+            (
+                notifier.webhook_url().to_string(),
+                notifier.payload(summary, &text),
+            )
+        })
+        .collect()
+}
+
+pub fn tenant(headers: &HeaderMap) -> String {
+    headers
+        .get(TENANT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or(DEFAULT_TENANT)
+        .to_string()
+}
+
+/// Where notifications point for analysis `id`: the review UI when
+/// `link_base` is set, the API otherwise.
+pub fn link(state: &AppState, id: &str) -> String {
+    match &state.notifications.link_base {
+        Some(base) => format!("{}/analyses/{id}", base.trim_end_matches('/')),
+        None => format!("/api/v1/legal/analyses/{id}"),
+    }
+}
+
+/// Fire-and-forget notifications for a finished analysis: one completion
+/// message, plus an escalation message when findings went to human review.
+pub fn analysis_completed(
+    state: &AppState,
+    tenant: &str,
+    doc_name: Option<&str>,
+    analysis: &AnalyzeResponse,
+) {
+    let issues = by_severity(
+        analysis
+            .issues
+            .iter()
+            .map(|i| (i.severity.clone(), i.description.clone()))
+            .collect(),
+    );
+    let risk_level = state.risk_model.level(analysis.risk_score);
+    let mut summary = Summary {
+        event: NotifyEvent::AnalysisCompleted,
+        tenant: tenant.to_string(),
+        analysis_id: analysis.analysis_id.clone(),
+        doc_name: doc_name.map_or_else(
+            || format!("Analysis {}", analysis.analysis_id),
+            str::to_string,
+        ),
+        recommendations: state.risk_model.recommendations(&risk_level),
+        risk_level,
+        risk_score: analysis.risk_score,
+        issues,
+        escalated: analysis.escalations.len(),
+        overdue: 0,
+        link: link(state, &analysis.analysis_id),
+    };
+    send(state, &summary);
+    if summary.escalated > 0 {
+        summary.event = NotifyEvent::Escalation;
+        send(state, &summary);
+    }
+}
+
+/// Fire-and-forget `obligation_overdue` messages for analysis `id`, each
+/// obligation given as `(due date, description)`.
+pub fn obligations_overdue(
+    state: &AppState,
+    tenant: &str,
+    analysis_id: &str,
+    obligations: Vec<(String, String)>,
+) {
+    send(
+        state,
+        &Summary {
+            event: NotifyEvent::ObligationOverdue,
+            tenant: tenant.to_string(),
+            analysis_id: analysis_id.to_string(),
+            doc_name: format!("Analysis {analysis_id}"),
+            risk_level: String::new(),
+            risk_score: 0.0,
+            overdue: obligations.len(),
+            issues: obligations,
+            escalated: 0,
+            recommendations: Vec::new(),
+            link: link(state, analysis_id),
+        },
+    );
+}
+
+fn send(state: &AppState, summary: &Summary) {
+    for (url, body) in messages(&state.notifications, summary) {
+        let client = state.http.clone();
+        let analysis_id = summary.analysis_id.clone();
+        let event = summary.event;
+        tokio::spawn(async move {
+            match client.post(&url).json(&body).send().await {
+                Ok(resp) if resp.status().is_success() => {
+                    info!(analysis_id = %analysis_id, ?event, "notification delivered");
+                }
+                Ok(resp) => {
+                    warn!(analysis_id = %analysis_id, ?event, status = %resp.status(), "notification rejected");
+                }
+                Err(e) => {
+                    warn!(analysis_id = %analysis_id, ?event, error = %e, "notification failed")
+                }
+            }
+        });
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(event: NotifyEvent, tenant: &str) -> Summary {
+        Summary {
+            event,
+            tenant: tenant.to_string(),
+            analysis_id: "a-1".to_string(),
+            doc_name: "Acme MSA".to_string(),
+            risk_level: "high".to_string(),
+            risk_score: 0.55,
+            issues: vec![
+                ("high".to_string(), "No liability cap.".to_string()),
+                ("medium".to_string(), "Auto-renewal.".to_string()),
+            ],
+            escalated: 1,
+            overdue: 0,
+            recommendations: vec!["Negotiate a liability cap.".to_string()],
+            link: "https://legal.example.com/analyses/a-1".to_string(),
+        }
+    }
+
+    fn config() -> NotificationConfig {
+        serde_json::from_value(json!({
+            "tenants": {
+                "default": [{ "kind": "slack", "webhook_url": "https://hooks.slack/default" }],
+                "acme": [
+                    {
+                        "kind": "teams",
+                        "webhook_url": "https://teams/acme",
+                        "events": ["escalation"],
+                        "top_issues": 1,
+                        "templates": { "escalation": "{{doc_name}}: {{escalated}} to review\n{{top_issues}}" }
+                    }
+                ]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn default_slack_message_summarises_the_analysis() {
+        let out = messages(&config(), &summary(NotifyEvent::AnalysisCompleted, "other"));
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].0, "https://hooks.slack/default");
+        let text = out[0].1["text"].as_str().unwrap();
+        assert!(text.starts_with("Analysis complete: *Acme MSA*\nRisk: high (0.55)\n"));
+        assert!(text.contains("• [high] No liability cap.\n• [medium] Auto-renewal."));
+        assert!(text.ends_with("/analyses/a-1"));
+    }
+
+    #[test]
+    fn tenant_routes_filter_events_and_use_their_templates() {
+        let config = config();
+        assert!(messages(&config, &summary(NotifyEvent::AnalysisCompleted, "acme")).is_empty());
+        let out = messages(&config, &summary(NotifyEvent::Escalation, "acme"));
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].1["@type"], "MessageCard");
+        assert_eq!(out[0].1["themeColor"], "D13438");
+        assert_eq!(
+            out[0].1["text"],
+            "Acme MSA: 1 to review\n\n• [high] No liability cap."
+        );
+    }
+
+    #[test]
+    fn email_routes_send_subject_and_both_parts() {
+        let config: NotificationConfig = serde_json::from_value(json!({
+            "tenants": {
+                "acme": [{ "kind": "email", "webhook_url": "https://mail.acme/relay" }]
+            }
+        }))
+        .unwrap();
+        let out = messages(&config, &summary(NotifyEvent::AnalysisCompleted, "acme"));
+        assert_eq!(out[0].0, "https://mail.acme/relay");
+        assert_eq!(out[0].1["subject"], "Acme MSA: high risk (0.55)");
+        assert!(out[0].1["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("Acme MSA\n\nRisk: high (0.55)\n"));
+        assert!(out[0].1["html"]
+            .as_str()
+            .unwrap()
+            .contains("<li>Negotiate a liability cap.</li>"));
+    }
+
+    #[test]
+    fn overdue_obligations_reach_only_routes_that_ask_for_them() {
+        let mut config = config();
+        let overdue = Summary {
+            event: NotifyEvent::ObligationOverdue,
+            issues: vec![(
+                "due 2026-01-16".to_string(),
+                "Vendor shall deliver the migration plan.".to_string(),
+            )],
+            overdue: 1,
+            ..summary(NotifyEvent::ObligationOverdue, "other")
+        };
+        assert!(messages(&config, &overdue).is_empty());
+        config.tenants.get_mut("default").unwrap()[0]
+            .events
+            .push(NotifyEvent::ObligationOverdue);
+        let out = messages(&config, &overdue);
+        assert_eq!(
+            out[0].1["text"],
+            "1 obligation(s) in *Acme MSA* are past their due date.\n\
+             • [due 2026-01-16] Vendor shall deliver the migration plan.\n\
+             https://legal.example.com/analyses/a-1"
+        );
+    }
+
+    #[test]
+    fn unknown_placeholders_are_rejected() {
+        let mut config = config();
+        config.tenants.get_mut("acme").unwrap()[0]
+            .templates
+            .insert(NotifyEvent::Escalation, "{{contract_value}}".to_string());
+        assert!(config.validate().unwrap_err().contains("contract_value"));
+        assert!(NotificationConfig::default().validate().is_ok());
+    }
+}
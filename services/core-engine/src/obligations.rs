@@ -0,0 +1,1128 @@
+//! Obligations register: who has to do what, and by when. Sentences with
+//! an obligation marker ("shall", "must", "agrees to") become records with
+//! the responsible party and the timing they state, and the term's expiry,
+//! renewal and notice dates are listed beside them, so contract managers
+//! get a register to track rather than a list of risk flags. Synced from a
+//! stored analysis, the obligations join the tenant's register, where each
+//! gets a RACI assignment and a status, and those that go past their due
+//! date are announced to the tenant's notification routes and webhooks.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, Utc, Weekday};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use crate::{
+    evidence::{self, Position},
+    notices::SyncAction,
+    notifier,
+    renewal::{self, parse_date},
+    webhooks, AppState,
+};
+
+/// The earliest of these in a sentence splits it into party and action.
+const MARKERS: [&str; 5] = [
+    " shall ",
+    " must ",
+    " agrees to ",
+    " agree to ",
+    " undertakes to ",
+];
+
+/// Words before a period that belong to its phrase, longest first.
+const LEADS: [&[&str]; 7] = [
+    &["no", "later", "than"],
+    &["not", "later", "than"],
+    &["not", "less", "than"],
+    &["at", "least"],
+    &["within"],
+    &["upon"],
+    &["every"],
+];
+
+/// Words tying a period to the event it counts from or to.
+const RELATIONS: [&[&str]; 5] = [
+    &["prior", "to"],
+    &["after"],
+    &["following"],
+    &["before"],
+    &["from"],
+];
+
+const RECURRENCES: [(&str, &str); 9] = [
+    ("weekly", "weekly"),
+    ("each week", "weekly"),
+    ("monthly", "monthly"),
+    ("each month", "monthly"),
+    ("every month", "monthly"),
+    ("quarterly", "quarterly"),
+    ("each quarter", "quarterly"),
+    ("annually", "annually"),
+    ("each year", "annually"),
+];
+
+const NUMBER_WORDS: [(&str, u32); 16] = [
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("ten", 10),
+    ("twelve", 12),
+    ("fourteen", 14),
+    ("fifteen", 15),
+    ("twenty", 20),
+    ("thirty", 30),
+    ("forty-five", 45),
+    ("sixty", 60),
+    ("ninety", 90),
+];
+
+/// Triggers that name a date the contract itself gives.
+const EFFECTIVE_ANCHORS: [&str; 2] = ["effective date", "commencement date"];
+const EXPIRY_ANCHORS: [&str; 3] = ["expir", "end of the", "term ends"];
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct ObligationsRequest {
+    pub document: String,
+    /// Turns periods counted from the effective date into due dates.
+    #[serde(default)]
+    pub effective_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObligationKind {
+    Obligation,
+    /// "shall not", "Neither party shall".
+    Prohibition,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Timing {
+    /// The phrase the timing was read from, e.g. "within 30 days after
+    /// receipt of the invoice".
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u32>,
+    /// `days`, `business_days`, `weeks`, `months` or `years`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    /// The event the period counts from or to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger: Option<String>,
+    /// A date the sentence names, or the period applied to the effective
+    /// date or the expiry when it counts from one of them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<NaiveDate>,
+    /// `weekly`, `monthly`, `quarterly` or `annually`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Obligation {
+    pub id: String,
+    pub kind: ObligationKind,
+    /// The sentence's subject as written, e.g. "Vendor" or "Each party".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub party: Option<String>,
+    /// The sentence from its marker on, e.g. "shall pay each invoice".
+    pub action: String,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<Timing>,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateKind {
+    Expiry,
+    Renewal,
+    /// Last day to give notice of non-renewal.
+    RenewalNotice,
+    /// A notice period for termination; dated only when the contract dates it.
+    TerminationNotice,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyDate {
+    pub kind: DateKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notice: Option<Timing>,
+    pub text: String,
+    pub position: Position,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ObligationsResponse {
+    pub parties: Vec<String>,
+    pub obligations: Vec<Obligation>,
+    pub dates: Vec<KeyDate>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObligationStatus {
+    #[default]
+    Open,
+    InProgress,
+    Done,
+    Waived,
+}
+
+impl ObligationStatus {
+    /// Still to be met, so it can fall overdue.
+    pub fn is_pending(self) -> bool {
+        matches!(self, Self::Open | Self::InProgress)
+    }
+}
+
+/// Who does the work, who answers for it, who is asked and who is told.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Raci {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub responsible: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accountable: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub consulted: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub informed: Vec<String>,
+}
+
+impl Raci {
+    pub fn names_mut(&mut self) -> impl Iterator<Item = &mut String> {
+        self.responsible
+            .iter_mut()
+            .chain(self.accountable.iter_mut())
+            .chain(self.consulted.iter_mut())
+            .chain(self.informed.iter_mut())
+    }
+
+    fn names(&self) -> impl Iterator<Item = &String> {
+        self.responsible
+            .iter()
+            .chain(self.accountable.iter())
+            .chain(self.consulted.iter())
+            .chain(self.informed.iter())
+    }
+}
+
+/// An obligation in the tenant's register.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackedObligation {
+    pub id: String,
+    pub analysis_id: String,
+    pub obligation: Obligation,
+    pub raci: Raci,
+    pub status: ObligationStatus,
+    /// The timing's due date, unless one was set by hand.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Past its due date and still open or in progress, as of the request.
+    pub overdue: bool,
+    pub updated_at: DateTime<Utc>,
+    /// `due_date` was set by hand, so a re-sync keeps it.
+    #[serde(skip)]
+    due_date_set: bool,
+    /// The due date an overdue notice went out for.
+    #[serde(skip)]
+    announced_for: Option<NaiveDate>,
+}
+
+impl TrackedObligation {
+    fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.status.is_pending() && self.due_date.is_some_and(|due| due < today)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncedObligation {
+    pub action: SyncAction,
+    #[serde(flatten)]
+    pub entry: TrackedObligation,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncParams {
+    /// Turns periods counted from the effective date into due dates.
+    #[serde(default)]
+    pub effective_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncResponse {
+    pub analysis_id: String,
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub obligations: Vec<SyncedObligation>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RegisterFilter {
+    #[serde(default)]
+    pub status: Option<ObligationStatus>,
+    /// Anyone named in the RACI assignment, ignoring case.
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub analysis_id: Option<String>,
+    #[serde(default)]
+    pub overdue: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RegisterResponse {
+    pub tenant: String,
+    /// Overdue obligations among those listed.
+    pub overdue: usize,
+    pub obligations: Vec<TrackedObligation>,
+}
+
+/// Fields left out stay as they are.
+#[derive(Debug, Default, Deserialize)]
+pub struct ObligationUpdate {
+    #[serde(default)]
+    pub status: Option<ObligationStatus>,
+    /// Replaces the whole assignment.
+    #[serde(default)]
+    pub raci: Option<Raci>,
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+// ── Extraction ────────────────────────────────────────────────────────────────
+
+/// Sentences and semicolon-separated parts with their byte offsets. A
+/// period only ends one before a capital or digit, so "Corp. and" and
+/// "4.2" stay whole.
+fn sentences(text: &str) -> Vec<(usize, &str)> {
+    let mut out = Vec::new();
+    let mut push = |start: usize, end: usize| {
+        let part = &text[start..end];
+        let trimmed = part.trim();
+        if !trimmed.is_empty() {
+            out.push((start + part.len() - part.trim_start().len(), trimmed));
+        }
+    };
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        let ends = match c {
+            '\n' | ';' => true,
+            '.' => {
+                let after = &text[i + 1..];
+                after.is_empty()
+                    || after.starts_with(char::is_whitespace)
+                        && after
+                            .trim_start()
+                            .starts_with(|c: char| c.is_uppercase() || c.is_ascii_digit())
+            }
+            _ => false,
+        };
+        if ends {
+            push(start, i + usize::from(c == '.'));
+            start = i + 1;
+        }
+    }
+    push(start, text.len());
+    out
+}
+
+fn clean(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric() && c != '-')
+        .to_lowercase()
+}
+
+/// "30", "(30)" or "thirty".
+fn number(word: &str) -> Option<u32> {
+    let word = clean(word);
+    word.parse().ok().or_else(|| {
+        NUMBER_WORDS
+            .iter()
+            .find(|(w, _)| *w == word)
+            .map(|&(_, n)| n)
+    })
+}
+
+/// The unit starting `words` and how many words it takes.
+fn unit(words: &[&str]) -> Option<(&'static str, usize)> {
+    let first = clean(words.first()?);
+    let second = words.get(1).map(|w| clean(w)).unwrap_or_default();
+    if (first == "business" || first == "working") && second.starts_with("day") {
+        return Some(("business_days", 2));
+    }
+    [
+        ("day", "days"),
+        ("week", "weeks"),
+        ("month", "months"),
+        ("year", "years"),
+    ]
+    .iter()
+    .find(|(stem, _)| first.starts_with(stem))
+    .map(|&(_, unit)| (unit, 1))
+}
+
+fn starts_with_words(words: &[&str], phrase: &[&str]) -> bool {
+    words.len() >= phrase.len() && words.iter().zip(phrase).all(|(w, p)| clean(w) == *p)
+}
+
+fn shift(date: NaiveDate, amount: u32, unit: &str, forward: bool) -> Option<NaiveDate> {
+    let step = |d: NaiveDate, days: u64| {
+        if forward {
+            d.checked_add_days(Days::new(days))
+        } else {
+            d.checked_sub_days(Days::new(days))
+        }
+    };
+    let months = |d: NaiveDate, n: u32| {
+        if forward {
+            d.checked_add_months(Months::new(n))
+        } else {
+            d.checked_sub_months(Months::new(n))
+        }
+    };
+    match unit {
+        "days" => step(date, u64::from(amount)),
+        "weeks" => step(date, u64::from(amount) * 7),
+        "months" => months(date, amount),
+        "years" => months(date, amount * 12),
+        "business_days" => {
+            let mut date = date;
+            let mut left = amount;
+            while left > 0 {
+                date = step(date, 1)?;
+                if !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+                    left -= 1;
+                }
+            }
+            Some(date)
+        }
+        _ => None,
+    }
+}
+
+struct Period {
+    from: usize,
+    to: usize,
+    amount: u32,
+    unit: &'static str,
+    /// Relation word and the event after it.
+    trigger: Option<(String, String)>,
+}
+
+/// Every "<n> <unit>" in `words`, with its lead-in and trigger.
+fn periods(words: &[&str]) -> Vec<Period> {
+    let mut found = Vec::new();
+    for i in 0..words.len() {
+        let Some(amount) = number(words[i]) else {
+            continue;
+        };
+        let Some((unit, len)) = unit(&words[i + 1..]) else {
+            continue;
+        };
+        // "thirty (30) days"
+        let mut from = if i > 0 && number(words[i - 1]) == Some(amount) {
+            i - 1
+        } else {
+            i
+        };
+        if let Some(lead) = LEADS
+            .iter()
+            .find(|l| from >= l.len() && starts_with_words(&words[from - l.len()..], l))
+        {
+            from -= lead.len();
+        }
+        let mut to = i + 1 + len;
+        let trigger = RELATIONS
+            .iter()
+            .find(|r| starts_with_words(&words[to..], r))
+            .and_then(|relation| {
+                let start = to + relation.len();
+                let end = words[start..]
+                    .iter()
+                    .position(|w| w.ends_with(','))
+                    .map_or(words.len(), |p| start + p + 1)
+                    .min(start + 8);
+                let event = words[start..end]
+                    .join(" ")
+                    .trim_end_matches(['.', ',', ':'])
+                    .to_string();
+                let event = event.strip_prefix("the ").unwrap_or(&event).to_string();
+                (!event.is_empty()).then(|| {
+                    to = end;
+                    (relation.join(" "), event)
+                })
+            });
+        found.push(Period {
+            from,
+            to,
+            amount,
+            unit,
+            trigger,
+        });
+    }
+    found
+}
+
+/// The timing `sentence` states, if any. A period tied to an event wins
+/// over a bare one ("renews for one year unless notice is given 60 days
+/// before expiry").
+fn timing(
+    sentence: &str,
+    effective: Option<NaiveDate>,
+    expiry: Option<NaiveDate>,
+) -> Option<Timing> {
+    let words: Vec<&str> = sentence.split_whitespace().collect();
+    let lower = sentence.to_lowercase();
+    let recurrence = RECURRENCES
+        .iter()
+        .find(|(phrase, _)| lower.contains(phrase))
+        .map(|&(_, r)| r.to_string());
+    let dated = (0..words.len()).find_map(|i| Some((i, parse_date(&words[i..])?)));
+    let mut periods = periods(&words);
+    let period = match periods.iter().position(|p| p.trigger.is_some()) {
+        Some(i) => Some(periods.swap_remove(i)),
+        None => periods.into_iter().next(),
+    };
+    if period.is_none() && dated.is_none() && recurrence.is_none() {
+        return None;
+    }
+
+    let anchored = period.as_ref().and_then(|p| {
+        let (relation, event) = p.trigger.as_ref()?;
+        let event = event.to_lowercase();
+        let anchor = if EFFECTIVE_ANCHORS.iter().any(|a| event.contains(a)) {
+            effective
+        } else if EXPIRY_ANCHORS.iter().any(|a| event.contains(a)) {
+            expiry
+        } else {
+            None
+        }?;
+        let forward = !matches!(relation.as_str(), "before" | "prior to");
+        shift(anchor, p.amount, p.unit, forward)
+    });
+    let text = match (&period, dated) {
+        (Some(p), _) => words[p.from..p.to].join(" "),
+        // The date and the word before it: "by March 1, 2026".
+        (None, Some((i, _))) => words[i.saturating_sub(1)..(i + 3).min(words.len())].join(" "),
+        (None, None) => recurrence.clone().unwrap_or_default(),
+    };
+    Some(Timing {
+        text: text.trim_end_matches(['.', ',', ';', ':']).to_string(),
+        amount: period.as_ref().map(|p| p.amount),
+        unit: period.as_ref().map(|p| p.unit.to_string()),
+        trigger: period.and_then(|p| p.trigger).map(|(_, event)| event),
+        due_date: dated.map(|(_, d)| d).or(anchored),
+        recurrence,
+    })
+}
+
+/// The subject before the marker, after any lead-in clause: "Upon
+/// termination, the Vendor" is the Vendor's.
+fn party(subject: &str) -> Option<String> {
+    let subject = subject.rsplit(',').next()?.trim();
+    let subject = ["the ", "The "]
+        .iter()
+        .find_map(|p| subject.strip_prefix(p))
+        .unwrap_or(subject)
+        .trim();
+    let words = subject.split_whitespace().count();
+    (1..=6).contains(&words).then(|| subject.to_string())
+}
+
+/// The obligations in `document`, numbered in document order.
+pub fn extract(
+    document: &str,
+    effective: Option<NaiveDate>,
+    expiry: Option<NaiveDate>,
+) -> Vec<Obligation> {
+    sentences(document)
+        .into_iter()
+        .filter_map(|(start, sentence)| {
+            let padded = format!(" {}", sentence.to_ascii_lowercase());
+            let (at, marker) = MARKERS
+                .iter()
+                .filter_map(|m| padded.find(m).map(|i| (i, m)))
+                .min()?;
+            // `padded` leads with a space, so `at` is the space before the marker.
+            let subject = &sentence[..at.saturating_sub(1).min(sentence.len())];
+            let action = sentence[at..].trim();
+            let negated = padded[at + marker.len()..].starts_with("not ");
+            let lower_subject = subject.trim().to_lowercase();
+            let kind = if negated
+                || lower_subject.starts_with("neither ")
+                || lower_subject.starts_with("no ")
+            {
+                ObligationKind::Prohibition
+            } else {
+                ObligationKind::Obligation
+            };
+            Some(Obligation {
+                id: String::new(),
+                kind,
+                party: party(subject),
+                action: action.to_string(),
+                text: sentence.to_string(),
+                timing: timing(sentence, effective, expiry),
+                position: evidence::position(document, start, start + sentence.len()),
+            })
+        })
+        .enumerate()
+        .map(|(i, o)| Obligation {
+            id: format!("obligation-{:03}", i + 1),
+            ..o
+        })
+        .collect()
+}
+
+/// Expiry, renewal and notice dates, in document order.
+pub fn key_dates(document: &str, expiry: Option<NaiveDate>) -> Vec<KeyDate> {
+    let mut dates = Vec::new();
+    for (start, sentence) in sentences(document) {
+        let lower = sentence.to_lowercase();
+        let date = |kind, date, notice| KeyDate {
+            kind,
+            date,
+            notice,
+            text: sentence.to_string(),
+            position: evidence::position(document, start, start + sentence.len()),
+        };
+        if let Some(ends) = renewal::expiry_date(sentence) {
+            dates.push(date(DateKind::Expiry, Some(ends), None));
+        }
+        let renews = lower.contains("renew");
+        if renews && (lower.contains("automatic") || lower.contains("auto-renew")) {
+            dates.push(date(DateKind::Renewal, expiry, None));
+        }
+        if !lower.contains("notice") {
+            continue;
+        }
+        let notice = timing(sentence, None, expiry).filter(|t| t.amount.is_some());
+        if renews {
+            if let Some(notice) = notice {
+                dates.push(date(DateKind::RenewalNotice, notice.due_date, Some(notice)));
+            }
+        } else if lower.contains("terminat") {
+            if let Some(notice) = notice {
+                dates.push(date(
+                    DateKind::TerminationNotice,
+                    notice.due_date,
+                    Some(notice),
+                ));
+            }
+        }
+    }
+    dates
+}
+
+// ── Register ──────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct ObligationRegister {
+    tenants: DashMap<String, Vec<TrackedObligation>>,
+}
+
+impl ObligationRegister {
+    /// Adds the obligations extracted from analysis `analysis_id`. One
+    /// already registered with the same text keeps its assignment, status
+    /// and any due date set by hand; its timing and position are refreshed.
+    pub fn sync(
+        &self,
+        tenant: &str,
+        analysis_id: &str,
+        obligations: Vec<Obligation>,
+        now: DateTime<Utc>,
+        next_id: &mut dyn FnMut() -> String,
+    ) -> Vec<SyncedObligation> {
+        let mut register = self.tenants.entry(tenant.to_string()).or_default();
+        obligations
+            .into_iter()
+            .map(|obligation| {
+                let due = obligation.timing.as_ref().and_then(|t| t.due_date);
+                let Some(entry) = register
+                    .iter_mut()
+                    .find(|e| e.analysis_id == analysis_id && e.obligation.text == obligation.text)
+                else {
+                    let entry = TrackedObligation {
+                        id: next_id(),
+                        analysis_id: analysis_id.to_string(),
+                        obligation,
+                        raci: Raci::default(),
+                        status: ObligationStatus::Open,
+                        due_date: due,
+                        note: None,
+                        overdue: false,
+                        updated_at: now,
+                        due_date_set: false,
+                        announced_for: None,
+                    };
+                    register.push(entry.clone());
+                    return SyncedObligation {
+                        action: SyncAction::Created,
+                        entry,
+                    };
+                };
+                let due = if entry.due_date_set {
+                    entry.due_date
+                } else {
+                    due
+                };
+                let action = if entry.obligation != obligation || entry.due_date != due {
+                    entry.obligation = obligation;
+                    entry.due_date = due;
+                    entry.updated_at = now;
+                    SyncAction::Updated
+                } else {
+                    SyncAction::Unchanged
+                };
+                SyncedObligation {
+                    action,
+                    entry: entry.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// The tenant's register in the order obligations were added, with
+    /// `overdue` as of `today`.
+    pub fn list(&self, tenant: &str, today: NaiveDate) -> Vec<TrackedObligation> {
+        self.tenants
+            .get(tenant)
+            .map(|r| {
+                r.iter()
+                    .map(|e| TrackedObligation {
+                        overdue: e.is_overdue(today),
+                        ..e.clone()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// `None` when the tenant has no obligation `id`.
+    pub fn update(
+        &self,
+        tenant: &str,
+        id: &str,
+        update: ObligationUpdate,
+        now: DateTime<Utc>,
+    ) -> Option<TrackedObligation> {
+        let mut register = self.tenants.get_mut(tenant)?;
+        let entry = register.iter_mut().find(|e| e.id == id)?;
+        if let Some(status) = update.status {
+            entry.status = status;
+        }
+        if let Some(raci) = update.raci {
+            entry.raci = raci;
+        }
+        if let Some(due) = update.due_date {
+            entry.due_date = Some(due);
+            entry.due_date_set = true;
+        }
+        if let Some(note) = update.note {
+            entry.note = Some(note).filter(|n| !n.trim().is_empty());
+        }
+        entry.updated_at = now;
+        Some(TrackedObligation {
+            overdue: entry.is_overdue(now.date_naive()),
+            ..entry.clone()
+        })
+    }
+
+    /// Obligations overdue as of `today` that have not been announced for
+    /// their current due date, by tenant; they count as announced from now.
+    pub fn newly_overdue(&self, today: NaiveDate) -> Vec<(String, Vec<TrackedObligation>)> {
+        self.tenants
+            .iter_mut()
+            .filter_map(|mut register| {
+                let due: Vec<TrackedObligation> = register
+                    .iter_mut()
+                    .filter(|e| e.is_overdue(today) && e.announced_for != e.due_date)
+                    .map(|e| {
+                        e.announced_for = e.due_date;
+                        TrackedObligation {
+                            overdue: true,
+                            ..e.clone()
+                        }
+                    })
+                    .collect();
+                (!due.is_empty()).then(|| (register.key().clone(), due))
+            })
+            .collect()
+    }
+
+    /// Drops the tenant's register, returning how many obligations it had.
+    pub fn remove_tenant(&self, tenant: &str) -> usize {
+        self.tenants.remove(tenant).map_or(0, |(_, r)| r.len())
+    }
+
+    /// Runs `f` over every tracked obligation along with its tenant.
+    pub fn for_each_mut(&self, mut f: impl FnMut(&str, &mut TrackedObligation)) {
+        for mut register in self.tenants.iter_mut() {
+            let (tenant, register) = register.pair_mut();
+            register.iter_mut().for_each(|o| f(tenant, o));
+        }
+    }
+}
+
+// ── Overdue checks ────────────────────────────────────────────────────────────
+
+fn describe(entry: &TrackedObligation) -> (String, String) {
+    let due = entry
+        .due_date
+        .map(|d| format!("due {d}"))
+        .unwrap_or_default();
+    let owner = entry
+        .raci
+        .responsible
+        .as_ref()
+        .map(|r| format!(" (responsible: {r})"))
+        .unwrap_or_default();
+    (due, format!("{}{owner}", entry.obligation.text))
+}
+
+/// Announces obligations that went past their due date since the last
+/// check: one message per analysis to the tenant's notification routes and
+/// webhooks. Each obligation is announced once per due date. Returns how
+/// many were announced.
+pub fn announce_overdue(state: &AppState) -> usize {
+    let mut announced = 0;
+    for (tenant, overdue) in state.obligations.newly_overdue(state.clock.today()) {
+        let mut by_analysis: BTreeMap<&str, Vec<&TrackedObligation>> = BTreeMap::new();
+        for entry in &overdue {
+            by_analysis
+                .entry(&entry.analysis_id)
+                .or_default()
+                .push(entry);
+        }
+        for (analysis_id, entries) in by_analysis {
+            let items = entries.iter().map(|e| describe(e)).collect();
+            notifier::obligations_overdue(state, &tenant, analysis_id, items);
+            webhooks::obligations_overdue(state, &tenant, analysis_id, &entries);
+        }
+        info!(tenant = %tenant, count = overdue.len(), "overdue obligations announced");
+        announced += overdue.len();
+    }
+    announced
+}
+
+/// Checks for overdue obligations on the configured interval for the life
+/// of the process.
+pub fn spawn_overdue_checks(state: AppState, interval: Duration) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            announce_overdue(&state);
+        }
+    });
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn extract_obligations(
+    Json(req): Json<ObligationsRequest>,
+) -> Result<Json<ObligationsResponse>, StatusCode> {
+    if req.document.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let expiry = renewal::expiry_date(&req.document);
+    Ok(Json(ObligationsResponse {
+        parties: renewal::parties(&req.document)
+            .map(|(a, b)| vec![a, b])
+            .unwrap_or_default(),
+        obligations: extract(&req.document, req.effective_date, expiry),
+        dates: key_dates(&req.document, expiry),
+    }))
+}
+
+pub async fn sync_obligations(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<SyncParams>,
+    headers: HeaderMap,
+) -> Result<Json<SyncResponse>, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    let doc = state
+        .corpus
+        .get(&id)
+        .filter(|d| d.tenant == tenant)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let expiry = renewal::expiry_date(&doc.text);
+    let mut obligations = state.obligations.sync(
+        &tenant,
+        &id,
+        extract(&doc.text, params.effective_date, expiry),
+        state.clock.now(),
+        &mut || format!("obl-{}", state.ids.next_id()),
+    );
+    let today = state.clock.today();
+    for synced in &mut obligations {
+        synced.entry.overdue = synced.entry.is_overdue(today);
+    }
+    let count = |action| obligations.iter().filter(|o| o.action == action).count();
+    let response = SyncResponse {
+        analysis_id: id,
+        created: count(SyncAction::Created),
+        updated: count(SyncAction::Updated),
+        unchanged: count(SyncAction::Unchanged),
+        obligations,
+    };
+    info!(
+        tenant = %tenant,
+        analysis_id = %response.analysis_id,
+        created = response.created,
+        updated = response.updated,
+        "obligations synced"
+    );
+    Ok(Json(response))
+}
+
+pub async fn get_register(
+    State(state): State<AppState>,
+    Query(filter): Query<RegisterFilter>,
+    headers: HeaderMap,
+) -> Json<RegisterResponse> {
+    let tenant = notifier::tenant(&headers);
+    let owner = filter.owner.map(|o| o.to_lowercase());
+    let obligations: Vec<TrackedObligation> = state
+        .obligations
+        .list(&tenant, state.clock.today())
+        .into_iter()
+        .filter(|e| filter.status.is_none_or(|s| e.status == s))
+        .filter(|e| {
+            filter
+                .analysis_id
+                .as_ref()
+                .is_none_or(|a| e.analysis_id == *a)
+        })
+        .filter(|e| !filter.overdue || e.overdue)
+        .filter(|e| {
+            owner
+                .as_ref()
+                .is_none_or(|o| e.raci.names().any(|n| n.to_lowercase() == *o))
+        })
+        .collect();
+    Json(RegisterResponse {
+        tenant,
+        overdue: obligations.iter().filter(|e| e.overdue).count(),
+        obligations,
+    })
+}
+
+pub async fn update_obligation(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(update): Json<ObligationUpdate>,
+) -> Result<Json<TrackedObligation>, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    let detail = json!({
+        "status": update.status,
+        "raci": update.raci,
+        "due_date": update.due_date,
+    });
+    let entry = state
+        .obligations
+        .update(&tenant, &id, update, state.clock.now())
+        .ok_or(StatusCode::NOT_FOUND)?;
+    info!(tenant = %tenant, obligation_id = %id, status = ?entry.status, "obligation updated");
+    state.record_audit("obligation.updated", &id, None, detail);
+    Ok(Json(entry))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTRACT: &str = "SERVICES AGREEMENT between Acme Corp. and Beta Software, Inc.\n\
+        1. Term. This Agreement expires on December 31, 2026. It automatically renews \
+        for successive one year terms unless either party gives notice of non-renewal \
+        at least sixty (60) days before the end of the then-current term.\n\
+        2. Payment. The Client shall pay each invoice within 30 days after receipt of \
+        the invoice. The Vendor must deliver a usage report monthly.\n\
+        3. Onboarding. Upon signature, the Vendor agrees to deliver the migration plan \
+        no later than 10 business days after the Effective Date.\n\
+        4. Confidentiality. Neither party shall disclose the other party's data.\n\
+        5. Termination. Either party may terminate upon 90 days written notice.\n";
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn obligations_name_the_party_action_and_timing() {
+        let effective = Some(date(2026, 1, 2));
+        let found = extract(CONTRACT, effective, renewal::expiry_date(CONTRACT));
+        let parties: Vec<Option<&str>> = found.iter().map(|o| o.party.as_deref()).collect();
+        assert_eq!(
+            parties,
+            [
+                Some("Client"),
+                Some("Vendor"),
+                Some("Vendor"),
+                Some("Neither party")
+            ]
+        );
+        assert_eq!(found[0].id, "obligation-001");
+        assert_eq!(
+            found[0].action,
+            "shall pay each invoice within 30 days after receipt of the invoice."
+        );
+        let pay = found[0].timing.as_ref().unwrap();
+        assert_eq!(pay.text, "within 30 days after receipt of the invoice");
+        assert_eq!((pay.amount, pay.unit.as_deref()), (Some(30), Some("days")));
+        assert_eq!(pay.trigger.as_deref(), Some("receipt of the invoice"));
+        assert_eq!(pay.due_date, None);
+
+        let report = found[1].timing.as_ref().unwrap();
+        assert_eq!(report.recurrence.as_deref(), Some("monthly"));
+        assert_eq!(report.amount, None);
+
+        // Friday the 2nd plus ten business days.
+        let plan = found[2].timing.as_ref().unwrap();
+        assert_eq!(plan.unit.as_deref(), Some("business_days"));
+        assert_eq!(plan.due_date, Some(date(2026, 1, 16)));
+        assert_eq!(found[2].position.section.as_deref(), Some("3. Onboarding"));
+
+        assert_eq!(found[3].kind, ObligationKind::Prohibition);
+        assert_eq!(found[0].kind, ObligationKind::Obligation);
+    }
+
+    #[test]
+    fn renewal_notice_is_dated_back_from_the_expiry() {
+        let dates = key_dates(CONTRACT, renewal::expiry_date(CONTRACT));
+        let kinds: Vec<DateKind> = dates.iter().map(|d| d.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                DateKind::Expiry,
+                DateKind::Renewal,
+                DateKind::RenewalNotice,
+                DateKind::TerminationNotice
+            ]
+        );
+        assert_eq!(dates[0].date, Some(date(2026, 12, 31)));
+        assert_eq!(dates[1].date, Some(date(2026, 12, 31)));
+        let notice = dates[2].notice.as_ref().unwrap();
+        assert_eq!(
+            notice.text,
+            "at least sixty (60) days before the end of the then-current term"
+        );
+        assert_eq!(dates[2].date, Some(date(2026, 11, 1)));
+        assert_eq!(dates[3].date, None);
+        assert_eq!(dates[3].notice.as_ref().unwrap().amount, Some(90));
+    }
+
+    #[test]
+    fn register_keeps_assignments_across_syncs_and_announces_overdue_once() {
+        let register = ObligationRegister::default();
+        let now = Utc::now();
+        let mut n = 0;
+        let mut next_id = || {
+            n += 1;
+            format!("obl-{n}")
+        };
+        let found = extract(CONTRACT, None, renewal::expiry_date(CONTRACT));
+        let synced = register.sync("acme", "a-1", found.clone(), now, &mut next_id);
+        assert!(synced.iter().all(|s| s.action == SyncAction::Created));
+        assert_eq!(synced[2].entry.due_date, None);
+
+        let update = ObligationUpdate {
+            status: Some(ObligationStatus::InProgress),
+            raci: Some(Raci {
+                responsible: Some("Jane Smith".to_string()),
+                ..Raci::default()
+            }),
+            due_date: None,
+            note: None,
+        };
+        let updated = register.update("acme", "obl-3", update, now).unwrap();
+        assert_eq!(updated.status, ObligationStatus::InProgress);
+        assert!(register
+            .update("beta", "obl-3", ObligationUpdate::default(), now)
+            .is_none());
+
+        // The effective date now dates the onboarding plan.
+        let effective = Some(date(2026, 1, 2));
+        let found = extract(CONTRACT, effective, renewal::expiry_date(CONTRACT));
+        let synced = register.sync("acme", "a-1", found, now, &mut next_id);
+        let actions: Vec<SyncAction> = synced.iter().map(|s| s.action).collect();
+        assert_eq!(
+            actions,
+            [
+                SyncAction::Unchanged,
+                SyncAction::Unchanged,
+                SyncAction::Updated,
+                SyncAction::Unchanged
+            ]
+        );
+        let plan = &synced[2].entry;
+        assert_eq!(plan.id, "obl-3");
+        assert_eq!(plan.due_date, Some(date(2026, 1, 16)));
+        assert_eq!(plan.raci.responsible.as_deref(), Some("Jane Smith"));
+
+        let listed = register.list("acme", date(2026, 1, 17));
+        assert!(listed[2].overdue);
+        assert!(!register.list("acme", date(2026, 1, 16))[2].overdue);
+        let announced = register.newly_overdue(date(2026, 1, 17));
+        assert_eq!(announced.len(), 1);
+        assert_eq!(announced[0].1[0].id, "obl-3");
+        assert!(register.newly_overdue(date(2026, 1, 18)).is_empty());
+
+        // A new due date is announced again once it passes; done ones never are.
+        let later = ObligationUpdate {
+            due_date: Some(date(2026, 2, 1)),
+            ..ObligationUpdate::default()
+        };
+        register.update("acme", "obl-3", later, now).unwrap();
+        assert_eq!(register.newly_overdue(date(2026, 2, 2)).len(), 1);
+        let done = ObligationUpdate {
+            status: Some(ObligationStatus::Done),
+            due_date: Some(date(2026, 3, 1)),
+            ..ObligationUpdate::default()
+        };
+        register.update("acme", "obl-3", done, now).unwrap();
+        assert!(register.newly_overdue(date(2026, 3, 2)).is_empty());
+        assert_eq!(register.remove_tenant("acme"), 4);
+    }
+
+    #[test]
+    fn sentences_keep_abbreviations_and_numbers_whole() {
+        let text = "Acme Corp. and Beta Inc. pay 4.5 units. The Vendor shall; report.";
+        let parts: Vec<&str> = sentences(text).into_iter().map(|(_, s)| s).collect();
+        assert_eq!(
+            parts,
+            [
+                "Acme Corp. and Beta Inc. pay 4.5 units.",
+                "The Vendor shall",
+                "report."
+            ]
+        );
+        for (start, s) in sentences(text) {
+            assert_eq!(&text[start..start + s.len()], s);
+        }
+    }
+}
@@ -0,0 +1,791 @@
+//! The OpenAPI 3 description of the engine, served at
+//! `/api/v1/openapi.json`, and a Swagger UI page over it at `/api/v1/docs`.
+//! Every route is listed in `ROUTES`; the main request and response
+//! bodies carry schemas derived from the serde types themselves, so the
+//! document changes when they do. A test keeps the list in step with the
+//! routers.
+
+use std::sync::OnceLock;
+
+use axum::response::{Html, Json};
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::Schema,
+    JsonSchema,
+};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    jobs::{JobRecord, JobRequest},
+    risk_model::RiskModel,
+    AnalyzeRequest, AnalyzeResponse, CompileRequest, CompileResponse, RiskRequest,
+    RiskScoreResponse, TemplatesResponse,
+};
+
+pub const SPEC_PATH: &str = "/api/v1/openapi.json";
+pub const DOCS_PATH: &str = "/api/v1/docs";
+
+/// Served from a CDN, so the page needs network access in the browser.
+const SWAGGER_UI_VERSION: &str = "5";
+
+type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+fn schema<T: JsonSchema>(gen: &mut SchemaGenerator) -> Schema {
+    gen.subschema_for::<T>()
+}
+
+/// Every route of the public and admin routers as `METHOD /path`, in router
+/// form with `:param` segments, with its summary, by tag.
+const ROUTES: &[(&str, &[(&str, &str)])] = &[
+    (
+        "ops",
+        &[
+            ("GET /health", "Uptime and version"),
+            ("GET /healthz", "Liveness probe"),
+            (
+                "GET /readyz",
+                "Readiness probe: dependencies and shutdown state",
+            ),
+            (
+                "GET /health/warm",
+                "Whether matchers and templates are precompiled",
+            ),
+            ("GET /metrics", "Prometheus metrics"),
+            ("GET /api/v1/openapi.json", "This document"),
+            ("GET /api/v1/docs", "Swagger UI over this document"),
+        ],
+    ),
+    (
+        "admin",
+        &[
+            (
+                "GET /api/v1/legal/admin/corpus/export",
+                "Export the corpus as training data",
+            ),
+            (
+                "GET /api/v1/legal/admin/corpus/storage",
+                "Corpus storage statistics",
+            ),
+            (
+                "POST /api/v1/legal/admin/corpus/compact",
+                "Compact the corpus",
+            ),
+            (
+                "GET /api/v1/legal/admin/corpus/:id/scores",
+                "A stored document's scores by ruleset version",
+            ),
+            (
+                "POST /api/v1/legal/admin/rescore",
+                "Start or resume re-scoring the corpus",
+            ),
+            ("GET /api/v1/legal/admin/rescore", "Re-scoring job progress"),
+            (
+                "DELETE /api/v1/legal/admin/rescore",
+                "Pause the re-scoring job",
+            ),
+            (
+                "GET /api/v1/legal/admin/content",
+                "Git-backed content status",
+            ),
+            (
+                "POST /api/v1/legal/admin/content/refresh",
+                "Pull the content repository",
+            ),
+            (
+                "GET /api/v1/legal/admin/tenants/:tenant/models",
+                "A tenant's registered model endpoints",
+            ),
+            (
+                "PUT /api/v1/legal/admin/tenants/:tenant/models/:kind",
+                "Probe and register a tenant model endpoint",
+            ),
+            (
+                "DELETE /api/v1/legal/admin/tenants/:tenant/models/:kind",
+                "Remove a tenant model endpoint",
+            ),
+            (
+                "GET /api/v1/legal/admin/tenants/:tenant/entity",
+                "A tenant's place in the organization hierarchy",
+            ),
+            (
+                "PUT /api/v1/legal/admin/tenants/:tenant/entity",
+                "Register a tenant as an entity under a parent",
+            ),
+            (
+                "DELETE /api/v1/legal/admin/tenants/:tenant/entity",
+                "Remove a tenant from the organization hierarchy",
+            ),
+            (
+                "GET /api/v1/legal/admin/variable-profiles",
+                "Variable inference profiles and their accuracy",
+            ),
+            (
+                "GET /admin/inflight",
+                "Running analyses, queued jobs and worker pool usage",
+            ),
+            (
+                "GET /api/v1/legal/admin/inflight",
+                "Alias of GET /admin/inflight",
+            ),
+            (
+                "GET /api/v1/legal/admin/tenants/:tenant/archive",
+                "Export a tenant's data as a migration archive",
+            ),
+            (
+                "POST /api/v1/legal/admin/tenants/:tenant/archive",
+                "Import a migration archive into a tenant",
+            ),
+        ],
+    ),
+    (
+        "analysis",
+        &[
+            ("POST /api/v1/legal/analyze", "Analyze a contract"),
+            (
+                "POST /api/v1/legal/analyze/batch",
+                "Analyze a portfolio of contracts",
+            ),
+            (
+                "POST /api/v1/legal/analyze/bundle",
+                "Split a bundle and analyze each document",
+            ),
+            (
+                "POST /api/v1/legal/analyze/file",
+                "Analyze an uploaded file",
+            ),
+            (
+                "POST /api/v1/legal/analyze/stream",
+                "Analyze a contract, streaming findings as Server-Sent Events",
+            ),
+            (
+                "POST /api/v1/legal/compare",
+                "Compare two documents clause by clause",
+            ),
+            (
+                "POST /api/v1/legal/entities/validate",
+                "Validate party entity names",
+            ),
+            (
+                "POST /api/v1/legal/conflicts/check",
+                "Check for internal conflicts",
+            ),
+        ],
+    ),
+    (
+        "jobs",
+        &[
+            ("POST /api/v1/legal/analyze/async", "Queue an analysis job"),
+            (
+                "POST /api/v1/legal/jobs",
+                "Queue an analysis job (older name)",
+            ),
+            ("GET /api/v1/legal/jobs/:id", "Job status and result"),
+            ("DELETE /api/v1/legal/jobs/:id", "Cancel a job"),
+        ],
+    ),
+    (
+        "templates",
+        &[
+            ("POST /api/v1/legal/compile", "Compile a template"),
+            (
+                "POST /api/v1/legal/compile/html",
+                "Compile a template to HTML",
+            ),
+            (
+                "GET /api/v1/legal/renderings/:id",
+                "Download a rendered document",
+            ),
+            ("GET /api/v1/legal/templates", "List templates"),
+            ("POST /api/v1/legal/templates", "Create a custom template"),
+            (
+                "GET /api/v1/legal/templates/analytics",
+                "Template, variable and optional section usage",
+            ),
+            (
+                "PUT /api/v1/legal/templates/:id",
+                "Replace a custom template",
+            ),
+            (
+                "DELETE /api/v1/legal/templates/:id",
+                "Delete a custom template",
+            ),
+            (
+                "GET /api/v1/legal/templates/:id/versions",
+                "List a custom template's versions",
+            ),
+            (
+                "POST /api/v1/legal/templates/:id/versions/:n/restore",
+                "Save an earlier version as the next one",
+            ),
+            ("GET /api/v1/legal/macros", "List macros"),
+            ("POST /api/v1/legal/macros", "Create a macro"),
+            ("GET /api/v1/legal/macros/:name", "Get a macro"),
+            ("PUT /api/v1/legal/macros/:name", "Update a macro"),
+            ("DELETE /api/v1/legal/macros/:name", "Delete a macro"),
+            ("GET /api/v1/legal/clause-library", "List library clauses"),
+            ("POST /api/v1/legal/clause-library", "Add a library clause"),
+            (
+                "GET /api/v1/legal/clause-library/:id",
+                "Get a library clause",
+            ),
+            (
+                "PUT /api/v1/legal/clause-library/:id",
+                "Draft a new clause version",
+            ),
+            (
+                "POST /api/v1/legal/clause-library/:id/approve",
+                "Approve a clause version",
+            ),
+            (
+                "GET /api/v1/legal/templates/:id/wizard",
+                "A template's question flow",
+            ),
+            (
+                "GET /api/v1/legal/templates/:id/preview",
+                "Preview a template with sample values",
+            ),
+            (
+                "GET /api/v1/legal/templates/:id/revisions",
+                "List template revisions",
+            ),
+            (
+                "POST /api/v1/legal/templates/:id/revisions",
+                "Add a template revision",
+            ),
+            (
+                "GET /api/v1/legal/templates/:id/revisions/:a/diff/:b",
+                "Diff two revisions",
+            ),
+            (
+                "GET /api/v1/legal/templates/:id/expanded",
+                "A template with includes expanded",
+            ),
+            (
+                "POST /api/v1/legal/extract-variables",
+                "Infer template variables from a contract",
+            ),
+            (
+                "POST /api/v1/legal/extract-variables/:id/feedback",
+                "Report the variable values kept",
+            ),
+            (
+                "POST /api/v1/legal/wizard-sessions",
+                "Start a wizard session",
+            ),
+            (
+                "GET /api/v1/legal/wizard-sessions/:id",
+                "Get a wizard session",
+            ),
+            (
+                "POST /api/v1/legal/wizard-sessions/:id/answers",
+                "Answer wizard questions",
+            ),
+            (
+                "POST /api/v1/legal/wizard-sessions/:id/compile",
+                "Compile a finished wizard session",
+            ),
+            (
+                "POST /api/v1/legal/renewals/draft",
+                "Draft a renewal of a stored agreement",
+            ),
+        ],
+    ),
+    (
+        "risk",
+        &[
+            ("POST /api/v1/legal/risk-score", "Score a contract's risk"),
+            ("GET /api/v1/legal/risk-model", "The active risk model"),
+            (
+                "POST /api/v1/legal/calibration/evaluate",
+                "Evaluate confidence calibration",
+            ),
+            ("GET /api/v1/legal/outcomes", "List outcome labels"),
+            ("POST /api/v1/legal/outcomes", "Upload outcome labels"),
+            ("DELETE /api/v1/legal/outcomes", "Delete outcome labels"),
+            (
+                "POST /api/v1/legal/backtest",
+                "Backtest risk factors against outcomes",
+            ),
+        ],
+    ),
+    (
+        "compliance",
+        &[
+            (
+                "POST /api/v1/legal/compliance",
+                "Check a document against a GDPR, CCPA or HIPAA checklist",
+            ),
+            (
+                "POST /api/v1/legal/export-control/check",
+                "Check a document for export-control terms",
+            ),
+            (
+                "POST /api/v1/legal/subcontracting/check",
+                "Check subcontracting flow-down",
+            ),
+            (
+                "GET /api/v1/legal/export-control/policy",
+                "The export-control policy table",
+            ),
+            (
+                "PUT /api/v1/legal/export-control/policy",
+                "Replace the export-control policy table",
+            ),
+            (
+                "GET /api/v1/legal/compile/policy",
+                "The jurisdiction compile policy",
+            ),
+            (
+                "PUT /api/v1/legal/compile/policy",
+                "Replace the jurisdiction compile policy",
+            ),
+            (
+                "GET /api/v1/legal/regulatory/rules",
+                "List deprecation rules",
+            ),
+            (
+                "POST /api/v1/legal/regulatory/rules",
+                "Add or replace a deprecation rule",
+            ),
+            (
+                "DELETE /api/v1/legal/regulatory/rules/:id",
+                "Delete a deprecation rule",
+            ),
+            (
+                "POST /api/v1/legal/regulatory/scan",
+                "Scan stored documents for deprecated references",
+            ),
+        ],
+    ),
+    (
+        "families",
+        &[
+            (
+                "GET /api/v1/legal/families/:id/flow-down",
+                "Flow-down gaps across a family",
+            ),
+            (
+                "GET /api/v1/legal/families/:id/risk",
+                "Rolled-up risk of a family",
+            ),
+        ],
+    ),
+    (
+        "signature",
+        &[
+            (
+                "POST /api/v1/legal/documents/:id/approvals",
+                "Record an approval",
+            ),
+            (
+                "POST /api/v1/legal/documents/:id/signature-gate",
+                "Dry-run the pre-signature gate",
+            ),
+            (
+                "POST /api/v1/legal/documents/:id/send-for-signature",
+                "Send a document for signature",
+            ),
+            (
+                "POST /api/v1/legal/documents/:id/execution",
+                "Record that a document was signed",
+            ),
+            (
+                "GET /api/v1/legal/signing-authority",
+                "The tenant's signing authority matrix",
+            ),
+            (
+                "PUT /api/v1/legal/signing-authority",
+                "Replace the signing authority matrix",
+            ),
+            (
+                "DELETE /api/v1/legal/signing-authority",
+                "Remove the signing authority matrix",
+            ),
+        ],
+    ),
+    (
+        "analyses",
+        &[
+            (
+                "POST /api/v1/legal/diligence/export",
+                "Export a due diligence workbook",
+            ),
+            ("GET /api/v1/legal/analyses", "List past analyses"),
+            (
+                "GET /api/v1/legal/analyses/:id",
+                "An analysis, filtered by access role",
+            ),
+            (
+                "GET /api/v1/legal/analyses/:id/summary",
+                "An analysis summary rendered as email",
+            ),
+            (
+                "POST /api/v1/legal/analyses/:id/clm-push",
+                "Push an analysis to CLM connectors",
+            ),
+            ("GET /api/v1/legal/clm/connectors", "List CLM connectors"),
+        ],
+    ),
+    (
+        "webhooks",
+        &[
+            ("GET /api/v1/webhooks", "The tenant's webhooks"),
+            ("POST /api/v1/webhooks", "Register a webhook"),
+            ("DELETE /api/v1/webhooks/:id", "Delete a webhook"),
+        ],
+    ),
+    (
+        "sharing",
+        &[
+            (
+                "POST /api/v1/legal/analyses/:id/share",
+                "Create a share link",
+            ),
+            (
+                "GET /api/v1/legal/analyses/:id/shares",
+                "List an analysis's share links",
+            ),
+            ("DELETE /api/v1/legal/shares/:id", "Revoke a share link"),
+            ("GET /api/v1/legal/shared/:token", "Open a share link"),
+        ],
+    ),
+    (
+        "privacy",
+        &[
+            (
+                "DELETE /api/v1/legal/tenants/:id/data",
+                "Erase a tenant's data",
+            ),
+            ("POST /api/v1/legal/erasure", "Erase a data subject"),
+            ("GET /api/v1/legal/audit", "Query the audit log"),
+        ],
+    ),
+    (
+        "reviews",
+        &[
+            (
+                "GET /api/v1/legal/analyses/:id/annotations",
+                "Findings and reviewer comments",
+            ),
+            (
+                "POST /api/v1/legal/analyses/:id/annotations/import",
+                "Import reviewer comments",
+            ),
+            ("GET /api/v1/legal/escalations", "Pending escalations"),
+            (
+                "POST /api/v1/legal/escalations/:id/resolve",
+                "Resolve an escalation",
+            ),
+        ],
+    ),
+    (
+        "abstracts",
+        &[
+            (
+                "POST /api/v1/legal/abstract",
+                "Abstract and key terms of a contract",
+            ),
+            (
+                "POST /api/v1/legal/obligations",
+                "Obligations register with deadlines and notice dates",
+            ),
+            (
+                "POST /api/v1/legal/analyses/:id/obligations/sync",
+                "Add a stored document's obligations to the tracked register",
+            ),
+            (
+                "GET /api/v1/legal/obligations/register",
+                "Tracked obligations with RACI owners, statuses and overdue flags",
+            ),
+            (
+                "PATCH /api/v1/legal/obligations/register/:id",
+                "Assign, reschedule or complete a tracked obligation",
+            ),
+        ],
+    ),
+    (
+        "notices",
+        &[
+            (
+                "POST /api/v1/legal/notices/extract",
+                "Extract notice contacts",
+            ),
+            (
+                "POST /api/v1/legal/analyses/:id/notice-contacts/sync",
+                "Sync notice contacts to the address book",
+            ),
+            (
+                "GET /api/v1/legal/address-book",
+                "The tenant's address book",
+            ),
+        ],
+    ),
+    (
+        "tenant",
+        &[
+            ("GET /api/v1/legal/lexicon", "The tenant lexicon"),
+            ("PUT /api/v1/legal/lexicon", "Replace the tenant lexicon"),
+            ("DELETE /api/v1/legal/lexicon", "Remove the tenant lexicon"),
+            (
+                "POST /api/v1/legal/embeddings",
+                "Embed texts with the tenant's model",
+            ),
+            (
+                "GET /api/v1/legal/model-calls",
+                "Calls made to external model backends",
+            ),
+            (
+                "GET /api/v1/legal/model-calls/settings",
+                "The tenant's prompt logging setting",
+            ),
+            (
+                "PUT /api/v1/legal/model-calls/settings",
+                "Change the tenant's prompt logging setting",
+            ),
+            (
+                "GET /api/v1/legal/style-profile",
+                "The drafting style profile",
+            ),
+            (
+                "PUT /api/v1/legal/style-profile",
+                "Replace the drafting style profile",
+            ),
+            (
+                "DELETE /api/v1/legal/style-profile",
+                "Remove the drafting style profile",
+            ),
+            (
+                "POST /api/v1/legal/style/check",
+                "Check a document against the style profile",
+            ),
+            ("GET /api/v1/legal/playbook", "The clause playbook"),
+            ("PUT /api/v1/legal/playbook", "Replace the clause playbook"),
+            ("DELETE /api/v1/legal/playbook", "Remove the clause playbook"),
+            (
+                "GET /api/v1/legal/org",
+                "The tenant's entity, ancestors and inherited settings",
+            ),
+            (
+                "GET /api/v1/legal/org/rollup",
+                "Analyses rolled up over the tenant's entities",
+            ),
+            (
+                "GET /api/v1/usage",
+                "The caller's rate limit and consumption",
+            ),
+            (
+                "GET /api/v1/legal/usage",
+                "Alias of GET /api/v1/usage",
+            ),
+        ],
+    ),
+];
+
+/// Routes whose bodies carry schemas: request, if any, and response.
+fn typed_routes() -> [(&'static str, Option<SchemaFn>, SchemaFn); 8] {
+    [
+        (
+            "POST /api/v1/legal/analyze",
+            Some(schema::<AnalyzeRequest>),
+            schema::<AnalyzeResponse>,
+        ),
+        (
+            "POST /api/v1/legal/analyze/async",
+            Some(schema::<JobRequest>),
+            schema::<JobRecord>,
+        ),
+        (
+            "POST /api/v1/legal/jobs",
+            Some(schema::<JobRequest>),
+            schema::<JobRecord>,
+        ),
+        ("GET /api/v1/legal/jobs/:id", None, schema::<JobRecord>),
+        (
+            "POST /api/v1/legal/compile",
+            Some(schema::<CompileRequest>),
+            schema::<CompileResponse>,
+        ),
+        (
+            "GET /api/v1/legal/templates",
+            None,
+            schema::<TemplatesResponse>,
+        ),
+        (
+            "POST /api/v1/legal/risk-score",
+            Some(schema::<RiskRequest>),
+            schema::<RiskScoreResponse>,
+        ),
+        ("GET /api/v1/legal/risk-model", None, schema::<RiskModel>),
+    ]
+}
+
+/// `/jobs/:id` as OpenAPI writes it, `/jobs/{id}`, with its parameter names.
+fn openapi_path(path: &str) -> (String, Vec<&str>) {
+    let mut params = Vec::new();
+    let segments: Vec<String> = path
+        .split('/')
+        .map(|s| match s.strip_prefix(':') {
+            Some(name) => {
+                params.push(name);
+                format!("{{{name}}}")
+            }
+            None => s.to_string(),
+        })
+        .collect();
+    (segments.join("/"), params)
+}
+
+fn json_content(schema: &Schema) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+pub fn document() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+    let typed = typed_routes();
+    let mut paths = Map::new();
+    for (tag, routes) in ROUTES {
+        for (route, summary) in routes.iter() {
+            let (method, path) = route.split_once(' ').expect("routes are `METHOD /path`");
+            let (path, params) = openapi_path(path);
+            let mut operation = json!({
+                "tags": [tag],
+                "summary": summary,
+                "responses": { "200": { "description": "OK" } },
+            });
+            if !params.is_empty() {
+                operation["parameters"] = params
+                    .iter()
+                    .map(|p| json!({ "name": p, "in": "path", "required": true, "schema": { "type": "string" } }))
+                    .collect();
+            }
+            if let Some((_, request, response)) = typed.iter().find(|(r, ..)| r == route) {
+                if let Some(request) = request {
+                    operation["requestBody"] =
+                        json!({ "required": true, "content": json_content(&request(&mut gen)) });
+                }
+                operation["responses"]["200"]["content"] = json_content(&response(&mut gen));
+            }
+            let item = paths.entry(path).or_insert_with(|| json!({}));
+            item[method.to_ascii_lowercase()] = operation;
+        }
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "ALICE Legal Engine",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "components": {
+            "schemas": gen.definitions(),
+            "securitySchemes": {
+                "bearer": { "type": "http", "scheme": "bearer" },
+                "apiKey": { "type": "apiKey", "in": "header", "name": crate::auth::API_KEY_HEADER },
+            },
+        },
+        "security": [{ "bearer": [] }, { "apiKey": [] }],
+        "paths": paths,
+    })
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn spec() -> Json<Value> {
+    static SPEC: OnceLock<Value> = OnceLock::new();
+    Json(SPEC.get_or_init(document).clone())
+}
+
+pub async fn docs() -> Html<String> {
+    Html(format!(
+        r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>ALICE Legal Engine API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@{v}/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@{v}/swagger-ui-bundle.js"></script>
+  <script>window.ui = SwaggerUIBundle({{ url: "{SPEC_PATH}", dom_id: "#swagger-ui" }});</script>
+</body>
+</html>
+"##,
+        v = SWAGGER_UI_VERSION,
+    ))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Route paths registered in `lib.rs`, read from its source.
+    fn routed_paths() -> Vec<String> {
+        let source = include_str!("lib.rs");
+        let mut paths: Vec<String> = source
+            .split(".route(")
+            .skip(1)
+            .filter_map(|rest| {
+                let rest = rest.trim_start();
+                let path = rest.strip_prefix('"')?.split('"').next()?;
+                Some(path.to_string())
+            })
+            .chain([SPEC_PATH.to_string(), DOCS_PATH.to_string()])
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    #[test]
+    fn every_route_is_documented() {
+        let mut documented: Vec<String> = ROUTES
+            .iter()
+            .flat_map(|(_, routes)| routes.iter())
+            .map(|(route, _)| route.split_once(' ').unwrap().1.to_string())
+            .collect();
+        documented.sort();
+        documented.dedup();
+        assert_eq!(documented, routed_paths());
+    }
+
+    #[test]
+    fn typed_routes_are_listed_routes() {
+        for (route, ..) in typed_routes() {
+            assert!(
+                ROUTES
+                    .iter()
+                    .any(|(_, routes)| routes.iter().any(|(r, _)| *r == route)),
+                "{route}"
+            );
+        }
+    }
+
+    #[test]
+    fn paths_use_braces_and_declare_their_parameters() {
+        let (path, params) = openapi_path("/api/v1/legal/templates/:id/revisions/:a/diff/:b");
+        assert_eq!(path, "/api/v1/legal/templates/{id}/revisions/{a}/diff/{b}");
+        assert_eq!(params, ["id", "a", "b"]);
+
+        let doc = document();
+        let analyze = &doc["paths"]["/api/v1/legal/analyze"]["post"];
+        assert_eq!(
+            analyze["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/AnalyzeRequest"
+        );
+        let request = &doc["components"]["schemas"]["AnalyzeRequest"];
+        let required: Vec<&str> = request["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        assert_eq!(required, ["document"]);
+        assert!(doc["components"]["schemas"]["Clause"]["properties"]["clause_type"].is_object());
+        assert_eq!(
+            doc["paths"]["/api/v1/legal/jobs/{id}"]["delete"]["parameters"][0]["name"],
+            "id"
+        );
+    }
+}
@@ -0,0 +1,517 @@
+//! Organization hierarchy for tenants with subsidiaries and business units.
+//! An admin registers a tenant as an entity under a parent tenant; the
+//! entity then inherits what its ancestors set: their custom templates
+//! (one of its own replaces an ancestor's when it names it in `overrides`),
+//! their playbooks and their analysis quotas, the nearest entity's setting
+//! winning. Analyses stay attributed to the tenant that ran them,
+//! and the roll-up adds them up over an entity and everything below it.
+//! The hierarchy is kept in memory only.
+
+use std::{collections::BTreeMap, sync::RwLock, time::Duration};
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::{
+    history::{HistoryKind, HistoryQuery},
+    notifier,
+    playbook::Playbook,
+    quota::QuotaConfig,
+    AppState,
+};
+
+/// Most levels a hierarchy may have, its root included.
+const MAX_DEPTH: usize = 8;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+/// Analysis quotas an entity sets for itself and the entities below it;
+/// unset fields are inherited, and the server's limits apply at the root.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_wall_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_budget: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stage_memory_mb: Option<u64>,
+}
+
+impl QuotaOverrides {
+    fn validate(&self) -> Result<(), String> {
+        let fields = [
+            ("max_wall_ms", self.max_wall_ms),
+            ("max_tokens", self.max_tokens.map(|n| n as u64)),
+            ("match_budget", self.match_budget),
+            ("stage_memory_mb", self.stage_memory_mb),
+        ];
+        match fields.iter().find(|(_, value)| *value == Some(0)) {
+            Some((name, _)) => Err(format!("quotas.{name} must be positive")),
+            None => Ok(()),
+        }
+    }
+
+    /// `self`'s fields where set, `inherited`'s elsewhere.
+    fn or(&self, inherited: &Self) -> Self {
+        Self {
+            max_wall_ms: self.max_wall_ms.or(inherited.max_wall_ms),
+            max_tokens: self.max_tokens.or(inherited.max_tokens),
+            match_budget: self.match_budget.or(inherited.match_budget),
+            stage_memory_mb: self.stage_memory_mb.or(inherited.stage_memory_mb),
+        }
+    }
+
+    /// `base` with the overridden limits replaced.
+    pub fn apply(&self, base: &QuotaConfig) -> QuotaConfig {
+        QuotaConfig {
+            max_wall: self
+                .max_wall_ms
+                .map_or(base.max_wall, Duration::from_millis),
+            max_tokens: self.max_tokens.unwrap_or(base.max_tokens),
+            match_budget: self.match_budget.unwrap_or(base.match_budget),
+            stage_memory_bytes: self
+                .stage_memory_mb
+                .map_or(base.stage_memory_bytes, |mb| mb << 20),
+        }
+    }
+}
+
+/// Body of `PUT /api/v1/legal/admin/tenants/:tenant/entity`.
+#[derive(Debug, Deserialize)]
+pub struct EntityRequest {
+    pub name: String,
+    /// The tenant this one belongs to; `None` makes it a root.
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub quotas: QuotaOverrides,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Entity {
+    pub tenant: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    pub quotas: QuotaOverrides,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A tenant's place in the hierarchy and the settings it ends up with.
+#[derive(Debug, Serialize)]
+pub struct OrgView {
+    pub tenant: String,
+    /// `None` for a tenant that was never registered as an entity.
+    pub entity: Option<Entity>,
+    /// Parent first, root last.
+    pub ancestors: Vec<String>,
+    pub children: Vec<String>,
+    /// Settings after inheritance.
+    pub playbook: Playbook,
+    pub quotas: QuotaOverrides,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EntityRollup {
+    pub tenant: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Levels below the tenant the roll-up is for.
+    pub depth: usize,
+    pub analyses: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_risk_score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_analyzed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Rollup {
+    pub tenant: String,
+    /// The tenant first, then every entity below it, parents before
+    /// their children.
+    pub entities: Vec<EntityRollup>,
+    /// Over all of `entities`.
+    pub total: EntityRollup,
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct OrgStore {
+    entities: RwLock<BTreeMap<String, Entity>>,
+}
+
+/// `tenant` and its ancestors, nearest first. Stops at a repeated tenant,
+/// which `put` never lets happen.
+fn lineage_in(entities: &BTreeMap<String, Entity>, tenant: &str) -> Vec<String> {
+    let mut lineage = vec![tenant.to_string()];
+    while let Some(parent) = entities
+        .get(lineage.last().unwrap())
+        .and_then(|e| e.parent.clone())
+    {
+        if lineage.contains(&parent) {
+            break;
+        }
+        lineage.push(parent);
+    }
+    lineage
+}
+
+fn subtree_in(entities: &BTreeMap<String, Entity>, tenant: &str) -> Vec<(String, usize)> {
+    let mut subtree = vec![(tenant.to_string(), 0)];
+    let mut i = 0;
+    while let Some((parent, depth)) = subtree.get(i).cloned() {
+        for child in entities
+            .values()
+            .filter(|e| e.parent.as_deref() == Some(parent.as_str()))
+        {
+            if !subtree.iter().any(|(t, _)| *t == child.tenant) {
+                subtree.push((child.tenant.clone(), depth + 1));
+            }
+        }
+        i += 1;
+    }
+    subtree
+}
+
+impl OrgStore {
+    pub fn get(&self, tenant: &str) -> Option<Entity> {
+        self.entities.read().unwrap().get(tenant).cloned()
+    }
+
+    /// `tenant`, then its parent, up to the root.
+    pub fn lineage(&self, tenant: &str) -> Vec<String> {
+        lineage_in(&self.entities.read().unwrap(), tenant)
+    }
+
+    pub fn children(&self, tenant: &str) -> Vec<String> {
+        self.entities
+            .read()
+            .unwrap()
+            .values()
+            .filter(|e| e.parent.as_deref() == Some(tenant))
+            .map(|e| e.tenant.clone())
+            .collect()
+    }
+
+    /// `tenant` and every entity below it with its depth under `tenant`,
+    /// parents before their children.
+    pub fn subtree(&self, tenant: &str) -> Vec<(String, usize)> {
+        subtree_in(&self.entities.read().unwrap(), tenant)
+    }
+
+    /// Adds or replaces `entity`; `true` when it replaced one. Refuses a
+    /// parent that would make a cycle or a hierarchy deeper than
+    /// [`MAX_DEPTH`].
+    pub fn put(&self, entity: Entity) -> Result<bool, String> {
+        let mut entities = self.entities.write().unwrap();
+        if let Some(parent) = &entity.parent {
+            let above = lineage_in(&entities, parent);
+            if above.contains(&entity.tenant) {
+                return Err(format!(
+                    "parent {parent:?} is {} itself or below it",
+                    entity.tenant
+                ));
+            }
+            let below = subtree_in(&entities, &entity.tenant)
+                .iter()
+                .map(|(_, depth)| *depth)
+                .max()
+                .unwrap_or_default();
+            if above.len() + 1 + below > MAX_DEPTH {
+                return Err(format!(
+                    "the hierarchy would be more than {MAX_DEPTH} levels deep"
+                ));
+            }
+        }
+        Ok(entities.insert(entity.tenant.clone(), entity).is_some())
+    }
+
+    /// Removes `tenant`'s entity; fails with the children it still has.
+    pub fn remove(&self, tenant: &str) -> Result<Option<Entity>, Vec<String>> {
+        let mut entities = self.entities.write().unwrap();
+        let children: Vec<String> = entities
+            .values()
+            .filter(|e| e.parent.as_deref() == Some(tenant))
+            .map(|e| e.tenant.clone())
+            .collect();
+        if !children.is_empty() {
+            return Err(children);
+        }
+        Ok(entities.remove(tenant))
+    }
+
+    /// Quota overrides in force for `tenant`, nearest entity first.
+    pub fn quotas(&self, tenant: &str) -> QuotaOverrides {
+        let entities = self.entities.read().unwrap();
+        lineage_in(&entities, tenant)
+            .iter()
+            .filter_map(|t| entities.get(t))
+            .fold(QuotaOverrides::default(), |q, e| q.or(&e.quotas))
+    }
+}
+
+// ── Validation ────────────────────────────────────────────────────────────────
+
+fn validate(tenant: &str, req: &EntityRequest) -> Result<(), String> {
+    if req.name.trim().is_empty() {
+        return Err("name is required".to_string());
+    }
+    match req.parent.as_deref() {
+        Some(parent) if parent.trim().is_empty() => {
+            return Err("parent must name a tenant".to_string())
+        }
+        Some(parent) if parent == tenant => {
+            return Err("an entity cannot be its own parent".to_string())
+        }
+        _ => {}
+    }
+    req.quotas.validate()
+}
+
+// ── Roll-up ───────────────────────────────────────────────────────────────────
+
+fn summarize(tenant: String, depth: usize, scores: &[(f64, DateTime<Utc>)]) -> EntityRollup {
+    EntityRollup {
+        tenant,
+        name: None,
+        depth,
+        analyses: scores.len(),
+        average_risk_score: (!scores.is_empty())
+            .then(|| scores.iter().map(|(s, _)| s).sum::<f64>() / scores.len() as f64),
+        last_analyzed_at: scores.iter().map(|(_, at)| *at).max(),
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+fn rejected(status: StatusCode, tenant: &str, error: String) -> Response {
+    info!(tenant = %tenant, error = %error, "entity rejected");
+    (status, Json(json!({ "error": error }))).into_response()
+}
+
+pub async fn get_entity(
+    State(state): State<AppState>,
+    Path(tenant): Path<String>,
+) -> Result<Json<Entity>, StatusCode> {
+    state
+        .orgs
+        .get(&tenant)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+pub async fn put_entity(
+    State(state): State<AppState>,
+    Path(tenant): Path<String>,
+    Json(req): Json<EntityRequest>,
+) -> Result<(StatusCode, Json<Entity>), Response> {
+    validate(&tenant, &req).map_err(|e| rejected(StatusCode::UNPROCESSABLE_ENTITY, &tenant, e))?;
+    let entity = Entity {
+        tenant: tenant.clone(),
+        name: req.name,
+        parent: req.parent,
+        quotas: req.quotas,
+        updated_at: state.clock.now(),
+    };
+    let replaced = state
+        .orgs
+        .put(entity.clone())
+        .map_err(|e| rejected(StatusCode::UNPROCESSABLE_ENTITY, &tenant, e))?;
+    info!(tenant = %tenant, parent = ?entity.parent, "entity saved");
+    state.record_audit(
+        "entity.saved",
+        &tenant,
+        None,
+        json!({ "parent": entity.parent, "replaced": replaced }),
+    );
+    let status = if replaced {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    Ok((status, Json(entity)))
+}
+
+pub async fn delete_entity(
+    State(state): State<AppState>,
+    Path(tenant): Path<String>,
+) -> Result<StatusCode, Response> {
+    match state.orgs.remove(&tenant) {
+        Ok(Some(_)) => {
+            state.record_audit("entity.removed", &tenant, None, json!({}));
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND.into_response()),
+        Err(children) => Err(rejected(
+            StatusCode::CONFLICT,
+            &tenant,
+            format!("entity still has children {children:?}"),
+        )),
+    }
+}
+
+pub async fn get_org(State(state): State<AppState>, headers: HeaderMap) -> Json<OrgView> {
+    let tenant = notifier::tenant(&headers);
+    let mut ancestors = state.orgs.lineage(&tenant);
+    let playbook = state.playbooks.resolve(&ancestors);
+    ancestors.remove(0);
+    Json(OrgView {
+        entity: state.orgs.get(&tenant),
+        ancestors,
+        children: state.orgs.children(&tenant),
+        playbook,
+        quotas: state.orgs.quotas(&tenant),
+        tenant,
+    })
+}
+
+pub async fn rollup(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Rollup>, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    let mut entities = Vec::new();
+    let mut all = Vec::new();
+    for (member, depth) in state.orgs.subtree(&tenant) {
+        let query = HistoryQuery {
+            tenant: member.clone(),
+            document_sha256: None,
+            kind: Some(HistoryKind::Analysis),
+            // SQL backends bind the limit as a signed integer.
+            limit: i64::MAX as usize,
+        };
+        let analyses = state.history.list(&query).map_err(|e| {
+            warn!(backend = state.history.backend(), error = %e, "roll-up history list failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        let scores: Vec<(f64, DateTime<Utc>)> = analyses
+            .iter()
+            .map(|a| (a.risk_score, a.created_at))
+            .collect();
+        all.extend_from_slice(&scores);
+        entities.push(EntityRollup {
+            name: state.orgs.get(&member).map(|e| e.name),
+            ..summarize(member, depth, &scores)
+        });
+    }
+    info!(tenant = %tenant, entities = entities.len(), "organization rolled up");
+    Ok(Json(Rollup {
+        total: EntityRollup {
+            name: state.orgs.get(&tenant).map(|e| e.name),
+            ..summarize(tenant.clone(), 0, &all)
+        },
+        tenant,
+        entities,
+    }))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(tenant: &str, parent: Option<&str>) -> Entity {
+        Entity {
+            tenant: tenant.to_string(),
+            name: tenant.to_uppercase(),
+            parent: parent.map(str::to_string),
+            quotas: QuotaOverrides::default(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn store() -> OrgStore {
+        let store = OrgStore::default();
+        store.put(entity("group", None)).unwrap();
+        store.put(entity("emea", Some("group"))).unwrap();
+        store.put(entity("de", Some("emea"))).unwrap();
+        store.put(entity("us", Some("group"))).unwrap();
+        store
+    }
+
+    #[test]
+    fn lineage_and_subtree_follow_parents() {
+        let store = store();
+        assert_eq!(store.lineage("de"), ["de", "emea", "group"]);
+        assert_eq!(store.lineage("unregistered"), ["unregistered"]);
+        assert_eq!(store.children("group"), ["emea", "us"]);
+        assert_eq!(
+            store.subtree("group"),
+            [
+                ("group".to_string(), 0),
+                ("emea".to_string(), 1),
+                ("us".to_string(), 1),
+                ("de".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn cycles_and_deep_hierarchies_are_refused() {
+        let store = store();
+        assert!(store.put(entity("group", Some("de"))).is_err());
+        assert!(store.put(entity("emea", Some("emea"))).is_err());
+        assert_eq!(store.lineage("group"), ["group"]);
+
+        let deep = OrgStore::default();
+        for level in 1..MAX_DEPTH {
+            deep.put(entity(
+                &format!("l{level}"),
+                Some(&format!("l{}", level - 1)),
+            ))
+            .unwrap();
+        }
+        let err = deep.put(entity("too-deep", Some("l7"))).unwrap_err();
+        assert!(err.contains("levels deep"), "{err}");
+        assert_eq!(store.remove("emea"), Err(vec!["de".to_string()]));
+        assert!(store.remove("de").unwrap().is_some());
+    }
+
+    #[test]
+    fn nearest_setting_wins() {
+        let store = store();
+        let mut group = entity("group", None);
+        group.quotas.max_tokens = Some(1_000);
+        group.quotas.max_wall_ms = Some(5_000);
+        store.put(group).unwrap();
+        let mut emea = entity("emea", Some("group"));
+        emea.quotas.max_tokens = Some(500);
+        store.put(emea).unwrap();
+
+        let quotas = store.quotas("de").apply(&QuotaConfig::default());
+        assert_eq!(quotas.max_tokens, 500);
+        assert_eq!(quotas.max_wall, Duration::from_secs(5));
+        assert_eq!(quotas.match_budget, QuotaConfig::default().match_budget);
+    }
+
+    #[test]
+    fn requests_are_validated() {
+        let req = |quotas: QuotaOverrides| EntityRequest {
+            name: "EMEA".to_string(),
+            parent: Some("group".to_string()),
+            quotas,
+        };
+        assert!(validate("emea", &req(QuotaOverrides::default())).is_ok());
+        assert!(validate("group", &req(QuotaOverrides::default())).is_err());
+        let zero = QuotaOverrides {
+            match_budget: Some(0),
+            ..QuotaOverrides::default()
+        };
+        assert_eq!(
+            validate("emea", &req(zero)),
+            Err("quotas.match_budget must be positive".to_string())
+        );
+    }
+}
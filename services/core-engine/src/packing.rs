@@ -0,0 +1,560 @@
+//! Context packing for the ML backend. A contract longer than the model's
+//! token budget is cut into its numbered sections, each scored by the
+//! clause types it mentions (weighted by their risk, and more for the types
+//! the request focuses on) and by how much of the request's query it
+//! covers. The `pack` strategy sends the best sections that fit, in
+//! document order; `map_reduce` sends the whole document in budget-sized
+//! chunks, one call each, and reduces their findings. Every decision is
+//! logged and returned in `metadata.ml_context`.
+
+use std::{collections::BTreeSet, ops::Range};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::{
+    chunking,
+    ensemble::{self, MlClause, MlFindings, MlIssue},
+    extraction::sections,
+    taxonomy::{self, ClauseKind},
+    warmup::KeywordMatcher,
+};
+
+/// Joins packed sections that were not adjacent in the document.
+const GAP_MARKER: &str = "\n[…]\n";
+/// Score multiplier for a clause type the request focuses on.
+const FOCUS_WEIGHT: f64 = 4.0;
+/// Score of a section covering every word of the query.
+const QUERY_WEIGHT: f64 = 10.0;
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PackingStrategy {
+    /// One call with the most relevant sections that fit the budget.
+    Pack,
+    /// One call per budget-sized chunk, findings reduced afterwards.
+    MapReduce,
+}
+
+impl PackingStrategy {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "pack" => Some(Self::Pack),
+            "map_reduce" => Some(Self::MapReduce),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PackingConfig {
+    /// Whitespace tokens the backend is sent per call.
+    pub max_tokens: usize,
+    pub strategy: PackingStrategy,
+    /// Most calls one map-reduce run makes; the least relevant chunks go.
+    pub max_calls: usize,
+}
+
+impl Default for PackingConfig {
+    fn default() -> Self {
+        Self {
+            max_tokens: 6_000,
+            strategy: PackingStrategy::Pack,
+            max_calls: 8,
+        }
+    }
+}
+
+impl PackingConfig {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        let count = |name: &str, default: usize| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(default)
+        };
+        Self {
+            max_tokens: count("LEGAL_ML_CONTEXT_TOKENS", d.max_tokens),
+            strategy: std::env::var("LEGAL_ML_CONTEXT_STRATEGY")
+                .ok()
+                .and_then(|v| PackingStrategy::parse(&v))
+                .unwrap_or(d.strategy),
+            max_calls: count("LEGAL_ML_MAP_MAX_CALLS", d.max_calls),
+        }
+    }
+}
+
+/// The request's say in what the backend is sent.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct ContextOptions {
+    /// Clause types whose sections are preferred, e.g. "Indemnification".
+    #[serde(default)]
+    pub focus: Vec<String>,
+    /// Free text; sections sharing more of its words are preferred.
+    #[serde(default)]
+    pub query: Option<String>,
+    /// Overrides `LEGAL_ML_CONTEXT_STRATEGY` for this request.
+    #[serde(default)]
+    pub strategy: Option<PackingStrategy>,
+}
+
+// ── Report ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PackedSection {
+    /// The numbered heading, when the section has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading: Option<String>,
+    /// Byte offsets into the submitted document.
+    pub start: usize,
+    pub end: usize,
+    pub tokens: usize,
+    pub score: f64,
+    pub clause_types: Vec<String>,
+    pub kept: bool,
+    /// Map-reduce: the call the section was sent in, from 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub call: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PackingReport {
+    pub strategy: PackingStrategy,
+    pub budget_tokens: usize,
+    pub document_tokens: usize,
+    /// Tokens sent over all calls.
+    pub packed_tokens: usize,
+    pub calls: usize,
+    /// In document order.
+    pub sections: Vec<PackedSection>,
+}
+
+/// What to send the backend.
+#[derive(Debug)]
+pub enum Plan {
+    /// The document fits the budget as it is.
+    Whole,
+    /// One text per call.
+    Packed(Vec<String>, PackingReport),
+}
+
+// ── Planning ──────────────────────────────────────────────────────────────────
+
+fn tokens(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+fn words(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn risk_weight(kind: &ClauseKind) -> f64 {
+    match kind.risk {
+        "high" => 3.0,
+        "medium" => 2.0,
+        _ => 1.0,
+    }
+}
+
+/// The preamble and numbered sections of `document`, or its paragraphs
+/// when it has no numbered sections; any longer than `budget` tokens are
+/// cut at paragraph and line breaks.
+fn units(document: &str, budget: usize) -> Vec<(Range<usize>, Option<String>)> {
+    let found = sections(document);
+    let mut units: Vec<(Range<usize>, Option<String>)> = match found.first() {
+        Some(first) => std::iter::once((0..first.start, None))
+            .chain(
+                found
+                    .iter()
+                    .map(|s| (s.start..s.end, Some(s.heading.to_string()))),
+            )
+            .collect(),
+        None => {
+            let mut start = 0;
+            let mut paragraphs = Vec::new();
+            while let Some(i) = document[start..].find("\n\n") {
+                paragraphs.push((start..start + i + 2, None));
+                start += i + 2;
+            }
+            paragraphs.push((start..document.len(), None));
+            paragraphs
+        }
+    };
+    units.retain(|(range, _)| !document[range.clone()].trim().is_empty());
+    units
+        .into_iter()
+        .flat_map(|(range, heading)| {
+            let text = &document[range.clone()];
+            let count = tokens(text);
+            if count <= budget {
+                return vec![(range, heading)];
+            }
+            let target = (text.len() * budget / count).max(1);
+            chunking::split(text, target)
+                .into_iter()
+                .map(|r| (range.start + r.start..range.start + r.end, heading.clone()))
+                .collect()
+        })
+        .collect()
+}
+
+/// What to send the ML backend for `document`: the whole of it when it
+/// fits the budget, else the packed sections or map-reduce chunks.
+pub fn plan(
+    config: &PackingConfig,
+    keywords: &KeywordMatcher,
+    document: &str,
+    options: &ContextOptions,
+) -> Plan {
+    let budget = config.max_tokens;
+    let document_tokens = tokens(document);
+    if document_tokens <= budget {
+        return Plan::Whole;
+    }
+    let strategy = options.strategy.unwrap_or(config.strategy);
+    let query = options.query.as_deref().map(words).unwrap_or_default();
+    let mut sections: Vec<PackedSection> = units(document, budget)
+        .into_iter()
+        .map(|(range, heading)| {
+            let text = &document[range.clone()];
+            let kinds: Vec<&ClauseKind> = taxonomy::detect(keywords, text)
+                .into_iter()
+                .map(|(kind, _)| kind)
+                .collect();
+            let typed: f64 = kinds
+                .iter()
+                .map(|kind| {
+                    let focused = options
+                        .focus
+                        .iter()
+                        .any(|f| f.eq_ignore_ascii_case(kind.name));
+                    risk_weight(kind) * if focused { FOCUS_WEIGHT } else { 1.0 }
+                })
+                .sum();
+            let asked = if query.is_empty() {
+                0.0
+            } else {
+                query.intersection(&words(text)).count() as f64 / query.len() as f64
+            };
+            PackedSection {
+                heading,
+                start: range.start,
+                end: range.end,
+                tokens: tokens(text),
+                score: typed + asked * QUERY_WEIGHT,
+                clause_types: kinds.iter().map(|k| k.name.to_string()).collect(),
+                kept: false,
+                call: None,
+            }
+        })
+        .collect();
+
+    let texts = match strategy {
+        PackingStrategy::Pack => pack(document, &mut sections, budget),
+        PackingStrategy::MapReduce => map(document, &mut sections, budget, config.max_calls),
+    };
+    let report = PackingReport {
+        strategy,
+        budget_tokens: budget,
+        document_tokens,
+        packed_tokens: sections.iter().filter(|s| s.kept).map(|s| s.tokens).sum(),
+        calls: texts.len(),
+        sections,
+    };
+    for s in &report.sections {
+        debug!(
+            heading = s.heading.as_deref().unwrap_or("-"),
+            start = s.start,
+            tokens = s.tokens,
+            score = s.score,
+            kept = s.kept,
+            call = ?s.call,
+            "ML context section"
+        );
+    }
+    info!(
+        strategy = ?report.strategy,
+        budget_tokens = budget,
+        document_tokens,
+        packed_tokens = report.packed_tokens,
+        calls = report.calls,
+        kept = report.sections.iter().filter(|s| s.kept).count(),
+        dropped = report.sections.iter().filter(|s| !s.kept).count(),
+        "ML context packed"
+    );
+    Plan::Packed(texts, report)
+}
+
+/// Keeps the highest-scoring sections that fit, and joins them in
+/// document order.
+fn pack(document: &str, sections: &mut [PackedSection], budget: usize) -> Vec<String> {
+    let mut order: Vec<usize> = (0..sections.len()).collect();
+    order.sort_by(|&a, &b| {
+        sections[b]
+            .score
+            .total_cmp(&sections[a].score)
+            .then(sections[a].start.cmp(&sections[b].start))
+    });
+    let mut left = budget;
+    for i in order {
+        if sections[i].tokens <= left {
+            left -= sections[i].tokens;
+            sections[i].kept = true;
+        }
+    }
+    let mut text = String::new();
+    let mut end = 0;
+    for s in sections.iter().filter(|s| s.kept) {
+        if s.start != end {
+            text.push_str(GAP_MARKER);
+        }
+        text.push_str(&document[s.start..s.end]);
+        end = s.end;
+    }
+    if end != document.len() {
+        text.push_str(GAP_MARKER);
+    }
+    vec![text]
+}
+
+/// Groups consecutive sections into chunks of at most `budget` tokens,
+/// keeping the `max_calls` chunks with the highest total score.
+fn map(
+    document: &str,
+    sections: &mut [PackedSection],
+    budget: usize,
+    max_calls: usize,
+) -> Vec<String> {
+    let mut chunks: Vec<Range<usize>> = Vec::new();
+    let mut tokens = 0;
+    for (i, s) in sections.iter().enumerate() {
+        match chunks.last_mut() {
+            Some(chunk) if tokens + s.tokens <= budget => {
+                chunk.end = i + 1;
+                tokens += s.tokens;
+            }
+            _ => {
+                chunks.push(i..i + 1);
+                tokens = s.tokens;
+            }
+        }
+    }
+    let score = |chunk: &Range<usize>| sections[chunk.clone()].iter().map(|s| s.score).sum::<f64>();
+    let mut ranked: Vec<usize> = (0..chunks.len()).collect();
+    ranked.sort_by(|&a, &b| {
+        score(&chunks[b])
+            .total_cmp(&score(&chunks[a]))
+            .then(a.cmp(&b))
+    });
+    ranked.truncate(max_calls);
+    ranked.sort_unstable();
+    ranked
+        .into_iter()
+        .enumerate()
+        .map(|(call, c)| {
+            let chunk = chunks[c].clone();
+            for s in &mut sections[chunk.clone()] {
+                s.kept = true;
+                s.call = Some(call);
+            }
+            document[sections[chunk.start].start..sections[chunk.end - 1].end].to_string()
+        })
+        .collect()
+}
+
+// ── Reducing ──────────────────────────────────────────────────────────────────
+
+/// One finding per clause type and per issue across the map calls, each
+/// the most confident report of it.
+pub fn reduce(answers: Vec<MlFindings>) -> MlFindings {
+    let mut clauses: Vec<MlClause> = Vec::new();
+    let mut issues: Vec<MlIssue> = Vec::new();
+    for answer in answers {
+        for clause in answer.clauses {
+            match clauses
+                .iter_mut()
+                .find(|c| c.clause_type.eq_ignore_ascii_case(&clause.clause_type))
+            {
+                Some(seen) if seen.confidence >= clause.confidence => {}
+                Some(seen) => *seen = clause,
+                None => clauses.push(clause),
+            }
+        }
+        for issue in answer.issues {
+            match issues
+                .iter_mut()
+                .find(|i| ensemble::same_issue(&i.description, &issue.description))
+            {
+                Some(seen) if seen.confidence >= issue.confidence => {}
+                Some(seen) => *seen = issue,
+                None => issues.push(issue),
+            }
+        }
+    }
+    MlFindings {
+        clauses,
+        issues,
+        ..MlFindings::default()
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warmup::Precompiled;
+
+    fn contract() -> String {
+        let filler = "The parties record the services delivered. ".repeat(30);
+        [
+            "MASTER SERVICES AGREEMENT between Acme and Beta.\n".to_string(),
+            format!("1. Services\n{filler}\n"),
+            format!("2. Indemnification\nThe Supplier shall indemnify the Customer. {filler}\n"),
+            format!("3. Notices\n{filler}\n"),
+            format!("4. Payment\nEach invoice is payable in 30 days. {filler}\n"),
+            format!("5. Governing Law\nThis Agreement is governed by Swiss law. {filler}\n"),
+        ]
+        .concat()
+    }
+
+    fn config(max_tokens: usize, strategy: PackingStrategy) -> PackingConfig {
+        PackingConfig {
+            max_tokens,
+            strategy,
+            max_calls: 8,
+        }
+    }
+
+    fn headings(report: &PackingReport) -> Vec<&str> {
+        report
+            .sections
+            .iter()
+            .filter(|s| s.kept)
+            .map(|s| s.heading.as_deref().unwrap_or("preamble"))
+            .collect()
+    }
+
+    #[test]
+    fn packs_the_riskiest_and_focused_sections_in_document_order() {
+        let pre = Precompiled::default();
+        let doc = contract();
+        assert!(matches!(
+            plan(
+                &config(10_000, PackingStrategy::Pack),
+                pre.keywords(),
+                &doc,
+                &Default::default()
+            ),
+            Plan::Whole
+        ));
+
+        let Plan::Packed(texts, report) = plan(
+            &config(500, PackingStrategy::Pack),
+            pre.keywords(),
+            &doc,
+            &ContextOptions::default(),
+        ) else {
+            panic!("expected packing");
+        };
+        assert_eq!(texts.len(), 1);
+        assert!(report.packed_tokens <= 500 && report.packed_tokens < tokens(&texts[0]));
+        // Indemnification is high risk; payment and governing law are low.
+        assert_eq!(headings(&report)[..2], ["preamble", "2. Indemnification"]);
+        let text = &texts[0];
+        // Section 1 was left out, and the gap is marked.
+        assert!(text.starts_with("MASTER SERVICES AGREEMENT"));
+        assert!(text.find("[…]").unwrap() < text.find("2. Indemnification").unwrap());
+        assert!(!text.contains("1. Services"));
+
+        let focused = ContextOptions {
+            focus: vec!["payment terms".to_string()],
+            query: Some("Swiss governing law".to_string()),
+            strategy: None,
+        };
+        let Plan::Packed(texts, report) = plan(
+            &config(500, PackingStrategy::Pack),
+            pre.keywords(),
+            &doc,
+            &focused,
+        ) else {
+            panic!("expected packing");
+        };
+        let kept = headings(&report);
+        assert!(
+            kept.contains(&"4. Payment") && kept.contains(&"5. Governing Law"),
+            "{kept:?}"
+        );
+        assert!(!kept.contains(&"2. Indemnification"), "{kept:?}");
+        assert!(texts[0].find("4. Payment").unwrap() < texts[0].find("5. Governing").unwrap());
+    }
+
+    #[test]
+    fn map_reduce_covers_the_document_within_the_call_limit() {
+        let pre = Precompiled::default();
+        let doc = contract();
+        let Plan::Packed(texts, report) = plan(
+            &config(400, PackingStrategy::MapReduce),
+            pre.keywords(),
+            &doc,
+            &Default::default(),
+        ) else {
+            panic!("expected chunks");
+        };
+        assert!(texts.len() >= 3);
+        assert!(report.sections.iter().all(|s| s.kept && s.call.is_some()));
+        assert!(texts.iter().all(|t| tokens(t) <= 400));
+        assert_eq!(texts.concat().len(), doc.len());
+
+        let limited = PackingConfig {
+            max_calls: 1,
+            ..config(400, PackingStrategy::MapReduce)
+        };
+        let Plan::Packed(texts, report) = plan(&limited, pre.keywords(), &doc, &Default::default())
+        else {
+            panic!("expected chunks");
+        };
+        assert_eq!((texts.len(), report.calls), (1, 1));
+        assert!(report.sections.iter().any(|s| !s.kept));
+    }
+
+    #[test]
+    fn reduce_keeps_the_most_confident_report_of_each_finding() {
+        let clause = |t: &str, c: f64| MlClause {
+            clause_type: t.to_string(),
+            risk_level: "high".to_string(),
+            confidence: c,
+            text: String::new(),
+        };
+        let issue = |d: &str, c: f64| MlIssue {
+            description: d.to_string(),
+            severity: "medium".to_string(),
+            confidence: c,
+            location: None,
+        };
+        let reduced = reduce(vec![
+            MlFindings {
+                clauses: vec![clause("Liability", 0.6), clause("Termination", 0.7)],
+                issues: vec![issue("Uncapped liability for data loss", 0.5)],
+                ..Default::default()
+            },
+            MlFindings {
+                clauses: vec![clause("liability", 0.9)],
+                issues: vec![issue("Uncapped liability for data loss claims", 0.8)],
+                ..Default::default()
+            },
+        ]);
+        assert_eq!(reduced.clauses.len(), 2);
+        assert_eq!(reduced.clauses[0].confidence, 0.9);
+        assert_eq!(reduced.issues.len(), 1);
+        assert_eq!(reduced.issues[0].confidence, 0.8);
+    }
+}
@@ -0,0 +1,252 @@
+//! "Our paper" vs counterparty paper: a submitted contract is ours when it
+//! contains most of the fixed wording of one of our templates.
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashSet},
+    hash::{Hash, Hasher},
+};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    deadline::AnalysisMode,
+    diligence::KeyTerms,
+    warmup::{ParsedTemplate, Segment},
+    AppState, Issue, BUILTIN_TEMPLATES,
+};
+
+/// Words per shingle; long enough that boilerplate phrases rarely collide.
+const SHINGLE_WORDS: usize = 4;
+/// Share of a template's shingles that must appear in the document.
+const MIN_CONTAINMENT: f64 = 0.6;
+
+/// Names of the playbook checks, as a playbook's `checks` refer to them.
+pub const PLAYBOOK_CHECKS: [&str; 5] = [
+    "liability_cap",
+    "governing_law",
+    "auto_renewal",
+    "exclusivity",
+    "non_compete",
+];
+/// What a playbook setting may set a check to.
+pub const PLAYBOOK_SETTINGS: [&str; 5] = ["off", "low", "medium", "high", "critical"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PaperSource {
+    Ours,
+    Counterparty,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PaperDetection {
+    pub source: PaperSource,
+    /// Best-matching template and revision, when the document is ours.
+    pub template_id: Option<String>,
+    pub revision: Option<u32>,
+    /// Containment of the best match, 0–1.
+    pub similarity: f64,
+    /// Mode the analysis actually ran in.
+    pub review_profile: AnalysisMode,
+}
+
+// ── Fingerprints ──────────────────────────────────────────────────────────────
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn shingles(words: &[String], into: &mut HashSet<u64>) {
+    for window in words.windows(SHINGLE_WORDS) {
+        let mut h = DefaultHasher::new();
+        window.hash(&mut h);
+        into.insert(h.finish());
+    }
+}
+
+pub fn fingerprint(text: &str) -> HashSet<u64> {
+    let mut fp = HashSet::new();
+    shingles(&words(text), &mut fp);
+    fp
+}
+
+/// Shingles of a template's fixed wording; none span a placeholder, since
+/// whatever fills it differs per contract.
+pub fn template_fingerprint(template: &ParsedTemplate) -> HashSet<u64> {
+    let mut fp = HashSet::new();
+    for segment in &template.segments {
+        if let Segment::Text(text) = segment {
+            shingles(&words(text), &mut fp);
+        }
+    }
+    fp
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn containment(template: &HashSet<u64>, document: &HashSet<u64>) -> f64 {
+    if template.is_empty() {
+        return 0.0;
+    }
+    template.intersection(document).count() as f64 / template.len() as f64
+}
+
+/// Compares against every revision of every built-in template, so contracts
+/// drafted from an older revision are still recognised.
+pub fn detect(state: &AppState, document: &str, requested: AnalysisMode) -> PaperDetection {
+    let doc_fp = fingerprint(document);
+    let mut best: Option<(String, u32, f64)> = None;
+    for id in BUILTIN_TEMPLATES {
+        for rev in state.template_revisions.list(id).unwrap_or_default() {
+            let Ok(parsed) = ParsedTemplate::parse(&rev.body) else {
+                continue;
+            };
+            let score = containment(&template_fingerprint(&parsed), &doc_fp);
+            if best.as_ref().is_none_or(|b| score > b.2) {
+                best = Some((id.to_string(), rev.revision, score));
+            }
+        }
+    }
+    let similarity = best.as_ref().map_or(0.0, |b| b.2);
+    let ours = similarity >= MIN_CONTAINMENT;
+    let (template_id, revision) = match best {
+        Some((id, rev, _)) if ours => (Some(id), Some(rev)),
+        _ => (None, None),
+    };
+    PaperDetection {
+        source: if ours {
+            PaperSource::Ours
+        } else {
+            PaperSource::Counterparty
+        },
+        template_id,
+        revision,
+        similarity,
+        // Counterparty paper gets the deep profile unless the caller asked
+        // for quick mode explicitly.
+        review_profile: match (ours, requested) {
+            (false, AnalysisMode::Standard) => AnalysisMode::Deep,
+            _ => requested,
+        },
+    }
+}
+
+// ── Playbook ──────────────────────────────────────────────────────────────────
+
+/// Stricter checks for counterparty paper: protections our templates always
+/// contain and theirs often omit or reverse. `settings` switch checks `off`
+/// or change their severity, by name. `next_id` continues numbering.
+pub fn playbook_issues(
+    terms: &KeyTerms,
+    settings: &BTreeMap<String, String>,
+    next_id: usize,
+) -> Vec<Issue> {
+    let checks = [
+        (
+            "liability_cap",
+            !terms.liability_cap,
+            "high",
+            "Counterparty paper has no limitation of liability cap.",
+        ),
+        (
+            "governing_law",
+            terms.governing_law.is_none(),
+            "medium",
+            "Counterparty paper does not state a governing law.",
+        ),
+        (
+            "auto_renewal",
+            terms.auto_renewal,
+            "medium",
+            "Counterparty paper renews automatically.",
+        ),
+        (
+            "exclusivity",
+            terms.exclusivity,
+            "high",
+            "Counterparty paper imposes exclusivity.",
+        ),
+        (
+            "non_compete",
+            terms.non_compete,
+            "high",
+            "Counterparty paper contains a non-compete.",
+        ),
+    ];
+    checks
+        .into_iter()
+        .filter(|(_, failed, _, _)| *failed)
+        .filter_map(|(name, _, severity, description)| {
+            match settings.get(name).map(String::as_str) {
+                Some("off") => None,
+                setting => Some((setting.unwrap_or(severity), description)),
+            }
+        })
+        .enumerate()
+        .map(|(i, (severity, description))| Issue {
+            id: format!("issue-{:03}", next_id + i),
+            description: description.to_string(),
+            severity: severity.to_string(),
+            location: "Document".to_string(),
+            confidence: 0.8,
+            review_status: "auto".to_string(),
+            provenance: None,
+            excerpt: None,
+            position: None,
+        })
+        .collect()
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compile_template, warmup::Precompiled, CompileExtras};
+    use std::collections::HashMap;
+
+    #[test]
+    fn compiled_template_is_our_paper() {
+        let pre = Precompiled::default();
+        let vars: HashMap<String, String> = [
+            ("party_a", "Acme Corp"),
+            ("party_b", "Globex Ltd"),
+            ("effective_date", "2024-01-01"),
+            ("jurisdiction", "Japan"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let doc = compile_template(&pre, "nda", &vars, CompileExtras::default())
+            .unwrap()
+            .compiled_document;
+        let fp = fingerprint(&doc);
+        let nda = template_fingerprint(pre.template("nda").unwrap());
+        assert!((containment(&nda, &fp) - 1.0).abs() < f64::EPSILON);
+        let sla = template_fingerprint(pre.template("sla").unwrap());
+        assert!(containment(&sla, &fp) < MIN_CONTAINMENT);
+    }
+
+    #[test]
+    fn playbook_flags_missing_protections() {
+        let issues = playbook_issues(&KeyTerms::default(), &BTreeMap::new(), 3);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].id, "issue-003");
+        assert_eq!(issues[0].severity, "high");
+    }
+
+    #[test]
+    fn playbook_settings_switch_off_or_reweight_checks() {
+        let settings = BTreeMap::from([
+            ("liability_cap".to_string(), "off".to_string()),
+            ("governing_law".to_string(), "critical".to_string()),
+        ]);
+        let issues = playbook_issues(&KeyTerms::default(), &settings, 1);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].id, "issue-001");
+        assert_eq!(issues[0].severity, "critical");
+    }
+}
@@ -0,0 +1,262 @@
+//! Sandbox for regular expressions supplied by tenants. Patterns compile to
+//! the `regex` crate's finite automata, which never backtrack: matching
+//! costs time linear in the text whatever the pattern, so `(a+)+$` is as
+//! safe as `a`. On top of that the sandbox caps how long a pattern is, how
+//! deep it nests, how often a counted repetition repeats and how large the
+//! compiled automaton grows, and turns constructs the engine cannot run
+//! (look-around, backreferences) into errors that say so.
+
+use std::fmt;
+
+use regex::{Regex, RegexBuilder};
+use regex_syntax::ast::{
+    self, parse::ParserBuilder, visit, Ast, ErrorKind, RepetitionKind, RepetitionRange, Visitor,
+};
+
+/// Longest pattern accepted, in bytes.
+pub const MAX_PATTERN_LEN: usize = 256;
+/// Most patterns one rule may carry.
+pub const MAX_PATTERNS: usize = 32;
+/// Largest bound of a counted repetition such as `x{2,100}`.
+const MAX_REPEAT: u32 = 100;
+/// Deepest nesting of groups, classes and repetitions.
+const NEST_LIMIT: u32 = 16;
+/// Heap the compiled program and the lazy DFA's cache may use.
+const SIZE_LIMIT: usize = 1 << 20;
+const DFA_SIZE_LIMIT: usize = 2 << 20;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+/// Why a pattern was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub pattern: String,
+    pub reason: String,
+}
+
+impl PatternError {
+    fn new(pattern: &str, reason: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pattern {:?}: {}", self.pattern, self.reason)
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+// ── Compile ───────────────────────────────────────────────────────────────────
+
+/// Rejects counted repetitions above `MAX_REPEAT`.
+struct RepeatLimit;
+
+impl Visitor for RepeatLimit {
+    type Output = ();
+    type Err = u32;
+
+    fn finish(self) -> Result<(), u32> {
+        Ok(())
+    }
+
+    fn visit_pre(&mut self, ast: &Ast) -> Result<(), u32> {
+        if let Ast::Repetition(rep) = ast {
+            if let RepetitionKind::Range(range) = &rep.op.kind {
+                let bound = match *range {
+                    RepetitionRange::Exactly(n) | RepetitionRange::AtLeast(n) => n,
+                    RepetitionRange::Bounded(_, n) => n,
+                };
+                if bound > MAX_REPEAT {
+                    return Err(bound);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn syntax_error(pattern: &str, error: &ast::Error) -> PatternError {
+    let reason = match error.kind() {
+        ErrorKind::UnsupportedLookAround => {
+            "look-ahead and look-behind are not supported".to_string()
+        }
+        ErrorKind::UnsupportedBackreference => "backreferences are not supported".to_string(),
+        ErrorKind::NestLimitExceeded(_) => {
+            format!("nests groups or repetitions more than {NEST_LIMIT} deep")
+        }
+        kind => format!("is not a valid regular expression: {kind}"),
+    };
+    PatternError::new(pattern, reason)
+}
+
+/// Compiles one tenant pattern, case-insensitively, inside the sandbox.
+pub fn compile(pattern: &str) -> Result<Regex, PatternError> {
+    if pattern.trim().is_empty() {
+        return Err(PatternError::new(pattern, "is empty"));
+    }
+    if pattern.len() > MAX_PATTERN_LEN {
+        return Err(PatternError::new(
+            pattern,
+            format!("is longer than {MAX_PATTERN_LEN} bytes"),
+        ));
+    }
+    let parsed = ParserBuilder::new()
+        .nest_limit(NEST_LIMIT)
+        .build()
+        .parse(pattern)
+        .map_err(|e| syntax_error(pattern, &e))?;
+    visit(&parsed, RepeatLimit).map_err(|bound| {
+        PatternError::new(
+            pattern,
+            format!("repeats {bound} times; the limit is {MAX_REPEAT}"),
+        )
+    })?;
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .nest_limit(NEST_LIMIT)
+        .size_limit(SIZE_LIMIT)
+        .dfa_size_limit(DFA_SIZE_LIMIT)
+        .build()
+        .map_err(|e| match e {
+            regex::Error::CompiledTooBig(_) => {
+                PatternError::new(pattern, "compiles to an automaton too large to run")
+            }
+            e => PatternError::new(pattern, format!("is not a valid regular expression: {e}")),
+        })
+}
+
+/// Compiles every pattern, failing on the first one refused.
+pub fn compile_all(patterns: &[String]) -> Result<Vec<Regex>, PatternError> {
+    if patterns.len() > MAX_PATTERNS {
+        return Err(PatternError::new(
+            &patterns[MAX_PATTERNS],
+            format!("is one of more than {MAX_PATTERNS} patterns"),
+        ));
+    }
+    patterns.iter().map(|p| compile(p)).collect()
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn reason(pattern: &str) -> String {
+        compile(pattern).unwrap_err().reason
+    }
+
+    /// xorshift64, so the fuzz cases are the same on every run.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn pick<'a>(&mut self, from: &[&'a str]) -> &'a str {
+            from[(self.next() % from.len() as u64) as usize]
+        }
+    }
+
+    #[test]
+    fn patterns_match_case_insensitively() {
+        let re = compile(r"privacy\s+shield").unwrap();
+        let m = re.find("under the EU-US Privacy  Shield.").unwrap();
+        assert_eq!(m.as_str(), "Privacy  Shield");
+        assert!(compile(r"\w+ (?:LIBOR|SOFR)").is_ok());
+    }
+
+    #[test]
+    fn unsupported_constructs_are_named() {
+        assert_eq!(
+            reason("shield(?=framework)"),
+            "look-ahead and look-behind are not supported"
+        );
+        assert_eq!(
+            reason("(?<!EU-US )shield"),
+            "look-ahead and look-behind are not supported"
+        );
+        assert_eq!(reason(r"(a)\1"), "backreferences are not supported");
+        assert_eq!(reason("   "), "is empty");
+        assert!(reason("(unclosed").starts_with("is not a valid regular expression"));
+    }
+
+    #[test]
+    fn size_and_complexity_are_limited() {
+        assert_eq!(
+            reason(&"a".repeat(MAX_PATTERN_LEN + 1)),
+            "is longer than 256 bytes"
+        );
+        assert_eq!(reason("a{1000}"), "repeats 1000 times; the limit is 100");
+        assert_eq!(reason("a{2,101}"), "repeats 101 times; the limit is 100");
+        assert_eq!(
+            reason(&format!("{}a{}", "(".repeat(20), ")".repeat(20))),
+            "nests groups or repetitions more than 16 deep"
+        );
+        assert_eq!(
+            reason(r"((\w{100}){100}){100}"),
+            "compiles to an automaton too large to run"
+        );
+        let many = vec!["a".to_string(); MAX_PATTERNS + 1];
+        assert!(compile_all(&many).is_err());
+        assert!(compile_all(&many[..MAX_PATTERNS]).is_ok());
+    }
+
+    #[test]
+    fn catastrophic_patterns_run_in_linear_time() {
+        let text = format!("{}!", "a".repeat(100_000));
+        for pattern in [
+            r"(a+)+$",
+            r"(a|aa)*b",
+            r"(.*a){20}x",
+            r"(a|a?)+\d",
+            r"(\w+\s?)*$",
+        ] {
+            let re = compile(pattern).unwrap();
+            let started = Instant::now();
+            let _ = re.find(&text);
+            assert!(
+                started.elapsed() < Duration::from_secs(2),
+                "{pattern} took {:?}",
+                started.elapsed()
+            );
+        }
+    }
+
+    #[test]
+    fn fuzzed_patterns_never_panic_and_match_on_char_boundaries() {
+        const PIECES: &[&str] = &[
+            "a", "b", "é", ".", "\\w", "\\d", "\\s", "\\b", "[a-z]", "[^x]", "(", ")", "(?:",
+            "(?=", "(?i)", "|", "*", "+", "?", "{2}", "{1,3}", "{200}", "^", "$", "\\", "\\1", "[",
+            "]", "-", "{", "}", "(?P<n>",
+        ];
+        const TEXTS: &[&str] = &["", "aaaa", "a1 b2 éé", "Privacy Shield", "((()))", "ß€𝔘"];
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        let mut compiled = 0;
+        for _ in 0..5_000 {
+            let len = 1 + rng.next() % 12;
+            let pattern: String = (0..len).map(|_| rng.pick(PIECES)).collect();
+            let Ok(re) = compile(&pattern) else {
+                continue;
+            };
+            compiled += 1;
+            for text in TEXTS {
+                for m in re.find_iter(text) {
+                    assert!(text.is_char_boundary(m.start()), "{pattern} on {text}");
+                    assert!(text.is_char_boundary(m.end()), "{pattern} on {text}");
+                }
+            }
+        }
+        assert!(compiled > 500, "only {compiled} fuzzed patterns compiled");
+    }
+}
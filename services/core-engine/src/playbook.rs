@@ -0,0 +1,452 @@
+//! Clause playbooks: a tenant's negotiation positions per clause type, with
+//! the preferred language, the fallbacks it accepts in that order, and the
+//! walk-away terms it never signs. Analyses grade every detected clause
+//! against the rule for its type and suggest the wording to propose when
+//! the clause is off-position. The playbook also tunes the checks run on
+//! counterparty paper (see `paper::playbook_issues`). Entities in an
+//! organization inherit their ancestors' playbooks, the nearest entity's
+//! rule for a clause type and setting for a check winning.
+//!
+//! A clause is on a position when it contains most of that position's
+//! words; a walk-away term anywhere in it overrides any match.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use dashmap::DashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    export_control::find_words,
+    favorability::clause_text,
+    notifier,
+    paper::{PLAYBOOK_CHECKS, PLAYBOOK_SETTINGS},
+    taxonomy::TAXONOMY,
+    AppState, Clause,
+};
+
+/// Share of a position's words a clause must contain to be on it.
+const MIN_COVERAGE: f64 = 0.8;
+/// Upper bounds that keep a playbook reviewable.
+const MAX_FALLBACKS: usize = 10;
+const MAX_WALK_AWAY: usize = 50;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaybookRule {
+    /// The `clause_type` analyses report, e.g. "Liability".
+    pub clause_type: String,
+    pub preferred: String,
+    /// Acceptable alternatives, the one to offer first first.
+    #[serde(default)]
+    pub fallbacks: Vec<String>,
+    /// Phrases that make a clause unacceptable whatever else it says.
+    #[serde(default)]
+    pub walk_away: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Playbook {
+    #[serde(default)]
+    pub rules: Vec<PlaybookRule>,
+    /// Counterparty-paper check name to `off` or the severity to report it at.
+    #[serde(default)]
+    pub checks: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlaybookResponse {
+    pub tenant: String,
+    #[serde(flatten)]
+    pub playbook: Playbook,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Compliance {
+    Preferred,
+    /// On one of the acceptable fallbacks.
+    Fallback,
+    /// On none of the positions.
+    Deviation,
+    /// Contains a walk-away term.
+    WalkAway,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ClausePlaybook {
+    pub clause_id: String,
+    pub clause_type: String,
+    pub status: Compliance,
+    /// Index of the fallback the clause is on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<usize>,
+    /// The walk-away terms found in the clause.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub walk_away_terms: Vec<String>,
+    /// Share of the closest position's words the clause contains, 0–1.
+    pub coverage: f64,
+    /// The first fallback, or the preferred language when there is none,
+    /// for a clause on no position.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggested_wording: Option<String>,
+}
+
+// ── Review ────────────────────────────────────────────────────────────────────
+
+fn words(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn coverage(position: &BTreeSet<String>, clause: &BTreeSet<String>) -> f64 {
+    if position.is_empty() {
+        return 0.0;
+    }
+    position.intersection(clause).count() as f64 / position.len() as f64
+}
+
+impl PlaybookRule {
+    /// Grades `text`, the wording of a clause of this rule's type.
+    fn review(&self, clause: &Clause, text: &str) -> ClausePlaybook {
+        let lower = text.to_ascii_lowercase();
+        let walk_away_terms: Vec<String> = self
+            .walk_away
+            .iter()
+            .filter(|t| !find_words(&lower, &t.trim().to_ascii_lowercase()).is_empty())
+            .cloned()
+            .collect();
+        let found = words(text);
+        let preferred = coverage(&words(&self.preferred), &found);
+        // The first fallback wins a tie, as the one offered first.
+        let best_fallback = self
+            .fallbacks
+            .iter()
+            .map(|f| coverage(&words(f), &found))
+            .enumerate()
+            .fold(None, |best: Option<(usize, f64)>, (i, c)| match best {
+                Some((_, b)) if b >= c => best,
+                _ => Some((i, c)),
+            });
+        let (status, fallback) = if !walk_away_terms.is_empty() {
+            (Compliance::WalkAway, None)
+        } else if preferred >= MIN_COVERAGE {
+            (Compliance::Preferred, None)
+        } else {
+            match best_fallback {
+                Some((i, c)) if c >= MIN_COVERAGE => (Compliance::Fallback, Some(i)),
+                _ => (Compliance::Deviation, None),
+            }
+        };
+        let suggested_wording = matches!(status, Compliance::Deviation | Compliance::WalkAway)
+            .then(|| self.fallbacks.first().unwrap_or(&self.preferred).clone());
+        ClausePlaybook {
+            clause_id: clause.id.clone(),
+            clause_type: clause.clause_type.clone(),
+            status,
+            fallback,
+            walk_away_terms,
+            coverage: best_fallback.map_or(preferred, |(_, c)| c.max(preferred)),
+            suggested_wording,
+        }
+    }
+}
+
+impl Playbook {
+    pub fn validate(&self) -> Result<(), String> {
+        let mut seen = BTreeSet::new();
+        for rule in &self.rules {
+            let kind = &rule.clause_type;
+            if !TAXONOMY.iter().any(|k| k.name == kind) {
+                return Err(format!("unknown clause type {kind:?}"));
+            }
+            if !seen.insert(kind) {
+                return Err(format!("more than one rule for {kind:?}"));
+            }
+            if words(&rule.preferred).is_empty() {
+                return Err(format!("{kind}: preferred language is required"));
+            }
+            if rule.fallbacks.len() > MAX_FALLBACKS {
+                return Err(format!("{kind}: at most {MAX_FALLBACKS} fallbacks"));
+            }
+            if rule.fallbacks.iter().any(|f| words(f).is_empty()) {
+                return Err(format!("{kind}: a fallback is empty"));
+            }
+            if rule.walk_away.len() > MAX_WALK_AWAY {
+                return Err(format!("{kind}: at most {MAX_WALK_AWAY} walk-away terms"));
+            }
+            if rule.walk_away.iter().any(|t| t.trim().is_empty()) {
+                return Err(format!("{kind}: a walk-away term is empty"));
+            }
+        }
+        for (check, setting) in &self.checks {
+            if !PLAYBOOK_CHECKS.contains(&check.as_str()) {
+                return Err(format!(
+                    "unknown playbook check {check:?}; expected one of {PLAYBOOK_CHECKS:?}"
+                ));
+            }
+            if !PLAYBOOK_SETTINGS.contains(&setting.as_str()) {
+                return Err(format!(
+                    "checks.{check} must be one of {PLAYBOOK_SETTINGS:?}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Grades each clause that has a rule for its type, reading it from
+    /// the line of its evidence in `document`.
+    pub fn review(&self, document: &str, clauses: &[Clause]) -> Vec<ClausePlaybook> {
+        clauses
+            .iter()
+            .filter_map(|clause| {
+                let rule = self
+                    .rules
+                    .iter()
+                    .find(|r| r.clause_type == clause.clause_type)?;
+                Some(rule.review(clause, clause_text(document, clause)))
+            })
+            .collect()
+    }
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct PlaybookStore {
+    tenants: DashMap<String, Playbook>,
+}
+
+impl PlaybookStore {
+    pub fn get(&self, tenant: &str) -> Option<Playbook> {
+        self.tenants.get(tenant).map(|p| p.value().clone())
+    }
+
+    /// The playbook in force for the first tenant of `lineage`: its own
+    /// rules and checks over its ancestors', by clause type and check name.
+    pub fn resolve(&self, lineage: &[String]) -> Playbook {
+        let mut rules = BTreeMap::new();
+        let mut checks = BTreeMap::new();
+        for tenant in lineage.iter().rev() {
+            if let Some(playbook) = self.tenants.get(tenant) {
+                for rule in &playbook.rules {
+                    rules.insert(rule.clause_type.clone(), rule.clone());
+                }
+                checks.extend(playbook.checks.clone());
+            }
+        }
+        Playbook {
+            rules: rules.into_values().collect(),
+            checks,
+        }
+    }
+
+    pub fn put(&self, tenant: &str, playbook: Playbook) -> Result<(), String> {
+        playbook.validate()?;
+        self.tenants.insert(tenant.to_string(), playbook);
+        Ok(())
+    }
+
+    pub fn remove(&self, tenant: &str) -> bool {
+        self.tenants.remove(tenant).is_some()
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn get_playbook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<PlaybookResponse>, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    let playbook = state.playbooks.get(&tenant).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(PlaybookResponse { tenant, playbook }))
+}
+
+pub async fn put_playbook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(playbook): Json<Playbook>,
+) -> Result<Json<PlaybookResponse>, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    if let Err(e) = state.playbooks.put(&tenant, playbook.clone()) {
+        info!(tenant = %tenant, error = %e, "playbook rejected");
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    info!(
+        tenant = %tenant,
+        rules = playbook.rules.len(),
+        checks = playbook.checks.len(),
+        "playbook saved"
+    );
+    let clause_types: Vec<&str> = playbook
+        .rules
+        .iter()
+        .map(|r| r.clause_type.as_str())
+        .collect();
+    state.record_audit(
+        "playbook.updated",
+        &tenant,
+        None,
+        serde_json::json!({ "clause_types": clause_types, "checks": playbook.checks }),
+    );
+    Ok(Json(PlaybookResponse { tenant, playbook }))
+}
+
+pub async fn delete_playbook(State(state): State<AppState>, headers: HeaderMap) -> StatusCode {
+    let tenant = notifier::tenant(&headers);
+    if !state.playbooks.remove(&tenant) {
+        return StatusCode::NOT_FOUND;
+    }
+    info!(tenant = %tenant, "playbook removed");
+    state.record_audit("playbook.deleted", &tenant, None, serde_json::json!({}));
+    StatusCode::NO_CONTENT
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule() -> PlaybookRule {
+        PlaybookRule {
+            clause_type: "Liability".to_string(),
+            preferred: "Liability is capped at the fees paid in the prior twelve months."
+                .to_string(),
+            fallbacks: vec![
+                "Liability is capped at twice the fees paid.".to_string(),
+                "Liability is capped at the contract value.".to_string(),
+            ],
+            walk_away: vec!["unlimited liability".to_string()],
+        }
+    }
+
+    fn clause(text: &str) -> Clause {
+        Clause {
+            id: "clause-001".to_string(),
+            text: text.to_string(),
+            clause_type: "Liability".to_string(),
+            risk_level: "high".to_string(),
+            confidence: 0.9,
+            review_status: "auto".to_string(),
+            provenance: None,
+            excerpt: None,
+            position: None,
+        }
+    }
+
+    fn playbook(rules: Vec<PlaybookRule>) -> Playbook {
+        Playbook {
+            rules,
+            ..Playbook::default()
+        }
+    }
+
+    fn review(text: &str) -> ClausePlaybook {
+        playbook(vec![rule()]).review("", &[clause(text)]).remove(0)
+    }
+
+    #[test]
+    fn clauses_are_graded_against_each_position() {
+        let preferred = review(
+            "7. Liability. Liability is capped at the fees paid in the prior twelve months.",
+        );
+        assert_eq!(preferred.status, Compliance::Preferred);
+        assert_eq!(preferred.suggested_wording, None);
+
+        let fallback = review("Each party's liability is capped at twice the fees paid.");
+        assert_eq!(fallback.status, Compliance::Fallback);
+        assert_eq!(fallback.fallback, Some(0));
+
+        let deviation = review("Neither party shall be liable for indirect damages.");
+        assert_eq!(deviation.status, Compliance::Deviation);
+        assert_eq!(
+            deviation.suggested_wording.as_deref(),
+            Some("Liability is capped at twice the fees paid.")
+        );
+        assert!(deviation.coverage < MIN_COVERAGE);
+
+        let walk_away = review(
+            "Supplier accepts Unlimited Liability; liability is capped at twice the fees paid.",
+        );
+        assert_eq!(walk_away.status, Compliance::WalkAway);
+        assert_eq!(walk_away.walk_away_terms, ["unlimited liability"]);
+        assert!(walk_away.suggested_wording.is_some());
+    }
+
+    #[test]
+    fn clauses_without_a_rule_are_not_graded() {
+        let mut other = clause("Either party may terminate on notice.");
+        other.clause_type = "Termination".to_string();
+        assert!(playbook(vec![rule()]).review("", &[other]).is_empty());
+    }
+
+    #[test]
+    fn nearer_entities_replace_inherited_rules() {
+        let store = PlaybookStore::default();
+        let mut termination = rule();
+        termination.clause_type = "Termination".to_string();
+        let mut group = playbook(vec![rule(), termination]);
+        group.checks = BTreeMap::from([
+            ("exclusivity".to_string(), "off".to_string()),
+            ("non_compete".to_string(), "medium".to_string()),
+        ]);
+        store.put("group", group).unwrap();
+        let mut local = rule();
+        local.preferred = "Liability is uncapped for data breaches.".to_string();
+        let mut emea = playbook(vec![local]);
+        emea.checks = BTreeMap::from([("exclusivity".to_string(), "high".to_string())]);
+        store.put("emea", emea).unwrap();
+
+        let resolved = store.resolve(&["emea".to_string(), "group".to_string()]);
+        assert_eq!(resolved.rules.len(), 2);
+        let liability = resolved
+            .rules
+            .iter()
+            .find(|r| r.clause_type == "Liability")
+            .unwrap();
+        assert_eq!(
+            liability.preferred,
+            "Liability is uncapped for data breaches."
+        );
+        assert_eq!(resolved.checks["exclusivity"], "high");
+        assert_eq!(resolved.checks["non_compete"], "medium");
+        assert_eq!(store.resolve(&["us".to_string()]), Playbook::default());
+    }
+
+    #[test]
+    fn invalid_playbooks_are_rejected() {
+        let mut unknown = rule();
+        unknown.clause_type = "Warranty Disclaimers".to_string();
+        assert!(playbook(vec![unknown]).validate().is_err());
+        assert!(playbook(vec![rule(), rule()]).validate().is_err());
+        let mut empty = rule();
+        empty.walk_away.push(" ".to_string());
+        assert!(playbook(vec![empty]).validate().is_err());
+        let mut checks = playbook(vec![rule()]);
+        checks
+            .checks
+            .insert("auto_renewal".to_string(), "low".to_string());
+        assert!(checks.validate().is_ok());
+        checks
+            .checks
+            .insert("auto_renewal".to_string(), "severe".to_string());
+        assert!(checks.validate().is_err());
+        let mut unknown_check = Playbook::default();
+        unknown_check
+            .checks
+            .insert("royalties".to_string(), "off".to_string());
+        assert!(unknown_check.validate().is_err());
+    }
+}
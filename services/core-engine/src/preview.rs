@@ -0,0 +1,265 @@
+//! Template previews filled with plausible sample values, so reviewers can
+//! read realistic output without assembling a variables payload.
+
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    annotations::ClauseAnnotation, compile_template, get_required_variables, AppState,
+    CompileExtras,
+};
+
+const COMPANIES: [&str; 6] = [
+    "Northwind Traders Ltd",
+    "Contoso Pharmaceuticals Inc",
+    "Fabrikam Robotics GmbH",
+    "Tailspin Logistics LLC",
+    "Wide World Importers plc",
+    "Litware Software KK",
+];
+const PEOPLE: [&str; 4] = [
+    "Alex Morgan",
+    "Priya Natarajan",
+    "Kenji Sato",
+    "Maria Gonzalez",
+];
+const JURISDICTIONS: [&str; 5] = [
+    "England and Wales",
+    "New York",
+    "Japan",
+    "Singapore",
+    "Germany",
+];
+const PRODUCTS: [&str; 3] = ["Northwind Cloud", "Contoso Analytics", "Fabrikam Vision"];
+const POSITIONS: [&str; 3] = [
+    "Senior Software Engineer",
+    "Head of Legal",
+    "Product Manager",
+];
+const DATA_TYPES: [&str; 2] = [
+    "names, email addresses and billing records",
+    "support tickets and usage telemetry",
+];
+const AMOUNTS: [&str; 4] = ["USD 12,500", "EUR 48,000", "GBP 7,250", "JPY 1,200,000"];
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleKind {
+    Organization,
+    Person,
+    Date,
+    Amount,
+    Percent,
+    Hours,
+    Duration,
+    Email,
+    Jurisdiction,
+    Text,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SampleValue {
+    pub kind: SampleKind,
+    pub value: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PreviewQuery {
+    /// Picks a different set of sample values; the same seed always gives
+    /// the same preview.
+    #[serde(default)]
+    pub seed: usize,
+    #[serde(default)]
+    pub annotations: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreviewResponse {
+    pub template_id: String,
+    pub seed: usize,
+    pub sample_variables: BTreeMap<String, SampleValue>,
+    pub compiled_document: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Vec<ClauseAnnotation>>,
+}
+
+// ── Samples ───────────────────────────────────────────────────────────────────
+
+/// Guesses what a variable holds from its name.
+pub fn kind_of(variable: &str) -> SampleKind {
+    let v = variable;
+    if v.ends_with("_date") {
+        SampleKind::Date
+    } else if v.contains("email") {
+        SampleKind::Email
+    } else if v.ends_with("_percent") {
+        SampleKind::Percent
+    } else if v.ends_with("_hours") {
+        SampleKind::Hours
+    } else if v.ends_with("_period") || v.ends_with("_months") {
+        SampleKind::Duration
+    } else if v == "jurisdiction" || v == "governing_law" {
+        SampleKind::Jurisdiction
+    } else if ["salary", "fee", "pricing", "price", "amount"]
+        .iter()
+        .any(|k| v.contains(k))
+    {
+        SampleKind::Amount
+    } else if v == "employee" {
+        SampleKind::Person
+    } else if v.starts_with("party_")
+        || v.ends_with("_name") && !v.starts_with("product") && !v.starts_with("software")
+        || [
+            "employer",
+            "controller",
+            "processor",
+            "licensor",
+            "licensee",
+            "customer",
+            "service_provider",
+        ]
+        .contains(&v)
+    {
+        SampleKind::Organization
+    } else {
+        SampleKind::Text
+    }
+}
+
+fn pick<'a>(pool: &[&'a str], n: usize) -> &'a str {
+    pool[n % pool.len()]
+}
+
+/// Dates are offsets from `today` chosen so start/end pairs stay ordered.
+fn sample_date(variable: &str, today: NaiveDate) -> String {
+    let days = match variable {
+        "current_end_date" => 90,
+        "new_end_date" => 455,
+        "start_date" => 14,
+        _ => 0,
+    };
+    today
+        .checked_add_days(Days::new(days))
+        .unwrap_or(today)
+        .to_string()
+}
+
+/// One value per variable. Organizations are numbered in template order so
+/// two parties never share a name.
+pub fn samples(
+    variables: &[String],
+    seed: usize,
+    today: NaiveDate,
+) -> BTreeMap<String, SampleValue> {
+    let mut organizations = 0;
+    variables
+        .iter()
+        .map(|name| {
+            let name = name.as_str();
+            let kind = kind_of(name);
+            let value = match kind {
+                SampleKind::Organization => {
+                    organizations += 1;
+                    pick(&COMPANIES, seed + organizations - 1).to_string()
+                }
+                SampleKind::Person => pick(&PEOPLE, seed).to_string(),
+                SampleKind::Date => sample_date(name, today),
+                SampleKind::Amount => pick(&AMOUNTS, seed).to_string(),
+                SampleKind::Percent => pick(&["99.9", "99.5", "99.95"], seed).to_string(),
+                SampleKind::Hours => pick(&["4", "8", "24"], seed).to_string(),
+                SampleKind::Duration => {
+                    pick(&["24 months", "90 days", "12 months"], seed).to_string()
+                }
+                SampleKind::Email => {
+                    pick(&["privacy@northwind.example", "dpo@contoso.example"], seed).to_string()
+                }
+                SampleKind::Jurisdiction => pick(&JURISDICTIONS, seed).to_string(),
+                SampleKind::Text => match name {
+                    n if n.starts_with("product") || n.starts_with("software") => {
+                        pick(&PRODUCTS, seed).to_string()
+                    }
+                    "position" => pick(&POSITIONS, seed).to_string(),
+                    "data_types" | "data_collected" => pick(&DATA_TYPES, seed).to_string(),
+                    "agreement_reference" => format!("AGR-{:04}", 1000 + seed),
+                    n => format!("[sample {}]", n.replace('_', " ")),
+                },
+            };
+            (name.to_string(), SampleValue { kind, value })
+        })
+        .collect()
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn preview(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(q): Query<PreviewQuery>,
+) -> Result<Json<PreviewResponse>, StatusCode> {
+    let sample_variables = samples(&get_required_variables(&id), q.seed, state.clock.today());
+    let values: HashMap<String, String> = sample_variables
+        .iter()
+        .map(|(k, v)| (k.clone(), v.value.clone()))
+        .collect();
+    let extras = CompileExtras {
+        annotations: q.annotations,
+        ..CompileExtras::default()
+    };
+    let compiled = compile_template(&state.precompiled, &id, &values, extras)?;
+    Ok(Json(PreviewResponse {
+        template_id: id,
+        seed: q.seed,
+        sample_variables,
+        compiled_document: compiled.compiled_document,
+        annotations: compiled.annotations,
+    }))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{warmup::Precompiled, BUILTIN_TEMPLATES};
+
+    #[test]
+    fn every_builtin_variable_gets_a_typed_sample() {
+        let today = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let pre = Precompiled::default();
+        for id in BUILTIN_TEMPLATES {
+            let vars = get_required_variables(id);
+            let s = samples(&vars, 0, today);
+            assert!(
+                s.values().all(|v| !v.value.starts_with("[sample")),
+                "{id} has an untyped variable"
+            );
+            let values = s
+                .iter()
+                .map(|(k, v)| (k.clone(), v.value.clone()))
+                .collect();
+            let compiled = compile_template(&pre, id, &values, CompileExtras::default()).unwrap();
+            assert!(compiled.missing_variables.is_empty());
+        }
+    }
+
+    #[test]
+    fn parties_differ_and_dates_stay_ordered() {
+        let today = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let vars = ["party_a", "party_b", "current_end_date", "new_end_date"].map(String::from);
+        let s = samples(&vars, 3, today);
+        assert_ne!(s["party_a"].value, s["party_b"].value);
+        assert!(s["current_end_date"].value < s["new_end_date"].value);
+        assert_eq!(kind_of("effective_date"), SampleKind::Date);
+        assert_eq!(kind_of("license_fee"), SampleKind::Amount);
+        assert_eq!(kind_of("software_name"), SampleKind::Text);
+    }
+}
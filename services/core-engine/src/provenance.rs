@@ -0,0 +1,311 @@
+//! Where each paragraph of a compiled document came from: the template and
+//! which saved version of it, the macros and library clauses whose text it
+//! holds, and the variable values filled into it. Every compile returns
+//! this manifest, so when a sentence of a signed contract is disputed it
+//! can be traced back to the content that produced it. Paragraphs and the
+//! document carry SHA-256 digests, which tie the manifest to the exact text
+//! it describes.
+
+use std::collections::{BTreeMap, HashMap};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    clause_library::EmbeddedClause,
+    custom_templates::Registered,
+    style::{self, StyleDeviation},
+    warmup::{Source, Traced},
+};
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct TemplateSource {
+    pub template_id: String,
+    pub built_in: bool,
+    /// Saved version of a custom template.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    /// Tenant that saved a custom template.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// The inherited template a custom template replaces.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overrides: Option<String>,
+    pub body_sha256: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct MacroSource {
+    pub name: String,
+    pub version: u32,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ParagraphSource {
+    pub paragraph: usize,
+    /// Byte range of the paragraph in `compiled_document`.
+    pub start: usize,
+    pub end: usize,
+    pub sha256: String,
+    /// Macros whose text is in the paragraph.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub macros: Vec<MacroSource>,
+    /// Library clauses whose text is in the paragraph.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub library_clauses: Vec<EmbeddedClause>,
+    /// Variables filled into the paragraph, with their values.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ProvenanceManifest {
+    pub template: TemplateSource,
+    pub document_sha256: String,
+    pub paragraphs: Vec<ParagraphSource>,
+}
+
+/// What a compile rendered: the template, and the versions of the macros
+/// and library clauses it could embed.
+pub struct Origin {
+    pub template: TemplateSource,
+    /// Latest version of each macro, by name.
+    pub macros: BTreeMap<String, u32>,
+    /// One per clause library reference of the template, in order.
+    pub library_clauses: Vec<EmbeddedClause>,
+}
+
+impl Origin {
+    pub fn built_in(template_id: &str, body: &str) -> Self {
+        Self {
+            template: TemplateSource {
+                template_id: template_id.to_string(),
+                built_in: true,
+                version: None,
+                tenant: None,
+                overrides: None,
+                body_sha256: sha256(body),
+            },
+            macros: BTreeMap::new(),
+            library_clauses: Vec::new(),
+        }
+    }
+
+    pub fn custom(
+        template_id: &str,
+        custom: &Registered,
+        macros: BTreeMap<String, u32>,
+        library_clauses: Vec<EmbeddedClause>,
+    ) -> Self {
+        let template = &custom.template;
+        Self {
+            template: TemplateSource {
+                template_id: template_id.to_string(),
+                built_in: false,
+                version: Some(template.version),
+                tenant: Some(template.tenant.clone()),
+                overrides: template.overrides.clone(),
+                body_sha256: sha256(&template.body),
+            },
+            macros,
+            library_clauses,
+        }
+    }
+}
+
+// ── Manifest ──────────────────────────────────────────────────────────────────
+
+fn sha256(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text))
+}
+
+/// The manifest of `traced`, rendered from `origin` with `variables`.
+pub fn manifest(
+    origin: &Origin,
+    traced: &Traced,
+    variables: &HashMap<String, String>,
+) -> ProvenanceManifest {
+    let text = &traced.rendered.text;
+    let paragraphs = traced
+        .rendered
+        .paragraphs
+        .iter()
+        .zip(&traced.sources)
+        .enumerate()
+        .map(|(paragraph, (range, sources))| {
+            let mut source = ParagraphSource {
+                paragraph,
+                start: range.start,
+                end: range.end,
+                sha256: sha256(&text[range.clone()]),
+                macros: Vec::new(),
+                library_clauses: Vec::new(),
+                variables: BTreeMap::new(),
+            };
+            for found in sources {
+                match found {
+                    Source::Macro(name) => {
+                        if let Some(&version) = origin.macros.get(name) {
+                            source.macros.push(MacroSource {
+                                name: name.clone(),
+                                version,
+                            });
+                        }
+                    }
+                    Source::Clause(i) => {
+                        source
+                            .library_clauses
+                            .extend(origin.library_clauses.get(*i).cloned());
+                    }
+                    Source::Variable(name) => {
+                        if let Some(value) = variables.get(name) {
+                            source.variables.insert(name.clone(), value.clone());
+                        }
+                    }
+                }
+            }
+            source
+        })
+        .collect();
+    ProvenanceManifest {
+        template: origin.template.clone(),
+        document_sha256: sha256(text),
+        paragraphs,
+    }
+}
+
+impl ProvenanceManifest {
+    /// Moves the paragraphs to where a drafting style's `changes` left them
+    /// in `document`, and digests the restyled text. Fails rather than
+    /// digest the wrong text when a paragraph no longer maps onto it.
+    pub fn restyle(&mut self, changes: &[StyleDeviation], document: &str) -> Result<(), String> {
+        for p in &mut self.paragraphs {
+            p.start = style::remap(changes, p.start);
+            p.end = style::remap(changes, p.end);
+            let text = document.get(p.start..p.end).ok_or_else(|| {
+                format!(
+                    "paragraph {} maps to {}..{}, outside the restyled document",
+                    p.paragraph, p.start, p.end
+                )
+            })?;
+            p.sha256 = sha256(text);
+        }
+        self.document_sha256 = sha256(document);
+        Ok(())
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warmup::{Fragments, ParsedTemplate};
+    use serde_json::Map;
+
+    #[test]
+    fn paragraphs_name_their_macros_clauses_and_variables() {
+        let t = ParsedTemplate::parse(
+            "{{party}} engages {{vendor}}.\n\n{{> macro/law state=state}}\n{{clause:cap}}",
+        )
+        .unwrap();
+        let fragments = Fragments {
+            macros: BTreeMap::from([(
+                "law".to_string(),
+                "Governed by {{state}} law.\n".to_string(),
+            )]),
+            clauses: vec!["Liability is capped.\n\nNo cap for {{party}} fraud.".to_string()],
+        };
+        let variables = HashMap::from([
+            ("party".to_string(), "Acme".to_string()),
+            ("vendor".to_string(), "Beta".to_string()),
+            ("state".to_string(), "Ohio".to_string()),
+        ]);
+        let traced = t
+            .render_traced(&variables, &Map::new(), &fragments)
+            .unwrap();
+        let origin = Origin {
+            template: Origin::built_in("msa", "body").template,
+            macros: BTreeMap::from([("law".to_string(), 3)]),
+            library_clauses: vec![EmbeddedClause {
+                reference: "clause:cap".to_string(),
+                clause_id: "c1".to_string(),
+                version: 2,
+            }],
+        };
+        let m = manifest(&origin, &traced, &variables);
+        assert_eq!(m.paragraphs.len(), 3);
+        assert_eq!(
+            m.paragraphs[0].variables.keys().collect::<Vec<_>>(),
+            ["party", "vendor"]
+        );
+        assert!(m.paragraphs[0].macros.is_empty());
+        let law = &m.paragraphs[1];
+        assert_eq!(
+            law.macros,
+            [MacroSource {
+                name: "law".to_string(),
+                version: 3
+            }]
+        );
+        assert_eq!(law.variables["state"], "Ohio");
+        assert_eq!(law.library_clauses[0].clause_id, "c1");
+        let fraud = &m.paragraphs[2];
+        assert_eq!(
+            &traced.rendered.text[fraud.start..fraud.end],
+            "No cap for Acme fraud."
+        );
+        assert_eq!(fraud.library_clauses[0].version, 2);
+        assert_eq!(fraud.variables.keys().collect::<Vec<_>>(), ["party"]);
+        assert_eq!(fraud.sha256, sha256("No cap for Acme fraud."));
+    }
+
+    #[test]
+    fn values_holding_trace_marks_are_matched_by_content() {
+        let t =
+            ParsedTemplate::parse("{{party}} engages {{vendor}}.\n\n{{vendor}} agrees.").unwrap();
+        let variables = HashMap::from([
+            ("party".to_string(), "Acme\u{E001}".to_string()),
+            (
+                "vendor".to_string(),
+                "\u{E000}v:party\u{E002}Beta".to_string(),
+            ),
+        ]);
+        let traced = t
+            .render_traced(&variables, &Map::new(), &Fragments::default())
+            .unwrap();
+        assert_eq!(
+            traced.rendered.text,
+            t.render(&variables).unwrap().text,
+            "the marks in the values are left as they are"
+        );
+        let m = manifest(&Origin::built_in("msa", "body"), &traced, &variables);
+        assert_eq!(
+            m.paragraphs[0].variables.keys().collect::<Vec<_>>(),
+            ["party", "vendor"]
+        );
+        assert_eq!(
+            m.paragraphs[1].variables.keys().collect::<Vec<_>>(),
+            ["vendor"]
+        );
+    }
+
+    #[test]
+    fn a_paragraph_the_style_moved_off_the_document_is_an_error() {
+        let t = ParsedTemplate::parse("{{party}} engages us.").unwrap();
+        let variables = HashMap::from([("party".to_string(), "Acme".to_string())]);
+        let traced = t
+            .render_traced(&variables, &Map::new(), &Fragments::default())
+            .unwrap();
+        let mut m = manifest(&Origin::built_in("msa", "body"), &traced, &variables);
+        assert_eq!(
+            m.restyle(&[], "Acme"),
+            Err("paragraph 0 maps to 0..16, outside the restyled document".to_string())
+        );
+        assert_eq!(m.restyle(&[], "Acme engages us."), Ok(()));
+        assert_eq!(m.paragraphs[0].sha256, sha256("Acme engages us."));
+    }
+}
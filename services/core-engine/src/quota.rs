@@ -0,0 +1,382 @@
+//! Hard limits on one analysis, so a pathological document degrades its own
+//! result instead of starving every other request on the instance. Four
+//! limits apply: wall time since the analysis started, tokens processed
+//! (longer documents are truncated), a budget of pattern-matching steps
+//! that each stage is charged for up front, and peak memory per stage.
+//! A stage that would break a limit is skipped, making the response
+//! partial; one that used more memory than allowed keeps its findings but
+//! stops the stages after it. The response says which limit was hit.
+//!
+//! Memory is counted by the global allocator per thread, which is exact
+//! here because the pipeline runs synchronously on one thread.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct QuotaConfig {
+    /// Optional stages stop starting once the analysis has run this long.
+    pub max_wall: Duration,
+    /// Whitespace-separated tokens analyzed; the rest is truncated.
+    pub max_tokens: usize,
+    /// Pattern-matching steps all stages may spend together.
+    pub match_budget: u64,
+    /// Peak bytes one stage may hold beyond what was live when it started.
+    pub stage_memory_bytes: u64,
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self {
+            max_wall: Duration::from_secs(20),
+            max_tokens: 200_000,
+            match_budget: 500_000_000,
+            stage_memory_bytes: 256 * 1024 * 1024,
+        }
+    }
+}
+
+fn env_number<T: std::str::FromStr + PartialOrd + Default>(key: &str, fallback: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n| *n > T::default())
+        .unwrap_or(fallback)
+}
+
+impl QuotaConfig {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        Self {
+            max_wall: Duration::from_millis(env_number(
+                "LEGAL_ANALYSIS_MAX_WALL_MS",
+                u64::try_from(d.max_wall.as_millis()).unwrap_or(u64::MAX),
+            )),
+            max_tokens: env_number("LEGAL_ANALYSIS_MAX_TOKENS", d.max_tokens),
+            match_budget: env_number("LEGAL_ANALYSIS_MATCH_BUDGET", d.match_budget),
+            stage_memory_bytes: env_number(
+                "LEGAL_ANALYSIS_STAGE_MEMORY_MB",
+                d.stage_memory_bytes >> 20,
+            ) << 20,
+        }
+    }
+}
+
+// ── Memory accounting ─────────────────────────────────────────────────────────
+
+thread_local! {
+    /// Bytes allocated minus bytes freed on this thread; negative when it
+    /// frees memory other threads allocated.
+    static LIVE: Cell<i64> = const { Cell::new(0) };
+    static PEAK: Cell<i64> = const { Cell::new(0) };
+}
+
+fn grow(bytes: usize) {
+    let _ = LIVE.try_with(|live| {
+        let now = live
+            .get()
+            .saturating_add(i64::try_from(bytes).unwrap_or(i64::MAX));
+        live.set(now);
+        let _ = PEAK.try_with(|peak| peak.set(peak.get().max(now)));
+    });
+}
+
+fn shrink(bytes: usize) {
+    let _ = LIVE.try_with(|live| {
+        live.set(
+            live.get()
+                .saturating_sub(i64::try_from(bytes).unwrap_or(i64::MAX)),
+        );
+    });
+}
+
+/// The system allocator, counting live bytes per thread.
+pub struct CountingAllocator;
+
+// SAFETY: every call is forwarded to `System` unchanged; the counters are
+// const-initialized thread locals, so updating them never allocates.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            shrink(layout.size());
+            grow(new_size);
+        }
+        new
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Runs `f` and returns its result with the most bytes it held at once on
+/// this thread beyond what was live before.
+pub fn peak_memory<T>(f: impl FnOnce() -> T) -> (T, u64) {
+    let before = LIVE.with(Cell::get);
+    PEAK.with(|p| p.set(before));
+    let out = f();
+    let peak = PEAK.with(Cell::get);
+    (out, u64::try_from(peak - before).unwrap_or(0))
+}
+
+/// Counts `bytes` that worker threads held at once on this thread's
+/// behalf toward its peak, so a stage that fans out is measured whole.
+pub fn held_by_workers(bytes: u64) {
+    let bytes = usize::try_from(bytes).unwrap_or(usize::MAX);
+    grow(bytes);
+    shrink(bytes);
+}
+
+// ── Guard ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Limit {
+    WallTime,
+    Tokens,
+    MatchBudget,
+    StageMemory,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct LimitExceeded {
+    pub limit: Limit,
+    /// The stage that was skipped, truncated or went over.
+    pub stage: String,
+    /// Milliseconds, tokens, match steps or bytes, by limit.
+    pub used: u64,
+    pub allowed: u64,
+}
+
+/// Present on analyses that hit a limit.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ResourceReport {
+    pub exceeded: Vec<LimitExceeded>,
+    /// Match steps charged to the stages that ran.
+    pub match_steps: u64,
+    /// Peak bytes held by each stage that ran.
+    pub stage_memory_bytes: BTreeMap<String, u64>,
+}
+
+/// Tracks one analysis against its limits.
+pub struct Guard<'a> {
+    config: &'a QuotaConfig,
+    started: Instant,
+    match_steps: u64,
+    stage_memory: BTreeMap<&'static str, u64>,
+    exceeded: Vec<LimitExceeded>,
+}
+
+impl<'a> Guard<'a> {
+    pub fn start(config: &'a QuotaConfig) -> Self {
+        Self {
+            config,
+            started: Instant::now(),
+            match_steps: 0,
+            stage_memory: BTreeMap::new(),
+            exceeded: Vec::new(),
+        }
+    }
+
+    pub fn max_tokens(&self) -> usize {
+        self.config.max_tokens
+    }
+
+    /// Records that a document of `tokens` was cut to the token limit.
+    pub fn truncated(&mut self, tokens: usize) {
+        if tokens > self.config.max_tokens {
+            self.exceed(
+                Limit::Tokens,
+                "segmentation",
+                tokens as u64,
+                self.config.max_tokens as u64,
+            );
+        }
+    }
+
+    fn exceed(&mut self, limit: Limit, stage: &'static str, used: u64, allowed: u64) {
+        warn!(
+            ?limit,
+            stage, used, allowed, "analysis resource limit exceeded"
+        );
+        self.exceeded.push(LimitExceeded {
+            limit,
+            stage: stage.to_string(),
+            used,
+            allowed,
+        });
+    }
+
+    /// Runs a stage the analysis cannot do without, charging its `steps`
+    /// and measuring its memory, whatever the limits say.
+    pub fn required<T>(&mut self, stage: &'static str, steps: u64, f: impl FnOnce() -> T) -> T {
+        self.match_steps = self.match_steps.saturating_add(steps);
+        self.measure(stage, f)
+    }
+
+    /// Runs an optional stage that costs `steps` match steps, or skips it
+    /// (`None`) when the wall time or match budget is spent, or an earlier
+    /// stage went over its memory limit.
+    pub fn optional<T>(
+        &mut self,
+        stage: &'static str,
+        steps: u64,
+        f: impl FnOnce() -> T,
+    ) -> Option<T> {
+        if self.exceeded.iter().any(|e| e.limit == Limit::StageMemory) {
+            return None;
+        }
+        let elapsed = self.started.elapsed();
+        if elapsed >= self.config.max_wall {
+            let ms = |d: Duration| u64::try_from(d.as_millis()).unwrap_or(u64::MAX);
+            self.exceed(
+                Limit::WallTime,
+                stage,
+                ms(elapsed),
+                ms(self.config.max_wall),
+            );
+            return None;
+        }
+        let spent = self.match_steps.saturating_add(steps);
+        if spent > self.config.match_budget {
+            self.exceed(Limit::MatchBudget, stage, spent, self.config.match_budget);
+            return None;
+        }
+        self.match_steps = spent;
+        Some(self.measure(stage, f))
+    }
+
+    fn measure<T>(&mut self, stage: &'static str, f: impl FnOnce() -> T) -> T {
+        let (out, bytes) = peak_memory(f);
+        debug!(stage, bytes, "analysis stage memory");
+        self.stage_memory.insert(stage, bytes);
+        if bytes > self.config.stage_memory_bytes {
+            self.exceed(
+                Limit::StageMemory,
+                stage,
+                bytes,
+                self.config.stage_memory_bytes,
+            );
+        }
+        out
+    }
+
+    /// The report, if any limit was hit.
+    pub fn report(self) -> Option<ResourceReport> {
+        (!self.exceeded.is_empty()).then(|| ResourceReport {
+            exceeded: self.exceeded,
+            match_steps: self.match_steps,
+            stage_memory_bytes: self
+                .stage_memory
+                .into_iter()
+                .map(|(stage, bytes)| (stage.to_string(), bytes))
+                .collect(),
+        })
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peak_memory_counts_what_a_stage_holds_at_once() {
+        let (len, peak) = peak_memory(|| {
+            let big = vec![0u8; 1 << 20];
+            drop(big);
+            vec![1u8; 1 << 10].len()
+        });
+        assert_eq!(len, 1 << 10);
+        assert!(peak >= 1 << 20, "{peak}");
+    }
+
+    #[test]
+    fn match_budget_skips_stages_that_would_overrun_it() {
+        let config = QuotaConfig {
+            match_budget: 100,
+            ..QuotaConfig::default()
+        };
+        let mut guard = Guard::start(&config);
+        assert_eq!(guard.required("classification", 60, || 1), 1);
+        assert_eq!(guard.optional("entity", 30, || 2), Some(2));
+        assert_eq!(guard.optional("conflicts", 20, || 3), None);
+        let report = guard.report().unwrap();
+        assert_eq!(
+            report.exceeded,
+            [LimitExceeded {
+                limit: Limit::MatchBudget,
+                stage: "conflicts".to_string(),
+                used: 110,
+                allowed: 100,
+            }]
+        );
+        assert_eq!(report.match_steps, 90);
+        assert!(report.stage_memory_bytes.contains_key("entity"));
+    }
+
+    #[test]
+    fn a_stage_over_its_memory_stops_the_ones_after_it() {
+        let config = QuotaConfig {
+            stage_memory_bytes: 1 << 16,
+            ..QuotaConfig::default()
+        };
+        let mut guard = Guard::start(&config);
+        let kept = guard.optional("export_control", 1, || vec![0u8; 1 << 20].len());
+        assert_eq!(kept, Some(1 << 20));
+        assert_eq!(guard.optional("entity", 1, || ()), None);
+        let report = guard.report().unwrap();
+        assert_eq!(report.exceeded[0].limit, Limit::StageMemory);
+        assert_eq!(report.exceeded[0].stage, "export_control");
+    }
+
+    #[test]
+    fn spent_wall_time_skips_optional_stages_only() {
+        let config = QuotaConfig {
+            max_wall: Duration::ZERO,
+            ..QuotaConfig::default()
+        };
+        let mut guard = Guard::start(&config);
+        assert_eq!(guard.required("classification", 1, || 1), 1);
+        assert_eq!(guard.optional("regulatory", 1, || 2), None);
+        assert_eq!(guard.report().unwrap().exceeded[0].limit, Limit::WallTime);
+
+        let unlimited = QuotaConfig::default();
+        let mut guard = Guard::start(&unlimited);
+        guard.truncated(10);
+        assert_eq!(guard.optional("entity", 1, || 2), Some(2));
+        assert!(guard.report().is_none());
+    }
+}
@@ -0,0 +1,408 @@
+//! Per-client rate limiting, so one misbehaving client cannot saturate the
+//! engine for everyone else. Each client gets a token bucket: it holds up
+//! to `burst` requests and refills at the client's rate per minute, and a
+//! request that finds it empty gets `429` with `Retry-After`. A client is
+//! its API key when keys are on, its address otherwise. Keys may carry a
+//! rate of their own in the auth file. Health probes and metrics are never
+//! limited, and neither is asking for one's own usage.
+
+use std::{
+    net::SocketAddr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+    Extension,
+};
+use dashmap::DashMap;
+use serde::Serialize;
+use serde_json::json;
+use tracing::{debug, warn};
+
+use crate::{auth::Principal, AppState};
+
+/// Never limited: probes must answer while the engine is busy.
+const UNLIMITED_PATHS: [&str; 5] = [
+    "/health",
+    "/health/warm",
+    "/metrics",
+    "/api/v1/usage",
+    "/api/v1/legal/usage",
+];
+/// Clients tracked before idle ones are forgotten.
+const MAX_CLIENTS: usize = 10_000;
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitConfig {
+    /// Requests per minute a client may sustain; `None` counts requests
+    /// without limiting them.
+    pub per_minute: Option<u32>,
+    /// Requests a client may send at once after being idle.
+    pub burst: u32,
+    /// Identify clients without a key by the first `X-Forwarded-For`
+    /// address, for deployments behind a proxy that sets it.
+    pub trust_forwarded: bool,
+}
+
+impl RateLimitConfig {
+    pub fn from_env() -> Self {
+        let number = |key: &str, fallback: u32| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(fallback)
+        };
+        Self {
+            per_minute: Some(number("LEGAL_RATE_LIMIT_PER_MINUTE", 600)).filter(|n| *n > 0),
+            burst: number("LEGAL_RATE_LIMIT_BURST", 60).max(1),
+            trust_forwarded: std::env::var("LEGAL_RATE_LIMIT_TRUST_FORWARDED")
+                .is_ok_and(|v| v == "true" || v == "1"),
+        }
+    }
+}
+
+// ── Buckets ───────────────────────────────────────────────────────────────────
+
+/// Who a request counts against and what it may spend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Client {
+    /// `key:<id>` or `ip:<address>`.
+    pub id: String,
+    /// `None` when the client is not limited.
+    pub per_minute: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(capacity: f64, now: Instant) -> Self {
+        Self {
+            tokens: capacity,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant, per_sec: f64, capacity: f64) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_sec).min(capacity);
+        self.updated = now;
+    }
+
+    /// Spends one token, or says how long until there is one.
+    fn take(&mut self, now: Instant, per_sec: f64, capacity: f64) -> Result<(), Duration> {
+        self.refill(now, per_sec, capacity);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / per_sec))
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ClientState {
+    bucket: Bucket,
+    /// Bucket size and refill rate per second; `None` when not limited.
+    shape: Option<(f64, f64)>,
+    requests: u64,
+    throttled: u64,
+}
+
+/// What `GET /api/v1/usage` reports.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Usage {
+    pub client: String,
+    pub limited: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_per_minute: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burst: Option<u32>,
+    /// Requests that could be sent right now.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<u32>,
+    /// Requests let through and turned away since the client was first seen.
+    pub requests: u64,
+    pub throttled: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    clients: DashMap<String, ClientState>,
+    throttled: AtomicU64,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Bucket size and refill rate per second for `per_minute`.
+    fn shape(&self, per_minute: u32) -> (f64, f64) {
+        let capacity = self.config.burst.min(per_minute).max(1);
+        (f64::from(capacity), f64::from(per_minute) / 60.0)
+    }
+
+    /// The client a request counts against.
+    pub fn client(
+        &self,
+        principal: Option<&Principal>,
+        headers: &HeaderMap,
+        peer: Option<SocketAddr>,
+    ) -> Client {
+        if let Some(p) = principal {
+            return Client {
+                id: format!("key:{}", p.key_id),
+                per_minute: p.rate_limit_per_minute.or(self.config.per_minute),
+            };
+        }
+        let forwarded = self
+            .config
+            .trust_forwarded
+            .then(|| headers.get("x-forwarded-for")?.to_str().ok())
+            .flatten()
+            .and_then(|v| v.split(',').next())
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        let address = forwarded
+            .map(str::to_string)
+            .or_else(|| peer.map(|p| p.ip().to_string()))
+            .unwrap_or_else(|| "unknown".to_string());
+        Client {
+            id: format!("ip:{address}"),
+            per_minute: self.config.per_minute,
+        }
+    }
+
+    /// Counts one request from `client`; `Err` holds how long it must wait.
+    pub fn check(&self, client: &Client, now: Instant) -> Result<(), Duration> {
+        if self.clients.len() >= MAX_CLIENTS && !self.clients.contains_key(&client.id) {
+            self.forget_idle(now);
+        }
+        let shape = client.per_minute.map(|n| self.shape(n));
+        let mut state = self
+            .clients
+            .entry(client.id.clone())
+            .or_insert_with(|| ClientState {
+                bucket: Bucket::full(shape.map_or(0.0, |(capacity, _)| capacity), now),
+                shape,
+                requests: 0,
+                throttled: 0,
+            });
+        let result = match shape {
+            Some((capacity, per_sec)) => state.bucket.take(now, per_sec, capacity),
+            None => Ok(()),
+        };
+        match result {
+            Ok(()) => state.requests += 1,
+            Err(_) => {
+                state.throttled += 1;
+                self.throttled.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        result
+    }
+
+    /// Drops clients whose buckets have refilled, which lose nothing but
+    /// their counts by it.
+    fn forget_idle(&self, now: Instant) {
+        let before = self.clients.len();
+        self.clients.retain(|_, c| {
+            c.shape.is_some_and(|(capacity, per_sec)| {
+                let mut bucket = c.bucket;
+                bucket.refill(now, per_sec, capacity);
+                bucket.tokens < capacity
+            })
+        });
+        debug!(
+            forgotten = before - self.clients.len(),
+            "idle rate limit buckets dropped"
+        );
+    }
+
+    pub fn usage(&self, client: &Client, now: Instant) -> Usage {
+        let shape = client.per_minute.map(|n| self.shape(n));
+        let state = self.clients.get(&client.id);
+        let remaining = shape.map(|(capacity, per_sec)| {
+            let mut bucket = state
+                .as_ref()
+                .map_or(Bucket::full(capacity, now), |s| s.bucket);
+            bucket.refill(now, per_sec, capacity);
+            bucket.tokens.floor() as u32
+        });
+        Usage {
+            client: client.id.clone(),
+            limited: client.per_minute.is_some(),
+            limit_per_minute: client.per_minute,
+            burst: shape.map(|(capacity, _)| capacity as u32),
+            remaining,
+            requests: state.as_ref().map_or(0, |s| s.requests),
+            throttled: state.as_ref().map_or(0, |s| s.throttled),
+        }
+    }
+
+    /// Requests turned away since startup, for `/metrics`.
+    pub fn throttled(&self) -> u64 {
+        self.throttled.load(Ordering::Relaxed)
+    }
+}
+
+// ── Middleware ────────────────────────────────────────────────────────────────
+
+fn too_many(wait: Duration) -> Response {
+    let secs = wait.as_secs_f64().ceil().max(1.0) as u64;
+    let mut resp = (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(json!({ "error": "rate limit exceeded", "retry_after_secs": secs })),
+    )
+        .into_response();
+    resp.headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+    resp
+}
+
+/// Runs after authentication, so the key is known, and before the route
+/// deadline, so waiting here is not charged to it.
+pub async fn limit(State(state): State<AppState>, mut req: Request, next: Next) -> Response {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|c| c.0);
+    let client = state
+        .rate_limit
+        .client(req.extensions().get::<Principal>(), req.headers(), peer);
+    let path = req.uri().path();
+    if !UNLIMITED_PATHS.contains(&path) {
+        if let Err(wait) = state.rate_limit.check(&client, Instant::now()) {
+            warn!(client = %client.id, path = %path, "rate limit exceeded");
+            return too_many(wait);
+        }
+    }
+    req.extensions_mut().insert(client);
+    next.run(req).await
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn usage(
+    State(state): State<AppState>,
+    Extension(client): Extension<Client>,
+) -> Json<Usage> {
+    Json(state.rate_limit.usage(&client, Instant::now()))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(per_minute: u32, burst: u32) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            per_minute: Some(per_minute),
+            burst,
+            trust_forwarded: false,
+        })
+    }
+
+    fn client(id: &str, per_minute: Option<u32>) -> Client {
+        Client {
+            id: id.to_string(),
+            per_minute,
+        }
+    }
+
+    #[test]
+    fn buckets_allow_a_burst_then_refill_at_the_rate() {
+        let limiter = limiter(60, 3);
+        let acme = client("key:acme", Some(60));
+        let t0 = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.check(&acme, t0).is_ok());
+        }
+        let wait = limiter.check(&acme, t0).unwrap_err();
+        assert!((wait.as_secs_f64() - 1.0).abs() < 1e-6, "{wait:?}");
+        // Another client has a bucket of its own.
+        assert!(limiter.check(&client("key:globex", Some(60)), t0).is_ok());
+
+        let t1 = t0 + Duration::from_millis(1_500);
+        assert!(limiter.check(&acme, t1).is_ok());
+        assert!(limiter.check(&acme, t1).is_err());
+        let usage = limiter.usage(&acme, t1 + Duration::from_secs(60));
+        assert_eq!(usage.remaining, Some(3));
+        assert_eq!((usage.requests, usage.throttled), (4, 2));
+        assert_eq!(limiter.throttled(), 2);
+    }
+
+    #[test]
+    fn unlimited_clients_are_counted_only() {
+        let limiter = RateLimiter::default();
+        let anyone = client("ip:10.0.0.1", None);
+        let now = Instant::now();
+        for _ in 0..1_000 {
+            assert!(limiter.check(&anyone, now).is_ok());
+        }
+        let usage = limiter.usage(&anyone, now);
+        assert!(!usage.limited);
+        assert_eq!(usage.remaining, None);
+        assert_eq!(usage.requests, 1_000);
+    }
+
+    #[test]
+    fn keys_win_over_addresses_and_forwarded_is_opt_in() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("203.0.113.7, 10.0.0.2"),
+        );
+        let peer = Some(SocketAddr::from(([10, 0, 0, 2], 40_000)));
+        let principal = Principal {
+            key_id: "acme-ci".to_string(),
+            admin: false,
+            rate_limit_per_minute: Some(5),
+        };
+
+        let direct = limiter(600, 60);
+        assert_eq!(
+            direct.client(Some(&principal), &headers, peer),
+            client("key:acme-ci", Some(5))
+        );
+        assert_eq!(direct.client(None, &headers, peer).id, "ip:10.0.0.2");
+        assert_eq!(direct.client(None, &headers, None).id, "ip:unknown");
+
+        let proxied = RateLimiter::new(RateLimitConfig {
+            trust_forwarded: true,
+            ..limiter(600, 60).config
+        });
+        assert_eq!(proxied.client(None, &headers, peer).id, "ip:203.0.113.7");
+    }
+
+    #[test]
+    fn idle_clients_are_forgotten_when_the_table_is_full() {
+        let limiter = limiter(60, 2);
+        let t0 = Instant::now();
+        for i in 0..MAX_CLIENTS {
+            limiter
+                .check(&client(&format!("ip:{i}"), Some(60)), t0)
+                .unwrap();
+        }
+        let later = t0 + Duration::from_secs(5);
+        limiter.check(&client("ip:new", Some(60)), later).unwrap();
+        assert_eq!(limiter.clients.len(), 1);
+    }
+}
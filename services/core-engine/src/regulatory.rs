@@ -0,0 +1,513 @@
+use std::collections::BTreeSet;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::NaiveDate;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::{
+    auth,
+    corpus::StoredDocument,
+    evidence::{self, EvidenceOptions},
+    lexicon::Lexicon,
+    notifier, patterns, AppState, Issue,
+};
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+/// Contract language that stopped being acceptable on `effective_date`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeprecationRule {
+    pub id: String,
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Regular expressions, run in the [`patterns`] sandbox.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub regexes: Vec<String>,
+    pub description: String,
+    pub effective_date: NaiveDate,
+    pub remediation: String,
+    pub reference: Option<String>,
+}
+
+impl DeprecationRule {
+    /// Why the rule cannot be saved, if it cannot.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("rule needs an id".to_string());
+        }
+        if self.regexes.is_empty() && self.patterns.iter().all(|p| p.trim().is_empty()) {
+            return Err("rule needs a pattern or a regex".to_string());
+        }
+        patterns::compile_all(&self.regexes)
+            .map(drop)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RulesResponse {
+    pub rules: Vec<DeprecationRule>,
+    pub count: usize,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ScanRequest {
+    /// Evaluate rules as of this date (defaults to today).
+    pub as_of: Option<NaiveDate>,
+    /// Restrict the sweep to these document IDs.
+    pub document_ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub analysis_options: EvidenceOptions,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RemediationItem {
+    pub document_id: String,
+    pub rule_id: String,
+    pub matched_text: String,
+    pub offset: usize,
+    pub excerpt: String,
+    pub effective_date: NaiveDate,
+    pub description: String,
+    pub remediation: String,
+    pub reference: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScanResponse {
+    pub as_of: NaiveDate,
+    pub rules_applied: usize,
+    pub documents_scanned: usize,
+    pub documents_requiring_repapering: usize,
+    pub remediation: Vec<RemediationItem>,
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+pub struct RegulatoryStore {
+    rules: DashMap<String, DeprecationRule>,
+}
+
+impl Default for RegulatoryStore {
+    fn default() -> Self {
+        let store = Self {
+            rules: DashMap::new(),
+        };
+        for rule in default_rules() {
+            store.rules.insert(rule.id.clone(), rule);
+        }
+        store
+    }
+}
+
+impl RegulatoryStore {
+    pub fn list(&self) -> Vec<DeprecationRule> {
+        let mut rules: Vec<DeprecationRule> =
+            self.rules.iter().map(|r| r.value().clone()).collect();
+        rules.sort_by(|a, b| a.id.cmp(&b.id));
+        rules
+    }
+
+    pub fn upsert(&self, rule: DeprecationRule) {
+        self.rules.insert(rule.id.clone(), rule);
+    }
+
+    pub fn remove(&self, id: &str) -> bool {
+        self.rules.remove(id).is_some()
+    }
+
+    /// Swaps in a complete rule set, dropping rules not in `rules`.
+    pub fn replace(&self, rules: Vec<DeprecationRule>) {
+        self.rules.retain(|id, _| rules.iter().any(|r| &r.id == id));
+        for rule in rules {
+            self.upsert(rule);
+        }
+    }
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).expect("valid built-in rule date")
+}
+
+fn default_rules() -> Vec<DeprecationRule> {
+    vec![
+        DeprecationRule {
+            id: "privacy-shield".to_string(),
+            patterns: vec!["privacy shield".to_string()],
+            description: "EU-US Privacy Shield was invalidated by the CJEU (Schrems II).".to_string(),
+            effective_date: date(2020, 7, 16),
+            remediation: "Replace with the 2021 SCCs or EU-US Data Privacy Framework certification.".to_string(),
+            regexes: Vec::new(),
+            reference: Some("CJEU C-311/18".to_string()),
+        },
+        DeprecationRule {
+            id: "safe-harbor".to_string(),
+            patterns: vec!["safe harbor".to_string(), "safe harbour".to_string()],
+            description: "The US-EU Safe Harbor framework was invalidated (Schrems I).".to_string(),
+            effective_date: date(2015, 10, 6),
+            remediation: "Replace with a current transfer mechanism (2021 SCCs or DPF).".to_string(),
+            regexes: Vec::new(),
+            reference: Some("CJEU C-362/14".to_string()),
+        },
+        DeprecationRule {
+            id: "legacy-scc".to_string(),
+            patterns: vec![
+                "2010/87/eu".to_string(),
+                "2004/915/ec".to_string(),
+                "2001/497/ec".to_string(),
+            ],
+            description: "Pre-2021 Standard Contractual Clauses ceased to be valid for existing contracts.".to_string(),
+            effective_date: date(2022, 12, 27),
+            remediation: "Repaper onto the 2021 SCCs (Decision 2021/914) with the appropriate module.".to_string(),
+            regexes: Vec::new(),
+            reference: Some("Commission Implementing Decision (EU) 2021/914".to_string()),
+        },
+        DeprecationRule {
+            id: "libor".to_string(),
+            patterns: vec!["libor".to_string()],
+            description: "USD LIBOR panel publication ceased.".to_string(),
+            effective_date: date(2023, 6, 30),
+            remediation: "Replace LIBOR references with SOFR (or the applicable risk-free rate) and fallback language.".to_string(),
+            regexes: Vec::new(),
+            reference: Some("FCA announcement, 5 March 2021".to_string()),
+        },
+    ]
+}
+
+// ── Scan ──────────────────────────────────────────────────────────────────────
+
+/// Matches are ASCII case-insensitive so byte offsets stay valid in the
+/// original text. A tenant lexicon widens rule patterns with its synonyms and
+/// matches them through its stopword-free view of each document; regexes
+/// match the text as written.
+pub fn scan_documents(
+    docs: &[StoredDocument],
+    rules: &[DeprecationRule],
+    as_of: NaiveDate,
+    context_chars: usize,
+    lexicon: Option<&Lexicon>,
+) -> Vec<RemediationItem> {
+    let active: Vec<(&DeprecationRule, Vec<regex::Regex>)> = rules
+        .iter()
+        .filter(|r| r.effective_date <= as_of)
+        .map(|r| {
+            // Saved rules were validated; one that no longer compiles is
+            // matched on its literal patterns alone.
+            let regexes = patterns::compile_all(&r.regexes).unwrap_or_else(|e| {
+                warn!(rule_id = %r.id, error = %e, "deprecation rule regex skipped");
+                Vec::new()
+            });
+            (r, regexes)
+        })
+        .collect();
+    let mut items = Vec::new();
+    for doc in docs {
+        let view = lexicon.map(|l| l.view(&doc.text));
+        let lower = view
+            .as_ref()
+            .map_or(doc.text.as_str(), |v| v.text.as_str())
+            .to_ascii_lowercase();
+        for (rule, regexes) in &active {
+            let mut seen = BTreeSet::new();
+            let mut spans = Vec::new();
+            for pattern in &rule.patterns {
+                let needles = lexicon
+                    .map_or_else(|| vec![pattern.to_ascii_lowercase()], |l| l.expand(pattern));
+                for needle in needles.iter().filter(|n| !n.is_empty()) {
+                    spans.extend(lower.match_indices(needle.as_str()).map(|(at, _)| {
+                        view.as_ref()
+                            .map_or((at, at + needle.len()), |v| v.span(at, at + needle.len()))
+                    }));
+                }
+            }
+            for regex in regexes {
+                spans.extend(
+                    regex
+                        .find_iter(&doc.text)
+                        .filter(|m| !m.is_empty())
+                        .map(|m| (m.start(), m.end())),
+                );
+            }
+            for (offset, end) in spans {
+                if !seen.insert(offset) {
+                    continue;
+                }
+                items.push(RemediationItem {
+                    document_id: doc.id.clone(),
+                    rule_id: rule.id.clone(),
+                    matched_text: doc.text[offset..end].to_string(),
+                    offset,
+                    excerpt: evidence::excerpt(&doc.text, offset, end, context_chars),
+                    effective_date: rule.effective_date,
+                    description: rule.description.clone(),
+                    remediation: rule.remediation.clone(),
+                    reference: rule.reference.clone(),
+                });
+            }
+        }
+    }
+    items.sort_by(|a, b| (&a.document_id, a.offset).cmp(&(&b.document_id, b.offset)));
+    items
+}
+
+/// Deprecated-language hits in one document, rendered as analysis issues;
+/// `next_id` continues the caller's numbering.
+pub fn issues(
+    doc: &StoredDocument,
+    rules: &[DeprecationRule],
+    as_of: NaiveDate,
+    next_id: usize,
+    context_chars: usize,
+    lexicon: Option<&Lexicon>,
+) -> Vec<Issue> {
+    scan_documents(
+        std::slice::from_ref(doc),
+        rules,
+        as_of,
+        context_chars,
+        lexicon,
+    )
+    .into_iter()
+    .enumerate()
+    .map(|(i, item)| {
+        let position = evidence::position(
+            &doc.text,
+            item.offset,
+            item.offset + item.matched_text.len(),
+        );
+        Issue {
+            id: format!("issue-{:03}", next_id + i),
+            description: format!("{} {}", item.description, item.remediation),
+            severity: "high".to_string(),
+            location: position.label(),
+            confidence: 0.9,
+            review_status: "auto".to_string(),
+            provenance: None,
+            excerpt: Some(item.excerpt),
+            position: Some(position),
+        }
+    })
+    .collect()
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn list_rules(State(state): State<AppState>) -> Json<RulesResponse> {
+    let rules = state.regulatory.list();
+    let count = rules.len();
+    Json(RulesResponse { rules, count })
+}
+
+pub async fn upsert_rule(
+    State(state): State<AppState>,
+    Json(rule): Json<DeprecationRule>,
+) -> Result<Json<DeprecationRule>, Response> {
+    if let Err(error) = rule.validate() {
+        info!(rule_id = %rule.id, error = %error, "deprecation rule rejected");
+        return Err((StatusCode::BAD_REQUEST, Json(json!({ "error": error }))).into_response());
+    }
+    info!(rule_id = %rule.id, effective_date = %rule.effective_date, "deprecation rule saved");
+    state.regulatory.upsert(rule.clone());
+    Ok(Json(rule))
+}
+
+pub async fn delete_rule(State(state): State<AppState>, Path(id): Path<String>) -> StatusCode {
+    if state.regulatory.remove(&id) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+pub async fn scan(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Option<Json<ScanRequest>>,
+) -> Result<Json<ScanResponse>, StatusCode> {
+    let req = body.map(|Json(r)| r).unwrap_or_default();
+    let context_chars = req.analysis_options.context_chars()?;
+    let as_of = req.as_of.unwrap_or_else(|| state.clock.today());
+
+    let docs: Vec<StoredDocument> = match &req.document_ids {
+        Some(ids) => ids
+            .iter()
+            .filter_map(|id| auth::owned_document(&state, &headers, id))
+            .collect(),
+        None => auth::tenant_documents(&headers, &state),
+    };
+    let rules = state.regulatory.list();
+    let matcher = state.lexicons.matcher(&notifier::tenant(&headers));
+    let lexicon = matcher.as_ref().and_then(|m| m.lexicon());
+    let remediation = scan_documents(&docs, &rules, as_of, context_chars, lexicon);
+
+    let mut flagged: Vec<&str> = remediation.iter().map(|r| r.document_id.as_str()).collect();
+    flagged.dedup();
+
+    info!(
+        documents_scanned = docs.len(),
+        flagged = flagged.len(),
+        findings = remediation.len(),
+        "regulatory deprecation scan completed"
+    );
+
+    Ok(Json(ScanResponse {
+        as_of,
+        rules_applied: rules.iter().filter(|r| r.effective_date <= as_of).count(),
+        documents_scanned: docs.len(),
+        documents_requiring_repapering: flagged.len(),
+        remediation,
+    }))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evidence::DEFAULT_CONTEXT_CHARS;
+
+    fn doc(id: &str, text: &str) -> StoredDocument {
+        StoredDocument {
+            id: id.to_string(),
+            text: text.to_string(),
+            language: "en".to_string(),
+            tenant: "default".to_string(),
+            stored_at: chrono::Utc::now(),
+            family_id: None,
+            parent_id: None,
+        }
+    }
+
+    #[test]
+    fn flags_privacy_shield_after_effective_date() {
+        let docs = vec![doc(
+            "a",
+            "Transfers rely on the EU-US Privacy Shield framework.",
+        )];
+        let items = scan_documents(
+            &docs,
+            &default_rules(),
+            date(2021, 1, 1),
+            DEFAULT_CONTEXT_CHARS,
+            None,
+        );
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].rule_id, "privacy-shield");
+        assert_eq!(items[0].matched_text, "Privacy Shield");
+        assert_eq!(items[0].offset, 28);
+    }
+
+    #[test]
+    fn rule_not_yet_effective_is_ignored() {
+        let docs = vec![doc("a", "Interest accrues at LIBOR plus 2%.")];
+        assert!(scan_documents(
+            &docs,
+            &default_rules(),
+            date(2023, 1, 1),
+            DEFAULT_CONTEXT_CHARS,
+            None,
+        )
+        .is_empty());
+        assert_eq!(
+            scan_documents(
+                &docs,
+                &default_rules(),
+                date(2023, 7, 1),
+                DEFAULT_CONTEXT_CHARS,
+                None,
+            )
+            .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn legacy_scc_reference_is_flagged() {
+        let docs = vec![
+            doc("b", "Clean document."),
+            doc("a", "The parties adopt the clauses in Decision 2010/87/EU."),
+        ];
+        let items = scan_documents(
+            &docs,
+            &default_rules(),
+            date(2024, 1, 1),
+            DEFAULT_CONTEXT_CHARS,
+            None,
+        );
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].document_id, "a");
+        assert_eq!(items[0].rule_id, "legacy-scc");
+    }
+
+    #[test]
+    fn excerpt_respects_char_boundaries() {
+        let text = "契約".repeat(40) + " privacy shield " + &"条項".repeat(40);
+        let items = scan_documents(
+            &[doc("a", &text)],
+            &default_rules(),
+            date(2024, 1, 1),
+            DEFAULT_CONTEXT_CHARS,
+            None,
+        );
+        assert_eq!(items.len(), 1);
+        assert!(items[0].excerpt.contains("privacy shield"));
+    }
+
+    #[test]
+    fn tenant_synonyms_and_stopwords_widen_rule_patterns() {
+        let lexicon = Lexicon {
+            synonyms: vec![vec![
+                "privacy shield".to_string(),
+                "PS framework".to_string(),
+            ]],
+            stopwords: vec!["the".to_string()],
+        };
+        let docs = vec![doc(
+            "a",
+            "Transfers rely on the PS framework and on the Privacy the Shield.",
+        )];
+        let rules = default_rules();
+        let items = scan_documents(
+            &docs,
+            &rules,
+            date(2021, 1, 1),
+            DEFAULT_CONTEXT_CHARS,
+            Some(&lexicon),
+        );
+        let matched: Vec<&str> = items.iter().map(|i| i.matched_text.as_str()).collect();
+        assert_eq!(matched, ["PS framework", "Privacy the Shield"]);
+        assert_eq!(items[0].offset, 22);
+        assert!(scan_documents(&docs, &rules, date(2021, 1, 1), 0, None).is_empty());
+    }
+
+    #[test]
+    fn regexes_are_validated_and_match_the_original_text() {
+        let mut rule = default_rules().remove(3);
+        rule.patterns.clear();
+        rule.regexes = vec![r"\b(?:usd|gbp)[- ]libor\b".to_string()];
+        assert_eq!(rule.validate(), Ok(()));
+        let docs = vec![doc("a", "Interest at 3M USD-LIBOR and at libor.")];
+        let items = scan_documents(&docs, &[rule.clone()], date(2024, 1, 1), 0, None);
+        let matched: Vec<&str> = items.iter().map(|i| i.matched_text.as_str()).collect();
+        assert_eq!(matched, ["USD-LIBOR"]);
+        assert_eq!(items[0].offset, 15);
+
+        rule.regexes = vec![r"(libor)\s+\1".to_string()];
+        assert_eq!(
+            rule.validate(),
+            Err(r#"pattern "(libor)\\s+\\1": backreferences are not supported"#.to_string())
+        );
+        rule.regexes.clear();
+        assert_eq!(
+            rule.validate(),
+            Err("rule needs a pattern or a regex".to_string())
+        );
+    }
+}
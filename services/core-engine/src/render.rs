@@ -0,0 +1,1143 @@
+//! Compiled documents as files: plain text, Markdown, HTML, PDF and DOCX.
+//! Every format carries the title, the numbered clauses as headings, lists,
+//! tables and a signature block per signing party. PDF and DOCX are written
+//! here (the standard Helvetica fonts, a minimal WordprocessingML package)
+//! so nothing has to be installed next to the engine. `compile` returns the
+//! file inline as base64 or keeps it for a short while behind a download URL.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::{
+    html_export::{self, Block},
+    notifier,
+    workbook::xml_escape,
+    AppState,
+};
+
+/// How long a rendering stored for download stays available.
+const DOWNLOAD_TTL_SECS: i64 = 15 * 60;
+/// Most renderings kept for download at once; the oldest go first.
+const MAX_STORED: usize = 1_000;
+/// Variables naming a signing party, in signature-block order.
+const PARTY_VARIABLES: [&str; 10] = [
+    "party_a",
+    "party_b",
+    "controller",
+    "processor",
+    "service_provider",
+    "customer",
+    "employer",
+    "employee",
+    "licensor",
+    "licensee",
+];
+const SIGNATURE_LINES: [&str; 4] = ["By", "Name", "Title", "Date"];
+const UNTITLED: &str = "Document";
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    Text,
+    Markdown,
+    Html,
+    Pdf,
+    Docx,
+}
+
+impl OutputFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Text => "text/plain; charset=utf-8",
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Html => "text/html; charset=utf-8",
+            Self::Pdf => "application/pdf",
+            Self::Docx => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Pdf => "pdf",
+            Self::Docx => "docx",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Delivery {
+    /// The file in the response, base64-encoded.
+    #[default]
+    Inline,
+    /// A short-lived `download_url` instead of the file.
+    Download,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct RenderedDocument {
+    pub format: OutputFormat,
+    pub content_type: String,
+    pub filename: String,
+    pub size_bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_base64: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+// ── Shared layout ─────────────────────────────────────────────────────────────
+
+/// Heading level of a titled clause below the document title: 2 for "1.",
+/// 3 for "1.1", and 4 for anything deeper.
+fn level(depth: usize) -> usize {
+    (depth + 1).min(4)
+}
+
+fn roman(n: usize) -> String {
+    const NUMERALS: [&str; 10] = ["i", "ii", "iii", "iv", "v", "vi", "vii", "viii", "ix", "x"];
+    NUMERALS
+        .get(n - 1)
+        .map_or_else(|| n.to_string(), |r| (*r).to_string())
+}
+
+/// Marker of the `index`th item of a list; `bullet` for unordered lists.
+fn marker(ordered: Option<&str>, index: usize, bullet: &str) -> String {
+    match ordered {
+        None => bullet.to_string(),
+        Some("1") => format!("{}.", index + 1),
+        Some("i") => format!("({})", roman(index + 1)),
+        Some(_) => format!("({})", char::from(b'a' + (index % 26) as u8)),
+    }
+}
+
+/// `explicit` names when given, otherwise the party variables that are set.
+pub fn signatories(
+    explicit: Option<&[String]>,
+    variables: &HashMap<String, String>,
+) -> Vec<String> {
+    let names: Vec<&str> = match explicit {
+        Some(names) => names.iter().map(|n| n.trim()).collect(),
+        None => PARTY_VARIABLES
+            .iter()
+            .filter_map(|v| variables.get(*v).map(|n| n.trim()))
+            .collect(),
+    };
+    let mut out: Vec<String> = Vec::new();
+    for name in names {
+        if !name.is_empty() && !out.iter().any(|n| n == name) {
+            out.push(name.to_string());
+        }
+    }
+    out
+}
+
+// ── Text and Markdown ─────────────────────────────────────────────────────────
+
+fn text(compiled: &str, signatories: &[String]) -> String {
+    let mut out = format!("{}\n", compiled.trim_end());
+    for party in signatories {
+        out.push_str(&format!("\nSigned for and on behalf of {party}\n"));
+        for line in SIGNATURE_LINES {
+            out.push_str(&format!("{line}: ____________________\n"));
+        }
+    }
+    out
+}
+
+/// Escapes Markdown punctuation, and a leading list marker when `text`
+/// starts a line.
+fn md_escape(text: &str, line_start: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_[]<>#|".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    if line_start {
+        let digits = out.bytes().take_while(u8::is_ascii_digit).count();
+        if digits > 0 && matches!(out.as_bytes().get(digits), Some(b'.' | b')')) {
+            out.insert(digits, '\\');
+        } else if out.starts_with(['-', '+']) {
+            out.insert(0, '\\');
+        }
+    }
+    out
+}
+
+fn markdown(compiled: &str, signatories: &[String]) -> String {
+    let (title, blocks) = html_export::outline(compiled);
+    let mut out = format!("# {}\n", md_escape(title.unwrap_or(UNTITLED), false));
+    for block in blocks {
+        out.push('\n');
+        match block {
+            Block::Clause {
+                depth,
+                number,
+                title: Some(title),
+                body,
+            } => {
+                out.push_str(&format!(
+                    "{} {}\n",
+                    "#".repeat(level(depth)),
+                    md_escape(&format!("{number} {title}"), false)
+                ));
+                if !body.is_empty() {
+                    out.push_str(&format!("\n{}\n", md_escape(body, true)));
+                }
+            }
+            Block::Clause {
+                number,
+                title: None,
+                body,
+                ..
+            } => out.push_str(&format!(
+                "{}\n",
+                md_escape(&format!("{number} {body}"), true)
+            )),
+            Block::List { ordered, items } => {
+                for (i, item) in items.iter().enumerate() {
+                    let item = md_escape(item, false);
+                    match ordered {
+                        None | Some("1") => {
+                            out.push_str(&format!("{} {item}\n", marker(ordered, i, "-")));
+                        }
+                        Some(_) => out.push_str(&format!("- {} {item}\n", marker(ordered, i, ""))),
+                    }
+                }
+            }
+            Block::Table(rows) => {
+                let width = rows.iter().map(Vec::len).max().unwrap_or_default();
+                for (r, row) in rows.iter().enumerate() {
+                    let cells: Vec<String> = (0..width)
+                        .map(|c| md_escape(row.get(c).copied().unwrap_or_default(), false))
+                        .collect();
+                    out.push_str(&format!("| {} |\n", cells.join(" | ")));
+                    if r == 0 {
+                        out.push_str(&format!("|{}\n", " --- |".repeat(width)));
+                    }
+                }
+            }
+            Block::Paragraph(text) => out.push_str(&format!("{}\n", md_escape(text, true))),
+        }
+    }
+    if !signatories.is_empty() {
+        out.push_str("\n## Signatures\n");
+        for party in signatories {
+            out.push_str(&format!(
+                "\n**Signed for and on behalf of {}**\n",
+                md_escape(party, false)
+            ));
+            for line in SIGNATURE_LINES {
+                out.push_str(&format!("\n{line}: ____________________\n"));
+            }
+        }
+    }
+    out
+}
+
+// ── PDF ───────────────────────────────────────────────────────────────────────
+
+/// A4 in points.
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 72.0;
+const BODY_SIZE: f32 = 11.0;
+const LIST_INDENT: f32 = 20.0;
+const CELL_PADDING: f32 = 4.0;
+
+/// Helvetica advance widths of ASCII 32..=126, in thousandths of an em.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Self::Regular => "F1",
+            Self::Bold => "F2",
+        }
+    }
+}
+
+/// Width of `text` in points. Bold glyphs are wider than the regular
+/// metrics, so they are padded rather than measured exactly.
+fn text_width(text: &str, font: Font, size: f32) -> f32 {
+    let em: f32 = text
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => f32::from(HELVETICA_WIDTHS[c as usize - 32]),
+            _ => 556.0,
+        })
+        .sum();
+    let em = if font == Font::Bold { em * 1.1 } else { em };
+    em * size / 1000.0
+}
+
+/// Greedy word wrap to `width` points; words wider than a line are split.
+fn wrap(text: &str, font: Font, size: f32, width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{line} {word}")
+        };
+        if text_width(&candidate, font, size) <= width {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        for c in word.chars() {
+            if !line.is_empty() && text_width(&format!("{line}{c}"), font, size) > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push(c);
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// WinAnsiEncoding byte for `c`: Latin-1 plus typographic punctuation;
+/// anything else prints as `?`.
+fn win_ansi(c: char) -> u8 {
+    match c {
+        '€' => 0x80,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        c if (' '..='~').contains(&c) || ('\u{a0}'..='\u{ff}').contains(&c) => c as u8,
+        _ => b'?',
+    }
+}
+
+/// A PDF literal string.
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut out = vec![b'('];
+    for c in text.chars() {
+        let b = win_ansi(c);
+        if matches!(b, b'(' | b')' | b'\\') {
+            out.push(b'\\');
+        }
+        out.push(b);
+    }
+    out.push(b')');
+    out
+}
+
+/// Content streams of the pages laid out so far, and the cursor on the last.
+struct Layout {
+    pages: Vec<Vec<u8>>,
+    y: f32,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Self {
+            pages: vec![Vec::new()],
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    /// Starts a new page unless `height` more points fit on this one.
+    fn ensure(&mut self, height: f32) {
+        if self.y - height < MARGIN && self.y < PAGE_HEIGHT - MARGIN {
+            self.pages.push(Vec::new());
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    fn stream(&mut self) -> &mut Vec<u8> {
+        self.pages.last_mut().expect("layout always has a page")
+    }
+
+    fn show(&mut self, x: f32, y: f32, text: &str, font: Font, size: f32) {
+        let stream = self.stream();
+        stream.extend_from_slice(
+            format!("BT /{} {size} Tf {x:.2} {y:.2} Td ", font.resource()).as_bytes(),
+        );
+        stream.extend(pdf_string(text));
+        stream.extend_from_slice(b" Tj ET\n");
+    }
+
+    fn rule(&mut self, x1: f32, x2: f32, y: f32) {
+        self.stream()
+            .extend_from_slice(format!("0.5 w {x1:.2} {y:.2} m {x2:.2} {y:.2} l S\n").as_bytes());
+    }
+
+    /// Wrapped lines of `text` from `x`, then `after` points of space.
+    fn paragraph(&mut self, text: &str, font: Font, size: f32, x: f32, after: f32) {
+        let leading = size * 1.3;
+        for line in wrap(text, font, size, PAGE_WIDTH - MARGIN - x) {
+            self.ensure(leading);
+            self.y -= leading;
+            self.show(x, self.y, &line, font, size);
+        }
+        self.y -= after;
+    }
+
+    fn table(&mut self, rows: &[Vec<&str>]) {
+        let columns = rows.iter().map(Vec::len).max().unwrap_or_default();
+        if columns == 0 {
+            return;
+        }
+        let size = BODY_SIZE - 1.0;
+        let leading = size * 1.3;
+        let width = (PAGE_WIDTH - 2.0 * MARGIN) / columns as f32;
+        for (r, row) in rows.iter().enumerate() {
+            let font = if r == 0 { Font::Bold } else { Font::Regular };
+            let cells: Vec<Vec<String>> = (0..columns)
+                .map(|c| {
+                    let text = row.get(c).copied().unwrap_or_default();
+                    wrap(text, font, size, width - 2.0 * CELL_PADDING)
+                })
+                .collect();
+            let lines = cells.iter().map(Vec::len).max().unwrap_or(1);
+            let height = lines as f32 * leading + 2.0 * CELL_PADDING;
+            self.ensure(height);
+            let top = self.y;
+            for (c, cell) in cells.iter().enumerate() {
+                let x = MARGIN + c as f32 * width;
+                self.stream().extend_from_slice(
+                    format!(
+                        "0.5 w {x:.2} {:.2} {width:.2} {height:.2} re S\n",
+                        top - height
+                    )
+                    .as_bytes(),
+                );
+                for (l, line) in cell.iter().enumerate() {
+                    let baseline = top - CELL_PADDING - (l + 1) as f32 * leading + size * 0.25;
+                    self.show(x + CELL_PADDING, baseline, line, font, size);
+                }
+            }
+            self.y = top - height;
+        }
+        self.y -= BODY_SIZE;
+    }
+
+    /// Kept on one page: the party, then a ruled line for each entry.
+    fn signature(&mut self, party: &str) {
+        const LINE_GAP: f32 = 24.0;
+        let heading = format!("Signed for and on behalf of {party}");
+        let heading_lines = wrap(&heading, Font::Bold, BODY_SIZE, PAGE_WIDTH - 2.0 * MARGIN).len();
+        self.ensure(
+            heading_lines as f32 * BODY_SIZE * 1.3 + SIGNATURE_LINES.len() as f32 * LINE_GAP,
+        );
+        self.paragraph(&heading, Font::Bold, BODY_SIZE, MARGIN, 0.0);
+        for line in SIGNATURE_LINES {
+            self.y -= LINE_GAP;
+            self.show(
+                MARGIN,
+                self.y,
+                &format!("{line}:"),
+                Font::Regular,
+                BODY_SIZE,
+            );
+            self.rule(MARGIN + 45.0, MARGIN + 260.0, self.y - 2.0);
+        }
+        self.y -= 2.0 * BODY_SIZE;
+    }
+}
+
+fn pdf_pages(compiled: &str, signatories: &[String]) -> (String, Vec<Vec<u8>>) {
+    let (title, blocks) = html_export::outline(compiled);
+    let title = title.unwrap_or(UNTITLED).to_string();
+    let mut layout = Layout::new();
+
+    let title_size = 16.0;
+    let title_width = PAGE_WIDTH - 2.0 * MARGIN;
+    for line in wrap(&title, Font::Bold, title_size, title_width) {
+        layout.y -= title_size * 1.3;
+        let x = (PAGE_WIDTH - text_width(&line, Font::Bold, title_size)) / 2.0;
+        layout.show(x.max(MARGIN), layout.y, &line, Font::Bold, title_size);
+    }
+    layout.y -= BODY_SIZE;
+
+    for block in blocks {
+        match block {
+            Block::Clause {
+                depth,
+                number,
+                title: Some(title),
+                body,
+            } => {
+                let size = if level(depth) == 2 { 12.5 } else { BODY_SIZE };
+                // Keep a heading with the first line of its clause.
+                layout.ensure(size * 1.3 + BODY_SIZE * 1.3 + 6.0);
+                layout.y -= 6.0;
+                layout.paragraph(&format!("{number} {title}"), Font::Bold, size, MARGIN, 4.0);
+                if !body.is_empty() {
+                    layout.paragraph(body, Font::Regular, BODY_SIZE, MARGIN, 6.0);
+                }
+            }
+            Block::Clause {
+                number,
+                title: None,
+                body,
+                ..
+            } => layout.paragraph(
+                &format!("{number} {body}"),
+                Font::Regular,
+                BODY_SIZE,
+                MARGIN,
+                6.0,
+            ),
+            Block::List { ordered, items } => {
+                for (i, item) in items.iter().enumerate() {
+                    layout.ensure(BODY_SIZE * 1.3);
+                    let top = layout.y;
+                    layout.show(
+                        MARGIN + LIST_INDENT / 2.0,
+                        top - BODY_SIZE * 1.3,
+                        &marker(ordered, i, "•"),
+                        Font::Regular,
+                        BODY_SIZE,
+                    );
+                    layout.paragraph(
+                        item,
+                        Font::Regular,
+                        BODY_SIZE,
+                        MARGIN + LIST_INDENT * 1.5,
+                        2.0,
+                    );
+                }
+                layout.y -= 4.0;
+            }
+            Block::Table(rows) => layout.table(&rows),
+            Block::Paragraph(text) => layout.paragraph(text, Font::Regular, BODY_SIZE, MARGIN, 6.0),
+        }
+    }
+    if !signatories.is_empty() {
+        layout.ensure(12.5 * 1.3 + BODY_SIZE * 6.0);
+        layout.y -= 6.0;
+        layout.paragraph("Signatures", Font::Bold, 12.5, MARGIN, 6.0);
+        for party in signatories {
+            layout.signature(party);
+        }
+    }
+
+    let count = layout.pages.len();
+    for (i, page) in layout.pages.iter_mut().enumerate() {
+        let footer = format!("Page {} of {count}", i + 1);
+        let x = (PAGE_WIDTH - text_width(&footer, Font::Regular, 9.0)) / 2.0;
+        page.extend_from_slice(format!("BT /F1 9 Tf {x:.2} 36.00 Td ").as_bytes());
+        page.extend(pdf_string(&footer));
+        page.extend_from_slice(b" Tj ET\n");
+    }
+    (title, layout.pages)
+}
+
+/// A PDF 1.4 file: catalog, page tree, the two fonts and document info,
+/// then a page object and an uncompressed content stream per page.
+fn pdf(compiled: &str, signatories: &[String]) -> Vec<u8> {
+    let (title, pages) = pdf_pages(compiled, signatories);
+    let count = pages.len();
+    let kids: Vec<String> = (0..count).map(|k| format!("{} 0 R", 6 + 2 * k)).collect();
+
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {count} >>",
+            kids.join(" ")
+        )
+        .into_bytes(),
+    ];
+    for base in ["Helvetica", "Helvetica-Bold"] {
+        objects.push(
+            format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{base} /Encoding /WinAnsiEncoding >>"
+            )
+            .into_bytes(),
+        );
+    }
+    let mut info = b"<< /Title ".to_vec();
+    info.extend(pdf_string(&title));
+    info.extend_from_slice(b" /Producer (legal-engine) >>");
+    objects.push(info);
+    for (k, content) in pages.into_iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                7 + 2 * k
+            )
+            .into_bytes(),
+        );
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend_from_slice(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut out = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, body) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        out.extend_from_slice(body);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = out.len();
+    out.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        out.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+    }
+    out.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .as_bytes(),
+    );
+    out
+}
+
+// ── DOCX ──────────────────────────────────────────────────────────────────────
+
+const W_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+const R_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+/// Text width of an A4 page with one-inch margins, in twentieths of a point.
+const TEXT_WIDTH_TWIPS: usize = 9026;
+
+fn run(text: &str, bold: bool) -> String {
+    let props = if bold { "<w:rPr><w:b/></w:rPr>" } else { "" };
+    format!(
+        r#"<w:r>{props}<w:t xml:space="preserve">{}</w:t></w:r>"#,
+        xml_escape(text)
+    )
+}
+
+fn paragraph(props: &str, runs: &str) -> String {
+    if props.is_empty() {
+        format!("<w:p>{runs}</w:p>")
+    } else {
+        format!("<w:p><w:pPr>{props}</w:pPr>{runs}</w:p>")
+    }
+}
+
+fn docx_table(rows: &[Vec<&str>]) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or_default();
+    let width = TEXT_WIDTH_TWIPS / columns.max(1);
+    let mut xml = String::from(
+        r#"<w:tbl><w:tblPr><w:tblStyle w:val="TableGrid"/><w:tblW w:w="0" w:type="auto"/></w:tblPr><w:tblGrid>"#,
+    );
+    xml.push_str(&format!(r#"<w:gridCol w:w="{width}"/>"#).repeat(columns));
+    xml.push_str("</w:tblGrid>");
+    for (r, row) in rows.iter().enumerate() {
+        xml.push_str("<w:tr>");
+        if r == 0 {
+            xml.push_str("<w:trPr><w:tblHeader/></w:trPr>");
+        }
+        for c in 0..columns {
+            let text = row.get(c).copied().unwrap_or_default();
+            xml.push_str(&format!(
+                r#"<w:tc><w:tcPr><w:tcW w:w="{width}" w:type="dxa"/></w:tcPr>{}</w:tc>"#,
+                paragraph("", &run(text, r == 0))
+            ));
+        }
+        xml.push_str("</w:tr>");
+    }
+    xml.push_str("</w:tbl>");
+    // Keeps tables apart and lets one end the document.
+    xml.push_str("<w:p/>");
+    xml
+}
+
+fn docx_body(compiled: &str, signatories: &[String]) -> (String, String) {
+    let (title, blocks) = html_export::outline(compiled);
+    let title = title.unwrap_or(UNTITLED).to_string();
+    let mut body = paragraph(r#"<w:pStyle w:val="Title"/>"#, &run(&title, false));
+    for block in blocks {
+        match block {
+            Block::Clause {
+                depth,
+                number,
+                title: Some(title),
+                body: text,
+            } => {
+                body.push_str(&paragraph(
+                    &format!(r#"<w:pStyle w:val="Heading{}"/>"#, level(depth) - 1),
+                    &run(&format!("{number} {title}"), false),
+                ));
+                if !text.is_empty() {
+                    body.push_str(&paragraph("", &run(text, false)));
+                }
+            }
+            Block::Clause {
+                number,
+                title: None,
+                body: text,
+                ..
+            } => body.push_str(&paragraph("", &run(&format!("{number} {text}"), false))),
+            Block::List { ordered, items } => {
+                for (i, item) in items.iter().enumerate() {
+                    body.push_str(&paragraph(
+                        r#"<w:spacing w:after="60"/><w:ind w:left="720" w:hanging="360"/>"#,
+                        &format!(
+                            "{}<w:r><w:tab/></w:r>{}",
+                            run(&marker(ordered, i, "•"), false),
+                            run(item, false)
+                        ),
+                    ));
+                }
+            }
+            Block::Table(rows) => body.push_str(&docx_table(&rows)),
+            Block::Paragraph(text) => body.push_str(&paragraph("", &run(text, false))),
+        }
+    }
+    if !signatories.is_empty() {
+        body.push_str(&paragraph(
+            r#"<w:pStyle w:val="Heading1"/>"#,
+            &run("Signatures", false),
+        ));
+        for party in signatories {
+            body.push_str(&paragraph(
+                r#"<w:keepNext/><w:spacing w:before="240"/>"#,
+                &run(&format!("Signed for and on behalf of {party}"), true),
+            ));
+            for (i, line) in SIGNATURE_LINES.iter().enumerate() {
+                let keep = if i + 1 < SIGNATURE_LINES.len() {
+                    "<w:keepNext/>"
+                } else {
+                    ""
+                };
+                // An underlined tab draws the line to write on.
+                body.push_str(&paragraph(
+                    &format!(
+                        r#"{keep}<w:tabs><w:tab w:val="left" w:pos="4536"/></w:tabs><w:spacing w:before="360" w:after="0"/>"#
+                    ),
+                    &format!(
+                        r#"{}<w:r><w:rPr><w:u w:val="single"/></w:rPr><w:tab/></w:r>"#,
+                        run(&format!("{line}: "), false)
+                    ),
+                ));
+            }
+        }
+    }
+    (title, body)
+}
+
+const DOCX_STYLES: &str = r#"<w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:cs="Calibri"/><w:sz w:val="22"/></w:rPr></w:rPrDefault><w:pPrDefault><w:pPr><w:spacing w:after="160" w:line="264" w:lineRule="auto"/></w:pPr></w:pPrDefault></w:docDefaults><w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/></w:style><w:style w:type="paragraph" w:styleId="Title"><w:name w:val="Title"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:jc w:val="center"/><w:spacing w:after="320"/></w:pPr><w:rPr><w:b/><w:sz w:val="32"/></w:rPr></w:style><w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="80"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/><w:sz w:val="26"/></w:rPr></w:style><w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="200" w:after="60"/><w:outlineLvl w:val="1"/></w:pPr><w:rPr><w:b/><w:sz w:val="23"/></w:rPr></w:style><w:style w:type="paragraph" w:styleId="Heading3"><w:name w:val="heading 3"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="160" w:after="40"/><w:outlineLvl w:val="2"/></w:pPr><w:rPr><w:b/><w:i/><w:sz w:val="22"/></w:rPr></w:style><w:style w:type="table" w:styleId="TableGrid"><w:name w:val="Table Grid"/><w:tblPr><w:tblBorders><w:top w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:left w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:bottom w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:right w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:insideH w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:insideV w:val="single" w:sz="4" w:space="0" w:color="auto"/></w:tblBorders><w:tblCellMar><w:left w:w="108" w:type="dxa"/><w:right w:w="108" w:type="dxa"/></w:tblCellMar></w:tblPr></w:style>"#;
+
+/// A WordprocessingML package: the document, its styles, a footer with
+/// the page number and core properties carrying the title.
+fn docx(compiled: &str, signatories: &[String]) -> std::io::Result<Vec<u8>> {
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#;
+    const PACKAGE_RELS: &str = "http://schemas.openxmlformats.org/package/2006/relationships";
+    let (title, body) = docx_body(compiled, signatories);
+
+    let content_types = format!(
+        r#"{XML}<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/><Override PartName="/word/footer1.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.footer+xml"/><Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/></Types>"#
+    );
+    let root_rels = format!(
+        r#"{XML}<Relationships xmlns="{PACKAGE_RELS}"><Relationship Id="rId1" Type="{R_NS}/officeDocument" Target="word/document.xml"/><Relationship Id="rId2" Type="{PACKAGE_RELS}/metadata/core-properties" Target="docProps/core.xml"/></Relationships>"#
+    );
+    let document_rels = format!(
+        r#"{XML}<Relationships xmlns="{PACKAGE_RELS}"><Relationship Id="rId1" Type="{R_NS}/styles" Target="styles.xml"/><Relationship Id="rId2" Type="{R_NS}/footer" Target="footer1.xml"/></Relationships>"#
+    );
+    let document = format!(
+        r#"{XML}<w:document xmlns:w="{W_NS}" xmlns:r="{R_NS}"><w:body>{body}<w:sectPr><w:footerReference w:type="default" r:id="rId2"/><w:pgSz w:w="11906" w:h="16838"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="708" w:footer="708" w:gutter="0"/></w:sectPr></w:body></w:document>"#
+    );
+    let styles = format!(r#"{XML}<w:styles xmlns:w="{W_NS}">{DOCX_STYLES}</w:styles>"#);
+    let footer = format!(
+        r#"{XML}<w:ftr xmlns:w="{W_NS}"><w:p><w:pPr><w:jc w:val="center"/></w:pPr><w:r><w:t xml:space="preserve">Page </w:t></w:r><w:fldSimple w:instr=" PAGE "><w:r><w:t>1</w:t></w:r></w:fldSimple></w:p></w:ftr>"#
+    );
+    let core = format!(
+        r#"{XML}<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:title>{}</dc:title></cp:coreProperties>"#,
+        xml_escape(&title)
+    );
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let opts = SimpleFileOptions::default();
+    for (path, part) in [
+        ("[Content_Types].xml", content_types),
+        ("_rels/.rels", root_rels),
+        ("word/document.xml", document),
+        ("word/_rels/document.xml.rels", document_rels),
+        ("word/styles.xml", styles),
+        ("word/footer1.xml", footer),
+        ("docProps/core.xml", core),
+    ] {
+        zip.start_file(path, opts)?;
+        zip.write_all(part.as_bytes())?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+// ── Rendering ─────────────────────────────────────────────────────────────────
+
+/// `compiled` as a `format` file with a signature block for each of
+/// `signatories`.
+pub fn render(
+    format: OutputFormat,
+    compiled: &str,
+    signatories: &[String],
+) -> std::io::Result<Vec<u8>> {
+    Ok(match format {
+        OutputFormat::Text => text(compiled, signatories).into_bytes(),
+        OutputFormat::Markdown => markdown(compiled, signatories).into_bytes(),
+        OutputFormat::Html => {
+            html_export::render_signed(compiled, "en", None, signatories).into_bytes()
+        }
+        OutputFormat::Pdf => pdf(compiled, signatories),
+        OutputFormat::Docx => docx(compiled, signatories)?,
+    })
+}
+
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(ALPHABET[(n >> (18 - 6 * i) & 63) as usize]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// `<template_id>.<extension>`, with anything unsafe in a header replaced.
+fn filename(template_id: &str, format: OutputFormat) -> String {
+    let stem: String = template_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let stem = if stem.is_empty() { "document" } else { &stem };
+    format!("{stem}.{}", format.extension())
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+struct Stored {
+    tenant: String,
+    format: OutputFormat,
+    filename: String,
+    bytes: Vec<u8>,
+    expires_at: DateTime<Utc>,
+}
+
+/// Renderings waiting to be downloaded, by ID.
+#[derive(Default)]
+pub struct RenderStore {
+    items: DashMap<String, Stored>,
+}
+
+impl RenderStore {
+    fn insert(&self, id: String, stored: Stored, now: DateTime<Utc>) {
+        self.items.retain(|_, s| s.expires_at > now);
+        if self.items.len() >= MAX_STORED {
+            let oldest = self
+                .items
+                .iter()
+                .min_by_key(|s| s.expires_at)
+                .map(|s| s.key().clone());
+            if let Some(oldest) = oldest {
+                self.items.remove(&oldest);
+            }
+        }
+        self.items.insert(id, stored);
+    }
+
+    /// The file, for its own tenant and until it expires.
+    fn get(
+        &self,
+        id: &str,
+        tenant: &str,
+        now: DateTime<Utc>,
+    ) -> Option<(OutputFormat, String, Vec<u8>)> {
+        let stored = self.items.get(id)?;
+        (stored.tenant == tenant && now < stored.expires_at)
+            .then(|| (stored.format, stored.filename.clone(), stored.bytes.clone()))
+    }
+}
+
+/// Renders `compiled` and returns it inline or stores it for download.
+pub fn deliver(
+    state: &AppState,
+    tenant: &str,
+    template_id: &str,
+    compiled: &str,
+    format: OutputFormat,
+    delivery: Delivery,
+    signatories: &[String],
+) -> Result<RenderedDocument, StatusCode> {
+    let bytes = render(format, compiled, signatories).map_err(|e| {
+        warn!(template_id, ?format, error = %e, "document rendering failed");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    info!(
+        template_id,
+        ?format,
+        ?delivery,
+        bytes = bytes.len(),
+        "document rendered"
+    );
+    let mut rendered = RenderedDocument {
+        format,
+        content_type: format.content_type().to_string(),
+        filename: filename(template_id, format),
+        size_bytes: bytes.len(),
+        content_base64: None,
+        download_url: None,
+        expires_at: None,
+    };
+    match delivery {
+        Delivery::Inline => rendered.content_base64 = Some(base64(&bytes)),
+        Delivery::Download => {
+            let now = state.clock.now();
+            let id = format!("rnd-{}", state.ids.next_id());
+            let expires_at = now + Duration::seconds(DOWNLOAD_TTL_SECS);
+            state.renderings.insert(
+                id.clone(),
+                Stored {
+                    tenant: tenant.to_string(),
+                    format,
+                    filename: rendered.filename.clone(),
+                    bytes,
+                    expires_at,
+                },
+                now,
+            );
+            rendered.download_url = Some(format!("/api/v1/legal/renderings/{id}"));
+            rendered.expires_at = Some(expires_at);
+        }
+    }
+    Ok(rendered)
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// A stored rendering as an attachment; `404` once expired or for another
+/// tenant.
+pub async fn download(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    let (format, filename, bytes) = state
+        .renderings
+        .get(&id, &tenant, state.clock.now())
+        .ok_or(StatusCode::NOT_FOUND)?;
+    info!(rendering_id = %id, ?format, bytes = bytes.len(), "rendering downloaded");
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    const DOC: &str = "MASTER SERVICES AGREEMENT\n\n1. Services. Acme provides the services.\n\
+        1.1 Levels. Uptime is 99.9% (monthly).\n\n2. Fees. As follows:\n(a) setup\n(b) monthly\n\
+        Item | Fee\n--- | ---\nSetup | 100\n\n3. Nothing else applies.";
+
+    fn parties() -> Vec<String> {
+        vec!["Acme Ltd".to_string(), "Globex & Co".to_string()]
+    }
+
+    #[test]
+    fn base64_pads_partial_groups() {
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(&[0xFF, 0xEF]), "/+8=");
+    }
+
+    #[test]
+    fn signatories_come_from_party_variables_unless_named() {
+        let variables: HashMap<String, String> = [
+            ("party_b", "Globex"),
+            ("party_a", " Acme "),
+            ("customer", "Acme"),
+            ("jurisdiction", "Utopia"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        assert_eq!(signatories(None, &variables), ["Acme", "Globex"]);
+        assert!(signatories(Some(&[]), &variables).is_empty());
+        assert_eq!(
+            signatories(Some(&["Initech".to_string(), " ".to_string()]), &variables),
+            ["Initech"]
+        );
+    }
+
+    #[test]
+    fn markdown_has_headings_numbering_and_signatures() {
+        let md = markdown(DOC, &parties());
+        assert!(md.starts_with("# MASTER SERVICES AGREEMENT\n"));
+        assert!(md.contains("\n## 1. Services\n\nAcme provides the services.\n"));
+        assert!(md.contains("\n### 1.1 Levels\n"));
+        assert!(md.contains("- (a) setup\n- (b) monthly\n"));
+        assert!(md.contains("| Item | Fee |\n| --- | --- |\n| Setup | 100 |\n"));
+        // An untitled clause stays a paragraph rather than becoming a list.
+        assert!(md.contains("\n3\\. Nothing else applies.\n"));
+        assert!(md.contains("**Signed for and on behalf of Globex & Co**\n\nBy: ____"));
+    }
+
+    #[test]
+    fn text_appends_signature_blocks() {
+        let out = text("NDA\n\nTerms.\n\n", &parties()[..1]);
+        assert_eq!(
+            out,
+            "NDA\n\nTerms.\n\nSigned for and on behalf of Acme Ltd\nBy: ____________________\n\
+             Name: ____________________\nTitle: ____________________\nDate: ____________________\n"
+        );
+    }
+
+    #[test]
+    fn pdf_is_well_formed() {
+        let bytes = pdf(DOC, &parties());
+        // Past the binary comment the file is ASCII, so offsets stay byte offsets.
+        assert!(bytes.starts_with(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n"));
+        let body = format!("{:15}{}", "", std::str::from_utf8(&bytes[15..]).unwrap());
+        assert!(body.ends_with("%%EOF\n"));
+        assert!(body.contains("/Title (MASTER SERVICES AGREEMENT)"));
+        assert!(body.contains("(1. Services) Tj"));
+        assert!(body.contains("(Uptime is 99.9% \\(monthly\\).) Tj"));
+        assert!(body.contains("(Signed for and on behalf of Globex & Co) Tj"));
+        assert!(body.contains("(Page 1 of 1) Tj"));
+        // Every xref entry points at the object it names.
+        let xref = body.rfind("\nxref\n").unwrap() + 1;
+        let startxref: usize = body[body.rfind("startxref\n").unwrap() + 10..]
+            .lines()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(startxref, xref);
+        for (i, entry) in body[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|l| l.ends_with(" n "))
+            .enumerate()
+        {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(bytes[offset..].starts_with(format!("{} 0 obj\n", i + 1).as_bytes()));
+        }
+    }
+
+    #[test]
+    fn long_documents_break_across_pages() {
+        let doc = format!("LONG\n\n{}", "A clause that goes on and on. ".repeat(600));
+        let body = String::from_utf8_lossy(&pdf(&doc, &[])).into_owned();
+        let pages = body.matches("/Type /Page ").count();
+        assert!(pages > 1);
+        assert!(body.contains(&format!("/Count {pages}")));
+        assert!(body.contains(&format!("(Page {pages} of {pages}) Tj")));
+        assert!(
+            wrap("word ".repeat(200).trim(), Font::Regular, BODY_SIZE, 451.0)
+                .iter()
+                .all(|l| text_width(l, Font::Regular, BODY_SIZE) <= 451.0)
+        );
+    }
+
+    #[test]
+    fn docx_package_has_styled_parts() {
+        let bytes = docx(DOC, &parties()).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut part = |name: &str| {
+            let mut out = String::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_string(&mut out)
+                .unwrap();
+            out
+        };
+        let document = part("word/document.xml");
+        assert!(document.contains(r#"<w:pStyle w:val="Title"/></w:pPr><w:r><w:t xml:space="preserve">MASTER SERVICES AGREEMENT</w:t>"#));
+        assert!(document.contains(r#"<w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t xml:space="preserve">1. Services</w:t>"#));
+        assert!(document.contains(
+            r#"<w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t xml:space="preserve">1.1 Levels</w:t>"#
+        ));
+        assert!(document.contains("(b)</w:t></w:r><w:r><w:tab/></w:r>"));
+        assert!(document.contains("<w:tblHeader/>"));
+        assert!(document.contains("Signed for and on behalf of Globex &amp; Co"));
+        assert!(part("word/styles.xml").contains(r#"w:styleId="Heading3""#));
+        assert!(part("word/footer1.xml").contains(r#"w:instr=" PAGE ""#));
+        assert!(
+            part("docProps/core.xml").contains("<dc:title>MASTER SERVICES AGREEMENT</dc:title>")
+        );
+        assert!(part("[Content_Types].xml").contains("/word/document.xml"));
+    }
+
+    #[test]
+    fn filenames_are_header_safe() {
+        assert_eq!(filename("nda", OutputFormat::Pdf), "nda.pdf");
+        assert_eq!(
+            filename("acme \"msa\"", OutputFormat::Docx),
+            "acme--msa-.docx"
+        );
+        assert_eq!(filename("", OutputFormat::Markdown), "document.md");
+    }
+}
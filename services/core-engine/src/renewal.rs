@@ -0,0 +1,302 @@
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use crate::{auth, compile_template, corpus::StoredDocument, AppState, CompileExtras};
+
+const TEMPLATE_ID: &str = "renewal";
+/// Only contracts expiring within this many days are drafted without `force`.
+const RENEWAL_WINDOW_DAYS: i64 = 180;
+const DEFAULT_EXTENSION_MONTHS: u32 = 12;
+
+pub const CURRENCY_MARKERS: [&str; 7] = ["$", "€", "£", "¥", "USD ", "EUR ", "JPY "];
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct RenewalDraftRequest {
+    /// Analysis ID of the contract being renewed.
+    pub document_id: String,
+    /// Overrides the expiry date found in the contract.
+    pub current_end_date: Option<NaiveDate>,
+    /// Defaults to the current end date plus `extension_months`.
+    pub new_end_date: Option<NaiveDate>,
+    pub extension_months: Option<u32>,
+    /// Overrides the pricing found in the contract.
+    pub pricing: Option<String>,
+    /// Draft even when expiry is further away than the renewal window.
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenewalDraft {
+    pub draft_id: String,
+    pub parent_id: String,
+    pub family_id: String,
+    pub template_id: String,
+    pub compiled_document: String,
+    /// Values used to fill the template, whether extracted or supplied.
+    pub variables: BTreeMap<String, String>,
+    pub missing_variables: Vec<String>,
+    pub current_end_date: NaiveDate,
+    pub new_end_date: NaiveDate,
+    pub days_until_expiry: i64,
+}
+
+// ── Extraction ────────────────────────────────────────────────────────────────
+
+/// The two names in "between <A> and <B>", cut at the end of the sentence.
+/// A period only ends it before a capital or the end of the text, so entity
+/// suffixes like "Inc." and "K.K." stay part of the names.
+pub fn parties(text: &str) -> Option<(String, String)> {
+    let lower = text.to_ascii_lowercase();
+    let start = lower.find("between ")? + "between ".len();
+    let rest = &text[start..];
+    let end = rest
+        .char_indices()
+        .find(|&(i, c)| match c {
+            ';' | '\n' => true,
+            '.' => {
+                let after = &rest[i + 1..];
+                after.is_empty()
+                    || after.starts_with(char::is_whitespace)
+                        && after.trim_start().starts_with(|c: char| c.is_uppercase())
+            }
+            _ => false,
+        })
+        .map_or(rest.len(), |(i, _)| i);
+    let clause = &rest[..end];
+    let split = clause.to_ascii_lowercase().find(" and ")?;
+    let clean = |s: &str| {
+        let s = s.split('(').next().unwrap_or(s);
+        // Keep "Acme, Inc."; cut "Acme Corp, a Delaware corporation".
+        let cut = s.match_indices(',').map(|(i, _)| i).find(|&i| {
+            let word = s[i + 1..].split_whitespace().next().unwrap_or("");
+            let word = word.trim_end_matches(['.', ',']).to_ascii_lowercase();
+            !["inc", "ltd", "llc", "co", "corp", "limited"].contains(&word.as_str())
+        });
+        let s = s[..cut.unwrap_or(s.len())].trim();
+        (!s.is_empty()).then(|| s.to_string())
+    };
+    Some((clean(&clause[..split])?, clean(&clause[split + 5..])?))
+}
+
+/// The first currency amount, with a following "per month/year" if present.
+pub fn pricing(text: &str) -> Option<String> {
+    let start = CURRENCY_MARKERS.iter().filter_map(|m| text.find(m)).min()?;
+    let rest = &text[start..];
+    let end = rest
+        .char_indices()
+        .skip_while(|(_, c)| !c.is_ascii_digit())
+        .find(|(_, c)| !(c.is_ascii_digit() || *c == ',' || *c == '.'))
+        .map_or(rest.len(), |(i, _)| i);
+    let amount = rest[..end].trim_end_matches(['.', ',']);
+    if !amount.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let tail = rest[amount.len()..].to_ascii_lowercase();
+    let period = ["per month", "per year", "per annum", "monthly", "annually"]
+        .iter()
+        .find(|p| tail.trim_start().starts_with(*p));
+    Some(match period {
+        Some(p) => format!("{amount} {p}"),
+        None => amount.to_string(),
+    })
+}
+
+/// A date starting `words`: ISO, "March 1, 2026" or "1 March 2026".
+pub fn parse_date(words: &[&str]) -> Option<NaiveDate> {
+    let clean = |w: &str| {
+        w.trim_matches(|c: char| !c.is_alphanumeric() && c != '-')
+            .to_string()
+    };
+    let first = clean(words.first()?);
+    if let Ok(d) = NaiveDate::parse_from_str(&first, "%Y-%m-%d") {
+        return Some(d);
+    }
+    let three: Vec<String> = words.iter().take(3).map(|w| clean(w)).collect();
+    if three.len() < 3 {
+        return None;
+    }
+    let joined = three.join(" ");
+    NaiveDate::parse_from_str(&joined, "%B %d %Y")
+        .or_else(|_| NaiveDate::parse_from_str(&joined, "%d %B %Y"))
+        .ok()
+}
+
+/// The latest date in a sentence about expiry or the end of the term.
+pub fn expiry_date(text: &str) -> Option<NaiveDate> {
+    text.split(['.', ';', '\n'])
+        .filter(|s| {
+            let l = s.to_ascii_lowercase();
+            ["expir", "until", "terminate on", "ends on", "end date"]
+                .iter()
+                .any(|k| l.contains(k))
+        })
+        .flat_map(|s| {
+            let words: Vec<&str> = s.split_whitespace().collect();
+            (0..words.len())
+                .filter_map(|i| parse_date(&words[i..]))
+                .collect::<Vec<_>>()
+        })
+        .max()
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn draft(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<RenewalDraftRequest>,
+) -> Result<(StatusCode, Json<RenewalDraft>), StatusCode> {
+    let parent =
+        auth::owned_document(&state, &headers, &req.document_id).ok_or(StatusCode::NOT_FOUND)?;
+
+    let current_end = req
+        .current_end_date
+        .or_else(|| expiry_date(&parent.text))
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+    let today = state.clock.today();
+    let days_until_expiry = (current_end - today).num_days();
+    if days_until_expiry > RENEWAL_WINDOW_DAYS && !req.force {
+        return Err(StatusCode::CONFLICT);
+    }
+    let new_end = match req.new_end_date {
+        Some(d) => d,
+        None => current_end
+            .checked_add_months(Months::new(
+                req.extension_months.unwrap_or(DEFAULT_EXTENSION_MONTHS),
+            ))
+            .ok_or(StatusCode::BAD_REQUEST)?,
+    };
+    if new_end <= current_end {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let effective = current_end
+        .checked_add_days(Days::new(1))
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    let mut variables = HashMap::from([
+        ("agreement_reference".to_string(), parent.id.clone()),
+        ("effective_date".to_string(), effective.to_string()),
+        ("current_end_date".to_string(), current_end.to_string()),
+        ("new_end_date".to_string(), new_end.to_string()),
+    ]);
+    if let Some((a, b)) = parties(&parent.text) {
+        variables.insert("party_a".to_string(), a);
+        variables.insert("party_b".to_string(), b);
+    }
+    if let Some(p) = req.pricing.clone().or_else(|| pricing(&parent.text)) {
+        variables.insert("pricing".to_string(), p);
+    }
+    let compiled = compile_template(
+        &state.precompiled,
+        TEMPLATE_ID,
+        &variables,
+        CompileExtras::default(),
+    )?;
+
+    // Drafts join the parent's family, creating one if it has none yet.
+    let family_id = match &parent.family_id {
+        Some(f) => f.clone(),
+        None => {
+            let f = format!("fam-{}", state.ids.next_id());
+            state
+                .corpus
+                .link_family(std::slice::from_ref(&parent.id), &f);
+            f
+        }
+    };
+    let draft_id = state.ids.next_id();
+    state.corpus.insert(StoredDocument {
+        id: draft_id.clone(),
+        text: compiled.compiled_document.clone(),
+        language: parent.language.clone(),
+        tenant: parent.tenant.clone(),
+        stored_at: state.clock.now(),
+        family_id: Some(family_id.clone()),
+        parent_id: Some(parent.id.clone()),
+    });
+    state.record_audit(
+        "renewal.drafted",
+        &parent.id,
+        None,
+        json!({ "draft_id": draft_id, "new_end_date": new_end }),
+    );
+
+    info!(
+        parent_id = %parent.id,
+        draft_id = %draft_id,
+        days_until_expiry,
+        missing = compiled.missing_variables.len(),
+        "renewal amendment drafted"
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(RenewalDraft {
+            draft_id,
+            parent_id: parent.id,
+            family_id,
+            template_id: TEMPLATE_ID.to_string(),
+            compiled_document: compiled.compiled_document,
+            variables: variables.into_iter().collect(),
+            missing_variables: compiled.missing_variables,
+            current_end_date: current_end,
+            new_end_date: new_end,
+            days_until_expiry,
+        }),
+    ))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTRACT: &str = "SERVICES AGREEMENT\n\
+        This Agreement is made between Acme Corp (\"Customer\") and Globex Ltd.\n\
+        Customer shall pay USD 12,500.00 per month.\n\
+        This Agreement remains in force until December 31, 2024, unless terminated earlier.";
+
+    #[test]
+    fn extracts_parties_pricing_and_expiry() {
+        assert_eq!(
+            parties(CONTRACT),
+            Some(("Acme Corp".to_string(), "Globex Ltd".to_string()))
+        );
+        assert_eq!(
+            parties("Made between Acme, Inc., a Delaware corporation, and Sony K.K. Both agree."),
+            // The sentence's own period cannot be told apart from the suffix's.
+            Some(("Acme, Inc.".to_string(), "Sony K.K".to_string()))
+        );
+        assert_eq!(
+            pricing(CONTRACT).as_deref(),
+            Some("USD 12,500.00 per month")
+        );
+        assert_eq!(expiry_date(CONTRACT), NaiveDate::from_ymd_opt(2024, 12, 31));
+    }
+
+    #[test]
+    fn expiry_accepts_iso_and_day_first_dates() {
+        assert_eq!(
+            expiry_date("The term expires on 2025-06-30."),
+            NaiveDate::from_ymd_opt(2025, 6, 30)
+        );
+        assert_eq!(
+            expiry_date("Expiry date: 1 March 2026"),
+            NaiveDate::from_ymd_opt(2026, 3, 1)
+        );
+        assert_eq!(expiry_date("Signed on 2024-01-01."), None);
+    }
+}
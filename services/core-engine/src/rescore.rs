@@ -0,0 +1,465 @@
+//! Re-scoring the stored corpus after a rule change. The ruleset version
+//! names the risk model and clause taxonomy in force; an admin starts a
+//! background job that scores every stored document under it, keeping the
+//! scores each document got under earlier versions.
+//!
+//! The job is rate-limited so it does not starve live analysis, and
+//! incremental: documents already scored under the version are skipped,
+//! so a paused job resumes where it stopped and a re-run after new
+//! documents arrive scores only those.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tokio::time::Instant;
+use tracing::info;
+
+use crate::{
+    corpus::StoredDocument,
+    risk_model::RiskModel,
+    taxonomy::{self, LOCALIZED, TAXONOMY},
+    AppState,
+};
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct RescoreConfig {
+    /// Documents scored per second.
+    pub rate: u32,
+}
+
+impl Default for RescoreConfig {
+    fn default() -> Self {
+        Self { rate: 20 }
+    }
+}
+
+impl RescoreConfig {
+    pub fn from_env() -> Self {
+        Self {
+            rate: std::env::var("LEGAL_RESCORE_RATE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(Self::default().rate),
+        }
+    }
+}
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+/// A document's score under one ruleset version.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StoredScore {
+    pub ruleset_version: String,
+    pub overall_score: f64,
+    pub risk_level: String,
+    pub clause_types: Vec<String>,
+    pub scored_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RescoreStatus {
+    Running,
+    /// Stopped by request; starting again resumes it.
+    Paused,
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RescoreProgress {
+    pub ruleset_version: String,
+    pub status: RescoreStatus,
+    /// Documents in the corpus when the job last started or resumed.
+    pub total: usize,
+    /// Of those, documents that have a score under the version.
+    pub done: usize,
+    /// Documents this job scored, across resumes.
+    pub rescored: usize,
+    pub rate_per_second: u32,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScoreHistory {
+    pub document_id: String,
+    pub current_ruleset_version: String,
+    /// Oldest first.
+    pub scores: Vec<StoredScore>,
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct Rescorer {
+    config: RescoreConfig,
+    scores: DashMap<String, Vec<StoredScore>>,
+    job: Mutex<Option<RescoreProgress>>,
+    pause: AtomicBool,
+}
+
+/// The risk model's version, or `rules` when it has none, and a hash of
+/// its factors and levels and of the clause taxonomy, so editing either
+/// without bumping the version still yields a new one.
+pub fn ruleset_version(model: &RiskModel) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(&model.factors).unwrap_or_default());
+    hasher.update(serde_json::to_vec(&model.levels).unwrap_or_default());
+    for kind in &TAXONOMY {
+        hasher.update(format!("{}|{}|{}|", kind.group, kind.name, kind.risk));
+        for keyword in kind.keywords {
+            hasher.update(keyword.as_bytes());
+            hasher.update([0]);
+        }
+    }
+    for (language, groups) in LOCALIZED {
+        for (group, keywords) in groups {
+            hasher.update(format!("{language}|{group}|"));
+            for keyword in *keywords {
+                hasher.update(keyword.as_bytes());
+                hasher.update([0]);
+            }
+        }
+    }
+    let hash: String = hasher.finalize()[..6]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    format!("{}-{hash}", model.version.as_deref().unwrap_or("rules"))
+}
+
+/// Scores `doc` under the state's rules, as `risk-score` and `analyze` do.
+fn score(state: &AppState, doc: &StoredDocument, version: &str) -> StoredScore {
+    let factors = state.risk_model.factors(&doc.text);
+    let overall_score: f64 = factors.iter().map(|f| f.weight * f.score).sum();
+    let lexicon = state.lexicons.matcher(&doc.tenant);
+    let keywords = lexicon
+        .as_deref()
+        .unwrap_or(state.precompiled.keywords())
+        .in_language(&doc.language);
+    StoredScore {
+        ruleset_version: version.to_string(),
+        overall_score,
+        risk_level: state.risk_model.level(overall_score),
+        clause_types: taxonomy::detect(&keywords, &doc.text)
+            .into_iter()
+            .map(|(kind, _)| kind.name.to_string())
+            .collect(),
+        scored_at: state.clock.now(),
+    }
+}
+
+impl Rescorer {
+    pub fn new(config: RescoreConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    pub fn progress(&self) -> Option<RescoreProgress> {
+        self.job.lock().unwrap().clone()
+    }
+
+    fn has_version(&self, id: &str, version: &str) -> bool {
+        self.scores
+            .get(id)
+            .is_some_and(|s| s.iter().any(|s| s.ruleset_version == version))
+    }
+
+    /// Keeps scores under other versions; replaces one under the same.
+    fn put(&self, id: &str, score: StoredScore) {
+        let mut scores = self.scores.entry(id.to_string()).or_default();
+        scores.retain(|s| s.ruleset_version != score.ruleset_version);
+        scores.push(score);
+    }
+
+    pub fn history(&self, id: &str) -> Vec<StoredScore> {
+        self.scores.get(id).map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// Forgets a document's scores; returns how many versions it had.
+    pub fn remove(&self, id: &str) -> usize {
+        self.scores.remove(id).map_or(0, |(_, s)| s.len())
+    }
+
+    /// Marks a job for `version` running, resuming a paused one for the
+    /// same version. Returns the running job instead if there is one.
+    fn begin(
+        &self,
+        version: &str,
+        total: usize,
+        now: DateTime<Utc>,
+    ) -> Result<RescoreProgress, RescoreProgress> {
+        let mut job = self.job.lock().unwrap();
+        if let Some(running) = job.as_ref().filter(|j| j.status == RescoreStatus::Running) {
+            return Err(running.clone());
+        }
+        let resumed = job
+            .take()
+            .filter(|j| j.status == RescoreStatus::Paused && j.ruleset_version == version);
+        self.pause.store(false, Ordering::SeqCst);
+        let progress = RescoreProgress {
+            ruleset_version: version.to_string(),
+            status: RescoreStatus::Running,
+            total,
+            done: 0,
+            rescored: resumed.as_ref().map_or(0, |j| j.rescored),
+            rate_per_second: self.config.rate,
+            started_at: resumed.map_or(now, |j| j.started_at),
+            updated_at: now,
+            finished_at: None,
+        };
+        *job = Some(progress.clone());
+        Ok(progress)
+    }
+
+    fn update(&self, now: DateTime<Utc>, change: impl FnOnce(&mut RescoreProgress)) {
+        if let Some(job) = self.job.lock().unwrap().as_mut() {
+            change(job);
+            job.updated_at = now;
+        }
+    }
+
+    /// Asks the running job to stop after the document it is on.
+    fn request_pause(&self) -> Option<RescoreProgress> {
+        let job = self.job.lock().unwrap();
+        let running = job
+            .as_ref()
+            .filter(|j| j.status == RescoreStatus::Running)?;
+        self.pause.store(true, Ordering::SeqCst);
+        Some(running.clone())
+    }
+}
+
+/// Scores every stored document not yet scored under `version`, at most
+/// `rate` a second.
+async fn run(state: AppState, version: String, ids: Vec<String>) {
+    let rescorer = state.rescorer.clone();
+    let interval = Duration::from_secs(1) / rescorer.config.rate;
+    let mut next = Instant::now();
+    for id in ids {
+        if rescorer.pause.load(Ordering::SeqCst) {
+            rescorer.update(state.clock.now(), |j| j.status = RescoreStatus::Paused);
+            info!(ruleset_version = %version, "corpus re-scoring paused");
+            return;
+        }
+        if rescorer.has_version(&id, &version) {
+            rescorer.update(state.clock.now(), |j| j.done += 1);
+            continue;
+        }
+        tokio::time::sleep_until(next).await;
+        next = Instant::now() + interval;
+        let (st, v) = (state.clone(), version.clone());
+        let scored = tokio::task::spawn_blocking(move || {
+            let doc = st.corpus.get(&id)?;
+            st.rescorer.put(&doc.id, score(&st, &doc, &v));
+            Some(())
+        })
+        .await
+        .ok()
+        .flatten();
+        rescorer.update(state.clock.now(), |j| {
+            // Documents removed since the job started drop out of it.
+            match scored {
+                Some(()) => {
+                    j.done += 1;
+                    j.rescored += 1;
+                }
+                None => j.total -= 1,
+            }
+        });
+    }
+    let now = state.clock.now();
+    rescorer.update(now, |j| {
+        j.status = RescoreStatus::Completed;
+        j.finished_at = Some(now);
+    });
+    info!(ruleset_version = %version, "corpus re-scoring completed");
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// `POST /admin/rescore`: starts or resumes the job for the current
+/// ruleset; 409 while one is running.
+pub async fn start(State(state): State<AppState>) -> impl IntoResponse {
+    let version = ruleset_version(&state.risk_model);
+    let ids = state.corpus.ids();
+    match state.rescorer.begin(&version, ids.len(), state.clock.now()) {
+        Ok(progress) => {
+            info!(ruleset_version = %version, documents = ids.len(), "corpus re-scoring started");
+            state.record_audit(
+                "corpus.rescore_started",
+                &version,
+                None,
+                json!({ "documents": ids.len() }),
+            );
+            tokio::spawn(run(state.clone(), version, ids));
+            (StatusCode::ACCEPTED, Json(progress))
+        }
+        Err(running) => (StatusCode::CONFLICT, Json(running)),
+    }
+}
+
+pub async fn status(State(state): State<AppState>) -> Result<Json<RescoreProgress>, StatusCode> {
+    state
+        .rescorer
+        .progress()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// `DELETE /admin/rescore`: pauses the running job; 409 if none is.
+pub async fn pause(State(state): State<AppState>) -> impl IntoResponse {
+    match state.rescorer.request_pause() {
+        Some(progress) => (StatusCode::ACCEPTED, Json(json!(progress))),
+        None => (
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "no re-scoring job is running" })),
+        ),
+    }
+}
+
+pub async fn history(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ScoreHistory>, StatusCode> {
+    let mut scores = state.rescorer.history(&id);
+    if scores.is_empty() && state.corpus.get(&id).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    scores.sort_by_key(|s| s.scored_at);
+    Ok(Json(ScoreHistory {
+        document_id: id,
+        current_ruleset_version: ruleset_version(&state.risk_model),
+        scores,
+    }))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{http_tests::SAMPLE_CONTRACT, notifier::DEFAULT_TENANT};
+    use std::sync::Arc;
+
+    fn doc(id: &str) -> StoredDocument {
+        StoredDocument {
+            id: id.to_string(),
+            text: SAMPLE_CONTRACT.to_string(),
+            language: "en".to_string(),
+            tenant: DEFAULT_TENANT.to_string(),
+            stored_at: Utc::now(),
+            family_id: None,
+            parent_id: None,
+        }
+    }
+
+    #[test]
+    fn the_version_changes_with_the_rules() {
+        let model = RiskModel::default();
+        let version = ruleset_version(&model);
+        assert_eq!(version, ruleset_version(&model.clone()));
+        assert!(version.starts_with("rules-"));
+
+        let mut reweighted = model.clone();
+        reweighted.factors[0].weight += 0.1;
+        assert_ne!(ruleset_version(&reweighted), version);
+
+        let named = RiskModel {
+            version: Some("2026.1".to_string()),
+            ..model
+        };
+        assert!(ruleset_version(&named).starts_with("2026.1-"));
+    }
+
+    #[tokio::test]
+    async fn new_versions_are_added_beside_old_ones_and_reruns_are_incremental() {
+        let mut state = AppState::in_memory();
+        state.rescorer = Arc::new(Rescorer::new(RescoreConfig { rate: 1000 }));
+        state.corpus.insert(doc("a"));
+        state.corpus.insert(doc("b"));
+
+        let first = ruleset_version(&state.risk_model);
+        let ids = state.corpus.ids();
+        state.rescorer.begin(&first, 2, Utc::now()).unwrap();
+        run(state.clone(), first.clone(), ids).await;
+        let progress = state.rescorer.progress().unwrap();
+        assert_eq!(progress.status, RescoreStatus::Completed);
+        assert_eq!(
+            (progress.total, progress.done, progress.rescored),
+            (2, 2, 2)
+        );
+        let history = state.rescorer.history("a");
+        assert_eq!(history.len(), 1);
+        assert!(history[0].clause_types.contains(&"Termination".to_string()));
+
+        // Nothing new under the same rules.
+        state.corpus.insert(doc("c"));
+        state.rescorer.begin(&first, 3, Utc::now()).unwrap();
+        run(state.clone(), first.clone(), state.corpus.ids()).await;
+        let progress = state.rescorer.progress().unwrap();
+        assert_eq!((progress.done, progress.rescored), (3, 1));
+
+        let mut model = (*state.risk_model).clone();
+        model.version = Some("v2".to_string());
+        state.risk_model = Arc::new(model);
+        let second = ruleset_version(&state.risk_model);
+        state.rescorer.begin(&second, 3, Utc::now()).unwrap();
+        run(state.clone(), second.clone(), state.corpus.ids()).await;
+        let versions: Vec<String> = state
+            .rescorer
+            .history("a")
+            .into_iter()
+            .map(|s| s.ruleset_version)
+            .collect();
+        assert_eq!(versions, [first, second]);
+        assert_eq!(state.rescorer.remove("a"), 2);
+    }
+
+    #[tokio::test]
+    async fn a_paused_job_resumes_without_rescoring() {
+        let state = AppState::in_memory();
+        for id in ["a", "b", "c"] {
+            state.corpus.insert(doc(id));
+        }
+        let version = ruleset_version(&state.risk_model);
+        state.rescorer.begin(&version, 3, Utc::now()).unwrap();
+        assert!(state.rescorer.begin(&version, 3, Utc::now()).is_err());
+        state.rescorer.put("a", score(&state, &doc("a"), &version));
+        state.rescorer.request_pause().unwrap();
+        run(state.clone(), version.clone(), state.corpus.ids()).await;
+        assert_eq!(
+            state.rescorer.progress().unwrap().status,
+            RescoreStatus::Paused
+        );
+        assert!(state.rescorer.request_pause().is_none());
+
+        let resumed = state.rescorer.begin(&version, 3, Utc::now()).unwrap();
+        assert_eq!(resumed.status, RescoreStatus::Running);
+        run(state.clone(), version, state.corpus.ids()).await;
+        let progress = state.rescorer.progress().unwrap();
+        assert_eq!((progress.done, progress.rescored), (3, 2));
+    }
+}
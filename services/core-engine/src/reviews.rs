@@ -0,0 +1,507 @@
+//! Reviewer comments imported from Word or CSV, anchored to document offsets
+//! and clauses, and kept next to the machine findings as one review history.
+
+use std::{
+    collections::BTreeMap,
+    io::{Cursor, Read},
+    ops::Range,
+};
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use crate::{access, auth, extraction::line_around, taxonomy, warmup::Precompiled, AppState};
+
+pub const DOCX_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    Docx,
+    Csv,
+}
+
+/// A comment as read from the reviewer's file, before anchoring.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct RawComment {
+    #[serde(default)]
+    pub author: Option<String>,
+    pub comment: String,
+    /// Text the comment is attached to.
+    #[serde(default)]
+    pub quote: Option<String>,
+    #[serde(default)]
+    pub clause_id: Option<String>,
+    #[serde(default)]
+    pub date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewerComment {
+    pub id: String,
+    pub author: Option<String>,
+    pub comment: String,
+    pub quote: Option<String>,
+    /// Byte offsets of the quoted text (or the clause) in the stored document.
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+    pub clause_id: Option<String>,
+    pub date: Option<String>,
+    pub format: ImportFormat,
+    pub imported_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResponse {
+    pub analysis_id: String,
+    pub imported: usize,
+    /// Comments whose quote was not found and that name no clause.
+    pub unanchored: usize,
+    pub comments: Vec<ReviewerComment>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntrySource {
+    Machine,
+    Reviewer,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryEntry {
+    pub source: EntrySource,
+    /// Clause, issue or comment ID.
+    pub item_id: String,
+    pub clause_id: Option<String>,
+    pub author: Option<String>,
+    pub text: String,
+    pub severity: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryResponse {
+    pub analysis_id: String,
+    pub entries: Vec<HistoryEntry>,
+    pub count: usize,
+}
+
+/// Imported reviewer comments per analysis.
+#[derive(Default)]
+pub struct ReviewStore {
+    comments: DashMap<String, Vec<ReviewerComment>>,
+}
+
+impl ReviewStore {
+    pub fn list(&self, analysis_id: &str) -> Vec<ReviewerComment> {
+        self.comments
+            .get(analysis_id)
+            .map(|c| c.value().clone())
+            .unwrap_or_default()
+    }
+
+    /// Drops an analysis's comments and returns how many there were.
+    pub fn remove(&self, analysis_id: &str) -> usize {
+        self.comments
+            .remove(analysis_id)
+            .map_or(0, |(_, comments)| comments.len())
+    }
+
+    /// Runs `f` over every comment along with its analysis ID.
+    pub fn for_each_mut(&self, mut f: impl FnMut(&str, &mut ReviewerComment)) {
+        for mut comments in self.comments.iter_mut() {
+            let (analysis_id, comments) = comments.pair_mut();
+            comments.iter_mut().for_each(|c| f(analysis_id, c));
+        }
+    }
+
+    fn append(&self, analysis_id: &str, comments: &[ReviewerComment]) {
+        self.comments
+            .entry(analysis_id.to_string())
+            .or_default()
+            .extend_from_slice(comments);
+    }
+}
+
+// ── Parsing ───────────────────────────────────────────────────────────────────
+
+pub fn parse_csv(bytes: &[u8]) -> Result<Vec<RawComment>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(bytes);
+    reader
+        .deserialize::<RawComment>()
+        .map(|r| r.map_err(|e| e.to_string()))
+        .filter(|r| r.as_ref().map_or(true, |c| !c.comment.is_empty()))
+        .collect()
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+pub fn attr(tag: &str, name: &str) -> Option<String> {
+    let key = format!(" {name}=\"");
+    let start = tag.find(&key)? + key.len();
+    let end = tag[start..].find('"')? + start;
+    Some(unescape(&tag[start..end]))
+}
+
+pub enum XmlEvent<'a> {
+    Tag(&'a str),
+    /// Contents of a `<w:t>` run, unescaped.
+    Text(String),
+}
+
+pub fn tag_name(tag: &str) -> &str {
+    tag.trim_start_matches(['<', '/'])
+        .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .next()
+        .unwrap_or_default()
+}
+
+/// Walks the tags and text runs of a WordprocessingML part.
+pub fn walk<'a>(xml: &'a str, mut on_event: impl FnMut(XmlEvent<'a>)) {
+    let mut rest = xml;
+    let mut in_text = false;
+    while let Some(open) = rest.find('<') {
+        if in_text {
+            on_event(XmlEvent::Text(unescape(&rest[..open])));
+        }
+        let Some(close) = rest[open..].find('>') else {
+            break;
+        };
+        let tag = &rest[open..=open + close];
+        if tag_name(tag) == "w:t" {
+            in_text = !tag.starts_with("</") && !tag.ends_with("/>");
+        }
+        on_event(XmlEvent::Tag(tag));
+        rest = &rest[open + close + 1..];
+    }
+}
+
+pub fn zip_entry(
+    archive: &mut zip::ZipArchive<Cursor<&[u8]>>,
+    name: &str,
+) -> Result<String, String> {
+    let mut out = String::new();
+    archive
+        .by_name(name)
+        .map_err(|e| format!("{name}: {e}"))?
+        .read_to_string(&mut out)
+        .map_err(|e| format!("{name}: {e}"))?;
+    Ok(out)
+}
+
+/// Comments from `word/comments.xml`, with their anchored text taken from
+/// the comment ranges in `word/document.xml`.
+pub fn parse_docx(bytes: &[u8]) -> Result<Vec<RawComment>, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    let comments_xml = zip_entry(&mut archive, "word/comments.xml")?;
+    let document_xml = zip_entry(&mut archive, "word/document.xml")?;
+
+    // Ranges may nest or overlap, so text goes to every open range.
+    let mut quotes: BTreeMap<String, String> = BTreeMap::new();
+    let mut open: Vec<String> = Vec::new();
+    walk(&document_xml, |event| match event {
+        XmlEvent::Tag(tag) => match (tag_name(tag), attr(tag, "w:id")) {
+            ("w:commentRangeStart", Some(id)) => open.push(id),
+            ("w:commentRangeEnd", Some(id)) => open.retain(|o| *o != id),
+            _ => {}
+        },
+        XmlEvent::Text(text) => {
+            for id in &open {
+                quotes.entry(id.clone()).or_default().push_str(&text);
+            }
+        }
+    });
+
+    let mut out: Vec<(String, RawComment)> = Vec::new();
+    let mut in_comment = false;
+    walk(&comments_xml, |event| match event {
+        XmlEvent::Tag(tag) => match tag_name(tag) {
+            "w:comment" if !tag.starts_with("</") => {
+                in_comment = true;
+                out.push((
+                    attr(tag, "w:id").unwrap_or_default(),
+                    RawComment {
+                        author: attr(tag, "w:author"),
+                        date: attr(tag, "w:date"),
+                        ..RawComment::default()
+                    },
+                ));
+            }
+            "w:comment" => in_comment = false,
+            // Paragraph breaks inside a comment become newlines.
+            "w:p" if in_comment && tag.starts_with("</") => {
+                if let Some((_, c)) = out.last_mut() {
+                    c.comment.push('\n');
+                }
+            }
+            _ => {}
+        },
+        XmlEvent::Text(text) if in_comment => {
+            if let Some((_, c)) = out.last_mut() {
+                c.comment.push_str(&text);
+            }
+        }
+        XmlEvent::Text(_) => {}
+    });
+
+    Ok(out
+        .into_iter()
+        .map(|(id, mut c)| {
+            c.comment = c.comment.trim().to_string();
+            c.quote = quotes.get(&id).map(|q| q.trim().to_string());
+            c
+        })
+        .filter(|c| !c.comment.is_empty())
+        .collect())
+}
+
+// ── Anchoring ─────────────────────────────────────────────────────────────────
+
+/// The line holding each clause's keyword match, under the ids the
+/// `keyword` extractor gives the clauses.
+fn clause_spans(pre: &Precompiled, text: &str) -> Vec<(String, Range<usize>)> {
+    taxonomy::detect(pre.keywords(), text)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, (s, _)))| (format!("clause-{:03}", i + 1), line_around(text, s)))
+        .collect()
+}
+
+/// Finds the quote in the document (exact, then ignoring case) and the clause
+/// whose line it falls on; a named clause without a quote anchors to its line.
+pub fn anchor(
+    pre: &Precompiled,
+    text: &str,
+    raw: &RawComment,
+) -> (Option<Range<usize>>, Option<String>) {
+    let spans = clause_spans(pre, text);
+    let quoted = raw
+        .quote
+        .as_deref()
+        .filter(|q| !q.is_empty())
+        .and_then(|q| {
+            text.find(q)
+                .or_else(|| text.to_ascii_lowercase().find(&q.to_ascii_lowercase()))
+                .filter(|&s| text.is_char_boundary(s + q.len()))
+                .map(|s| s..s + q.len())
+        });
+    let clause_id = raw.clause_id.clone().or_else(|| {
+        let range = quoted.as_ref()?;
+        spans
+            .iter()
+            .find(|(_, line)| line.start <= range.start && range.start < line.end)
+            .map(|(id, _)| id.to_string())
+    });
+    let range = quoted.or_else(|| {
+        let id = clause_id.as_deref()?;
+        spans.iter().find(|(c, _)| *c == id).map(|(_, r)| r.clone())
+    });
+    (range, clause_id)
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// Body is the reviewer's file; `Content-Type` picks the parser.
+pub async fn import(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<ImportResponse>), StatusCode> {
+    let doc = auth::owned_document(&state, &headers, &id).ok_or(StatusCode::NOT_FOUND)?;
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let (format, parsed) = if content_type.starts_with(DOCX_CONTENT_TYPE) {
+        (ImportFormat::Docx, parse_docx(&body))
+    } else if content_type.starts_with("text/csv") {
+        (ImportFormat::Csv, parse_csv(&body))
+    } else {
+        return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    };
+    let raw = parsed.map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    let now = state.clock.now();
+    let comments: Vec<ReviewerComment> = raw
+        .into_iter()
+        .map(|raw| {
+            let (range, clause_id) = anchor(&state.precompiled, &doc.text, &raw);
+            ReviewerComment {
+                id: format!("cmt-{}", state.ids.next_id()),
+                author: raw.author,
+                comment: raw.comment,
+                quote: raw.quote,
+                start: range.as_ref().map(|r| r.start),
+                end: range.map(|r| r.end),
+                clause_id,
+                date: raw.date,
+                format,
+                imported_at: now,
+            }
+        })
+        .collect();
+    let unanchored = comments.iter().filter(|c| c.start.is_none()).count();
+    state.reviews.append(&id, &comments);
+    state.record_audit(
+        "annotations.imported",
+        &id,
+        None,
+        json!({ "format": format, "imported": comments.len(), "unanchored": unanchored }),
+    );
+    info!(
+        analysis_id = %id,
+        ?format,
+        imported = comments.len(),
+        unanchored,
+        "reviewer annotations imported"
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ImportResponse {
+            analysis_id: id,
+            imported: comments.len(),
+            unanchored,
+            comments,
+        }),
+    ))
+}
+
+/// Machine findings and reviewer comments for one analysis, oldest first.
+pub async fn history(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<HistoryResponse>, StatusCode> {
+    let doc = auth::owned_document(&state, &headers, &id).ok_or(StatusCode::NOT_FOUND)?;
+    let stored_at = doc.stored_at;
+    let record = access::record(&state, doc);
+
+    let mut entries: Vec<HistoryEntry> = record
+        .clauses
+        .into_iter()
+        .map(|c| HistoryEntry {
+            source: EntrySource::Machine,
+            clause_id: Some(c.id.clone()),
+            item_id: c.id,
+            author: None,
+            text: c.text,
+            severity: Some(c.risk_level),
+            at: stored_at,
+        })
+        .chain(record.issues.into_iter().map(|i| HistoryEntry {
+            source: EntrySource::Machine,
+            item_id: i.id,
+            clause_id: None,
+            author: None,
+            text: i.description,
+            severity: Some(i.severity),
+            at: stored_at,
+        }))
+        .collect();
+    entries.extend(state.reviews.list(&id).into_iter().map(|c| {
+        HistoryEntry {
+            source: EntrySource::Reviewer,
+            // Word dates are RFC 3339; anything else sorts by import time.
+            at: c
+                .date
+                .as_deref()
+                .and_then(|d| DateTime::parse_from_rfc3339(d).ok())
+                .map_or(c.imported_at, |d| d.with_timezone(&Utc)),
+            item_id: c.id,
+            clause_id: c.clause_id,
+            author: c.author,
+            text: c.comment,
+            severity: None,
+        }
+    }));
+    entries.sort_by_key(|e| e.at);
+
+    let count = entries.len();
+    Ok(Json(HistoryResponse {
+        analysis_id: id,
+        entries,
+        count,
+    }))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    fn docx(document: &str, comments: &str) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, body) in [
+            ("word/document.xml", document),
+            ("word/comments.xml", comments),
+        ] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(body.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn docx_comments_carry_their_anchored_text() {
+        let document = r#"<w:document><w:body><w:p><w:r><w:t>4. </w:t></w:r>
+            <w:commentRangeStart w:id="0"/><w:r><w:t xml:space="preserve">Either party may </w:t></w:r>
+            <w:r><w:t>terminate</w:t></w:r><w:commentRangeEnd w:id="0"/></w:p></w:body></w:document>"#;
+        let comments = r#"<w:comments><w:comment w:id="0" w:author="Jane &amp; Co" w:date="2024-05-02T10:00:00Z">
+            <w:p><w:r><w:t>Notice period</w:t></w:r></w:p><w:p><w:r><w:t>too short.</w:t></w:r></w:p>
+            </w:comment></w:comments>"#;
+        let parsed = parse_docx(&docx(document, comments)).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].author.as_deref(), Some("Jane & Co"));
+        assert_eq!(parsed[0].comment, "Notice period\ntoo short.");
+        assert_eq!(
+            parsed[0].quote.as_deref(),
+            Some("Either party may terminate")
+        );
+        assert_eq!(parsed[0].date.as_deref(), Some("2024-05-02T10:00:00Z"));
+    }
+
+    #[test]
+    fn csv_rows_and_anchoring() {
+        let csv = "author,comment,quote,clause_id\n\
+            Jane,Cap is too low,Neither party shall be liable,\n\
+            Raj,Prefer Delaware,,clause-001\n\
+            ,,ignored empty comment,\n";
+        let parsed = parse_csv(csv.as_bytes()).unwrap();
+        assert_eq!(parsed.len(), 2);
+
+        let pre = Precompiled::default();
+        let text = crate::http_tests::SAMPLE_CONTRACT;
+        let (range, clause) = anchor(&pre, text, &parsed[0]);
+        assert_eq!(&text[range.unwrap()], "Neither party shall be liable");
+        assert_eq!(clause.as_deref(), Some("clause-002"));
+
+        let (range, clause) = anchor(&pre, text, &parsed[1]);
+        assert!(text[range.unwrap()].starts_with("1. Governing Law."));
+        assert_eq!(clause.as_deref(), Some("clause-001"));
+    }
+}
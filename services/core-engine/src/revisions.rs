@@ -0,0 +1,487 @@
+use std::collections::BTreeSet;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{get_template_body, includes, AppState};
+
+// Token overlap above which a removed and an added clause count as one edited clause.
+const MODIFIED_SIMILARITY: f64 = 0.5;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateRevision {
+    pub revision: u32,
+    pub body: String,
+    pub note: Option<String>,
+    /// `None` for the built-in revision.
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevisionsResponse {
+    pub template_id: String,
+    pub revisions: Vec<TemplateRevision>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewRevisionRequest {
+    pub body: String,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Unchanged,
+    Added,
+    Removed,
+    Modified,
+    Moved,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Annotation {
+    VariableAdded { variable: String },
+    VariableRemoved { variable: String },
+    SectionReordered { from: usize, to: usize },
+}
+
+/// One clause (blank-line separated paragraph); indices are zero-based
+/// positions in the respective revision.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClauseChange {
+    pub change: ChangeKind,
+    pub from_index: Option<usize>,
+    pub to_index: Option<usize>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub moved: usize,
+    pub unchanged: usize,
+    pub variables_added: Vec<String>,
+    pub variables_removed: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevisionDiff {
+    pub template_id: String,
+    pub from_revision: u32,
+    pub to_revision: u32,
+    pub summary: DiffSummary,
+    pub changes: Vec<ClauseChange>,
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+/// Revision history per template. Revision 1 is the built-in body and is
+/// created on first access.
+#[derive(Default)]
+pub struct RevisionStore {
+    revisions: DashMap<String, Vec<TemplateRevision>>,
+}
+
+impl RevisionStore {
+    pub fn list(&self, template_id: &str) -> Option<Vec<TemplateRevision>> {
+        self.seeded(template_id)?;
+        self.revisions.get(template_id).map(|r| r.value().clone())
+    }
+
+    /// Body of the newest revision.
+    pub fn latest(&self, template_id: &str) -> Option<String> {
+        self.seeded(template_id)?;
+        self.revisions
+            .get(template_id)
+            .and_then(|r| r.last().map(|rev| rev.body.clone()))
+    }
+
+    pub fn add(
+        &self,
+        template_id: &str,
+        body: String,
+        note: Option<String>,
+        at: DateTime<Utc>,
+    ) -> Option<u32> {
+        self.seeded(template_id)?;
+        let mut revs = self.revisions.get_mut(template_id)?;
+        let revision = revs.last().map_or(1, |r| r.revision + 1);
+        revs.push(TemplateRevision {
+            revision,
+            body,
+            note,
+            created_at: Some(at),
+        });
+        Some(revision)
+    }
+
+    fn seeded(&self, template_id: &str) -> Option<()> {
+        if self.revisions.contains_key(template_id) {
+            return Some(());
+        }
+        let body = get_template_body(template_id)?;
+        self.revisions
+            .entry(template_id.to_string())
+            .or_insert_with(|| {
+                vec![TemplateRevision {
+                    revision: 1,
+                    body,
+                    note: Some("built-in".to_string()),
+                    created_at: None,
+                }]
+            });
+        Some(())
+    }
+}
+
+// ── Diff ──────────────────────────────────────────────────────────────────────
+
+fn split_clauses(body: &str) -> Vec<String> {
+    body.split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Names of the `{{name}}` placeholders in `text`.
+fn variables(text: &str) -> BTreeSet<String> {
+    let mut vars = BTreeSet::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else { break };
+        let name = after[..end].trim();
+        if !name.is_empty() {
+            vars.insert(name.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+    vars
+}
+
+fn variable_annotations(before: &str, after: &str) -> Vec<Annotation> {
+    let (old, new) = (variables(before), variables(after));
+    new.difference(&old)
+        .map(|v| Annotation::VariableAdded {
+            variable: v.clone(),
+        })
+        .chain(old.difference(&new).map(|v| Annotation::VariableRemoved {
+            variable: v.clone(),
+        }))
+        .collect()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn similarity(a: &str, b: &str) -> f64 {
+    let ta: BTreeSet<String> = a.split_whitespace().map(str::to_lowercase).collect();
+    let tb: BTreeSet<String> = b.split_whitespace().map(str::to_lowercase).collect();
+    let union = ta.union(&tb).count();
+    if union == 0 {
+        return 1.0;
+    }
+    ta.intersection(&tb).count() as f64 / union as f64
+}
+
+/// Index pairs of the longest common subsequence of equal clauses.
+fn lcs_pairs(a: &[String], b: &[String]) -> Vec<(usize, usize)> {
+    let mut table = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            table[i][j] = if a[i] == b[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j, mut pairs) = (0, 0, Vec::new());
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Clause-level diff of two template bodies, split at blank lines.
+pub fn diff_bodies(before: &str, after: &str) -> (Vec<ClauseChange>, DiffSummary) {
+    let changes = diff_clauses(&split_clauses(before), &split_clauses(after));
+    let count = |k: ChangeKind| changes.iter().filter(|c| c.change == k).count();
+    let (vars_before, vars_after) = (variables(before), variables(after));
+    let summary = DiffSummary {
+        added: count(ChangeKind::Added),
+        removed: count(ChangeKind::Removed),
+        modified: count(ChangeKind::Modified),
+        moved: count(ChangeKind::Moved),
+        unchanged: count(ChangeKind::Unchanged),
+        variables_added: vars_after.difference(&vars_before).cloned().collect(),
+        variables_removed: vars_before.difference(&vars_after).cloned().collect(),
+    };
+    (changes, summary)
+}
+
+/// In-order equal clauses are unchanged, equal clauses out of order are
+/// moves, and similar leftovers are paired as modifications.
+pub fn diff_clauses(old: &[String], new: &[String]) -> Vec<ClauseChange> {
+    let mut old_match: Vec<Option<(usize, ChangeKind)>> = vec![None; old.len()];
+    let mut new_match: Vec<Option<(usize, ChangeKind)>> = vec![None; new.len()];
+    for (i, j) in lcs_pairs(old, new) {
+        old_match[i] = Some((j, ChangeKind::Unchanged));
+        new_match[j] = Some((i, ChangeKind::Unchanged));
+    }
+    for kind in [ChangeKind::Moved, ChangeKind::Modified] {
+        for j in 0..new.len() {
+            if new_match[j].is_some() {
+                continue;
+            }
+            let candidate = (0..old.len())
+                .filter(|&i| old_match[i].is_none())
+                .filter(|&i| match kind {
+                    ChangeKind::Moved => old[i] == new[j],
+                    _ => similarity(&old[i], &new[j]) >= MODIFIED_SIMILARITY,
+                })
+                .max_by(|&x, &y| {
+                    similarity(&old[x], &new[j]).total_cmp(&similarity(&old[y], &new[j]))
+                });
+            if let Some(i) = candidate {
+                old_match[i] = Some((j, kind));
+                new_match[j] = Some((i, kind));
+            }
+        }
+    }
+
+    // Walk the new revision, emitting removals where they sat in the old one.
+    let mut changes = Vec::new();
+    let mut next_old = 0;
+    let mut emit_removed_until = |limit: usize, changes: &mut Vec<ClauseChange>| {
+        while next_old < limit {
+            if old_match[next_old].is_none() {
+                changes.push(ClauseChange {
+                    change: ChangeKind::Removed,
+                    from_index: Some(next_old),
+                    to_index: None,
+                    before: Some(old[next_old].clone()),
+                    after: None,
+                    annotations: variable_annotations(&old[next_old], ""),
+                });
+            }
+            next_old += 1;
+        }
+    };
+    for (j, clause) in new.iter().enumerate() {
+        match new_match[j] {
+            Some((i, ChangeKind::Unchanged)) => {
+                emit_removed_until(i, &mut changes);
+                changes.push(ClauseChange {
+                    change: ChangeKind::Unchanged,
+                    from_index: Some(i),
+                    to_index: Some(j),
+                    before: None,
+                    after: Some(clause.clone()),
+                    annotations: Vec::new(),
+                });
+            }
+            Some((i, kind)) => {
+                let mut annotations = variable_annotations(&old[i], clause);
+                if kind == ChangeKind::Moved {
+                    annotations.push(Annotation::SectionReordered { from: i, to: j });
+                }
+                changes.push(ClauseChange {
+                    change: kind,
+                    from_index: Some(i),
+                    to_index: Some(j),
+                    before: (kind == ChangeKind::Modified).then(|| old[i].clone()),
+                    after: Some(clause.clone()),
+                    annotations,
+                });
+            }
+            None => changes.push(ClauseChange {
+                change: ChangeKind::Added,
+                from_index: None,
+                to_index: Some(j),
+                before: None,
+                after: Some(clause.clone()),
+                annotations: variable_annotations("", clause),
+            }),
+        }
+    }
+    emit_removed_until(old.len(), &mut changes);
+    changes
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn list_revisions(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<RevisionsResponse>, StatusCode> {
+    let revisions = state
+        .template_revisions
+        .list(&id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(RevisionsResponse {
+        template_id: id,
+        revisions,
+    }))
+}
+
+pub async fn add_revision(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<NewRevisionRequest>,
+) -> Result<(StatusCode, Json<RevisionsResponse>), Response> {
+    if req.body.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+    if state.template_revisions.latest(&id).is_none() {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    }
+    // Checked from every template, since others may include this one.
+    let lookup = |t: &str| {
+        if t == id {
+            Some(req.body.clone())
+        } else {
+            state.template_revisions.latest(t)
+        }
+    };
+    if let Err(e) = includes::validate_all(&lookup, &state.include_limits) {
+        info!(template_id = %id, error = %e, "template revision rejected");
+        return Err(e.into_response());
+    }
+    let revision = state
+        .template_revisions
+        .add(&id, req.body, req.note, state.clock.now())
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+    info!(template_id = %id, revision, "template revision added");
+    let revisions = state.template_revisions.list(&id).unwrap_or_default();
+    Ok((
+        StatusCode::CREATED,
+        Json(RevisionsResponse {
+            template_id: id,
+            revisions,
+        }),
+    ))
+}
+
+pub async fn diff_revisions(
+    State(state): State<AppState>,
+    Path((id, a, b)): Path<(String, u32, u32)>,
+) -> Result<Json<RevisionDiff>, StatusCode> {
+    let revisions = state
+        .template_revisions
+        .list(&id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let find = |rev: u32| {
+        revisions
+            .iter()
+            .find(|r| r.revision == rev)
+            .ok_or(StatusCode::NOT_FOUND)
+    };
+    let (from, to) = (find(a)?, find(b)?);
+    let (changes, summary) = diff_bodies(&from.body, &to.body);
+    Ok(Json(RevisionDiff {
+        template_id: id,
+        from_revision: a,
+        to_revision: b,
+        summary,
+        changes,
+    }))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_bodies_are_unchanged() {
+        let (changes, summary) = diff_bodies("A.\n\nB.", "A.\n\nB.");
+        assert_eq!(changes.len(), 2);
+        assert_eq!(summary.unchanged, 2);
+    }
+
+    #[test]
+    fn reordered_sections_are_moves() {
+        let (changes, summary) = diff_bodies(
+            "Intro.\n\nPayment terms.\n\nTermination.",
+            "Intro.\n\nTermination.\n\nPayment terms.",
+        );
+        assert_eq!(summary.moved, 1);
+        let moved = changes
+            .iter()
+            .find(|c| c.change == ChangeKind::Moved)
+            .unwrap();
+        assert!(moved
+            .annotations
+            .iter()
+            .any(|a| matches!(a, Annotation::SectionReordered { .. })));
+    }
+
+    #[test]
+    fn edited_clause_reports_variable_changes() {
+        let before = "HEADER\n\nThis Agreement is between {{party_a}} and {{party_b}} today.";
+        let after = "HEADER\n\nThis Agreement is between {{party_a}} and {{counterparty}} today.";
+        let (changes, summary) = diff_bodies(before, after);
+        assert_eq!(summary.modified, 1);
+        assert_eq!(summary.variables_added, vec!["counterparty"]);
+        assert_eq!(summary.variables_removed, vec!["party_b"]);
+        let modified = &changes[1];
+        assert_eq!(
+            modified.before.as_deref(),
+            Some(before.split("\n\n").nth(1).unwrap())
+        );
+        assert_eq!(modified.annotations.len(), 2);
+    }
+
+    #[test]
+    fn unrelated_clauses_are_added_and_removed_in_place() {
+        let (changes, summary) = diff_bodies(
+            "Keep.\n\nOld clause.",
+            "Keep.\n\nBrand new {{fee}} wording here.",
+        );
+        assert_eq!((summary.added, summary.removed), (1, 1));
+        assert_eq!(changes[1].change, ChangeKind::Added);
+        assert_eq!(changes[2].change, ChangeKind::Removed);
+        assert_eq!(
+            changes[1].annotations,
+            vec![Annotation::VariableAdded {
+                variable: "fee".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn revisions_seed_from_builtin_template() {
+        let store = RevisionStore::default();
+        assert_eq!(store.list("nda").unwrap().len(), 1);
+        assert_eq!(
+            store.add("nda", "New body".to_string(), None, Utc::now()),
+            Some(2)
+        );
+        assert!(store.list("missing").is_none());
+    }
+}
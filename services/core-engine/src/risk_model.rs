@@ -0,0 +1,404 @@
+//! The risk model behind `risk-score` and every roll-up built on it: the
+//! factors with their keywords, scores and weights, the level cutoffs, and
+//! the recommendations for each level. The built-in model applies unless
+//! `LEGAL_RISK_MODEL_FILE` names a TOML definition, which is read once at
+//! startup so risk policy changes with a restart rather than a rebuild.
+
+use axum::{extract::State, response::Json};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{AppState, RiskFactor};
+
+/// Allowed drift of the weight sum from 1.
+const WEIGHT_SUM_TOLERANCE: f64 = 0.001;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FactorDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub weight: f64,
+    /// Lowercase phrases; the factor scores `matched_score` when any occurs.
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub matched_score: f64,
+    #[serde(default)]
+    pub baseline_score: f64,
+    /// Words naming the factor's subject in an amendment, which may be
+    /// broader than the keywords; the keywords when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<String>,
+    /// Scores by length instead of keywords: word count over this, at most 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length_words: Option<u32>,
+}
+
+impl FactorDefinition {
+    pub fn is_length(&self) -> bool {
+        self.length_words.is_some()
+    }
+
+    /// Score for a lowercased document of `words` words.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn score(&self, lower: &str, words: usize) -> f64 {
+        match self.length_words {
+            Some(n) => (words as f64 / f64::from(n)).min(1.0),
+            None if self.keywords.iter().any(|k| lower.contains(k.as_str())) => self.matched_score,
+            None => self.baseline_score,
+        }
+    }
+
+    pub fn topics(&self) -> &[String] {
+        if self.topics.is_empty() {
+            &self.keywords
+        } else {
+            &self.topics
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LevelDefinition {
+    pub name: String,
+    /// Scores at or above this, and below the next level up, get the level.
+    pub min_score: f64,
+    #[serde(default)]
+    pub recommendations: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RiskModel {
+    /// Where the model came from: `built-in` or the file path.
+    #[serde(skip_deserializing)]
+    pub source: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    pub factors: Vec<FactorDefinition>,
+    /// Highest cutoff first.
+    pub levels: Vec<LevelDefinition>,
+}
+
+fn keyword_factor(
+    name: &str,
+    description: &str,
+    weight: f64,
+    keywords: &[&str],
+    topics: &[&str],
+    (matched_score, baseline_score): (f64, f64),
+) -> FactorDefinition {
+    let strings = |s: &[&str]| s.iter().map(|k| k.to_string()).collect();
+    FactorDefinition {
+        name: name.to_string(),
+        description: description.to_string(),
+        weight,
+        keywords: strings(keywords),
+        matched_score,
+        baseline_score,
+        topics: strings(topics),
+        length_words: None,
+    }
+}
+
+fn level(name: &str, min_score: f64, recommendations: &[&str]) -> LevelDefinition {
+    LevelDefinition {
+        name: name.to_string(),
+        min_score,
+        recommendations: recommendations.iter().map(|r| r.to_string()).collect(),
+    }
+}
+
+impl Default for RiskModel {
+    fn default() -> Self {
+        Self {
+            source: "built-in".to_string(),
+            version: None,
+            factors: vec![
+                keyword_factor(
+                    "Liability Clauses",
+                    "Provisions limiting or expanding liability exposure.",
+                    0.30,
+                    &["limitation of liability"],
+                    &["liabilit"],
+                    (0.8, 0.3),
+                ),
+                keyword_factor(
+                    "Indemnification",
+                    "Obligations to compensate for losses or damages.",
+                    0.25,
+                    &["indemnif"],
+                    &[],
+                    (0.7, 0.2),
+                ),
+                keyword_factor(
+                    "Termination Rights",
+                    "Conditions and notice requirements for contract termination.",
+                    0.20,
+                    &["terminat"],
+                    &[],
+                    (0.5, 0.4),
+                ),
+                keyword_factor(
+                    "IP Assignment",
+                    "Transfer or licensing of intellectual property rights.",
+                    0.15,
+                    &["intellectual property", "copyright"],
+                    &[],
+                    (0.6, 0.2),
+                ),
+                FactorDefinition {
+                    name: "Document Complexity".to_string(),
+                    description: "Risk from ambiguity correlated with document length.".to_string(),
+                    weight: 0.10,
+                    keywords: Vec::new(),
+                    matched_score: 0.0,
+                    baseline_score: 0.0,
+                    topics: Vec::new(),
+                    length_words: Some(10_000),
+                },
+            ],
+            levels: vec![
+                level(
+                    "critical",
+                    0.7,
+                    &[
+                        "Engage qualified legal counsel before signing.",
+                        "Negotiate liability cap to a fixed monetary amount.",
+                        "Request mutual indemnification rather than one-sided obligation.",
+                        "Add dispute resolution and arbitration clause.",
+                    ],
+                ),
+                level(
+                    "high",
+                    0.5,
+                    &[
+                        "Review indemnification scope with an attorney.",
+                        "Clarify IP ownership provisions.",
+                        "Ensure termination notice periods are reasonable.",
+                    ],
+                ),
+                level(
+                    "medium",
+                    0.3,
+                    &[
+                        "Verify jurisdiction and governing law aligns with your location.",
+                        "Confirm data retention periods meet regulatory requirements.",
+                    ],
+                ),
+                level(
+                    "low",
+                    0.0,
+                    &["Document appears low risk. Standard review recommended."],
+                ),
+            ],
+        }
+    }
+}
+
+// ── Loading ───────────────────────────────────────────────────────────────────
+
+impl RiskModel {
+    /// Reads `LEGAL_RISK_MODEL_FILE` when set. An unreadable or invalid
+    /// model stops startup rather than scoring with a policy nobody chose.
+    pub fn from_env() -> Self {
+        match std::env::var("LEGAL_RISK_MODEL_FILE") {
+            Ok(path) => {
+                let raw = std::fs::read_to_string(&path)
+                    .unwrap_or_else(|e| panic!("cannot read risk model {path}: {e}"));
+                let mut model =
+                    Self::parse(&raw).unwrap_or_else(|e| panic!("invalid risk model {path}: {e}"));
+                model.source = path;
+                model
+            }
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let mut model: Self = toml::from_str(raw).map_err(|e| e.to_string())?;
+        model.validate()?;
+        for f in &mut model.factors {
+            for k in f.keywords.iter_mut().chain(f.topics.iter_mut()) {
+                *k = k.to_lowercase();
+            }
+        }
+        model
+            .levels
+            .sort_by(|a, b| b.min_score.total_cmp(&a.min_score));
+        Ok(model)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let unit = |v: f64| (0.0..=1.0).contains(&v);
+        if self.factors.is_empty() {
+            return Err("at least one factor is required".to_string());
+        }
+        for (i, f) in self.factors.iter().enumerate() {
+            if f.name.trim().is_empty() {
+                return Err(format!("factor {} has no name", i + 1));
+            }
+            if self.factors[..i].iter().any(|g| g.name == f.name) {
+                return Err(format!("duplicate factor {}", f.name));
+            }
+            if !unit(f.weight) || !unit(f.matched_score) || !unit(f.baseline_score) {
+                return Err(format!(
+                    "{}: weight and scores must be within 0..=1",
+                    f.name
+                ));
+            }
+            match f.length_words {
+                Some(0) => return Err(format!("{}: length_words must be positive", f.name)),
+                Some(_) if !f.keywords.is_empty() => {
+                    return Err(format!(
+                        "{}: set keywords or length_words, not both",
+                        f.name
+                    ))
+                }
+                None if f.keywords.iter().all(|k| k.trim().is_empty()) => {
+                    return Err(format!("{}: keywords or length_words is required", f.name))
+                }
+                _ => {}
+            }
+        }
+        let sum: f64 = self.factors.iter().map(|f| f.weight).sum();
+        if (sum - 1.0).abs() > WEIGHT_SUM_TOLERANCE {
+            return Err(format!("factor weights sum to {sum}, not 1"));
+        }
+        for (i, l) in self.levels.iter().enumerate() {
+            if l.name.trim().is_empty() || !unit(l.min_score) {
+                return Err(format!(
+                    "level {} needs a name and a min_score within 0..=1",
+                    i + 1
+                ));
+            }
+            if self.levels[..i].iter().any(|m| m.name == l.name) {
+                return Err(format!("duplicate level {}", l.name));
+            }
+        }
+        if !self.levels.iter().any(|l| l.min_score == 0.0) {
+            return Err("one level needs min_score 0 so every score has a level".to_string());
+        }
+        Ok(())
+    }
+}
+
+// ── Scoring ───────────────────────────────────────────────────────────────────
+
+impl RiskModel {
+    /// Every factor scored against `document`, in model order.
+    pub fn factors(&self, document: &str) -> Vec<RiskFactor> {
+        let lower = document.to_lowercase();
+        let words = document.split_whitespace().count();
+        self.factors
+            .iter()
+            .map(|f| RiskFactor {
+                factor: f.name.clone(),
+                weight: f.weight,
+                score: f.score(&lower, words),
+                description: f.description.clone(),
+            })
+            .collect()
+    }
+
+    fn level_for(&self, score: f64) -> &LevelDefinition {
+        self.levels
+            .iter()
+            .find(|l| score >= l.min_score)
+            .or(self.levels.last())
+            .expect("a validated model has levels")
+    }
+
+    pub fn level(&self, score: f64) -> String {
+        self.level_for(score).name.clone()
+    }
+
+    pub fn recommendations(&self, level: &str) -> Vec<String> {
+        self.levels
+            .iter()
+            .find(|l| l.name == level)
+            .map(|l| l.recommendations.clone())
+            .unwrap_or_default()
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn active_model(State(state): State<AppState>) -> Json<RiskModel> {
+    Json(state.risk_model.as_ref().clone())
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = r#"
+version = "2024-q3"
+
+[[factors]]
+name = "Uncapped Liability"
+weight = 0.6
+keywords = ["Unlimited Liability"]
+matched_score = 0.9
+baseline_score = 0.1
+
+[[factors]]
+name = "Length"
+weight = 0.4
+length_words = 100
+
+[[levels]]
+name = "low"
+min_score = 0.0
+recommendations = ["Standard review."]
+
+[[levels]]
+name = "elevated"
+min_score = 0.4
+"#;
+
+    #[test]
+    fn builtin_model_scores_as_before() {
+        let model = RiskModel::default();
+        model.validate().unwrap();
+        let factors = model.factors("Limitation of Liability. The Vendor shall indemnify.");
+        let scores: Vec<f64> = factors.iter().map(|f| f.score).collect();
+        assert_eq!(&scores[..4], [0.8, 0.7, 0.4, 0.2]);
+        assert_eq!(model.level(0.7), "critical");
+        assert_eq!(model.level(0.29), "low");
+        assert_eq!(model.recommendations("high").len(), 3);
+    }
+
+    #[test]
+    fn file_models_replace_factors_and_levels() {
+        let model = RiskModel::parse(MODEL).unwrap();
+        assert_eq!(model.levels[0].name, "elevated");
+        let doc = "This agreement carries unlimited liability for both parties.";
+        let factors = model.factors(doc);
+        assert_eq!(factors[0].score, 0.9);
+        assert!((factors[1].score - 0.08).abs() < 1e-9);
+        let overall: f64 = factors.iter().map(|f| f.weight * f.score).sum();
+        assert_eq!(model.level(overall), "elevated");
+        assert!(model.recommendations("elevated").is_empty());
+        assert_eq!(model.recommendations("low"), ["Standard review."]);
+    }
+
+    #[test]
+    fn invalid_models_are_rejected() {
+        let err = RiskModel::parse(&MODEL.replace("weight = 0.4", "weight = 0.5")).unwrap_err();
+        assert!(err.contains("sum to"), "{err}");
+        let err =
+            RiskModel::parse(&MODEL.replace("min_score = 0.0", "min_score = 0.1")).unwrap_err();
+        assert!(err.contains("min_score 0"), "{err}");
+        let err = RiskModel::parse(&MODEL.replace("length_words = 100", "")).unwrap_err();
+        assert!(err.contains("keywords or length_words"), "{err}");
+        assert!(RiskModel::parse(&MODEL.replace("weight = 0.6", "wieght = 0.6")).is_err());
+    }
+}
@@ -0,0 +1,459 @@
+//! Read-only share links for a stored analysis or its document text. A link
+//! carries a random token, an expiry and optionally a password; it shows
+//! what the creator's role may see and nothing more. Only hashes of the
+//! token and password are kept, every access (granted or not) goes to the
+//! audit trail, and a revoked link stops working at once.
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+use crate::access::{self, ROLE_HEADER};
+use crate::auth;
+use crate::AppState;
+
+/// Shared links start with this so they are recognizable in logs and chats.
+pub const TOKEN_PREFIX: &str = "shr_";
+pub const PASSWORD_HEADER: &str = "x-share-password";
+const DEFAULT_TTL_SECS: i64 = 24 * 60 * 60;
+const MAX_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareKind {
+    /// The analysis record, filtered by the creator's role.
+    #[default]
+    Analysis,
+    /// The document text as a download.
+    Document,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ShareRequest {
+    #[serde(default)]
+    pub kind: ShareKind,
+    #[serde(default)]
+    pub expires_in_secs: Option<i64>,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShareLink {
+    pub share_id: String,
+    pub analysis_id: String,
+    pub kind: ShareKind,
+    /// Role whose field policy applies to the shared view.
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub password_protected: bool,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub access_count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedShare {
+    #[serde(flatten)]
+    pub link: ShareLink,
+    /// Shown once; only its hash is stored.
+    pub token: String,
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SharesResponse {
+    pub shares: Vec<ShareLink>,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Denied {
+    Unknown,
+    Revoked,
+    Expired,
+    Password,
+}
+
+impl Denied {
+    fn status(self) -> StatusCode {
+        match self {
+            Self::Unknown => StatusCode::NOT_FOUND,
+            Self::Revoked | Self::Expired => StatusCode::GONE,
+            Self::Password => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    fn reason(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Revoked => "revoked",
+            Self::Expired => "expired",
+            Self::Password => "password",
+        }
+    }
+}
+
+struct Share {
+    link: ShareLink,
+    password_hash: Option<String>,
+}
+
+// ── Hashing ───────────────────────────────────────────────────────────────────
+
+pub fn digest(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Equal-length comparison that does not stop at the first difference.
+pub fn same(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+/// Shares by token hash; `ids` maps share IDs back for listing and revocation.
+#[derive(Default)]
+pub struct ShareStore {
+    shares: DashMap<String, Share>,
+    ids: DashMap<String, String>,
+}
+
+impl ShareStore {
+    pub fn insert(&self, token: &str, link: ShareLink, password: Option<&str>) {
+        let password_hash = password.map(|p| digest(&[&link.share_id, p]));
+        let key = digest(&[token]);
+        self.ids.insert(link.share_id.clone(), key.clone());
+        self.shares.insert(
+            key,
+            Share {
+                link,
+                password_hash,
+            },
+        );
+    }
+
+    /// Checks the token and password and counts the access.
+    pub fn open(
+        &self,
+        token: &str,
+        password: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<ShareLink, (Denied, Option<Box<ShareLink>>)> {
+        let mut share = self
+            .shares
+            .get_mut(&digest(&[token]))
+            .ok_or((Denied::Unknown, None))?;
+        let denied = if share.link.revoked_at.is_some() {
+            Some(Denied::Revoked)
+        } else if now >= share.link.expires_at {
+            Some(Denied::Expired)
+        } else {
+            share.password_hash.as_ref().and_then(|expected| {
+                let given = digest(&[&share.link.share_id, password.unwrap_or_default()]);
+                (!same(expected, &given)).then_some(Denied::Password)
+            })
+        };
+        match denied {
+            Some(d) => Err((d, Some(Box::new(share.link.clone())))),
+            None => {
+                share.link.access_count += 1;
+                Ok(share.link.clone())
+            }
+        }
+    }
+
+    pub fn link(&self, share_id: &str) -> Option<ShareLink> {
+        let key = self.ids.get(share_id)?;
+        let share = self.shares.get(key.value())?;
+        Some(share.link.clone())
+    }
+
+    pub fn revoke(&self, share_id: &str, now: DateTime<Utc>) -> Option<ShareLink> {
+        let key = self.ids.get(share_id)?;
+        let mut share = self.shares.get_mut(key.value())?;
+        share.link.revoked_at.get_or_insert(now);
+        Some(share.link.clone())
+    }
+
+    /// Deletes every link to an analysis and returns how many there were.
+    pub fn remove_for(&self, analysis_id: &str) -> usize {
+        let before = self.shares.len();
+        self.shares.retain(|_, s| s.link.analysis_id != analysis_id);
+        self.ids
+            .retain(|_, key| self.shares.contains_key(key.as_str()));
+        before - self.shares.len()
+    }
+
+    pub fn for_analysis(&self, analysis_id: &str) -> Vec<ShareLink> {
+        let mut links: Vec<ShareLink> = self
+            .shares
+            .iter()
+            .filter(|s| s.link.analysis_id == analysis_id)
+            .map(|s| s.link.clone())
+            .collect();
+        links.sort_by_key(|l| l.created_at);
+        links
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+fn role(headers: &HeaderMap) -> Option<&str> {
+    headers.get(ROLE_HEADER).and_then(|v| v.to_str().ok())
+}
+
+pub async fn create_share(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ShareRequest>,
+) -> Result<(StatusCode, Json<CreatedShare>), StatusCode> {
+    let role = role(&headers).ok_or(StatusCode::FORBIDDEN)?;
+    let allowed = state
+        .access_policy
+        .roles
+        .get(role)
+        .ok_or(StatusCode::FORBIDDEN)?;
+    if auth::owned_document(&state, &headers, &id).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    // A download hands over the whole text, so the role must already see it.
+    if req.kind == ShareKind::Document && !allowed.iter().any(|p| p == "*" || p == "text") {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let ttl = req.expires_in_secs.unwrap_or(DEFAULT_TTL_SECS);
+    if !(1..=MAX_TTL_SECS).contains(&ttl) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    if req.password.as_deref().is_some_and(str::is_empty) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let now = state.clock.now();
+    let token = format!(
+        "{TOKEN_PREFIX}{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+    let link = ShareLink {
+        share_id: format!("shr-{}", state.ids.next_id()),
+        analysis_id: id.clone(),
+        kind: req.kind,
+        role: role.to_string(),
+        created_at: now,
+        expires_at: now + Duration::seconds(ttl),
+        password_protected: req.password.is_some(),
+        revoked_at: None,
+        access_count: 0,
+    };
+    state
+        .shares
+        .insert(&token, link.clone(), req.password.as_deref());
+    info!(share_id = %link.share_id, analysis_id = %id, kind = ?link.kind, "share link created");
+    state.record_audit(
+        "share.created",
+        &id,
+        Some(role),
+        json!({
+            "share_id": link.share_id,
+            "kind": link.kind,
+            "expires_at": link.expires_at,
+            "password_protected": link.password_protected,
+        }),
+    );
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedShare {
+            url: format!("/api/v1/legal/shared/{token}"),
+            token,
+            link,
+        }),
+    ))
+}
+
+pub async fn list_shares(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<SharesResponse>, StatusCode> {
+    if auth::owned_document(&state, &headers, &id).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let shares = state.shares.for_analysis(&id);
+    Ok(Json(SharesResponse {
+        count: shares.len(),
+        shares,
+    }))
+}
+
+pub async fn revoke_share(
+    State(state): State<AppState>,
+    Path(share_id): Path<String>,
+    headers: HeaderMap,
+) -> StatusCode {
+    let owned = state
+        .shares
+        .link(&share_id)
+        .is_some_and(|l| auth::owned_document(&state, &headers, &l.analysis_id).is_some());
+    if !owned {
+        return StatusCode::NOT_FOUND;
+    }
+    let Some(link) = state.shares.revoke(&share_id, state.clock.now()) else {
+        return StatusCode::NOT_FOUND;
+    };
+    info!(share_id = %share_id, "share link revoked");
+    state.record_audit(
+        "share.revoked",
+        &link.analysis_id,
+        role(&headers),
+        json!({ "share_id": share_id }),
+    );
+    StatusCode::NO_CONTENT
+}
+
+pub async fn open_share(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let password = headers.get(PASSWORD_HEADER).and_then(|v| v.to_str().ok());
+    let link = match state.shares.open(&token, password, state.clock.now()) {
+        Ok(link) => link,
+        Err((denied, link)) => {
+            if let Some(link) = link {
+                state.record_audit(
+                    "share.denied",
+                    &link.analysis_id,
+                    None,
+                    json!({ "share_id": link.share_id, "reason": denied.reason() }),
+                );
+            }
+            return Err(denied.status());
+        }
+    };
+    // The stored document may have been removed since the link was made.
+    let doc = state
+        .corpus
+        .get(&link.analysis_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    state.record_audit(
+        "share.accessed",
+        &link.analysis_id,
+        None,
+        json!({ "share_id": link.share_id, "kind": link.kind }),
+    );
+
+    match link.kind {
+        ShareKind::Document => Ok((
+            [
+                (
+                    header::CONTENT_TYPE,
+                    "text/plain; charset=utf-8".to_string(),
+                ),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}.txt\"", link.analysis_id),
+                ),
+            ],
+            doc.text,
+        )
+            .into_response()),
+        ShareKind::Analysis => {
+            // Policy is read now, so narrowing a role also narrows its links.
+            let allowed = state
+                .access_policy
+                .roles
+                .get(&link.role)
+                .ok_or(StatusCode::FORBIDDEN)?;
+            let full = serde_json::to_value(access::record(&state, doc))
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            Ok(Json(access::filter(&full, allowed)).into_response())
+        }
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(now: DateTime<Utc>) -> ShareLink {
+        ShareLink {
+            share_id: "shr-1".to_string(),
+            analysis_id: "doc-1".to_string(),
+            kind: ShareKind::Analysis,
+            role: "sales".to_string(),
+            created_at: now,
+            expires_at: now + Duration::hours(1),
+            password_protected: true,
+            revoked_at: None,
+            access_count: 0,
+        }
+    }
+
+    #[test]
+    fn open_checks_password_expiry_and_revocation() {
+        let now = Utc::now();
+        let store = ShareStore::default();
+        store.insert("shr_secret", link(now), Some("hunter22"));
+
+        let denied = |r: Result<ShareLink, (Denied, Option<Box<ShareLink>>)>| r.unwrap_err().0;
+        assert_eq!(denied(store.open("shr_other", None, now)), Denied::Unknown);
+        assert_eq!(
+            denied(store.open("shr_secret", None, now)),
+            Denied::Password
+        );
+        assert_eq!(
+            denied(store.open("shr_secret", Some("hunter2"), now)),
+            Denied::Password
+        );
+        let opened = store.open("shr_secret", Some("hunter22"), now).unwrap();
+        assert_eq!(opened.access_count, 1);
+        assert_eq!(
+            denied(store.open("shr_secret", Some("hunter22"), now + Duration::hours(1))),
+            Denied::Expired
+        );
+
+        assert!(store.revoke("shr-1", now).is_some());
+        assert!(store.revoke("shr-2", now).is_none());
+        assert_eq!(
+            denied(store.open("shr_secret", Some("hunter22"), now)),
+            Denied::Revoked
+        );
+        assert_eq!(store.for_analysis("doc-1")[0].revoked_at, Some(now));
+    }
+
+    #[test]
+    fn secrets_are_stored_hashed() {
+        assert_eq!(digest(&["a"]).len(), 64);
+        assert_ne!(digest(&["ab", "c"]), digest(&["a", "bc"]));
+        assert!(same("abc", "abc"));
+        assert!(!same("abc", "abd"));
+        assert!(!same("abc", "ab"));
+    }
+}
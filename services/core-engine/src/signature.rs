@@ -0,0 +1,691 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use crate::{
+    auth,
+    authority::{self, AuthorityMatrix, Deal},
+    evidence::DEFAULT_CONTEXT_CHARS,
+    export_control, AppState, Issue,
+};
+
+// Markers drafters leave behind for values still to be filled in.
+const PLACEHOLDER_MARKERS: [&str; 5] = ["{{", "[●]", "[insert", "[tbd", "____"];
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GateCheck {
+    NoUnresolvedPlaceholders,
+    NoCriticalIssues,
+    ApprovalsComplete,
+    SignatoryEntities,
+    SigningAuthority,
+}
+
+impl GateCheck {
+    const ALL: [GateCheck; 5] = [
+        GateCheck::NoUnresolvedPlaceholders,
+        GateCheck::NoCriticalIssues,
+        GateCheck::ApprovalsComplete,
+        GateCheck::SignatoryEntities,
+        GateCheck::SigningAuthority,
+    ];
+
+    /// The checks that still apply once a document has been signed.
+    const EXECUTION: [GateCheck; 2] = [GateCheck::SignatoryEntities, GateCheck::SigningAuthority];
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "no_unresolved_placeholders" => Some(Self::NoUnresolvedPlaceholders),
+            "no_critical_issues" => Some(Self::NoCriticalIssues),
+            "approvals_complete" => Some(Self::ApprovalsComplete),
+            "signatory_entities" => Some(Self::SignatoryEntities),
+            "signing_authority" => Some(Self::SigningAuthority),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SignatureGateConfig {
+    pub checks: Vec<GateCheck>,
+    /// Approval roles that must all be recorded before sending.
+    pub required_approvals: Vec<String>,
+}
+
+impl Default for SignatureGateConfig {
+    fn default() -> Self {
+        Self {
+            checks: GateCheck::ALL.to_vec(),
+            required_approvals: vec!["legal".to_string()],
+        }
+    }
+}
+
+fn env_list(key: &str) -> Option<Vec<String>> {
+    std::env::var(key).ok().map(|v| {
+        v.split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    })
+}
+
+impl SignatureGateConfig {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        Self {
+            checks: env_list("LEGAL_SIGNATURE_CHECKS").map_or(d.checks, |l| {
+                l.iter().filter_map(|c| GateCheck::parse(c)).collect()
+            }),
+            required_approvals: env_list("LEGAL_SIGNATURE_REQUIRED_APPROVALS")
+                .unwrap_or(d.required_approvals),
+        }
+    }
+}
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Signatory {
+    pub name: String,
+    /// Legal entity the signatory signs for; must be a party named in the document.
+    pub entity: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Approval {
+    pub approver: String,
+    pub role: String,
+    pub approved_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApprovalRequest {
+    pub approver: String,
+    pub role: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GateOverride {
+    pub check: GateCheck,
+    pub reason: String,
+    pub approved_by: String,
+}
+
+/// Override what the signing authority check reads from the document: its
+/// largest currency amount and its title.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DealTerms {
+    pub contract_value: Option<f64>,
+    pub contract_type: Option<String>,
+}
+
+impl DealTerms {
+    fn deal(&self, text: &str) -> Deal {
+        Deal {
+            value: self
+                .contract_value
+                .or_else(|| authority::largest_amount(text)),
+            contract_type: self
+                .contract_type
+                .clone()
+                .unwrap_or_else(|| authority::title(text)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GateRequest {
+    #[serde(default)]
+    pub signatories: Vec<Signatory>,
+    #[serde(flatten)]
+    pub terms: DealTerms,
+}
+
+/// Sending for signature, or recording that a document was executed.
+#[derive(Debug, Deserialize)]
+pub struct SendRequest {
+    #[serde(default)]
+    pub signatories: Vec<Signatory>,
+    #[serde(default)]
+    pub overrides: Vec<GateOverride>,
+    pub requested_by: Option<String>,
+    #[serde(flatten)]
+    pub terms: DealTerms,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub check: GateCheck,
+    pub passed: bool,
+    pub details: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overridden: Option<GateOverride>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GateResult {
+    pub document_id: String,
+    pub passed: bool,
+    pub checks: Vec<CheckResult>,
+    pub audit_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Dispatch {
+    pub envelope_id: String,
+    pub document_id: String,
+    pub signatories: Vec<Signatory>,
+    pub sent_at: DateTime<Utc>,
+    pub gate: GateResult,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Execution {
+    pub document_id: String,
+    pub signatories: Vec<Signatory>,
+    pub executed_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recorded_by: Option<String>,
+    pub gate: GateResult,
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct SignatureStore {
+    approvals: DashMap<String, Vec<Approval>>,
+    dispatches: DashMap<String, Dispatch>,
+    executions: DashMap<String, Execution>,
+}
+
+impl SignatureStore {
+    /// Drops a document's approvals, dispatch and execution record; returns
+    /// how many of each.
+    pub fn remove(&self, document_id: &str) -> (usize, usize, usize) {
+        let approvals = self
+            .approvals
+            .remove(document_id)
+            .map_or(0, |(_, a)| a.len());
+        let dispatches = usize::from(self.dispatches.remove(document_id).is_some());
+        let executions = usize::from(self.executions.remove(document_id).is_some());
+        (approvals, dispatches, executions)
+    }
+
+    /// Runs `f` over every approval along with its document ID.
+    pub fn for_each_approval_mut(&self, mut f: impl FnMut(&str, &mut Approval)) {
+        for mut approvals in self.approvals.iter_mut() {
+            let (document_id, approvals) = approvals.pair_mut();
+            approvals.iter_mut().for_each(|a| f(document_id, a));
+        }
+    }
+
+    pub fn for_each_dispatch_mut(&self, mut f: impl FnMut(&mut Dispatch)) {
+        self.dispatches
+            .iter_mut()
+            .for_each(|mut d| f(d.value_mut()));
+    }
+
+    pub fn for_each_execution_mut(&self, mut f: impl FnMut(&mut Execution)) {
+        self.executions
+            .iter_mut()
+            .for_each(|mut e| f(e.value_mut()));
+    }
+}
+
+// ── Checks ────────────────────────────────────────────────────────────────────
+
+fn placeholders(text: &str) -> Vec<String> {
+    let lower = text.to_lowercase();
+    PLACEHOLDER_MARKERS
+        .iter()
+        .filter(|m| lower.contains(*m))
+        .map(|m| format!("unresolved placeholder marker \"{m}\""))
+        .collect()
+}
+
+fn critical_issues(issues: &[Issue]) -> Vec<String> {
+    issues
+        .iter()
+        .filter(|i| i.severity == "critical")
+        .map(|i| format!("{}: {}", i.id, i.description))
+        .collect()
+}
+
+fn missing_approvals(required: &[String], approvals: &[Approval]) -> Vec<String> {
+    required
+        .iter()
+        .filter(|role| !approvals.iter().any(|a| a.role.eq_ignore_ascii_case(role)))
+        .map(|role| format!("missing approval from role \"{role}\""))
+        .collect()
+}
+
+fn signatory_problems(text: &str, signatories: &[Signatory]) -> Vec<String> {
+    if signatories.is_empty() {
+        return vec!["no signatories given".to_string()];
+    }
+    let lower = text.to_lowercase();
+    signatories
+        .iter()
+        .filter(|s| s.entity.trim().is_empty() || !lower.contains(&s.entity.trim().to_lowercase()))
+        .map(|s| {
+            format!(
+                "{} signs for \"{}\", which is not a party to the document",
+                s.name, s.entity
+            )
+        })
+        .collect()
+}
+
+/// Runs the configured checks. A failed check counts as passed only when an
+/// override for it is supplied. Signing authority passes when the tenant
+/// has no `authority` matrix.
+pub fn evaluate(
+    config: &SignatureGateConfig,
+    text: &str,
+    issues: &[Issue],
+    approvals: &[Approval],
+    signatories: &[Signatory],
+    authority: Option<(&AuthorityMatrix, &Deal)>,
+    overrides: &[GateOverride],
+) -> (bool, Vec<CheckResult>) {
+    let checks: Vec<CheckResult> = config
+        .checks
+        .iter()
+        .map(|&check| {
+            let details = match check {
+                GateCheck::NoUnresolvedPlaceholders => placeholders(text),
+                GateCheck::NoCriticalIssues => critical_issues(issues),
+                GateCheck::ApprovalsComplete => {
+                    missing_approvals(&config.required_approvals, approvals)
+                }
+                GateCheck::SignatoryEntities => signatory_problems(text, signatories),
+                GateCheck::SigningAuthority => authority
+                    .map(|(matrix, deal)| matrix.violations(signatories, deal))
+                    .unwrap_or_default(),
+            };
+            let passed = details.is_empty();
+            CheckResult {
+                check,
+                passed,
+                details,
+                overridden: if passed {
+                    None
+                } else {
+                    overrides.iter().find(|o| o.check == check).cloned()
+                },
+            }
+        })
+        .collect();
+    let passed = checks.iter().all(|c| c.passed || c.overridden.is_some());
+    (passed, checks)
+}
+
+fn document_issues(state: &AppState, id: &str, text: &str) -> Vec<Issue> {
+    // Prefer the reviewed analysis so rejected findings no longer block signing.
+    if let Some(analysis) = state.escalations.analysis(id) {
+        return analysis.issues;
+    }
+    export_control::issues(text, &state.export_policy.get(), 1, DEFAULT_CONTEXT_CHARS)
+}
+
+fn run_gate(
+    state: &AppState,
+    headers: &HeaderMap,
+    id: &str,
+    config: &SignatureGateConfig,
+    req: &SendRequest,
+) -> Result<GateResult, StatusCode> {
+    let doc = auth::owned_document(state, headers, id).ok_or(StatusCode::NOT_FOUND)?;
+    let issues = document_issues(state, id, &doc.text);
+    let approvals = state
+        .signatures
+        .approvals
+        .get(id)
+        .map(|a| a.value().clone())
+        .unwrap_or_default();
+    let matrix = state.authority.get(&doc.tenant);
+    let deal = req.terms.deal(&doc.text);
+    let (passed, checks) = evaluate(
+        config,
+        &doc.text,
+        &issues,
+        &approvals,
+        &req.signatories,
+        matrix.as_ref().map(|m| (m, &deal)),
+        &req.overrides,
+    );
+    let audit = state.record_audit(
+        "signature_gate.evaluated",
+        id,
+        req.requested_by.as_deref(),
+        json!({ "passed": passed, "checks": checks }),
+    );
+    Ok(GateResult {
+        document_id: id.to_string(),
+        passed,
+        checks,
+        audit_id: audit.id,
+    })
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn add_approval(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ApprovalRequest>,
+) -> Result<(StatusCode, Json<Approval>), StatusCode> {
+    if req.approver.trim().is_empty() || req.role.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    auth::owned_document(&state, &headers, &id).ok_or(StatusCode::NOT_FOUND)?;
+    let approval = Approval {
+        approver: req.approver,
+        role: req.role,
+        approved_at: state.clock.now(),
+    };
+    state
+        .signatures
+        .approvals
+        .entry(id.clone())
+        .or_default()
+        .push(approval.clone());
+    state.record_audit(
+        "approval.recorded",
+        &id,
+        Some(&approval.approver),
+        json!({ "role": approval.role }),
+    );
+    Ok((StatusCode::CREATED, Json(approval)))
+}
+
+/// Dry run: evaluates the gate without sending anything.
+pub async fn check_gate(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<GateRequest>,
+) -> Result<Json<GateResult>, StatusCode> {
+    let req = SendRequest {
+        signatories: req.signatories,
+        overrides: Vec::new(),
+        requested_by: None,
+        terms: req.terms,
+    };
+    run_gate(&state, &headers, &id, &state.signature_gate, &req).map(Json)
+}
+
+/// The gate under `config`, with its overrides audited; `Err` with the
+/// `412` response when it fails.
+fn gated(
+    state: &AppState,
+    headers: &HeaderMap,
+    id: &str,
+    config: &SignatureGateConfig,
+    req: &SendRequest,
+) -> Result<Result<GateResult, Response>, StatusCode> {
+    if req
+        .overrides
+        .iter()
+        .any(|o| o.reason.trim().is_empty() || o.approved_by.trim().is_empty())
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let gate = run_gate(state, headers, id, config, req)?;
+    for check in gate.checks.iter().filter(|c| c.overridden.is_some()) {
+        state.record_audit(
+            "signature_gate.overridden",
+            id,
+            check.overridden.as_ref().map(|o| o.approved_by.as_str()),
+            json!({ "check": check.check, "details": check.details, "override": check.overridden }),
+        );
+    }
+    if !gate.passed {
+        return Ok(Err(
+            (StatusCode::PRECONDITION_FAILED, Json(gate)).into_response()
+        ));
+    }
+    Ok(Ok(gate))
+}
+
+pub async fn send_for_signature(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<SendRequest>,
+) -> Result<Response, StatusCode> {
+    if state.signatures.dispatches.contains_key(&id) {
+        return Err(StatusCode::CONFLICT);
+    }
+    let gate = match gated(&state, &headers, &id, &state.signature_gate, &req)? {
+        Ok(gate) => gate,
+        Err(blocked) => {
+            info!(document_id = %id, "send for signature blocked by pre-signature gate");
+            return Ok(blocked);
+        }
+    };
+    let actor = req.requested_by.as_deref();
+
+    let dispatch = Dispatch {
+        envelope_id: format!("env-{}", state.ids.next_id()),
+        document_id: id.clone(),
+        signatories: req.signatories,
+        sent_at: state.clock.now(),
+        gate,
+    };
+    state
+        .signatures
+        .dispatches
+        .insert(id.clone(), dispatch.clone());
+    state.record_audit(
+        "signature.sent",
+        &id,
+        actor,
+        json!({ "envelope_id": dispatch.envelope_id, "signatories": dispatch.signatories }),
+    );
+    info!(document_id = %id, envelope_id = %dispatch.envelope_id, "document sent for signature");
+    Ok(Json(dispatch).into_response())
+}
+
+/// Records that a document was signed, after checking its signatories'
+/// entities and signing authority.
+pub async fn record_execution(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<SendRequest>,
+) -> Result<Response, StatusCode> {
+    if state.signatures.executions.contains_key(&id) {
+        return Err(StatusCode::CONFLICT);
+    }
+    let config = SignatureGateConfig {
+        checks: state
+            .signature_gate
+            .checks
+            .iter()
+            .copied()
+            .filter(|c| GateCheck::EXECUTION.contains(c))
+            .collect(),
+        required_approvals: Vec::new(),
+    };
+    let gate = match gated(&state, &headers, &id, &config, &req)? {
+        Ok(gate) => gate,
+        Err(blocked) => {
+            info!(document_id = %id, "execution record blocked by signing authority");
+            return Ok(blocked);
+        }
+    };
+    let execution = Execution {
+        document_id: id.clone(),
+        signatories: req.signatories,
+        executed_at: state.clock.now(),
+        recorded_by: req.requested_by,
+        gate,
+    };
+    state
+        .signatures
+        .executions
+        .insert(id.clone(), execution.clone());
+    if let Some(doc) = state.corpus.get(&id) {
+        state
+            .template_analytics
+            .record_signature(&doc.tenant, &doc.text, execution.executed_at);
+    }
+    state.record_audit(
+        "signature.executed",
+        &id,
+        execution.recorded_by.as_deref(),
+        json!({ "signatories": execution.signatories }),
+    );
+    info!(document_id = %id, "document recorded as executed");
+    Ok((StatusCode::CREATED, Json(execution)).into_response())
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "SERVICES AGREEMENT between Acme Corp and Beta GmbH. Fees: USD 10,000.";
+
+    fn signatory(entity: &str) -> Signatory {
+        Signatory {
+            name: "Jane Roe".to_string(),
+            entity: entity.to_string(),
+        }
+    }
+
+    fn approval(role: &str) -> Approval {
+        Approval {
+            approver: "counsel".to_string(),
+            role: role.to_string(),
+            approved_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn clean_document_passes() {
+        let (passed, checks) = evaluate(
+            &SignatureGateConfig::default(),
+            DOC,
+            &[],
+            &[approval("Legal")],
+            &[signatory("Acme Corp"), signatory("beta gmbh")],
+            None,
+            &[],
+        );
+        assert!(passed, "{checks:?}");
+    }
+
+    #[test]
+    fn placeholders_and_wrong_entity_fail() {
+        let text = format!("{DOC} Effective {{{{effective_date}}}}.");
+        let (passed, checks) = evaluate(
+            &SignatureGateConfig::default(),
+            &text,
+            &[],
+            &[approval("legal")],
+            &[signatory("Acme Holdings Ltd")],
+            None,
+            &[],
+        );
+        assert!(!passed);
+        let failed: Vec<GateCheck> = checks
+            .iter()
+            .filter(|c| !c.passed)
+            .map(|c| c.check)
+            .collect();
+        assert_eq!(
+            failed,
+            vec![
+                GateCheck::NoUnresolvedPlaceholders,
+                GateCheck::SignatoryEntities
+            ]
+        );
+    }
+
+    #[test]
+    fn override_lets_a_failed_check_through() {
+        let overrides = vec![GateOverride {
+            check: GateCheck::ApprovalsComplete,
+            reason: "GC approved by phone".to_string(),
+            approved_by: "gc".to_string(),
+        }];
+        let (passed, checks) = evaluate(
+            &SignatureGateConfig::default(),
+            DOC,
+            &[],
+            &[],
+            &[signatory("Acme Corp")],
+            None,
+            &overrides,
+        );
+        assert!(passed);
+        let approvals = checks
+            .iter()
+            .find(|c| c.check == GateCheck::ApprovalsComplete)
+            .unwrap();
+        assert!(!approvals.passed);
+        assert!(approvals.overridden.is_some());
+    }
+
+    #[test]
+    fn signatories_beyond_their_authority_fail() {
+        let matrix = AuthorityMatrix {
+            signers: vec![crate::authority::Signer {
+                name: "Jane Roe".to_string(),
+                title: None,
+                entity: "Acme Corp".to_string(),
+                max_value: Some(5_000.0),
+                contract_types: Vec::new(),
+                reports_to: None,
+            }],
+        };
+        let deal = DealTerms::default().deal(DOC);
+        assert_eq!(deal.value, Some(10_000.0));
+        let run = |deal: &Deal| {
+            evaluate(
+                &SignatureGateConfig::default(),
+                DOC,
+                &[],
+                &[approval("legal")],
+                &[signatory("Acme Corp"), signatory("Beta GmbH")],
+                Some((&matrix, deal)),
+                &[],
+            )
+        };
+        let (passed, checks) = run(&deal);
+        assert!(!passed);
+        let failed = checks.iter().find(|c| !c.passed).unwrap();
+        assert_eq!(failed.check, GateCheck::SigningAuthority);
+        assert_eq!(failed.details.len(), 1, "{:?}", failed.details);
+
+        let small = DealTerms {
+            contract_value: Some(4_000.0),
+            contract_type: None,
+        };
+        assert!(run(&small.deal(DOC)).0);
+    }
+
+    #[test]
+    fn check_names_parse() {
+        assert_eq!(
+            GateCheck::parse("no_critical_issues"),
+            Some(GateCheck::NoCriticalIssues)
+        );
+        assert_eq!(GateCheck::parse("bogus"), None);
+    }
+}
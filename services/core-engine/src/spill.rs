@@ -0,0 +1,198 @@
+//! Low-memory mode for batch work: intermediate artifacts stay in memory up
+//! to a ceiling, and anything beyond it is written to an anonymous temp file
+//! and read back through a memory map.
+
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, Write},
+    ops::Range,
+    path::PathBuf,
+};
+
+use memmap2::Mmap;
+use serde::Serialize;
+
+#[derive(Debug, Clone)]
+pub struct MemoryConfig {
+    /// Bytes of artifacts kept in memory per batch; `None` never spills.
+    pub ceiling_bytes: Option<usize>,
+    pub spill_dir: PathBuf,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            ceiling_bytes: None,
+            spill_dir: std::env::temp_dir(),
+        }
+    }
+}
+
+impl MemoryConfig {
+    /// `LEGAL_LOW_MEMORY_CEILING_MB` turns low-memory mode on.
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        Self {
+            ceiling_bytes: std::env::var("LEGAL_LOW_MEMORY_CEILING_MB")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|mb| *mb > 0)
+                .map(|mb| mb * 1024 * 1024),
+            spill_dir: std::env::var("LEGAL_SPILL_DIR")
+                .map(PathBuf::from)
+                .unwrap_or(d.spill_dir),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpillReport {
+    pub ceiling_bytes: usize,
+    pub peak_resident_bytes: usize,
+    pub spilled_artifacts: usize,
+    pub spilled_bytes: usize,
+}
+
+enum Slot {
+    Resident(String),
+    Spilled(Range<usize>),
+    Released,
+}
+
+/// Texts indexed in push order, resident or spilled depending on the ceiling.
+pub struct ArtifactBuffer {
+    ceiling: Option<usize>,
+    dir: PathBuf,
+    slots: Vec<Slot>,
+    resident: usize,
+    peak: usize,
+    file: Option<File>,
+    written: usize,
+    map: Option<Mmap>,
+    spilled: usize,
+}
+
+impl ArtifactBuffer {
+    pub fn new(config: &MemoryConfig) -> Self {
+        Self {
+            ceiling: config.ceiling_bytes,
+            dir: config.spill_dir.clone(),
+            slots: Vec::new(),
+            resident: 0,
+            peak: 0,
+            file: None,
+            written: 0,
+            map: None,
+            spilled: 0,
+        }
+    }
+
+    pub fn push(&mut self, text: String) -> io::Result<usize> {
+        let len = text.len();
+        let fits = self.ceiling.is_none_or(|c| self.resident + len <= c);
+        let slot = if fits {
+            self.resident += len;
+            self.peak = self.peak.max(self.resident);
+            Slot::Resident(text)
+        } else {
+            let file = match &mut self.file {
+                Some(f) => f,
+                // Unlinked on creation, so nothing is left behind on a crash.
+                None => self.file.insert(tempfile::tempfile_in(&self.dir)?),
+            };
+            file.write_all(text.as_bytes())?;
+            let range = self.written..self.written + len;
+            self.written += len;
+            self.spilled += 1;
+            Slot::Spilled(range)
+        };
+        self.slots.push(slot);
+        Ok(self.slots.len() - 1)
+    }
+
+    pub fn get(&mut self, index: usize) -> io::Result<Cow<'_, str>> {
+        let range = match self.slots.get(index) {
+            Some(Slot::Resident(text)) => return Ok(Cow::Borrowed(text.as_str())),
+            Some(Slot::Spilled(range)) => range.clone(),
+            Some(Slot::Released) | None => {
+                return Err(io::Error::new(io::ErrorKind::NotFound, "artifact released"))
+            }
+        };
+        if self.map.as_ref().is_none_or(|m| m.len() < range.end) {
+            let file = self.file.as_mut().expect("spilled slot implies a file");
+            file.flush()?;
+            // SAFETY: the file is an unlinked temp file owned by this buffer;
+            // it is only ever appended to through `file`, so mapped bytes are
+            // never truncated or rewritten while the map is alive.
+            self.map = Some(unsafe { Mmap::map(&*file)? });
+        }
+        let map = self.map.as_ref().expect("mapped above");
+        std::str::from_utf8(&map[range])
+            .map(Cow::Borrowed)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Drops an artifact that is no longer needed, freeing its share of the ceiling.
+    pub fn release(&mut self, index: usize) {
+        if let Some(slot) = self.slots.get_mut(index) {
+            if let Slot::Resident(text) = slot {
+                self.resident -= text.len();
+            }
+            *slot = Slot::Released;
+        }
+    }
+
+    /// `None` unless low-memory mode is on.
+    pub fn report(&self) -> Option<SpillReport> {
+        Some(SpillReport {
+            ceiling_bytes: self.ceiling?,
+            peak_resident_bytes: self.peak,
+            spilled_artifacts: self.spilled,
+            spilled_bytes: self.written,
+        })
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spills_past_the_ceiling_and_reads_back() {
+        let mut buf = ArtifactBuffer::new(&MemoryConfig {
+            ceiling_bytes: Some(10),
+            ..MemoryConfig::default()
+        });
+        let a = buf.push("12345678".to_string()).unwrap();
+        let b = buf.push("spilled one".to_string()).unwrap();
+        let c = buf.push("and two — ü".to_string()).unwrap();
+        assert_eq!(buf.get(b).unwrap(), "spilled one");
+        assert_eq!(buf.get(c).unwrap(), "and two — ü");
+        assert_eq!(buf.get(a).unwrap(), "12345678");
+
+        // Releasing frees room for the next artifact to stay resident.
+        buf.release(a);
+        let d = buf.push("tiny".to_string()).unwrap();
+        assert_eq!(buf.get(d).unwrap(), "tiny");
+        assert!(buf.get(a).is_err());
+
+        let report = buf.report().unwrap();
+        assert_eq!(report.peak_resident_bytes, 8);
+        assert_eq!(report.spilled_artifacts, 2);
+        assert_eq!(
+            report.spilled_bytes,
+            "spilled one".len() + "and two — ü".len()
+        );
+    }
+
+    #[test]
+    fn without_a_ceiling_nothing_spills() {
+        let mut buf = ArtifactBuffer::new(&MemoryConfig::default());
+        let i = buf.push("x".repeat(1 << 16)).unwrap();
+        assert_eq!(buf.get(i).unwrap().len(), 1 << 16);
+        assert!(buf.report().is_none());
+    }
+}
@@ -0,0 +1,380 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    deadline::{AnalysisMode, Deadline},
+    evidence::EvidenceOptions,
+    inflight::Origin,
+    isolation::{self, Site},
+    notifier, run_analysis,
+    spill::{ArtifactBuffer, SpillReport},
+    webhooks, AnalysisOptions, AnalyzeResponse, AppState,
+};
+
+const PAGE_BREAK: char = '\u{c}';
+
+const TITLE_KEYWORDS: [&str; 9] = [
+    "AGREEMENT",
+    "CONTRACT",
+    "AMENDMENT",
+    "ADDENDUM",
+    "STATEMENT OF WORK",
+    "ORDER FORM",
+    "TERMS AND CONDITIONS",
+    "MEMORANDUM OF UNDERSTANDING",
+    "LETTER OF INTENT",
+];
+
+// Attachments that belong to the preceding agreement rather than starting a new one.
+const ATTACHMENT_PREFIXES: [&str; 5] = ["EXHIBIT", "SCHEDULE", "ANNEX", "APPENDIX", "ATTACHMENT"];
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct BundleRequest {
+    /// Extracted bundle text with pages separated by form feeds (`\f`).
+    #[serde(default)]
+    pub document: Option<String>,
+    /// Alternatively, the pages themselves.
+    #[serde(default)]
+    pub pages: Option<Vec<String>>,
+    pub language: String,
+    #[serde(default)]
+    pub analysis_options: EvidenceOptions,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoundaryReason {
+    TitlePage,
+    AfterSignaturePage,
+    PageNumberReset,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub title: Option<String>,
+    pub start_page: usize,
+    pub end_page: usize,
+    pub boundary_reasons: Vec<BoundaryReason>,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BundleMember {
+    pub index: usize,
+    pub title: Option<String>,
+    pub start_page: usize,
+    pub end_page: usize,
+    pub boundary_reasons: Vec<BoundaryReason>,
+    pub analysis: AnalyzeResponse,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BundleResponse {
+    pub family_id: String,
+    pub page_count: usize,
+    pub document_count: usize,
+    pub documents: Vec<BundleMember>,
+    /// Present in low-memory mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<SpillReport>,
+}
+
+// ── Boundary detection ────────────────────────────────────────────────────────
+
+fn first_line(page: &str) -> Option<&str> {
+    page.lines().map(str::trim).find(|l| !l.is_empty())
+}
+
+/// An all-caps opening line naming an agreement type, e.g. "MASTER SERVICES AGREEMENT".
+fn title_of(page: &str) -> Option<String> {
+    let line = first_line(page)?;
+    let has_letters = line.chars().any(char::is_alphabetic);
+    let is_upper = !line.chars().any(char::is_lowercase);
+    if has_letters && is_upper && TITLE_KEYWORDS.iter().any(|k| line.contains(k)) {
+        Some(line.to_string())
+    } else {
+        None
+    }
+}
+
+fn is_attachment(page: &str) -> bool {
+    first_line(page)
+        .map(|l| l.to_uppercase())
+        .is_some_and(|l| ATTACHMENT_PREFIXES.iter().any(|p| l.starts_with(p)))
+}
+
+fn is_signature_page(page: &str) -> bool {
+    let lower = page.to_lowercase();
+    lower.contains("in witness whereof")
+        || (lower.contains("by:") && lower.contains("name:") && lower.contains("title:"))
+}
+
+/// Page number printed in the footer: "Page 3", "Page 3 of 10", "- 3 -" or a bare "3".
+fn page_number(page: &str) -> Option<u32> {
+    let line = page.lines().map(str::trim).rev().find(|l| !l.is_empty())?;
+    let lower = line.to_lowercase();
+    let candidate = if let Some(rest) = lower.strip_prefix("page") {
+        rest.split_whitespace().next()?.to_string()
+    } else {
+        lower
+            .trim_matches(|c: char| c == '-' || c.is_whitespace())
+            .to_string()
+    };
+    candidate.parse().ok()
+}
+
+/// Page-at-a-time boundary detection, so a bundle never has to be held as
+/// pages and segments at once.
+#[derive(Default)]
+pub struct Splitter {
+    current: Option<Segment>,
+    pages_seen: usize,
+    prev_signature: bool,
+    prev_number: Option<u32>,
+}
+
+impl Splitter {
+    /// Adds the next page; returns the previous segment once it has ended.
+    pub fn push(&mut self, page: &str) -> Option<Segment> {
+        let i = self.pages_seen;
+        self.pages_seen += 1;
+        let title = title_of(page);
+        let number = page_number(page);
+
+        let mut reasons = Vec::new();
+        if i > 0 {
+            if title.is_some() {
+                reasons.push(BoundaryReason::TitlePage);
+            }
+            if self.prev_signature && !is_attachment(page) {
+                reasons.push(BoundaryReason::AfterSignaturePage);
+            }
+            if number == Some(1) && self.prev_number.is_some_and(|n| n > 1) {
+                reasons.push(BoundaryReason::PageNumberReset);
+            }
+        }
+        self.prev_signature = is_signature_page(page);
+        self.prev_number = number;
+
+        match &mut self.current {
+            Some(seg) if reasons.is_empty() => {
+                seg.end_page = i + 1;
+                seg.text.push('\n');
+                seg.text.push_str(page);
+                None
+            }
+            _ => self
+                .current
+                .replace(Segment {
+                    title,
+                    start_page: i + 1,
+                    end_page: i + 1,
+                    boundary_reasons: reasons,
+                    text: page.to_string(),
+                })
+                .filter(|s| !s.text.trim().is_empty()),
+        }
+    }
+
+    pub fn finish(self) -> Option<Segment> {
+        self.current.filter(|s| !s.text.trim().is_empty())
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn analyze_bundle(
+    State(state): State<AppState>,
+    Extension(deadline): Extension<Deadline>,
+    headers: HeaderMap,
+    Json(req): Json<BundleRequest>,
+) -> Result<Response, StatusCode> {
+    let pages: Box<dyn Iterator<Item = &str>> = match (&req.pages, &req.document) {
+        (Some(pages), _) => Box::new(pages.iter().map(String::as_str)),
+        (None, Some(doc)) => Box::new(doc.split(PAGE_BREAK)),
+        (None, None) => return Err(StatusCode::BAD_REQUEST),
+    };
+
+    let tenant = notifier::tenant(&headers);
+    let keywords = state.lexicons.matcher(&tenant);
+    let opts = AnalysisOptions {
+        mode: AnalysisMode::Standard,
+        deadline,
+        context_chars: req.analysis_options.context_chars()?,
+        fallback_clauses: req.analysis_options.fallback_clauses,
+        cancel: None,
+        latency: None,
+        keywords: keywords.as_deref(),
+        tenant: &tenant,
+        // Segments are analyzed with the heuristics only.
+        ml: None,
+        model_call: None,
+        progress: None,
+        origin: Origin::Bundle,
+        our_party: None,
+    };
+
+    // Segment texts go through the artifact buffer, which spills them to disk
+    // past the low-memory ceiling; only their metadata stays here.
+    let io_error = |e: std::io::Error| {
+        warn!(error = %e, "bundle artifact spill failed");
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    let mut buffer = ArtifactBuffer::new(&state.memory);
+    let mut stash = |mut seg: Segment| -> Result<(Segment, usize), StatusCode> {
+        let slot = buffer
+            .push(std::mem::take(&mut seg.text))
+            .map_err(io_error)?;
+        Ok((seg, slot))
+    };
+    let mut segments = Vec::new();
+    let mut splitter = Splitter::default();
+    let mut page_count = 0;
+    for page in pages {
+        page_count += 1;
+        if let Some(seg) = splitter.push(page) {
+            segments.push(stash(seg)?);
+        }
+    }
+    if let Some(seg) = splitter.finish() {
+        segments.push(stash(seg)?);
+    }
+    if segments.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let family_id = format!("fam-{}", state.ids.next_id());
+    let mut documents = Vec::with_capacity(segments.len());
+    for (index, (seg, slot)) in segments.into_iter().enumerate() {
+        let text = buffer.get(slot).map_err(io_error)?;
+        let analysis = match isolation::contain(&state, Site::Analysis, || {
+            run_analysis(&state, &text, &req.language, opts)
+        }) {
+            Ok(analysis) => analysis,
+            Err(panicked) => return Ok(panicked.into_response()),
+        };
+        buffer.release(slot);
+        documents.push(BundleMember {
+            index,
+            analysis,
+            title: seg.title,
+            start_page: seg.start_page,
+            end_page: seg.end_page,
+            boundary_reasons: seg.boundary_reasons,
+        });
+    }
+
+    let ids: Vec<String> = documents
+        .iter()
+        .map(|d| d.analysis.analysis_id.clone())
+        .collect();
+    state.corpus.link_family(&ids, &family_id);
+    let tenant = notifier::tenant(&headers);
+    for doc in &documents {
+        notifier::analysis_completed(&state, &tenant, doc.title.as_deref(), &doc.analysis);
+        webhooks::risk_scored(&state, &tenant, &doc.analysis);
+    }
+
+    let memory = buffer.report();
+    info!(
+        family_id = %family_id,
+        pages = page_count,
+        documents = documents.len(),
+        spilled = memory.as_ref().map_or(0, |m| m.spilled_artifacts),
+        "bundle split and analyzed"
+    );
+
+    Ok(Json(BundleResponse {
+        family_id,
+        page_count,
+        document_count: documents.len(),
+        documents,
+        memory,
+    })
+    .into_response())
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages(p: &[&str]) -> Vec<String> {
+        p.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn split_pages(pages: &[String]) -> Vec<Segment> {
+        let mut splitter = Splitter::default();
+        let mut segments: Vec<Segment> = pages.iter().filter_map(|p| splitter.push(p)).collect();
+        segments.extend(splitter.finish());
+        segments
+    }
+
+    #[test]
+    fn splits_on_title_pages() {
+        let segs = split_pages(&pages(&[
+            "MASTER SERVICES AGREEMENT\nThis Agreement...",
+            "2. Services. The Vendor shall...",
+            "STATEMENT OF WORK NO. 1\nScope...",
+        ]));
+        assert_eq!(segs.len(), 2);
+        assert_eq!(segs[0].title.as_deref(), Some("MASTER SERVICES AGREEMENT"));
+        assert_eq!((segs[0].start_page, segs[0].end_page), (1, 2));
+        assert_eq!(segs[1].boundary_reasons, vec![BoundaryReason::TitlePage]);
+    }
+
+    #[test]
+    fn splits_after_signature_page_but_keeps_exhibits() {
+        let segs = split_pages(&pages(&[
+            "Mutual NDA terms...",
+            "IN WITNESS WHEREOF the parties have signed.\nBy: ____",
+            "Exhibit A - Permitted Purpose",
+            "IN WITNESS WHEREOF the parties have signed.",
+            "Consulting terms continue here...",
+        ]));
+        assert_eq!(segs.len(), 2);
+        assert_eq!(segs[0].end_page, 4);
+        assert_eq!(
+            segs[1].boundary_reasons,
+            vec![BoundaryReason::AfterSignaturePage]
+        );
+    }
+
+    #[test]
+    fn splits_on_page_number_reset() {
+        let segs = split_pages(&pages(&[
+            "Terms...\nPage 1 of 2",
+            "More terms...\nPage 2 of 2",
+            "Other terms...\n- 1 -",
+        ]));
+        assert_eq!(segs.len(), 2);
+        assert_eq!(
+            segs[1].boundary_reasons,
+            vec![BoundaryReason::PageNumberReset]
+        );
+    }
+
+    #[test]
+    fn single_document_is_one_segment() {
+        let segs = split_pages(&pages(&["Just one agreement with lower case title."]));
+        assert_eq!(segs.len(), 1);
+        assert!(segs[0].boundary_reasons.is_empty());
+    }
+
+    #[test]
+    fn page_number_formats() {
+        assert_eq!(page_number("text\nPage 3 of 10"), Some(3));
+        assert_eq!(page_number("text\n- 7 -"), Some(7));
+        assert_eq!(page_number("text\n12"), Some(12));
+        assert_eq!(page_number("text\nno number"), None);
+    }
+}
@@ -0,0 +1,256 @@
+//! `POST /api/v1/legal/analyze/stream`: the `analyze` pipeline with its
+//! findings sent as Server-Sent Events while it runs, so a UI can render a
+//! long contract's clauses and issues before the last stage finishes.
+//!
+//! Events, in order: a `clause` or `issue` for each finding as the stage
+//! that produced it completes, then one `summary` with the risk score; or
+//! an `error` in place of the summary if the analysis panicked. Findings are
+//! sent as first found; the stored analysis holds their final review status
+//! and ML confidence. The gRPC `AnalyzeStream` call runs the same stream.
+
+use std::{cell::RefCell, collections::HashSet, convert::Infallible};
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::sse::{Event, KeepAlive, Sse},
+    Extension, Json,
+};
+use futures_util::Stream;
+use serde::Serialize;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::warn;
+
+use crate::{
+    ensemble,
+    inflight::Origin,
+    isolation::{self, Panicked, Site},
+    language::LanguageDetection,
+    latency_budget, notifier, run_analysis, webhooks, AnalysisOptions, AnalyzeRequest,
+    AnalyzeResponse, AppState, Clause, Deadline, Issue,
+};
+
+/// One event of the stream, before it is encoded for SSE or gRPC.
+#[derive(Debug)]
+pub enum StreamEvent {
+    Clause(Clause),
+    Issue(Issue),
+    Summary(RiskSummary),
+    Error(Panicked),
+}
+
+impl StreamEvent {
+    fn sse(&self) -> Option<Event> {
+        let (name, event) = match self {
+            Self::Clause(clause) => ("clause", Event::default().json_data(clause)),
+            Self::Issue(issue) => ("issue", Event::default().json_data(issue)),
+            Self::Summary(summary) => ("summary", Event::default().json_data(summary)),
+            Self::Error(panicked) => ("error", Event::default().json_data(panicked)),
+        };
+        match event {
+            Ok(event) => Some(event.event(name)),
+            Err(e) => {
+                warn!(event = name, error = %e, "stream event not serialized");
+                None
+            }
+        }
+    }
+}
+
+/// Hands findings to the event stream, each once.
+#[derive(Debug)]
+pub struct Progress {
+    events: UnboundedSender<StreamEvent>,
+    sent: RefCell<HashSet<String>>,
+}
+
+impl Progress {
+    fn new(events: UnboundedSender<StreamEvent>) -> Self {
+        Self {
+            events,
+            sent: RefCell::new(HashSet::new()),
+        }
+    }
+
+    /// Sends the findings not sent yet. Clause and issue IDs do not overlap.
+    pub fn found(&self, clauses: &[Clause], issues: &[Issue]) {
+        for clause in clauses {
+            if self.sent.borrow_mut().insert(clause.id.clone()) {
+                self.send(StreamEvent::Clause(clause.clone()));
+            }
+        }
+        for issue in issues {
+            if self.sent.borrow_mut().insert(issue.id.clone()) {
+                self.send(StreamEvent::Issue(issue.clone()));
+            }
+        }
+    }
+
+    /// A client that hung up just stops receiving.
+    fn send(&self, event: StreamEvent) {
+        let _ = self.events.send(event);
+    }
+}
+
+/// The last event of a successful stream.
+#[derive(Debug, Serialize)]
+pub struct RiskSummary {
+    pub analysis_id: String,
+    pub risk_score: f64,
+    pub clause_count: usize,
+    pub issue_count: usize,
+    /// IDs of the findings sent for human review.
+    pub escalations: Vec<String>,
+    pub language: String,
+    pub language_detection: LanguageDetection,
+    pub word_count: usize,
+    pub partial: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_stages: Vec<String>,
+}
+
+impl From<&AnalyzeResponse> for RiskSummary {
+    fn from(analysis: &AnalyzeResponse) -> Self {
+        Self {
+            analysis_id: analysis.analysis_id.clone(),
+            risk_score: analysis.risk_score,
+            clause_count: analysis.clauses.len(),
+            issue_count: analysis.issues.len(),
+            escalations: analysis.escalations.clone(),
+            language: analysis.language.clone(),
+            language_detection: analysis.language_detection.clone(),
+            word_count: analysis.word_count,
+            partial: analysis.partial,
+            skipped_stages: analysis.skipped_stages.clone(),
+        }
+    }
+}
+
+fn events(
+    receiver: UnboundedReceiver<StreamEvent>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    futures_util::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            if let Some(event) = receiver.recv().await?.sse() {
+                return Some((Ok(event), receiver));
+            }
+        }
+    })
+}
+
+pub async fn analyze_stream(
+    State(state): State<AppState>,
+    Extension(deadline): Extension<Deadline>,
+    headers: HeaderMap,
+    Json(req): Json<AnalyzeRequest>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let receiver = start(
+        state,
+        deadline,
+        notifier::tenant(&headers),
+        req,
+        Origin::Stream,
+    )
+    .await?;
+    Ok(Sse::new(events(receiver)).keep_alive(KeepAlive::default()))
+}
+
+/// Checks the request and starts the analysis on a blocking thread; its
+/// events arrive on the receiver, which closes after the summary or error.
+pub(crate) async fn start(
+    state: AppState,
+    deadline: Deadline,
+    tenant: String,
+    req: AnalyzeRequest,
+    origin: Origin<'static>,
+) -> Result<UnboundedReceiver<StreamEvent>, StatusCode> {
+    if req.document.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let latency = latency_budget(&state, &req, deadline)?;
+    let context_chars = req.analysis_options.context_chars()?;
+    let keywords = state.lexicons.matcher(&tenant);
+    let ml = ensemble::ml_findings(&state, &tenant, &req).await;
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::task::spawn_blocking(move || {
+        let progress = Progress::new(sender);
+        let opts = AnalysisOptions {
+            mode: req.mode,
+            deadline,
+            context_chars,
+            fallback_clauses: req.analysis_options.fallback_clauses,
+            cancel: None,
+            latency,
+            keywords: keywords.as_deref(),
+            tenant: &tenant,
+            ml: ml.findings.as_ref(),
+            model_call: ml.call_id.as_deref(),
+            progress: Some(&progress),
+            origin,
+            our_party: req.our_party.as_deref(),
+        };
+        match isolation::contain(&state, Site::Analysis, || {
+            run_analysis(&state, &req.document, &req.language, opts)
+        }) {
+            Ok(analysis) => {
+                // Findings only the ML backend reported arrive with the merge.
+                progress.found(&analysis.clauses, &analysis.issues);
+                notifier::analysis_completed(
+                    &state,
+                    &tenant,
+                    req.document_name.as_deref(),
+                    &analysis,
+                );
+                webhooks::risk_scored(&state, &tenant, &analysis);
+                progress.send(StreamEvent::Summary(RiskSummary::from(&analysis)));
+            }
+            Err(panicked) => progress.send(StreamEvent::Error(panicked)),
+        }
+    });
+    Ok(receiver)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{deadline::AnalysisMode, http_tests::SAMPLE_CONTRACT, notifier::DEFAULT_TENANT};
+    use std::time::Duration;
+
+    fn drain(receiver: &mut UnboundedReceiver<StreamEvent>) -> usize {
+        std::iter::from_fn(|| receiver.try_recv().ok()).count()
+    }
+
+    #[test]
+    fn findings_are_sent_once_as_stages_add_to_them() {
+        let state = AppState::in_memory();
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let progress = Progress::new(sender);
+        let opts = AnalysisOptions {
+            mode: AnalysisMode::Standard,
+            deadline: Deadline::after(Duration::from_secs(60), Duration::ZERO),
+            context_chars: 0,
+            fallback_clauses: false,
+            cancel: None,
+            latency: None,
+            keywords: None,
+            tenant: DEFAULT_TENANT,
+            ml: None,
+            model_call: None,
+            progress: Some(&progress),
+            origin: Origin::Stream,
+            our_party: None,
+        };
+        let analysis = run_analysis(&state, SAMPLE_CONTRACT, "en", opts);
+        assert!(!analysis.clauses.is_empty());
+        assert_eq!(
+            drain(&mut receiver),
+            analysis.clauses.len() + analysis.issues.len()
+        );
+
+        progress.found(&analysis.clauses, &analysis.issues);
+        assert_eq!(drain(&mut receiver), 0);
+    }
+}
@@ -0,0 +1,672 @@
+//! Tenant drafting style: defined-term casing, "shall" or "will", the Oxford
+//! comma, date format and section numbering. Compilation rewrites generated
+//! documents to the profile, and the style check reports the same rules'
+//! deviations in any text, including third-party paper.
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::NaiveDate;
+use dashmap::DashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{export_control::find_words, notifier, AppState};
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+/// Unset rules are neither applied nor checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StyleProfile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub defined_terms: Option<TermCase>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obligation_verb: Option<ObligationVerb>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oxford_comma: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<DateFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub numbering: Option<Numbering>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TermCase {
+    /// "Confidential Information"
+    Title,
+    /// "CONFIDENTIAL INFORMATION"
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObligationVerb {
+    Shall,
+    Will,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateFormat {
+    /// 2024-05-01
+    Iso,
+    /// 05/01/2024
+    Us,
+    /// May 1, 2024
+    Long,
+    /// 1 May 2024
+    DayMonth,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Numbering {
+    /// "1." and "1.1"
+    Decimal,
+    /// "Section 1." and "Section 1.1"
+    Section,
+    /// "Article 1." and "Article 1.1"
+    Article,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StyleRule {
+    DefinedTerms,
+    ObligationVerb,
+    OxfordComma,
+    DateFormat,
+    Numbering,
+}
+
+/// One place where the text departs from the profile; `start..end` is the
+/// byte range of `found`.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct StyleDeviation {
+    pub rule: StyleRule,
+    pub start: usize,
+    pub end: usize,
+    pub found: String,
+    pub expected: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StyleProfileResponse {
+    pub tenant: String,
+    #[serde(flatten)]
+    pub profile: StyleProfile,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StyleCheckRequest {
+    pub document: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StyleCheckResponse {
+    pub tenant: String,
+    pub profile: StyleProfile,
+    pub deviations: Vec<StyleDeviation>,
+}
+
+// ── Rules ─────────────────────────────────────────────────────────────────────
+
+/// `word` in the capitalization of `like`: all caps, leading capital or lower.
+fn match_case(word: &str, like: &str) -> String {
+    if like.len() > 1 && !like.chars().any(|c| c.is_lowercase()) {
+        word.to_uppercase()
+    } else if like.starts_with(|c: char| c.is_uppercase()) {
+        capitalize(word)
+    } else {
+        word.to_lowercase()
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|c| c.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+fn obligation_verbs(text: &str, verb: ObligationVerb) -> Vec<StyleDeviation> {
+    let (wanted, other) = match verb {
+        ObligationVerb::Shall => ("shall", "will"),
+        ObligationVerb::Will => ("will", "shall"),
+    };
+    let lower = text.to_ascii_lowercase();
+    find_words(&lower, other)
+        .into_iter()
+        // "at will" is a term of art, not an obligation.
+        .filter(|&i| !lower[..i].trim_end().ends_with(" at"))
+        .map(|i| {
+            let found = &text[i..i + other.len()];
+            deviation(
+                StyleRule::ObligationVerb,
+                i,
+                found,
+                match_case(wanted, found),
+            )
+        })
+        .collect()
+}
+
+/// Terms defined in parentheses, quoted (`("Services")`, `(the "Services")`)
+/// or not (`(Controller)`). Bare all-caps words are taken for acronyms.
+fn defined_terms(text: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for (open, _) in text.match_indices('(') {
+        let Some(close) = text[open..].find(')') else {
+            continue;
+        };
+        let inner = text[open + 1..open + close].trim();
+        let inner = inner.strip_prefix("the ").unwrap_or(inner).trim();
+        let quoted = inner
+            .strip_prefix(['"', '\u{201c}'])
+            .and_then(|t| t.strip_suffix(['"', '\u{201d}']));
+        let term = match quoted {
+            Some(t) => t.trim(),
+            None => {
+                let words: Vec<&str> = inner.split_whitespace().collect();
+                let acronym = words.len() == 1 && !inner.chars().any(char::is_lowercase);
+                let capitalized = !words.is_empty()
+                    && words.len() <= 4
+                    && words.iter().all(|w| {
+                        w.starts_with(|c: char| c.is_uppercase())
+                            && w.chars().all(char::is_alphabetic)
+                    });
+                if acronym || !capitalized {
+                    continue;
+                }
+                inner
+            }
+        };
+        if !term.is_empty()
+            && !terms.iter().any(|t| t.eq_ignore_ascii_case(term))
+            && term.is_ascii()
+        {
+            terms.push(term.to_string());
+        }
+    }
+    terms
+}
+
+const MINOR_WORDS: [&str; 8] = ["a", "an", "and", "for", "in", "of", "or", "the"];
+
+fn term_case(term: &str, case: TermCase) -> String {
+    match case {
+        TermCase::Upper => term.to_uppercase(),
+        TermCase::Title => term
+            .split(' ')
+            .enumerate()
+            .map(|(i, w)| {
+                let lower = w.to_ascii_lowercase();
+                if i > 0 && MINOR_WORDS.contains(&lower.as_str()) {
+                    lower
+                } else {
+                    capitalize(&lower)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+fn term_casing(text: &str, case: TermCase) -> Vec<StyleDeviation> {
+    let lower = text.to_ascii_lowercase();
+    let mut out = Vec::new();
+    for term in defined_terms(text) {
+        let expected = term_case(&term, case);
+        for i in find_words(&lower, &term.to_ascii_lowercase()) {
+            let found = &text[i..i + term.len()];
+            if found != expected {
+                out.push(deviation(
+                    StyleRule::DefinedTerms,
+                    i,
+                    found,
+                    expected.clone(),
+                ));
+            }
+        }
+    }
+    out
+}
+
+/// Words an item may have and still be read as one entry of a list.
+const MAX_ITEM_WORDS: usize = 4;
+
+fn item_words(item: &str) -> usize {
+    item.split_whitespace().count()
+}
+
+/// Lists of three or more ("A, B and C") are checked; a single comma before
+/// the conjunction usually joins clauses and is left alone.
+fn oxford_commas(text: &str, wanted: bool) -> Vec<StyleDeviation> {
+    let lower = text.to_ascii_lowercase();
+    let mut out = Vec::new();
+    for conj in ["and", "or"] {
+        for at in find_words(&lower, conj) {
+            let sentence_start = text[..at]
+                .rfind(['.', ';', ':', '\n', '(', ')'])
+                .map_or(0, |i| i + 1);
+            let before = text[sentence_start..at].trim_end();
+            let has_comma = before.ends_with(',');
+            let before = before.trim_end_matches(',');
+            let Some(last_comma) = before.rfind(',') else {
+                continue;
+            };
+            let item = &before[last_comma + 1..];
+            let earlier = &before[..last_comma];
+            let first_item = earlier.rfind(',').map_or(earlier, |c| &earlier[c + 1..]);
+            if !(1..=MAX_ITEM_WORDS).contains(&item_words(item))
+                || !(1..=MAX_ITEM_WORDS).contains(&item_words(first_item))
+                || has_comma == wanted
+            {
+                continue;
+            }
+            let start = sentence_start + last_comma + 1 + (item.len() - item.trim_start().len());
+            let end = at + conj.len();
+            let item = item.trim();
+            let expected = if wanted {
+                format!("{item}, {}", &text[at..end])
+            } else {
+                format!("{item} {}", &text[at..end])
+            };
+            out.push(deviation(
+                StyleRule::OxfordComma,
+                start,
+                &text[start..end],
+                expected,
+            ));
+        }
+    }
+    out
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Leading ASCII digits of `s`, when there are `min..=max` of them.
+fn digits(s: &str, min: usize, max: usize) -> Option<(u32, usize)> {
+    let n = s.bytes().take_while(u8::is_ascii_digit).count();
+    if n < min || n > max {
+        return None;
+    }
+    Some((s[..n].parse().ok()?, n))
+}
+
+fn month_at(s: &str) -> Option<(u32, usize)> {
+    MONTHS
+        .iter()
+        .position(|m| s.starts_with(m))
+        .map(|i| (i as u32 + 1, MONTHS[i].len()))
+}
+
+/// A date starting at the beginning of `s`, and its length in bytes.
+fn date_at(s: &str) -> Option<(NaiveDate, usize)> {
+    let date = |y: u32, m: u32, d: u32| NaiveDate::from_ymd_opt(y as i32, m, d);
+    // 2024-05-01
+    if let Some((y, 4)) = digits(s, 4, 4) {
+        if s[4..].starts_with('-') {
+            let (m, mn) = digits(&s[5..], 2, 2)?;
+            let rest = &s[5 + mn..];
+            let (d, dn) = digits(rest.strip_prefix('-')?, 2, 2)?;
+            return Some((date(y, m, d)?, 5 + mn + 1 + dn));
+        }
+        return None;
+    }
+    // 05/01/2024
+    if let Some((m, mn)) = digits(s, 1, 2) {
+        if let Some(rest) = s[mn..].strip_prefix('/') {
+            let (d, dn) = digits(rest, 1, 2)?;
+            let (y, _) = digits(rest[dn..].strip_prefix('/')?, 4, 4)?;
+            return Some((date(y, m, d)?, mn + 1 + dn + 1 + 4));
+        }
+        // 1 May 2024
+        let (d, dn) = (m, mn);
+        let rest = s[dn..].strip_prefix(' ')?;
+        let (m, len) = month_at(rest)?;
+        let (y, _) = digits(rest[len..].strip_prefix(' ')?, 4, 4)?;
+        return Some((date(y, m, d)?, dn + 1 + len + 1 + 4));
+    }
+    // May 1, 2024
+    let (m, len) = month_at(s)?;
+    let rest = s[len..].strip_prefix(' ')?;
+    let (d, dn) = digits(rest, 1, 2)?;
+    let (y, _) = digits(rest[dn..].strip_prefix(", ")?, 4, 4)?;
+    Some((date(y, m, d)?, len + 1 + dn + 2 + 4))
+}
+
+fn format_date(date: NaiveDate, format: DateFormat) -> String {
+    date.format(match format {
+        DateFormat::Iso => "%Y-%m-%d",
+        DateFormat::Us => "%m/%d/%Y",
+        DateFormat::Long => "%B %-d, %Y",
+        DateFormat::DayMonth => "%-d %B %Y",
+    })
+    .to_string()
+}
+
+fn dates(text: &str, format: DateFormat) -> Vec<StyleDeviation> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < text.len() {
+        let boundary = i == 0 || !bytes[i - 1].is_ascii_alphanumeric();
+        let parsed = (boundary && text.is_char_boundary(i))
+            .then(|| date_at(&text[i..]))
+            .flatten()
+            .filter(|(_, len)| bytes.get(i + len).is_none_or(|b| !b.is_ascii_digit()));
+        match parsed {
+            Some((date, len)) => {
+                let found = &text[i..i + len];
+                let expected = format_date(date, format);
+                if found != expected {
+                    out.push(deviation(StyleRule::DateFormat, i, found, expected));
+                }
+                i += len;
+            }
+            None => i += 1,
+        }
+    }
+    out
+}
+
+/// The section label at the start of `line` ("4.", "4.2", "Section 4.")
+/// and the number inside it, when the line is a numbered heading.
+pub fn section_label(line: &str) -> Option<(usize, &str)> {
+    let lower = line.to_ascii_lowercase();
+    let prefix = ["section ", "article "]
+        .iter()
+        .find(|p| lower.starts_with(*p))
+        .map_or(0, |p| p.len());
+    let rest = &line[prefix..];
+    let n = rest
+        .bytes()
+        .take_while(|b| b.is_ascii_digit() || *b == b'.')
+        .count();
+    let number = rest[..n].trim_end_matches('.');
+    if number.is_empty() || number.starts_with('.') || number.contains("..") {
+        return None;
+    }
+    let dotted = n > number.len() || number.contains('.');
+    let label_end = prefix + n;
+    if !(prefix > 0 || dotted) || !line[label_end..].starts_with(' ') {
+        return None;
+    }
+    Some((label_end, number))
+}
+
+/// The label of section `number` in `numbering` style.
+pub fn format_label(number: &str, numbering: Numbering) -> String {
+    let decimal = if number.contains('.') {
+        number.to_string()
+    } else {
+        format!("{number}.")
+    };
+    match numbering {
+        Numbering::Decimal => decimal,
+        Numbering::Section => format!("Section {decimal}"),
+        Numbering::Article => format!("Article {decimal}"),
+    }
+}
+
+fn numbering(text: &str, numbering: Numbering) -> Vec<StyleDeviation> {
+    let mut out = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start().len();
+        if let Some((len, number)) = section_label(&line[indent..]) {
+            let start = offset + indent;
+            let found = &text[start..start + len];
+            let expected = format_label(number, numbering);
+            if found != expected {
+                out.push(deviation(StyleRule::Numbering, start, found, expected));
+            }
+        }
+        offset += line.len();
+    }
+    out
+}
+
+fn deviation(rule: StyleRule, start: usize, found: &str, expected: String) -> StyleDeviation {
+    StyleDeviation {
+        rule,
+        start,
+        end: start + found.len(),
+        found: found.to_string(),
+        expected,
+    }
+}
+
+/// Every deviation from `profile`, in text order; where two overlap only
+/// the first is kept.
+pub fn check(profile: &StyleProfile, text: &str) -> Vec<StyleDeviation> {
+    let mut out = Vec::new();
+    if let Some(case) = profile.defined_terms {
+        out.extend(term_casing(text, case));
+    }
+    if let Some(verb) = profile.obligation_verb {
+        out.extend(obligation_verbs(text, verb));
+    }
+    if let Some(wanted) = profile.oxford_comma {
+        out.extend(oxford_commas(text, wanted));
+    }
+    if let Some(format) = profile.date_format {
+        out.extend(dates(text, format));
+    }
+    if let Some(style) = profile.numbering {
+        out.extend(numbering(text, style));
+    }
+    out.sort_by_key(|d| (d.start, d.end));
+    let mut end = 0;
+    out.retain(|d| {
+        let keep = d.start >= end;
+        if keep {
+            end = d.end;
+        }
+        keep
+    });
+    out
+}
+
+/// `text` rewritten to `profile`, with the changes that were made.
+pub fn apply(profile: &StyleProfile, text: &str) -> (String, Vec<StyleDeviation>) {
+    let changes = check(profile, text);
+    let mut out = String::with_capacity(text.len());
+    let mut at = 0;
+    for c in &changes {
+        out.push_str(&text[at..c.start]);
+        out.push_str(&c.expected);
+        at = c.end;
+    }
+    out.push_str(&text[at..]);
+    (out, changes)
+}
+
+/// Where `offset` of the original text ends up after `changes`.
+pub fn remap(changes: &[StyleDeviation], offset: usize) -> usize {
+    changes
+        .iter()
+        .take_while(|c| c.end <= offset)
+        .fold(offset, |o, c| o + c.expected.len() - c.found.len())
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct StyleStore {
+    tenants: DashMap<String, StyleProfile>,
+}
+
+impl StyleStore {
+    pub fn get(&self, tenant: &str) -> Option<StyleProfile> {
+        self.tenants.get(tenant).map(|p| p.value().clone())
+    }
+
+    pub fn put(&self, tenant: &str, profile: StyleProfile) {
+        self.tenants.insert(tenant.to_string(), profile);
+    }
+
+    pub fn remove(&self, tenant: &str) -> bool {
+        self.tenants.remove(tenant).is_some()
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn get_profile(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<StyleProfileResponse>, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    let profile = state.styles.get(&tenant).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(StyleProfileResponse { tenant, profile }))
+}
+
+pub async fn put_profile(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(profile): Json<StyleProfile>,
+) -> Json<StyleProfileResponse> {
+    let tenant = notifier::tenant(&headers);
+    state.styles.put(&tenant, profile.clone());
+    info!(tenant = %tenant, "style profile saved");
+    state.record_audit(
+        "style_profile.updated",
+        &tenant,
+        None,
+        serde_json::to_value(&profile).unwrap_or_default(),
+    );
+    Json(StyleProfileResponse { tenant, profile })
+}
+
+pub async fn delete_profile(State(state): State<AppState>, headers: HeaderMap) -> StatusCode {
+    let tenant = notifier::tenant(&headers);
+    if state.styles.remove(&tenant) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// Checks any text, typically a counterparty's draft, against the tenant's
+/// profile without changing it.
+pub async fn check_document(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<StyleCheckRequest>,
+) -> Result<Json<StyleCheckResponse>, StatusCode> {
+    let tenant = notifier::tenant(&headers);
+    let profile = state.styles.get(&tenant).ok_or(StatusCode::NOT_FOUND)?;
+    let deviations = check(&profile, &req.document);
+    Ok(Json(StyleCheckResponse {
+        tenant,
+        profile,
+        deviations,
+    }))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile() -> StyleProfile {
+        StyleProfile {
+            defined_terms: Some(TermCase::Title),
+            obligation_verb: Some(ObligationVerb::Shall),
+            oxford_comma: Some(true),
+            date_format: Some(DateFormat::Long),
+            numbering: Some(Numbering::Section),
+        }
+    }
+
+    #[test]
+    fn apply_rewrites_every_rule() {
+        let text = "1. Definitions\n\
+            The vendor (the \"Service provider\") will deliver reports, invoices and logs.\n\
+            1.1 The SERVICE PROVIDER Will start on 2024-05-01.\n\
+            Section 2. Term\n\
+            Employment is at will.";
+        let (out, changes) = apply(&profile(), text);
+        assert_eq!(
+            out,
+            "Section 1. Definitions\n\
+            The vendor (the \"Service Provider\") shall deliver reports, invoices, and logs.\n\
+            Section 1.1 The Service Provider Shall start on May 1, 2024.\n\
+            Section 2. Term\n\
+            Employment is at will."
+        );
+        assert_eq!(changes.len(), 8);
+        // Applying again changes nothing.
+        assert!(check(&profile(), &out).is_empty());
+    }
+
+    #[test]
+    fn each_rule_reports_its_deviations() {
+        let profile = StyleProfile {
+            defined_terms: Some(TermCase::Upper),
+            obligation_verb: Some(ObligationVerb::Will),
+            oxford_comma: Some(false),
+            date_format: Some(DateFormat::Iso),
+            numbering: Some(Numbering::Decimal),
+        };
+        let text = "Article 4. Fees\n\
+            The Customer (Customer) shall pay fees, taxes, and costs by 5/1/2024 or 1 June 2024.";
+        let deviations = check(&profile, text);
+        let found: Vec<(StyleRule, &str, &str)> = deviations
+            .iter()
+            .map(|d| (d.rule, &text[d.start..d.end], d.expected.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (StyleRule::Numbering, "Article 4.", "4."),
+                (StyleRule::DefinedTerms, "Customer", "CUSTOMER"),
+                (StyleRule::DefinedTerms, "Customer", "CUSTOMER"),
+                (StyleRule::ObligationVerb, "shall", "will"),
+                (StyleRule::OxfordComma, "taxes, and", "taxes and"),
+                (StyleRule::DateFormat, "5/1/2024", "2024-05-01"),
+                (StyleRule::DateFormat, "1 June 2024", "2024-06-01"),
+            ]
+        );
+    }
+
+    #[test]
+    fn clause_joins_acronyms_and_numbers_are_left_alone() {
+        let text = "Fees are due monthly, and late fees accrue (GDPR). It costs 30.5 units.\n\
+            3 (three) years apply. Invalid 2024-13-45.";
+        assert!(check(&profile(), text).is_empty());
+    }
+
+    #[test]
+    fn remap_follows_length_changes() {
+        let text = "A will pay. B will pay.";
+        let profile = StyleProfile {
+            obligation_verb: Some(ObligationVerb::Shall),
+            ..StyleProfile::default()
+        };
+        let (out, changes) = apply(&profile, text);
+        let b = text.find('B').unwrap();
+        assert_eq!(&out[remap(&changes, b)..], "B shall pay.");
+    }
+}
@@ -0,0 +1,427 @@
+//! Subcontracting terms: whether a contract lets the supplier subcontract,
+//! which obligations must be flowed down to subcontractors, and which
+//! subcontracts in a family are missing that flow-down language.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{auth, export_control::find_words, AppState};
+
+const FLOW_DOWN_MARKERS: [&str; 7] = [
+    "flow down",
+    "flow-down",
+    "flowed down",
+    "flow through",
+    "in all subcontracts",
+    "in each subcontract",
+    "in every subcontract",
+];
+
+/// Obligations commonly named in commercial flow-down clauses, by alias.
+const TOPICS: [(&str, &[&str]); 7] = [
+    (
+        "confidentiality",
+        &["confidentiality", "confidential information"],
+    ),
+    (
+        "data protection",
+        &["data protection", "personal data", "privacy"],
+    ),
+    (
+        "audit rights",
+        &["audit", "inspection", "access to records"],
+    ),
+    ("insurance", &["insurance"]),
+    (
+        "export control",
+        &["export control", "itar", "export administration"],
+    ),
+    (
+        "anti-bribery",
+        &["anti-bribery", "anti-corruption", "bribery"],
+    ),
+    (
+        "information security",
+        &["information security", "cybersecurity", "safeguarding"],
+    ),
+];
+
+const SUBCONTRACT_MARKERS: [&str; 3] = [
+    "subcontract agreement",
+    "this subcontract",
+    "sub-contract agreement",
+];
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    Permitted,
+    ConsentRequired,
+    Prohibited,
+    /// The contract does not address subcontracting.
+    Silent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequirementKind {
+    /// A FAR/DFARS clause number such as 52.204-21.
+    Regulation,
+    Topic,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FlowDownRequirement {
+    pub reference: String,
+    pub kind: RequirementKind,
+    pub offset: usize,
+    pub sentence: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubcontractingReport {
+    pub permission: Permission,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permission_sentence: Option<String>,
+    /// The supplier stays liable for its subcontractors' performance.
+    pub prime_remains_liable: bool,
+    /// The document presents itself as a subcontract.
+    pub is_subcontract: bool,
+    pub flow_downs: Vec<FlowDownRequirement>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckRequest {
+    pub document: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubcontractGap {
+    pub document_id: String,
+    pub missing: Vec<FlowDownRequirement>,
+    pub compliant: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FamilyFlowDownReport {
+    pub family_id: String,
+    /// Documents in the family that impose flow-down obligations.
+    pub prime_documents: Vec<String>,
+    pub requirements: Vec<FlowDownRequirement>,
+    pub subcontracts: Vec<SubcontractGap>,
+}
+
+// ── Detection ─────────────────────────────────────────────────────────────────
+
+/// Sentence spans; a period only ends a sentence when followed by whitespace,
+/// so clause numbers like 52.204-21 stay whole.
+pub fn sentences(text: &str) -> Vec<(usize, &str)> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::new();
+    let mut start = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        let boundary = match b {
+            b'.' | b';' => bytes.get(i + 1).is_none_or(u8::is_ascii_whitespace),
+            b'\n' => bytes.get(i + 1) == Some(&b'\n'),
+            _ => false,
+        };
+        if boundary {
+            let s = &text[start..=i];
+            if !s.trim().is_empty() {
+                out.push((start, s));
+            }
+            start = i + 1;
+        }
+    }
+    if !text[start..].trim().is_empty() {
+        out.push((start, &text[start..]));
+    }
+    out
+}
+
+/// FAR (52.x) and DFARS (252.x) clause numbers in a sentence.
+fn clause_numbers(sentence: &str) -> Vec<String> {
+    sentence
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '(' || c == ')')
+        .map(|t| t.trim_end_matches(['.', ';', ':']))
+        .filter(|t| {
+            let Some((part, rest)) = t.split_once('.') else {
+                return false;
+            };
+            let Some((section, number)) = rest.split_once('-') else {
+                return false;
+            };
+            (part == "52" || part == "252")
+                && section.len() == 3
+                && section.bytes().all(|b| b.is_ascii_digit())
+                && !number.is_empty()
+                && number.bytes().all(|b| b.is_ascii_digit())
+        })
+        .map(|t| {
+            let prefix = if t.starts_with("252.") {
+                "DFARS"
+            } else {
+                "FAR"
+            };
+            format!("{prefix} {t}")
+        })
+        .collect()
+}
+
+fn permission(lower: &str) -> Option<Permission> {
+    if !lower.contains("subcontract") && !lower.contains("sub-contract") {
+        return None;
+    }
+    let negated = [
+        "shall not",
+        "may not",
+        "must not",
+        "will not",
+        "no subcontracting",
+        "prohibited",
+    ]
+    .iter()
+    .any(|n| lower.contains(n));
+    if lower.contains("consent") || lower.contains("approval") {
+        Some(Permission::ConsentRequired)
+    } else if negated {
+        Some(Permission::Prohibited)
+    } else if [
+        "may subcontract",
+        "permitted to subcontract",
+        "entitled to subcontract",
+        "right to subcontract",
+    ]
+    .iter()
+    .any(|p| lower.contains(p))
+    {
+        Some(Permission::Permitted)
+    } else {
+        None
+    }
+}
+
+pub fn analyze(document: &str) -> SubcontractingReport {
+    let mut report = SubcontractingReport {
+        permission: Permission::Silent,
+        permission_sentence: None,
+        prime_remains_liable: false,
+        is_subcontract: false,
+        flow_downs: Vec::new(),
+    };
+    let doc_lower = document.to_ascii_lowercase();
+    report.is_subcontract = SUBCONTRACT_MARKERS.iter().any(|m| doc_lower.contains(m));
+
+    for (offset, sentence) in sentences(document) {
+        let lower = sentence.to_ascii_lowercase();
+        if report.permission_sentence.is_none() {
+            if let Some(p) = permission(&lower) {
+                report.permission = p;
+                report.permission_sentence = Some(sentence.trim().to_string());
+            }
+        }
+        if lower.contains("subcontractor")
+            && [
+                "remain responsible",
+                "remain liable",
+                "remain fully responsible",
+                "remain fully liable",
+            ]
+            .iter()
+            .any(|p| lower.contains(p))
+        {
+            report.prime_remains_liable = true;
+        }
+        if !FLOW_DOWN_MARKERS.iter().any(|m| lower.contains(m)) {
+            continue;
+        }
+        let mut found: Vec<(String, RequirementKind)> = clause_numbers(sentence)
+            .into_iter()
+            .map(|r| (r, RequirementKind::Regulation))
+            .collect();
+        for (topic, aliases) in TOPICS {
+            if aliases.iter().any(|a| !find_words(&lower, a).is_empty()) {
+                found.push((topic.to_string(), RequirementKind::Topic));
+            }
+        }
+        for (reference, kind) in found {
+            if report.flow_downs.iter().any(|f| f.reference == reference) {
+                continue;
+            }
+            report.flow_downs.push(FlowDownRequirement {
+                reference,
+                kind,
+                offset,
+                sentence: sentence.trim().to_string(),
+            });
+        }
+    }
+    report
+}
+
+/// Whether `document` carries the requirement: the clause number for
+/// regulations, any alias for topics.
+pub fn satisfies(document: &str, requirement: &FlowDownRequirement) -> bool {
+    match requirement.kind {
+        RequirementKind::Regulation => {
+            let number = requirement
+                .reference
+                .split_once(' ')
+                .map_or(requirement.reference.as_str(), |(_, n)| n);
+            document.contains(number)
+        }
+        RequirementKind::Topic => {
+            let lower = document.to_ascii_lowercase();
+            TOPICS
+                .iter()
+                .find(|(t, _)| *t == requirement.reference)
+                .is_some_and(|(_, aliases)| {
+                    aliases.iter().any(|a| !find_words(&lower, a).is_empty())
+                })
+        }
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn check_document(
+    Json(req): Json<CheckRequest>,
+) -> Result<Json<SubcontractingReport>, StatusCode> {
+    if req.document.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(Json(analyze(&req.document)))
+}
+
+/// Compares each subcontract in a family against the flow-down obligations
+/// of the other documents in it.
+pub async fn family_flow_down(
+    State(state): State<AppState>,
+    Path(family_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<FamilyFlowDownReport>, StatusCode> {
+    let docs: Vec<_> = auth::tenant_documents(&headers, &state)
+        .into_iter()
+        .filter(|d| d.family_id.as_deref() == Some(family_id.as_str()))
+        .map(|d| {
+            let report = analyze(&d.text);
+            (d, report)
+        })
+        .collect();
+    if docs.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let mut prime_documents = Vec::new();
+    let mut requirements: Vec<FlowDownRequirement> = Vec::new();
+    for (doc, report) in docs.iter().filter(|(_, r)| !r.is_subcontract) {
+        if report.flow_downs.is_empty() {
+            continue;
+        }
+        prime_documents.push(doc.id.clone());
+        for f in &report.flow_downs {
+            if !requirements.iter().any(|r| r.reference == f.reference) {
+                requirements.push(f.clone());
+            }
+        }
+    }
+
+    let subcontracts: Vec<SubcontractGap> = docs
+        .iter()
+        .filter(|(_, r)| r.is_subcontract)
+        .map(|(doc, _)| {
+            let missing: Vec<FlowDownRequirement> = requirements
+                .iter()
+                .filter(|r| !satisfies(&doc.text, r))
+                .cloned()
+                .collect();
+            SubcontractGap {
+                document_id: doc.id.clone(),
+                compliant: missing.is_empty(),
+                missing,
+            }
+        })
+        .collect();
+
+    info!(
+        family_id = %family_id,
+        requirements = requirements.len(),
+        subcontracts = subcontracts.len(),
+        non_compliant = subcontracts.iter().filter(|s| !s.compliant).count(),
+        "flow-down comparison completed"
+    );
+    Ok(Json(FamilyFlowDownReport {
+        family_id,
+        prime_documents,
+        requirements,
+        subcontracts,
+    }))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIME: &str = "Supplier shall not subcontract any of its obligations without the prior \
+        written consent of the Government. Supplier shall remain fully responsible for the acts of \
+        any subcontractor. Supplier shall flow down FAR 52.204-21 and DFARS 252.204-7012 in all \
+        subcontracts. The confidentiality obligations in Section 8 shall be flowed down to every \
+        subcontractor.";
+
+    #[test]
+    fn detects_permission_and_flow_downs() {
+        let report = analyze(PRIME);
+        assert_eq!(report.permission, Permission::ConsentRequired);
+        assert!(report.prime_remains_liable);
+        assert!(!report.is_subcontract);
+        let refs: Vec<&str> = report
+            .flow_downs
+            .iter()
+            .map(|f| f.reference.as_str())
+            .collect();
+        assert_eq!(
+            refs,
+            ["FAR 52.204-21", "DFARS 252.204-7012", "confidentiality"]
+        );
+    }
+
+    #[test]
+    fn permission_variants() {
+        assert_eq!(
+            analyze("Vendor may not subcontract the Services.").permission,
+            Permission::Prohibited
+        );
+        assert_eq!(
+            analyze("Vendor may subcontract routine maintenance.").permission,
+            Permission::Permitted
+        );
+        assert_eq!(
+            analyze("Fees are payable in 30 days.").permission,
+            Permission::Silent
+        );
+    }
+
+    #[test]
+    fn subcontracts_satisfy_by_number_or_topic() {
+        let report = analyze(PRIME);
+        let sub = "SUBCONTRACT AGREEMENT. Subcontractor shall comply with FAR 52.204-21 and keep \
+            all Confidential Information secret.";
+        let missing: Vec<&str> = report
+            .flow_downs
+            .iter()
+            .filter(|r| !satisfies(sub, r))
+            .map(|r| r.reference.as_str())
+            .collect();
+        assert_eq!(missing, ["DFARS 252.204-7012"]);
+        assert!(analyze(sub).is_subcontract);
+    }
+}
@@ -0,0 +1,362 @@
+//! Contract abstracts: the few sentences that carry a contract's key terms,
+//! plus the terms themselves. Segmentation and term extraction follow the
+//! document's language. English, German and French split on sentence
+//! punctuation minus each language's abbreviations and count words without
+//! their stopwords (French elisions dropped). Japanese has no spaces, so it
+//! splits on 。！？ and takes runs of kanji and katakana as terms, leaving
+//! the hiragana particles between them out.
+
+use std::collections::HashMap;
+
+use axum::{http::StatusCode, response::Json};
+use serde::{Deserialize, Serialize};
+
+const MAX_SENTENCES: usize = 3;
+const MAX_TERMS: usize = 10;
+/// Shorter Latin-script words are rarely terms ("of", "le", "im").
+const MIN_WORD_CHARS: usize = 3;
+/// Kanji and katakana runs shorter than this are mostly inflection stems.
+const MIN_JA_TERM_CHARS: usize = 2;
+
+const EN_STOPWORDS: &[&str] = &[
+    "the",
+    "and",
+    "for",
+    "that",
+    "this",
+    "with",
+    "from",
+    "are",
+    "was",
+    "were",
+    "been",
+    "its",
+    "any",
+    "all",
+    "not",
+    "such",
+    "which",
+    "shall",
+    "may",
+    "will",
+    "under",
+    "upon",
+    "other",
+    "than",
+    "each",
+    "into",
+    "within",
+    "after",
+    "before",
+    "has",
+    "have",
+    "had",
+    "being",
+    "but",
+    "by",
+    "per",
+    "whether",
+    "hereunder",
+    "herein",
+    "thereof",
+    "these",
+    "those",
+    "their",
+    "party",
+    "parties",
+];
+const DE_STOPWORDS: &[&str] = &[
+    "der", "die", "das", "den", "dem", "des", "und", "oder", "ein", "eine", "einer", "eines",
+    "einem", "einen", "ist", "sind", "wird", "werden", "wurde", "mit", "von", "für", "auf", "aus",
+    "bei", "nach", "nicht", "sich", "dass", "als", "auch", "zum", "zur", "über", "unter", "durch",
+    "diese", "dieser", "dieses", "soweit", "sofern", "kann", "muss", "soll", "hat", "haben",
+    "partei", "parteien",
+];
+const FR_STOPWORDS: &[&str] = &[
+    "les", "des", "une", "est", "sont", "par", "pour", "dans", "sur", "avec", "qui", "que", "aux",
+    "cette", "ces", "son", "ses", "leur", "leurs", "pas", "plus", "ont", "été", "être", "sera",
+    "seront", "doit", "peut", "tout", "toute", "tous", "toutes", "entre", "sans", "lors", "selon",
+    "dont", "elle", "ils", "partie", "parties",
+];
+
+/// Words ending in a period that do not end a sentence.
+const EN_ABBREVIATIONS: &[&str] = &[
+    "e.g.", "i.e.", "inc.", "ltd.", "co.", "corp.", "no.", "mr.", "ms.", "dr.", "vs.", "etc.",
+];
+const DE_ABBREVIATIONS: &[&str] = &[
+    "z.", "b.", "z.b.", "bzw.", "ca.", "nr.", "abs.", "art.", "ggf.", "gem.", "inkl.", "vgl.",
+    "usw.", "d.", "h.", "d.h.", "u.", "a.", "u.a.", "s.", "str.", "dr.",
+];
+const FR_ABBREVIATIONS: &[&str] = &[
+    "art.", "cf.", "m.", "mme.", "mlle.", "p.", "ex.", "p.ex.", "n°.", "no.", "etc.", "av.", "bd.",
+];
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct AbstractRequest {
+    pub document: String,
+    pub language: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeyTerm {
+    pub term: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Abstract {
+    pub language: String,
+    /// The chosen sentences, in document order.
+    pub sentences: Vec<String>,
+    /// Most frequent terms first.
+    pub key_terms: Vec<KeyTerm>,
+    pub sentence_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    /// Space-separated words, with a language's stopwords and abbreviations.
+    Latin {
+        stopwords: &'static [&'static str],
+        abbreviations: &'static [&'static str],
+    },
+    Japanese,
+}
+
+impl Script {
+    /// Unknown languages get the English rules.
+    fn of(language: &str) -> Self {
+        let (stopwords, abbreviations) = match language {
+            "ja" => return Self::Japanese,
+            "de" => (DE_STOPWORDS, DE_ABBREVIATIONS),
+            "fr" => (FR_STOPWORDS, FR_ABBREVIATIONS),
+            _ => (EN_STOPWORDS, EN_ABBREVIATIONS),
+        };
+        Self::Latin {
+            stopwords,
+            abbreviations,
+        }
+    }
+}
+
+// ── Segmentation ──────────────────────────────────────────────────────────────
+
+fn sentences(text: &str, script: Script) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, n)| n);
+        let end = i + c.len_utf8();
+        let boundary = match (script, c) {
+            (_, '\n') => next == Some('\n'),
+            (Script::Japanese, '。' | '！' | '？' | '!' | '?') => true,
+            (Script::Japanese, _) => false,
+            (Script::Latin { abbreviations, .. }, '.') => {
+                next.is_none_or(char::is_whitespace)
+                    && !abbreviated(&text[start..end], abbreviations)
+            }
+            (Script::Latin { .. }, '!' | '?' | ';') => next.is_none_or(char::is_whitespace),
+            _ => false,
+        };
+        if boundary {
+            push_sentence(&mut out, &text[start..end]);
+            start = end;
+        }
+    }
+    push_sentence(&mut out, &text[start..]);
+    out
+}
+
+/// Whether the period closing `sentence` belongs to an abbreviation or an
+/// ordinal or list number ("Nr.", "z. B.", "1. Januar", "2. Term").
+fn abbreviated(sentence: &str, abbreviations: &[&str]) -> bool {
+    let Some(word) = sentence.split_whitespace().last() else {
+        return false;
+    };
+    let lower = word.to_lowercase();
+    abbreviations.contains(&lower.as_str())
+        || lower
+            .trim_end_matches('.')
+            .chars()
+            .all(|c| c.is_ascii_digit())
+}
+
+fn push_sentence<'a>(out: &mut Vec<&'a str>, s: &'a str) {
+    let s = s.trim();
+    if !s.is_empty() {
+        out.push(s);
+    }
+}
+
+// ── Terms ─────────────────────────────────────────────────────────────────────
+
+fn is_kanji(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '々')
+}
+
+fn is_katakana(c: char) -> bool {
+    matches!(c, '\u{30A1}'..='\u{30FA}' | 'ー')
+}
+
+/// Candidate terms of one sentence, in order, repeats kept.
+fn terms(sentence: &str, script: Script) -> Vec<String> {
+    match script {
+        Script::Japanese => sentence
+            .split(|c: char| !(is_kanji(c) || is_katakana(c)))
+            .filter(|run| run.chars().count() >= MIN_JA_TERM_CHARS)
+            .map(str::to_string)
+            .collect(),
+        Script::Latin { stopwords, .. } => sentence
+            .split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '’' || c == '-'))
+            .map(|w| {
+                // French elisions: l'article, d'exécution, qu'il.
+                let w = w.rsplit(['\'', '’']).next().unwrap_or(w);
+                w.trim_matches('-').to_lowercase()
+            })
+            .filter(|w| {
+                w.chars().count() >= MIN_WORD_CHARS
+                    && w.chars().any(char::is_alphabetic)
+                    && !stopwords.contains(&w.as_str())
+            })
+            .collect(),
+    }
+}
+
+/// Builds the abstract: key terms by frequency, then the sentences that
+/// carry the most of them relative to their length.
+pub fn summarize(document: &str, language: &str) -> Abstract {
+    let script = Script::of(language);
+    let sentences = sentences(document, script);
+    let per_sentence: Vec<Vec<String>> = sentences.iter().map(|s| terms(s, script)).collect();
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut first_seen: HashMap<&str, usize> = HashMap::new();
+    for term in per_sentence.iter().flatten() {
+        let next = first_seen.len();
+        first_seen.entry(term).or_insert(next);
+        *counts.entry(term).or_default() += 1;
+    }
+    let mut ranked: Vec<(&str, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(first_seen[a.0].cmp(&first_seen[b.0])));
+    let weights: HashMap<&str, usize> = ranked.iter().copied().collect();
+
+    let mut scored: Vec<(usize, f64)> = per_sentence
+        .iter()
+        .enumerate()
+        .filter(|(_, terms)| !terms.is_empty())
+        .map(|(i, terms)| {
+            let mut distinct: Vec<&str> = terms.iter().map(String::as_str).collect();
+            distinct.sort_unstable();
+            distinct.dedup();
+            let weight: usize = distinct.iter().map(|t| weights[t]).sum();
+            (i, weight as f64 / (terms.len() as f64).sqrt())
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    let mut chosen: Vec<usize> = scored.iter().take(MAX_SENTENCES).map(|(i, _)| *i).collect();
+    chosen.sort_unstable();
+
+    Abstract {
+        language: language.to_string(),
+        sentences: chosen.iter().map(|&i| sentences[i].to_string()).collect(),
+        key_terms: ranked
+            .into_iter()
+            .take(MAX_TERMS)
+            .map(|(term, count)| KeyTerm {
+                term: term.to_string(),
+                count,
+            })
+            .collect(),
+        sentence_count: sentences.len(),
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn abstract_document(
+    Json(req): Json<AbstractRequest>,
+) -> Result<Json<Abstract>, StatusCode> {
+    if req.document.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(Json(summarize(&req.document, &req.language)))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms_of(a: &Abstract) -> Vec<&str> {
+        a.key_terms.iter().map(|t| t.term.as_str()).collect()
+    }
+
+    #[test]
+    fn german_abbreviations_and_ordinals_do_not_split_sentences() {
+        let text =
+            "Die Vergütung ist z. B. am 1. Januar fällig. Gem. Abs. 2 gilt die Haftung. Ende.";
+        assert_eq!(
+            sentences(text, Script::of("de")),
+            [
+                "Die Vergütung ist z. B. am 1. Januar fällig.",
+                "Gem. Abs. 2 gilt die Haftung.",
+                "Ende."
+            ]
+        );
+    }
+
+    #[test]
+    fn japanese_splits_on_full_stops_and_takes_kanji_and_katakana_runs() {
+        let text = "本契約は秘密保持について定める。受領者は秘密情報を第三者に開示してはならない。\
+                    秘密情報の管理はデータ保護法に従う。";
+        let a = summarize(text, "ja");
+        assert_eq!(a.sentence_count, 3);
+        assert_eq!(
+            a.key_terms[0],
+            KeyTerm {
+                term: "秘密情報".to_string(),
+                count: 2
+            }
+        );
+        assert!(terms_of(&a).contains(&"データ保護法"));
+        assert!(terms_of(&a).iter().all(|t| !t.contains('は')));
+    }
+
+    #[test]
+    fn french_drops_elisions_and_stopwords() {
+        let a = summarize(
+            "L'article 5 prévoit la résiliation. La résiliation d'office est exclue. \
+             Le prestataire doit notifier la résiliation.",
+            "fr",
+        );
+        assert_eq!(
+            a.key_terms[0],
+            KeyTerm {
+                term: "résiliation".to_string(),
+                count: 3
+            }
+        );
+        assert!(terms_of(&a).contains(&"article"));
+        assert!(terms_of(&a).contains(&"office"));
+        assert!(!terms_of(&a).contains(&"doit"));
+    }
+
+    #[test]
+    fn abstract_keeps_document_order_and_caps_its_length() {
+        let text = "Confidential information stays confidential. The weather was fine. \
+                    Confidential information excludes public information. Lunch is at noon. \
+                    Breach of confidentiality allows termination. Payment is due monthly.";
+        let a = summarize(text, "en");
+        assert_eq!(a.sentences.len(), MAX_SENTENCES);
+        assert_eq!(
+            a.sentences[0],
+            "Confidential information stays confidential."
+        );
+        assert_eq!(a.key_terms[0].term, "confidential");
+        assert!(!a.sentences.contains(&"Lunch is at noon.".to_string()));
+    }
+}
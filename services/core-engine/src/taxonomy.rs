@@ -0,0 +1,274 @@
+//! The clause taxonomy: every clause type the engine recognizes, the
+//! keywords that detect it and the risk level it starts at. The keyword
+//! matcher is built from this table (widened by a tenant's lexicon), and
+//! extractors report only the types whose keywords occur in the document.
+//! Earlier entries win when a section heading names more than one type.
+
+use crate::warmup::KeywordMatcher;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ClauseKind {
+    /// Keyword group name, as reported by `KeywordMatcher::hits`.
+    pub group: &'static str,
+    /// The `clause_type` findings carry.
+    pub name: &'static str,
+    pub risk: &'static str,
+    /// Matched ASCII case-insensitively anywhere in the text, so a stem
+    /// ("terminat") covers its inflections.
+    pub keywords: &'static [&'static str],
+}
+
+const fn kind(
+    group: &'static str,
+    name: &'static str,
+    risk: &'static str,
+    keywords: &'static [&'static str],
+) -> ClauseKind {
+    ClauseKind {
+        group,
+        name,
+        risk,
+        keywords,
+    }
+}
+
+/// `Jurisdiction` covers governing law and `Liability` its limitation; both
+/// keep the names stored analyses, calibration sets and ML backends use.
+pub const TAXONOMY: [ClauseKind; 14] = [
+    kind(
+        "jurisdiction",
+        "Jurisdiction",
+        "low",
+        &["jurisdiction", "governed by", "governing law"],
+    ),
+    kind("liability", "Liability", "high", &["liability", "liable"]),
+    kind("termination", "Termination", "medium", &["terminat"]),
+    kind(
+        "indemnification",
+        "Indemnification",
+        "high",
+        &["indemnif", "hold harmless"],
+    ),
+    kind("retention", "Data Retention", "medium", &["retention"]),
+    kind(
+        "assignment",
+        "Assignment",
+        "medium",
+        &["assignment", "assign this agreement", "assign its rights"],
+    ),
+    kind(
+        "force_majeure",
+        "Force Majeure",
+        "low",
+        &["force majeure", "act of god", "acts of god"],
+    ),
+    kind(
+        "auto_renewal",
+        "Auto-Renewal",
+        "medium",
+        &[
+            "automatically renew",
+            "renew automatically",
+            "renews automatically",
+            "automatic renewal",
+            "auto-renew",
+            "evergreen",
+        ],
+    ),
+    kind(
+        "non_compete",
+        "Non-Compete",
+        "high",
+        &["non-compet", "noncompet", "not compete"],
+    ),
+    kind(
+        "confidentiality",
+        "Confidentiality",
+        "medium",
+        &["confidential", "non-disclosure"],
+    ),
+    kind(
+        "payment",
+        "Payment Terms",
+        "low",
+        &["payment", "invoice", "payable"],
+    ),
+    kind("warranty", "Warranty", "medium", &["warrant"]),
+    kind(
+        "intellectual_property",
+        "Intellectual Property",
+        "high",
+        &["intellectual property", "copyright", "patent"],
+    ),
+    kind(
+        "dispute_resolution",
+        "Dispute Resolution",
+        "low",
+        &["arbitrat", "dispute resolution", "mediation"],
+    ),
+];
+
+/// A language code and its keywords by keyword group.
+pub type Localized = (
+    &'static str,
+    &'static [(&'static str, &'static [&'static str])],
+);
+
+/// Keywords in other languages, by language and keyword group. They count
+/// only in documents in that language, where stems like French "cession"
+/// cannot misfire on English words; the English keywords count everywhere,
+/// since contracts in any language quote them.
+pub const LOCALIZED: [Localized; 3] = [
+    (
+        "de",
+        &[
+            (
+                "jurisdiction",
+                &["gerichtsstand", "anwendbares recht", "unterliegt dem recht"],
+            ),
+            ("liability", &["haftung", "haftet"]),
+            ("termination", &["kündig"]),
+            ("indemnification", &["freistell", "schadlos"]),
+            ("retention", &["aufbewahrung"]),
+            ("assignment", &["abtret"]),
+            ("force_majeure", &["höhere gewalt"]),
+            (
+                "auto_renewal",
+                &["verlängert sich automatisch", "stillschweigend verlängert"],
+            ),
+            ("non_compete", &["wettbewerbsverbot"]),
+            ("confidentiality", &["vertraulich", "geheimhaltung"]),
+            ("payment", &["zahlung", "rechnung"]),
+            ("warranty", &["gewährleistung", "garantie"]),
+            (
+                "intellectual_property",
+                &["geistiges eigentum", "geistigen eigentum", "urheberrecht"],
+            ),
+            (
+                "dispute_resolution",
+                &["schiedsgericht", "schiedsverfahren", "schlichtung"],
+            ),
+        ],
+    ),
+    (
+        "fr",
+        &[
+            (
+                "jurisdiction",
+                &[
+                    "juridiction",
+                    "tribunaux compétents",
+                    "droit applicable",
+                    "régi par",
+                ],
+            ),
+            ("liability", &["responsabilité", "responsable"]),
+            ("termination", &["résiliation", "résilier"]),
+            ("indemnification", &["indemnis", "garantir contre"]),
+            (
+                "retention",
+                &["conservation des données", "durée de conservation"],
+            ),
+            ("assignment", &["cession", "céder"]),
+            (
+                "auto_renewal",
+                &["reconduction tacite", "renouvelé automatiquement"],
+            ),
+            ("non_compete", &["non-concurrence", "non concurrence"]),
+            ("confidentiality", &["confidentialité", "confidentiel"]),
+            ("payment", &["paiement", "facture"]),
+            ("warranty", &["garantie"]),
+            (
+                "intellectual_property",
+                &["propriété intellectuelle", "droit d'auteur", "brevet"],
+            ),
+            (
+                "dispute_resolution",
+                &["arbitrage", "médiation", "règlement des différends"],
+            ),
+        ],
+    ),
+    (
+        "ja",
+        &[
+            ("jurisdiction", &["管轄", "準拠法"]),
+            ("liability", &["責任", "損害賠償"]),
+            ("termination", &["解除", "解約"]),
+            ("indemnification", &["補償", "免責"]),
+            ("retention", &["保存期間", "保管期間"]),
+            ("assignment", &["譲渡"]),
+            ("force_majeure", &["不可抗力"]),
+            ("auto_renewal", &["自動更新", "自動的に更新"]),
+            ("non_compete", &["競業"]),
+            ("confidentiality", &["秘密", "機密"]),
+            ("payment", &["支払", "請求書"]),
+            ("warranty", &["保証"]),
+            ("intellectual_property", &["知的財産", "著作権", "特許"]),
+            ("dispute_resolution", &["仲裁", "調停", "紛争解決"]),
+        ],
+    ),
+];
+
+/// The clause type of keyword group `group`.
+pub fn by_group(group: &str) -> Option<&'static ClauseKind> {
+    TAXONOMY.iter().find(|k| k.group == group)
+}
+
+/// The types detected in `document` with the span of each one's first
+/// keyword, in document order.
+pub fn detect(
+    keywords: &KeywordMatcher,
+    document: &str,
+) -> Vec<(&'static ClauseKind, (usize, usize))> {
+    let mut found: Vec<_> = keywords
+        .hits(document)
+        .into_iter()
+        .filter_map(|(group, span)| by_group(group).map(|k| (k, span)))
+        .collect();
+    found.sort_by_key(|&(_, (start, _))| start);
+    found
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warmup::Precompiled;
+
+    #[test]
+    fn groups_and_names_are_unique() {
+        for (i, a) in TAXONOMY.iter().enumerate() {
+            for b in &TAXONOMY[i + 1..] {
+                assert_ne!(a.group, b.group);
+                assert_ne!(a.name, b.name);
+            }
+            assert!(["low", "medium", "high"].contains(&a.risk), "{}", a.name);
+        }
+        for (language, groups) in LOCALIZED {
+            for (group, keywords) in groups {
+                assert!(by_group(group).is_some(), "{language}: {group}");
+                assert!(
+                    keywords.iter().all(|k| *k == k.to_lowercase()),
+                    "{language}: {group}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn only_present_types_are_detected_in_document_order() {
+        let pre = Precompiled::default();
+        let text = "Neither party may assign this Agreement. Payment is due on invoice. \
+                    Neither party is liable for delays caused by force majeure.";
+        let names: Vec<&str> = detect(pre.keywords(), text)
+            .iter()
+            .map(|(k, _)| k.name)
+            .collect();
+        assert_eq!(
+            names,
+            ["Assignment", "Payment Terms", "Liability", "Force Majeure"]
+        );
+        assert!(detect(pre.keywords(), "Lunch is served at noon.").is_empty());
+    }
+}
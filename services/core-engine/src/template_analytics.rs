@@ -0,0 +1,367 @@
+//! Usage analytics for the template library: how often each template is
+//! compiled, which variables callers fill in or leave out, which optional
+//! sections end up in the document, and how long compiled documents take
+//! to be signed. Legal ops use it to prune templates nobody compiles and
+//! fix the variables everybody forgets.
+//!
+//! A compile is linked to a signature by its text: a document recorded as
+//! executed whose text, whitespace aside, is a tracked compile output counts
+//! towards that template's compile-to-signature time.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use axum::{extract::State, http::HeaderMap, response::Json};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{notifier, AppState, CompileResponse};
+
+/// Compile outputs remembered for matching signatures; the oldest are
+/// forgotten first.
+const MAX_TRACKED_COMPILES: usize = 10_000;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default)]
+struct Usage {
+    compiles: u64,
+    variables: BTreeMap<String, u64>,
+    missing: BTreeMap<String, u64>,
+    /// Compiles in which each `{{#if}}`/`{{#unless}}` condition was set.
+    conditions: BTreeMap<String, u64>,
+    signed: u64,
+    signature_seconds: f64,
+}
+
+#[derive(Debug, Clone)]
+struct Compiled {
+    template_id: String,
+    compiled_at: DateTime<Utc>,
+}
+
+/// A tenant and the fingerprint of a document it compiled.
+type TextKey = (String, [u8; 32]);
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NameCount {
+    pub name: String,
+    pub count: u64,
+    /// Share of the template's compiles.
+    pub rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct TemplateUsage {
+    pub template_id: String,
+    pub compiles: u64,
+    /// Variables given a value, most used first.
+    pub variables: Vec<NameCount>,
+    /// Required variables left out, most often missing first.
+    pub missing_variables: Vec<NameCount>,
+    /// Conditions of optional sections, by how often they were set; an
+    /// `{{#unless}}` section renders when its condition is not.
+    pub optional_sections: Vec<NameCount>,
+    /// Compiled documents later recorded as executed.
+    pub signed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_compile_to_signature_hours: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalyticsResponse {
+    pub tenant: String,
+    /// Most compiled first.
+    pub templates: Vec<TemplateUsage>,
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct TemplateAnalytics {
+    usage: DashMap<(String, String), Usage>,
+    compiled: Mutex<Tracked>,
+}
+
+#[derive(Default)]
+struct Tracked {
+    by_text: HashMap<TextKey, Compiled>,
+    order: VecDeque<TextKey>,
+}
+
+/// Hash of the text with runs of whitespace collapsed, so a compiled
+/// document still matches after a round trip that rewraps lines.
+fn fingerprint(text: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for (i, word) in text.split_whitespace().enumerate() {
+        if i > 0 {
+            hasher.update(b" ");
+        }
+        hasher.update(word.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// Handlebars truthiness, with `variables` winning over `data`.
+fn is_set(name: &str, variables: &HashMap<String, String>, data: &Map<String, Value>) -> bool {
+    if let Some(value) = variables.get(name) {
+        return !value.is_empty();
+    }
+    match data.get(name) {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_f64().is_some_and(|n| n != 0.0),
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(Value::Array(a)) => !a.is_empty(),
+        Some(Value::Object(_)) => true,
+    }
+}
+
+fn ranked(counts: &BTreeMap<String, u64>, compiles: u64) -> Vec<NameCount> {
+    let mut ranked: Vec<NameCount> = counts
+        .iter()
+        .map(|(name, &count)| NameCount {
+            name: name.clone(),
+            count,
+            rate: count as f64 / compiles.max(1) as f64,
+        })
+        .collect();
+    ranked.sort_by_key(|n| std::cmp::Reverse(n.count));
+    ranked
+}
+
+impl TemplateAnalytics {
+    /// Counts a successful compile of `template_id` whose optional sections
+    /// test `conditions`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_compile(
+        &self,
+        tenant: &str,
+        template_id: &str,
+        conditions: &BTreeSet<&str>,
+        variables: &HashMap<String, String>,
+        data: &Map<String, Value>,
+        compiled: &CompileResponse,
+        at: DateTime<Utc>,
+    ) {
+        {
+            let mut usage = self
+                .usage
+                .entry((tenant.to_string(), template_id.to_string()))
+                .or_default();
+            usage.compiles += 1;
+            for (name, value) in variables {
+                if !value.is_empty() {
+                    *usage.variables.entry(name.clone()).or_default() += 1;
+                }
+            }
+            for name in &compiled.missing_variables {
+                *usage.missing.entry(name.clone()).or_default() += 1;
+            }
+            for name in conditions {
+                let count = usage.conditions.entry(name.to_string()).or_default();
+                *count += u64::from(is_set(name, variables, data));
+            }
+        }
+
+        let key = (tenant.to_string(), fingerprint(&compiled.compiled_document));
+        let mut tracked = self.compiled.lock().unwrap();
+        if tracked.by_text.contains_key(&key) {
+            tracked.order.retain(|k| *k != key);
+        }
+        tracked.by_text.insert(
+            key.clone(),
+            Compiled {
+                template_id: template_id.to_string(),
+                compiled_at: at,
+            },
+        );
+        tracked.order.push_back(key);
+        while tracked.order.len() > MAX_TRACKED_COMPILES {
+            if let Some(oldest) = tracked.order.pop_front() {
+                tracked.by_text.remove(&oldest);
+            }
+        }
+    }
+
+    /// Counts the signature of `text` if it is a tracked compile output of
+    /// `tenant`'s; returns the template it was compiled from.
+    pub fn record_signature(&self, tenant: &str, text: &str, at: DateTime<Utc>) -> Option<String> {
+        let key = (tenant.to_string(), fingerprint(text));
+        let compiled = {
+            let mut tracked = self.compiled.lock().unwrap();
+            let compiled = tracked.by_text.remove(&key)?;
+            tracked.order.retain(|k| *k != key);
+            compiled
+        };
+        let mut usage = self
+            .usage
+            .entry((tenant.to_string(), compiled.template_id.clone()))
+            .or_default();
+        usage.signed += 1;
+        usage.signature_seconds += (at - compiled.compiled_at).num_seconds().max(0) as f64;
+        Some(compiled.template_id)
+    }
+
+    pub fn report(&self, tenant: &str) -> Vec<TemplateUsage> {
+        let mut templates: Vec<TemplateUsage> = self
+            .usage
+            .iter()
+            .filter(|e| e.key().0 == tenant)
+            .map(|e| {
+                let usage = e.value();
+                TemplateUsage {
+                    template_id: e.key().1.clone(),
+                    compiles: usage.compiles,
+                    variables: ranked(&usage.variables, usage.compiles),
+                    missing_variables: ranked(&usage.missing, usage.compiles),
+                    optional_sections: ranked(&usage.conditions, usage.compiles),
+                    signed: usage.signed,
+                    average_compile_to_signature_hours: (usage.signed > 0).then(|| {
+                        (usage.signature_seconds / usage.signed as f64 / 36.0).round() / 100.0
+                    }),
+                }
+            })
+            .collect();
+        templates.sort_by(|a, b| {
+            b.compiles
+                .cmp(&a.compiles)
+                .then_with(|| a.template_id.cmp(&b.template_id))
+        });
+        templates
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn analytics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Json<AnalyticsResponse> {
+    let tenant = notifier::tenant(&headers);
+    Json(AnalyticsResponse {
+        templates: state.template_analytics.report(&tenant),
+        tenant,
+    })
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn compiled(text: &str, missing: &[&str]) -> CompileResponse {
+        CompileResponse {
+            template_id: "nda".to_string(),
+            template_version: None,
+            compiled_document: text.to_string(),
+            variables_applied: 0,
+            missing_variables: missing.iter().map(|m| m.to_string()).collect(),
+            annotations: None,
+            policy_warnings: Vec::new(),
+            entity_warnings: Vec::new(),
+            library_clauses: Vec::new(),
+            rendered: None,
+            provenance: None,
+        }
+    }
+
+    #[test]
+    fn compiles_count_variables_missing_ones_and_set_conditions() {
+        let store = TemplateAnalytics::default();
+        let at = Utc.with_ymd_and_hms(2026, 1, 5, 9, 0, 0).unwrap();
+        let conditions = BTreeSet::from(["cap", "schedules"]);
+        let variables = HashMap::from([
+            ("party_a".to_string(), "Acme".to_string()),
+            ("cap".to_string(), String::new()),
+        ]);
+        let data = serde_json::json!({ "schedules": [{ "name": "A" }] });
+        let data = data.as_object().unwrap();
+        store.record_compile(
+            "t1",
+            "nda",
+            &conditions,
+            &variables,
+            data,
+            &compiled("one", &["party_b"]),
+            at,
+        );
+        store.record_compile(
+            "t1",
+            "nda",
+            &conditions,
+            &HashMap::new(),
+            &Map::new(),
+            &compiled("two", &["party_a", "party_b"]),
+            at,
+        );
+        store.record_compile(
+            "t1",
+            "sla",
+            &BTreeSet::new(),
+            &HashMap::new(),
+            &Map::new(),
+            &compiled("three", &[]),
+            at,
+        );
+
+        let report = store.report("t1");
+        assert_eq!(report[0].template_id, "nda");
+        assert_eq!(report[0].compiles, 2);
+        assert_eq!(
+            report[0].variables,
+            [NameCount {
+                name: "party_a".to_string(),
+                count: 1,
+                rate: 0.5
+            }]
+        );
+        assert_eq!(report[0].missing_variables[0].name, "party_b");
+        assert_eq!(report[0].missing_variables[0].rate, 1.0);
+        let sections: Vec<(&str, u64)> = report[0]
+            .optional_sections
+            .iter()
+            .map(|s| (s.name.as_str(), s.count))
+            .collect();
+        assert_eq!(sections, [("schedules", 1), ("cap", 0)]);
+        assert_eq!(report[1].template_id, "sla");
+        assert!(store.report("t2").is_empty());
+    }
+
+    #[test]
+    fn signatures_of_compiled_text_give_the_average_lead_time() {
+        let store = TemplateAnalytics::default();
+        let at = Utc.with_ymd_and_hms(2026, 1, 5, 9, 0, 0).unwrap();
+        let none = BTreeSet::new();
+        let doc = compiled("NDA between\nAcme and Beta.", &[]);
+        store.record_compile("t1", "nda", &none, &HashMap::new(), &Map::new(), &doc, at);
+
+        assert_eq!(
+            store.record_signature("t2", &doc.compiled_document, at),
+            None
+        );
+        assert_eq!(store.record_signature("t1", "Something else.", at), None);
+        let signed = store.record_signature(
+            "t1",
+            "NDA between Acme  and Beta.\n",
+            at + Duration::hours(30),
+        );
+        assert_eq!(signed.as_deref(), Some("nda"));
+        // A compile is signed once.
+        assert_eq!(
+            store.record_signature("t1", &doc.compiled_document, at),
+            None
+        );
+
+        let report = store.report("t1");
+        assert_eq!(report[0].signed, 1);
+        assert_eq!(report[0].average_compile_to_signature_hours, Some(30.0));
+    }
+}
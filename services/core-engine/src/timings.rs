@@ -0,0 +1,98 @@
+//! Where an analysis spent its time, reported in every response so slow
+//! requests can be traced to a stage.
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{clock::Clock, packing::PackingReport};
+
+/// Per-stage wall time in milliseconds, measured on the state's clock so
+/// reproducible runs (frozen clock) report zeros and stay byte-identical.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct AnalysisTimings {
+    /// Tokenizing and fingerprinting for paper detection.
+    pub parse_ms: f64,
+    /// Section splitting and quick-mode truncation.
+    pub segmentation_ms: f64,
+    /// Clause, issue, export-control, regulatory and playbook checks.
+    pub classification_ms: f64,
+    /// Risk scoring and escalation of low-confidence findings.
+    pub scoring_ms: f64,
+    /// Time waiting on the ML backend, when one is configured.
+    pub backend_calls_ms: f64,
+    pub total_ms: f64,
+    /// Results served from cache instead of recomputed; there is no result
+    /// cache yet.
+    pub cache_hits: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct AnalysisMetadata {
+    pub timings: AnalysisTimings,
+    /// Set in latency-bounded mode: the budget the analysis was fitted to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_budget_ms: Option<u64>,
+    /// Set when a large document was classified in chunks: how many.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunks: Option<usize>,
+    /// Set when the document was over the ML backend's token budget: what
+    /// it was sent instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ml_context: Option<PackingReport>,
+}
+
+/// Measures consecutive stages against a clock.
+pub struct Stopwatch<'a> {
+    clock: &'a dyn Clock,
+    started: DateTime<Utc>,
+    last: DateTime<Utc>,
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn millis(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    (to - from).num_microseconds().unwrap_or(i64::MAX) as f64 / 1000.0
+}
+
+impl<'a> Stopwatch<'a> {
+    pub fn start(clock: &'a dyn Clock) -> Self {
+        let now = clock.now();
+        Self {
+            clock,
+            started: now,
+            last: now,
+        }
+    }
+
+    /// Milliseconds since the previous lap (or the start).
+    pub fn lap(&mut self) -> f64 {
+        let now = self.clock.now();
+        let ms = millis(self.last, now);
+        self.last = now;
+        ms
+    }
+
+    pub fn total(&self) -> f64 {
+        millis(self.started, self.clock.now())
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+
+    #[test]
+    fn laps_measure_consecutive_stages() {
+        let clock = FixedClock::new(Utc::now());
+        let mut watch = Stopwatch::start(&clock);
+        clock.advance(chrono::Duration::microseconds(1_500));
+        assert!((watch.lap() - 1.5).abs() < f64::EPSILON);
+        assert!(watch.lap().abs() < f64::EPSILON);
+        clock.advance(chrono::Duration::milliseconds(2));
+        assert!((watch.lap() - 2.0).abs() < f64::EPSILON);
+        assert!((watch.total() - 3.5).abs() < f64::EPSILON);
+    }
+}
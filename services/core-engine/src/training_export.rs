@@ -0,0 +1,270 @@
+//! Streams the clause corpus as JSON Lines for classifier training: one line
+//! per extracted clause, with the machine prediction and any reviewer label,
+//! anonymized before it leaves the service.
+
+use std::convert::Infallible;
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use futures_util::stream;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    access::ROLE_HEADER,
+    corpus::StoredDocument,
+    escalation::{ItemKind, Verdict},
+    evidence::DEFAULT_CONTEXT_CHARS,
+    renewal, AppState,
+};
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExportQuery {
+    /// Matches the reviewed clause type when there is one, else the predicted.
+    pub clause_type: Option<String>,
+    pub risk_level: Option<String>,
+    pub language: Option<String>,
+    /// Documents stored at or after this instant.
+    pub since: Option<DateTime<Utc>>,
+    /// Only clauses a reviewer has ruled on.
+    #[serde(default)]
+    pub labeled_only: bool,
+}
+
+/// A reviewer's ruling on a clause. Reviewer identities are not exported.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Label {
+    pub verdict: Verdict,
+    /// `None` when the reviewer rejected the clause outright.
+    pub clause_type: Option<String>,
+    pub risk_level: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrainingRecord {
+    pub analysis_id: String,
+    pub clause_id: String,
+    pub language: String,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+    pub predicted_clause_type: String,
+    pub predicted_risk_level: String,
+    pub confidence: f64,
+    pub label: Option<Label>,
+}
+
+// ── Anonymization ─────────────────────────────────────────────────────────────
+
+/// The token with its sensitive core replaced, keeping surrounding punctuation.
+fn scrub_token(token: &str) -> Option<String> {
+    let core = token.trim_matches(|c: char| !c.is_alphanumeric() && !"$€£¥".contains(c));
+    let tag = if core.contains('@') && core.contains('.') {
+        "[EMAIL]"
+    } else if core.starts_with(['$', '€', '£', '¥'])
+        || core.chars().filter(char::is_ascii_digit).count() >= 5
+    {
+        "[NUMBER]"
+    } else {
+        return None;
+    };
+    Some(token.replacen(core, tag, 1))
+}
+
+/// Replaces party names, email addresses, amounts and long digit runs
+/// (phone, account and registration numbers). Whitespace is preserved.
+pub fn anonymize(text: &str, parties: &[String]) -> String {
+    let mut named = text.to_string();
+    for (i, party) in parties.iter().enumerate() {
+        let tag = format!("[PARTY_{}]", char::from(b'A' + (i % 26) as u8));
+        named = named.replace(party.as_str(), &tag);
+    }
+    let mut out = String::with_capacity(named.len());
+    for piece in named.split_inclusive(char::is_whitespace) {
+        let token = piece.trim_end_matches(char::is_whitespace);
+        match scrub_token(token) {
+            Some(scrubbed) => {
+                out.push_str(&scrubbed);
+                out.push_str(&piece[token.len()..]);
+            }
+            None => out.push_str(piece),
+        }
+    }
+    out
+}
+
+// ── Records ───────────────────────────────────────────────────────────────────
+
+/// Fresh predictions for a document joined with reviewer verdicts; rejected
+/// clauses are kept, labelled as rejected, since they are useful negatives.
+pub fn records(state: &AppState, doc: &StoredDocument) -> Vec<TrainingRecord> {
+    let verdicts = state.escalations.resolved_for(&doc.id);
+    let reviewed = state.escalations.analysis(&doc.id);
+    let parties = renewal::parties(&doc.text).map_or_else(Vec::new, |(a, b)| vec![a, b]);
+
+    state
+        .clause_extractor
+        .extract(
+            state.precompiled.keywords(),
+            &doc.text,
+            DEFAULT_CONTEXT_CHARS,
+        )
+        .into_iter()
+        .map(|clause| {
+            let label = verdicts
+                .iter()
+                .find(|e| e.item_kind == ItemKind::Clause && e.item_id == clause.id)
+                .and_then(|e| {
+                    let verdict = e.verdict?;
+                    let current = reviewed
+                        .as_ref()
+                        .and_then(|a| a.clauses.iter().find(|c| c.id == clause.id));
+                    Some(Label {
+                        verdict,
+                        clause_type: current.map(|c| c.clause_type.clone()),
+                        risk_level: current.map(|c| c.risk_level.clone()),
+                    })
+                });
+            TrainingRecord {
+                analysis_id: doc.id.clone(),
+                clause_id: clause.id,
+                language: doc.language.clone(),
+                text: anonymize(&clause.text, &parties),
+                excerpt: clause.excerpt.map(|e| anonymize(&e, &parties)),
+                predicted_clause_type: clause.clause_type,
+                predicted_risk_level: clause.risk_level,
+                confidence: clause.confidence,
+                label,
+            }
+        })
+        .collect()
+}
+
+fn matches(record: &TrainingRecord, q: &ExportQuery) -> bool {
+    let label = record.label.as_ref();
+    let clause_type = label
+        .and_then(|l| l.clause_type.as_deref())
+        .unwrap_or(&record.predicted_clause_type);
+    let risk_level = label
+        .and_then(|l| l.risk_level.as_deref())
+        .unwrap_or(&record.predicted_risk_level);
+    (!q.labeled_only || label.is_some())
+        && q.clause_type
+            .as_deref()
+            .is_none_or(|t| t.eq_ignore_ascii_case(clause_type))
+        && q.risk_level
+            .as_deref()
+            .is_none_or(|r| r.eq_ignore_ascii_case(risk_level))
+}
+
+fn lines(state: &AppState, doc: &StoredDocument, q: &ExportQuery) -> String {
+    let mut out = String::new();
+    for record in records(state, doc).iter().filter(|r| matches(r, q)) {
+        // Plain structs of strings and numbers always serialize.
+        out.push_str(&serde_json::to_string(record).expect("training record serializes"));
+        out.push('\n');
+    }
+    out
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// Restricted to roles that may see every field, since clause text leaves
+/// with the export. Documents are analyzed one at a time as the body streams.
+pub async fn export_corpus(
+    State(state): State<AppState>,
+    Query(q): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let role = headers
+        .get(ROLE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or(StatusCode::FORBIDDEN)?;
+    let full_access = state
+        .access_policy
+        .roles
+        .get(role)
+        .is_some_and(|paths| paths.iter().any(|p| p == "*"));
+    if !full_access {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let docs: Vec<StoredDocument> = state
+        .corpus
+        .all()
+        .into_iter()
+        .filter(|d| q.language.as_deref().is_none_or(|l| l == d.language))
+        .filter(|d| q.since.is_none_or(|s| d.stored_at >= s))
+        .collect();
+    info!(
+        role,
+        documents = docs.len(),
+        "training corpus export started"
+    );
+    state.record_audit(
+        "corpus.exported",
+        "clauses",
+        Some(role),
+        serde_json::json!({ "documents": docs.len() }),
+    );
+
+    let body = stream::iter(
+        docs.into_iter()
+            .map(move |doc| Ok::<_, Infallible>(lines(&state, &doc, &q))),
+    );
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anonymize_replaces_parties_contacts_and_numbers() {
+        let parties = vec!["Acme Corp".to_string(), "Globex Ltd".to_string()];
+        let text =
+            "Acme Corp pays Globex Ltd $25,000 by 30 June.\nContact legal@acme.com or 555-0100-22.";
+        assert_eq!(
+            anonymize(text, &parties),
+            "[PARTY_A] pays [PARTY_B] [NUMBER] by 30 June.\nContact [EMAIL] or [NUMBER]."
+        );
+    }
+
+    #[test]
+    fn filters_prefer_the_reviewer_label() {
+        let record = TrainingRecord {
+            analysis_id: "a1".to_string(),
+            clause_id: "clause-002".to_string(),
+            language: "en".to_string(),
+            text: String::new(),
+            excerpt: None,
+            predicted_clause_type: "Liability".to_string(),
+            predicted_risk_level: "high".to_string(),
+            confidence: 0.6,
+            label: Some(Label {
+                verdict: Verdict::Override,
+                clause_type: Some("Indemnity".to_string()),
+                risk_level: Some("medium".to_string()),
+            }),
+        };
+        let q = |t: &str| ExportQuery {
+            clause_type: Some(t.to_string()),
+            ..ExportQuery::default()
+        };
+        assert!(matches(&record, &q("indemnity")));
+        assert!(!matches(&record, &q("Liability")));
+    }
+}
@@ -0,0 +1,292 @@
+//! Quick-mode input limit. Oversized documents keep every section heading and
+//! the first and last tokens of each section body; everything else is
+//! reported as a skipped span instead of being dropped silently.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+pub const STRATEGY: &str = "headings_head_tail";
+/// Longer heading lines only keep this many of their leading tokens.
+const HEADING_TOKENS: usize = 12;
+/// Joins kept runs so readers (and detectors) see where text was cut.
+const GAP_MARKER: &str = "\n[…]\n";
+
+#[derive(Debug, Clone)]
+pub struct TruncationConfig {
+    /// Whitespace-separated tokens processed in quick mode.
+    pub quick_max_tokens: usize,
+}
+
+impl Default for TruncationConfig {
+    fn default() -> Self {
+        Self {
+            quick_max_tokens: 4_000,
+        }
+    }
+}
+
+impl TruncationConfig {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        Self {
+            quick_max_tokens: std::env::var("LEGAL_QUICK_MAX_TOKENS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(d.quick_max_tokens),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct SkippedSpan {
+    /// Heading of the section the span was cut from, if it has one.
+    pub section: Option<String>,
+    /// Byte offsets into the submitted document.
+    pub start: usize,
+    pub end: usize,
+    pub tokens: usize,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct TruncationReport {
+    pub strategy: String,
+    pub limit_tokens: usize,
+    pub input_tokens: usize,
+    pub kept_tokens: usize,
+    pub skipped_spans: Vec<SkippedSpan>,
+    /// Clause and issue IDs without evidence in the kept text; their
+    /// conclusion may differ on the full document.
+    pub affected_findings: Vec<String>,
+}
+
+pub struct Truncated {
+    pub text: String,
+    pub report: TruncationReport,
+    /// Byte ranges of the submitted document that `text` keeps, in order.
+    pub kept: Vec<std::ops::Range<usize>>,
+}
+
+impl Truncated {
+    /// The offset in the submitted document of byte `offset` of `text`. A
+    /// gap marker maps to the end of the run before it.
+    pub fn original_offset(&self, offset: usize) -> usize {
+        let mut from = 0;
+        for run in &self.kept {
+            if offset <= from + run.len() {
+                return run.start + offset - from;
+            }
+            from += run.len() + GAP_MARKER.len();
+            if offset < from {
+                return run.end;
+            }
+        }
+        self.kept.last().map_or(offset, |run| run.end)
+    }
+}
+
+// ── Sections ──────────────────────────────────────────────────────────────────
+
+fn is_heading(line: &str) -> bool {
+    let line = line.trim();
+    let lower = line.to_ascii_lowercase();
+    let numbered = line
+        .split_once(['.', ')'])
+        .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+    let caps = line.chars().any(char::is_alphabetic)
+        && !line.chars().any(char::is_lowercase)
+        && line.len() <= 80;
+    numbered || caps || lower.starts_with("section ") || lower.starts_with("article ")
+}
+
+fn token_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                spans.push((s, i));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        spans.push((s, text.len()));
+    }
+    spans
+}
+
+struct Section {
+    /// Token index range of the whole section.
+    tokens: std::ops::Range<usize>,
+    heading_tokens: usize,
+}
+
+/// Splits at lines that look like headings; text before the first heading
+/// is a section without one.
+fn sections(text: &str, spans: &[(usize, usize)]) -> Vec<Section> {
+    let token_at = |offset: usize| spans.partition_point(|&(s, _)| s < offset);
+    // (first token, heading tokens) per section start.
+    let mut starts: Vec<(usize, usize)> = vec![(0, 0)];
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let line_end = line_start + line.len();
+        if is_heading(line) {
+            let first = token_at(line_start);
+            let heading = (token_at(line_end) - first).min(HEADING_TOKENS);
+            match starts.last_mut() {
+                Some(last) if last.0 == first => last.1 = heading,
+                _ => starts.push((first, heading)),
+            }
+        }
+        line_start = line_end;
+    }
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, &(start, heading_tokens))| Section {
+            tokens: start..starts.get(i + 1).map_or(spans.len(), |s| s.0),
+            heading_tokens,
+        })
+        .filter(|s| !s.tokens.is_empty())
+        .collect()
+}
+
+// ── Strategy ──────────────────────────────────────────────────────────────────
+
+/// `None` when the document fits within `limit` tokens.
+pub fn truncate(text: &str, limit: usize) -> Option<Truncated> {
+    let spans = token_spans(text);
+    if spans.len() <= limit {
+        return None;
+    }
+    let sections = sections(text, &spans);
+
+    // Headings are always kept; the rest of the budget is shared out so short
+    // sections are kept whole and their unused share goes to longer ones.
+    let headings: usize = sections.iter().map(|s| s.heading_tokens).sum();
+    let mut remaining = limit.saturating_sub(headings);
+    let mut order: Vec<usize> = (0..sections.len()).collect();
+    order.sort_by_key(|&i| sections[i].tokens.len() - sections[i].heading_tokens);
+    let mut body_budget = vec![0; sections.len()];
+    for (n, &i) in order.iter().enumerate() {
+        let body = sections[i].tokens.len() - sections[i].heading_tokens;
+        let share = remaining / (order.len() - n);
+        body_budget[i] = body.min(share);
+        remaining -= body_budget[i];
+    }
+
+    let mut kept: Vec<std::ops::Range<usize>> = Vec::new();
+    let mut skipped_spans = Vec::new();
+    for (section, budget) in sections.iter().zip(body_budget) {
+        let body_start = section.tokens.start + section.heading_tokens;
+        let body_len = section.tokens.end - body_start;
+        let head = budget.div_ceil(2);
+        let tail = budget - head;
+        let cut = (body_start + head)..(section.tokens.end - tail);
+        let mut keep = |r: std::ops::Range<usize>| match kept.last_mut() {
+            Some(last) if last.end == r.start => last.end = r.end,
+            _ if r.is_empty() => {}
+            _ => kept.push(r),
+        };
+        if budget >= body_len {
+            keep(section.tokens.clone());
+            continue;
+        }
+        keep(section.tokens.start..cut.start);
+        skipped_spans.push(SkippedSpan {
+            section: (section.heading_tokens > 0).then(|| {
+                let (s, _) = spans[section.tokens.start];
+                let (_, e) = spans[section.tokens.start + section.heading_tokens - 1];
+                text[s..e].lines().next().unwrap_or_default().to_string()
+            }),
+            start: spans[cut.start].0,
+            end: spans[cut.end - 1].1,
+            tokens: cut.len(),
+        });
+        keep(cut.end..section.tokens.end);
+    }
+
+    let kept_tokens = kept.iter().map(|r| r.len()).sum();
+    let kept: Vec<std::ops::Range<usize>> = kept
+        .iter()
+        .map(|r| spans[r.start].0..spans[r.end - 1].1)
+        .collect();
+    Some(Truncated {
+        text: kept
+            .iter()
+            .map(|r| &text[r.clone()])
+            .collect::<Vec<_>>()
+            .join(GAP_MARKER),
+        report: TruncationReport {
+            strategy: STRATEGY.to_string(),
+            limit_tokens: limit,
+            input_tokens: spans.len(),
+            kept_tokens,
+            skipped_spans,
+            affected_findings: Vec::new(),
+        },
+        kept,
+    })
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(prefix: &str, n: usize) -> String {
+        (0..n)
+            .map(|i| format!("{prefix}{i}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn short_documents_are_untouched() {
+        assert!(truncate("1. Term. One year.", 10).is_none());
+    }
+
+    #[test]
+    fn kept_offsets_map_back_to_the_submitted_document() {
+        let doc = format!("1. Scope\n{}\n2. Fees\n{}\n", words("a", 40), words("b", 4));
+        let t = truncate(&doc, 20).unwrap();
+        for word in ["Scope", "a3", "a36", "b2"] {
+            let at = t.text.find(word).unwrap();
+            assert_eq!(&doc[t.original_offset(at)..][..word.len()], word);
+        }
+        let gap = t.text.find("[…]").unwrap();
+        assert_eq!(t.original_offset(gap), doc.find(" a6").unwrap());
+    }
+
+    #[test]
+    fn keeps_headings_and_section_ends() {
+        let doc = format!("1. Scope\n{}\n2. Fees\n{}\n", words("a", 40), words("b", 4));
+        let t = truncate(&doc, 20).unwrap();
+        assert_eq!(t.report.input_tokens, 48);
+        assert_eq!(t.report.kept_tokens, 20);
+        // The short section is kept whole; the long one loses its middle.
+        assert!(t.text.contains("2. Fees\nb0 b1 b2 b3"));
+        assert!(t.text.starts_with("1. Scope\na0 a1 a2 a3 a4 a5"));
+        assert!(t.text.contains("a5\n[…]\na34 "));
+        assert!(t.text.contains("a39\n2. Fees"));
+
+        assert_eq!(t.report.skipped_spans.len(), 1);
+        let span = &t.report.skipped_spans[0];
+        assert_eq!(span.section.as_deref(), Some("1. Scope"));
+        assert_eq!(span.tokens, 28);
+        assert!(doc[span.start..span.end].starts_with("a6 "));
+        assert!(doc[span.start..span.end].ends_with(" a33"));
+    }
+
+    #[test]
+    fn text_before_first_heading_has_no_section() {
+        let doc = format!("{}\nARTICLE ONE\n{}", words("p", 30), words("q", 2));
+        let t = truncate(&doc, 10).unwrap();
+        assert_eq!(t.report.skipped_spans[0].section, None);
+        assert!(t.text.contains("ARTICLE ONE"));
+    }
+}
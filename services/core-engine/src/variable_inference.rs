@@ -0,0 +1,957 @@
+//! Infers a template's required variables from an uploaded contract, so an
+//! existing agreement can seed a new one. Each document type has a profile
+//! naming, per variable, which kind of entity holds it and the cue phrases
+//! it follows: an employment agreement's salary is the amount after
+//! "salary", an SLA's uptime the percentage after "availability". Clients
+//! report the values they kept, and the profiles' accuracy is tallied per
+//! variable to show which cues need tuning.
+
+use std::{collections::BTreeMap, sync::OnceLock};
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use crate::{entities, notifier, AppState};
+
+/// How far after its cue a value may start.
+const CUE_REACH: usize = 200;
+/// Title keywords are looked for in this many leading bytes first.
+const TITLE_BYTES: usize = 300;
+const MAX_PHRASE_CHARS: usize = 80;
+const CUED: f64 = 0.9;
+const POSITIONAL: f64 = 0.6;
+const FIRST_FOUND: f64 = 0.4;
+
+// ── Profiles ──────────────────────────────────────────────────────────────────
+
+/// Where a variable's value comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// The party defined as one of the cues, else the party at `index`.
+    Party {
+        index: usize,
+    },
+    Amount,
+    Percentage,
+    Date,
+    /// A period such as "4 hours"; in `unit`s when set, as written otherwise.
+    Duration {
+        unit: Option<&'static str>,
+    },
+    Email,
+    /// The words after the cue, up to a comma or the end of the sentence.
+    Phrase,
+    /// Like `Phrase`, but running on past commas.
+    List,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VariableRule {
+    pub variable: &'static str,
+    pub source: Source,
+    /// Lowercase; the first one found in the document wins.
+    pub cues: &'static [&'static str],
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Profile {
+    /// Also the template the variables are for.
+    pub document_type: &'static str,
+    /// Lowercase phrases that identify the document type in its title.
+    pub title_keywords: &'static [&'static str],
+    pub rules: Vec<VariableRule>,
+}
+
+const fn rule(
+    variable: &'static str,
+    source: Source,
+    cues: &'static [&'static str],
+) -> VariableRule {
+    VariableRule {
+        variable,
+        source,
+        cues,
+    }
+}
+
+const GOVERNING_LAW: &[&str] = &["governed by the laws of", "laws of"];
+
+/// One profile per built-in template, covering its required variables.
+pub fn profiles() -> &'static [Profile] {
+    static PROFILES: OnceLock<Vec<Profile>> = OnceLock::new();
+    PROFILES.get_or_init(|| {
+        use Source::*;
+        vec![
+            Profile {
+                document_type: "employment",
+                title_keywords: &[
+                    "employment agreement",
+                    "employment contract",
+                    "offer letter",
+                ],
+                rules: vec![
+                    rule("employer", Party { index: 0 }, &["employer", "company"]),
+                    rule("employee", Party { index: 1 }, &["employee", "executive"]),
+                    rule(
+                        "start_date",
+                        Date,
+                        &[
+                            "start date",
+                            "commencement date",
+                            "commence on",
+                            "starting on",
+                        ],
+                    ),
+                    rule(
+                        "salary",
+                        Amount,
+                        &["base salary", "salary", "annual compensation", "base pay"],
+                    ),
+                    rule(
+                        "position",
+                        Phrase,
+                        &[
+                            "position of",
+                            "employed as",
+                            "role of",
+                            "job title",
+                            "serve as",
+                        ],
+                    ),
+                ],
+            },
+            Profile {
+                document_type: "sla",
+                title_keywords: &["service level agreement", "service level"],
+                rules: vec![
+                    rule(
+                        "service_provider",
+                        Party { index: 0 },
+                        &["service provider", "provider", "supplier", "vendor"],
+                    ),
+                    rule("customer", Party { index: 1 }, &["customer", "client"]),
+                    rule("uptime_percent", Percentage, &["uptime", "availability"]),
+                    rule(
+                        "response_time_hours",
+                        Duration {
+                            unit: Some("hours"),
+                        },
+                        &["respond within", "response time", "responded to within"],
+                    ),
+                ],
+            },
+            Profile {
+                document_type: "nda",
+                title_keywords: &[
+                    "non-disclosure",
+                    "nondisclosure",
+                    "confidentiality agreement",
+                ],
+                rules: vec![
+                    rule("party_a", Party { index: 0 }, &["disclosing party"]),
+                    rule("party_b", Party { index: 1 }, &["receiving party"]),
+                    rule(
+                        "effective_date",
+                        Date,
+                        &["effective date", "entered into on", "made on", "dated"],
+                    ),
+                    rule("jurisdiction", Phrase, GOVERNING_LAW),
+                ],
+            },
+            Profile {
+                document_type: "dpa",
+                title_keywords: &["data processing agreement", "data processing addendum"],
+                rules: vec![
+                    rule("controller", Party { index: 0 }, &["controller"]),
+                    rule("processor", Party { index: 1 }, &["processor"]),
+                    rule(
+                        "data_types",
+                        List,
+                        &["categories of personal data", "types of personal data"],
+                    ),
+                    rule(
+                        "retention_period",
+                        Duration { unit: None },
+                        &["retained for", "deleted within", "retention period"],
+                    ),
+                ],
+            },
+            Profile {
+                document_type: "tos",
+                title_keywords: &["terms of service", "terms of use"],
+                rules: vec![
+                    rule("company_name", Party { index: 0 }, &["company"]),
+                    rule("product_name", Phrase, &["your use of", "access to"]),
+                    rule("governing_law", Phrase, GOVERNING_LAW),
+                ],
+            },
+            Profile {
+                document_type: "privacy",
+                title_keywords: &["privacy policy", "privacy notice"],
+                rules: vec![
+                    rule("company_name", Party { index: 0 }, &["company"]),
+                    rule("contact_email", Email, &["contact", "email"]),
+                    rule(
+                        "data_collected",
+                        List,
+                        &["information we collect", "data we collect", "we collect"],
+                    ),
+                ],
+            },
+            Profile {
+                document_type: "license",
+                title_keywords: &["license agreement", "licence agreement"],
+                rules: vec![
+                    rule("licensor", Party { index: 0 }, &["licensor"]),
+                    rule("licensee", Party { index: 1 }, &["licensee"]),
+                    rule(
+                        "software_name",
+                        Phrase,
+                        &["software known as", "license to use", "licence to use"],
+                    ),
+                    rule(
+                        "license_fee",
+                        Amount,
+                        &["license fee", "licence fee", "royalt", "fee of"],
+                    ),
+                ],
+            },
+            Profile {
+                document_type: "renewal",
+                title_keywords: &["renewal", "extension amendment"],
+                rules: vec![
+                    rule(
+                        "agreement_reference",
+                        Phrase,
+                        &["amends the", "extends the"],
+                    ),
+                    rule("party_a", Party { index: 0 }, &[]),
+                    rule("party_b", Party { index: 1 }, &[]),
+                    rule("effective_date", Date, &["effective"]),
+                    rule(
+                        "current_end_date",
+                        Date,
+                        &["currently expires", "current term ends", "expires on"],
+                    ),
+                    rule(
+                        "new_end_date",
+                        Date,
+                        &["extended until", "extended to", "new end date"],
+                    ),
+                    rule(
+                        "pricing",
+                        Phrase,
+                        &["pricing:", "fees shall be", "pricing shall be"],
+                    ),
+                ],
+            },
+        ]
+    })
+}
+
+pub fn profile(document_type: &str) -> Option<&'static Profile> {
+    profiles().iter().find(|p| p.document_type == document_type)
+}
+
+/// The profile whose title keyword appears first in the document's opening,
+/// else the one whose keywords appear most often anywhere.
+pub fn detect(text: &str) -> Option<&'static Profile> {
+    let lower = text.to_ascii_lowercase();
+    let title = &lower[..floor_boundary(&lower, TITLE_BYTES)];
+    let in_title = profiles()
+        .iter()
+        .filter_map(|p| {
+            let at = p
+                .title_keywords
+                .iter()
+                .filter_map(|k| title.find(k))
+                .min()?;
+            Some((at, p))
+        })
+        .min_by_key(|(at, _)| *at);
+    if let Some((_, p)) = in_title {
+        return Some(p);
+    }
+    profiles()
+        .iter()
+        .map(|p| {
+            let hits: usize = p
+                .title_keywords
+                .iter()
+                .map(|k| lower.matches(k).count())
+                .sum();
+            (hits, p)
+        })
+        .filter(|(hits, _)| *hits > 0)
+        .max_by_key(|(hits, _)| *hits)
+        .map(|(_, p)| p)
+}
+
+fn floor_boundary(text: &str, at: usize) -> usize {
+    (0..=at.min(text.len()))
+        .rev()
+        .find(|i| text.is_char_boundary(*i))
+        .unwrap_or(0)
+}
+
+// ── Inference ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Basis {
+    /// Found after one of the rule's cues.
+    Cue,
+    /// The party in the rule's position, none being defined by a cue.
+    Position,
+    /// The first value of its kind, no cue being found.
+    FirstFound,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferredValue {
+    pub value: String,
+    pub confidence: f64,
+    pub basis: Basis,
+    /// Byte offsets of the value in the document; `end` is exclusive.
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A value, its span, and whether a cue placed it.
+type Found = (String, usize, usize, Basis);
+
+fn duration_pattern() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?i)\b(\d+(?:\.\d+)?)\s*(?:\(\d+\)\s*)?(business days?|hours?|days?|weeks?|months?|years?)\b")
+            .expect("valid duration pattern")
+    })
+}
+
+fn email_pattern() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").expect("valid email pattern")
+    })
+}
+
+/// Hours in one `unit`, for the units a duration converts between.
+fn hours_in(unit: &str) -> Option<f64> {
+    match unit.trim_end_matches('s') {
+        "hour" => Some(1.0),
+        "day" => Some(24.0),
+        "week" => Some(168.0),
+        _ => None,
+    }
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{value:.0}")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Cue positions in the document, ends of the cue, in document order.
+fn cue_ends(lower: &str, cues: &[&str]) -> Vec<usize> {
+    let mut ends: Vec<usize> = cues
+        .iter()
+        .flat_map(|cue| lower.match_indices(cue).map(|(at, m)| at + m.len()))
+        .collect();
+    ends.sort_unstable();
+    ends.dedup();
+    ends
+}
+
+/// The first span starting within reach after a cue, else the first span.
+fn nearest<T>(
+    spans: impl IntoIterator<Item = (usize, usize, T)>,
+    cues: &[usize],
+) -> Option<(usize, usize, T, Basis)> {
+    let spans: Vec<(usize, usize, T)> = spans.into_iter().collect();
+    let cued = cues.iter().find_map(|&cue| {
+        spans
+            .iter()
+            .position(|(start, _, _)| *start >= cue && *start - cue <= CUE_REACH)
+    });
+    let (index, basis) = match cued {
+        Some(i) => (i, Basis::Cue),
+        None if spans.is_empty() => return None,
+        None => (0, Basis::FirstFound),
+    };
+    let (start, end, value) = spans.into_iter().nth(index)?;
+    Some((start, end, value, basis))
+}
+
+/// The words after the first cue: leading filler and articles dropped,
+/// cut at the end of the sentence (and at a comma unless `list`).
+fn phrase(text: &str, cues: &[usize], list: bool) -> Option<Found> {
+    const FILLER: [&str; 9] = [
+        "include ",
+        "includes ",
+        "including ",
+        "are ",
+        "is ",
+        "shall be ",
+        "the ",
+        "an ",
+        "a ",
+    ];
+    cues.iter().find_map(|&cue| {
+        let mut start = cue;
+        loop {
+            let rest = &text[start..];
+            let trimmed = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ':');
+            start += rest.len() - trimmed.len();
+            let lower = trimmed.to_ascii_lowercase();
+            match FILLER.iter().find(|f| lower.starts_with(**f)) {
+                Some(f) => start += f.len(),
+                None => break,
+            }
+        }
+        let stops: &[char] = if list {
+            &['.', ';', '\n', '(']
+        } else {
+            &['.', ';', '\n', '(', ',']
+        };
+        let rest = &text[start..];
+        let mut end = start + rest.find(stops).unwrap_or(rest.len());
+        // A full stop inside a word or a number ("Inc.", "2.5") does not end it.
+        while text[end..].starts_with('.')
+            && text[end + 1..]
+                .chars()
+                .next()
+                .is_some_and(|c| !c.is_whitespace())
+        {
+            let after = &text[end + 1..];
+            end = end + 1 + after.find(stops).unwrap_or(after.len());
+        }
+        let value = text[start..end].trim_end();
+        let end = start + value.len();
+        (!value.is_empty() && value.chars().count() <= MAX_PHRASE_CHARS)
+            .then(|| (value.to_string(), start, end, Basis::Cue))
+    })
+}
+
+fn infer_one(
+    text: &str,
+    lower: &str,
+    found: &entities::Entities,
+    rule: &VariableRule,
+) -> Option<Found> {
+    let cues = cue_ends(lower, rule.cues);
+    let spans =
+        |(start, end, value, basis): (usize, usize, String, Basis)| (value, start, end, basis);
+    match rule.source {
+        Source::Party { index } => found
+            .parties
+            .iter()
+            .find(|p| {
+                p.defined_as.as_deref().is_some_and(|term| {
+                    let term = term.to_ascii_lowercase();
+                    rule.cues.iter().any(|cue| term == *cue)
+                })
+            })
+            .map(|p| (p.name.clone(), p.start, p.end, Basis::Cue))
+            .or_else(|| {
+                let p = found.parties.get(index)?;
+                Some((p.name.clone(), p.start, p.end, Basis::Position))
+            }),
+        Source::Amount => nearest(
+            found
+                .amounts
+                .iter()
+                .map(|a| (a.start, a.end, a.text.clone())),
+            &cues,
+        )
+        .map(spans),
+        Source::Percentage => nearest(
+            found
+                .percentages
+                .iter()
+                .map(|p| (p.start, p.end, format_number(p.value))),
+            &cues,
+        )
+        .map(spans),
+        Source::Date => nearest(
+            found
+                .dates
+                .iter()
+                .map(|d| (d.start, d.end, d.date.to_string())),
+            &cues,
+        )
+        .map(spans),
+        Source::Duration { unit } => {
+            let periods = duration_pattern().captures_iter(text).filter_map(|c| {
+                let whole = c.get(0)?;
+                let value = match unit {
+                    Some(unit) => {
+                        let amount: f64 = c[1].parse().ok()?;
+                        let written = c[2].to_ascii_lowercase();
+                        format_number(amount * hours_in(&written)? / hours_in(unit)?)
+                    }
+                    None => whole.as_str().to_string(),
+                };
+                Some((whole.start(), whole.end(), value))
+            });
+            nearest(periods, &cues).map(spans)
+        }
+        Source::Email => nearest(
+            email_pattern()
+                .find_iter(text)
+                .map(|m| (m.start(), m.end(), m.as_str().to_string())),
+            &cues,
+        )
+        .map(spans),
+        Source::Phrase => phrase(text, &cues, false),
+        Source::List => phrase(text, &cues, true),
+    }
+}
+
+fn confidence(basis: Basis) -> f64 {
+    match basis {
+        Basis::Cue => CUED,
+        Basis::Position => POSITIONAL,
+        Basis::FirstFound => FIRST_FOUND,
+    }
+}
+
+/// Every variable of the profile found in `text`, and the ones that were not.
+pub fn infer(
+    profile: &Profile,
+    text: &str,
+    language: &str,
+) -> (BTreeMap<String, InferredValue>, Vec<String>) {
+    let found = entities::extract(text, language);
+    let lower = text.to_ascii_lowercase();
+    let mut values = BTreeMap::new();
+    let mut missing = Vec::new();
+    for rule in &profile.rules {
+        match infer_one(text, &lower, &found, rule) {
+            Some((value, start, end, basis)) => {
+                values.insert(
+                    rule.variable.to_string(),
+                    InferredValue {
+                        value,
+                        confidence: confidence(basis),
+                        basis,
+                        start,
+                        end,
+                    },
+                );
+            }
+            None => missing.push(rule.variable.to_string()),
+        }
+    }
+    (values, missing)
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Extraction {
+    pub extraction_id: String,
+    pub document_type: String,
+    /// False when the caller named the document type.
+    pub detected: bool,
+    pub variables: BTreeMap<String, InferredValue>,
+    pub missing: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip)]
+    pub tenant: String,
+    #[serde(skip)]
+    reviewed: bool,
+}
+
+/// Feedback on one variable of one profile, across every extraction.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VariableStats {
+    pub inferred: u64,
+    pub not_found: u64,
+    /// Inferred values the client kept.
+    pub confirmed: u64,
+    /// Inferred values the client replaced.
+    pub corrected: u64,
+    /// Values the client supplied for a variable that was not found.
+    pub supplied: u64,
+}
+
+impl VariableStats {
+    /// Share of reviewed inferred values that were kept.
+    pub fn accuracy(&self) -> Option<f64> {
+        let reviewed = self.confirmed + self.corrected;
+        (reviewed > 0).then(|| self.confirmed as f64 / reviewed as f64)
+    }
+
+    /// Share of extractions in which the variable was found.
+    pub fn coverage(&self) -> Option<f64> {
+        let total = self.inferred + self.not_found;
+        (total > 0).then(|| self.inferred as f64 / total as f64)
+    }
+}
+
+#[derive(Default)]
+pub struct InferenceStore {
+    extractions: DashMap<String, Extraction>,
+    /// By document type, then variable.
+    stats: DashMap<String, BTreeMap<String, VariableStats>>,
+}
+
+impl InferenceStore {
+    fn insert(&self, extraction: Extraction) {
+        let mut stats = self
+            .stats
+            .entry(extraction.document_type.clone())
+            .or_default();
+        for variable in extraction.variables.keys() {
+            stats.entry(variable.clone()).or_default().inferred += 1;
+        }
+        for variable in &extraction.missing {
+            stats.entry(variable.clone()).or_default().not_found += 1;
+        }
+        drop(stats);
+        self.extractions
+            .insert(extraction.extraction_id.clone(), extraction);
+    }
+
+    /// Compares the values the client kept with the inferred ones.
+    /// Variables left out of `values` are not counted.
+    fn review(
+        &self,
+        tenant: &str,
+        id: &str,
+        values: &BTreeMap<String, String>,
+    ) -> Result<BTreeMap<String, Verdict>, StatusCode> {
+        let mut extraction = self
+            .extractions
+            .get_mut(id)
+            .filter(|e| e.tenant == tenant)
+            .ok_or(StatusCode::NOT_FOUND)?;
+        if extraction.reviewed {
+            return Err(StatusCode::CONFLICT);
+        }
+        let mut verdicts = BTreeMap::new();
+        for (variable, kept) in values {
+            let verdict = match extraction.variables.get(variable) {
+                Some(inferred) if same(&inferred.value, kept) => Verdict::Confirmed,
+                Some(_) => Verdict::Corrected,
+                None if extraction.missing.contains(variable) => Verdict::Supplied,
+                None => return Err(StatusCode::UNPROCESSABLE_ENTITY),
+            };
+            verdicts.insert(variable.clone(), verdict);
+        }
+        extraction.reviewed = true;
+        let mut stats = self
+            .stats
+            .entry(extraction.document_type.clone())
+            .or_default();
+        for (variable, verdict) in &verdicts {
+            let s = stats.entry(variable.clone()).or_default();
+            match verdict {
+                Verdict::Confirmed => s.confirmed += 1,
+                Verdict::Corrected => s.corrected += 1,
+                Verdict::Supplied => s.supplied += 1,
+            }
+        }
+        Ok(verdicts)
+    }
+
+    pub fn stats(&self, document_type: &str) -> BTreeMap<String, VariableStats> {
+        self.stats
+            .get(document_type)
+            .map(|s| s.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn for_each_mut(&self, mut f: impl FnMut(&mut Extraction)) {
+        for mut e in self.extractions.iter_mut() {
+            f(&mut e);
+        }
+    }
+
+    /// Drops the tenant's extractions; the tallies, which name no one, stay.
+    pub fn remove_tenant(&self, tenant: &str) -> usize {
+        let before = self.extractions.len();
+        self.extractions.retain(|_, e| e.tenant != tenant);
+        before - self.extractions.len()
+    }
+}
+
+/// Values match ignoring case and surrounding whitespace.
+fn same(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Confirmed,
+    Corrected,
+    Supplied,
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct ExtractRequest {
+    pub document: String,
+    #[serde(default = "default_language")]
+    pub language: String,
+    /// A template ID; detected from the document's title when absent.
+    #[serde(default)]
+    pub document_type: Option<String>,
+}
+
+fn default_language() -> String {
+    "en".into()
+}
+
+fn unprocessable(message: String) -> Response {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(json!({
+            "error": message,
+            "document_types": profiles().iter().map(|p| p.document_type).collect::<Vec<_>>(),
+        })),
+    )
+        .into_response()
+}
+
+pub async fn extract(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<ExtractRequest>,
+) -> Result<Json<Extraction>, Response> {
+    if req.document.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+    let (profile, detected) = match req.document_type.as_deref() {
+        Some(kind) => (
+            profile(kind).ok_or_else(|| unprocessable(format!("unknown document type {kind}")))?,
+            false,
+        ),
+        None => (
+            detect(&req.document).ok_or_else(|| {
+                unprocessable("document type not recognized; pass document_type".to_string())
+            })?,
+            true,
+        ),
+    };
+    let (variables, missing) = infer(profile, &req.document, &req.language);
+    let extraction = Extraction {
+        extraction_id: format!("vx-{}", state.ids.next_id()),
+        document_type: profile.document_type.to_string(),
+        detected,
+        variables,
+        missing,
+        created_at: state.clock.now(),
+        tenant: notifier::tenant(&headers),
+        reviewed: false,
+    };
+    info!(
+        extraction_id = %extraction.extraction_id,
+        document_type = profile.document_type,
+        found = extraction.variables.len(),
+        missing = extraction.missing.len(),
+        "variables inferred"
+    );
+    state.variable_inference.insert(extraction.clone());
+    Ok(Json(extraction))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeedbackRequest {
+    /// The value the client used for each variable it reviewed.
+    pub values: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeedbackResponse {
+    pub extraction_id: String,
+    pub verdicts: BTreeMap<String, Verdict>,
+}
+
+/// Once per extraction; a second report is `409`. A variable the profile
+/// does not infer is `422`.
+pub async fn feedback(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<FeedbackRequest>,
+) -> Result<Json<FeedbackResponse>, StatusCode> {
+    let verdicts =
+        state
+            .variable_inference
+            .review(&notifier::tenant(&headers), &id, &req.values)?;
+    Ok(Json(FeedbackResponse {
+        extraction_id: id,
+        verdicts,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuleReport {
+    #[serde(flatten)]
+    pub rule: VariableRule,
+    #[serde(flatten)]
+    pub stats: VariableStats,
+    pub accuracy: Option<f64>,
+    pub coverage: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileReport {
+    pub document_type: &'static str,
+    pub title_keywords: &'static [&'static str],
+    /// Over every reviewed variable of the profile.
+    pub accuracy: Option<f64>,
+    pub variables: Vec<RuleReport>,
+}
+
+pub async fn list_profiles(State(state): State<AppState>) -> Json<Vec<ProfileReport>> {
+    Json(
+        profiles()
+            .iter()
+            .map(|p| {
+                let stats = state.variable_inference.stats(p.document_type);
+                let mut total = VariableStats::default();
+                let variables = p
+                    .rules
+                    .iter()
+                    .map(|rule| {
+                        let stats = stats.get(rule.variable).cloned().unwrap_or_default();
+                        total.confirmed += stats.confirmed;
+                        total.corrected += stats.corrected;
+                        RuleReport {
+                            rule: rule.clone(),
+                            accuracy: stats.accuracy(),
+                            coverage: stats.coverage(),
+                            stats,
+                        }
+                    })
+                    .collect();
+                ProfileReport {
+                    document_type: p.document_type,
+                    title_keywords: p.title_keywords,
+                    accuracy: total.accuracy(),
+                    variables,
+                }
+            })
+            .collect(),
+    )
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_required_variables;
+
+    const EMPLOYMENT: &str = "EMPLOYMENT AGREEMENT\n\n\
+        This Employment Agreement is made between Acme Corp (the \"Company\") and \
+        Jane Doe (the \"Employee\").\n\
+        1. Position. The Employee is employed in the position of Senior Data Engineer, \
+        reporting to the CTO.\n\
+        2. Start Date. Employment begins on the start date of March 1, 2026.\n\
+        3. Compensation. The Employee's base salary is USD 150,000 per year, with a \
+        signing bonus of USD 10,000.\n";
+
+    const SLA: &str = "SERVICE LEVEL AGREEMENT\n\n\
+        This Agreement is between Cloudy Ltd (the \"Provider\") and Beta Inc. (the \"Customer\").\n\
+        1. Availability. The Provider guarantees 99.9% availability each month.\n\
+        2. Support. The Provider shall respond within 2 business days to Severity 2 \
+        tickets and within 4 hours to Severity 1 tickets.\n";
+
+    #[test]
+    fn every_profile_covers_its_templates_required_variables() {
+        for p in profiles() {
+            let mut covered: Vec<&str> = p.rules.iter().map(|r| r.variable).collect();
+            covered.sort_unstable();
+            let mut required = get_required_variables(p.document_type);
+            required.sort();
+            assert_eq!(covered, required, "{}", p.document_type);
+        }
+    }
+
+    #[test]
+    fn titles_pick_the_profile() {
+        assert_eq!(detect(EMPLOYMENT).unwrap().document_type, "employment");
+        assert_eq!(detect(SLA).unwrap().document_type, "sla");
+        assert!(detect("Minutes of the board meeting.").is_none());
+    }
+
+    #[test]
+    fn employment_agreements_yield_salary_position_and_start_date() {
+        let (values, missing) = infer(profile("employment").unwrap(), EMPLOYMENT, "en");
+        assert!(missing.is_empty(), "{missing:?}");
+        let value = |v: &str| values[v].value.as_str();
+        assert_eq!(value("employer"), "Acme Corp");
+        assert_eq!(value("employee"), "Jane Doe");
+        assert_eq!(value("position"), "Senior Data Engineer");
+        assert_eq!(value("start_date"), "2026-03-01");
+        assert_eq!(value("salary"), "USD 150,000");
+        assert_eq!(values["salary"].basis, Basis::Cue);
+        let salary = &values["salary"];
+        assert_eq!(&EMPLOYMENT[salary.start..salary.end], "USD 150,000");
+    }
+
+    #[test]
+    fn slas_yield_uptime_and_response_hours() {
+        let (values, missing) = infer(profile("sla").unwrap(), SLA, "en");
+        assert!(missing.is_empty(), "{missing:?}");
+        assert_eq!(values["service_provider"].value, "Cloudy Ltd");
+        assert_eq!(values["customer"].value, "Beta Inc.");
+        assert_eq!(values["uptime_percent"].value, "99.9");
+        // Business days have no fixed length in hours; the next period does.
+        assert_eq!(values["response_time_hours"].value, "4");
+    }
+
+    #[test]
+    fn reviews_tally_per_variable_once() {
+        let store = InferenceStore::default();
+        let (variables, missing) = infer(profile("sla").unwrap(), SLA, "en");
+        store.insert(Extraction {
+            extraction_id: "vx-1".to_string(),
+            document_type: "sla".to_string(),
+            detected: true,
+            variables,
+            missing,
+            created_at: Utc::now(),
+            tenant: "acme".to_string(),
+            reviewed: false,
+        });
+        let kept = BTreeMap::from([
+            ("uptime_percent".to_string(), "99.9".to_string()),
+            ("response_time_hours".to_string(), "1".to_string()),
+        ]);
+        assert_eq!(
+            store.review("globex", "vx-1", &kept),
+            Err(StatusCode::NOT_FOUND)
+        );
+        let verdicts = store.review("acme", "vx-1", &kept).unwrap();
+        assert_eq!(verdicts["uptime_percent"], Verdict::Confirmed);
+        assert_eq!(verdicts["response_time_hours"], Verdict::Corrected);
+        assert_eq!(
+            store.review("acme", "vx-1", &kept),
+            Err(StatusCode::CONFLICT)
+        );
+
+        let stats = store.stats("sla");
+        assert_eq!(stats["uptime_percent"].accuracy(), Some(1.0));
+        assert_eq!(stats["response_time_hours"].accuracy(), Some(0.0));
+        assert_eq!(stats["customer"].accuracy(), None);
+        assert_eq!(stats["customer"].coverage(), Some(1.0));
+    }
+}
@@ -0,0 +1,927 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ops::Range,
+    sync::{Arc, OnceLock},
+    time::Instant,
+};
+
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder};
+use axum::{extract::State, http::StatusCode, response::Json};
+use handlebars::Handlebars;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{
+    clause_library::{ClauseRef, CLAUSE_PREFIX},
+    get_required_variables, get_template_body,
+    lexicon::Lexicon,
+    taxonomy::{LOCALIZED, TAXONOMY},
+    AppState, BUILTIN_TEMPLATES,
+};
+
+// ── Components ────────────────────────────────────────────────────────────────
+
+/// The taxonomy's keyword groups, which the clause and issue detectors look
+/// for, matched in one ASCII case-insensitive pass so offsets stay valid in
+/// the original text. Every language's keywords are in the automaton; hits
+/// on a localized keyword count only in a matcher set to its language.
+#[derive(Debug, Clone)]
+pub struct KeywordMatcher {
+    automaton: AhoCorasick,
+    groups: Arc<[&'static str]>,
+    /// Language of each pattern; `None` for the English ones.
+    languages: Arc<[Option<&'static str>]>,
+    /// The language whose localized keywords count, besides English.
+    language: Option<&'static str>,
+    /// Tenant dictionary the patterns were expanded with; documents are then
+    /// matched through its stopword-free view.
+    lexicon: Option<Arc<Lexicon>>,
+}
+
+/// Every built-in keyword with its group and, unless English, language.
+fn keywords() -> impl Iterator<Item = (&'static str, &'static str, Option<&'static str>)> {
+    let english = TAXONOMY
+        .iter()
+        .flat_map(|k| k.keywords.iter().map(|w| (*w, k.group, None)));
+    let localized = LOCALIZED.iter().flat_map(|(language, groups)| {
+        groups.iter().flat_map(move |(group, words)| {
+            words.iter().map(move |w| (*w, *group, Some(*language)))
+        })
+    });
+    english.chain(localized)
+}
+
+impl KeywordMatcher {
+    fn from_patterns(
+        patterns: Vec<(String, &'static str, Option<&'static str>)>,
+        lexicon: Option<Lexicon>,
+    ) -> Result<Self, String> {
+        let mut texts = Vec::with_capacity(patterns.len());
+        let mut groups = Vec::with_capacity(patterns.len());
+        let mut languages = Vec::with_capacity(patterns.len());
+        for (text, group, language) in patterns {
+            texts.push(text);
+            groups.push(group);
+            languages.push(language);
+        }
+        let automaton = AhoCorasickBuilder::new()
+            .ascii_case_insensitive(true)
+            .build(&texts)
+            .map_err(|e| format!("keyword automaton: {e}"))?;
+        Ok(Self {
+            automaton,
+            groups: groups.into(),
+            languages: languages.into(),
+            language: None,
+            lexicon: lexicon.map(Arc::new),
+        })
+    }
+
+    fn build() -> Result<Self, String> {
+        let patterns = keywords()
+            .map(|(w, group, language)| (w.to_string(), group, language))
+            .collect();
+        Self::from_patterns(patterns, None)
+    }
+
+    /// The built-in groups widened with the lexicon's synonyms.
+    pub fn with_lexicon(lexicon: Lexicon) -> Result<Self, String> {
+        let patterns = keywords()
+            .flat_map(|(w, group, language)| {
+                lexicon
+                    .expand(w)
+                    .into_iter()
+                    .map(move |p| (p, group, language))
+            })
+            .collect();
+        Self::from_patterns(patterns, Some(lexicon))
+    }
+
+    /// This matcher with `language`'s localized keywords counting too; only
+    /// English ones for languages without any.
+    pub fn in_language(&self, language: &str) -> Self {
+        Self {
+            language: LOCALIZED.iter().map(|(l, _)| *l).find(|l| *l == language),
+            ..self.clone()
+        }
+    }
+
+    pub fn lexicon(&self) -> Option<&Lexicon> {
+        self.lexicon.as_deref()
+    }
+
+    fn counts(&self, pattern: usize) -> bool {
+        self.languages[pattern].is_none_or(|l| self.language == Some(l))
+    }
+
+    /// Byte span of the first keyword hit for each group found in `document`.
+    pub fn hits(&self, document: &str) -> BTreeMap<&'static str, (usize, usize)> {
+        let mut hits = BTreeMap::new();
+        match &self.lexicon {
+            None => {
+                for m in self.automaton.find_overlapping_iter(document) {
+                    if self.counts(m.pattern().as_usize()) {
+                        hits.entry(self.groups[m.pattern().as_usize()])
+                            .or_insert((m.start(), m.end()));
+                    }
+                }
+            }
+            Some(lexicon) => {
+                let view = lexicon.view(document);
+                for m in self.automaton.find_overlapping_iter(&view.text) {
+                    if self.counts(m.pattern().as_usize()) {
+                        hits.entry(self.groups[m.pattern().as_usize()])
+                            .or_insert_with(|| view.span(m.start(), m.end()));
+                    }
+                }
+            }
+        }
+        hits
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Text(String),
+    Placeholder(String),
+    /// A block, helper, partial or comment tag, e.g. `{{#each items}}`.
+    Tag(String),
+}
+
+/// A compiled template and the byte range of each paragraph in it.
+pub struct Rendered {
+    pub text: String,
+    pub paragraphs: Vec<Range<usize>>,
+}
+
+/// What put text into a rendered document, besides the template itself.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Source {
+    Macro(String),
+    /// Index of one of the template's clause library references.
+    Clause(usize),
+    Variable(String),
+}
+
+impl Source {
+    fn label(&self) -> String {
+        match self {
+            Source::Macro(name) => format!("m:{name}"),
+            Source::Clause(i) => format!("c:{i}"),
+            Source::Variable(name) => format!("v:{name}"),
+        }
+    }
+
+    fn parse(label: &str) -> Option<Self> {
+        match label.split_once(':')? {
+            ("m", name) => Some(Source::Macro(name.to_string())),
+            ("c", i) => i.parse().ok().map(Source::Clause),
+            ("v", name) => Some(Source::Variable(name.to_string())),
+            _ => None,
+        }
+    }
+
+    /// `text` between this source's trace marks.
+    fn mark(&self, text: &str) -> String {
+        format!("{TRACE_OPEN}{}{LABEL_END}{text}{TRACE_CLOSE}", self.label())
+    }
+}
+
+/// A compiled template and the sources found in each of its paragraphs.
+pub struct Traced {
+    pub rendered: Rendered,
+    pub sources: Vec<BTreeSet<Source>>,
+}
+
+/// Which sources a render marks in its output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Trace {
+    Off,
+    /// Macros and library clauses only.
+    Fragments,
+    All,
+}
+
+/// Name the body is registered under in the template's own registry.
+const BODY: &str = "body";
+/// Partial-name prefix of macros from the boilerplate library.
+pub const MACRO_PREFIX: &str = "macro/";
+/// Root value `{{clause:…}}` references are rewritten to, followed by the
+/// reference's index. A value rather than a partial, so a reference alone on
+/// its line keeps its line break.
+const CLAUSE_VALUE: &str = "__clause_";
+/// Stands in for a blank line inside a value while rendering, so it is not
+/// taken for a paragraph break; same length, so offsets are unaffected.
+const VALUE_BREAK: &str = "\n\u{1}";
+
+/// Private-use characters that mark a source's text in a traced render:
+/// the open mark, the source's label and the label's end, then the text
+/// and the close mark.
+const TRACE_OPEN: char = '\u{E000}';
+const TRACE_CLOSE: char = '\u{E001}';
+const LABEL_END: char = '\u{E002}';
+const TRACE_MARKS: [char; 3] = [TRACE_OPEN, TRACE_CLOSE, LABEL_END];
+
+/// A Handlebars template body, also split into literal text, `{{name}}`
+/// placeholders and tags for callers that inspect its structure. Output is
+/// plain text: values are not HTML-escaped, and `\{{` writes literal braces.
+#[derive(Debug, Clone)]
+pub struct ParsedTemplate {
+    pub segments: Vec<Segment>,
+    /// Clause library references, each once, in order of first use.
+    clauses: Vec<ClauseRef>,
+    registry: Handlebars<'static>,
+}
+
+/// What a template's macros and clause library references render as.
+#[derive(Debug, Default)]
+pub struct Fragments {
+    /// Latest macro bodies by name.
+    pub macros: BTreeMap<String, String>,
+    /// Approved text for each of the template's clause references, in order.
+    pub clauses: Vec<String>,
+}
+
+/// Blocks whose body resolves names against something other than the top
+/// level, so their placeholders are not the template's variables.
+fn opens_scope(tag: &str) -> bool {
+    tag.starts_with("#each") || tag.starts_with("#with")
+}
+
+fn is_path(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_alphanumeric() || matches!(c, '_' | '.' | '@'))
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | '@'))
+}
+
+/// A path that names a value in the context rather than `this` or `@index`.
+fn is_variable(path: &str) -> bool {
+    !path.starts_with('@') && path != "this" && !path.starts_with("this.")
+}
+
+fn shield(value: &Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(s.replace("\n\n", VALUE_BREAK)),
+        Value::Array(items) => Value::Array(items.iter().map(shield).collect()),
+        Value::Object(map) => {
+            Value::Object(map.iter().map(|(k, v)| (k.clone(), shield(v))).collect())
+        }
+        other => other.clone(),
+    }
+}
+
+/// Byte ranges of the paragraphs of `raw`, which break at blank lines
+/// the template produces.
+fn paragraphs(raw: &str) -> Vec<Range<usize>> {
+    let mut paragraphs = Vec::new();
+    let mut start = 0;
+    let breaks = raw
+        .match_indices("\n\n")
+        .map(|(i, _)| (i, i + 2))
+        .chain([(raw.len(), raw.len())]);
+    for (end, next) in breaks {
+        if end > start {
+            paragraphs.push(start..end);
+        }
+        start = start.max(next);
+    }
+    paragraphs
+}
+
+/// `raw` without its trace marks, and the span of the unmarked text each
+/// marked source covers.
+fn untrace(raw: &str) -> (String, Vec<(Range<usize>, Source)>) {
+    let mut text = String::with_capacity(raw.len());
+    let mut open: Vec<(usize, Option<Source>)> = Vec::new();
+    let mut spans = Vec::new();
+    let mut rest = raw;
+    while let Some(at) = rest.find([TRACE_OPEN, TRACE_CLOSE]) {
+        text.push_str(&rest[..at]);
+        let mark = rest[at..].chars().next().unwrap_or(TRACE_CLOSE);
+        rest = &rest[at + mark.len_utf8()..];
+        if mark == TRACE_OPEN {
+            let (label, after) = rest.split_once(LABEL_END).unwrap_or((rest, ""));
+            open.push((text.len(), Source::parse(label)));
+            rest = after;
+        } else if let Some((start, Some(source))) = open.pop() {
+            spans.push((start..text.len(), source));
+        }
+    }
+    text.push_str(rest);
+    (text, spans)
+}
+
+impl ParsedTemplate {
+    pub fn parse(body: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut rest = body;
+        let mut text = String::new();
+        // The body as Handlebars sees it, with clause references as values.
+        let mut source = String::with_capacity(body.len());
+        let mut copied = 0;
+        let mut clauses: Vec<ClauseRef> = Vec::new();
+        while let Some(start) = rest.find("{{") {
+            // `\{{…}}` is literal text.
+            if rest[..start].ends_with('\\') {
+                let end = rest[start..]
+                    .find("}}")
+                    .map_or(rest.len(), |e| start + e + 2);
+                text.push_str(&rest[..start - 1]);
+                text.push_str(&rest[start..end]);
+                rest = &rest[end..];
+                continue;
+            }
+            text.push_str(&rest[..start]);
+            if !text.is_empty() {
+                segments.push(Segment::Text(std::mem::take(&mut text)));
+            }
+            let (after, close) = match rest[start..].strip_prefix("{{{") {
+                Some(after) => (after, "}}}"),
+                None => (&rest[start + 2..], "}}"),
+            };
+            let end = after
+                .find(close)
+                .ok_or_else(|| "unterminated placeholder".to_string())?;
+            let inner = after[..end]
+                .trim()
+                .trim_start_matches('~')
+                .trim_end_matches('~')
+                .trim();
+            if inner.is_empty() || inner.contains("{{") {
+                return Err(format!("malformed placeholder {{{{{}}}}}", &after[..end]));
+            }
+            let name = inner.trim_start_matches('&').trim();
+            if name.starts_with(CLAUSE_PREFIX) {
+                let clause = ClauseRef::parse(name)?;
+                let index = match clauses.iter().position(|c| *c == clause) {
+                    Some(i) => i,
+                    None => {
+                        clauses.push(clause);
+                        clauses.len() - 1
+                    }
+                };
+                let tag_start = body.len() - rest.len() + start;
+                source.push_str(&body[copied..tag_start]);
+                source.push_str(&format!("{{{{@root.{CLAUSE_VALUE}{index}}}}}"));
+                copied = body.len() - after.len() + end + close.len();
+            }
+            segments.push(if is_path(name) && name != "else" {
+                Segment::Placeholder(name.to_string())
+            } else {
+                Segment::Tag(inner.to_string())
+            });
+            rest = &after[end + close.len()..];
+        }
+        if rest.contains("}}") {
+            return Err("unmatched closing braces".to_string());
+        }
+        text.push_str(rest);
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+
+        source.push_str(&body[copied..]);
+
+        let mut registry = Handlebars::new();
+        registry.register_escape_fn(handlebars::no_escape);
+        registry
+            .register_template_string(BODY, source)
+            .map_err(|e| e.reason().to_string())?;
+        Ok(Self {
+            segments,
+            clauses,
+            registry,
+        })
+    }
+
+    /// `render_with` and no structured data.
+    /// Names tested by `{{#if}}` and `{{#unless}}`, i.e. what decides the
+    /// optional sections.
+    pub fn conditions(&self) -> BTreeSet<&str> {
+        self.segments
+            .iter()
+            .filter_map(|s| match s {
+                Segment::Tag(t) => t
+                    .strip_prefix("#if ")
+                    .or_else(|| t.strip_prefix("#unless "))
+                    .map(str::trim),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn render(&self, variables: &HashMap<String, String>) -> Result<Rendered, String> {
+        self.render_with(variables, &Map::new())
+    }
+
+    /// Renders with `variables` over `data`, the structured values that
+    /// `{{#if}}` and `{{#each}}` use. Missing top-level placeholders stay
+    /// visible as `{{name}}`. Paragraphs break at blank lines the template
+    /// produces, never inside a substituted value.
+    pub fn render_with(
+        &self,
+        variables: &HashMap<String, String>,
+        data: &Map<String, Value>,
+    ) -> Result<Rendered, String> {
+        self.render_with_fragments(variables, data, &Fragments::default())
+    }
+
+    /// `render_with`, expanding `{{> macro/name key=value}}` from the
+    /// fragments' macros and `{{clause:…}}` from their clauses. Macros see
+    /// the template's values plus their own parameters, and may use other
+    /// macros; clauses see the template's values.
+    pub fn render_with_fragments(
+        &self,
+        variables: &HashMap<String, String>,
+        data: &Map<String, Value>,
+        fragments: &Fragments,
+    ) -> Result<Rendered, String> {
+        let raw = self.render_raw(variables, data, fragments, Trace::Off)?;
+        Ok(Rendered {
+            paragraphs: paragraphs(&raw),
+            text: raw.replace(VALUE_BREAK, "\n\n"),
+        })
+    }
+
+    /// `render_with_fragments`, also reporting which macros, library
+    /// clauses and variables each paragraph's text came from. Where a
+    /// helper compares variable values, which tracing them through the
+    /// render would upset, or where the text already holds a trace mark
+    /// that would be misread, a paragraph counts the variables whose values
+    /// it contains instead.
+    pub fn render_traced(
+        &self,
+        variables: &HashMap<String, String>,
+        data: &Map<String, Value>,
+        fragments: &Fragments,
+    ) -> Result<Traced, String> {
+        let raw = self.render_raw(variables, data, fragments, Trace::Off)?;
+        let mut spans = Vec::new();
+        let mut variables_traced = false;
+        // Marks already in the text could not be told from the render's own.
+        let traces: &[Trace] = if raw.contains(TRACE_MARKS) {
+            &[]
+        } else {
+            &[Trace::All, Trace::Fragments]
+        };
+        for &trace in traces {
+            let (text, found) = untrace(&self.render_raw(variables, data, fragments, trace)?);
+            if text == raw {
+                spans = found;
+                variables_traced = trace == Trace::All;
+                break;
+            }
+        }
+        let paragraphs = paragraphs(&raw);
+        let sources = paragraphs
+            .iter()
+            .map(|p| {
+                let mut found: BTreeSet<Source> = spans
+                    .iter()
+                    .filter(|(span, _)| span.start < p.end && p.start < span.end)
+                    .map(|(_, source)| source.clone())
+                    .collect();
+                if !variables_traced {
+                    found.extend(
+                        variables
+                            .iter()
+                            .filter(|(_, v)| {
+                                !v.is_empty()
+                                    && raw[p.clone()].contains(&v.replace("\n\n", VALUE_BREAK))
+                            })
+                            .map(|(k, _)| Source::Variable(k.clone())),
+                    );
+                }
+                found
+            })
+            .collect();
+        Ok(Traced {
+            rendered: Rendered {
+                text: raw.replace(VALUE_BREAK, "\n\n"),
+                paragraphs,
+            },
+            sources,
+        })
+    }
+
+    /// The rendered text, blank lines inside values still shielded, with
+    /// the sources `trace` asks for marked around their text.
+    fn render_raw(
+        &self,
+        variables: &HashMap<String, String>,
+        data: &Map<String, Value>,
+        fragments: &Fragments,
+        trace: Trace,
+    ) -> Result<String, String> {
+        if let Some(unresolved) = self.clauses.get(fragments.clauses.len()) {
+            return Err(format!("clause {{{{{unresolved}}}}} is not resolved"));
+        }
+        let mut context: Map<String, Value> =
+            data.iter().map(|(k, v)| (k.clone(), shield(v))).collect();
+        for (k, v) in variables {
+            let value = match shield(&Value::String(v.clone())) {
+                // An empty value stays empty, so conditions on it stay false.
+                Value::String(s) if trace == Trace::All && !s.is_empty() => {
+                    Value::String(Source::Variable(k.clone()).mark(&s))
+                }
+                value => value,
+            };
+            context.insert(k.clone(), value);
+        }
+        // A condition on a missing name must stay false.
+        let conditions = self.conditions();
+        for name in self.placeholders() {
+            if !name.contains(['.', '/']) && !conditions.contains(name) {
+                context
+                    .entry(name)
+                    .or_insert_with(|| Value::String(format!("{{{{{name}}}}}")));
+            }
+        }
+        // Rendered unshielded: a clause's own paragraphs are paragraphs.
+        for (i, (clause, text)) in self.clauses.iter().zip(&fragments.clauses).enumerate() {
+            let rendered = ParsedTemplate::parse(text)
+                .and_then(|t| t.render_raw(variables, data, &Fragments::default(), trace))
+                .map_err(|e| format!("{clause}: {e}"))?
+                .replace(VALUE_BREAK, "\n\n");
+            let rendered = match trace {
+                Trace::Off => rendered,
+                _ => Source::Clause(i).mark(&rendered),
+            };
+            context.insert(format!("{CLAUSE_VALUE}{i}"), Value::String(rendered));
+        }
+        let with_macros;
+        let registry = if self.macros().is_empty() {
+            &self.registry
+        } else {
+            let mut registry = self.registry.clone();
+            for (name, body) in &fragments.macros {
+                let body = match trace {
+                    Trace::Off => body.clone(),
+                    _ => Source::Macro(name.clone()).mark(body),
+                };
+                registry
+                    .register_partial(&format!("{MACRO_PREFIX}{name}"), body)
+                    .map_err(|e| format!("macro {name}: {}", e.reason()))?;
+            }
+            with_macros = registry;
+            &with_macros
+        };
+        registry
+            .render(BODY, &context)
+            .map_err(|e| e.reason().to_string())
+    }
+
+    /// Clause library references, each once, in order of first use.
+    pub fn clauses(&self) -> &[ClauseRef] {
+        &self.clauses
+    }
+
+    /// Macros used through `{{> macro/name}}`, by name.
+    pub fn macros(&self) -> BTreeSet<&str> {
+        self.segments
+            .iter()
+            .filter_map(|s| match s {
+                Segment::Tag(t) => t
+                    .strip_prefix('>')?
+                    .split_whitespace()
+                    .next()?
+                    .strip_prefix(MACRO_PREFIX),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Names the template reads from the top level, outside `{{#each}}` and
+    /// `{{#with}}` blocks, including values passed to macros as
+    /// `key=name`; `this` and `@` variables are not included.
+    pub fn placeholders(&self) -> BTreeSet<&str> {
+        let mut depth = 0usize;
+        let mut names = BTreeSet::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Tag(t) if opens_scope(t) => depth += 1,
+                Segment::Tag(t) if t.starts_with("/each") || t.starts_with("/with") => {
+                    depth = depth.saturating_sub(1);
+                }
+                Segment::Placeholder(p) if depth == 0 && is_variable(p) => {
+                    names.insert(p.as_str());
+                }
+                Segment::Tag(t) if depth == 0 && t.starts_with('>') => {
+                    names.extend(
+                        t.split_whitespace()
+                            .filter_map(|arg| arg.split_once('=').map(|(_, v)| v))
+                            .filter(|v| is_path(v) && is_variable(v))
+                            .filter(|v| !v.starts_with(|c: char| c.is_ascii_digit()))
+                            .filter(|v| !matches!(*v, "true" | "false" | "null")),
+                    );
+                }
+                _ => {}
+            }
+        }
+        names
+    }
+}
+
+fn parse_builtin_templates() -> Result<HashMap<String, ParsedTemplate>, String> {
+    let mut parsed = HashMap::new();
+    for id in BUILTIN_TEMPLATES {
+        let body = get_template_body(id).ok_or_else(|| format!("template {id}: no body"))?;
+        let template = ParsedTemplate::parse(&body).map_err(|e| format!("template {id}: {e}"))?;
+        let required = get_required_variables(id);
+        let required: BTreeSet<&str> = required.iter().map(String::as_str).collect();
+        if template.placeholders() != required {
+            return Err(format!(
+                "template {id}: placeholders {:?} do not match required variables {:?}",
+                template.placeholders(),
+                required
+            ));
+        }
+        parsed.insert(id.to_string(), template);
+    }
+    Ok(parsed)
+}
+
+// ── Precompiled ───────────────────────────────────────────────────────────────
+
+/// Matchers and parsed templates, compiled once. `warm` builds them all at
+/// startup; in states that skip it (tests) they are built on first use.
+/// Regulatory rules and export policies are editable at runtime and are
+/// validated when written instead.
+#[derive(Default)]
+pub struct Precompiled {
+    keywords: OnceLock<KeywordMatcher>,
+    templates: OnceLock<HashMap<String, ParsedTemplate>>,
+    warmed_in_ms: OnceLock<u64>,
+}
+
+impl Precompiled {
+    /// Whether the matcher and the templates are both loaded.
+    pub fn is_warm(&self) -> bool {
+        self.keywords.get().is_some() && self.templates.get().is_some()
+    }
+
+    pub fn keywords(&self) -> &KeywordMatcher {
+        self.keywords
+            .get_or_init(|| KeywordMatcher::build().expect("built-in keywords compile"))
+    }
+
+    pub fn template(&self, id: &str) -> Option<&ParsedTemplate> {
+        self.templates
+            .get_or_init(|| parse_builtin_templates().expect("built-in templates parse"))
+            .get(id)
+    }
+
+    /// Builds every component, returning the first error instead of
+    /// panicking so startup can report it and exit.
+    pub fn warm(&self) -> Result<(), String> {
+        let started = Instant::now();
+        if self.keywords.get().is_none() {
+            let _ = self.keywords.set(KeywordMatcher::build()?);
+        }
+        if self.templates.get().is_none() {
+            let _ = self.templates.set(parse_builtin_templates()?);
+        }
+        let _ = self
+            .warmed_in_ms
+            .set(u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX));
+        Ok(())
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct ComponentStatus {
+    pub name: String,
+    pub loaded: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WarmResponse {
+    pub status: String,
+    pub warmed_in_ms: Option<u64>,
+    pub components: Vec<ComponentStatus>,
+}
+
+/// 200 once every component is loaded, 503 while any is still cold.
+pub async fn health_warm(State(state): State<AppState>) -> (StatusCode, Json<WarmResponse>) {
+    let pre = &state.precompiled;
+    let components = vec![
+        ComponentStatus {
+            name: "keyword_matcher".to_string(),
+            loaded: pre.keywords.get().is_some(),
+        },
+        ComponentStatus {
+            name: "templates".to_string(),
+            loaded: pre.templates.get().is_some(),
+        },
+    ];
+
+    let warm = components.iter().all(|c| c.loaded);
+    let status = if warm {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(WarmResponse {
+            status: if warm { "warm" } else { "cold" }.to_string(),
+            warmed_in_ms: pre.warmed_in_ms.get().copied(),
+            components,
+        }),
+    )
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_templates_validate() {
+        let pre = Precompiled::default();
+        pre.warm().unwrap();
+        assert_eq!(
+            pre.template("nda").unwrap().placeholders(),
+            BTreeSet::from(["effective_date", "jurisdiction", "party_a", "party_b"])
+        );
+    }
+
+    #[test]
+    fn keyword_groups_match_overlapping_terms() {
+        let pre = Precompiled::default();
+        let hits = pre
+            .keywords()
+            .hits("The Governing Law clause limits liability on termination");
+        assert_eq!(
+            hits.keys().copied().collect::<Vec<_>>(),
+            ["jurisdiction", "liability", "termination"]
+        );
+        assert_eq!(hits["jurisdiction"], (4, 17));
+    }
+
+    #[test]
+    fn localized_keywords_count_only_in_their_language() {
+        let pre = Precompiled::default();
+        let text = "Die Haftung ist beschränkt; Gerichtsstand ist München.";
+        assert!(pre.keywords().hits(text).is_empty());
+        let hits = pre.keywords().in_language("de").hits(text);
+        assert_eq!(
+            hits.keys().copied().collect::<Vec<_>>(),
+            ["jurisdiction", "liability"]
+        );
+        assert_eq!(&text[hits["liability"].0..hits["liability"].1], "Haftung");
+        assert!(pre.keywords().in_language("fr").hits(text).is_empty());
+    }
+
+    #[test]
+    fn tenant_lexicon_widens_groups_and_skips_stopwords() {
+        let text = "Supplier shall hold Customer harmless. Governed solely by Ohio law.";
+        let matcher = KeywordMatcher::with_lexicon(Lexicon {
+            synonyms: vec![vec!["indemnify".to_string(), "hold harmless".to_string()]],
+            stopwords: vec!["customer".to_string(), "solely".to_string()],
+        })
+        .unwrap();
+        let hits = matcher.hits(text);
+        let (s, e) = hits["indemnification"];
+        assert_eq!(&text[s..e], "hold Customer harmless");
+        let (s, e) = hits["jurisdiction"];
+        assert_eq!(&text[s..e], "Governed solely by");
+        assert!(!Precompiled::default()
+            .keywords()
+            .hits(text)
+            .contains_key("indemnification"));
+    }
+
+    #[test]
+    fn conditionals_loops_and_escapes_render() {
+        let t = ParsedTemplate::parse(
+            "To {{party}}.{{#if cap}} Cap: {{cap}}.{{/if}}\n\n\
+             {{#each items}}{{@index}}. {{name}} & co\n{{/each}}\\{{literal}} {{missing}}",
+        )
+        .unwrap();
+        assert_eq!(
+            t.placeholders(),
+            BTreeSet::from(["cap", "missing", "party"])
+        );
+        let variables = HashMap::from([("party".to_string(), "A<B>\n\nC".to_string())]);
+        let data = serde_json::json!({ "items": [{ "name": "X" }, { "name": "Y" }] });
+        let rendered = t
+            .render_with(&variables, data.as_object().unwrap())
+            .unwrap();
+        assert_eq!(
+            rendered.text,
+            "To A<B>\n\nC.\n\n0. X & co\n1. Y & co\n{{literal}} {{missing}}"
+        );
+        // The blank line inside the value is not a paragraph break.
+        assert_eq!(rendered.paragraphs.len(), 2);
+        assert_eq!(
+            &rendered.text[rendered.paragraphs[0].clone()],
+            "To A<B>\n\nC."
+        );
+
+        assert!(ParsedTemplate::parse("{{#if x}}open").is_err());
+        assert!(ParsedTemplate::parse("{{> nda}}")
+            .unwrap()
+            .render(&HashMap::new())
+            .is_err());
+    }
+
+    #[test]
+    fn macros_render_with_parameters_and_the_template_values() {
+        let t = ParsedTemplate::parse(
+            "{{> macro/law state=jurisdiction}}\n\n{{> macro/sign copies=2 strict=true}}",
+        )
+        .unwrap();
+        assert_eq!(t.macros(), BTreeSet::from(["law", "sign"]));
+        assert_eq!(t.placeholders(), BTreeSet::from(["jurisdiction"]));
+
+        let macros = BTreeMap::from([
+            // A macro alone on its line replaces the line break too.
+            (
+                "law".to_string(),
+                "Governed by {{state}} law.\n".to_string(),
+            ),
+            (
+                "sign".to_string(),
+                "{{copies}} counterparts{{> macro/law state=\"any\"}}".to_string(),
+            ),
+        ]);
+        let vars = HashMap::from([("jurisdiction".to_string(), "Delaware".to_string())]);
+        let fragments = Fragments {
+            macros,
+            clauses: Vec::new(),
+        };
+        let out = t
+            .render_with_fragments(&vars, &Map::new(), &fragments)
+            .unwrap();
+        assert_eq!(
+            out.text,
+            "Governed by Delaware law.\n\n2 counterpartsGoverned by any law.\n"
+        );
+        assert_eq!(out.paragraphs.len(), 2);
+        let missing = t
+            .render_with_fragments(&HashMap::new(), &Map::new(), &fragments)
+            .unwrap();
+        assert!(missing
+            .text
+            .starts_with("Governed by {{jurisdiction}} law."));
+        assert!(t.render(&vars).is_err());
+    }
+
+    #[test]
+    fn clause_references_render_their_resolved_text() {
+        let t = ParsedTemplate::parse(
+            "{{clause:liability | risk=low}}\n\n\\{{clause:liability}} {{clause:liability|risk=low}}",
+        )
+        .unwrap();
+        assert_eq!(t.clauses().len(), 1);
+        assert_eq!(t.clauses()[0].to_string(), "clause:liability|risk=low");
+        assert!(t.placeholders().is_empty());
+
+        let fragments = Fragments {
+            macros: BTreeMap::new(),
+            clauses: vec!["Capped for {{party}}.".to_string()],
+        };
+        let vars = HashMap::from([("party".to_string(), "Acme".to_string())]);
+        let out = t
+            .render_with_fragments(&vars, &Map::new(), &fragments)
+            .unwrap();
+        assert_eq!(
+            out.text,
+            "Capped for Acme.\n\n{{clause:liability}} Capped for Acme."
+        );
+        assert_eq!(
+            t.render(&vars).err().unwrap(),
+            "clause {{clause:liability|risk=low}} is not resolved"
+        );
+        let looped =
+            ParsedTemplate::parse("{{#each items}}{{clause:liability|risk=low}} {{/each}}")
+                .unwrap();
+        let data = serde_json::json!({ "items": [1, 2] });
+        let out = looped
+            .render_with_fragments(&vars, data.as_object().unwrap(), &fragments)
+            .unwrap();
+        assert_eq!(out.text, "Capped for Acme. Capped for Acme. ");
+        assert!(ParsedTemplate::parse("{{clause:Liability}}").is_err());
+    }
+
+    #[test]
+    fn malformed_templates_are_rejected() {
+        assert!(ParsedTemplate::parse("Hello {{name").is_err());
+        assert!(ParsedTemplate::parse("Hello {{ }}").is_err());
+        assert!(ParsedTemplate::parse("Hello name}}").is_err());
+        let t = ParsedTemplate::parse("A {{x}} B").unwrap();
+        assert_eq!(
+            t.segments,
+            vec![
+                Segment::Text("A ".to_string()),
+                Segment::Placeholder("x".to_string()),
+                Segment::Text(" B".to_string()),
+            ]
+        );
+    }
+}
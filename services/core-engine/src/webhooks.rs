@@ -0,0 +1,717 @@
+//! Signed HTTP callbacks to endpoints each tenant registers, so workflow
+//! systems hear about finished analysis jobs, risky analyses, escalated
+//! findings, finished batches and overdue obligations instead of polling. Every delivery carries an HMAC-SHA256 of its
+//! body under the webhook's secret and is retried with exponential backoff.
+
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::{
+    batch::Portfolio,
+    clm,
+    jobs::{JobRecord, JobStatus},
+    notifier,
+    obligations::TrackedObligation,
+    AnalyzeResponse, AppState,
+};
+
+pub const EVENT_HEADER: &str = "x-legal-event";
+pub const DELIVERY_HEADER: &str = "x-legal-delivery";
+/// `t=<unix seconds>,v1=<hex HMAC-SHA256 of "{t}.{body}">`.
+pub const SIGNATURE_HEADER: &str = "x-legal-signature";
+const SECRET_PREFIX: &str = "whsec_";
+const MIN_SECRET_LEN: usize = 16;
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub max_attempts: u32,
+    /// Doubled after each failed attempt.
+    pub retry_backoff_ms: u64,
+    /// Risk score above which `risk_threshold_exceeded` fires, for webhooks
+    /// registered without a threshold of their own.
+    pub risk_threshold: f64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            retry_backoff_ms: 1000,
+            risk_threshold: 0.7,
+        }
+    }
+}
+
+impl WebhookConfig {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        let var = |key: &str| std::env::var(key).ok();
+        Self {
+            max_attempts: var("LEGAL_WEBHOOK_MAX_ATTEMPTS")
+                .and_then(|v| v.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(d.max_attempts),
+            retry_backoff_ms: var("LEGAL_WEBHOOK_RETRY_BACKOFF_MS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(d.retry_backoff_ms),
+            risk_threshold: var("LEGAL_WEBHOOK_RISK_THRESHOLD")
+                .and_then(|v| v.parse().ok())
+                .filter(|t| (0.0..=1.0).contains(t))
+                .unwrap_or(d.risk_threshold),
+        }
+    }
+}
+
+// ── Registry ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// An analysis job finished with a result.
+    AnalysisCompleted,
+    RiskThresholdExceeded,
+    /// A low-confidence finding awaits a human verdict.
+    EscalationCreated,
+    BatchCompleted,
+    /// Obligations in the register went past their due date.
+    ObligationOverdue,
+}
+
+fn all_events() -> Vec<WebhookEvent> {
+    vec![
+        WebhookEvent::AnalysisCompleted,
+        WebhookEvent::RiskThresholdExceeded,
+        WebhookEvent::EscalationCreated,
+        WebhookEvent::BatchCompleted,
+        WebhookEvent::ObligationOverdue,
+    ]
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WebhookStats {
+    pub delivered: u64,
+    pub failed: u64,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Webhook {
+    pub id: String,
+    pub tenant: String,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub risk_threshold: Option<f64>,
+    pub created_at: DateTime<Utc>,
+    /// Only returned when the webhook is registered.
+    #[serde(skip)]
+    secret: String,
+    #[serde(flatten)]
+    pub stats: WebhookStats,
+}
+
+#[derive(Default)]
+pub struct WebhookStore {
+    pub config: WebhookConfig,
+    hooks: DashMap<String, Webhook>,
+}
+
+impl WebhookStore {
+    pub fn new(config: WebhookConfig) -> Self {
+        Self {
+            config,
+            hooks: DashMap::new(),
+        }
+    }
+
+    /// The tenant's webhooks, oldest first.
+    pub fn list(&self, tenant: &str) -> Vec<Webhook> {
+        let mut hooks: Vec<Webhook> = self
+            .hooks
+            .iter()
+            .filter(|h| h.tenant == tenant)
+            .map(|h| h.clone())
+            .collect();
+        hooks.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        hooks
+    }
+
+    fn subscribers(&self, tenant: &str, event: WebhookEvent) -> Vec<Webhook> {
+        self.list(tenant)
+            .into_iter()
+            .filter(|h| h.events.contains(&event))
+            .collect()
+    }
+
+    /// `false` when the webhook is unknown or belongs to another tenant.
+    pub fn remove(&self, tenant: &str, id: &str) -> bool {
+        self.hooks
+            .remove_if(id, |_, h| h.tenant == tenant)
+            .is_some()
+    }
+
+    pub fn remove_tenant(&self, tenant: &str) -> usize {
+        let before = self.hooks.len();
+        self.hooks.retain(|_, h| h.tenant != tenant);
+        before - self.hooks.len()
+    }
+
+    fn record(&self, id: &str, outcome: &Result<(), String>, now: DateTime<Utc>) {
+        // A webhook deleted while its delivery was retrying keeps no stats.
+        let Some(mut hook) = self.hooks.get_mut(id) else {
+            return;
+        };
+        hook.stats.last_attempt_at = Some(now);
+        match outcome {
+            Ok(()) => hook.stats.delivered += 1,
+            Err(e) => {
+                hook.stats.failed += 1;
+                hook.stats.last_error = Some(e.clone());
+            }
+        }
+    }
+}
+
+// ── Signing ───────────────────────────────────────────────────────────────────
+
+/// HMAC-SHA256 (RFC 2104) of `message` under `key`.
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+    let mut block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.map(|b| b ^ byte);
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+/// The `x-legal-signature` value for `body` sent at `timestamp`.
+pub fn signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut message = format!("{timestamp}.").into_bytes();
+    message.extend_from_slice(body);
+    let mac: String = hmac_sha256(secret.as_bytes(), &message)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    format!("t={timestamp},v1={mac}")
+}
+
+// ── Delivery ──────────────────────────────────────────────────────────────────
+
+/// Posts `body` until the endpoint accepts it, `max_attempts` runs out or
+/// the endpoint answers with an error that will not go away. Each attempt
+/// is signed afresh; the delivery ID stays the same, so receivers can
+/// discard repeats.
+async fn deliver(
+    state: &AppState,
+    hook: &Webhook,
+    event: WebhookEvent,
+    delivery_id: &str,
+    body: &[u8],
+) -> Result<(), String> {
+    let config = &state.webhooks.config;
+    let event_name = json!(event);
+    let event_name = event_name.as_str().unwrap_or_default();
+    let mut backoff = Duration::from_millis(config.retry_backoff_ms);
+    let mut error = String::new();
+    for attempt in 0..config.max_attempts {
+        if attempt > 0 {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+        let timestamp = state.clock.now().timestamp();
+        let request = state
+            .http
+            .post(&hook.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event_name)
+            .header(DELIVERY_HEADER, delivery_id)
+            .header(SIGNATURE_HEADER, signature(&hook.secret, timestamp, body))
+            .body(body.to_vec());
+        match request.send().await {
+            Ok(resp) if resp.status().is_success() => return Ok(()),
+            Ok(resp) => {
+                let status = resp.status();
+                error = format!("endpoint responded {status}");
+                if !clm::retryable(status) {
+                    break;
+                }
+            }
+            Err(e) => error = e.to_string(),
+        }
+    }
+    Err(error)
+}
+
+/// Fire-and-forget deliveries of one event to `hooks`.
+fn send(state: &AppState, tenant: &str, hooks: Vec<Webhook>, event: WebhookEvent, data: Value) {
+    for hook in hooks {
+        let delivery_id = format!("whd-{}", state.ids.next_id());
+        let body = json!({
+            "id": delivery_id,
+            "event": event,
+            "tenant": tenant,
+            "occurred_at": state.clock.now(),
+            "data": data,
+        });
+        let Ok(body) = serde_json::to_vec(&body) else {
+            continue;
+        };
+        let state = state.clone();
+        tokio::spawn(async move {
+            let outcome = deliver(&state, &hook, event, &delivery_id, &body).await;
+            state.webhooks.record(&hook.id, &outcome, state.clock.now());
+            match outcome {
+                Ok(()) => {
+                    info!(webhook_id = %hook.id, delivery_id = %delivery_id, ?event, "webhook delivered")
+                }
+                Err(e) => {
+                    warn!(webhook_id = %hook.id, delivery_id = %delivery_id, ?event, error = %e, "webhook delivery failed")
+                }
+            }
+        });
+    }
+}
+
+fn analysis_data(state: &AppState, analysis: &AnalyzeResponse) -> Value {
+    json!({
+        "analysis_id": analysis.analysis_id,
+        "risk_score": analysis.risk_score,
+        "risk_level": state.risk_model.level(analysis.risk_score),
+        "issue_count": analysis.issues.len(),
+        "escalated": analysis.escalations.len(),
+        "link": notifier::link(state, &analysis.analysis_id),
+    })
+}
+
+/// `risk_threshold_exceeded` to the tenant's webhooks whose threshold the
+/// analysis scored above.
+pub fn risk_scored(state: &AppState, tenant: &str, analysis: &AnalyzeResponse) {
+    let default = state.webhooks.config.risk_threshold;
+    let mut by_threshold: Vec<(f64, Vec<Webhook>)> = Vec::new();
+    for hook in state
+        .webhooks
+        .subscribers(tenant, WebhookEvent::RiskThresholdExceeded)
+    {
+        let threshold = hook.risk_threshold.unwrap_or(default);
+        if analysis.risk_score <= threshold {
+            continue;
+        }
+        match by_threshold.iter_mut().find(|(t, _)| *t == threshold) {
+            Some((_, hooks)) => hooks.push(hook),
+            None => by_threshold.push((threshold, vec![hook])),
+        }
+    }
+    for (threshold, hooks) in by_threshold {
+        let mut data = analysis_data(state, analysis);
+        data["threshold"] = json!(threshold);
+        send(
+            state,
+            tenant,
+            hooks,
+            WebhookEvent::RiskThresholdExceeded,
+            data,
+        );
+    }
+}
+
+/// `analysis_completed` for a job that finished with a result, then the
+/// risk check on that result. Cancelled and failed jobs send nothing.
+pub fn job_finished(state: &AppState, tenant: &str, job: &JobRecord) {
+    let (JobStatus::Completed, Some(analysis)) = (job.status, &job.result) else {
+        return;
+    };
+    let mut data = analysis_data(state, analysis);
+    data["job_id"] = json!(job.id);
+    let hooks = state
+        .webhooks
+        .subscribers(tenant, WebhookEvent::AnalysisCompleted);
+    send(state, tenant, hooks, WebhookEvent::AnalysisCompleted, data);
+    risk_scored(state, tenant, analysis);
+}
+
+/// `escalation_created` to the tenant's webhooks and, given as `(url,
+/// secret)`, the instance's reviewer endpoint. The reviewer endpoint has no
+/// registration, so it keeps no delivery stats.
+pub fn escalation_created(
+    state: &AppState,
+    tenant: &str,
+    reviewer: Option<(&str, &str)>,
+    data: Value,
+) {
+    let mut hooks = state
+        .webhooks
+        .subscribers(tenant, WebhookEvent::EscalationCreated);
+    if let Some((url, secret)) = reviewer {
+        hooks.push(Webhook {
+            id: "reviewer".to_string(),
+            tenant: tenant.to_string(),
+            url: url.to_string(),
+            events: vec![WebhookEvent::EscalationCreated],
+            risk_threshold: None,
+            created_at: state.clock.now(),
+            secret: secret.to_string(),
+            stats: WebhookStats::default(),
+        });
+    }
+    if !hooks.is_empty() {
+        send(state, tenant, hooks, WebhookEvent::EscalationCreated, data);
+    }
+}
+
+pub fn batch_completed(state: &AppState, tenant: &str, portfolio: &Portfolio) {
+    let hooks = state
+        .webhooks
+        .subscribers(tenant, WebhookEvent::BatchCompleted);
+    if !hooks.is_empty() {
+        send(
+            state,
+            tenant,
+            hooks,
+            WebhookEvent::BatchCompleted,
+            json!(portfolio),
+        );
+    }
+}
+
+/// `obligation_overdue` for the obligations of analysis `analysis_id` that
+/// went past their due date.
+pub fn obligations_overdue(
+    state: &AppState,
+    tenant: &str,
+    analysis_id: &str,
+    obligations: &[&TrackedObligation],
+) {
+    let hooks = state
+        .webhooks
+        .subscribers(tenant, WebhookEvent::ObligationOverdue);
+    if !hooks.is_empty() {
+        let data = json!({
+            "analysis_id": analysis_id,
+            "obligations": obligations,
+            "link": notifier::link(state, analysis_id),
+        });
+        send(state, tenant, hooks, WebhookEvent::ObligationOverdue, data);
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterRequest {
+    pub url: String,
+    #[serde(default = "all_events")]
+    pub events: Vec<WebhookEvent>,
+    /// Overrides `LEGAL_WEBHOOK_RISK_THRESHOLD` for this webhook.
+    #[serde(default)]
+    pub risk_threshold: Option<f64>,
+    /// Generated when absent.
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Registered {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    /// Shown once; signatures are computed with it.
+    pub secret: String,
+}
+
+fn invalid(message: &str) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(json!({ "error": message })),
+    )
+}
+
+pub async fn register(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<RegisterRequest>,
+) -> Result<(StatusCode, Json<Registered>), (StatusCode, Json<Value>)> {
+    if !req.url.starts_with("http://") && !req.url.starts_with("https://") {
+        return Err(invalid("url must be an http(s) URL"));
+    }
+    if req.events.is_empty() {
+        return Err(invalid("events must not be empty"));
+    }
+    if req
+        .risk_threshold
+        .is_some_and(|t| !(0.0..=1.0).contains(&t))
+    {
+        return Err(invalid("risk_threshold must be between 0 and 1"));
+    }
+    let secret = match req.secret {
+        Some(secret) if secret.len() < MIN_SECRET_LEN => {
+            return Err(invalid("secret must be at least 16 characters"));
+        }
+        Some(secret) => secret,
+        None => format!(
+            "{SECRET_PREFIX}{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        ),
+    };
+    let mut events = req.events;
+    events.sort();
+    events.dedup();
+    let webhook = Webhook {
+        id: format!("wh-{}", state.ids.next_id()),
+        tenant: notifier::tenant(&headers),
+        url: req.url,
+        events,
+        risk_threshold: req.risk_threshold,
+        created_at: state.clock.now(),
+        secret: secret.clone(),
+        stats: WebhookStats::default(),
+    };
+    state
+        .webhooks
+        .hooks
+        .insert(webhook.id.clone(), webhook.clone());
+    info!(webhook_id = %webhook.id, tenant = %webhook.tenant, "webhook registered");
+    state.record_audit(
+        "webhooks.registered",
+        &webhook.id,
+        Some(&webhook.tenant),
+        json!({ "url": webhook.url, "events": webhook.events }),
+    );
+    Ok((StatusCode::CREATED, Json(Registered { webhook, secret })))
+}
+
+pub async fn list(State(state): State<AppState>, headers: HeaderMap) -> Json<Vec<Webhook>> {
+    Json(state.webhooks.list(&notifier::tenant(&headers)))
+}
+
+pub async fn delete(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> StatusCode {
+    let tenant = notifier::tenant(&headers);
+    if !state.webhooks.remove(&tenant, &id) {
+        return StatusCode::NOT_FOUND;
+    }
+    info!(webhook_id = %id, tenant = %tenant, "webhook deleted");
+    state.record_audit("webhooks.deleted", &id, Some(&tenant), json!({}));
+    StatusCode::NO_CONTENT
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, routing::post, Router};
+    use std::sync::{Arc, Mutex};
+
+    type Received = Arc<Mutex<Vec<(HeaderMap, Bytes)>>>;
+
+    /// An endpoint answering with `statuses` in turn, then `200`.
+    async fn receiver(statuses: Vec<u16>) -> (String, Received) {
+        let received = Received::default();
+        let seen = received.clone();
+        let app = Router::new().route(
+            "/hooks",
+            post(move |headers: HeaderMap, body: Bytes| {
+                let seen = seen.clone();
+                let statuses = statuses.clone();
+                async move {
+                    let mut seen = seen.lock().unwrap();
+                    let status = statuses.get(seen.len()).copied().unwrap_or(200);
+                    seen.push((headers, body));
+                    StatusCode::from_u16(status).unwrap()
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}/hooks"), received)
+    }
+
+    fn state() -> AppState {
+        AppState {
+            webhooks: Arc::new(WebhookStore::new(WebhookConfig {
+                max_attempts: 3,
+                retry_backoff_ms: 0,
+                ..WebhookConfig::default()
+            })),
+            ..AppState::in_memory()
+        }
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn hmac_matches_the_rfc_4231_vectors() {
+        assert_eq!(
+            hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // Keys longer than a block are hashed first.
+        assert_eq!(
+            hex(&hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn signatures_cover_the_timestamp_and_the_body() {
+        let sig = signature("whsec_test", 1_700_000_000, b"{}");
+        assert!(sig.starts_with("t=1700000000,v1="));
+        assert_eq!(sig.len(), "t=1700000000,v1=".len() + 64);
+        assert_ne!(sig, signature("whsec_test", 1_700_000_001, b"{}"));
+        assert_ne!(sig, signature("whsec_test", 1_700_000_000, b"[]"));
+        assert_ne!(sig, signature("whsec_other", 1_700_000_000, b"{}"));
+    }
+
+    fn hook(id: &str, tenant: &str, events: Vec<WebhookEvent>) -> Webhook {
+        Webhook {
+            id: id.to_string(),
+            tenant: tenant.to_string(),
+            url: "https://hooks.example.com".to_string(),
+            events,
+            risk_threshold: None,
+            created_at: Utc::now(),
+            secret: "whsec_test".to_string(),
+            stats: WebhookStats::default(),
+        }
+    }
+
+    #[test]
+    fn subscribers_are_the_tenants_webhooks_for_the_event() {
+        let store = WebhookStore::default();
+        for h in [
+            hook("wh-1", "acme", all_events()),
+            hook("wh-2", "acme", vec![WebhookEvent::BatchCompleted]),
+            hook("wh-3", "globex", all_events()),
+        ] {
+            store.hooks.insert(h.id.clone(), h);
+        }
+        let ids = |hooks: Vec<Webhook>| hooks.into_iter().map(|h| h.id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(store.subscribers("acme", WebhookEvent::AnalysisCompleted)),
+            ["wh-1"]
+        );
+        assert_eq!(
+            ids(store.subscribers("acme", WebhookEvent::BatchCompleted)).len(),
+            2
+        );
+        assert!(!store.remove("acme", "wh-3"));
+        store.record(
+            "wh-1",
+            &Err("endpoint responded 500".to_string()),
+            Utc::now(),
+        );
+        assert_eq!(store.list("acme")[0].stats.failed, 1);
+        assert_eq!(store.remove_tenant("acme"), 2);
+        assert_eq!(store.list("globex").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn deliveries_are_signed_and_retried_on_server_errors() {
+        let state = state();
+        let (url, received) = receiver(vec![503]).await;
+        let mut webhook = hook("wh-1", "acme", all_events());
+        webhook.url = url;
+        let body = br#"{"event":"batch_completed"}"#;
+        let outcome = deliver(
+            &state,
+            &webhook,
+            WebhookEvent::BatchCompleted,
+            "whd-1",
+            body,
+        )
+        .await;
+        assert_eq!(outcome, Ok(()));
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        for (headers, sent) in received.iter() {
+            assert_eq!(sent.as_ref(), body);
+            assert_eq!(headers[EVENT_HEADER], "batch_completed");
+            assert_eq!(headers[DELIVERY_HEADER], "whd-1");
+            let sig = headers[SIGNATURE_HEADER].to_str().unwrap();
+            let timestamp: i64 = sig[2..sig.find(',').unwrap()].parse().unwrap();
+            assert_eq!(sig, signature("whsec_test", timestamp, body));
+        }
+    }
+
+    #[tokio::test]
+    async fn escalations_reach_the_reviewer_endpoint_signed() {
+        let state = state();
+        let (url, received) = receiver(vec![500]).await;
+        escalation_created(
+            &state,
+            "acme",
+            Some((&url, "whsec_reviewer_secret")),
+            json!({ "analysis_id": "an-1" }),
+        );
+        for _ in 0..100 {
+            if received.lock().unwrap().len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        let (headers, body) = &received[1];
+        assert_eq!(headers[EVENT_HEADER], "escalation_created");
+        let sig = headers[SIGNATURE_HEADER].to_str().unwrap();
+        let timestamp: i64 = sig[2..sig.find(',').unwrap()].parse().unwrap();
+        assert_eq!(sig, signature("whsec_reviewer_secret", timestamp, body));
+        let sent: Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(sent["tenant"], "acme");
+        assert_eq!(sent["data"]["analysis_id"], "an-1");
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let state = state();
+        let (url, received) = receiver(vec![410, 410, 410]).await;
+        let mut webhook = hook("wh-1", "acme", all_events());
+        webhook.url = url;
+        let outcome = deliver(
+            &state,
+            &webhook,
+            WebhookEvent::BatchCompleted,
+            "whd-1",
+            b"{}",
+        )
+        .await;
+        assert_eq!(outcome, Err("endpoint responded 410 Gone".to_string()));
+        assert_eq!(received.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,458 @@
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::NaiveDate;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    get_required_variables, get_template_body,
+    isolation::{self, Site},
+    jurisdiction_policy, notifier, AppState, CompileExtras,
+};
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestionKind {
+    Text,
+    Boolean,
+    Choice,
+    Date,
+    Number,
+}
+
+/// Shows a question only when an earlier question was answered with `equals`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShowIf {
+    pub question: String,
+    pub equals: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Question {
+    pub id: String,
+    pub prompt: String,
+    pub kind: QuestionKind,
+    /// Template variable the answer fills, if any.
+    pub variable: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+    pub show_if: Option<ShowIf>,
+    pub required: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WizardDefinition {
+    pub template_id: String,
+    pub questions: Vec<Question>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSessionRequest {
+    pub template_id: String,
+    #[serde(default)]
+    pub answers: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnswerRequest {
+    pub answers: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WizardSession {
+    pub id: String,
+    pub template_id: String,
+    pub answers: BTreeMap<String, String>,
+    /// Visible, still-unanswered questions in flow order.
+    pub next_questions: Vec<Question>,
+    pub complete: bool,
+    pub variables: BTreeMap<String, String>,
+}
+
+// ── Definitions ───────────────────────────────────────────────────────────────
+
+fn question(id: &str, prompt: &str, kind: QuestionKind) -> Question {
+    Question {
+        id: id.to_string(),
+        prompt: prompt.to_string(),
+        kind,
+        variable: None,
+        options: Vec::new(),
+        show_if: None,
+        required: true,
+    }
+}
+
+fn variable(id: &str, prompt: &str, kind: QuestionKind) -> Question {
+    Question {
+        variable: Some(id.to_string()),
+        ..question(id, prompt, kind)
+    }
+}
+
+fn shown_if(mut q: Question, question: &str, equals: &str) -> Question {
+    q.show_if = Some(ShowIf {
+        question: question.to_string(),
+        equals: equals.to_string(),
+    });
+    q
+}
+
+/// Hand-written intake flows; any other template gets one text question per
+/// required variable.
+pub fn definition(template_id: &str) -> Option<WizardDefinition> {
+    get_template_body(template_id)?;
+    let questions = match template_id {
+        "nda" => vec![
+            question(
+                "mutual",
+                "Will both parties share confidential information (mutual NDA)?",
+                QuestionKind::Boolean,
+            ),
+            Question {
+                options: vec!["party_a".to_string(), "party_b".to_string()],
+                ..shown_if(
+                    question(
+                        "disclosing_party",
+                        "Which party discloses confidential information?",
+                        QuestionKind::Choice,
+                    ),
+                    "mutual",
+                    "no",
+                )
+            },
+            variable(
+                "party_a",
+                "Legal name of the first party",
+                QuestionKind::Text,
+            ),
+            variable(
+                "party_b",
+                "Legal name of the second party",
+                QuestionKind::Text,
+            ),
+            variable("effective_date", "Effective date", QuestionKind::Date),
+            variable("jurisdiction", "Governing jurisdiction", QuestionKind::Text),
+        ],
+        "employment" => vec![
+            variable("employer", "Employer legal name", QuestionKind::Text),
+            variable("employee", "Employee full name", QuestionKind::Text),
+            variable("position", "Job title", QuestionKind::Text),
+            variable("start_date", "Start date", QuestionKind::Date),
+            variable("salary", "Annual salary", QuestionKind::Number),
+            question(
+                "non_compete",
+                "Include a post-employment non-compete?",
+                QuestionKind::Boolean,
+            ),
+            shown_if(
+                question(
+                    "non_compete_months",
+                    "Non-compete duration in months",
+                    QuestionKind::Number,
+                ),
+                "non_compete",
+                "yes",
+            ),
+        ],
+        _ => get_required_variables(template_id)
+            .iter()
+            .map(|v| variable(v, &v.replace('_', " "), QuestionKind::Text))
+            .collect(),
+    };
+    Some(WizardDefinition {
+        template_id: template_id.to_string(),
+        questions,
+    })
+}
+
+// ── Flow evaluation ───────────────────────────────────────────────────────────
+
+fn normalize(q: &Question, raw: &str) -> Option<String> {
+    let value = raw.trim();
+    if value.is_empty() {
+        return None;
+    }
+    match q.kind {
+        QuestionKind::Text => Some(value.to_string()),
+        QuestionKind::Boolean => match value.to_lowercase().as_str() {
+            "yes" | "true" | "y" => Some("yes".to_string()),
+            "no" | "false" | "n" => Some("no".to_string()),
+            _ => None,
+        },
+        QuestionKind::Choice => q.options.iter().find(|o| *o == value).cloned(),
+        QuestionKind::Date => value.parse::<NaiveDate>().ok().map(|d| d.to_string()),
+        QuestionKind::Number => value
+            .replace(',', "")
+            .parse::<f64>()
+            .ok()
+            .map(|_| value.to_string()),
+    }
+}
+
+fn is_visible(q: &Question, answers: &BTreeMap<String, String>) -> bool {
+    q.show_if
+        .as_ref()
+        .is_none_or(|c| answers.get(&c.question) == Some(&c.equals))
+}
+
+/// Validates and merges `incoming` into `answers`; unknown question IDs and
+/// values that don't fit the question type are rejected.
+pub fn apply_answers(
+    def: &WizardDefinition,
+    answers: &mut BTreeMap<String, String>,
+    incoming: &HashMap<String, String>,
+) -> Result<(), StatusCode> {
+    for (id, raw) in incoming {
+        let q = def
+            .questions
+            .iter()
+            .find(|q| q.id == *id)
+            .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+        let value = normalize(q, raw).ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+        answers.insert(id.clone(), value);
+    }
+    Ok(())
+}
+
+pub fn evaluate(
+    def: &WizardDefinition,
+    id: &str,
+    answers: BTreeMap<String, String>,
+) -> WizardSession {
+    // Walk in order so branching on earlier answers is resolved before later questions.
+    let mut visible_answers = BTreeMap::new();
+    let mut next_questions = Vec::new();
+    let mut variables = BTreeMap::new();
+    for q in &def.questions {
+        if !is_visible(q, &visible_answers) {
+            continue;
+        }
+        match answers.get(&q.id) {
+            Some(value) => {
+                visible_answers.insert(q.id.clone(), value.clone());
+                if let Some(var) = &q.variable {
+                    variables.insert(var.clone(), value.clone());
+                }
+            }
+            None if q.required => next_questions.push(q.clone()),
+            None => {}
+        }
+    }
+    WizardSession {
+        id: id.to_string(),
+        template_id: def.template_id.clone(),
+        complete: next_questions.is_empty(),
+        answers: visible_answers,
+        next_questions,
+        variables,
+    }
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub struct WizardStore {
+    sessions: DashMap<String, WizardSession>,
+    /// Tenant of each session.
+    owners: DashMap<String, String>,
+}
+
+impl WizardStore {
+    fn owned_by(&self, id: &str, tenant: &str) -> bool {
+        self.owners.get(id).is_some_and(|t| *t == tenant)
+    }
+
+    /// Runs `f` over every session along with its tenant.
+    pub fn for_each_mut(&self, mut f: impl FnMut(&str, &mut WizardSession)) {
+        for mut s in self.sessions.iter_mut() {
+            let tenant = self
+                .owners
+                .get(s.key())
+                .map(|t| t.clone())
+                .unwrap_or_default();
+            f(&tenant, s.value_mut());
+        }
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+pub async fn get_wizard(Path(id): Path<String>) -> Result<Json<WizardDefinition>, StatusCode> {
+    definition(&id).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+pub async fn create_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateSessionRequest>,
+) -> Result<Json<WizardSession>, StatusCode> {
+    let def = definition(&req.template_id).ok_or(StatusCode::NOT_FOUND)?;
+    let mut answers = BTreeMap::new();
+    apply_answers(&def, &mut answers, &req.answers)?;
+
+    let session = evaluate(&def, &format!("wiz-{}", state.ids.next_id()), answers);
+    state
+        .wizards
+        .owners
+        .insert(session.id.clone(), notifier::tenant(&headers));
+    state
+        .wizards
+        .sessions
+        .insert(session.id.clone(), session.clone());
+    info!(session_id = %session.id, template_id = %session.template_id, "wizard session started");
+    Ok(Json(session))
+}
+
+pub async fn get_session(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<WizardSession>, StatusCode> {
+    if !state.wizards.owned_by(&id, &notifier::tenant(&headers)) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    state
+        .wizards
+        .sessions
+        .get(&id)
+        .map(|s| Json(s.value().clone()))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+pub async fn answer(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<AnswerRequest>,
+) -> Result<Json<WizardSession>, StatusCode> {
+    if !state.wizards.owned_by(&id, &notifier::tenant(&headers)) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let mut session = state
+        .wizards
+        .sessions
+        .get_mut(&id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let def = definition(&session.template_id).ok_or(StatusCode::NOT_FOUND)?;
+    let mut answers = session.answers.clone();
+    apply_answers(&def, &mut answers, &req.answers)?;
+    *session = evaluate(&def, &id, answers);
+    Ok(Json(session.clone()))
+}
+
+pub async fn compile_session(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    if !state.wizards.owned_by(&id, &notifier::tenant(&headers)) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let session = state
+        .wizards
+        .sessions
+        .get(&id)
+        .map(|s| s.value().clone())
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if !session.complete {
+        return Err(StatusCode::CONFLICT);
+    }
+    let variables: HashMap<String, String> = session.variables.into_iter().collect();
+    isolation::contain(&state, Site::Compile, || {
+        jurisdiction_policy::compile_checked(
+            &state,
+            &notifier::tenant(&headers),
+            &session.template_id,
+            None,
+            &variables,
+            &serde_json::Map::new(),
+            CompileExtras::default(),
+        )
+    })
+    .unwrap_or_else(|panicked| Ok(panicked.into_response()))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answers(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn mutual_nda_hides_one_way_question() {
+        let def = definition("nda").unwrap();
+        let mut a = BTreeMap::new();
+        apply_answers(&def, &mut a, &answers(&[("mutual", "Yes")])).unwrap();
+        let session = evaluate(&def, "s", a);
+        assert!(session
+            .next_questions
+            .iter()
+            .all(|q| q.id != "disclosing_party"));
+        assert_eq!(session.next_questions[0].id, "party_a");
+    }
+
+    #[test]
+    fn one_way_nda_asks_disclosing_party() {
+        let def = definition("nda").unwrap();
+        let mut a = BTreeMap::new();
+        apply_answers(&def, &mut a, &answers(&[("mutual", "no")])).unwrap();
+        let session = evaluate(&def, "s", a);
+        assert_eq!(session.next_questions[0].id, "disclosing_party");
+    }
+
+    #[test]
+    fn answers_to_hidden_questions_are_dropped() {
+        let def = definition("employment").unwrap();
+        let mut a = BTreeMap::new();
+        apply_answers(
+            &def,
+            &mut a,
+            &answers(&[("non_compete", "no"), ("non_compete_months", "12")]),
+        )
+        .unwrap();
+        let session = evaluate(&def, "s", a);
+        assert!(!session.answers.contains_key("non_compete_months"));
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let def = definition("nda").unwrap();
+        let mut a = BTreeMap::new();
+        assert_eq!(
+            apply_answers(
+                &def,
+                &mut a,
+                &answers(&[("effective_date", "next tuesday")])
+            ),
+            Err(StatusCode::UNPROCESSABLE_ENTITY)
+        );
+        assert_eq!(
+            apply_answers(&def, &mut a, &answers(&[("unknown", "x")])),
+            Err(StatusCode::UNPROCESSABLE_ENTITY)
+        );
+    }
+
+    #[test]
+    fn generic_wizard_from_required_variables() {
+        let def = definition("sla").unwrap();
+        assert_eq!(def.questions.len(), 4);
+        assert!(def.questions.iter().all(|q| q.variable.is_some()));
+        assert!(definition("missing").is_none());
+    }
+}
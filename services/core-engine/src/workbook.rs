@@ -0,0 +1,268 @@
+//! Minimal tabular export: the same sheets rendered either as an XLSX
+//! workbook or as a zip of CSV files.
+
+use std::io::{Cursor, Write};
+
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    Empty,
+}
+
+impl Cell {
+    fn as_csv(&self) -> String {
+        match self {
+            Cell::Text(s) => s.clone(),
+            Cell::Number(n) => n.to_string(),
+            Cell::Bool(b) => b.to_string(),
+            Cell::Empty => String::new(),
+        }
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(s: &str) -> Self {
+        Cell::Text(s.to_string())
+    }
+}
+
+impl From<String> for Cell {
+    fn from(s: String) -> Self {
+        Cell::Text(s)
+    }
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Cell::Empty, Into::into)
+    }
+}
+
+impl From<bool> for Cell {
+    fn from(b: bool) -> Self {
+        Cell::Bool(b)
+    }
+}
+
+impl From<f64> for Cell {
+    fn from(n: f64) -> Self {
+        Cell::Number(n)
+    }
+}
+
+impl From<usize> for Cell {
+    #[allow(clippy::cast_precision_loss)]
+    fn from(n: usize) -> Self {
+        Cell::Number(n as f64)
+    }
+}
+
+impl From<u32> for Cell {
+    fn from(n: u32) -> Self {
+        Cell::Number(f64::from(n))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sheet {
+    pub name: String,
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+// ── CSV bundle ────────────────────────────────────────────────────────────────
+
+/// One `<sheet name>.csv` per sheet inside a zip archive.
+pub fn to_csv_bundle(sheets: &[Sheet]) -> std::io::Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for sheet in sheets {
+        zip.start_file(format!("{}.csv", sheet.name), SimpleFileOptions::default())?;
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(&sheet.headers)?;
+        for row in &sheet.rows {
+            writer.write_record(row.iter().map(Cell::as_csv))?;
+        }
+        let bytes = writer
+            .into_inner()
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        zip.write_all(&bytes)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+// ── XLSX ──────────────────────────────────────────────────────────────────────
+
+pub(crate) fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            // Control characters other than tab/newline are invalid in XML 1.0.
+            c if c.is_control() && c != '\t' && c != '\n' && c != '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Zero-based column index to spreadsheet letters: 0 → A, 26 → AA.
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).expect("ASCII column name")
+}
+
+fn cell_xml(reference: &str, cell: &Cell) -> String {
+    match cell {
+        Cell::Text(s) => format!(
+            r#"<c r="{reference}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+            xml_escape(s)
+        ),
+        Cell::Number(n) if n.is_finite() => format!(r#"<c r="{reference}"><v>{n}</v></c>"#),
+        Cell::Number(_) | Cell::Empty => String::new(),
+        Cell::Bool(b) => format!(r#"<c r="{reference}" t="b"><v>{}</v></c>"#, u8::from(*b)),
+    }
+}
+
+fn sheet_xml(sheet: &Sheet) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+    );
+    let header: Vec<Cell> = sheet
+        .headers
+        .iter()
+        .map(|h| Cell::Text(h.clone()))
+        .collect();
+    for (r, row) in std::iter::once(&header).chain(&sheet.rows).enumerate() {
+        xml.push_str(&format!(r#"<row r="{}">"#, r + 1));
+        for (c, cell) in row.iter().enumerate() {
+            xml.push_str(&cell_xml(&format!("{}{}", column_name(c), r + 1), cell));
+        }
+        xml.push_str("</row>");
+    }
+    xml.push_str("</sheetData></worksheet>");
+    xml
+}
+
+/// Sheet names are limited to 31 characters and may not contain `[]:*?/\`.
+fn sheet_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !"[]:*?/\\".contains(*c))
+        .take(31)
+        .collect()
+}
+
+pub fn to_xlsx(sheets: &[Sheet]) -> std::io::Result<Vec<u8>> {
+    let mut content_types = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#,
+    );
+    let mut workbook = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>"#,
+    );
+    let mut workbook_rels = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    );
+    for (i, sheet) in sheets.iter().enumerate() {
+        let n = i + 1;
+        content_types.push_str(&format!(
+            r#"<Override PartName="/xl/worksheets/sheet{n}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#
+        ));
+        workbook.push_str(&format!(
+            r#"<sheet name="{}" sheetId="{n}" r:id="rId{n}"/>"#,
+            xml_escape(&sheet_name(&sheet.name))
+        ));
+        workbook_rels.push_str(&format!(
+            r#"<Relationship Id="rId{n}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{n}.xml"/>"#
+        ));
+    }
+    content_types.push_str("</Types>");
+    workbook.push_str("</sheets></workbook>");
+    workbook_rels.push_str("</Relationships>");
+    let root_rels = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let opts = SimpleFileOptions::default();
+    let mut parts = vec![
+        ("[Content_Types].xml".to_string(), content_types),
+        ("_rels/.rels".to_string(), root_rels.to_string()),
+        ("xl/workbook.xml".to_string(), workbook),
+        ("xl/_rels/workbook.xml.rels".to_string(), workbook_rels),
+    ];
+    for (i, sheet) in sheets.iter().enumerate() {
+        parts.push((
+            format!("xl/worksheets/sheet{}.xml", i + 1),
+            sheet_xml(sheet),
+        ));
+    }
+    for (path, body) in parts {
+        zip.start_file(path, opts)?;
+        zip.write_all(body.as_bytes())?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn sheet() -> Sheet {
+        Sheet {
+            name: "contracts".to_string(),
+            headers: vec!["id".to_string(), "value".to_string(), "flag".to_string()],
+            rows: vec![vec!["a & b".into(), 1.5.into(), true.into()]],
+        }
+    }
+
+    fn read_entry(bytes: Vec<u8>, name: &str) -> String {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut out = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut out)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn column_names() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(701), "ZZ");
+        assert_eq!(column_name(702), "AAA");
+    }
+
+    #[test]
+    fn csv_bundle_has_one_file_per_sheet() {
+        let csv = read_entry(to_csv_bundle(&[sheet()]).unwrap(), "contracts.csv");
+        assert_eq!(csv, "id,value,flag\na & b,1.5,true\n");
+    }
+
+    #[test]
+    fn xlsx_sheet_escapes_text_and_types_cells() {
+        let xml = read_entry(to_xlsx(&[sheet()]).unwrap(), "xl/worksheets/sheet1.xml");
+        assert!(xml.contains(
+            r#"<c r="A2" t="inlineStr"><is><t xml:space="preserve">a &amp; b</t></is></c>"#
+        ));
+        assert!(xml.contains(r#"<c r="B2"><v>1.5</v></c>"#));
+        assert!(xml.contains(r#"<c r="C2" t="b"><v>1</v></c>"#));
+    }
+}